@@ -1,13 +1,42 @@
-use serde::Serialize;
+use rubato::{FftFixedOut, Resampler};
+use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use tauri::ipc::Channel;
+use tauri::Manager;
 
 #[derive(Clone, Serialize)]
 pub struct AudioChunk {
     pub samples: Vec<f32>,
     pub sample_rate: u32,
+    /// Set on the last message when capture ended on its own (for example
+    /// the device was unplugged); `samples` is empty in that case.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// An output device whose audio can be captured. `id` is the platform
+/// identifier passed back to `start_system_audio_capture`.
+#[derive(Clone, Serialize)]
+pub struct AudioOutputDevice {
+    pub id: String,
+    pub name: String,
+}
+
+const CAPTURE_SETTINGS_FILE: &str = "audio-capture.json";
+
+/// Rate of every chunk sent to the frontend unless the caller asks for
+/// another. The WebAudio bridge and the LiveKit/WebRTC encoder both run at
+/// 48 kHz.
+const CAPTURE_OUTPUT_RATE: u32 = 48_000;
+
+/// Output rates a caller may request.
+const CAPTURE_OUTPUT_RATES: std::ops::RangeInclusive<u32> = 8_000..=192_000;
+
+#[derive(Default, Serialize, Deserialize)]
+struct CaptureSettings {
+    device_id: Option<String>,
 }
 
 struct CaptureHandle {
@@ -23,11 +52,80 @@ pub fn set_system_audio_capture_enabled(enabled: bool) {
     SYSTEM_AUDIO_CAPTURE_ENABLED.store(enabled, Ordering::SeqCst);
 }
 
+fn capture_settings_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("failed to resolve app data dir: {e}"))?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("failed to create app data dir: {e}"))?;
+    Ok(dir.join(CAPTURE_SETTINGS_FILE))
+}
+
+fn load_capture_device(app: &tauri::AppHandle) -> Option<String> {
+    let raw = std::fs::read(capture_settings_path(app).ok()?).ok()?;
+    serde_json::from_slice::<CaptureSettings>(&raw)
+        .ok()?
+        .device_id
+}
+
+fn save_capture_device(app: &tauri::AppHandle, device_id: Option<&str>) -> Result<(), String> {
+    let settings = CaptureSettings {
+        device_id: device_id.map(str::to_string),
+    };
+    let raw = serde_json::to_vec(&settings).map_err(|e| e.to_string())?;
+    std::fs::write(capture_settings_path(app)?, raw)
+        .map_err(|e| format!("failed to save capture device: {e}"))
+}
+
 #[tauri::command]
-pub fn start_system_audio_capture(on_audio: Channel<AudioChunk>) -> Result<(), String> {
+pub fn list_audio_output_devices() -> Result<Vec<AudioOutputDevice>, String> {
+    // Run on a fresh thread: the Windows backend needs an MTA COM apartment,
+    // which the command thread may already have initialised differently.
+    thread::spawn(list_output_devices)
+        .join()
+        .map_err(|_| "Audio device enumeration panicked".to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// The remembered capture device, if one was explicitly selected.
+#[tauri::command]
+pub fn get_audio_capture_device(app: tauri::AppHandle) -> Option<String> {
+    load_capture_device(&app)
+}
+
+/// Start capturing system audio as mono chunks at `sample_rate` (48 kHz when
+/// omitted). `device_id` selects an entry from `list_audio_output_devices`
+/// (empty string = system default) and is remembered; when omitted, the last
+/// selection is reused.
+#[tauri::command]
+pub fn start_system_audio_capture(
+    app: tauri::AppHandle,
+    on_audio: Channel<AudioChunk>,
+    device_id: Option<String>,
+    sample_rate: Option<u32>,
+) -> Result<(), String> {
     if !SYSTEM_AUDIO_CAPTURE_ENABLED.load(Ordering::SeqCst) {
         return Err("System audio capture disabled".into());
     }
+    let sample_rate = sample_rate.unwrap_or(CAPTURE_OUTPUT_RATE);
+    if !CAPTURE_OUTPUT_RATES.contains(&sample_rate) {
+        return Err(format!(
+            "Unsupported capture sample rate {sample_rate}Hz; expected {}-{}Hz",
+            CAPTURE_OUTPUT_RATES.start(),
+            CAPTURE_OUTPUT_RATES.end()
+        ));
+    }
+
+    let device_id = match device_id {
+        Some(id) => {
+            let id = Some(id.trim().to_string()).filter(|id| !id.is_empty());
+            if let Err(e) = save_capture_device(&app, id.as_deref()) {
+                eprintln!("[audio_capture] {e}");
+            }
+            id
+        }
+        None => load_capture_device(&app),
+    };
 
     let mut guard = CAPTURE.lock().map_err(|e| e.to_string())?;
     if guard.is_some() {
@@ -38,8 +136,15 @@ pub fn start_system_audio_capture(on_audio: Channel<AudioChunk>) -> Result<(), S
     let stop = stop_flag.clone();
 
     let thread = thread::spawn(move || {
-        if let Err(e) = capture_loop(&on_audio, &stop) {
+        let mut output = CaptureOutput::new(
+            move |chunk| {
+                let _ = on_audio.send(chunk);
+            },
+            sample_rate,
+        );
+        if let Err(e) = capture_loop(&mut output, &stop, device_id.as_deref()) {
             eprintln!("[audio_capture] Capture loop error: {e}");
+            output.send_error(e.to_string());
         }
     });
 
@@ -102,11 +207,12 @@ mod win_process_loopback {
                 let mut activated: Option<windows_core::IUnknown> = None;
                 op.GetActivateResult(&mut hr, &mut activated)?;
 
-                let mut guard = self.result.lock().unwrap();
-                if hr.is_ok() {
-                    *guard = Some(Ok(activated.unwrap()));
-                } else {
-                    *guard = Some(Err(windows_core::Error::from(hr)));
+                if let Ok(mut guard) = self.result.lock() {
+                    *guard = Some(if hr.is_ok() {
+                        activated.ok_or_else(|| windows_core::Error::from(E_POINTER))
+                    } else {
+                        Err(windows_core::Error::from(hr))
+                    });
                 }
                 let _ = SetEvent(self.event);
             }
@@ -180,7 +286,9 @@ mod win_process_loopback {
             // Now safe to drop (VT_EMPTY won't free anything).
             std::mem::ManuallyDrop::drop(&mut prop);
 
-            let guard = result_holder.lock().unwrap();
+            let guard = result_holder
+                .lock()
+                .map_err(|_| windows_core::Error::from(E_FAIL))?;
             match guard.as_ref() {
                 Some(Ok(unknown)) => unknown.cast::<IAudioClient>(),
                 Some(Err(e)) => Err(e.clone()),
@@ -195,8 +303,9 @@ mod win_process_loopback {
 
 #[cfg(target_os = "windows")]
 fn capture_loop(
-    channel: &Channel<AudioChunk>,
+    output: &mut CaptureOutput,
     stop_flag: &Arc<AtomicBool>,
+    device_id: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Initialize COM on this thread — required for all WASAPI / IAudioClient calls.
     // Both the Process Loopback path and the legacy WASAPI path need COM.
@@ -209,6 +318,16 @@ fn capture_loop(
         .map_err(|e| format!("COM initialization failed: {e}"))?;
     }
 
+    // Process loopback mixes every endpoint, so an explicitly selected device
+    // always goes through the per-device WASAPI loopback instead.
+    if device_id.is_some() {
+        let result = capture_loop_legacy(output, stop_flag, device_id);
+        unsafe {
+            windows::Win32::System::Com::CoUninitialize();
+        }
+        return result;
+    }
+
     // Try the Process Loopback Exclusion API first (Windows 10 2004+).
     // This captures all system audio EXCEPT our own process, eliminating echo.
     let my_pid = std::process::id();
@@ -220,14 +339,14 @@ fn capture_loop(
     let result = match win_process_loopback::activate_process_loopback_exclude(my_pid) {
         Ok(client) => {
             eprintln!("[audio_capture] Process Loopback Exclusion API activated successfully");
-            match capture_loop_with_client(channel, stop_flag, &client) {
+            match capture_loop_with_client(output, stop_flag, &client) {
                 Ok(()) => Ok(()),
                 Err(e) => {
                     eprintln!(
                         "[audio_capture] Process Loopback capture failed ({e}), \
                          falling back to legacy WASAPI loopback"
                     );
-                    capture_loop_legacy(channel, stop_flag)
+                    capture_loop_legacy(output, stop_flag, None)
                 }
             }
         }
@@ -236,7 +355,7 @@ fn capture_loop(
                 "[audio_capture] Process Loopback Exclusion API unavailable ({e}), \
                  falling back to legacy WASAPI loopback"
             );
-            capture_loop_legacy(channel, stop_flag)
+            capture_loop_legacy(output, stop_flag, None)
         }
     };
 
@@ -251,7 +370,7 @@ fn capture_loop(
 /// the Process Loopback Exclusion API.
 #[cfg(target_os = "windows")]
 fn capture_loop_with_client(
-    channel: &Channel<AudioChunk>,
+    output: &mut CaptureOutput,
     stop_flag: &Arc<AtomicBool>,
     client: &windows::Win32::Media::Audio::IAudioClient,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -304,10 +423,19 @@ fn capture_loop_with_client(
         // Start the audio stream
         client.Start()?;
 
-        while !stop_flag.load(Ordering::Relaxed) {
+        // Set when the stream dies under us (e.g. AUDCLNT_E_DEVICE_INVALIDATED).
+        let mut failure: Option<String> = None;
+
+        'capture: while !stop_flag.load(Ordering::Relaxed) {
             // Wait for buffer event with 100ms timeout
             let wait_result = WaitForSingleObject(event, 100);
             if wait_result == WAIT_TIMEOUT {
+                // An invalidated stream stops signalling instead of erroring,
+                // so probe it while idle.
+                if let Err(e) = client.GetCurrentPadding() {
+                    failure = Some(format!("Capture stream lost: {e}"));
+                    break;
+                }
                 continue;
             }
 
@@ -316,8 +444,8 @@ fn capture_loop_with_client(
                 let packet_size = match capture.GetNextPacketSize() {
                     Ok(size) => size,
                     Err(e) => {
-                        eprintln!("[audio_capture] GetNextPacketSize error: {e}");
-                        break;
+                        failure = Some(format!("GetNextPacketSize error: {e}"));
+                        break 'capture;
                     }
                 };
 
@@ -332,8 +460,8 @@ fn capture_loop_with_client(
                 if let Err(e) =
                     capture.GetBuffer(&mut data_ptr, &mut frames_read, &mut flags, None, None)
                 {
-                    eprintln!("[audio_capture] GetBuffer error: {e}");
-                    break;
+                    failure = Some(format!("GetBuffer error: {e}"));
+                    break 'capture;
                 }
 
                 if frames_read > 0 {
@@ -342,34 +470,37 @@ fn capture_loop_with_client(
 
                     if is_silent {
                         // Send silence
-                        let stereo = vec![0.0f32; frames_read as usize * 2];
-                        let _ = channel.send(AudioChunk {
-                            samples: stereo,
-                            sample_rate,
-                        });
+                        let mono = vec![0.0f32; frames_read as usize];
+                        if let Err(e) = output.send(&mono, sample_rate) {
+                            failure = Some(e);
+                            break 'capture;
+                        }
                     } else {
                         let data_bytes = frames_read as usize * block_align;
                         let raw_data = std::slice::from_raw_parts(data_ptr, data_bytes);
-                        let stereo =
-                            interleaved_to_stereo_f32(raw_data, num_channels, bytes_per_sample);
-                        if !stereo.is_empty() {
-                            let _ = channel.send(AudioChunk {
-                                samples: stereo,
-                                sample_rate,
-                            });
+                        let mono =
+                            interleaved_to_mono_f32(raw_data, num_channels, bytes_per_sample);
+                        if !mono.is_empty() {
+                            if let Err(e) = output.send(&mono, sample_rate) {
+                                failure = Some(e);
+                                break 'capture;
+                            }
                         }
                     }
                 }
 
                 if let Err(e) = capture.ReleaseBuffer(frames_read) {
-                    eprintln!("[audio_capture] ReleaseBuffer error: {e}");
-                    break;
+                    failure = Some(format!("ReleaseBuffer error: {e}"));
+                    break 'capture;
                 }
             }
         }
 
-        client.Stop()?;
+        let _ = client.Stop();
         let _ = CloseHandle(event);
+        if let Some(failure) = failure {
+            return Err(failure.into());
+        }
     }
 
     eprintln!("[audio_capture] Stopped");
@@ -379,16 +510,22 @@ fn capture_loop_with_client(
 /// Legacy WASAPI loopback capture using the wasapi crate.
 /// Used as a fallback when the Process Loopback Exclusion API is unavailable
 /// (e.g. on Windows versions older than 10 2004).
+/// Also used for an explicitly selected render device (`device_id`).
 /// Note: This captures ALL system audio including our own voice chat playback.
 #[cfg(target_os = "windows")]
 fn capture_loop_legacy(
-    channel: &Channel<AudioChunk>,
+    output: &mut CaptureOutput,
     stop_flag: &Arc<AtomicBool>,
+    device_id: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     wasapi::initialize_mta().ok()?;
 
     let enumerator = wasapi::DeviceEnumerator::new()?;
-    let device = enumerator.get_default_device(&wasapi::Direction::Render)?;
+    let device = match device_id {
+        Some(id) => find_render_device(&enumerator, id)?
+            .ok_or_else(|| format!("Audio output device '{id}' not found"))?,
+        None => enumerator.get_default_device(&wasapi::Direction::Render)?,
+    };
     let mut audio_client = device.get_iaudioclient()?;
     let format = audio_client.get_mixformat()?;
 
@@ -420,15 +557,22 @@ fn capture_loop_legacy(
         sample_rate, num_channels, bits_per_sample
     );
 
+    let mut failure: Option<String> = None;
     while !stop_flag.load(Ordering::Relaxed) {
         if h_event.wait_for_event(100).is_err() {
+            // A removed device stops signalling; the padding query is what
+            // reports AUDCLNT_E_DEVICE_INVALIDATED.
+            if let Err(e) = audio_client.get_current_padding() {
+                failure = Some(format!("Audio output device lost: {e}"));
+                break;
+            }
             continue;
         }
 
         let (frames_read, _info) = match capture_client.read_from_device(&mut buffer) {
             Ok(result) => result,
             Err(e) => {
-                eprintln!("[audio_capture] Read error: {e}");
+                failure = Some(format!("Read error: {e}"));
                 break;
             }
         };
@@ -438,28 +582,66 @@ fn capture_loop_legacy(
         }
 
         let data_bytes = frames_read as usize * block_align;
-        let stereo =
-            interleaved_to_stereo_f32(&buffer[..data_bytes], num_channels, bytes_per_sample);
-        if !stereo.is_empty() {
-            let _ = channel.send(AudioChunk {
-                samples: stereo,
-                sample_rate,
-            });
+        let mono = interleaved_to_mono_f32(&buffer[..data_bytes], num_channels, bytes_per_sample);
+        if !mono.is_empty() {
+            if let Err(e) = output.send(&mono, sample_rate) {
+                failure = Some(e);
+                break;
+            }
         }
     }
 
-    audio_client.stop_stream()?;
+    let _ = audio_client.stop_stream();
+    if let Some(failure) = failure {
+        return Err(failure.into());
+    }
     eprintln!("[audio_capture] Stopped");
     Ok(())
 }
 
+#[cfg(target_os = "windows")]
+fn find_render_device(
+    enumerator: &wasapi::DeviceEnumerator,
+    id: &str,
+) -> Result<Option<wasapi::Device>, Box<dyn std::error::Error + Send + Sync>> {
+    let collection = enumerator.get_device_collection(&wasapi::Direction::Render)?;
+    for index in 0..collection.get_nbr_devices()? {
+        let device = collection.get_device_at_index(index)?;
+        if device.get_id()? == id {
+            return Ok(Some(device));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(target_os = "windows")]
+fn list_output_devices() -> Result<Vec<AudioOutputDevice>, Box<dyn std::error::Error + Send + Sync>>
+{
+    wasapi::initialize_mta().ok()?;
+
+    let enumerator = wasapi::DeviceEnumerator::new()?;
+    let collection = enumerator.get_device_collection(&wasapi::Direction::Render)?;
+    let mut devices = Vec::new();
+    for index in 0..collection.get_nbr_devices()? {
+        let device = collection.get_device_at_index(index)?;
+        devices.push(AudioOutputDevice {
+            id: device.get_id()?,
+            name: device.get_friendlyname()?,
+        });
+    }
+    Ok(devices)
+}
+
 // ---------------------------------------------------------------------------
 // Linux: PulseAudio monitor source capture
+// Also covers PipeWire desktops, which expose the same monitor sources
+// through pipewire-pulse.
 // ---------------------------------------------------------------------------
 #[cfg(target_os = "linux")]
 fn capture_loop(
-    channel: &Channel<AudioChunk>,
+    output: &mut CaptureOutput,
     stop_flag: &Arc<AtomicBool>,
+    device_id: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     use libpulse_binding::sample::{Format, Spec};
     use libpulse_binding::stream::Direction;
@@ -479,13 +661,18 @@ fn capture_loop(
     }
 
     // @DEFAULT_MONITOR@ captures the default output sink's monitor source,
-    // which provides system audio loopback.
+    // which provides system audio loopback. A selected sink is captured
+    // through its own `<sink>.monitor` source.
+    let source = match device_id {
+        Some(sink) => format!("{sink}.monitor"),
+        None => "@DEFAULT_MONITOR@".to_string(),
+    };
     let pulse = Simple::new(
-        None,                      // default server
-        "Paracord",                // app name
-        Direction::Record,         // recording
-        Some("@DEFAULT_MONITOR@"), // monitor source for loopback
-        "System Audio Capture",    // stream description
+        None,                   // default server
+        "Paracord",             // app name
+        Direction::Record,      // recording
+        Some(&source),          // monitor source for loopback
+        "System Audio Capture", // stream description
         &spec,
         None, // default channel map
         None, // default buffering attributes
@@ -502,10 +689,10 @@ fn capture_loop(
     );
 
     while !stop_flag.load(Ordering::Relaxed) {
-        if let Err(e) = pulse.read(&mut buffer) {
-            eprintln!("[audio_capture] PulseAudio read error: {e}");
-            break;
-        }
+        // Fails when the monitored sink goes away or the server drops us.
+        pulse
+            .read(&mut buffer)
+            .map_err(|e| format!("PulseAudio read error: {e}"))?;
 
         // Convert raw f32le bytes to Vec<f32>
         let samples: Vec<f32> = buffer
@@ -514,10 +701,7 @@ fn capture_loop(
             .collect();
 
         if !samples.is_empty() {
-            let _ = channel.send(AudioChunk {
-                samples,
-                sample_rate,
-            });
+            output.send(&samples, sample_rate)?;
         }
     }
 
@@ -525,19 +709,232 @@ fn capture_loop(
     Ok(())
 }
 
+/// Enumerate PulseAudio sinks; each one's monitor source can be captured.
+#[cfg(target_os = "linux")]
+fn list_output_devices() -> Result<Vec<AudioOutputDevice>, Box<dyn std::error::Error + Send + Sync>>
+{
+    use libpulse_binding::callbacks::ListResult;
+    use libpulse_binding::context::{Context, FlagSet, State};
+    use libpulse_binding::mainloop::standard::{IterateResult, Mainloop};
+    use libpulse_binding::operation::State as OperationState;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let mut mainloop = Mainloop::new().ok_or("Failed to create PulseAudio mainloop")?;
+    let mut context =
+        Context::new(&mainloop, "Paracord").ok_or("Failed to create PulseAudio context")?;
+    context
+        .connect(None, FlagSet::NOFLAGS, None)
+        .map_err(|e| format!("Failed to connect to PulseAudio: {e}"))?;
+
+    loop {
+        if let IterateResult::Quit(_) | IterateResult::Err(_) = mainloop.iterate(true) {
+            return Err("PulseAudio mainloop failed".into());
+        }
+        match context.get_state() {
+            State::Ready => break,
+            State::Failed | State::Terminated => {
+                return Err("Failed to connect to PulseAudio".into());
+            }
+            _ => {}
+        }
+    }
+
+    let devices = Rc::new(RefCell::new(Vec::new()));
+    let collected = devices.clone();
+    let operation = context.introspect().get_sink_info_list(move |result| {
+        if let ListResult::Item(info) = result {
+            if let Some(id) = info.name.as_ref() {
+                let name = info
+                    .description
+                    .as_ref()
+                    .map(|d| d.to_string())
+                    .unwrap_or_else(|| id.to_string());
+                collected.borrow_mut().push(AudioOutputDevice {
+                    id: id.to_string(),
+                    name,
+                });
+            }
+        }
+    });
+    while operation.get_state() == OperationState::Running {
+        if let IterateResult::Quit(_) | IterateResult::Err(_) = mainloop.iterate(true) {
+            return Err("PulseAudio mainloop failed".into());
+        }
+    }
+    context.disconnect();
+
+    let devices = devices.borrow().clone();
+    Ok(devices)
+}
+
 // ---------------------------------------------------------------------------
-// macOS: stub (not yet implemented)
+// macOS: CoreAudio capture from a loopback / aggregate input device
+// macOS has no built-in output loopback, so system audio has to be routed
+// through a virtual device (BlackHole, Loopback, or an aggregate device that
+// includes one). We open that device's input side with an AUHAL unit.
 // ---------------------------------------------------------------------------
+#[cfg(target_os = "macos")]
+const MACOS_LOOPBACK_DEVICE_HINTS: &[&str] = &[
+    "blackhole",
+    "loopback",
+    "soundflower",
+    "aggregate",
+    "paracord",
+];
+
+/// Input-capable devices whose name looks like a loopback driver or an
+/// aggregate device.
+#[cfg(target_os = "macos")]
+fn macos_loopback_devices() -> Vec<(coreaudio::sys::AudioDeviceID, String)> {
+    use coreaudio::audio_unit::macos_helpers::{get_audio_device_ids_for_scope, get_device_name};
+    use coreaudio::audio_unit::Scope;
+
+    get_audio_device_ids_for_scope(Scope::Input)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|id| get_device_name(id).ok().map(|name| (id, name)))
+        .filter(|(_, name)| {
+            let lower = name.to_lowercase();
+            MACOS_LOOPBACK_DEVICE_HINTS
+                .iter()
+                .any(|hint| lower.contains(hint))
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn list_output_devices() -> Result<Vec<AudioOutputDevice>, Box<dyn std::error::Error + Send + Sync>>
+{
+    Ok(macos_loopback_devices()
+        .into_iter()
+        .map(|(id, name)| AudioOutputDevice {
+            id: id.to_string(),
+            name,
+        })
+        .collect())
+}
+
 #[cfg(target_os = "macos")]
 fn capture_loop(
-    _channel: &Channel<AudioChunk>,
-    _stop_flag: &Arc<AtomicBool>,
+    output: &mut CaptureOutput,
+    stop_flag: &Arc<AtomicBool>,
+    device_id: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    Err(
-        "Native system audio capture is not yet supported on macOS. \
-         Audio from screen shares will still work via browser APIs."
-            .into(),
-    )
+    use coreaudio::audio_unit::audio_format::LinearPcmFlags;
+    use coreaudio::audio_unit::macos_helpers::{audio_unit_from_device_id, get_audio_device_ids};
+    use coreaudio::audio_unit::render_callback::{self, data};
+    use coreaudio::audio_unit::{Element, SampleFormat, Scope, StreamFormat};
+    use coreaudio::sys::{kAudioUnitProperty_StreamFormat, AudioStreamBasicDescription};
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    let devices = macos_loopback_devices();
+    let selected = match device_id {
+        Some(id) => devices
+            .into_iter()
+            .find(|(candidate, _)| candidate.to_string() == id)
+            .ok_or_else(|| format!("Audio device '{id}' not found"))?,
+        None => devices.into_iter().next().ok_or(
+            "No loopback audio device found. Install a loopback driver such as BlackHole \
+             and route system output through it (e.g. with a Multi-Output Device).",
+        )?,
+    };
+    let (device_id, device_name) = selected;
+
+    let mut audio_unit = audio_unit_from_device_id(device_id, true)
+        .map_err(|e| format!("Failed to open '{device_name}': {e}"))?;
+
+    // The hardware side of the input element dictates rate and channel
+    // count; AUHAL cannot resample input, so the client format must match.
+    let hw: AudioStreamBasicDescription = audio_unit
+        .get_property(
+            kAudioUnitProperty_StreamFormat,
+            Scope::Input,
+            Element::Input,
+        )
+        .map_err(|e| format!("Failed to read device format: {e}"))?;
+    let sample_rate = hw.mSampleRate as u32;
+    let num_channels = hw.mChannelsPerFrame.max(1) as usize;
+
+    let client_format = StreamFormat {
+        sample_rate: hw.mSampleRate,
+        sample_format: SampleFormat::F32,
+        flags: LinearPcmFlags::IS_FLOAT | LinearPcmFlags::IS_PACKED,
+        channels: num_channels as u32,
+    };
+    audio_unit
+        .set_property(
+            kAudioUnitProperty_StreamFormat,
+            Scope::Output,
+            Element::Input,
+            Some(&client_format.to_asbd()),
+        )
+        .map_err(|e| format!("Failed to set capture format: {e}"))?;
+
+    // The input callback runs on CoreAudio's realtime thread; hand the raw
+    // frames to this thread instead of doing IPC from there.
+    let (tx, rx) = mpsc::channel::<Vec<u8>>();
+    type Args = render_callback::Args<data::Interleaved<f32>>;
+    audio_unit
+        .set_input_callback(move |args: Args| {
+            let bytes: Vec<u8> = args
+                .data
+                .buffer
+                .iter()
+                .flat_map(|s| s.to_le_bytes())
+                .collect();
+            let _ = tx.send(bytes);
+            Ok(())
+        })
+        .map_err(|e| format!("Failed to install capture callback: {e}"))?;
+    audio_unit
+        .start()
+        .map_err(|e| format!("Failed to start capture: {e}"))?;
+
+    eprintln!(
+        "[audio_capture] Started CoreAudio loopback '{}': {}Hz, {} ch, f32",
+        device_name, sample_rate, num_channels
+    );
+
+    let mut last_data = Instant::now();
+    let mut failure: Option<String> = None;
+    while !stop_flag.load(Ordering::Relaxed) {
+        let raw = match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(raw) => raw,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                // CoreAudio just stops calling back when a device is removed,
+                // so check it still exists once input has gone quiet.
+                if last_data.elapsed() >= Duration::from_secs(1) {
+                    last_data = Instant::now();
+                    let alive = get_audio_device_ids()
+                        .map(|ids| ids.contains(&device_id))
+                        .unwrap_or(true);
+                    if !alive {
+                        failure = Some(format!("Audio device '{device_name}' was disconnected"));
+                        break;
+                    }
+                }
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+        last_data = Instant::now();
+        let mono = interleaved_to_mono_f32(&raw, num_channels, 4);
+        if !mono.is_empty() {
+            if let Err(e) = output.send(&mono, sample_rate) {
+                failure = Some(e);
+                break;
+            }
+        }
+    }
+
+    let _ = audio_unit.stop();
+    if let Some(failure) = failure {
+        return Err(failure.into());
+    }
+    eprintln!("[audio_capture] Stopped");
+    Ok(())
 }
 
 // ---------------------------------------------------------------------------
@@ -545,12 +942,115 @@ fn capture_loop(
 // ---------------------------------------------------------------------------
 #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
 fn capture_loop(
-    _channel: &Channel<AudioChunk>,
+    _output: &mut CaptureOutput,
     _stop_flag: &Arc<AtomicBool>,
+    _device_id: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     Err("System audio capture is not supported on this platform.".into())
 }
 
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+fn list_output_devices() -> Result<Vec<AudioOutputDevice>, Box<dyn std::error::Error + Send + Sync>>
+{
+    Ok(Vec::new())
+}
+
+/// Resamples captured mono audio to a fixed rate and forwards it in fixed 10 ms chunks, whatever the device's rate and
+/// period size, so the frontend and encoder always receive steady frames at
+/// the rate they expect.
+struct CaptureOutput {
+    sink: Box<dyn FnMut(AudioChunk) + Send>,
+    target_rate: u32,
+    chunk_frames: usize,
+    source_rate: u32,
+    resampler: Option<FftFixedOut<f32>>,
+    /// Mono samples at `source_rate` not yet emitted.
+    pending: Vec<f32>,
+}
+
+impl CaptureOutput {
+    fn new(sink: impl FnMut(AudioChunk) + Send + 'static, target_rate: u32) -> Self {
+        Self {
+            sink: Box::new(sink),
+            target_rate,
+            chunk_frames: (target_rate / 100).max(1) as usize,
+            source_rate: target_rate,
+            resampler: None,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Queue mono samples captured at `sample_rate` and send every complete
+    /// chunk.
+    fn send(&mut self, mono: &[f32], sample_rate: u32) -> Result<(), String> {
+        if sample_rate != self.source_rate {
+            self.reconfigure(sample_rate)?;
+        }
+        self.pending.extend_from_slice(mono);
+
+        loop {
+            let needed = match &self.resampler {
+                Some(resampler) => resampler.input_frames_next(),
+                None => self.chunk_frames,
+            };
+            if self.pending.len() < needed {
+                return Ok(());
+            }
+            let input: Vec<f32> = self.pending.drain(..needed).collect();
+            let samples = match self.resampler.as_mut() {
+                Some(resampler) => resampler
+                    .process(&[input], None)
+                    .map_err(|e| format!("Resampler error: {e}"))?
+                    .pop()
+                    .unwrap_or_default(),
+                None => input,
+            };
+            (self.sink)(AudioChunk {
+                samples,
+                sample_rate: self.target_rate,
+                error: None,
+            });
+        }
+    }
+
+    fn reconfigure(&mut self, sample_rate: u32) -> Result<(), String> {
+        self.pending.clear();
+        self.resampler = if sample_rate == self.target_rate {
+            None
+        } else {
+            Some(
+                FftFixedOut::<f32>::new(
+                    sample_rate as usize,
+                    self.target_rate as usize,
+                    self.chunk_frames,
+                    1, // sub_chunks
+                    1, // channels
+                )
+                .map_err(|e| {
+                    format!(
+                        "Cannot resample {sample_rate}Hz to {}Hz: {e}",
+                        self.target_rate
+                    )
+                })?,
+            )
+        };
+        self.source_rate = sample_rate;
+        eprintln!(
+            "[audio_capture] Resampling {}Hz -> {}Hz in {}-frame chunks",
+            sample_rate, self.target_rate, self.chunk_frames
+        );
+        Ok(())
+    }
+
+    fn send_error(&mut self, message: String) {
+        (self.sink)(AudioChunk {
+            samples: Vec::new(),
+            sample_rate: self.target_rate,
+            error: Some(message),
+        });
+    }
+}
+
 /// Decode a single PCM sample from raw bytes at the given offset.
 #[inline]
 fn decode_sample(data: &[u8], offset: usize, bytes_per_sample: usize) -> f32 {
@@ -583,72 +1083,7 @@ fn decode_sample(data: &[u8], offset: usize, bytes_per_sample: usize) -> f32 {
     }
 }
 
-/// Convert interleaved raw PCM bytes to interleaved stereo f32 (L, R, L, R, ...).
-/// Mono sources are duplicated to both channels; >2 channels are downmixed.
-#[allow(dead_code)]
-fn interleaved_to_stereo_f32(
-    data: &[u8],
-    num_channels: usize,
-    bytes_per_sample: usize,
-) -> Vec<f32> {
-    let frame_size = num_channels * bytes_per_sample;
-    if frame_size == 0 {
-        return Vec::new();
-    }
-    let num_frames = data.len() / frame_size;
-    let mut stereo = Vec::with_capacity(num_frames * 2);
-
-    for frame_idx in 0..num_frames {
-        let frame_start = frame_idx * frame_size;
-        match num_channels {
-            1 => {
-                let s = decode_sample(data, frame_start, bytes_per_sample);
-                stereo.push(s);
-                stereo.push(s);
-            }
-            2 => {
-                let l = decode_sample(data, frame_start, bytes_per_sample);
-                let r = decode_sample(data, frame_start + bytes_per_sample, bytes_per_sample);
-                stereo.push(l);
-                stereo.push(r);
-            }
-            _ => {
-                // Downmix N channels to stereo: left = average of even channels,
-                // right = average of odd channels (standard surround downmix).
-                let mut left_sum = 0.0f32;
-                let mut right_sum = 0.0f32;
-                let mut left_count = 0u32;
-                let mut right_count = 0u32;
-                for ch in 0..num_channels {
-                    let offset = frame_start + ch * bytes_per_sample;
-                    let s = decode_sample(data, offset, bytes_per_sample);
-                    if ch % 2 == 0 {
-                        left_sum += s;
-                        left_count += 1;
-                    } else {
-                        right_sum += s;
-                        right_count += 1;
-                    }
-                }
-                stereo.push(if left_count > 0 {
-                    left_sum / left_count as f32
-                } else {
-                    0.0
-                });
-                stereo.push(if right_count > 0 {
-                    right_sum / right_count as f32
-                } else {
-                    0.0
-                });
-            }
-        }
-    }
-
-    stereo
-}
-
 /// Convert interleaved raw PCM bytes to a mono f32 vector by averaging all channels.
-#[allow(dead_code)]
 fn interleaved_to_mono_f32(data: &[u8], num_channels: usize, bytes_per_sample: usize) -> Vec<f32> {
     let frame_size = num_channels * bytes_per_sample;
     if frame_size == 0 {
@@ -667,3 +1102,88 @@ fn interleaved_to_mono_f32(data: &[u8], num_channels: usize, bytes_per_sample: u
     }
     mono
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{interleaved_to_mono_f32, AudioChunk, CaptureOutput};
+    use std::sync::{Arc, Mutex};
+
+    fn collecting_output(target_rate: u32) -> (CaptureOutput, Arc<Mutex<Vec<AudioChunk>>>) {
+        let chunks = Arc::new(Mutex::new(Vec::new()));
+        let sink = chunks.clone();
+        let output = CaptureOutput::new(move |chunk| sink.lock().unwrap().push(chunk), target_rate);
+        (output, chunks)
+    }
+
+    fn sine(frequency: f32, sample_rate: u32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                (2.0 * std::f32::consts::PI * frequency * t).sin() * 0.5
+            })
+            .collect()
+    }
+
+    #[test]
+    fn downmixes_stereo_into_fixed_mono_chunks() {
+        let (mut output, chunks) = collecting_output(48_000);
+        let stereo: Vec<u8> = [1.0f32, 0.0]
+            .repeat(1_000)
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect();
+        let mono = interleaved_to_mono_f32(&stereo, 2, 4);
+        output.send(&mono, 48_000).unwrap();
+
+        let chunks = chunks.lock().unwrap();
+        // 1000 frames make two whole 10 ms chunks; the rest waits.
+        assert_eq!(chunks.len(), 2);
+        for chunk in chunks.iter() {
+            assert_eq!(chunk.sample_rate, 48_000);
+            assert_eq!(chunk.samples.len(), 480);
+            assert!(chunk.samples.iter().all(|&s| s == 0.5));
+        }
+        assert_eq!(output.pending.len(), 40);
+    }
+
+    #[test]
+    fn resamples_to_the_requested_rate_without_shifting_pitch() {
+        let (mut output, chunks) = collecting_output(48_000);
+        let input = sine(1_000.0, 44_100, 44_100);
+        // Device periods rarely line up with output chunks.
+        for period in input.chunks(441 * 3) {
+            output.send(period, 44_100).unwrap();
+        }
+
+        let chunks = chunks.lock().unwrap();
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.samples.len() == 480 && chunk.sample_rate == 48_000));
+        let samples: Vec<f32> = chunks.iter().flat_map(|c| c.samples.clone()).collect();
+        // One second in gives close to one second out, less resampler delay.
+        assert!(samples.len() > 46_000 && samples.len() <= 48_000);
+
+        // Skip the resampler's warm-up, then count rising zero crossings over
+        // half a second: a 1 kHz tone keeps its pitch at the new rate.
+        let window = &samples[4_800..4_800 + 24_000];
+        let crossings = window
+            .windows(2)
+            .filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0)
+            .count();
+        assert!((499..=501).contains(&crossings), "{crossings} crossings");
+    }
+
+    #[test]
+    fn follows_a_device_rate_change() {
+        let (mut output, chunks) = collecting_output(16_000);
+        output.send(&[0.25; 160], 16_000).unwrap();
+        assert_eq!(chunks.lock().unwrap().len(), 1);
+
+        output.send(&sine(440.0, 48_000, 4_800), 48_000).unwrap();
+        let chunks = chunks.lock().unwrap();
+        assert!(chunks.len() > 1);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.samples.len() == 160 && chunk.sample_rate == 16_000));
+    }
+}
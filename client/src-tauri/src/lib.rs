@@ -98,6 +98,8 @@ pub fn run() {
         commands::set_activity_sharing_enabled,
         commands::get_foreground_application,
         audio_capture::set_system_audio_capture_enabled,
+        audio_capture::list_audio_output_devices,
+        audio_capture::get_audio_capture_device,
         audio_capture::start_system_audio_capture,
         audio_capture::stop_system_audio_capture,
         // Native QUIC media engine
@@ -19,6 +19,12 @@ export const adminApi = {
     total_guilds: number;
     total_messages: number;
     total_channels: number;
+    federation?: {
+      outbound_queue_depth: number;
+      outbound_retrying: number;
+      outbound_dead_lettered: number;
+      outbound_failures_24h: number;
+    };
   }>('/admin/stats'),
 
   listSecurityEvents: (params?: { before?: string; limit?: number; action?: string }) =>
@@ -52,6 +58,15 @@ export const adminApi = {
   deleteUser: (userId: string) =>
     apiClient.delete(`/admin/users/${userId}`),
 
+  bulkUserAction: (
+    action: 'disable' | 'force_logout' | 'delete' | 'unban',
+    userIds: string[],
+  ) =>
+    apiClient.post<{
+      action: string;
+      results: Array<{ id: string; success: boolean; error?: string }>;
+    }>('/admin/users/bulk', { action, user_ids: userIds }),
+
   getGuilds: () =>
     apiClient.get<{
       guilds: Array<{
@@ -1,5 +1,14 @@
 import { apiClient } from './client';
-import type { LoginRequest, LoginResponse, RegisterRequest, ReadState, User, UserSettings } from '../types';
+import type {
+  CustomStatus,
+  LoginRequest,
+  LoginResponse,
+  RegisterRequest,
+  RegisterResponse,
+  ReadState,
+  User,
+  UserSettings,
+} from '../types';
 
 export interface AuthSession {
   id: string;
@@ -15,12 +24,15 @@ export interface AuthSession {
 export interface AuthOptions {
   allow_username_login: boolean;
   require_email: boolean;
+  require_email_verification?: boolean;
 }
 
 export const authApi = {
   options: () => apiClient.get<AuthOptions>('/auth/options'),
   login: (data: LoginRequest) => apiClient.post<LoginResponse>('/auth/login', data),
-  register: (data: RegisterRequest) => apiClient.post<LoginResponse>('/auth/register', data),
+  register: (data: RegisterRequest) => apiClient.post<RegisterResponse>('/auth/register', data),
+  verifyEmail: (token: string) =>
+    apiClient.get<{ verified: boolean }>('/auth/verify-email', { params: { token } }),
   refresh: (refreshToken?: string) =>
     apiClient.post<{ token: string; refresh_token?: string }>(
       '/auth/refresh',
@@ -29,12 +41,29 @@ export const authApi = {
   logout: () => apiClient.post('/auth/logout'),
   listSessions: () => apiClient.get<AuthSession[]>('/auth/sessions'),
   revokeSession: (sessionId: string) => apiClient.delete(`/auth/sessions/${sessionId}`),
+  revokeOtherSessions: () => apiClient.delete<{ revoked: number }>('/users/@me/sessions'),
   attachPublicKey: (publicKey: string) =>
     apiClient.post<LoginResponse>('/auth/attach-public-key', { public_key: publicKey }),
   getMe: () => apiClient.get<User>('/users/@me'),
   updateMe: (data: Partial<User>) => apiClient.patch<User>('/users/@me', data),
+  uploadAvatar: (file: File) => {
+    const formData = new FormData();
+    formData.append('image', file);
+    return apiClient.put<User>('/users/@me/avatar', formData);
+  },
+  removeAvatar: () => apiClient.delete('/users/@me/avatar'),
+  uploadBanner: (file: File) => {
+    const formData = new FormData();
+    formData.append('image', file);
+    return apiClient.put<User>('/users/@me/banner', formData);
+  },
+  removeBanner: () => apiClient.delete('/users/@me/banner'),
+  getUser: (userId: string) => apiClient.get<User>(`/users/${userId}`),
   getSettings: () => apiClient.get<UserSettings>('/users/@me/settings'),
   updateSettings: (data: Partial<UserSettings>) => apiClient.patch<UserSettings>('/users/@me/settings', data),
+  setCustomStatus: (data: { text?: string; emoji?: string; expires_at?: string | null }) =>
+    apiClient.put<CustomStatus>('/users/@me/custom-status', data),
+  clearCustomStatus: () => apiClient.delete('/users/@me/custom-status'),
   getReadStates: () => apiClient.get<ReadState[]>('/users/@me/read-states'),
   changePassword: (currentPassword: string, newPassword: string) =>
     apiClient.put('/users/@me/password', {
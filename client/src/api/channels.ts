@@ -8,6 +8,7 @@ import type {
   PaginationParams,
   Poll,
   SendMessageRequest,
+  User,
 } from '../types';
 
 interface CreateThreadRequest {
@@ -16,6 +17,12 @@ interface CreateThreadRequest {
   auto_archive_duration?: number;
 }
 
+interface ThreadMember {
+  id: string;
+  user_id: string;
+  join_timestamp: string;
+}
+
 interface UpdateThreadRequest {
   name?: string;
   archived?: boolean;
@@ -34,7 +41,16 @@ interface CreatePollRequest {
   expires_in_minutes?: number;
 }
 
+/** Server-enforced message limits; length counts characters as displayed. */
+export interface MessageLimits {
+  max_message_length: number;
+  max_attachments_per_message: number;
+  max_upload_size: number;
+  max_pins_per_channel: number;
+}
+
 export const channelApi = {
+  limits: () => apiClient.get<MessageLimits>('/limits'),
   get: (id: string) => apiClient.get<Channel>(`/channels/${id}`),
   update: (id: string, data: Partial<Channel>) => apiClient.patch<Channel>(`/channels/${id}`, data),
   delete: (id: string) => apiClient.delete(`/channels/${id}`),
@@ -44,7 +60,7 @@ export const channelApi = {
   searchMessages: (id: string, q: string, limit = 20) =>
     apiClient.get<Message[]>(`/channels/${id}/messages/search`, { params: { q, limit } }),
   bulkDeleteMessages: (id: string, messageIds: string[]) =>
-    apiClient.post<{ deleted: number }>(`/channels/${id}/messages/bulk-delete`, { message_ids: messageIds }),
+    apiClient.post<{ deleted: number; skipped_ids: string[] }>(`/channels/${id}/messages/bulk-delete`, { message_ids: messageIds }),
   sendMessage: (id: string, data: SendMessageRequest) =>
     apiClient.post<Message>(`/channels/${id}/messages`, data),
   editMessage: (channelId: string, messageId: string, data: EditMessageRequest) =>
@@ -66,13 +82,23 @@ export const channelApi = {
     apiClient.delete(
       `/channels/${channelId}/messages/${messageId}/reactions/${encodeURIComponent(emoji)}/@me`
     ),
+  getReactionUsers: (
+    channelId: string,
+    messageId: string,
+    emoji: string,
+    params?: { after?: string; limit?: number }
+  ) =>
+    apiClient.get<Pick<User, 'id' | 'username' | 'discriminator' | 'avatar_hash'>[]>(
+      `/channels/${channelId}/messages/${messageId}/reactions/${encodeURIComponent(emoji)}`,
+      { params }
+    ),
 
   triggerTyping: (id: string) => apiClient.post(`/channels/${id}/typing`),
   updateReadState: (id: string, lastMessageId?: string) =>
     apiClient.put(`/channels/${id}/read`, { last_message_id: lastMessageId }),
 
   updatePositions: (guildId: string, positions: { id: string; position: number; parent_id?: string | null }[]) =>
-    apiClient.patch<{ updated: number }>(`/guilds/${guildId}/channels`, positions),
+    apiClient.patch<{ updated: number; channels: Channel[] }>(`/guilds/${guildId}/channels`, positions),
 
   createThread: (channelId: string, data: CreateThreadRequest) =>
     apiClient.post<Channel>(`/channels/${channelId}/threads`, data),
@@ -84,6 +110,15 @@ export const channelApi = {
     apiClient.patch<Channel>(`/channels/${channelId}/threads/${threadId}`, data),
   deleteThread: (channelId: string, threadId: string) =>
     apiClient.delete(`/channels/${channelId}/threads/${threadId}`),
+  createThreadFromMessage: (
+    channelId: string,
+    messageId: string,
+    data: Omit<CreateThreadRequest, 'message_id'>
+  ) => apiClient.post<Channel>(`/channels/${channelId}/messages/${messageId}/threads`, data),
+  getThreadMembers: (threadId: string) =>
+    apiClient.get<ThreadMember[]>(`/channels/${threadId}/thread-members`),
+  joinThread: (threadId: string) => apiClient.put(`/channels/${threadId}/thread-members/@me`),
+  leaveThread: (threadId: string) => apiClient.delete(`/channels/${threadId}/thread-members/@me`),
 
   createPoll: (channelId: string, data: CreatePollRequest) =>
     apiClient.post<Message>(`/channels/${channelId}/polls`, data),
@@ -10,10 +10,29 @@ export interface ApiErrorResponse {
   code: string;
   message: string;
   details?: unknown;
+  /** Server-side request id; quote it when reporting the error. */
+  request_id?: string | null;
   /** Legacy field kept for backwards compatibility. */
   error?: string;
 }
 
+const PASSWORD_RULE_MESSAGES: Record<string, string> = {
+  min_length: 'is too short',
+  max_length: 'is too long',
+  lowercase: 'needs a lowercase letter',
+  uppercase: 'needs an uppercase letter',
+  digit: 'needs a number',
+  symbol: 'needs a symbol',
+  breached: 'has appeared in a data breach; choose a different one',
+};
+
+function describeWeakPassword(details: unknown): string | null {
+  const rules = (details as { failed_rules?: unknown } | null)?.failed_rules;
+  if (!Array.isArray(rules) || rules.length === 0) return null;
+  const parts = rules.map((rule) => PASSWORD_RULE_MESSAGES[String(rule)] ?? String(rule));
+  return `Password ${parts.join(', ')}.`;
+}
+
 /**
  * Extract a human-readable error message from an API error.
  * Supports the standardized {code, message, details} format.
@@ -21,6 +40,10 @@ export interface ApiErrorResponse {
 export function extractApiError(err: unknown): string {
   if (axios.isAxiosError(err)) {
     const data = (err as AxiosError<ApiErrorResponse>).response?.data;
+    if (data && typeof data === 'object' && data.code === 'WEAK_PASSWORD') {
+      const described = describeWeakPassword(data.details);
+      if (described) return described;
+    }
     if (data && typeof data === 'object' && typeof data.message === 'string') {
       return data.message;
     }
@@ -6,6 +6,7 @@ import type {
   Member,
   Role,
   Invite,
+  InviteAnalytics,
   Ban,
   AuditLogEntry,
   CreateGuildRequest,
@@ -15,21 +16,51 @@ import type {
   UpdateMemberRequest,
 } from '../types';
 
+type MemberPage = { members: Member[]; total: number; has_more: boolean };
+type BanPage = { bans: Ban[]; has_more: boolean };
+
 export const guildApi = {
   getAll: () => apiClient.get<Guild[]>('/users/@me/guilds'),
   create: (data: CreateGuildRequest) => apiClient.post<Guild>('/guilds', data),
   get: (id: string) => apiClient.get<Guild>(`/guilds/${id}`),
   update: (id: string, data: Partial<Guild>) => apiClient.patch<Guild>(`/guilds/${id}`, data),
   delete: (id: string) => apiClient.delete(`/guilds/${id}`),
+  updateVanityUrl: (id: string, code: string | null) =>
+    apiClient.patch<{ code: string | null }>(`/guilds/${id}/vanity-url`, { code }),
   transferOwnership: (id: string, newOwnerId: string) =>
     apiClient.post(`/guilds/${id}/owner`, { new_owner_id: newOwnerId }),
+  uploadIcon: (id: string, file: File) => {
+    const formData = new FormData();
+    formData.append('image', file);
+    return apiClient.put<Guild>(`/guilds/${id}/icon`, formData);
+  },
+  removeIcon: (id: string) => apiClient.delete(`/guilds/${id}/icon`),
+  uploadBanner: (id: string, file: File) => {
+    const formData = new FormData();
+    formData.append('image', file);
+    return apiClient.put<Guild>(`/guilds/${id}/banner`, formData);
+  },
+  removeBanner: (id: string) => apiClient.delete(`/guilds/${id}/banner`),
 
   getChannels: (id: string, config?: AxiosRequestConfig) =>
     apiClient.get<Channel[]>(`/guilds/${id}/channels`, config),
   createChannel: (id: string, data: CreateChannelRequest) =>
     apiClient.post<Channel>(`/guilds/${id}/channels`, data),
 
-  getMembers: (id: string) => apiClient.get<Member[]>(`/guilds/${id}/members`),
+  getMembers: (id: string) => apiClient.get<MemberPage>(`/guilds/${id}/members`),
+  searchMembers: (
+    id: string,
+    params: {
+      q?: string;
+      role_id?: string;
+      timed_out?: boolean;
+      joined_after?: string;
+      joined_before?: string;
+      after?: string;
+      limit?: number;
+    }
+  ) =>
+    apiClient.get<MemberPage>(`/guilds/${id}/members`, { params }),
   updateMember: (guildId: string, userId: string, data: UpdateMemberRequest) =>
     apiClient.patch<Member>(`/guilds/${guildId}/members/${userId}`, data),
   kickMember: (guildId: string, userId: string) =>
@@ -43,14 +74,39 @@ export const guildApi = {
     apiClient.patch<Role>(`/guilds/${guildId}/roles/${roleId}`, data),
   deleteRole: (guildId: string, roleId: string) =>
     apiClient.delete(`/guilds/${guildId}/roles/${roleId}`),
+  uploadRoleIcon: (guildId: string, roleId: string, file: File) => {
+    const formData = new FormData();
+    formData.append('image', file);
+    return apiClient.put<Role>(`/guilds/${guildId}/roles/${roleId}/icon`, formData);
+  },
+  removeRoleIcon: (guildId: string, roleId: string) =>
+    apiClient.delete(`/guilds/${guildId}/roles/${roleId}/icon`),
+  reorderRoles: (guildId: string, positions: { id: string; position: number }[]) =>
+    apiClient.patch<{ updated: number; roles: Role[] }>(`/guilds/${guildId}/roles`, positions),
 
-  getBans: (id: string) => apiClient.get<Ban[]>(`/guilds/${id}/bans`),
-  banMember: (guildId: string, userId: string, reason?: string) =>
-    apiClient.put(`/guilds/${guildId}/bans/${userId}`, { reason }),
+  getBans: (id: string) => apiClient.get<BanPage>(`/guilds/${id}/bans`),
+  searchBans: (
+    id: string,
+    params: { banned_by?: string; reason?: string; after?: string; limit?: number }
+  ) => apiClient.get<BanPage>(`/guilds/${id}/bans`, { params }),
+  banMember: (
+    guildId: string,
+    userId: string,
+    reason?: string,
+    durationSeconds?: number,
+    deleteMessageSeconds?: number
+  ) =>
+    apiClient.put<{ deleted_message_count: number }>(`/guilds/${guildId}/bans/${userId}`, {
+      reason,
+      duration_seconds: durationSeconds,
+      delete_message_seconds: deleteMessageSeconds,
+    }),
   unbanMember: (guildId: string, userId: string) =>
     apiClient.delete(`/guilds/${guildId}/bans/${userId}`),
 
   getInvites: (id: string) => apiClient.get<Invite[]>(`/guilds/${id}/invites`),
+  getInviteAnalytics: (id: string, params?: { days?: number; limit?: number }) =>
+    apiClient.get<InviteAnalytics>(`/guilds/${id}/invites/analytics`, { params }),
   createInvite: (channelId: string, data?: CreateInviteRequest) =>
     apiClient.post<Invite>(`/channels/${channelId}/invites`, data),
 
@@ -0,0 +1,21 @@
+import { apiClient } from './client';
+
+/** Shape of `PushSubscription.toJSON()` as reported by the browser. */
+export interface PushSubscriptionPayload {
+  endpoint: string;
+  keys: {
+    p256dh: string;
+    auth: string;
+  };
+}
+
+export const pushApi = {
+  getVapidPublicKey: () =>
+    apiClient.get<{ public_key: string }>('/push/vapid-public-key'),
+
+  subscribe: (subscription: PushSubscriptionPayload) =>
+    apiClient.post('/users/@me/push-subscriptions', subscription),
+
+  unsubscribe: (endpoint: string) =>
+    apiClient.delete('/users/@me/push-subscriptions', { data: { endpoint } }),
+};
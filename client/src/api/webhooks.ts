@@ -20,6 +20,8 @@ export const webhookApi = {
   create: (guildId: string, data: CreateWebhookRequest) =>
     apiClient.post<Webhook>(`/guilds/${guildId}/webhooks`, data),
   listGuild: (guildId: string) => apiClient.get<Webhook[]>(`/guilds/${guildId}/webhooks`),
+  createInChannel: (channelId: string, data: { name: string }) =>
+    apiClient.post<Webhook>(`/channels/${channelId}/webhooks`, data),
   listChannel: (channelId: string) => apiClient.get<Webhook[]>(`/channels/${channelId}/webhooks`),
   get: (webhookId: string) => apiClient.get<Webhook>(`/webhooks/${webhookId}`),
   update: (webhookId: string, data: UpdateWebhookRequest) =>
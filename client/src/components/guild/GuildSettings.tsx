@@ -170,7 +170,7 @@ export function GuildSettings({ guildId, guildName, onClose }: GuildSettingsProp
         setIconDataUrl(`/api/v1/guilds/${guildId}/icon`);
       }
       setRoles(rolesRes.data);
-      setMembers(membersRes.data);
+      setMembers(membersRes.data.members);
       const normalizedChannels = channelsRes.data.map((channel) => ({
         ...channel,
         required_role_ids: channel.required_role_ids ?? [],
@@ -186,7 +186,7 @@ export function GuildSettings({ guildId, guildName, onClose }: GuildSettingsProp
         if (current && ownAppsRes.data.some((app) => app.id === current)) return current;
         return ownAppsRes.data[0].id;
       });
-      setBans(bansRes.data);
+      setBans(bansRes.data.bans);
       setAuditEntries(auditRes.data.audit_log_entries || []);
       if (!newWebhookChannelId) {
         const firstTextChannel = normalizedChannels.find((c) => c.type === 0 || c.channel_type === 0);
@@ -1730,7 +1730,7 @@ export function GuildSettings({ guildId, guildName, onClose }: GuildSettingsProp
               <div className="card-stack">
                 {auditEntries.map((entry) => (
                   <div key={entry.id} className="card-surface rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-3.5 py-3">
-                    <div className="text-sm" style={{ color: 'var(--text-primary)' }}>Action {entry.action_type} on {entry.target_id || 'n/a'}</div>
+                    <div className="text-sm" style={{ color: 'var(--text-primary)' }}>{entry.action === 'unknown' ? `Action ${entry.action_type}` : entry.action.replace(/_/g, ' ')} on {entry.target_id || 'n/a'}</div>
                     <div className="text-xs mt-1" style={{ color: 'var(--text-muted)' }}>
                       by {entry.user_id} at {new Date(entry.created_at).toLocaleString()}
                     </div>
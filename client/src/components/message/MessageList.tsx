@@ -12,7 +12,7 @@ import { useMemberStore } from '../../stores/memberStore';
 import { channelApi } from '../../api/channels';
 import { fileApi } from '../../api/files';
 import { extractApiError } from '../../api/client';
-import { MessageType, Permissions, hasPermission, type Channel, type Message } from '../../types';
+import { MessageType, Permissions, hasPermission, type Channel, type Message, type MessageReferenceSnapshot } from '../../types';
 import { UserProfilePopup } from '../user/UserProfile';
 import { EmojiPicker } from '../ui/EmojiPicker';
 import { ContextMenu, useContextMenu, type ContextMenuItem } from '../ui/ContextMenu';
@@ -140,6 +140,17 @@ function getReplyPreviewText(message: Message): string {
   return '[Message]';
 }
 
+function getReplySnapshotText(snapshot: MessageReferenceSnapshot): string {
+  if (snapshot.deleted) return 'Original message deleted';
+  const text = (snapshot.content || '').trim();
+  if (text) return truncateInline(text.replace(/\s+/g, ' '));
+  if (snapshot.attachment_count) {
+    return snapshot.attachment_count === 1 ? '[Attachment]' : `[${snapshot.attachment_count} attachments]`;
+  }
+  if (snapshot.e2ee) return '[Encrypted message]';
+  return '[Message]';
+}
+
 function resolveReplyParentId(message: Message): string | null {
   const legacyReferencedId = (message as Message & { referenced_message_id?: string }).referenced_message_id;
   const raw = message.reference_id || message.referenced_message?.id || legacyReferencedId || null;
@@ -891,6 +902,7 @@ export function MessageList({ channelId, onReply }: MessageListProps) {
     const replyDepth = row.replyDepth;
     const replyParentId = row.replyParentId;
     const replyParentMessage = replyParentId ? messageById.get(replyParentId) : undefined;
+    const replySnapshot = msg.referenced_message ?? null;
     const replyIndent = Math.min(replyDepth, MAX_REPLY_NEST_DEPTH) * REPLY_INDENT_PX;
     const isOwnMessage = msg.author.id === me;
     const canEditMessage = isOwnMessage;
@@ -975,10 +987,14 @@ export function MessageList({ channelId, onReply }: MessageListProps) {
               >
                 <Reply size={11} className="shrink-0" />
                 <span className="max-w-[8rem] truncate font-semibold" style={{ color: 'var(--text-secondary)' }}>
-                  {replyParentMessage?.author.username || 'Original message'}
+                  {replyParentMessage?.author.username || replySnapshot?.author?.username || 'Original message'}
                 </span>
                 <span className="truncate">
-                  {replyParentMessage ? getReplyPreviewText(replyParentMessage) : 'Message not loaded'}
+                  {replyParentMessage
+                    ? getReplyPreviewText(replyParentMessage)
+                    : replySnapshot
+                      ? getReplySnapshotText(replySnapshot)
+                      : 'Message not loaded'}
                 </span>
               </button>
             </div>
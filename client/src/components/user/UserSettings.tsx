@@ -27,6 +27,13 @@ import {
   readableAppName,
   saveKnownActivityAppsToStorage,
 } from '../../lib/activityPresence';
+import { isTauri } from '../../lib/tauriEnv';
+import {
+  getNativeAudioCaptureDevice,
+  listNativeAudioOutputDevices,
+  setNativeAudioCaptureDevice,
+  type NativeAudioOutputDevice,
+} from '../../lib/systemAudioCapture';
 
 interface UserSettingsProps {
   onClose: () => void;
@@ -83,6 +90,7 @@ export function UserSettings({ onClose }: UserSettingsProps) {
   const [saving, setSaving] = useState(false);
   const [statusText, setStatusText] = useState<string | null>(null);
   const cryptoAuthEnabled = settings?.crypto_auth_enabled === true;
+  const showMutuals = settings?.show_mutuals !== false;
   const {
     audioInputDevices,
     audioOutputDevices,
@@ -92,6 +100,8 @@ export function UserSettings({ onClose }: UserSettingsProps) {
     selectAudioOutput,
     enumerate,
   } = useMediaDevices();
+  const [nativeCaptureDevices, setNativeCaptureDevices] = useState<NativeAudioOutputDevice[]>([]);
+  const [nativeCaptureDevice, setNativeCaptureDeviceState] = useState('');
   const applyAudioInputDevice = useVoiceStore((s) => s.applyAudioInputDevice);
   const applyAudioOutputDevice = useVoiceStore((s) => s.applyAudioOutputDevice);
   const userIsAdmin = user ? isAdmin(user.flags ?? 0) : false;
@@ -124,6 +134,21 @@ export function UserSettings({ onClose }: UserSettingsProps) {
   const [importFile, setImportFile] = useState<File | null>(null);
   const [identityStatus, setIdentityStatus] = useState<string | null>(null);
 
+  useEffect(() => {
+    if (!isTauri()) return;
+    let cancelled = false;
+    void Promise.all([listNativeAudioOutputDevices(), getNativeAudioCaptureDevice()])
+      .then(([devices, selected]) => {
+        if (cancelled) return;
+        setNativeCaptureDevices(devices);
+        setNativeCaptureDeviceState(selected);
+      })
+      .catch(() => {});
+    return () => {
+      cancelled = true;
+    };
+  }, []);
+
   useEffect(() => {
     void checkNotificationPermission().then((granted) => {
       setNotifPermission(granted ? 'granted' : 'denied');
@@ -318,6 +343,21 @@ export function UserSettings({ onClose }: UserSettingsProps) {
     }
   };
 
+  const revokeOtherSessions = async () => {
+    if (sessionBusyId) return;
+    if (!(await confirm({ title: 'Log out everywhere else?', description: 'Every other device will be signed out immediately.', confirmLabel: 'Log out', variant: 'danger' }))) return;
+    setSessionBusyId('others');
+    try {
+      const { data } = await authApi.revokeOtherSessions();
+      setSessions((prev) => prev.filter((session) => session.current));
+      setStatusText(data.revoked === 1 ? 'Signed out 1 other session.' : `Signed out ${data.revoked} other sessions.`);
+    } catch (err) {
+      setStatusText(`Failed to sign out other sessions: ${extractApiError(err)}`);
+    } finally {
+      setSessionBusyId(null);
+    }
+  };
+
   const submitPasswordChange = async () => {
     const current = passwordCurrentPassword.trim();
     const nextPassword = accountNewPassword.trim();
@@ -448,6 +488,18 @@ export function UserSettings({ onClose }: UserSettingsProps) {
     await saveSettings();
   };
 
+  const handleShowMutualsToggle = async (enabled: boolean) => {
+    setSaving(true);
+    try {
+      await updateSettings({ show_mutuals: enabled });
+      setStatusText(enabled ? 'Mutual friends and spaces are visible.' : 'Mutual friends and spaces are hidden.');
+    } catch {
+      setStatusText('Failed to update mutuals visibility.');
+    } finally {
+      setSaving(false);
+    }
+  };
+
   const handleCryptoSecurityToggle = async (enabled: boolean) => {
     if (!localCryptoAccountReady) return;
     setSaving(true);
@@ -832,13 +884,24 @@ export function UserSettings({ onClose }: UserSettingsProps) {
                             <div className="text-xs font-semibold uppercase tracking-wide text-text-secondary">
                               Active Sessions
                             </div>
-                            <button
-                              className="rounded-lg px-3 py-1.5 text-xs font-semibold text-text-secondary transition-colors hover:bg-bg-mod-strong hover:text-text-primary"
-                              onClick={() => void loadSessions()}
-                              disabled={sessionsLoading}
-                            >
-                              {sessionsLoading ? 'Refreshing...' : 'Refresh'}
-                            </button>
+                            <div className="flex items-center gap-2">
+                              {sessions.some((session) => !session.current) && (
+                                <button
+                                  className="rounded-lg border border-accent-danger/35 bg-accent-danger/10 px-3 py-1.5 text-xs font-semibold text-accent-danger transition-colors hover:bg-accent-danger/15 disabled:opacity-60"
+                                  onClick={() => void revokeOtherSessions()}
+                                  disabled={sessionBusyId === 'others'}
+                                >
+                                  {sessionBusyId === 'others' ? 'Signing out...' : 'Log Out Everywhere Else'}
+                                </button>
+                              )}
+                              <button
+                                className="rounded-lg px-3 py-1.5 text-xs font-semibold text-text-secondary transition-colors hover:bg-bg-mod-strong hover:text-text-primary"
+                                onClick={() => void loadSessions()}
+                                disabled={sessionsLoading}
+                              >
+                                {sessionsLoading ? 'Refreshing...' : 'Refresh'}
+                              </button>
+                            </div>
                           </div>
                           <div className="space-y-2.5">
                             {sessions.map((session) => (
@@ -1082,6 +1145,27 @@ export function UserSettings({ onClose }: UserSettingsProps) {
                     ))}
                   </select>
                 </label>
+                {isTauri() && (
+                  <label className="card-surface block rounded-xl border border-border-subtle bg-bg-mod-subtle/55 px-6 py-5">
+                    <span className="text-xs font-semibold uppercase tracking-wide text-text-secondary">Screen Share Audio Source</span>
+                    <select
+                      className="select-field mt-3"
+                      value={nativeCaptureDevice}
+                      onChange={(e) => {
+                        const value = e.target.value;
+                        setNativeCaptureDeviceState(value);
+                        setNativeAudioCaptureDevice(value);
+                      }}
+                    >
+                      <option value="">Default</option>
+                      {nativeCaptureDevices.map((device) => (
+                        <option key={device.id} value={device.id}>
+                          {device.name}
+                        </option>
+                      ))}
+                    </select>
+                  </label>
+                )}
                 <div className="card-surface flex flex-wrap items-center justify-between gap-3 rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-6 py-5">
                   <div>
                     <div className="text-sm font-medium text-text-primary">Noise Suppression</div>
@@ -1174,6 +1258,18 @@ export function UserSettings({ onClose }: UserSettingsProps) {
                     onToggle={() => setNotifications((prev) => ({ ...prev, messageSound: !Boolean(prev.messageSound) }))}
                   />
                 </div>
+                <div className="card-surface flex items-center justify-between rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-6 py-5">
+                  <div>
+                    <div className="text-sm font-medium text-text-primary">Security Alert Emails</div>
+                    <div className="text-xs text-text-muted">Email me when my account signs in from a new device</div>
+                  </div>
+                  <ToggleSwitch
+                    on={Boolean(mergedNotifications.securityAlertEmails)}
+                    onToggle={() =>
+                      setNotifications((prev) => ({ ...prev, securityAlertEmails: !Boolean(prev.securityAlertEmails) }))
+                    }
+                  />
+                </div>
               </div>
               <div className="settings-action-row">
                 <button className="btn-primary" onClick={() => void saveSettings()} disabled={saving}>
@@ -1200,6 +1296,19 @@ export function UserSettings({ onClose }: UserSettingsProps) {
                   />
                 </div>
 
+                <div className="card-surface flex flex-wrap items-center justify-between gap-3 rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-6 py-5">
+                  <div>
+                    <div className="text-sm font-medium text-text-primary">Show mutual friends and spaces</div>
+                    <div className="text-xs text-text-muted">
+                      Let other users see which friends and spaces you have in common.
+                    </div>
+                  </div>
+                  <ToggleSwitch
+                    on={showMutuals}
+                    onToggle={() => void handleShowMutualsToggle(!showMutuals)}
+                  />
+                </div>
+
                 <div className="card-surface rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-6 py-6">
                   <div className="mb-3 text-xs font-semibold uppercase tracking-wide text-text-secondary">
                     Detected Apps
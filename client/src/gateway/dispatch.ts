@@ -131,6 +131,9 @@ export function dispatchGatewayEvent(serverId: string, event: string, data: any)
     case GatewayEvents.MESSAGE_UPDATE:
       useMessageStore.getState().updateMessage(data.channel_id, data);
       break;
+    case GatewayEvents.MESSAGE_EMBEDS_UPDATE:
+      useMessageStore.getState().setMessageEmbeds(data.channel_id, data.id, data.embeds ?? []);
+      break;
     case GatewayEvents.MESSAGE_DELETE:
       useMessageStore.getState().removeMessage(data.channel_id, data.id);
       break;
@@ -254,7 +257,7 @@ export function dispatchGatewayEvent(serverId: string, event: string, data: any)
 
     case GatewayEvents.TYPING_START:
       if (data.channel_id && data.user_id) {
-        useTypingStore.getState().addTyping(data.channel_id, data.user_id);
+        useTypingStore.getState().addTyping(data.channel_id, data.user_id, data.expires_at);
       }
       break;
 
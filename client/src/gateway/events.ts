@@ -24,6 +24,7 @@ export const GatewayEvents = {
   MESSAGE_UPDATE: 'MESSAGE_UPDATE',
   MESSAGE_DELETE: 'MESSAGE_DELETE',
   MESSAGE_DELETE_BULK: 'MESSAGE_DELETE_BULK',
+  MESSAGE_EMBEDS_UPDATE: 'MESSAGE_EMBEDS_UPDATE',
   MESSAGE_REACTION_ADD: 'MESSAGE_REACTION_ADD',
   MESSAGE_REACTION_REMOVE: 'MESSAGE_REACTION_REMOVE',
   POLL_VOTE_ADD: 'POLL_VOTE_ADD',
@@ -36,3 +36,7 @@ export function formatCustomEmojiToken(name: string, emojiId: string, animated =
 export function buildGuildEmojiImageUrl(guildId: string, emojiId: string): string {
   return `${normalizedApiBase()}/guilds/${encodeURIComponent(guildId)}/emojis/${encodeURIComponent(emojiId)}/image`;
 }
+
+export function buildEmojiImageUrl(emojiId: string, animated = false): string {
+  return `${normalizedApiBase()}/emojis/${encodeURIComponent(emojiId)}.${animated ? 'gif' : 'png'}`;
+}
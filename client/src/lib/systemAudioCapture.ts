@@ -2,6 +2,39 @@ import { isTauri } from './tauriEnv';
 import { SystemAudioBridge } from './systemAudioWorklet';
 
 let activeBridge: SystemAudioBridge | null = null;
+// Device chosen in settings but not yet handed to the backend, which
+// remembers it once a capture starts with it.
+let pendingDeviceId: string | null = null;
+
+export interface NativeAudioOutputDevice {
+  id: string;
+  name: string;
+}
+
+interface NativeAudioMessage {
+  samples: number[];
+  sample_rate: number;
+  error?: string;
+}
+
+export async function listNativeAudioOutputDevices(): Promise<NativeAudioOutputDevice[]> {
+  if (!isTauri()) return [];
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<NativeAudioOutputDevice[]>('list_audio_output_devices');
+}
+
+/** Currently selected capture device id; `''` means the system default. */
+export async function getNativeAudioCaptureDevice(): Promise<string> {
+  if (!isTauri()) return '';
+  if (pendingDeviceId !== null) return pendingDeviceId;
+  const { invoke } = await import('@tauri-apps/api/core');
+  return (await invoke<string | null>('get_audio_capture_device')) ?? '';
+}
+
+/** Select the device used by the next capture; `''` selects the system default. */
+export function setNativeAudioCaptureDevice(deviceId: string): void {
+  pendingDeviceId = deviceId;
+}
 
 function errorMessage(err: unknown): string {
   if (err instanceof Error) return err.message;
@@ -34,24 +67,35 @@ export async function startNativeSystemAudio(): Promise<MediaStreamTrack | null>
     const workingBridge = bridge;
     const track = await workingBridge.start();
 
-    const channel = new Channel<{ samples: number[]; sample_rate: number }>();
+    const channel = new Channel<NativeAudioMessage>();
     channel.onmessage = (msg) => {
+      if (msg.error) {
+        // Capture ended on its own (e.g. the device was unplugged).
+        console.warn('[voice] Native system audio capture stopped:', msg.error);
+        return;
+      }
       workingBridge.pushSamples(new Float32Array(msg.samples));
     };
 
+    // Omitted device id = backend reuses the remembered selection.
+    const sampleRate = workingBridge.sampleRate;
+    const args = pendingDeviceId === null
+      ? { onAudio: channel, sampleRate }
+      : { onAudio: channel, deviceId: pendingDeviceId, sampleRate };
     try {
-      await invoke('start_system_audio_capture', { onAudio: channel });
+      await invoke('start_system_audio_capture', args);
     } catch (startErr) {
       // One retry path for "already running" races from overlapping stop/start.
       if (!isCaptureAlreadyRunningError(startErr)) {
         throw startErr;
       }
       await invoke('stop_system_audio_capture').catch(() => {});
-      await invoke('start_system_audio_capture', { onAudio: channel });
+      await invoke('start_system_audio_capture', args);
     }
+    pendingDeviceId = null;
 
     activeBridge = bridge;
-    console.info('[voice] Native system audio capture started (stereo)');
+    console.info('[voice] Native system audio capture started (mono)');
     return track;
   } catch (err) {
     console.warn('[voice] Failed to start native system audio capture:', err);
@@ -11,26 +11,13 @@ class SystemAudioProcessor extends AudioWorkletProcessor {
   }
   process(_inputs, outputs) {
     const output = outputs[0];
-    if (!output || output.length < 2) return true;
-    const left = output[0];
-    const right = output[1];
-    const needed = left.length;
-    // Buffer contains interleaved stereo: L, R, L, R, ...
-    const samplesNeeded = needed * 2;
-    if (this._buffer.length >= samplesNeeded) {
-      const chunk = this._buffer.splice(0, samplesNeeded);
-      for (let i = 0; i < needed; i++) {
-        left[i] = chunk[i * 2];
-        right[i] = chunk[i * 2 + 1];
-      }
-    } else {
-      const pairs = Math.floor(this._buffer.length / 2);
-      if (pairs > 0) {
-        const chunk = this._buffer.splice(0, pairs * 2);
-        for (let i = 0; i < pairs; i++) {
-          left[i] = chunk[i * 2];
-          right[i] = chunk[i * 2 + 1];
-        }
+    if (!output || output.length < 1) return true;
+    // Buffer contains mono samples; every output channel gets the same one.
+    const count = Math.min(output[0].length, this._buffer.length);
+    if (count > 0) {
+      const chunk = this._buffer.splice(0, count);
+      for (const channel of output) {
+        channel.set(chunk);
       }
     }
     return true;
@@ -44,8 +31,11 @@ export class SystemAudioBridge {
   private workletNode: AudioWorkletNode | null = null;
   private destination: MediaStreamAudioDestinationNode | null = null;
 
+  /** Rate the native capture must deliver samples at. */
+  readonly sampleRate = 48000;
+
   async start(): Promise<MediaStreamTrack> {
-    this.ctx = new AudioContext({ sampleRate: 48000 });
+    this.ctx = new AudioContext({ sampleRate: this.sampleRate });
 
     const blob = new Blob([WORKLET_PROCESSOR_CODE], { type: 'application/javascript' });
     const url = URL.createObjectURL(blob);
@@ -58,7 +48,7 @@ export class SystemAudioBridge {
     this.workletNode = new AudioWorkletNode(this.ctx, 'system-audio-processor', {
       numberOfInputs: 0,
       numberOfOutputs: 1,
-      outputChannelCount: [2],
+      outputChannelCount: [1],
     });
 
     this.destination = this.ctx.createMediaStreamDestination();
@@ -681,6 +681,30 @@ function SettingsPanel() {
           </button>
         </div>
 
+        {/* Email Verification Toggle */}
+        <div className="card-surface flex items-center justify-between rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-6 py-6">
+          <div>
+            <p className="font-medium text-text-primary">Require Email Verification</p>
+            <p className="text-sm text-text-muted">New accounts must confirm their email before signing in</p>
+          </div>
+          <button
+            onClick={() =>
+              update('require_email_verification', settings.require_email_verification === 'true' ? 'false' : 'true')
+            }
+            className={`relative h-7 w-12 rounded-full transition-colors ${
+              settings.require_email_verification === 'true'
+                ? 'bg-accent-success'
+                : 'bg-bg-mod-strong'
+            }`}
+          >
+            <div
+              className={`absolute top-0.5 h-6 w-6 rounded-full bg-white shadow transition-transform ${
+                settings.require_email_verification === 'true' ? 'translate-x-5' : 'translate-x-0.5'
+              }`}
+            />
+          </button>
+        </div>
+
         {/* Max guilds per user */}
         <div>
           <label className="mb-3 block text-sm font-medium text-text-secondary">
@@ -707,6 +731,23 @@ function SettingsPanel() {
           />
         </div>
 
+        {/* Allowed web origins */}
+        <div>
+          <label className="mb-3 block text-sm font-medium text-text-secondary">
+            Allowed Web Origins
+          </label>
+          <input
+            type="text"
+            value={settings.cors_allowed_origins || ''}
+            onChange={(e) => update('cors_allowed_origins', e.target.value)}
+            placeholder="https://chat.example.com, https://app.example.com"
+            className="input-field"
+          />
+          <p className="mt-1 text-xs text-text-muted">
+            Comma-separated browser origins allowed to call the API and open the gateway, in addition to the public URL. Applies within a few seconds.
+          </p>
+        </div>
+
         {/* ── Guild Storage ─────────────────────────────────── */}
         <div className="border-t border-border-subtle pt-6">
           <h3 className="mb-4 text-sm font-semibold uppercase tracking-wide text-text-secondary">
@@ -789,6 +830,46 @@ function SettingsPanel() {
           </p>
         </div>
 
+        {/* ── Data Retention ────────────────────────────────── */}
+        <div className="border-t border-border-subtle pt-6">
+          <h3 className="mb-4 text-sm font-semibold uppercase tracking-wide text-text-secondary">
+            Data Retention
+          </h3>
+        </div>
+
+        <div>
+          <label className="mb-3 block text-sm font-medium text-text-secondary">
+            Audit Log Retention (days)
+          </label>
+          <input
+            type="number"
+            min={0}
+            max={3650}
+            value={settings.audit_log_retention_days || ''}
+            onChange={(e) => update('audit_log_retention_days', e.target.value)}
+            placeholder="0 (keep forever)"
+            className="input-field"
+          />
+        </div>
+
+        <div>
+          <label className="mb-3 block text-sm font-medium text-text-secondary">
+            Security Event Retention (days)
+          </label>
+          <input
+            type="number"
+            min={0}
+            max={3650}
+            value={settings.security_event_retention_days || ''}
+            onChange={(e) => update('security_event_retention_days', e.target.value)}
+            placeholder="0 (keep forever)"
+            className="input-field"
+          />
+          <p className="mt-1 text-xs text-text-muted">
+            Older entries are purged by the retention worker. Set to 0 to keep them forever.
+          </p>
+        </div>
+
         {/* Save button */}
         <div className="settings-action-row">
           <button
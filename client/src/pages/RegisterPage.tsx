@@ -6,6 +6,7 @@ import { useServerListStore } from '../stores/serverListStore';
 import { getStoredServerUrl, getCurrentOriginServerUrl, setStoredServerUrl } from '../lib/apiBaseUrl';
 import { hasAccount } from '../lib/account';
 import { authApi } from '../api/auth';
+import { extractApiError } from '../api/client';
 import { MIN_PASSWORD_LENGTH } from '../lib/constants';
 
 export function RegisterPage() {
@@ -17,6 +18,7 @@ export function RegisterPage() {
   const [error, setError] = useState('');
   const [loading, setLoading] = useState(false);
   const [requireEmail, setRequireEmail] = useState(false);
+  const [verificationSent, setVerificationSent] = useState(false);
   const navigate = useNavigate();
   const register = useAuthStore((s) => s.register);
 
@@ -49,7 +51,11 @@ export function RegisterPage() {
     setError('');
     setLoading(true);
     try {
-      await register(email, username, password, displayName);
+      const verificationRequired = await register(email, username, password, displayName);
+      if (verificationRequired) {
+        setVerificationSent(true);
+        return;
+      }
 
       // If the user already has a local keypair, attach it to this server account.
       if (hasAccount()) {
@@ -87,7 +93,7 @@ export function RegisterPage() {
       // keypair. Users can set up a local crypto identity later in Settings.
       navigate('/app');
     } catch (err: any) {
-      setError(err.response?.data?.message || 'Registration failed. Please try again.');
+      setError(err.response?.data ? extractApiError(err) : 'Registration failed. Please try again.');
     } finally {
       setLoading(false);
     }
@@ -102,6 +108,16 @@ export function RegisterPage() {
             <p className="mt-3 text-sm text-text-muted">Pick your username and start hanging out.</p>
           </div>
 
+          {verificationSent && (
+            <div className="rounded-xl border border-border-subtle bg-bg-mod-subtle/60 px-5 py-4 text-sm text-text-secondary">
+              Check your email for a verification link, then{' '}
+              <Link to="/login" className="font-semibold text-text-link hover:underline">
+                sign in
+              </Link>
+              .
+            </div>
+          )}
+
           {error && (
             <div className="rounded-xl border border-accent-danger/35 bg-accent-danger/10 px-5 py-4 text-sm font-medium text-accent-danger">
               {error}
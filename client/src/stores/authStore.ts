@@ -15,7 +15,8 @@ interface AuthState {
   error: string | null;
 
   login: (identifier: string, password: string) => Promise<void>;
-  register: (email: string, username: string, password: string, displayName?: string) => Promise<void>;
+  /** Resolves to true when the account must verify its email before signing in. */
+  register: (email: string, username: string, password: string, displayName?: string) => Promise<boolean>;
   initializeSession: () => Promise<void>;
   setToken: (token: string | null) => void;
   logout: () => Promise<void>;
@@ -77,9 +78,14 @@ export const useAuthStore = create<AuthState>()((set) => ({
         password,
         display_name: displayName || undefined,
       });
+      if ('verification_required' in data) {
+        set({ isLoading: false });
+        return true;
+      }
       setAccessToken(data.token);
       if (data.refresh_token) setRefreshToken(data.refresh_token);
       set({ token: data.token, user: data.user, isLoading: false });
+      return false;
     } catch (err: unknown) {
       const message =
         (err as { response?: { data?: { message?: string } } }).response?.data?.message ||
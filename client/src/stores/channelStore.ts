@@ -198,7 +198,16 @@ export const useChannelStore = create<ChannelState>()((set, get) => ({
     });
 
     try {
-      await channelApi.updatePositions(guildId, positions);
+      const { data } = await channelApi.updatePositions(guildId, positions);
+      if (Array.isArray(data.channels)) {
+        // Reconcile with the server's authoritative ordering.
+        const sorted = data.channels.map(normalizeChannel).sort((a, b) => a.position - b.position);
+        set((state) => {
+          const channelsByGuild = { ...state.channelsByGuild, [guildId]: sorted };
+          const channels = state.selectedGuildId === guildId ? sorted : state.channels;
+          return { channelsByGuild, channels };
+        });
+      }
     } catch (err) {
       // Rollback on failure
       set((state) => {
@@ -34,7 +34,7 @@ export const useMemberStore = create<MemberState>()((set, get) => ({
       const { data } = await guildApi.getMembers(guildId);
       set((state) => {
         const members = new Map(state.members);
-        members.set(guildId, data);
+        members.set(guildId, data.members);
         const membersLoaded = { ...state.membersLoaded, [guildId]: true };
         return { members, membersLoaded, isLoading: false };
       });
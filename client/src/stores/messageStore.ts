@@ -4,6 +4,7 @@ import type {
   EditMessageRequest,
   Message,
   MessageE2eePayload,
+  MessageEmbed,
   PaginationParams,
   SendMessageRequest,
 } from '../types';
@@ -197,6 +198,7 @@ interface MessageState {
   // Gateway event handlers
   addMessage: (channelId: string, message: Message) => void;
   updateMessage: (channelId: string, message: Message) => void;
+  setMessageEmbeds: (channelId: string, messageId: string, embeds: MessageEmbed[]) => void;
   removeMessage: (channelId: string, messageId: string) => void;
   removeMessages: (channelId: string, messageIds: string[]) => void;
 }
@@ -620,6 +622,18 @@ export const useMessageStore = create<MessageState>()((set, get) => ({
     }
   },
 
+  setMessageEmbeds: (channelId, messageId, embeds) =>
+    set((state) => {
+      const existing = state.messages[channelId];
+      if (!existing) return state;
+      return {
+        messages: {
+          ...state.messages,
+          [channelId]: existing.map((m) => (m.id === messageId ? { ...m, embeds } : m)),
+        },
+      };
+    }),
+
   removeMessage: (channelId, messageId) =>
     set((state) => {
       const existing = state.messages[channelId] || [];
@@ -1,17 +1,19 @@
 import { create } from 'zustand';
 
 const typingTimeouts = new Map<string, ReturnType<typeof setTimeout>>();
+const DEFAULT_TYPING_DISPLAY_MS = 8000;
 
 interface TypingState {
   typingByChannel: Record<string, string[]>;
-  addTyping: (channelId: string, userId: string) => void;
+  /** `expiresAt` is the server-provided expiry in unix seconds. */
+  addTyping: (channelId: string, userId: string, expiresAt?: number) => void;
   clearChannel: (channelId: string) => void;
 }
 
 export const useTypingStore = create<TypingState>()((set) => ({
   typingByChannel: {},
 
-  addTyping: (channelId, userId) =>
+  addTyping: (channelId, userId, expiresAt) =>
     set((state) => {
       const channelUsers = state.typingByChannel[channelId] || [];
       const nextUsers = channelUsers.includes(userId)
@@ -21,6 +23,10 @@ export const useTypingStore = create<TypingState>()((set) => ({
       const timeoutKey = `${channelId}:${userId}`;
       const existing = typingTimeouts.get(timeoutKey);
       if (existing) clearTimeout(existing);
+      const displayMs =
+        typeof expiresAt === 'number'
+          ? Math.max(0, expiresAt * 1000 - Date.now())
+          : DEFAULT_TYPING_DISPLAY_MS;
       typingTimeouts.set(
         timeoutKey,
         setTimeout(() => {
@@ -34,7 +40,7 @@ export const useTypingStore = create<TypingState>()((set) => ({
             };
           });
           typingTimeouts.delete(timeoutKey);
-        }, 8000)
+        }, displayMs)
       );
 
       return {
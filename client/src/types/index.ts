@@ -8,7 +8,11 @@ export interface User {
   avatar?: string;
   avatar_hash?: string | null;
   banner?: string;
-  bio?: string;
+  banner_hash?: string | null;
+  bio?: string | null;
+  pronouns?: string | null;
+  /** 24-bit RGB value. */
+  accent_color?: number | null;
   display_name?: string | null;
   bot: boolean;
   system: boolean;
@@ -23,12 +27,39 @@ export interface UserSettings {
   message_display_compact: boolean;
   custom_css?: string;
   status: 'online' | 'idle' | 'dnd' | 'invisible';
-  custom_status?: string;
+  custom_status?: CustomStatus | null;
   crypto_auth_enabled: boolean;
+  show_mutuals?: boolean;
+  /** Only friends see the user's bio and pronouns. */
+  friends_only_profile?: boolean;
   notifications?: Record<string, unknown>;
+  notification_settings?: NotificationSetting[];
   keybinds?: Record<string, unknown>;
 }
 
+/** Set via `PUT /users/@me/custom-status`; cleared automatically at `expires_at`. */
+export interface CustomStatus {
+  text: string | null;
+  emoji: string | null;
+  expires_at: string | null;
+}
+
+export type NotificationLevel = 'all' | 'mentions' | 'nothing';
+
+/** Per-space or per-channel notification preferences; channel entries override their space. */
+export interface NotificationSetting {
+  guild_id?: string;
+  channel_id?: string;
+  /** Null inherits the space setting (or "all"). */
+  level: NotificationLevel | null;
+  /** Null follows `level`. */
+  push_level: NotificationLevel | null;
+  suppress_everyone: boolean;
+  muted: boolean;
+  /** Null while muted means muted until cleared. */
+  muted_until: string | null;
+}
+
 export interface HubSettings {
   description?: string;
   banner_hash?: string;
@@ -48,7 +79,9 @@ export interface Guild {
   owner_id: string;
   member_count: number;
   features: string[];
-  system_channel_id?: string;
+  system_channel_id?: string | null;
+  /** Bit 1 suppresses join announcements, bit 2 leave/kick/ban announcements. */
+  system_channel_flags?: number;
   rules_channel_id?: string;
   default_channel_id?: string | null;
   vanity_url_code?: string;
@@ -98,6 +131,7 @@ export interface Channel {
   message_count?: number | null;
   applied_tags?: string[] | null;
   default_sort_order?: number | null;
+  message_retention_days?: number | null;
   created_at: string;
   recipient?: {
     id: string;
@@ -117,6 +151,9 @@ export enum MessageType {
   ChannelIconChange = 5,
   PinnedMessage = 6,
   GuildMemberJoin = 7,
+  GuildMemberLeave = 9,
+  GuildMemberKick = 10,
+  GuildMemberBan = 11,
   Reply = 19,
   Poll = 20,
 }
@@ -137,6 +174,8 @@ export interface Message {
   channel_id: string;
   author: MessageAuthor;
   content: string | null;
+  /** Server-detected mentions, links and code spans in `content`. */
+  entities?: MessageEntity[];
   e2ee?: MessageE2eePayload | null;
   timestamp?: string;
   created_at?: string;
@@ -151,10 +190,29 @@ export interface Message {
   attachments: Attachment[];
   reactions: Reaction[] | unknown[];
   poll?: Poll;
-  referenced_message?: Message;
+  referenced_message?: MessageReferenceSnapshot | null;
   embeds?: MessageEmbed[];
 }
 
+/** Offsets and lengths are in UTF-16 code units, i.e. JavaScript string indices. */
+export type MessageEntity = { offset: number; length: number } & (
+  | { type: 'user_mention' | 'role_mention' | 'channel_mention'; id: string }
+  | { type: 'everyone' | 'here' | 'inline_code' }
+  | { type: 'url'; url: string }
+  | { type: 'code_block'; language: string | null }
+);
+
+export interface MessageReferenceSnapshot {
+  id: string;
+  channel_id?: string;
+  deleted: boolean;
+  author: MessageAuthor | null;
+  content: string | null;
+  e2ee?: boolean;
+  attachment_count?: number;
+  created_at?: string;
+}
+
 export interface MessageAuthor {
   id: string;
   username: string;
@@ -204,6 +262,7 @@ export interface Attachment {
 
 export interface Reaction {
   emoji: string;
+  emoji_id?: string | null;
   count: number;
   me: boolean;
 }
@@ -235,9 +294,18 @@ export interface Member {
   guild_id?: string;
   nick?: string | null;
   roles: string[];
+  /** Highest-position hoisted role; the member-list group this member goes in. */
+  hoisted_role_id?: string | null;
   joined_at: string;
   deaf: boolean;
   mute: boolean;
+  presence?: MemberPresence;
+}
+
+export interface MemberPresence {
+  status: Presence['status'];
+  custom_status?: CustomStatus | null;
+  last_seen_at: string | null;
 }
 
 export interface Role {
@@ -246,6 +314,7 @@ export interface Role {
   name: string;
   color: number;
   hoist: boolean;
+  icon?: string | null;
   position: number;
   permissions: string | number;
   mentionable: boolean;
@@ -260,6 +329,10 @@ export interface Invite {
   uses: number;
   max_uses?: number;
   max_age?: number;
+  remaining_uses?: number | null;
+  expires_at?: string | null;
+  expires_in?: number | null;
+  vanity?: boolean;
   temporary: boolean;
   created_at: string;
   guild?: Guild;
@@ -267,6 +340,23 @@ export interface Invite {
   inviter?: User;
 }
 
+export interface InviteAnalyticsEntry {
+  code: string;
+  inviter_id: string | null;
+  joins: number;
+  first_joined_at: string;
+  last_joined_at: string;
+  /** `null` once the invite has expired or been deleted. */
+  invite: Invite | null;
+  recent_joins: { user_id: string; joined_at: string }[];
+}
+
+export interface InviteAnalytics {
+  guild_id: string;
+  since: string;
+  invites: InviteAnalyticsEntry[];
+}
+
 export interface Webhook {
   id: string;
   guild_id: string;
@@ -307,6 +397,8 @@ export interface Presence {
   guild_id?: string;
   status: 'online' | 'idle' | 'dnd' | 'offline';
   activities: Activity[];
+  custom_status?: CustomStatus | null;
+  last_seen_at?: string | null;
 }
 
 export interface Activity {
@@ -321,8 +413,12 @@ export interface Activity {
 
 export interface Ban {
   user: User;
+  user_id: string;
   reason?: string;
   guild_id: string;
+  banned_by?: string | null;
+  created_at?: string;
+  expires_at?: string | null;
 }
 
 export interface AuditLogEntry {
@@ -330,6 +426,7 @@ export interface AuditLogEntry {
   guild_id: string;
   user_id: string;
   action_type: number;
+  action: string;
   target_id?: string;
   changes?: Record<string, unknown>;
   reason?: string;
@@ -339,7 +436,11 @@ export interface AuditLogEntry {
 export interface ReadState {
   channel_id: string;
   last_message_id: string;
+  /** Capped server-side; 100 means "99+". */
+  unread_count?: number;
   mention_count: number;
+  /** Muted channels report no unread messages. */
+  muted?: boolean;
 }
 
 // ============ User Flags ============
@@ -436,6 +537,9 @@ export interface LoginResponse {
   refresh_token?: string;
 }
 
+/** Registration either signs in or, when verification is required, waits for it. */
+export type RegisterResponse = LoginResponse | { verification_required: true; user: User };
+
 export interface RegisterRequest {
   email: string;
   username: string;
@@ -501,5 +605,6 @@ export interface InviteAcceptResponse {
 export interface PaginationParams {
   before?: string;
   after?: string;
+  around?: string;
   limit?: number;
 }
@@ -0,0 +1,258 @@
+//! Client address resolution. `X-Forwarded-For` is only believed when the
+//! socket peer is a configured trusted proxy, and is then read from the right:
+//! trusted hops are skipped and the first untrusted address is the client.
+//! Anything further left was written by the client and is ignored. With no
+//! trusted proxies configured the peer address is used as-is.
+//!
+//! The resolved address is stored on the request and on the handling task so
+//! rate limiting, auth guards and security event logging all agree on it.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::OnceLock;
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// The client address of a request, available as a request extension.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+tokio::task_local! {
+    static CURRENT_CLIENT_IP: Option<IpAddr>;
+}
+
+/// Client address of the request being handled on the current task, if any.
+pub fn current() -> Option<IpAddr> {
+    CURRENT_CLIENT_IP.try_with(|ip| *ip).ok().flatten()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Addresses and CIDR ranges of the reverse proxies in front of the server.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    nets: Vec<IpNet>,
+}
+
+impl TrustedProxies {
+    /// Parse entries like `10.0.0.5`, `10.0.0.0/8` or `fd00::/8`.
+    pub fn parse<S: AsRef<str>>(entries: &[S]) -> Result<Self, String> {
+        let mut nets = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let entry = entry.as_ref().trim();
+            if entry.is_empty() {
+                continue;
+            }
+            let (addr, prefix) = match entry.split_once('/') {
+                Some((addr, prefix)) => (addr, Some(prefix)),
+                None => (entry, None),
+            };
+            let addr: IpAddr = addr
+                .parse()
+                .map_err(|_| format!("invalid trusted proxy address '{entry}'"))?;
+            let addr = addr.to_canonical();
+            let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+            let prefix = match prefix {
+                Some(raw) => raw
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|prefix| *prefix <= max_prefix)
+                    .ok_or_else(|| format!("invalid trusted proxy prefix in '{entry}'"))?,
+                None => max_prefix,
+            };
+            nets.push(IpNet { addr, prefix });
+        }
+        Ok(Self { nets })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nets.is_empty()
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.nets.iter().any(|net| net.contains(ip))
+    }
+
+    /// Resolve the client address of a request from `peer` to `headers`.
+    pub fn resolve(&self, headers: &HeaderMap, peer: Option<IpAddr>) -> Option<IpAddr> {
+        let peer = peer?.to_canonical();
+        if !self.contains(peer) {
+            return Some(peer);
+        }
+        let hops: Vec<&str> = headers
+            .get_all(FORWARDED_FOR_HEADER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect();
+        let mut client = peer;
+        for hop in hops.into_iter().rev() {
+            // A hop we cannot parse ends the chain; the last proxy that
+            // forwarded it is as far as we can vouch for.
+            let Some(ip) = parse_hop(hop) else {
+                break;
+            };
+            client = ip;
+            if !self.contains(ip) {
+                break;
+            }
+        }
+        Some(client)
+    }
+}
+
+fn parse_hop(raw: &str) -> Option<IpAddr> {
+    let raw = raw.trim();
+    raw.parse::<IpAddr>()
+        .or_else(|_| raw.parse::<SocketAddr>().map(|addr| addr.ip()))
+        .ok()
+        .map(|ip| ip.to_canonical())
+}
+
+static TRUSTED_PROXIES: OnceLock<TrustedProxies> = OnceLock::new();
+
+pub fn install_trusted_proxies(proxies: TrustedProxies) {
+    let _ = TRUSTED_PROXIES.set(proxies);
+}
+
+/// The installed trusted proxies. Before the server installs them (unit
+/// tests, tools) they are read from `PARACORD_TRUST_PROXY` and
+/// `PARACORD_TRUSTED_PROXY_IPS` on each call.
+pub fn trusted_proxies() -> TrustedProxies {
+    if let Some(proxies) = TRUSTED_PROXIES.get() {
+        return proxies.clone();
+    }
+    let trust_proxy = std::env::var("PARACORD_TRUST_PROXY")
+        .ok()
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .unwrap_or(false);
+    if !trust_proxy {
+        return TrustedProxies::default();
+    }
+    let entries: Vec<String> = std::env::var("PARACORD_TRUSTED_PROXY_IPS")
+        .map(|raw| raw.split(',').map(str::to_string).collect())
+        .unwrap_or_default();
+    TrustedProxies::parse(&entries).unwrap_or_default()
+}
+
+/// Whether forwarded headers from `peer` may be believed.
+pub fn peer_is_trusted(peer: Option<IpAddr>) -> bool {
+    peer.is_some_and(|peer| trusted_proxies().contains(peer))
+}
+
+/// Client address for a request from `peer`, honouring trusted proxies.
+pub fn resolve(headers: &HeaderMap, peer: Option<IpAddr>) -> Option<IpAddr> {
+    trusted_proxies().resolve(headers, peer)
+}
+
+/// Middleware that resolves the client address once per request. It must
+/// wrap the rate limiter, which keys on [`ClientIp`].
+pub async fn client_ip_middleware(mut req: Request, next: Next) -> Response {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    let ip = resolve(req.headers(), peer);
+    if let Some(ip) = ip {
+        req.extensions_mut().insert(ClientIp(ip));
+    }
+    CURRENT_CLIENT_IP.scope(ip, next.run(req)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn forwarded(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(FORWARDED_FOR_HEADER, HeaderValue::from_static(value));
+        headers
+    }
+
+    fn ip(raw: &str) -> IpAddr {
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn untrusted_peers_cannot_forge_forwarded_for() {
+        let proxies = TrustedProxies::parse(&["10.0.0.5"]).unwrap();
+        let headers = forwarded("203.0.113.9");
+        assert_eq!(
+            proxies.resolve(&headers, Some(ip("198.51.100.7"))),
+            Some(ip("198.51.100.7"))
+        );
+        // Nothing configured: the header is never read.
+        assert_eq!(
+            TrustedProxies::default().resolve(&headers, Some(ip("10.0.0.5"))),
+            Some(ip("10.0.0.5"))
+        );
+    }
+
+    #[test]
+    fn forwarded_for_is_read_from_the_right() {
+        let proxies = TrustedProxies::parse(&["10.0.0.0/8", "fd00::/8"]).unwrap();
+        // The client prepended a fake address; the proxy appended the real one.
+        let headers = forwarded("1.2.3.4, 203.0.113.9, 10.1.2.3");
+        assert_eq!(
+            proxies.resolve(&headers, Some(ip("10.0.0.5"))),
+            Some(ip("203.0.113.9"))
+        );
+        // Dual-stack listeners report IPv4 peers as mapped IPv6.
+        assert_eq!(
+            proxies.resolve(&headers, Some(ip("::ffff:10.0.0.5"))),
+            Some(ip("203.0.113.9"))
+        );
+        // Only trusted hops: the leftmost is the best we have.
+        let headers = forwarded("10.9.9.9, 10.1.2.3");
+        assert_eq!(
+            proxies.resolve(&headers, Some(ip("fd00::1"))),
+            Some(ip("10.9.9.9"))
+        );
+        // Garbage stops the walk at the last trusted proxy.
+        let headers = forwarded("203.0.113.9, not-an-ip");
+        assert_eq!(
+            proxies.resolve(&headers, Some(ip("10.0.0.5"))),
+            Some(ip("10.0.0.5"))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_entries() {
+        assert!(TrustedProxies::parse(&["10.0.0.0/33"]).is_err());
+        assert!(TrustedProxies::parse(&["proxy.internal"]).is_err());
+        let proxies = TrustedProxies::parse(&["", " 192.168.1.1 "]).unwrap();
+        assert!(proxies.contains(ip("192.168.1.1")));
+        assert!(!proxies.contains(ip("192.168.1.2")));
+    }
+}
@@ -1,5 +1,5 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -14,29 +14,67 @@ pub enum ApiError {
     Unauthorized,
     #[error("forbidden")]
     Forbidden,
+    /// A block between the two users prevents opening or writing to a DM.
+    #[error("you cannot message this user")]
+    Blocked,
+    #[error("email address has not been verified")]
+    EmailNotVerified,
     #[error("bad request: {0}")]
     BadRequest(String),
     #[error("conflict: {0}")]
     Conflict(String),
+    /// One or more request fields were invalid; each entry is a field name
+    /// and what was wrong with it.
+    #[error("{}", join_field_messages(fields))]
+    Validation { fields: Vec<(&'static str, String)> },
+    /// Forbidden by the role hierarchy; the message says which rule applied.
+    #[error("{0}")]
+    RoleHierarchy(String),
     #[error("rate limited")]
     RateLimited,
+    #[error("invite has expired")]
+    InviteExpired,
+    #[error("invite has reached its maximum uses")]
+    InviteMaxUses,
+    /// Channel slowmode is active; `retry_after` is in seconds.
+    #[error("slowmode is active; retry after {retry_after:.1}s")]
+    Slowmode { retry_after: f64 },
     #[error("service unavailable: {0}")]
     ServiceUnavailable(String),
+    /// A new password failed the password policy; `failed` names each rule.
+    #[error("password does not meet requirements: {}", failed.join(", "))]
+    WeakPassword { failed: Vec<&'static str> },
     #[error("internal server error")]
     Internal(#[from] anyhow::Error),
 }
 
 impl ApiError {
-    /// Machine-readable error code string.
-    fn error_code(&self) -> &'static str {
+    /// A validation error for a single request field.
+    pub fn invalid_field(field: &'static str, message: impl Into<String>) -> Self {
+        ApiError::Validation {
+            fields: vec![(field, message.into())],
+        }
+    }
+
+    /// Stable machine-readable code sent as `code` in every error body.
+    /// Clients branch on these, so existing values must never change.
+    pub fn code(&self) -> &'static str {
         match self {
             ApiError::NotFound => "NOT_FOUND",
             ApiError::Unauthorized => "UNAUTHORIZED",
             ApiError::Forbidden => "FORBIDDEN",
+            ApiError::Blocked => "USER_BLOCKED",
+            ApiError::EmailNotVerified => "EMAIL_NOT_VERIFIED",
             ApiError::BadRequest(_) => "BAD_REQUEST",
             ApiError::Conflict(_) => "CONFLICT",
+            ApiError::Validation { .. } => "VALIDATION_FAILED",
+            ApiError::RoleHierarchy(_) => "ROLE_HIERARCHY",
             ApiError::RateLimited => "RATE_LIMITED",
+            ApiError::InviteExpired => "INVITE_EXPIRED",
+            ApiError::InviteMaxUses => "INVITE_MAX_USES",
+            ApiError::Slowmode { .. } => "SLOWMODE",
             ApiError::ServiceUnavailable(_) => "SERVICE_UNAVAILABLE",
+            ApiError::WeakPassword { .. } => "WEAK_PASSWORD",
             ApiError::Internal(_) => "INTERNAL_ERROR",
         }
     }
@@ -45,10 +83,16 @@ impl ApiError {
         match self {
             ApiError::NotFound => StatusCode::NOT_FOUND,
             ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
-            ApiError::Forbidden => StatusCode::FORBIDDEN,
-            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Forbidden
+            | ApiError::Blocked
+            | ApiError::EmailNotVerified
+            | ApiError::RoleHierarchy(_) => StatusCode::FORBIDDEN,
+            ApiError::BadRequest(_)
+            | ApiError::Validation { .. }
+            | ApiError::WeakPassword { .. } => StatusCode::BAD_REQUEST,
             ApiError::Conflict(_) => StatusCode::CONFLICT,
-            ApiError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::RateLimited | ApiError::Slowmode { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::InviteExpired | ApiError::InviteMaxUses => StatusCode::GONE,
             ApiError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
             ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -58,7 +102,7 @@ impl ApiError {
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let status = self.status_code();
-        let code = self.error_code();
+        let code = self.code();
 
         let message = match &self {
             ApiError::Internal(err) => {
@@ -68,26 +112,88 @@ impl IntoResponse for ApiError {
             other => other.to_string(),
         };
 
-        let body = json!({
-            "code": code,
-            "message": message,
-            // Keep legacy "error" field for backwards compatibility
-            "error": message,
-            "details": Value::Null,
-        });
+        let mut body = error_body(code, &message);
+
+        if let ApiError::Slowmode { retry_after } = self {
+            body["retry_after"] = json!(retry_after);
+            body["details"] = json!({ "retry_after": retry_after });
+            let mut response = (status, Json(body)).into_response();
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(retry_after.ceil() as u64),
+            );
+            return response;
+        }
+
+        match self {
+            ApiError::Validation { fields } => {
+                let fields: serde_json::Map<String, Value> = fields
+                    .into_iter()
+                    .map(|(field, message)| (field.to_string(), Value::String(message)))
+                    .collect();
+                body["details"] = json!({ "fields": fields });
+            }
+            ApiError::WeakPassword { failed } => {
+                body["details"] = json!({ "failed_rules": failed });
+            }
+            _ => {}
+        }
 
         (status, Json(body)).into_response()
     }
 }
 
+fn join_field_messages(fields: &[(&'static str, String)]) -> String {
+    fields
+        .iter()
+        .map(|(_, message)| message.as_str())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// The `{code, message, details}` body shared by every error response,
+/// tagged with the request id so a reported error can be found in the logs.
+pub(crate) fn error_body(code: &str, message: &str) -> Value {
+    json!({
+        "code": code,
+        "message": message,
+        // Keep legacy "error" field for backwards compatibility
+        "error": message,
+        "details": Value::Null,
+        "request_id": crate::request_id::current(),
+    })
+}
+
+/// Code for an error response that didn't come from an `ApiError`, such as
+/// an extractor rejection or an unmatched route.
+pub(crate) fn code_for_status(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "BAD_REQUEST",
+        StatusCode::UNAUTHORIZED => "UNAUTHORIZED",
+        StatusCode::FORBIDDEN => "FORBIDDEN",
+        StatusCode::NOT_FOUND => "NOT_FOUND",
+        StatusCode::METHOD_NOT_ALLOWED => "METHOD_NOT_ALLOWED",
+        StatusCode::CONFLICT => "CONFLICT",
+        StatusCode::PAYLOAD_TOO_LARGE => "PAYLOAD_TOO_LARGE",
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => "UNSUPPORTED_MEDIA_TYPE",
+        StatusCode::UNPROCESSABLE_ENTITY => "VALIDATION_FAILED",
+        StatusCode::TOO_MANY_REQUESTS => "RATE_LIMITED",
+        StatusCode::SERVICE_UNAVAILABLE => "SERVICE_UNAVAILABLE",
+        s if s.is_server_error() => "INTERNAL_ERROR",
+        _ => "BAD_REQUEST",
+    }
+}
+
 impl From<paracord_core::error::CoreError> for ApiError {
     fn from(e: paracord_core::error::CoreError) -> Self {
         match e {
             paracord_core::error::CoreError::NotFound => ApiError::NotFound,
             paracord_core::error::CoreError::Forbidden => ApiError::Forbidden,
+            paracord_core::error::CoreError::Blocked => ApiError::Blocked,
             paracord_core::error::CoreError::MissingPermission => ApiError::Forbidden,
             paracord_core::error::CoreError::BadRequest(msg) => ApiError::BadRequest(msg),
             paracord_core::error::CoreError::Conflict(msg) => ApiError::Conflict(msg),
+            paracord_core::error::CoreError::RoleHierarchy(msg) => ApiError::RoleHierarchy(msg),
             paracord_core::error::CoreError::Database(_) => {
                 ApiError::Internal(anyhow::anyhow!("database error"))
             }
@@ -1,5 +1,5 @@
 use axum::{
-    extract::{ConnectInfo, DefaultBodyLimit, Request},
+    extract::{DefaultBodyLimit, MatchedPath, Request, State},
     http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
     middleware::{from_fn, Next},
     response::IntoResponse,
@@ -8,19 +8,25 @@ use axum::{
     Json, Router,
 };
 use dashmap::DashMap;
-use paracord_core::{observability, AppState};
+use paracord_core::rate_limit::{HttpRateLimits, RouteCategory, RouteLimit, TokenBucket};
+use paracord_core::{observability, AppState, RuntimeSettings};
 use serde_json::json;
-use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 use tokio::sync::Notify;
 
+pub mod client_ip;
 pub mod error;
 pub mod middleware;
+pub mod request_id;
 pub mod routes;
 
 const DEFAULT_REQUEST_BODY_LIMIT_BYTES: usize = 2 * 1024 * 1024;
 const ATTACHMENT_REQUEST_BODY_LIMIT_BYTES: usize = 64 * 1024 * 1024;
+/// Largest plain-text error body rewritten into the JSON error shape.
+const ERROR_BODY_REWRITE_LIMIT_BYTES: usize = 64 * 1024;
+/// Longest the readiness probe waits on the database before calling it down.
+const READINESS_DB_TIMEOUT: Duration = Duration::from_secs(2);
 
 pub fn build_router() -> Router<AppState> {
     let cors = build_cors_layer();
@@ -28,8 +34,19 @@ pub fn build_router() -> Router<AppState> {
         // Health
         .route("/health", get(health))
         .route("/api/v1/health", get(health))
+        .route("/health/ready", get(health_ready))
+        .route("/api/v1/health/ready", get(health_ready))
+        .route(
+            "/health/livekit",
+            get(routes::livekit_proxy::livekit_health),
+        )
+        .route(
+            "/api/v1/health/livekit",
+            get(routes::livekit_proxy::livekit_health),
+        )
         .route("/metrics", get(metrics))
         .route("/api/v1/metrics", get(metrics))
+        .route("/api/v1/limits", get(limits))
         // Realtime v2 (SSE + HTTP command bus)
         .route("/api/v2/rt/session", post(routes::realtime::create_session))
         .route("/api/v2/rt/events", get(routes::realtime::stream_events))
@@ -96,6 +113,7 @@ pub fn build_router() -> Router<AppState> {
         .route("/api/v1/auth/register", post(routes::auth::register))
         .route("/api/v1/auth/login", post(routes::auth::login))
         .route("/api/v1/auth/options", get(routes::auth::auth_options))
+        .route("/api/v1/auth/verify-email", get(routes::auth::verify_email))
         .route("/api/v1/auth/refresh", post(routes::auth::refresh))
         .route("/api/v1/auth/logout", post(routes::auth::logout))
         .route("/api/v1/auth/challenge", post(routes::auth::challenge))
@@ -109,6 +127,20 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/auth/sessions/{session_id}",
             delete(routes::auth::revoke_session),
         )
+        .route("/api/v1/auth/mfa", post(routes::auth::complete_mfa_login))
+        .route("/api/v1/auth/mfa/totp", get(routes::auth::get_totp_status))
+        .route(
+            "/api/v1/auth/mfa/totp/enroll",
+            post(routes::auth::enroll_totp),
+        )
+        .route(
+            "/api/v1/auth/mfa/totp/confirm",
+            post(routes::auth::confirm_totp),
+        )
+        .route(
+            "/api/v1/auth/mfa/totp/disable",
+            post(routes::auth::disable_totp),
+        )
         // Users
         .route(
             "/api/v1/users/@me",
@@ -137,10 +169,47 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/users/@me/import",
             post(routes::users::import_identity),
         )
+        .route(
+            "/api/v1/users/@me/custom-status",
+            put(routes::users::set_custom_status).delete(routes::users::clear_custom_status),
+        )
+        .route(
+            "/api/v1/users/@me/banner",
+            put(routes::users::upload_banner).delete(routes::users::delete_banner),
+        )
+        .route(
+            "/api/v1/users/@me/avatar",
+            put(routes::users::upload_avatar).delete(routes::users::delete_avatar),
+        )
+        .route("/api/v1/users/{user_id}", get(routes::users::get_user))
+        .route(
+            "/api/v1/users/{user_id}/banners/{hash}",
+            get(routes::users::get_banner),
+        )
+        .route(
+            "/api/v1/users/{user_id}/avatars/{hash}",
+            get(routes::users::get_avatar),
+        )
         .route(
             "/api/v1/users/{user_id}/profile",
             get(routes::users::get_user_profile),
         )
+        .route(
+            "/api/v1/users/{user_id}/mutual-friends",
+            get(routes::users::get_mutual_friends),
+        )
+        .route(
+            "/api/v1/users/{user_id}/mutual-spaces",
+            get(routes::users::get_mutual_spaces),
+        )
+        .route(
+            "/api/v1/users/@me/sessions",
+            get(routes::auth::list_sessions).delete(routes::auth::revoke_other_sessions),
+        )
+        .route(
+            "/api/v1/users/@me/sessions/{session_id}",
+            delete(routes::auth::revoke_session),
+        )
         .route("/api/v1/users/@me/guilds", get(routes::guilds::list_guilds))
         .route(
             "/api/v1/users/@me/dms",
@@ -150,6 +219,19 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/users/@me/read-states",
             get(routes::users::get_read_states),
         )
+        .route(
+            "/api/v1/users/@me/overview",
+            get(routes::overview::get_overview),
+        )
+        .route(
+            "/api/v1/users/@me/push-subscriptions",
+            post(routes::push::create_push_subscription)
+                .delete(routes::push::delete_push_subscription),
+        )
+        .route(
+            "/api/v1/push/vapid-public-key",
+            get(routes::push::get_vapid_public_key),
+        )
         // Guilds
         .route("/api/v1/guilds", post(routes::guilds::create_guild))
         .route(
@@ -158,6 +240,26 @@ pub fn build_router() -> Router<AppState> {
                 .patch(routes::guilds::update_guild)
                 .delete(routes::guilds::delete_guild),
         )
+        .route(
+            "/api/v1/guilds/{guild_id}/icon",
+            put(routes::guilds::upload_guild_icon).delete(routes::guilds::delete_guild_icon),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/icons/{hash}",
+            get(routes::guilds::get_guild_icon),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/banner",
+            put(routes::guilds::upload_guild_banner).delete(routes::guilds::delete_guild_banner),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/banners/{hash}",
+            get(routes::guilds::get_guild_banner),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/vanity-url",
+            patch(routes::guilds::update_vanity_url),
+        )
         .route(
             "/api/v1/guilds/{guild_id}/owner",
             post(routes::guilds::transfer_ownership),
@@ -190,16 +292,30 @@ pub fn build_router() -> Router<AppState> {
         )
         .route(
             "/api/v1/guilds/{guild_id}/roles",
-            get(routes::roles::list_roles).post(routes::roles::create_role),
+            get(routes::roles::list_roles)
+                .post(routes::roles::create_role)
+                .patch(routes::roles::update_role_positions),
         )
         .route(
             "/api/v1/guilds/{guild_id}/roles/{role_id}",
             patch(routes::roles::update_role).delete(routes::roles::delete_role),
         )
+        .route(
+            "/api/v1/guilds/{guild_id}/roles/{role_id}/icon",
+            put(routes::roles::upload_role_icon).delete(routes::roles::delete_role_icon),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/roles/{role_id}/icons/{hash}",
+            get(routes::roles::get_role_icon),
+        )
         .route(
             "/api/v1/guilds/{guild_id}/invites",
             get(routes::invites::list_guild_invites),
         )
+        .route(
+            "/api/v1/guilds/{guild_id}/invites/analytics",
+            get(routes::invites::get_invite_analytics),
+        )
         .route(
             "/api/v1/guilds/{guild_id}/emojis",
             get(routes::emojis::list_guild_emojis).post(routes::emojis::create_emoji),
@@ -212,6 +328,7 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/guilds/{guild_id}/emojis/{emoji_id}/image",
             get(routes::emojis::get_emoji_image),
         )
+        .route("/api/v1/emojis/{file}", get(routes::emojis::get_emoji_file))
         .route(
             "/api/v1/guilds/{guild_id}/webhooks",
             get(routes::webhooks::list_guild_webhooks).post(routes::webhooks::create_webhook),
@@ -238,6 +355,10 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/guilds/{guild_id}/bots/{bot_app_id}",
             delete(routes::bots::remove_guild_bot),
         )
+        .route(
+            "/api/v1/guilds/{guild_id}/commands",
+            get(routes::commands::list_guild_commands),
+        )
         .route(
             "/api/v1/guilds/{guild_id}/storage",
             get(routes::guilds::get_storage).patch(routes::guilds::update_storage),
@@ -250,6 +371,10 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/guilds/{guild_id}/audit-logs",
             get(routes::audit_logs::get_audit_logs),
         )
+        .route(
+            "/api/v1/guilds/{guild_id}/audit-logs/export",
+            get(routes::audit_logs::export_audit_logs),
+        )
         // Channels
         .route(
             "/api/v1/channels/{channel_id}",
@@ -261,6 +386,10 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/channels/{channel_id}/messages",
             get(routes::channels::get_messages).post(routes::channels::send_message),
         )
+        .route(
+            "/api/v1/channels/{channel_id}/export",
+            get(routes::channel_export::export_channel),
+        )
         .route(
             "/api/v1/channels/{channel_id}/messages/search",
             get(routes::channels::search_messages),
@@ -273,6 +402,10 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/channels/{channel_id}/messages/{message_id}",
             patch(routes::channels::edit_message).delete(routes::channels::delete_message),
         )
+        .route(
+            "/api/v1/channels/{channel_id}/messages/{message_id}/history",
+            get(routes::channels::get_message_history),
+        )
         .route(
             "/api/v1/channels/{channel_id}/polls",
             post(routes::channels::create_poll),
@@ -314,9 +447,14 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/channels/{channel_id}/messages/{message_id}/reactions/{emoji}/@me",
             put(routes::channels::add_reaction).delete(routes::channels::remove_reaction),
         )
+        .route(
+            "/api/v1/channels/{channel_id}/messages/{message_id}/reactions/{emoji}",
+            get(routes::channels::get_reactions),
+        )
         .route(
             "/api/v1/channels/{channel_id}/webhooks",
-            get(routes::webhooks::list_channel_webhooks),
+            get(routes::webhooks::list_channel_webhooks)
+                .post(routes::webhooks::create_channel_webhook),
         )
         // Threads
         .route(
@@ -331,6 +469,18 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/channels/{channel_id}/threads/{thread_id}",
             patch(routes::channels::update_thread).delete(routes::channels::delete_thread),
         )
+        .route(
+            "/api/v1/channels/{channel_id}/messages/{message_id}/threads",
+            post(routes::channels::create_thread_from_message),
+        )
+        .route(
+            "/api/v1/channels/{channel_id}/thread-members",
+            get(routes::channels::list_thread_members),
+        )
+        .route(
+            "/api/v1/channels/{channel_id}/thread-members/@me",
+            put(routes::channels::join_thread).delete(routes::channels::leave_thread),
+        )
         .route(
             "/api/v1/channels/{channel_id}/forum/posts",
             get(routes::channels::get_forum_posts).post(routes::channels::create_forum_post),
@@ -390,10 +540,27 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/bots/applications/{bot_app_id}/token",
             post(routes::bots::regenerate_bot_token),
         )
+        .route(
+            "/api/v1/applications/{app_id}/token/regenerate",
+            post(routes::bots::regenerate_bot_token),
+        )
         .route(
             "/api/v1/bots/applications/{bot_app_id}/installs",
             get(routes::bots::list_bot_application_installs),
         )
+        .route(
+            "/api/v1/applications/{app_id}/commands",
+            get(routes::commands::list_application_commands)
+                .put(routes::commands::overwrite_application_commands),
+        )
+        .route(
+            "/api/v1/channels/{channel_id}/interactions",
+            post(routes::commands::invoke_command),
+        )
+        .route(
+            "/api/v1/interactions/{interaction_id}/{token}/callback",
+            post(routes::commands::interaction_callback),
+        )
         .route(
             "/api/v1/oauth2/authorize",
             post(routes::bots::oauth2_authorize),
@@ -454,6 +621,20 @@ pub fn build_router() -> Router<AppState> {
             post(routes::files::upload_file)
                 .layer(DefaultBodyLimit::max(ATTACHMENT_REQUEST_BODY_LIMIT_BYTES)),
         )
+        .route(
+            "/api/v1/channels/{channel_id}/attachments/init",
+            post(routes::files::init_chunked_upload),
+        )
+        .route(
+            "/api/v1/channels/{channel_id}/attachments/{session_id}",
+            get(routes::files::get_chunked_upload)
+                .patch(routes::files::upload_chunk)
+                .layer(DefaultBodyLimit::max(ATTACHMENT_REQUEST_BODY_LIMIT_BYTES)),
+        )
+        .route(
+            "/api/v1/channels/{channel_id}/attachments/{session_id}/complete",
+            post(routes::files::complete_chunked_upload),
+        )
         .route(
             "/api/v1/attachments/{id}",
             get(routes::files::download_file).delete(routes::files::delete_file),
@@ -489,6 +670,10 @@ pub fn build_router() -> Router<AppState> {
             get(routes::admin::get_settings).patch(routes::admin::update_settings),
         )
         .route("/api/v1/admin/users", get(routes::admin::list_users))
+        .route(
+            "/api/v1/admin/users/bulk",
+            post(routes::admin::bulk_user_action),
+        )
         .route(
             "/api/v1/admin/users/{user_id}",
             patch(routes::admin::update_user).delete(routes::admin::delete_user),
@@ -517,16 +702,20 @@ pub fn build_router() -> Router<AppState> {
         )
         // Middleware layers
         .layer(DefaultBodyLimit::max(DEFAULT_REQUEST_BODY_LIMIT_BYTES))
+        .layer(from_fn(error_body_middleware))
         .layer(from_fn(metrics_middleware))
         .layer(from_fn(rate_limit_middleware))
+        .layer(from_fn(client_ip::client_ip_middleware))
         .layer(from_fn(security_headers_middleware))
         .layer(cors)
         .layer(
             tower_http::trace::TraceLayer::new_for_http()
                 .make_span_with(|request: &Request| {
-                    let req_id = HTTP_TRACE_REQUEST_ID
-                        .fetch_add(1, Ordering::Relaxed)
-                        .saturating_add(1);
+                    let request_id = request
+                        .extensions()
+                        .get::<request_id::RequestId>()
+                        .map(|id| id.0.as_str())
+                        .unwrap_or_default();
                     let matched_path = request
                         .extensions()
                         .get::<axum::extract::MatchedPath>()
@@ -534,7 +723,7 @@ pub fn build_router() -> Router<AppState> {
                         .unwrap_or_else(|| request.uri().path());
                     tracing::info_span!(
                         "http",
-                        req_id,
+                        request_id,
                         method = %request.method(),
                         path = %matched_path
                     )
@@ -592,6 +781,88 @@ pub fn build_router() -> Router<AppState> {
                     }
                 }),
         )
+        // Outermost, so the trace span above can read the request id.
+        .layer(from_fn(request_id::request_id_middleware))
+}
+
+/// How long the CORS layer reuses its snapshot of the admin-configured
+/// origins before reading the runtime settings again.
+const CORS_ORIGINS_TTL: Duration = Duration::from_secs(5);
+
+static CORS_RUNTIME_SETTINGS: OnceLock<Arc<tokio::sync::RwLock<RuntimeSettings>>> =
+    OnceLock::new();
+
+/// Let the CORS layer pick up origins added through the admin settings.
+pub fn install_cors_runtime_settings(runtime: Arc<tokio::sync::RwLock<RuntimeSettings>>) {
+    let _ = CORS_RUNTIME_SETTINGS.set(runtime);
+}
+
+/// Origins the CORS layer accepts: the built-in desktop/dev origins and the
+/// environment, fixed at startup, plus `cors_allowed_origins` from the
+/// runtime settings, re-read every [`CORS_ORIGINS_TTL`].
+struct CorsOrigins {
+    fixed: std::collections::BTreeSet<String>,
+    runtime: Mutex<(Option<Instant>, Arc<std::collections::BTreeSet<String>>)>,
+}
+
+static CORS_ORIGINS: OnceLock<CorsOrigins> = OnceLock::new();
+
+impl CorsOrigins {
+    fn allows(&self, origin: &str) -> bool {
+        let origin = normalize_origin(origin);
+        self.fixed.contains(&origin) || self.runtime_origins().contains(&origin)
+    }
+
+    fn runtime_origins(&self) -> Arc<std::collections::BTreeSet<String>> {
+        let mut cached = match self.runtime.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if cached
+            .0
+            .is_some_and(|loaded_at| loaded_at.elapsed() < CORS_ORIGINS_TTL)
+        {
+            return cached.1.clone();
+        }
+        // Never wait on the settings lock from the request path; a writer
+        // holds it only briefly, so the next request refreshes instead.
+        if let Some(settings) = CORS_RUNTIME_SETTINGS
+            .get()
+            .and_then(|runtime| runtime.try_read().ok())
+        {
+            *cached = (
+                Some(Instant::now()),
+                Arc::new(
+                    settings
+                        .cors_allowed_origins
+                        .iter()
+                        .map(|origin| normalize_origin(origin))
+                        .collect(),
+                ),
+            );
+        }
+        cached.1.clone()
+    }
+
+    fn invalidate(&self) {
+        let mut cached = match self.runtime.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        cached.0 = None;
+    }
+}
+
+/// Drop the cached runtime origins so a settings change applies to the
+/// next request.
+pub(crate) fn invalidate_cors_origins() {
+    if let Some(origins) = CORS_ORIGINS.get() {
+        origins.invalidate();
+    }
+}
+
+fn normalize_origin(origin: &str) -> String {
+    origin.trim().trim_end_matches('/').to_ascii_lowercase()
 }
 
 fn build_cors_layer() -> tower_http::cors::CorsLayer {
@@ -635,7 +906,9 @@ fn build_cors_layer() -> tower_http::cors::CorsLayer {
             header::CONTENT_TYPE,
             header::ACCEPT,
             header::ORIGIN,
+            HeaderName::from_static(request_id::REQUEST_ID_HEADER),
         ])
+        .expose_headers([HeaderName::from_static(request_id::REQUEST_ID_HEADER)])
         .max_age(Duration::from_secs(600));
 
     if allow_any {
@@ -646,11 +919,20 @@ fn build_cors_layer() -> tower_http::cors::CorsLayer {
             .allow_origin(tower_http::cors::Any)
             .allow_credentials(false);
     } else {
-        let values: Vec<HeaderValue> = allowed_origins
-            .into_iter()
-            .filter_map(|origin| HeaderValue::from_str(&origin).ok())
-            .collect();
-        cors = cors.allow_origin(values).allow_credentials(true);
+        let origins = CORS_ORIGINS.get_or_init(|| CorsOrigins {
+            fixed: allowed_origins
+                .iter()
+                .map(|origin| normalize_origin(origin))
+                .collect(),
+            runtime: Mutex::new((None, Arc::new(Default::default()))),
+        });
+        cors = cors
+            .allow_origin(tower_http::cors::AllowOrigin::predicate(
+                move |origin: &HeaderValue, _parts| {
+                    origin.to_str().is_ok_and(|origin| origins.allows(origin))
+                },
+            ))
+            .allow_credentials(true);
     }
 
     cors
@@ -663,7 +945,90 @@ async fn health() -> impl IntoResponse {
     )
 }
 
-async fn metrics(headers: HeaderMap) -> impl IntoResponse {
+/// Content limits enforced by this server, so clients can validate before
+/// sending. Message length is counted in grapheme clusters.
+async fn limits(State(state): State<AppState>) -> Json<serde_json::Value> {
+    Json(json!({
+        "max_message_length": state.config.max_message_length,
+        "max_attachments_per_message": state.config.max_attachments_per_message,
+        "max_upload_size": state.config.max_upload_size,
+        "max_pins_per_channel": state.config.max_pins_per_channel,
+    }))
+}
+
+/// Readiness probe for load balancers. Unlike `/health` (liveness), this
+/// checks the database and, when voice is backed by LiveKit, that LiveKit is
+/// reachable; any unhealthy component turns the response into a 503.
+async fn health_ready(State(state): State<AppState>) -> Response {
+    let stats = paracord_db::pool_stats(&state.db);
+    let pool = json!({
+        "open": stats.open,
+        "idle": stats.idle,
+        "in_use": stats.in_use(),
+        "min": stats.min,
+        "max": stats.max,
+        "saturated": stats.saturated(),
+    });
+    let started = Instant::now();
+    let database =
+        match tokio::time::timeout(READINESS_DB_TIMEOUT, paracord_db::ping(&state.db)).await {
+            Ok(Ok(())) => json!({
+                "status": "ok",
+                "latency_ms": started.elapsed().as_millis() as u64,
+                "pool": pool,
+            }),
+            Ok(Err(err)) => {
+                tracing::warn!("Readiness probe: database check failed: {}", err);
+                json!({ "status": "unavailable", "pool": pool })
+            }
+            Err(_) => {
+                tracing::warn!(
+                    "Readiness probe: database did not answer within {}ms",
+                    READINESS_DB_TIMEOUT.as_millis()
+                );
+                json!({ "status": "timeout", "pool": pool })
+            }
+        };
+
+    let livekit = if !state.config.livekit_available {
+        json!({ "status": "disabled" })
+    } else {
+        match routes::livekit_proxy::probe_livekit_backend(&state.config.livekit_http_url).await {
+            Ok(latency) => json!({
+                "status": "ok",
+                "latency_ms": latency.as_millis() as u64,
+            }),
+            Err(err) => {
+                tracing::warn!("Readiness probe: LiveKit check failed: {}", err);
+                json!({ "status": "unavailable" })
+            }
+        }
+    };
+
+    let healthy = |component: &serde_json::Value| {
+        matches!(component["status"].as_str(), Some("ok" | "disabled"))
+    };
+    let ready = healthy(&database) && healthy(&livekit);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(json!({
+            "status": if ready { "ok" } else { "unavailable" },
+            "service": "paracord",
+            "components": {
+                "database": database,
+                "livekit": livekit,
+            },
+        })),
+    )
+        .into_response()
+}
+
+async fn metrics(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
     let public_metrics = std::env::var("PARACORD_ENABLE_PUBLIC_METRICS")
         .ok()
         .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
@@ -695,74 +1060,20 @@ async fn metrics(headers: HeaderMap) -> impl IntoResponse {
         }
     }
 
-    let requests = REQUEST_COUNT.load(Ordering::Relaxed);
-    let limited = RATE_LIMITED_COUNT.load(Ordering::Relaxed);
-
-    let s2xx = STATUS_2XX.load(Ordering::Relaxed);
-    let s4xx = STATUS_4XX.load(Ordering::Relaxed);
-    let s5xx = STATUS_5XX.load(Ordering::Relaxed);
-
-    let ws_snapshot = paracord_core::observability::ws_metrics_snapshot();
-    let ws_active = ws_snapshot.active_connections;
-    let ws_events = ws_snapshot.total_events;
-
-    let dur_sum_us = DURATION_SUM_US.load(Ordering::Relaxed);
-    let dur_count = DURATION_COUNT.load(Ordering::Relaxed);
-    let dur_sum_s = dur_sum_us as f64 / 1_000_000.0;
-
-    let mut body = format!(
-        "# HELP paracord_up Whether the server is up.\n\
-         # TYPE paracord_up gauge\n\
-         paracord_up 1\n\
-         # HELP paracord_http_requests_total Total HTTP requests.\n\
-         # TYPE paracord_http_requests_total counter\n\
-         paracord_http_requests_total {requests}\n\
-         # HELP paracord_http_rate_limited_total Requests rejected by rate limiter.\n\
-         # TYPE paracord_http_rate_limited_total counter\n\
-         paracord_http_rate_limited_total {limited}\n\
-         # HELP paracord_http_responses_total HTTP responses by status class.\n\
-         # TYPE paracord_http_responses_total counter\n\
-         paracord_http_responses_total{{status_class=\"2xx\"}} {s2xx}\n\
-         paracord_http_responses_total{{status_class=\"4xx\"}} {s4xx}\n\
-         paracord_http_responses_total{{status_class=\"5xx\"}} {s5xx}\n\
-         # HELP paracord_http_request_duration_seconds HTTP request duration histogram.\n\
-         # TYPE paracord_http_request_duration_seconds histogram\n\
-         paracord_http_request_duration_seconds_bucket{{le=\"0.005\"}} {}\n\
-         paracord_http_request_duration_seconds_bucket{{le=\"0.01\"}} {}\n\
-         paracord_http_request_duration_seconds_bucket{{le=\"0.025\"}} {}\n\
-         paracord_http_request_duration_seconds_bucket{{le=\"0.05\"}} {}\n\
-         paracord_http_request_duration_seconds_bucket{{le=\"0.1\"}} {}\n\
-         paracord_http_request_duration_seconds_bucket{{le=\"0.25\"}} {}\n\
-         paracord_http_request_duration_seconds_bucket{{le=\"0.5\"}} {}\n\
-         paracord_http_request_duration_seconds_bucket{{le=\"1.0\"}} {}\n\
-         paracord_http_request_duration_seconds_bucket{{le=\"+Inf\"}} {}\n\
-         paracord_http_request_duration_seconds_sum {dur_sum_s}\n\
-         paracord_http_request_duration_seconds_count {dur_count}\n\
-         # HELP paracord_ws_connections_active Active WebSocket gateway connections.\n\
-         # TYPE paracord_ws_connections_active gauge\n\
-         paracord_ws_connections_active {ws_active}\n\
-         # HELP paracord_ws_events_total Total WebSocket events dispatched.\n\
-         # TYPE paracord_ws_events_total counter\n\
-         paracord_ws_events_total {ws_events}\n\
-         # HELP paracord_ws_events_by_type_total Total WebSocket events dispatched by event type.\n\
-         # TYPE paracord_ws_events_by_type_total counter\n",
-        DURATION_LE_5.load(Ordering::Relaxed),
-        DURATION_LE_10.load(Ordering::Relaxed),
-        DURATION_LE_25.load(Ordering::Relaxed),
-        DURATION_LE_50.load(Ordering::Relaxed),
-        DURATION_LE_100.load(Ordering::Relaxed),
-        DURATION_LE_250.load(Ordering::Relaxed),
-        DURATION_LE_500.load(Ordering::Relaxed),
-        DURATION_LE_1000.load(Ordering::Relaxed),
-        DURATION_LE_INF.load(Ordering::Relaxed),
-    );
-    for (event_type, count) in ws_snapshot.events_by_type {
-        body.push_str(&format!(
-            "paracord_ws_events_by_type_total{{event_type=\"{}\"}} {}\n",
-            prometheus_escape_label_value(&event_type),
-            count
-        ));
+    if let Some(limiter) = HTTP_RATE_LIMITER.get() {
+        for category in RouteCategory::ALL {
+            let limit = limiter.limits.get(category);
+            observability::set_http_rate_limit(
+                category.as_str(),
+                limit.max_requests,
+                limit.window_seconds,
+            );
+        }
+        observability::set_http_rate_limit_tracked_keys(limiter.buckets.len());
     }
+    let pool = paracord_db::pool_stats(&state.db);
+    observability::set_db_pool_stats(pool.open, pool.idle, pool.max);
+    let body = observability::render_prometheus();
 
     (
         StatusCode::OK,
@@ -771,138 +1082,118 @@ async fn metrics(headers: HeaderMap) -> impl IntoResponse {
     )
 }
 
-struct RateBucket {
-    count: u32,
-    window_start: i64,
+/// Outcome of charging one request against a bucket.
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Unix timestamp (seconds) at which the bucket is full again.
+    pub reset_at: i64,
+    /// Seconds until the next request would be admitted; zero if allowed.
+    pub retry_after: i64,
 }
 
 pub struct HttpRateLimiter {
-    buckets: DashMap<String, Mutex<RateBucket>>,
+    buckets: DashMap<String, TokenBucket>,
+    limits: HttpRateLimits,
 }
 
 impl HttpRateLimiter {
-    fn new() -> Self {
+    fn new(limits: HttpRateLimits) -> Self {
         Self {
             buckets: DashMap::new(),
+            limits,
         }
     }
 
-    fn check_rate_limit(&self, key: &str, window_seconds: i64, max_count: u32) -> bool {
-        let now = chrono::Utc::now().timestamp();
-        let bucket = self.buckets.entry(key.to_string()).or_insert_with(|| {
-            Mutex::new(RateBucket {
-                count: 0,
-                window_start: now,
-            })
-        });
-        let mut guard = match bucket.lock() {
-            Ok(guard) => guard,
-            Err(poisoned) => poisoned.into_inner(),
-        };
-        if now.saturating_sub(guard.window_start) >= window_seconds {
-            guard.window_start = now;
-            guard.count = 0;
+    fn check_rate_limit(&self, key: &str, limit: RouteLimit) -> RateLimitDecision {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        // Checked before taking the entry: `len` locks every shard, including
+        // the one the entry would hold.
+        if !self.buckets.contains_key(key) && self.buckets.len() >= self.limits.max_tracked_keys {
+            self.evict_least_recent();
+        }
+        let check = self
+            .buckets
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket::full(limit, now_ms))
+            .try_take(limit, now_ms);
+        let now = now_ms.div_euclid(1000);
+        RateLimitDecision {
+            allowed: check.allowed,
+            limit: limit.max_requests,
+            remaining: check.remaining,
+            reset_at: now.saturating_add((check.full_after_ms + 999) / 1000),
+            retry_after: (check.retry_after_ms + 999) / 1000,
         }
-        guard.count = guard.count.saturating_add(1);
-        guard.count <= max_count
     }
 
-    fn cleanup_stale(&self, max_age_seconds: i64) {
-        let now = chrono::Utc::now().timestamp();
-        self.buckets.retain(|_, bucket| {
-            let guard = match bucket.lock() {
-                Ok(guard) => guard,
-                Err(poisoned) => poisoned.into_inner(),
-            };
-            now.saturating_sub(guard.window_start) <= max_age_seconds
-        });
+    /// Drop buckets idle for longer than the longest window. They have
+    /// refilled completely, so forgetting them changes nothing.
+    fn cleanup_stale(&self) {
+        let cutoff = chrono::Utc::now().timestamp_millis()
+            - self.limits.longest_window_seconds().saturating_mul(1000);
+        let before = self.buckets.len();
+        self.buckets
+            .retain(|_, bucket| bucket.updated_ms() >= cutoff);
+        let after = self.buckets.len();
+        observability::http_rate_limit_evicted("idle", before.saturating_sub(after));
+        observability::set_http_rate_limit_tracked_keys(after);
     }
-}
-
-static HTTP_RATE_LIMITER: OnceLock<HttpRateLimiter> = OnceLock::new();
-static HTTP_TRACE_REQUEST_ID: AtomicU64 = AtomicU64::new(0);
-static REQUEST_COUNT: AtomicU64 = AtomicU64::new(0);
-static RATE_LIMITED_COUNT: AtomicU64 = AtomicU64::new(0);
 
-// ── Observability: request duration histogram buckets ──────────────────────
-// We track durations in discrete buckets (in milliseconds) using atomics.
-static DURATION_LE_5: AtomicU64 = AtomicU64::new(0);
-static DURATION_LE_10: AtomicU64 = AtomicU64::new(0);
-static DURATION_LE_25: AtomicU64 = AtomicU64::new(0);
-static DURATION_LE_50: AtomicU64 = AtomicU64::new(0);
-static DURATION_LE_100: AtomicU64 = AtomicU64::new(0);
-static DURATION_LE_250: AtomicU64 = AtomicU64::new(0);
-static DURATION_LE_500: AtomicU64 = AtomicU64::new(0);
-static DURATION_LE_1000: AtomicU64 = AtomicU64::new(0);
-static DURATION_LE_INF: AtomicU64 = AtomicU64::new(0);
-static DURATION_SUM_US: AtomicU64 = AtomicU64::new(0);
-static DURATION_COUNT: AtomicU64 = AtomicU64::new(0);
-
-// ── Observability: HTTP status code counters ───────────────────────────────
-static STATUS_2XX: AtomicU64 = AtomicU64::new(0);
-static STATUS_4XX: AtomicU64 = AtomicU64::new(0);
-static STATUS_5XX: AtomicU64 = AtomicU64::new(0);
-
-fn prometheus_escape_label_value(value: &str) -> String {
-    let mut escaped = String::with_capacity(value.len());
-    for ch in value.chars() {
-        match ch {
-            '\\' => escaped.push_str("\\\\"),
-            '"' => escaped.push_str("\\\""),
-            '\n' => escaped.push_str("\\n"),
-            _ => escaped.push(ch),
+    /// Make room at capacity by dropping the least recently used tenth of
+    /// the buckets, so a flood of new client keys cannot grow memory without
+    /// bound.
+    fn evict_least_recent(&self) {
+        let mut last_used: Vec<i64> = self
+            .buckets
+            .iter()
+            .map(|bucket| bucket.updated_ms())
+            .collect();
+        if last_used.is_empty() {
+            return;
         }
+        let evict = (self.limits.max_tracked_keys / 10).clamp(1, last_used.len());
+        let (_, cutoff, _) = last_used.select_nth_unstable(evict - 1);
+        let cutoff = *cutoff;
+        let before = self.buckets.len();
+        self.buckets
+            .retain(|_, bucket| bucket.updated_ms() > cutoff);
+        let after = self.buckets.len();
+        observability::http_rate_limit_evicted("capacity", before.saturating_sub(after));
+        observability::set_http_rate_limit_tracked_keys(after);
     }
-    escaped
 }
 
-fn record_request_duration(elapsed_ms: u64) {
-    if elapsed_ms <= 5 {
-        DURATION_LE_5.fetch_add(1, Ordering::Relaxed);
-    }
-    if elapsed_ms <= 10 {
-        DURATION_LE_10.fetch_add(1, Ordering::Relaxed);
-    }
-    if elapsed_ms <= 25 {
-        DURATION_LE_25.fetch_add(1, Ordering::Relaxed);
-    }
-    if elapsed_ms <= 50 {
-        DURATION_LE_50.fetch_add(1, Ordering::Relaxed);
-    }
-    if elapsed_ms <= 100 {
-        DURATION_LE_100.fetch_add(1, Ordering::Relaxed);
-    }
-    if elapsed_ms <= 250 {
-        DURATION_LE_250.fetch_add(1, Ordering::Relaxed);
-    }
-    if elapsed_ms <= 500 {
-        DURATION_LE_500.fetch_add(1, Ordering::Relaxed);
-    }
-    if elapsed_ms <= 1000 {
-        DURATION_LE_1000.fetch_add(1, Ordering::Relaxed);
-    }
-    DURATION_LE_INF.fetch_add(1, Ordering::Relaxed);
-    DURATION_SUM_US.fetch_add(elapsed_ms.saturating_mul(1000), Ordering::Relaxed);
-    DURATION_COUNT.fetch_add(1, Ordering::Relaxed);
-}
+static HTTP_RATE_LIMITER: OnceLock<HttpRateLimiter> = OnceLock::new();
 
-fn record_status_code(status: u16) {
-    match status {
-        200..=299 => {
-            STATUS_2XX.fetch_add(1, Ordering::Relaxed);
+/// Classify a request for rate limiting from its method and matched route
+/// pattern (e.g. `/api/v1/channels/{channel_id}/messages`).
+fn classify_route(method: &Method, route: &str) -> RouteCategory {
+    if *method == Method::POST {
+        match route {
+            "/api/v1/auth/login"
+            | "/api/v1/auth/register"
+            | "/api/v1/auth/mfa"
+            | "/api/v1/auth/challenge"
+            | "/api/v1/auth/verify" => return RouteCategory::Auth,
+            "/api/v1/channels/{channel_id}/messages"
+            | "/api/v1/channels/{channel_id}/interactions"
+            | "/api/v1/interactions/{interaction_id}/{token}/callback"
+            | "/api/v1/webhooks/{webhook_id}/{token}" => return RouteCategory::MessageSend,
+            _ => {}
         }
-        400..=499 => {
-            STATUS_4XX.fetch_add(1, Ordering::Relaxed);
-        }
-        500..=599 => {
-            STATUS_5XX.fetch_add(1, Ordering::Relaxed);
-        }
-        _ => {}
+    }
+    if *method == Method::GET || *method == Method::HEAD {
+        RouteCategory::Read
+    } else {
+        RouteCategory::Write
     }
 }
 
-pub fn install_http_rate_limiter() {
-    let _ = HTTP_RATE_LIMITER.set(HttpRateLimiter::new());
+pub fn install_http_rate_limiter(limits: HttpRateLimits) {
+    let _ = HTTP_RATE_LIMITER.set(HttpRateLimiter::new(limits));
 }
 
 pub fn spawn_http_rate_limiter_cleanup(shutdown: Arc<Notify>) {
@@ -913,8 +1204,18 @@ pub fn spawn_http_rate_limiter_cleanup(shutdown: Arc<Notify>) {
                 _ = shutdown.notified() => break,
                 _ = ticker.tick() => {
                     if let Some(limiter) = HTTP_RATE_LIMITER.get() {
-                        limiter.cleanup_stale(600);
+                        limiter.cleanup_stale();
                     }
+                    let now_ms = chrono::Utc::now().timestamp_millis();
+                    paracord_core::rate_limit::slowmode().prune(now_ms);
+                    paracord_core::rate_limit::webhooks().prune(
+                        now_ms,
+                        paracord_core::rate_limit::WEBHOOK_EXECUTE_LIMIT.window_seconds,
+                    );
+                    paracord_core::rate_limit::system_messages().prune(
+                        now_ms,
+                        paracord_core::system_messages::ANNOUNCEMENT_LIMIT.window_seconds,
+                    );
                 }
             }
         }
@@ -922,10 +1223,6 @@ pub fn spawn_http_rate_limiter_cleanup(shutdown: Arc<Notify>) {
 }
 
 async fn rate_limit_middleware(req: Request, next: Next) -> Response {
-    const GLOBAL_LIMIT_PER_SECOND: u32 = 120;
-    const AUTH_LIMIT_PER_MINUTE: u32 = 60;
-    const BOT_LIMIT_PER_MINUTE: u32 = 300;
-
     if req.method() == Method::OPTIONS {
         return next.run(req).await;
     }
@@ -938,56 +1235,27 @@ async fn rate_limit_middleware(req: Request, next: Next) -> Response {
         return next.run(req).await;
     }
 
-    REQUEST_COUNT.fetch_add(1, Ordering::Relaxed);
-    let is_auth_path = path.starts_with("/api/v1/auth/");
-    let trust_proxy = std::env::var("PARACORD_TRUST_PROXY")
-        .ok()
-        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
-        .unwrap_or(false);
-    let peer_ip = req
+    let route = req
         .extensions()
-        .get::<ConnectInfo<std::net::SocketAddr>>()
-        .map(|info| info.0.ip().to_string());
-    let trusted_proxy_ips = if trust_proxy {
-        std::env::var("PARACORD_TRUSTED_PROXY_IPS")
-            .ok()
-            .map(|raw| {
-                raw.split(',')
-                    .map(str::trim)
-                    .filter(|v| !v.is_empty())
-                    .map(str::to_string)
-                    .collect::<Vec<_>>()
-            })
-            .unwrap_or_default()
-    } else {
-        Vec::new()
-    };
-    let can_trust_forwarded = trust_proxy
-        && peer_ip
-            .as_deref()
-            .is_some_and(|ip| trusted_proxy_ips.iter().any(|trusted| trusted == ip));
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or(path.as_str());
+    let category = classify_route(req.method(), route);
+    let key = req
+        .extensions()
+        .get::<client_ip::ClientIp>()
+        .map(|ip| ip.0.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
 
-    let key = if can_trust_forwarded {
-        req.headers()
-            .get("x-forwarded-for")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.split(',').next())
-            .map(str::trim)
-            .filter(|s| !s.is_empty())
-            .map(str::to_string)
-            .or_else(|| peer_ip.clone())
-            .unwrap_or_else(|| "unknown".to_string())
-    } else {
-        peer_ip.unwrap_or_else(|| "unknown".to_string())
+    let Some(limiter) = HTTP_RATE_LIMITER.get() else {
+        return next.run(req).await;
     };
 
-    if let Some(limiter) = HTTP_RATE_LIMITER.get() {
-        let global_key = format!("http:global:{key}");
-        if !limiter.check_rate_limit(&global_key, 1, GLOBAL_LIMIT_PER_SECOND) {
-            RATE_LIMITED_COUNT.fetch_add(1, Ordering::Relaxed);
-            return crate::error::ApiError::RateLimited.into_response();
-        }
+    let category_key = format!("http:{}:{key}", category.as_str());
+    let mut decision = limiter.check_rate_limit(&category_key, limiter.limits.get(category));
+    observability::http_rate_limit_checked(category.as_str(), decision.remaining, decision.allowed);
 
+    if decision.allowed {
         if let Some(bot_token) = req
             .headers()
             .get(header::AUTHORIZATION)
@@ -998,22 +1266,47 @@ async fn rate_limit_middleware(req: Request, next: Next) -> Response {
         {
             let token_hash = paracord_db::bot_applications::hash_token(bot_token);
             let bot_key = format!("http:bot:{}", &token_hash[..24]);
-            if !limiter.check_rate_limit(&bot_key, 60, BOT_LIMIT_PER_MINUTE) {
-                RATE_LIMITED_COUNT.fetch_add(1, Ordering::Relaxed);
-                return crate::error::ApiError::RateLimited.into_response();
+            let bot_decision = limiter.check_rate_limit(&bot_key, limiter.limits.bot);
+            // Report whichever bucket is closer to exhaustion.
+            if !bot_decision.allowed || bot_decision.remaining < decision.remaining {
+                decision = bot_decision;
             }
         }
+    }
 
-        if is_auth_path {
-            let auth_key = format!("http:auth:{key}");
-            if !limiter.check_rate_limit(&auth_key, 60, AUTH_LIMIT_PER_MINUTE) {
-                RATE_LIMITED_COUNT.fetch_add(1, Ordering::Relaxed);
-                return crate::error::ApiError::RateLimited.into_response();
-            }
-        }
+    if !decision.allowed {
+        let mut response = crate::error::ApiError::RateLimited.into_response();
+        apply_rate_limit_headers(response.headers_mut(), &decision, true);
+        return response;
     }
 
-    next.run(req).await
+    let mut response = next.run(req).await;
+    apply_rate_limit_headers(response.headers_mut(), &decision, false);
+    response
+}
+
+/// Decorate a response with the client's current rate limit state so
+/// well-behaved clients can pace themselves.
+fn apply_rate_limit_headers(headers: &mut HeaderMap, decision: &RateLimitDecision, limited: bool) {
+    headers.insert(
+        HeaderName::from_static("x-ratelimit-limit"),
+        HeaderValue::from(decision.limit),
+    );
+    headers.insert(
+        HeaderName::from_static("x-ratelimit-remaining"),
+        HeaderValue::from(decision.remaining),
+    );
+    headers.insert(
+        HeaderName::from_static("x-ratelimit-reset"),
+        HeaderValue::from(decision.reset_at),
+    );
+    if limited {
+        // Seconds until the next token, never less than one.
+        headers.insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(decision.retry_after.max(1)),
+        );
+    }
 }
 
 async fn security_headers_middleware(req: Request, next: Next) -> Response {
@@ -1049,6 +1342,7 @@ async fn security_headers_middleware(req: Request, next: Next) -> Response {
         HeaderValue::from_static("same-origin"),
     );
     if path == "/health"
+        || path.starts_with("/health/")
         || path == "/metrics"
         || path.starts_with("/api/")
         || path.starts_with("/_paracord/")
@@ -1076,12 +1370,56 @@ async fn security_headers_middleware(req: Request, next: Next) -> Response {
     response
 }
 
-/// Middleware that records request duration and response status for the /metrics endpoint.
+/// Middleware that gives API errors produced outside `ApiError` (extractor
+/// rejections, unmatched routes, wrong methods) the same
+/// `{code, message, details}` JSON body.
+async fn error_body_middleware(req: Request, next: Next) -> Response {
+    let rewrite = req.uri().path().starts_with("/api/") && req.method() != Method::HEAD;
+    let response = next.run(req).await;
+    let status = response.status();
+    if !rewrite || !(status.is_client_error() || status.is_server_error()) {
+        return response;
+    }
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let message = match axum::body::to_bytes(body, ERROR_BODY_REWRITE_LIMIT_BYTES).await {
+        Ok(bytes) if !bytes.trim_ascii().is_empty() => {
+            String::from_utf8_lossy(bytes.trim_ascii()).into_owned()
+        }
+        _ => status
+            .canonical_reason()
+            .unwrap_or("request failed")
+            .to_ascii_lowercase(),
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.remove(header::CONTENT_TYPE);
+    let body = error::error_body(error::code_for_status(status), &message);
+    (parts, Json(body)).into_response()
+}
+
+/// Middleware that records request counts and latency, labeled by method,
+/// matched route and status, for the /metrics endpoint.
 async fn metrics_middleware(req: Request, next: Next) -> Response {
+    let method = req.method().clone();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string());
     let start = Instant::now();
     let response = next.run(req).await;
-    let elapsed_ms = start.elapsed().as_millis() as u64;
-    record_request_duration(elapsed_ms);
-    record_status_code(response.status().as_u16());
+    observability::http_request_completed(
+        method.as_str(),
+        route.as_deref().unwrap_or(observability::UNMATCHED_ROUTE),
+        response.status().as_u16(),
+        start.elapsed(),
+    );
     response
 }
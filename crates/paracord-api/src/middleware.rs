@@ -70,7 +70,7 @@ async fn validate_auth(
             .ok_or(ApiError::Unauthorized)?,
     };
 
-    let claims = paracord_core::auth::validate_token(&token, &state.config.jwt_secret)
+    let claims = paracord_core::auth::validate_token(&token, &state.config.jwt_keys)
         .map_err(|_| ApiError::Unauthorized)?;
 
     let (session_id, jti) = match (claims.sid.as_deref(), claims.jti.as_deref()) {
@@ -95,6 +95,10 @@ async fn validate_auth(
 }
 
 /// Validate a "Bot <token>" header by looking up the token hash in bot_applications.
+///
+/// The resulting `AuthUser` is the bot user. What it may do in a space is the
+/// intersection of its roles and the application's permission scope, which
+/// `paracord_core::permissions` applies whenever it computes permissions.
 async fn validate_bot_auth(parts: &Parts, state: &AppState) -> Result<i64, ApiError> {
     let token = match extract_auth_scheme(parts) {
         Some(AuthScheme::Bot(t)) => t,
@@ -0,0 +1,59 @@
+//! `X-Request-Id` propagation. Every request gets an id (the client's own if
+//! it sent a sane one) that is recorded on the request's tracing span, echoed
+//! back in the response and included in error bodies, so a client-side error
+//! can be matched to the server logs for that request.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Longer client-supplied ids are replaced rather than logged.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The id assigned to a request, available as a request extension.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// Id of the request being handled on the current task, if any.
+pub fn current() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+fn is_acceptable_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
+
+/// Middleware that reads or generates the request id. It must wrap the trace
+/// layer so the span can pick the id up from the request extensions.
+pub async fn request_id_middleware(mut req: Request, next: Next) -> Response {
+    let header_name = HeaderName::from_static(REQUEST_ID_HEADER);
+    let id = req
+        .headers()
+        .get(&header_name)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| is_acceptable_request_id(v))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let header_value = HeaderValue::from_str(&id).expect("request ids are visible ASCII");
+
+    // Overwrite the inbound header so proxied requests carry the final id.
+    req.headers_mut()
+        .insert(header_name.clone(), header_value.clone());
+    req.extensions_mut().insert(RequestId(id.clone()));
+
+    let mut response = CURRENT_REQUEST_ID.scope(id, next.run(req)).await;
+    response.headers_mut().insert(header_name, header_value);
+    response
+}
@@ -12,7 +12,7 @@ use tokio_util::io::ReaderStream;
 
 use crate::error::ApiError;
 use crate::middleware::AdminUser;
-use crate::routes::security;
+use crate::routes::{files, security};
 
 // ── Restart & Update ─────────────────────────────────────────────────
 
@@ -43,11 +43,26 @@ pub async fn get_stats(
     _admin: AdminUser,
 ) -> Result<Json<Value>, ApiError> {
     let stats = paracord_core::admin::get_server_stats(&state.db).await?;
+    let livekit = paracord_core::observability::livekit_status_snapshot();
+    let since_ms = chrono::Utc::now().timestamp_millis() - 86_400_000;
+    let outbound = paracord_db::federation::outbound_queue_stats(&state.db, since_ms)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
     Ok(Json(json!({
         "total_users": stats.total_users,
         "total_guilds": stats.total_guilds,
         "total_messages": stats.total_messages,
         "total_channels": stats.total_channels,
+        "livekit": {
+            "status": livekit.status.as_str(),
+            "restarts": livekit.restarts,
+        },
+        "federation": {
+            "outbound_queue_depth": outbound.queued,
+            "outbound_retrying": outbound.retrying,
+            "outbound_dead_lettered": outbound.dead_lettered,
+            "outbound_failures_24h": outbound.failed_attempts,
+        },
     })))
 }
 
@@ -135,37 +150,67 @@ pub async fn get_settings(
     .ok()
     .flatten()
     .unwrap_or_else(|| state.config.federation_file_cache_ttl_hours.to_string());
+    let audit_log_retention_days = paracord_db::server_settings::get_setting(
+        &state.db,
+        "audit_log_retention_days",
+    )
+    .await
+    .ok()
+    .flatten()
+    .unwrap_or_else(|| retention_days_setting(state.config.audit_log_retention_days));
+    let security_event_retention_days = paracord_db::server_settings::get_setting(
+        &state.db,
+        "security_event_retention_days",
+    )
+    .await
+    .ok()
+    .flatten()
+    .unwrap_or_else(|| retention_days_setting(state.config.security_event_retention_days));
 
     Ok(Json(json!({
         "registration_enabled": settings.registration_enabled.to_string(),
+        "require_email_verification": settings.require_email_verification.to_string(),
         "server_name": settings.server_name,
         "server_description": settings.server_description,
         "max_guilds_per_user": settings.max_guilds_per_user.to_string(),
         "max_members_per_guild": settings.max_members_per_guild.to_string(),
+        "cors_allowed_origins": settings.cors_allowed_origins.join(","),
         "max_guild_storage_quota": max_guild_storage_quota,
         "federation_file_cache_enabled": federation_file_cache_enabled,
         "federation_file_cache_max_size": federation_file_cache_max_size,
         "federation_file_cache_ttl_hours": federation_file_cache_ttl_hours,
+        "audit_log_retention_days": audit_log_retention_days,
+        "security_event_retention_days": security_event_retention_days,
     })))
 }
 
+/// Settings-string form of a retention window; "0" means keep forever.
+fn retention_days_setting(days: Option<i64>) -> String {
+    days.filter(|d| *d > 0).unwrap_or(0).to_string()
+}
+
 const ALLOWED_SETTINGS: &[&str] = &[
     "registration_enabled",
+    "require_email_verification",
     "server_name",
     "server_description",
     "max_guilds_per_user",
     "max_members_per_guild",
+    "cors_allowed_origins",
     "max_guild_storage_quota",
     "federation_file_cache_enabled",
     "federation_file_cache_max_size",
     "federation_file_cache_ttl_hours",
+    "audit_log_retention_days",
+    "security_event_retention_days",
 ];
 
 const MAX_STRING_SETTING_LEN: usize = 256;
+const MAX_CORS_ORIGINS: usize = 32;
 
 fn validate_setting(key: &str, value: &str) -> Result<(), String> {
     match key {
-        "registration_enabled" => {
+        "registration_enabled" | "require_email_verification" => {
             if value != "true" && value != "false" {
                 return Err(format!("{key}: must be \"true\" or \"false\""));
             }
@@ -185,6 +230,15 @@ fn validate_setting(key: &str, value: &str) -> Result<(), String> {
                 ));
             }
         }
+        "cors_allowed_origins" => {
+            let origins = paracord_core::parse_origin_list(value);
+            if origins.len() > MAX_CORS_ORIGINS {
+                return Err(format!("{key}: at most {MAX_CORS_ORIGINS} origins"));
+            }
+            for origin in &origins {
+                validate_origin(origin).map_err(|reason| format!("{key}: {origin}: {reason}"))?;
+            }
+        }
         "max_guilds_per_user" | "max_members_per_guild" => {
             let n: u32 = value
                 .parse()
@@ -208,11 +262,41 @@ fn validate_setting(key: &str, value: &str) -> Result<(), String> {
                 .parse()
                 .map_err(|_| format!("{key}: must be a positive integer"))?;
         }
+        "audit_log_retention_days" | "security_event_retention_days" => {
+            let n: u32 = value
+                .parse()
+                .map_err(|_| format!("{key}: must be a non-negative integer"))?;
+            if n > 3650 {
+                return Err(format!("{key}: must be between 0 (keep forever) and 3650"));
+            }
+        }
         _ => {}
     }
     Ok(())
 }
 
+/// An origin is a scheme and host (and optional port) with nothing after.
+/// Wildcards are refused: `*` disables credentialed CORS, so it can only be
+/// set through `PARACORD_CORS_ALLOWED_ORIGINS`.
+fn validate_origin(origin: &str) -> Result<(), &'static str> {
+    if origin.contains('*') {
+        return Err("wildcards are not allowed");
+    }
+    let url = reqwest::Url::parse(origin).map_err(|_| "not a valid origin")?;
+    if !matches!(url.scheme(), "http" | "https" | "tauri") || url.host_str().is_none() {
+        return Err("must be an http(s) origin");
+    }
+    if !url.username().is_empty()
+        || url.password().is_some()
+        || url.path() != "/"
+        || url.query().is_some()
+        || url.fragment().is_some()
+    {
+        return Err("must not include a path, query or credentials");
+    }
+    Ok(())
+}
+
 pub async fn update_settings(
     State(state): State<AppState>,
     admin: AdminUser,
@@ -234,6 +318,7 @@ pub async fn update_settings(
         .map(|(key, value)| {
             let value = match key.as_str() {
                 "server_name" | "server_description" => value.trim().to_string(),
+                "cors_allowed_origins" => paracord_core::parse_origin_list(&value).join(","),
                 _ => value,
             };
             (key, value)
@@ -254,6 +339,9 @@ pub async fn update_settings(
             "registration_enabled" => {
                 settings.registration_enabled = value == "true";
             }
+            "require_email_verification" => {
+                settings.require_email_verification = value == "true";
+            }
             "server_name" => {
                 settings.server_name = value.clone();
             }
@@ -270,6 +358,10 @@ pub async fn update_settings(
                     settings.max_members_per_guild = v;
                 }
             }
+            "cors_allowed_origins" => {
+                settings.cors_allowed_origins = paracord_core::parse_origin_list(value);
+                crate::invalidate_cors_origins();
+            }
             _ => {}
         }
     }
@@ -288,10 +380,12 @@ pub async fn update_settings(
 
     Ok(Json(json!({
         "registration_enabled": settings.registration_enabled.to_string(),
+        "require_email_verification": settings.require_email_verification.to_string(),
         "server_name": settings.server_name,
         "server_description": settings.server_description,
         "max_guilds_per_user": settings.max_guilds_per_user.to_string(),
         "max_members_per_guild": settings.max_members_per_guild.to_string(),
+        "cors_allowed_origins": settings.cors_allowed_origins.join(","),
     })))
 }
 
@@ -404,6 +498,7 @@ pub async fn delete_user(
     }
 
     paracord_core::admin::admin_delete_user(&state.db, user_id).await?;
+    paracord_core::permissions::invalidate_user(&state.permission_cache, user_id).await;
     security::log_security_event(
         &state,
         "admin.user.delete",
@@ -417,6 +512,130 @@ pub async fn delete_user(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Most ids accepted by one bulk user action.
+const MAX_BULK_USER_IDS: usize = 100;
+
+#[derive(Deserialize)]
+pub struct BulkUserActionRequest {
+    pub user_ids: Vec<String>,
+    pub action: String,
+}
+
+pub async fn bulk_user_action(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Json(body): Json<BulkUserActionRequest>,
+) -> Result<Json<Value>, ApiError> {
+    use paracord_db::users::BulkUserAction;
+
+    let (action, revoke_reason) = match body.action.as_str() {
+        "disable" => (
+            BulkUserAction::Disable {
+                flag: paracord_core::USER_FLAG_DISABLED,
+            },
+            "admin_disabled",
+        ),
+        "force_logout" => (BulkUserAction::ForceLogout, "admin_force_logout"),
+        "delete" => (BulkUserAction::Delete, "admin_deleted"),
+        "unban" => (
+            BulkUserAction::Unban {
+                flag: paracord_core::USER_FLAG_DISABLED,
+            },
+            "admin_unbanned",
+        ),
+        _ => {
+            return Err(ApiError::invalid_field(
+                "action",
+                "must be one of disable, force_logout, delete, unban",
+            ))
+        }
+    };
+    if body.user_ids.is_empty() {
+        return Err(ApiError::invalid_field(
+            "user_ids",
+            "must contain at least one user",
+        ));
+    }
+    if body.user_ids.len() > MAX_BULK_USER_IDS {
+        return Err(ApiError::invalid_field(
+            "user_ids",
+            format!("may contain at most {MAX_BULK_USER_IDS} users"),
+        ));
+    }
+
+    // Ids that can't be acted on are reported back rather than failing the
+    // whole batch.
+    let mut outcomes: Vec<(String, Result<i64, &'static str>)> = Vec::new();
+    let mut targets = Vec::with_capacity(body.user_ids.len());
+    for raw in &body.user_ids {
+        match raw.parse::<i64>() {
+            Ok(id) if targets.contains(&id) => {}
+            Ok(id) if id == admin.user_id && action != BulkUserAction::ForceLogout => {
+                outcomes.push((id.to_string(), Err("cannot_target_self")));
+            }
+            Ok(id) => {
+                targets.push(id);
+                outcomes.push((id.to_string(), Ok(id)));
+            }
+            Err(_) => outcomes.push((raw.clone(), Err("invalid_id"))),
+        }
+    }
+
+    let applied: HashMap<i64, bool> = paracord_db::users::apply_bulk_user_action(
+        &state.db,
+        &targets,
+        action,
+        revoke_reason,
+        chrono::Utc::now(),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    .into_iter()
+    .collect();
+
+    let mut results = Vec::with_capacity(outcomes.len());
+    for (id, outcome) in outcomes {
+        let outcome = outcome.and_then(|user_id| match applied.get(&user_id) {
+            Some(true) => Ok(user_id),
+            _ => Err("not_found"),
+        });
+        match outcome {
+            Ok(user_id) => {
+                paracord_core::permissions::invalidate_user(&state.permission_cache, user_id).await;
+                results.push(json!({ "id": id, "success": true }));
+            }
+            Err(error) => results.push(json!({ "id": id, "success": false, "error": error })),
+        }
+    }
+    let succeeded = results
+        .iter()
+        .filter(|r| r["success"].as_bool() == Some(true))
+        .count();
+
+    security::log_security_event(
+        &state,
+        "admin.users.bulk",
+        Some(admin.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({
+            "action": body.action,
+            "requested": results.len(),
+            "succeeded": succeeded,
+            "failed": results.len() - succeeded,
+            "results": results,
+        })),
+    )
+    .await;
+
+    Ok(Json(json!({
+        "action": body.action,
+        "results": results,
+    })))
+}
+
 // ── Guilds ──────────────────────────────────────────────────────────────
 
 #[derive(Deserialize)]
@@ -424,6 +643,8 @@ pub struct UpdateGuildRequest {
     pub name: Option<String>,
     pub description: Option<String>,
     pub icon: Option<String>,
+    /// Replaces the guild's features, e.g. `["ANIMATED_ICON"]`.
+    pub features: Option<Vec<String>>,
 }
 
 pub async fn list_guilds(
@@ -458,12 +679,21 @@ pub async fn update_guild(
     Path(guild_id): Path<i64>,
     Json(body): Json<UpdateGuildRequest>,
 ) -> Result<Json<Value>, ApiError> {
+    let features = body
+        .features
+        .as_deref()
+        .map(|names| {
+            paracord_db::guilds::space_features_from_names(names)
+                .ok_or_else(|| ApiError::invalid_field("features", "Unknown guild feature"))
+        })
+        .transpose()?;
     let updated = paracord_core::admin::admin_update_guild(
         &state.db,
         guild_id,
         body.name.as_deref(),
         body.description.as_deref(),
         body.icon.as_deref(),
+        features,
     )
     .await?;
 
@@ -473,6 +703,7 @@ pub async fn update_guild(
         "description": updated.description,
         "icon_hash": updated.icon_hash,
         "owner_id": updated.owner_id.to_string(),
+        "features": updated.feature_names(),
         "created_at": updated.created_at.to_rfc3339(),
     });
 
@@ -500,7 +731,8 @@ pub async fn delete_guild(
     headers: HeaderMap,
     Path(guild_id): Path<i64>,
 ) -> Result<StatusCode, ApiError> {
-    paracord_core::admin::admin_delete_guild(&state.db, guild_id).await?;
+    let removed = paracord_core::admin::admin_delete_guild(&state.db, guild_id).await?;
+    files::remove_stored_files(&state, &removed).await;
 
     state.member_index.remove_guild(guild_id);
     state.event_bus.dispatch(
@@ -711,4 +943,12 @@ mod tests {
     fn validate_setting_accepts_valid_numeric_limits() {
         assert!(validate_setting("max_guilds_per_user", "100").is_ok());
     }
+
+    #[test]
+    fn validate_setting_bounds_retention_days() {
+        assert!(validate_setting("audit_log_retention_days", "0").is_ok());
+        assert!(validate_setting("security_event_retention_days", "90").is_ok());
+        assert!(validate_setting("audit_log_retention_days", "-1").is_err());
+        assert!(validate_setting("security_event_retention_days", "3651").is_err());
+    }
 }
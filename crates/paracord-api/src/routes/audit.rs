@@ -1,43 +1,105 @@
 use paracord_core::AppState;
-use serde_json::Value;
-
-pub const ACTION_GUILD_UPDATE: i16 = 1;
-pub const ACTION_CHANNEL_CREATE: i16 = 10;
-pub const ACTION_CHANNEL_UPDATE: i16 = 11;
-pub const ACTION_CHANNEL_DELETE: i16 = 12;
-pub const ACTION_MEMBER_UPDATE: i16 = 20;
-pub const ACTION_MEMBER_KICK: i16 = 21;
-pub const ACTION_MEMBER_BAN_ADD: i16 = 22;
-pub const ACTION_MEMBER_BAN_REMOVE: i16 = 23;
-pub const ACTION_ROLE_CREATE: i16 = 30;
-pub const ACTION_ROLE_UPDATE: i16 = 31;
-pub const ACTION_ROLE_DELETE: i16 = 32;
-pub const ACTION_INVITE_CREATE: i16 = 40;
-pub const ACTION_INVITE_DELETE: i16 = 41;
+use paracord_models::audit_log::AuditAction;
+use serde_json::{Map, Value};
 
 pub async fn log_action(
     state: &AppState,
     guild_id: i64,
     actor_id: i64,
-    action_type: i16,
+    action: AuditAction,
     target_id: Option<i64>,
     reason: Option<&str>,
     changes: Option<Value>,
 ) {
-    let log_id = paracord_util::snowflake::generate(1);
+    let log_id = paracord_util::snowflake::generate();
     let change_ref = changes.as_ref();
-    if let Err(err) = paracord_db::audit_log::create_entry(
-        &state.db,
-        log_id,
-        guild_id,
-        actor_id,
-        action_type,
-        target_id,
-        reason,
-        change_ref,
+    if let Err(err) = paracord_db::audit_log::create_action_entry(
+        &state.db, log_id, guild_id, actor_id, action, target_id, reason, change_ref,
     )
     .await
     {
         tracing::warn!("failed to write audit entry: {}", err);
     }
 }
+
+/// Build the `changes` payload of an audit entry as
+/// `{"field": {"old": .., "new": ..}}` for the listed `fields` of two object
+/// snapshots. Edits keep only fields whose value differs; a create has no
+/// `before` and records `new` values, a delete has no `after` and records
+/// `old` values, both skipping nulls. Returns `None` when nothing changed.
+pub fn diff_changes(
+    before: Option<&Value>,
+    after: Option<&Value>,
+    fields: &[&str],
+) -> Option<Value> {
+    let mut changes = Map::new();
+    for field in fields {
+        let old = before
+            .and_then(|value| value.get(*field))
+            .filter(|v| !v.is_null());
+        let new = after
+            .and_then(|value| value.get(*field))
+            .filter(|v| !v.is_null());
+        let mut change = Map::new();
+        match (before.is_some(), after.is_some()) {
+            (true, true) => {
+                if old == new {
+                    continue;
+                }
+                change.insert("old".into(), old.cloned().unwrap_or(Value::Null));
+                change.insert("new".into(), new.cloned().unwrap_or(Value::Null));
+            }
+            (false, true) => match new {
+                Some(new) => {
+                    change.insert("new".into(), new.clone());
+                }
+                None => continue,
+            },
+            (true, false) => match old {
+                Some(old) => {
+                    change.insert("old".into(), old.clone());
+                }
+                None => continue,
+            },
+            (false, false) => continue,
+        }
+        changes.insert((*field).to_string(), Value::Object(change));
+    }
+    if changes.is_empty() {
+        None
+    } else {
+        Some(Value::Object(changes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const FIELDS: &[&str] = &["name", "topic", "nsfw"];
+
+    #[test]
+    fn edits_record_only_changed_fields() {
+        let before = json!({"name": "general", "topic": null, "nsfw": false});
+        let after = json!({"name": "lobby", "topic": null, "nsfw": false});
+        assert_eq!(
+            diff_changes(Some(&before), Some(&after), FIELDS),
+            Some(json!({"name": {"old": "general", "new": "lobby"}}))
+        );
+        assert_eq!(diff_changes(Some(&before), Some(&before), FIELDS), None);
+    }
+
+    #[test]
+    fn creates_and_deletes_record_one_side() {
+        let channel = json!({"name": "general", "topic": null, "nsfw": false});
+        assert_eq!(
+            diff_changes(None, Some(&channel), FIELDS),
+            Some(json!({"name": {"new": "general"}, "nsfw": {"new": false}}))
+        );
+        assert_eq!(
+            diff_changes(Some(&channel), None, FIELDS),
+            Some(json!({"name": {"old": "general"}, "nsfw": {"old": false}}))
+        );
+    }
+}
@@ -1,14 +1,27 @@
 use axum::{
+    body::{Body, Bytes},
     extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::Response,
     Json,
 };
+use futures_util::stream;
 use paracord_core::AppState;
+use paracord_db::audit_log::AuditLogEntryRow;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
+use crate::routes::security;
+
+/// Entries fetched per keyset page while streaming an export.
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+const CSV_HEADER: &str =
+    "id,created_at,user_id,username,action_type,action,target_id,reason,changes\n";
 
 #[derive(Deserialize)]
 pub struct AuditLogQuery {
@@ -18,26 +31,45 @@ pub struct AuditLogQuery {
     pub limit: Option<i64>,
 }
 
-pub async fn get_audit_logs(
-    State(state): State<AppState>,
-    auth: AuthUser,
-    Path(guild_id): Path<i64>,
-    Query(params): Query<AuditLogQuery>,
-) -> Result<Json<Value>, ApiError> {
+#[derive(Deserialize)]
+pub struct AuditLogExportQuery {
+    pub format: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Csv,
+    Json,
+}
+
+async fn require_audit_log_access(
+    state: &AppState,
+    user_id: i64,
+    guild_id: i64,
+) -> Result<(), ApiError> {
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .ok_or(ApiError::NotFound)?;
 
-    let roles = paracord_db::roles::get_member_roles(&state.db, auth.user_id, guild_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let perms = paracord_core::permissions::compute_permissions_from_roles(
-        &roles,
+    let perms = paracord_core::permissions::compute_guild_permissions(
+        &state.db,
+        guild_id,
         guild.owner_id,
-        auth.user_id,
-    );
+        user_id,
+    )
+    .await?;
     paracord_core::permissions::require_permission(perms, Permissions::VIEW_AUDIT_LOG)?;
+    Ok(())
+}
+
+pub async fn get_audit_logs(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Query(params): Query<AuditLogQuery>,
+) -> Result<Json<Value>, ApiError> {
+    require_audit_log_access(&state, auth.user_id, guild_id).await?;
 
     let limit = params.limit.unwrap_or(50).min(100);
 
@@ -60,6 +92,7 @@ pub async fn get_audit_logs(
                 "guild_id": e.guild_id().to_string(),
                 "user_id": e.user_id.to_string(),
                 "action_type": e.action_type,
+                "action": e.action().name(),
                 "target_id": e.target_id.map(|id| id.to_string()),
                 "reason": e.reason,
                 "changes": e.changes,
@@ -72,3 +105,196 @@ pub async fn get_audit_logs(
         "audit_log_entries": audit_log_entries,
     })))
 }
+
+struct ExportStreamState {
+    app_state: AppState,
+    guild_id: i64,
+    format: ExportFormat,
+    before: Option<i64>,
+    header_pending: bool,
+    done: bool,
+    usernames: HashMap<i64, String>,
+}
+
+/// Stream the complete audit log of a guild as CSV or newline-delimited JSON.
+/// Entries are read newest first in keyset pages so the whole history is never
+/// held in memory.
+pub async fn export_audit_logs(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    headers: HeaderMap,
+    Path(guild_id): Path<i64>,
+    Query(params): Query<AuditLogExportQuery>,
+) -> Result<Response, ApiError> {
+    let format = match params.format.as_deref().unwrap_or("json") {
+        "csv" => ExportFormat::Csv,
+        "json" => ExportFormat::Json,
+        _ => return Err(ApiError::BadRequest("format must be csv or json".into())),
+    };
+    require_audit_log_access(&state, auth.user_id, guild_id).await?;
+
+    security::log_security_event(
+        &state,
+        "guild.audit_log.export",
+        Some(auth.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({
+            "guild_id": guild_id.to_string(),
+            "format": if format == ExportFormat::Csv { "csv" } else { "json" },
+        })),
+    )
+    .await;
+
+    let stream_state = ExportStreamState {
+        app_state: state,
+        guild_id,
+        format,
+        before: None,
+        header_pending: format == ExportFormat::Csv,
+        done: false,
+        usernames: HashMap::new(),
+    };
+    let body_stream = stream::unfold(stream_state, |mut st| async move {
+        if st.header_pending {
+            st.header_pending = false;
+            return Some((Ok(Bytes::from_static(CSV_HEADER.as_bytes())), st));
+        }
+        if st.done {
+            return None;
+        }
+
+        let page = match paracord_db::audit_log::get_space_entries(
+            &st.app_state.db,
+            st.guild_id,
+            None,
+            None,
+            st.before,
+            EXPORT_PAGE_SIZE,
+        )
+        .await
+        {
+            Ok(page) => page,
+            Err(err) => {
+                tracing::warn!("audit log export for guild {} failed: {}", st.guild_id, err);
+                st.done = true;
+                return Some((Err(std::io::Error::other(err.to_string())), st));
+            }
+        };
+        st.before = Some(page.last()?.id);
+        st.done = (page.len() as i64) < EXPORT_PAGE_SIZE;
+
+        let mut chunk = String::new();
+        for entry in &page {
+            let username = resolve_username(&st.app_state, &mut st.usernames, entry.user_id).await;
+            match st.format {
+                ExportFormat::Csv => push_csv_row(&mut chunk, entry, &username),
+                ExportFormat::Json => {
+                    chunk.push_str(&export_json_line(entry, &username).to_string());
+                    chunk.push('\n');
+                }
+            }
+        }
+        Some((Ok(Bytes::from(chunk)), st))
+    });
+
+    let (content_type, extension) = match format {
+        ExportFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+        ExportFormat::Json => ("application/x-ndjson", "ndjson"),
+    };
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"audit-log-{guild_id}.{extension}\""),
+        )
+        .body(Body::from_stream(body_stream))
+        .unwrap())
+}
+
+async fn resolve_username(
+    state: &AppState,
+    cache: &mut HashMap<i64, String>,
+    user_id: i64,
+) -> String {
+    if let Some(name) = cache.get(&user_id) {
+        return name.clone();
+    }
+    let name = match paracord_db::users::get_user_by_id(&state.db, user_id).await {
+        Ok(Some(user)) => user.username,
+        _ => String::new(),
+    };
+    cache.insert(user_id, name.clone());
+    name
+}
+
+fn export_json_line(entry: &AuditLogEntryRow, username: &str) -> Value {
+    json!({
+        "id": entry.id.to_string(),
+        "created_at": entry.created_at.to_rfc3339(),
+        "user_id": entry.user_id.to_string(),
+        "username": username,
+        "action_type": entry.action_type,
+        "action": entry.action().name(),
+        "target_id": entry.target_id.map(|id| id.to_string()),
+        "reason": entry.reason,
+        "changes": entry.changes,
+    })
+}
+
+fn push_csv_row(out: &mut String, entry: &AuditLogEntryRow, username: &str) {
+    let changes = entry
+        .changes
+        .as_ref()
+        .map(|c| c.to_string())
+        .unwrap_or_default();
+    let fields = [
+        entry.id.to_string(),
+        entry.created_at.to_rfc3339(),
+        entry.user_id.to_string(),
+        username.to_string(),
+        entry.action_type.to_string(),
+        entry.action().name().to_string(),
+        entry.target_id.map(|id| id.to_string()).unwrap_or_default(),
+        entry.reason.clone().unwrap_or_default(),
+        changes,
+    ];
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&csv_field(field));
+    }
+    out.push('\n');
+}
+
+/// Quote a CSV field when needed. Values a spreadsheet would evaluate as a
+/// formula are prefixed with `'` so an exported reason cannot run on open.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{value}")
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_and_formula_safe() {
+        assert_eq!(csv_field("spam"), "spam");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(csv_field("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(csv_field("@everyone"), "'@everyone");
+    }
+}
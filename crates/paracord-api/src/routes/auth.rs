@@ -1,8 +1,8 @@
 use axum::{
     body::to_bytes,
-    extract::{ConnectInfo, Path, Request, State},
+    extract::{ConnectInfo, Path, Query, Request, State},
     http::{header, HeaderMap, HeaderValue, StatusCode},
-    response::{AppendHeaders, IntoResponse},
+    response::{AppendHeaders, IntoResponse, Response},
     Json,
 };
 use chrono::{Duration, Utc};
@@ -11,10 +11,8 @@ use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Mutex, OnceLock};
 use uuid::Uuid;
 
 use crate::error::ApiError;
@@ -25,34 +23,80 @@ const REFRESH_COOKIE_NAME: &str = "paracord_refresh";
 const REFRESH_COOKIE_PATH: &str = "/api/v1/auth";
 const ACCESS_COOKIE_NAME: &str = "paracord_access";
 const ACCESS_COOKIE_PATH: &str = "/api/v1";
-const CHALLENGE_STORE_MAX_ENTRIES: usize = 10_000;
+const CHALLENGE_TTL_SECONDS: i64 = 120;
+const CHALLENGE_STORE_MAX_ENTRIES: i64 = 10_000;
 const MAX_DISPLAY_NAME_LEN: usize = 64;
 const AUTH_GUARD_TTL_SECONDS: i64 = 3600;
 const AUTH_GUARD_CLEANUP_LIMIT: i64 = 512;
 const MAX_LOGIN_BODY_BYTES: usize = 16 * 1024;
+const MFA_TICKET_TTL_SECONDS: u64 = 300;
+/// How long a verification link stays valid. Accounts still unverified after
+/// this long are pruned.
+pub const EMAIL_VERIFICATION_TTL_SECONDS: i64 = 48 * 3600;
+const BREACHED_PASSWORD_LOOKUP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
 
-// In-memory challenge nonce store (nonce -> timestamp). Cleaned up on each request.
-static CHALLENGE_STORE: OnceLock<Mutex<HashMap<String, i64>>> = OnceLock::new();
 static AUTH_GUARD_OP_COUNTER: AtomicU64 = AtomicU64::new(0);
 
-fn challenge_store() -> &'static Mutex<HashMap<String, i64>> {
-    CHALLENGE_STORE.get_or_init(|| Mutex::new(HashMap::new()))
-}
+/// Check a new password against the configured policy and, when enabled, the
+/// breached-password range API. The error names every rule that failed.
+pub(crate) async fn enforce_password_policy(
+    state: &AppState,
+    password: &str,
+) -> Result<(), ApiError> {
+    use paracord_util::validation::PasswordRule;
 
-fn cleanup_expired_challenges(store: &mut HashMap<String, i64>) {
-    let now = Utc::now().timestamp();
-    store.retain(|_, ts| (now - *ts).abs() <= 120);
+    let mut failed = state.config.password_policy.violations(password);
+    if failed.is_empty() {
+        if let Some(api_url) = state.config.breached_password_api_url.as_deref() {
+            if password_is_breached(api_url, password).await {
+                failed.push(PasswordRule::Breached);
+            }
+        }
+    }
+    if failed.is_empty() {
+        return Ok(());
+    }
+    Err(ApiError::WeakPassword {
+        failed: failed.into_iter().map(PasswordRule::as_str).collect(),
+    })
 }
 
-fn cap_oldest_challenges(store: &mut HashMap<String, i64>) {
-    if store.len() <= CHALLENGE_STORE_MAX_ENTRIES {
-        return;
-    }
-    let mut entries: Vec<(String, i64)> = store.iter().map(|(k, v)| (k.clone(), *v)).collect();
-    entries.sort_by_key(|(_, ts)| *ts);
-    let overflow = store.len().saturating_sub(CHALLENGE_STORE_MAX_ENTRIES);
-    for (key, _) in entries.into_iter().take(overflow) {
-        store.remove(&key);
+/// k-anonymity lookup: only the first 5 hex characters of the password's
+/// SHA-1 are sent. Fails open, so an unreachable service never blocks
+/// registration or password changes.
+async fn password_is_breached(api_url: &str, password: &str) -> bool {
+    let (prefix, suffix) = paracord_util::validation::breached_password_range(password);
+    let url = format!("{}/{}", api_url.trim_end_matches('/'), prefix);
+    let client = match reqwest::Client::builder()
+        .timeout(BREACHED_PASSWORD_LOOKUP_TIMEOUT)
+        .build()
+    {
+        Ok(client) => client,
+        Err(err) => {
+            tracing::warn!("Breached password check unavailable: {}", err);
+            return false;
+        }
+    };
+    let response = match client.get(&url).header("Add-Padding", "true").send().await {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => {
+            tracing::warn!(
+                "Breached password check returned HTTP {}; allowing password",
+                response.status().as_u16()
+            );
+            return false;
+        }
+        Err(err) => {
+            tracing::warn!("Breached password check failed; allowing password: {}", err);
+            return false;
+        }
+    };
+    match response.text().await {
+        Ok(body) => paracord_util::validation::breached_range_contains(&body, &suffix),
+        Err(err) => {
+            tracing::warn!("Breached password check failed; allowing password: {}", err);
+            false
+        }
     }
 }
 
@@ -69,46 +113,14 @@ fn constant_time_equal(a: &str, b: &str) -> bool {
     diff == 0
 }
 
-fn trust_proxy_headers() -> bool {
-    std::env::var("PARACORD_TRUST_PROXY")
-        .ok()
-        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
-        .unwrap_or(false)
-}
-
 fn proxy_peer_is_trusted(peer_ip: Option<&str>) -> bool {
-    if !trust_proxy_headers() {
-        return false;
-    }
-    let Some(peer_ip) = peer_ip else {
-        return false;
-    };
-    let trusted = std::env::var("PARACORD_TRUSTED_PROXY_IPS")
-        .ok()
-        .map(|raw| {
-            raw.split(',')
-                .map(str::trim)
-                .filter(|v| !v.is_empty())
-                .map(str::to_string)
-                .collect::<Vec<_>>()
-        })
-        .unwrap_or_default();
-    !trusted.is_empty() && trusted.iter().any(|ip| ip == peer_ip)
+    crate::client_ip::peer_is_trusted(peer_ip.and_then(|ip| ip.parse().ok()))
 }
 
 fn resolve_client_ip(headers: &HeaderMap, peer_ip: Option<&str>) -> String {
-    if proxy_peer_is_trusted(peer_ip) {
-        if let Some(ip) = headers
-            .get("x-forwarded-for")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|raw| raw.split(',').next())
-            .map(str::trim)
-            .filter(|v| !v.is_empty())
-        {
-            return ip.to_string();
-        }
-    }
-    peer_ip.unwrap_or("unknown").to_string()
+    crate::client_ip::resolve(headers, peer_ip.and_then(|ip| ip.parse().ok()))
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
 }
 
 fn auth_guard_keys(
@@ -544,22 +556,14 @@ fn get_cookie_value(headers: &HeaderMap, cookie_name: &str) -> Option<String> {
 fn random_token_hex(bytes: usize) -> String {
     let mut buf = vec![0u8; bytes];
     rand::thread_rng().fill_bytes(&mut buf);
-    let mut out = String::with_capacity(bytes * 2);
-    for b in &buf {
-        out.push_str(&format!("{:02x}", b));
-    }
-    out
+    paracord_util::hex::hex_encode(&buf)
 }
 
 fn sha256_hex(value: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(value.as_bytes());
     let digest = hasher.finalize();
-    let mut out = String::with_capacity(digest.len() * 2);
-    for b in digest {
-        out.push_str(&format!("{:02x}", b));
-    }
-    out
+    paracord_util::hex::hex_encode(&digest)
 }
 
 fn header_value(value: &str) -> Result<HeaderValue, ApiError> {
@@ -596,6 +600,7 @@ async fn issue_auth_session(
     headers: &HeaderMap,
     peer_ip: Option<&str>,
 ) -> Result<(String, String, String, String, String), ApiError> {
+    let user = ensure_account_enabled(state, user_id).await?;
     let session_id = Uuid::new_v4().to_string();
     let jti = Uuid::new_v4().to_string();
     let refresh_token = random_token_hex(48);
@@ -604,6 +609,8 @@ async fn issue_auth_session(
     let now = Utc::now();
     let expires_at = now + Duration::days(ttl_days);
     let (device_id, user_agent, ip_address) = request_metadata(headers, peer_ip);
+    let new_device =
+        is_new_device_login(state, user_id, device_id.as_deref(), user_agent.as_deref()).await;
 
     paracord_db::sessions::create_session(
         &state.db,
@@ -620,10 +627,22 @@ async fn issue_auth_session(
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
+    if new_device {
+        alert_new_device_login(
+            state,
+            &user,
+            &session_id,
+            headers,
+            user_agent.as_deref(),
+            ip_address.as_deref(),
+        )
+        .await;
+    }
+
     let access_token = paracord_core::auth::create_session_token(
         user_id,
         public_key,
-        &state.config.jwt_secret,
+        &state.config.jwt_keys,
         state.config.jwt_expiry_seconds,
         &session_id,
         &jti,
@@ -636,6 +655,90 @@ async fn issue_auth_session(
     Ok((access_token, access_cookie, refresh_cookie, session_id, refresh_token))
 }
 
+/// A sign-in counts as coming from a new device when the account has signed
+/// in before but never with this device ID (or, for clients that send none,
+/// this user agent). First sessions are never flagged.
+async fn is_new_device_login(
+    state: &AppState,
+    user_id: i64,
+    device_id: Option<&str>,
+    user_agent: Option<&str>,
+) -> bool {
+    let known = match paracord_db::sessions::list_session_devices(&state.db, user_id).await {
+        Ok(known) => known,
+        Err(err) => {
+            tracing::warn!(user_id, "Failed to load known devices: {}", err);
+            return false;
+        }
+    };
+    !known.is_empty()
+        && !known.iter().any(|(known_device, known_agent)| {
+            match (device_id, known_device.as_deref()) {
+                (Some(id), Some(known_id)) => id == known_id,
+                _ => user_agent.is_some() && known_agent.as_deref() == user_agent,
+            }
+        })
+}
+
+/// Records a new-device sign-in and, if the user opted in to security alert
+/// emails, queues one. Never fails the login.
+async fn alert_new_device_login(
+    state: &AppState,
+    user: &paracord_db::users::UserRow,
+    session_id: &str,
+    headers: &HeaderMap,
+    user_agent: Option<&str>,
+    ip_address: Option<&str>,
+) {
+    security::log_security_event(
+        state,
+        "auth.login.new_device",
+        Some(user.id),
+        Some(user.id),
+        Some(session_id),
+        Some(headers),
+        None,
+    )
+    .await;
+
+    let opted_in = paracord_db::users::get_user_settings(&state.db, user.id)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|settings| settings.notifications.get("securityAlertEmails")?.as_bool())
+        .unwrap_or(false);
+    if !opted_in || user.email == synthesized_local_email(user.id) {
+        return;
+    }
+    state.mailer.send(
+        &user.email,
+        &paracord_mail::Template::NewDeviceLogin {
+            username: user.username.clone(),
+            device: user_agent.unwrap_or("Unknown device").to_string(),
+            ip_address: ip_address.map(str::to_string),
+            time: Utc::now().format("%Y-%m-%d %H:%M UTC").to_string(),
+        },
+    );
+}
+
+/// Disabled and unverified accounts can neither start nor refresh a session.
+async fn ensure_account_enabled(
+    state: &AppState,
+    user_id: i64,
+) -> Result<paracord_db::users::UserRow, ApiError> {
+    let user = paracord_db::users::get_user_by_id(&state.db, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Unauthorized)?;
+    if paracord_core::is_disabled(user.flags) {
+        return Err(ApiError::Forbidden);
+    }
+    if paracord_core::is_unverified(user.flags) {
+        return Err(ApiError::EmailNotVerified);
+    }
+    Ok(user)
+}
+
 /// Result: (access_token, access_cookie, refresh_cookie, session_id, raw_new_refresh_token)
 async fn rotate_auth_session(
     state: &AppState,
@@ -650,6 +753,7 @@ async fn rotate_auth_session(
     if session.revoked_at.is_some() || session.expires_at <= now {
         return Err(ApiError::Unauthorized);
     }
+    ensure_account_enabled(state, session.user_id).await?;
 
     let new_refresh = random_token_hex(48);
     let new_refresh_hash = sha256_hex(&new_refresh);
@@ -674,7 +778,7 @@ async fn rotate_auth_session(
     let access_token = paracord_core::auth::create_session_token(
         session.user_id,
         session.pub_key.as_deref(),
-        &state.config.jwt_secret,
+        &state.config.jwt_keys,
         state.config.jwt_expiry_seconds,
         &session.id,
         &new_jti,
@@ -722,8 +826,8 @@ async fn auto_join_public_spaces(state: &AppState, user_id: i64) -> Result<(), A
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
     for space in spaces.iter().filter(|s| {
-        s.visibility == "public"
-            && paracord_db::guilds::parse_allowed_role_ids(&s.allowed_roles).is_empty()
+        s.visibility() == paracord_db::guilds::SpaceVisibility::Public
+            && s.allowed_role_ids().is_empty()
     }) {
         let _ = paracord_db::members::add_member(&state.db, user_id, space.id).await;
         let _ = paracord_db::roles::add_member_role(&state.db, user_id, space.id, space.id).await;
@@ -758,6 +862,15 @@ pub struct AuthResponse {
     pub refresh_token: Option<String>,
 }
 
+/// Returned by `login` instead of an `AuthResponse` when the account has 2FA
+/// enabled. The ticket is exchanged at `/api/v1/auth/mfa`.
+#[derive(Serialize)]
+pub struct MfaRequiredResponse {
+    pub mfa_required: bool,
+    pub mfa_ticket: String,
+    pub methods: Vec<&'static str>,
+}
+
 #[derive(Serialize)]
 pub struct AuthSessionView {
     pub id: String,
@@ -774,6 +887,7 @@ pub struct AuthSessionView {
 pub struct AuthOptionsResponse {
     pub allow_username_login: bool,
     pub require_email: bool,
+    pub require_email_verification: bool,
 }
 
 pub async fn auth_options(State(state): State<AppState>) -> Json<AuthOptionsResponse> {
@@ -784,19 +898,34 @@ pub async fn auth_options(State(state): State<AppState>) -> Json<AuthOptionsResp
     Json(AuthOptionsResponse {
         allow_username_login,
         require_email: state.config.require_email,
+        require_email_verification: state.runtime.read().await.require_email_verification,
     })
 }
 
+/// True when an existing account's username shares the confusable skeleton of
+/// `username`, e.g. `\u{0430}dmin` (Cyrillic a) against `admin`.
+async fn username_confusable_with_existing(
+    state: &AppState,
+    username: &str,
+) -> Result<bool, ApiError> {
+    let skeleton = paracord_util::validation::username_skeleton(username);
+    let existing = paracord_db::users::find_user_id_by_username_skeleton(&state.db, &skeleton)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(existing.is_some())
+}
+
 pub async fn register(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     Json(body): Json<RegisterRequest>,
-) -> Result<impl IntoResponse, ApiError> {
+) -> Result<Response, ApiError> {
     let peer_ip = addr.ip().to_string();
+    let username = paracord_util::validation::normalize_username(&body.username);
     let normalized_email = normalize_email_for_auth(&body.email);
     let account_hint = if normalized_email.is_empty() {
-        normalize_login_identifier_for_auth(&body.username)
+        normalize_login_identifier_for_auth(&username)
     } else {
         normalized_email.clone()
     };
@@ -820,7 +949,7 @@ pub async fn register(
         return Err(ApiError::Forbidden);
     }
 
-    if paracord_util::validation::validate_username(&body.username).is_err() {
+    if paracord_util::validation::validate_username(&username).is_err() {
         auth_guard_record_failure(
             &state,
             &headers,
@@ -828,8 +957,9 @@ pub async fn register(
             Some(&account_hint),
         )
         .await;
-        return Err(ApiError::BadRequest(
-            "Username must be between 2 and 32 valid characters".into(),
+        return Err(ApiError::invalid_field(
+            "username",
+            "Username must be between 2 and 32 valid characters",
         ));
     }
     if state.config.require_email && normalized_email.is_empty() {
@@ -840,7 +970,7 @@ pub async fn register(
             Some(&account_hint),
         )
         .await;
-        return Err(ApiError::BadRequest("Email is required".into()));
+        return Err(ApiError::invalid_field("email", "Email is required"));
     }
     if !normalized_email.is_empty()
         && paracord_util::validation::validate_email(&normalized_email).is_err()
@@ -852,7 +982,7 @@ pub async fn register(
             Some(&account_hint),
         )
         .await;
-        return Err(ApiError::BadRequest("Invalid email address".into()));
+        return Err(ApiError::invalid_field("email", "Invalid email address"));
     }
     let allow_username_login = username_login_effective(
         state.config.allow_username_login,
@@ -870,9 +1000,7 @@ pub async fn register(
             "Server requires email login or username login support".into(),
         ));
     }
-    paracord_util::validation::validate_password(&body.password).map_err(|_| {
-        ApiError::BadRequest("Password must be between 10 and 128 characters".into())
-    })?;
+    enforce_password_policy(&state, &body.password).await?;
 
     if !normalized_email.is_empty() {
         let existing = paracord_db::users::get_user_by_email(&state.db, &normalized_email)
@@ -893,10 +1021,23 @@ pub async fn register(
         }
     }
 
+    if username_confusable_with_existing(&state, &username).await? {
+        auth_guard_record_failure(
+            &state,
+            &headers,
+            Some(peer_ip.as_str()),
+            Some(&account_hint),
+        )
+        .await;
+        return Err(ApiError::BadRequest(
+            "Username is too similar to an existing username".into(),
+        ));
+    }
+
     let password_hash = paracord_core::auth::hash_password(&body.password)
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
-    let id = paracord_util::snowflake::generate(1);
+    let id = paracord_util::snowflake::generate();
     let resolved_email = if normalized_email.is_empty() {
         synthesized_local_email(id)
     } else {
@@ -905,7 +1046,7 @@ pub async fn register(
     let mut user = paracord_db::users::create_user_as_first_admin(
         &state.db,
         id,
-        &body.username,
+        &username,
         0,
         &resolved_email,
         &password_hash,
@@ -922,11 +1063,56 @@ pub async fn register(
         .map(str::trim)
         .filter(|s| !s.is_empty())
     {
-        user = paracord_db::users::update_user(&state.db, user.id, Some(display_name), None, None)
+        let update = paracord_db::users::UserProfileUpdate {
+            display_name: Some(display_name),
+            ..Default::default()
+        };
+        user = paracord_db::users::update_user(&state.db, user.id, &update)
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
     }
 
+    // The first account is the server admin and is never held back; neither
+    // are accounts without a real address to verify.
+    let needs_verification = state.runtime.read().await.require_email_verification
+        && !normalized_email.is_empty()
+        && !paracord_core::is_admin(user.flags);
+    if needs_verification {
+        user = paracord_db::users::update_user_flags(
+            &state.db,
+            user.id,
+            user.flags | paracord_core::USER_FLAG_UNVERIFIED,
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        send_email_verification(&state, &user).await?;
+        security::log_security_event(
+            &state,
+            "auth.register.password",
+            Some(user.id),
+            Some(user.id),
+            None,
+            Some(&headers),
+            Some(json!({ "auth_method": "password", "verification_pending": true })),
+        )
+        .await;
+        auth_guard_record_success(
+            &state,
+            &headers,
+            Some(peer_ip.as_str()),
+            Some(&account_hint),
+        )
+        .await;
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(json!({
+                "verification_required": true,
+                "user": user_json(&user),
+            })),
+        )
+            .into_response());
+    }
+
     let (token, access_cookie, refresh_cookie, session_id, raw_refresh) = issue_auth_session(
         &state,
         user.id,
@@ -964,7 +1150,95 @@ pub async fn register(
             user: user_json(&user),
             refresh_token: Some(raw_refresh),
         }),
-    ))
+    )
+        .into_response())
+}
+
+/// Build the link that verifies an address, or `None` when there is no
+/// public URL to make it absolute.
+fn email_verification_link(public_url: Option<&str>, token: &str) -> Option<String> {
+    let base = public_url
+        .map(|url| url.trim().trim_end_matches('/'))
+        .filter(|url| !url.is_empty())?;
+    Some(format!("{base}/api/v1/auth/verify-email?token={token}"))
+}
+
+/// Issue a verification token for `user` and email them the link. Without a
+/// configured `public_url` the link could not be opened, so nothing is sent
+/// and the operator is warned instead.
+async fn send_email_verification(
+    state: &AppState,
+    user: &paracord_db::users::UserRow,
+) -> Result<(), ApiError> {
+    let token = random_token_hex(32);
+    let Some(link) = email_verification_link(state.config.public_url.as_deref(), &token) else {
+        tracing::warn!(
+            user_id = user.id,
+            "public_url is not configured; cannot email a verification link"
+        );
+        return Ok(());
+    };
+    let expires_at = Utc::now().timestamp() + EMAIL_VERIFICATION_TTL_SECONDS;
+    paracord_db::email_verifications::create_email_verification(
+        &state.db,
+        &sha256_hex(&token),
+        user.id,
+        expires_at,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    state.mailer.send(
+        &user.email,
+        &paracord_mail::Template::EmailVerification {
+            username: user.username.clone(),
+            link,
+        },
+    );
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct VerifyEmailQuery {
+    pub token: String,
+}
+
+pub async fn verify_email(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<VerifyEmailQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let user_id = paracord_db::email_verifications::consume_email_verification(
+        &state.db,
+        &sha256_hex(params.token.trim()),
+        Utc::now().timestamp(),
+        paracord_core::USER_FLAG_UNVERIFIED,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    .ok_or_else(|| ApiError::BadRequest("Invalid or expired verification token".into()))?;
+    security::log_security_event(
+        &state,
+        "auth.email.verified",
+        Some(user_id),
+        Some(user_id),
+        None,
+        Some(&headers),
+        None,
+    )
+    .await;
+    Ok(Json(json!({ "verified": true })))
+}
+
+/// Delete accounts that never verified their email within the link lifetime.
+pub async fn prune_unverified_accounts_once(state: &AppState) -> Result<u64, paracord_db::DbError> {
+    let now = Utc::now();
+    paracord_db::email_verifications::prune_unverified_users(
+        &state.db,
+        paracord_core::USER_FLAG_UNVERIFIED,
+        now - Duration::seconds(EMAIL_VERIFICATION_TTL_SECONDS),
+        now.timestamp(),
+    )
+    .await
 }
 
 pub async fn login(
@@ -972,7 +1246,7 @@ pub async fn login(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     request: Request,
-) -> Result<impl IntoResponse, ApiError> {
+) -> Result<Response, ApiError> {
     let peer_ip = addr.ip().to_string();
 
     let (_, request_body) = request.into_parts();
@@ -1069,6 +1343,55 @@ pub async fn login(
         return Err(ApiError::Unauthorized);
     }
 
+    // Upgrade hashes made under weaker settings while the plaintext is at hand.
+    if paracord_core::auth::password_needs_rehash(&user.password_hash) {
+        match paracord_core::auth::hash_password(&body.password) {
+            Ok(rehashed) => {
+                if let Err(e) =
+                    paracord_db::users::update_user_password_hash(&state.db, user.id, &rehashed)
+                        .await
+                {
+                    tracing::warn!(
+                        "Failed to store upgraded password hash for {}: {}",
+                        user.id,
+                        e
+                    );
+                }
+            }
+            Err(e) => tracing::warn!("Failed to rehash password for {}: {}", user.id, e),
+        }
+    }
+
+    let mfa = paracord_db::user_mfa::get_user_mfa(&state.db, user.id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if mfa.is_some_and(|m| m.mfa_enabled) {
+        // Password was correct but the session is only issued once the second
+        // factor is verified via `/api/v1/auth/mfa`.
+        let ticket = paracord_core::auth::create_mfa_ticket(
+            user.id,
+            &state.config.jwt_keys,
+            MFA_TICKET_TTL_SECONDS,
+        )
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        security::log_security_event(
+            &state,
+            "auth.login.mfa_required",
+            Some(user.id),
+            Some(user.id),
+            None,
+            Some(&headers),
+            Some(json!({ "auth_method": "password" })),
+        )
+        .await;
+        return Ok(Json(MfaRequiredResponse {
+            mfa_required: true,
+            mfa_ticket: ticket,
+            methods: vec!["totp", "recovery_code"],
+        })
+        .into_response());
+    }
+
     let (token, access_cookie, refresh_cookie, session_id, raw_refresh) = issue_auth_session(
         &state,
         user.id,
@@ -1105,7 +1428,8 @@ pub async fn login(
             user: user_auth_json(&user),
             refresh_token: Some(raw_refresh),
         }),
-    ))
+    )
+        .into_response())
 }
 
 pub async fn refresh(
@@ -1235,6 +1559,7 @@ pub async fn revoke_session(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(session_id): Path<String>,
+    headers: HeaderMap,
 ) -> Result<axum::response::Response, ApiError> {
     let revoked = paracord_db::sessions::revoke_session(
         &state.db,
@@ -1255,8 +1580,8 @@ pub async fn revoke_session(
         Some(auth.user_id),
         Some(auth.user_id),
         Some(&session_id),
-        None,
-        None,
+        Some(&headers),
+        Some(json!({ "reason": "user_session_revoke" })),
     )
     .await;
 
@@ -1278,6 +1603,53 @@ pub async fn revoke_session(
     }
 }
 
+/// "Log out everywhere": revoke every active session except the one making
+/// the request. Each revocation is recorded separately so the security log
+/// shows exactly which devices were signed out.
+pub async fn revoke_other_sessions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    headers: HeaderMap,
+) -> Result<Json<Value>, ApiError> {
+    let current = auth
+        .session_id
+        .as_deref()
+        .ok_or_else(|| ApiError::BadRequest("Only user sessions can do this".into()))?;
+    let now = Utc::now();
+    let sessions = paracord_db::sessions::list_user_sessions(&state.db, auth.user_id, now)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let mut revoked = 0usize;
+    for session in sessions.iter().filter(|session| session.id != current) {
+        let was_active = paracord_db::sessions::revoke_session(
+            &state.db,
+            &session.id,
+            auth.user_id,
+            "user_logout_everywhere",
+            now,
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        if !was_active {
+            continue;
+        }
+        revoked += 1;
+        security::log_security_event(
+            &state,
+            "auth.session.revoke",
+            Some(auth.user_id),
+            Some(auth.user_id),
+            Some(&session.id),
+            Some(&headers),
+            Some(json!({ "reason": "user_logout_everywhere" })),
+        )
+        .await;
+    }
+
+    Ok(Json(json!({ "revoked": revoked })))
+}
+
 // --- Public key attachment (migration for existing password-based accounts) ---
 
 #[derive(Deserialize)]
@@ -1381,15 +1753,18 @@ pub async fn challenge(
 
     let (nonce, timestamp) = paracord_core::auth::generate_challenge();
 
-    // Store the nonce.
-    {
-        let mut store = challenge_store()
-            .lock()
-            .map_err(|_| ApiError::Internal(anyhow::anyhow!("lock error")))?;
-        cleanup_expired_challenges(&mut store);
-        store.insert(nonce.clone(), timestamp);
-        cap_oldest_challenges(&mut store);
-    }
+    paracord_db::auth_challenges::create_auth_challenge(
+        &state.db,
+        &nonce,
+        timestamp,
+        timestamp + CHALLENGE_TTL_SECONDS,
+    )
+    .await?;
+    paracord_db::auth_challenges::cap_outstanding_auth_challenges(
+        &state.db,
+        CHALLENGE_STORE_MAX_ENTRIES,
+    )
+    .await?;
 
     let server_origin = resolve_server_origin(
         state.config.public_url.as_deref(),
@@ -1404,6 +1779,13 @@ pub async fn challenge(
     }))
 }
 
+/// Delete challenge nonces that have expired, whether or not they were used.
+/// Returns how many were removed.
+pub async fn purge_expired_challenges_once(state: &AppState) -> Result<u64, paracord_db::DbError> {
+    paracord_db::auth_challenges::purge_expired_auth_challenges(&state.db, Utc::now().timestamp())
+        .await
+}
+
 #[derive(Deserialize)]
 pub struct VerifyRequest {
     pub public_key: String,
@@ -1429,7 +1811,8 @@ pub async fn verify(
     )
     .await?;
 
-    if paracord_util::validation::validate_username(&body.username).is_err() {
+    let username = paracord_util::validation::normalize_username(&body.username);
+    if paracord_util::validation::validate_username(&username).is_err() {
         auth_guard_record_failure(
             &state,
             &headers,
@@ -1437,8 +1820,9 @@ pub async fn verify(
             Some(&body.public_key),
         )
         .await;
-        return Err(ApiError::BadRequest(
-            "Username must be between 2 and 32 valid characters".into(),
+        return Err(ApiError::invalid_field(
+            "username",
+            "Username must be between 2 and 32 valid characters",
         ));
     }
 
@@ -1488,13 +1872,15 @@ pub async fn verify(
         return Err(ApiError::BadRequest("Invalid public key".into()));
     }
 
-    // Consume the nonce (one-time use) without holding the mutex across awaits.
-    let nonce_consumed = {
-        let mut store = challenge_store()
-            .lock()
-            .map_err(|_| ApiError::Internal(anyhow::anyhow!("lock error")))?;
-        store.remove(&body.nonce).is_some()
-    };
+    // Consume the nonce (one-time use) before checking the signature, so a
+    // captured signature can't be replayed even within its freshness window.
+    let nonce_consumed = paracord_db::auth_challenges::consume_auth_challenge(
+        &state.db,
+        &body.nonce,
+        body.timestamp,
+        Utc::now().timestamp(),
+    )
+    .await?;
     if !nonce_consumed {
         auth_guard_record_failure(
             &state,
@@ -1550,14 +1936,26 @@ pub async fn verify(
                 .await;
                 return Err(ApiError::Forbidden);
             }
+            if username_confusable_with_existing(&state, &username).await? {
+                auth_guard_record_failure(
+                    &state,
+                    &headers,
+                    Some(peer_ip.as_str()),
+                    Some(&body.public_key),
+                )
+                .await;
+                return Err(ApiError::BadRequest(
+                    "Username is too similar to an existing username".into(),
+                ));
+            }
 
             // Auto-register: create new user from public key.
-            let id = paracord_util::snowflake::generate(1);
+            let id = paracord_util::snowflake::generate();
             let new_user = paracord_db::users::create_user_from_pubkey_as_first_admin(
                 &state.db,
                 id,
                 &body.public_key,
-                &body.username,
+                &username,
                 normalized_display_name.as_deref(),
                 paracord_core::USER_FLAG_ADMIN,
             )
@@ -1609,12 +2007,350 @@ pub async fn verify(
     ))
 }
 
+// --- TOTP two-factor authentication ---
+
+fn mfa_guard_identifier(user_id: i64) -> String {
+    format!("mfa:{user_id}")
+}
+
+/// Check a TOTP code against the user's stored secret, returning the matched
+/// time step.
+async fn verify_totp_for_user(
+    state: &AppState,
+    user_id: i64,
+    secret_enc: &str,
+    code: &str,
+) -> Result<Option<i64>, ApiError> {
+    let secret =
+        paracord_core::mfa::open_totp_secret(&state.config.jwt_secret, user_id, secret_enc)
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(paracord_util::totp::verify_code(
+        &secret,
+        code,
+        Utc::now().timestamp(),
+        paracord_core::mfa::TOTP_WINDOW_STEPS,
+    ))
+}
+
+/// Verify either a TOTP code or a recovery code for a user with 2FA enabled.
+async fn verify_second_factor(
+    state: &AppState,
+    mfa: &paracord_db::user_mfa::UserMfaRow,
+    code: Option<&str>,
+    recovery_code: Option<&str>,
+) -> Result<Option<&'static str>, ApiError> {
+    if let Some(code) = code.map(str::trim).filter(|c| !c.is_empty()) {
+        let Some(secret_enc) = mfa.totp_secret_enc.as_deref() else {
+            return Ok(None);
+        };
+        let Some(step) = verify_totp_for_user(state, mfa.user_id, secret_enc, code).await? else {
+            return Ok(None);
+        };
+        // Recording the step rejects replay of a code that was already used.
+        let fresh = paracord_db::user_mfa::advance_totp_step(&state.db, mfa.user_id, step)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        return Ok(fresh.then_some("totp"));
+    }
+    if let Some(recovery_code) = recovery_code.map(str::trim).filter(|c| !c.is_empty()) {
+        let consumed = paracord_db::user_mfa::consume_recovery_code(
+            &state.db,
+            mfa.user_id,
+            &paracord_core::mfa::hash_recovery_code(recovery_code),
+            Utc::now(),
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        return Ok(consumed.then_some("recovery_code"));
+    }
+    Ok(None)
+}
+
+#[derive(Deserialize)]
+pub struct MfaLoginRequest {
+    pub ticket: String,
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+/// Second step of a password login for accounts with 2FA enabled.
+pub async fn complete_mfa_login(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(body): Json<MfaLoginRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let peer_ip = addr.ip().to_string();
+    let claims = paracord_core::auth::validate_mfa_ticket(&body.ticket, &state.config.jwt_keys)
+        .map_err(|_| ApiError::Unauthorized)?;
+    let guard_identifier = mfa_guard_identifier(claims.sub);
+    auth_guard_enforce(
+        &state,
+        &headers,
+        Some(peer_ip.as_str()),
+        Some(&guard_identifier),
+    )
+    .await?;
+
+    let mfa = paracord_db::user_mfa::get_user_mfa(&state.db, claims.sub)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|m| m.mfa_enabled)
+        .ok_or(ApiError::Unauthorized)?;
+    let method = verify_second_factor(
+        &state,
+        &mfa,
+        body.code.as_deref(),
+        body.recovery_code.as_deref(),
+    )
+    .await?;
+    let Some(method) = method else {
+        auth_guard_record_failure(
+            &state,
+            &headers,
+            Some(peer_ip.as_str()),
+            Some(&guard_identifier),
+        )
+        .await;
+        return Err(ApiError::Unauthorized);
+    };
+
+    let user = paracord_db::users::get_user_auth_by_id(&state.db, claims.sub)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Unauthorized)?;
+    let (token, access_cookie, refresh_cookie, session_id, raw_refresh) = issue_auth_session(
+        &state,
+        user.id,
+        user.public_key.as_deref(),
+        &headers,
+        Some(peer_ip.as_str()),
+    )
+    .await?;
+    security::log_security_event(
+        &state,
+        "auth.login.mfa",
+        Some(user.id),
+        Some(user.id),
+        Some(&session_id),
+        Some(&headers),
+        Some(json!({ "auth_method": "password", "mfa_method": method })),
+    )
+    .await;
+    auth_guard_record_success(
+        &state,
+        &headers,
+        Some(peer_ip.as_str()),
+        Some(&guard_identifier),
+    )
+    .await;
+
+    Ok((
+        AppendHeaders([
+            (header::SET_COOKIE, header_value(&access_cookie)?),
+            (header::SET_COOKIE, header_value(&refresh_cookie)?),
+        ]),
+        Json(AuthResponse {
+            token,
+            user: user_auth_json(&user),
+            refresh_token: Some(raw_refresh),
+        }),
+    ))
+}
+
+pub async fn get_totp_status(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Value>, ApiError> {
+    let mfa = paracord_db::user_mfa::get_user_mfa(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let remaining = if mfa.mfa_enabled {
+        paracord_db::user_mfa::count_unused_recovery_codes(&state.db, auth.user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    } else {
+        0
+    };
+    Ok(Json(json!({
+        "enabled": mfa.mfa_enabled,
+        "recovery_codes_remaining": remaining,
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct TotpEnrollRequest {
+    pub password: String,
+}
+
+/// Start TOTP enrollment. The secret is returned only here; it is stored
+/// encrypted and never exposed again once enrollment is confirmed.
+pub async fn enroll_totp(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    headers: HeaderMap,
+    Json(body): Json<TotpEnrollRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let user = paracord_db::users::get_user_auth_by_id(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    if user.password_hash.trim().is_empty()
+        || !paracord_core::auth::verify_password(&body.password, &user.password_hash)
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let secret = paracord_util::totp::generate_secret();
+    let sealed = paracord_core::mfa::seal_totp_secret(&state.config.jwt_secret, user.id, &secret)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let stored = paracord_db::user_mfa::set_pending_totp_secret(&state.db, user.id, &sealed)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !stored {
+        return Err(ApiError::Conflict(
+            "Two-factor authentication is already enabled".into(),
+        ));
+    }
+
+    let issuer = state.runtime.read().await.server_name.clone();
+    let account = format!("{}#{:04}", user.username, user.discriminator);
+    security::log_security_event(
+        &state,
+        "auth.mfa.enroll_started",
+        Some(user.id),
+        Some(user.id),
+        auth.session_id.as_deref(),
+        Some(&headers),
+        None,
+    )
+    .await;
+    Ok(Json(json!({
+        "secret": paracord_util::totp::base32_encode(&secret),
+        "otpauth_uri": paracord_util::totp::provisioning_uri(&secret, &issuer, &account),
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct TotpConfirmRequest {
+    pub code: String,
+}
+
+/// Confirm enrollment with a code from the authenticator app, enabling 2FA
+/// and returning a fresh set of recovery codes (shown once).
+pub async fn confirm_totp(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    headers: HeaderMap,
+    Json(body): Json<TotpConfirmRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let mfa = paracord_db::user_mfa::get_user_mfa(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    if mfa.mfa_enabled {
+        return Err(ApiError::Conflict(
+            "Two-factor authentication is already enabled".into(),
+        ));
+    }
+    let secret_enc = mfa
+        .totp_secret_enc
+        .as_deref()
+        .ok_or_else(|| ApiError::BadRequest("No pending two-factor enrollment".into()))?;
+    let step = verify_totp_for_user(&state, auth.user_id, secret_enc, &body.code)
+        .await?
+        .ok_or_else(|| ApiError::BadRequest("Invalid two-factor code".into()))?;
+
+    let codes = paracord_core::mfa::generate_recovery_codes();
+    let hashed: Vec<(i64, String)> = codes
+        .iter()
+        .map(|code| {
+            (
+                paracord_util::snowflake::generate(),
+                paracord_core::mfa::hash_recovery_code(code),
+            )
+        })
+        .collect();
+    let enabled = paracord_db::user_mfa::enable_totp(&state.db, auth.user_id, step, &hashed)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !enabled {
+        return Err(ApiError::Conflict(
+            "Two-factor enrollment changed, please try again".into(),
+        ));
+    }
+
+    security::log_security_event(
+        &state,
+        "auth.mfa.enabled",
+        Some(auth.user_id),
+        Some(auth.user_id),
+        auth.session_id.as_deref(),
+        Some(&headers),
+        None,
+    )
+    .await;
+    Ok(Json(json!({ "enabled": true, "recovery_codes": codes })))
+}
+
+#[derive(Deserialize)]
+pub struct TotpDisableRequest {
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+pub async fn disable_totp(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    headers: HeaderMap,
+    Json(body): Json<TotpDisableRequest>,
+) -> Result<StatusCode, ApiError> {
+    let mfa = paracord_db::user_mfa::get_user_mfa(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    if !mfa.mfa_enabled {
+        // Abandon a pending enrollment, if any.
+        paracord_db::user_mfa::disable_totp(&state.db, auth.user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    let method = verify_second_factor(
+        &state,
+        &mfa,
+        body.code.as_deref(),
+        body.recovery_code.as_deref(),
+    )
+    .await?;
+    if method.is_none() {
+        return Err(ApiError::BadRequest("Invalid two-factor code".into()));
+    }
+
+    paracord_db::user_mfa::disable_totp(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    security::log_security_event(
+        &state,
+        "auth.mfa.disabled",
+        Some(auth.user_id),
+        Some(auth.user_id),
+        auth.session_id.as_deref(),
+        Some(&headers),
+        None,
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        auth_guard_keys, build_refresh_cookie, get_cookie_value, normalize_email_for_auth,
-        parse_login_form_value, parse_login_json_value, parse_login_request,
-        parse_username_with_discriminator, resolve_server_origin,
+        auth_guard_keys, build_refresh_cookie, email_verification_link, get_cookie_value,
+        normalize_email_for_auth, parse_login_form_value, parse_login_json_value,
+        parse_login_request, parse_username_with_discriminator, resolve_server_origin,
         should_use_secure_cookie_with_public_url, synthesized_local_email,
         username_login_effective, HeaderMap, LoginRequest,
     };
@@ -1649,6 +2385,16 @@ mod tests {
         assert_eq!(parsed.as_deref(), Some("token-value"));
     }
 
+    #[test]
+    fn email_verification_link_needs_a_public_url() {
+        assert_eq!(
+            email_verification_link(Some("https://chat.example.com/"), "abc").as_deref(),
+            Some("https://chat.example.com/api/v1/auth/verify-email?token=abc")
+        );
+        assert_eq!(email_verification_link(None, "abc"), None);
+        assert_eq!(email_verification_link(Some("  "), "abc"), None);
+    }
+
     #[test]
     fn normalizes_email_to_ascii_lowercase_and_trimmed() {
         assert_eq!(
@@ -1,9 +1,10 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
 use paracord_core::AppState;
+use paracord_models::audit_log::AuditAction;
 use serde::Deserialize;
 use serde_json::{json, Value};
 
@@ -12,6 +13,7 @@ use crate::middleware::AuthUser;
 use crate::routes::audit;
 
 const MAX_BAN_REASON_LEN: usize = 512;
+const MAX_BAN_LIST_LIMIT: i64 = 1000;
 
 fn contains_dangerous_markup(value: &str) -> bool {
     let lower = value.to_ascii_lowercase();
@@ -22,10 +24,22 @@ fn contains_dangerous_markup(value: &str) -> bool {
         || lower.contains("<iframe")
 }
 
+#[derive(Deserialize)]
+pub struct ListBansQuery {
+    pub limit: Option<i64>,
+    pub after: Option<i64>,
+    /// Only bans placed by this moderator.
+    pub banned_by: Option<i64>,
+    /// Case-insensitive prefix of the ban reason.
+    pub reason: Option<String>,
+}
+
+/// One page of bans as `{ bans, has_more }`, filtered or not.
 pub async fn list_bans(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(guild_id): Path<i64>,
+    Query(params): Query<ListBansQuery>,
 ) -> Result<Json<Value>, ApiError> {
     // Verify user has BAN_MEMBERS permission
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
@@ -33,42 +47,70 @@ pub async fn list_bans(
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .ok_or(ApiError::NotFound)?;
 
-    let roles = paracord_db::roles::get_member_roles(&state.db, auth.user_id, guild_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let perms = paracord_core::permissions::compute_permissions_from_roles(
-        &roles,
+    let perms = paracord_core::permissions::compute_guild_permissions(
+        &state.db,
+        guild_id,
         guild.owner_id,
         auth.user_id,
-    );
+    )
+    .await?;
     paracord_core::permissions::require_permission(
         perms,
         paracord_models::permissions::Permissions::BAN_MEMBERS,
     )?;
 
-    let bans = paracord_db::bans::get_guild_bans(&state.db, guild_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let limit = params
+        .limit
+        .unwrap_or(MAX_BAN_LIST_LIMIT)
+        .clamp(1, MAX_BAN_LIST_LIMIT);
+    let filter = paracord_db::bans::BanListFilter {
+        banned_by: params.banned_by,
+        reason_query: params
+            .reason
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .map(str::to_string),
+    };
+    let page =
+        paracord_db::bans::list_guild_bans(&state.db, guild_id, &filter, limit, params.after)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
-    let result: Vec<Value> = bans
+    let result: Vec<Value> = page
+        .bans
         .iter()
-        .map(|b| {
+        .map(|entry| {
+            let b = &entry.ban;
             json!({
                 "user_id": b.user_id.to_string(),
                 "guild_id": guild_id.to_string(),
                 "reason": b.reason,
                 "banned_by": b.banned_by.map(|id| id.to_string()),
                 "created_at": b.created_at.to_rfc3339(),
+                "expires_at": b.expires_at.map(|v| v.to_rfc3339()),
+                "user": {
+                    "id": b.user_id.to_string(),
+                    "username": entry.username,
+                    "discriminator": entry.discriminator,
+                    "avatar_hash": entry.avatar_hash,
+                },
             })
         })
         .collect();
 
-    Ok(Json(json!(result)))
+    Ok(Json(json!({ "bans": result, "has_more": page.has_more })))
 }
 
 #[derive(Deserialize)]
 pub struct BanRequest {
     pub reason: Option<String>,
+    /// Lift the ban automatically after this many seconds. Omit for a
+    /// permanent ban.
+    pub duration_seconds: Option<i64>,
+    /// Also delete the member's messages from this many seconds back, up to
+    /// seven days.
+    pub delete_message_seconds: Option<i64>,
 }
 
 pub async fn ban_member(
@@ -76,8 +118,10 @@ pub async fn ban_member(
     auth: AuthUser,
     Path((guild_id, user_id)): Path<(i64, i64)>,
     body: Option<Json<BanRequest>>,
-) -> Result<StatusCode, ApiError> {
-    let reason = body.and_then(|b| b.0.reason);
+) -> Result<Json<Value>, ApiError> {
+    let (reason, duration_seconds, delete_message_seconds) = body
+        .map(|Json(b)| (b.reason, b.duration_seconds, b.delete_message_seconds))
+        .unwrap_or_default();
     if let Some(reason_text) = reason.as_deref() {
         if reason_text.trim().len() > MAX_BAN_REASON_LEN {
             return Err(ApiError::BadRequest("Ban reason is too long".into()));
@@ -88,14 +132,35 @@ pub async fn ban_member(
             ));
         }
     }
-    paracord_core::admin::ban_member(
+    let deleted_messages = paracord_core::admin::ban_member(
         &state.db,
         guild_id,
         auth.user_id,
         user_id,
         reason.as_deref(),
+        duration_seconds,
+        delete_message_seconds.unwrap_or(0),
     )
     .await?;
+    paracord_core::permissions::invalidate_member(&state.permission_cache, guild_id, user_id).await;
+
+    let mut deleted_by_channel: Vec<(i64, Vec<String>)> = Vec::new();
+    for (channel_id, message_id) in &deleted_messages {
+        match deleted_by_channel.last_mut() {
+            Some((current, ids)) if current == channel_id => ids.push(message_id.to_string()),
+            _ => deleted_by_channel.push((*channel_id, vec![message_id.to_string()])),
+        }
+    }
+    for (channel_id, ids) in deleted_by_channel {
+        state.event_bus.dispatch(
+            "MESSAGE_DELETE_BULK",
+            json!({
+                "channel_id": channel_id.to_string(),
+                "ids": ids,
+            }),
+            Some(guild_id),
+        );
+    }
 
     state.event_bus.dispatch(
         "GUILD_BAN_ADD",
@@ -115,6 +180,13 @@ pub async fn ban_member(
         }),
         Some(guild_id),
     );
+    crate::routes::channels::announce_member_event(
+        &state,
+        guild_id,
+        user_id,
+        paracord_core::system_messages::MemberEvent::Ban,
+    )
+    .await;
 
     if paracord_federation::is_enabled() {
         let fed_state = state.clone();
@@ -133,14 +205,21 @@ pub async fn ban_member(
         &state,
         guild_id,
         auth.user_id,
-        audit::ACTION_MEMBER_BAN_ADD,
+        AuditAction::MemberBanAdd,
         Some(user_id),
         reason.as_deref(),
-        None,
+        Some(json!({
+            "duration_seconds": duration_seconds,
+            "deleted_message_count": deleted_messages.len(),
+        })),
     )
     .await;
 
-    Ok(StatusCode::NO_CONTENT)
+    Ok(Json(json!({
+        "guild_id": guild_id.to_string(),
+        "user_id": user_id.to_string(),
+        "deleted_message_count": deleted_messages.len(),
+    })))
 }
 
 pub async fn unban_member(
@@ -162,7 +241,7 @@ pub async fn unban_member(
         &state,
         guild_id,
         auth.user_id,
-        audit::ACTION_MEMBER_BAN_REMOVE,
+        AuditAction::MemberBanRemove,
         Some(user_id),
         None,
         None,
@@ -171,3 +250,51 @@ pub async fn unban_member(
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+const BAN_EXPIRY_BATCH: i64 = 256;
+
+/// Lift temporary bans that have run out, announcing and auditing each one
+/// as an unban. Returns how many bans were lifted.
+pub async fn lift_expired_bans_once(state: &AppState) -> Result<usize, paracord_db::DbError> {
+    let now = chrono::Utc::now();
+    let expired = paracord_db::bans::get_expired_bans(&state.db, now, BAN_EXPIRY_BATCH).await?;
+    let mut lifted = 0;
+    for ban in expired {
+        // Skip bans that were lifted or replaced since the scan.
+        if !paracord_db::bans::delete_expired_ban(&state.db, ban.user_id, ban.guild_id, now).await?
+        {
+            continue;
+        }
+        lifted += 1;
+
+        state.event_bus.dispatch(
+            "GUILD_BAN_REMOVE",
+            json!({
+                "guild_id": ban.guild_id.to_string(),
+                "user_id": ban.user_id.to_string(),
+            }),
+            Some(ban.guild_id),
+        );
+
+        // Attribute the unban to whoever placed the ban, else the owner.
+        let actor_id = match ban.banned_by {
+            Some(id) => Some(id),
+            None => paracord_db::guilds::get_guild(&state.db, ban.guild_id)
+                .await?
+                .map(|guild| guild.owner_id),
+        };
+        if let Some(actor_id) = actor_id {
+            audit::log_action(
+                state,
+                ban.guild_id,
+                actor_id,
+                AuditAction::MemberBanRemove,
+                Some(ban.user_id),
+                Some("Ban expired"),
+                Some(json!({ "expired": true })),
+            )
+            .await;
+        }
+    }
+    Ok(lifted)
+}
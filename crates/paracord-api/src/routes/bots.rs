@@ -1,6 +1,6 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     Json,
 };
 use paracord_core::AppState;
@@ -12,6 +12,7 @@ use url::Url;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
+use crate::routes::security;
 
 const MAX_BOT_NAME_LEN: usize = 80;
 const MAX_BOT_DESCRIPTION_LEN: usize = 400;
@@ -29,11 +30,7 @@ fn contains_dangerous_markup(value: &str) -> bool {
 fn generate_secure_token() -> String {
     let mut bytes = [0_u8; 32];
     rand::rngs::OsRng.fill_bytes(&mut bytes);
-    let mut out = String::with_capacity(bytes.len() * 2);
-    for b in bytes {
-        out.push_str(&format!("{:02x}", b));
-    }
-    out
+    paracord_util::hex::hex_encode(&bytes)
 }
 
 fn parse_permission_bits(raw: &str, field_name: &str) -> Result<i64, ApiError> {
@@ -85,6 +82,33 @@ fn validate_redirect_uri(raw: &str) -> Result<String, ApiError> {
     Ok(trimmed.to_string())
 }
 
+/// Interactions are POSTed to this URL, so it has to be an https URL that the
+/// outbound guard would let us reach. An empty value clears it.
+fn validate_interactions_endpoint_url(raw: &str) -> Result<String, ApiError> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Ok(String::new());
+    }
+    if trimmed.len() > MAX_REDIRECT_URI_LEN {
+        return Err(ApiError::BadRequest(
+            "interactions_endpoint_url too long".into(),
+        ));
+    }
+    let parsed = Url::parse(trimmed).map_err(|_| {
+        ApiError::BadRequest("interactions_endpoint_url is not a valid URL".into())
+    })?;
+    if parsed.scheme() != "https"
+        || !parsed.username().is_empty()
+        || parsed.password().is_some()
+        || !paracord_core::outbound::is_fetchable_url(&parsed)
+    {
+        return Err(ApiError::BadRequest(
+            "interactions_endpoint_url must be a public https URL".into(),
+        ));
+    }
+    Ok(trimmed.to_string())
+}
+
 fn bot_app_to_json(
     row: &paracord_db::bot_applications::BotApplicationRow,
     token: Option<&str>,
@@ -97,6 +121,11 @@ fn bot_app_to_json(
         "bot_user_id": row.bot_user_id.to_string(),
         "redirect_uri": row.redirect_uri,
         "permissions": row.permissions.to_string(),
+        "interactions_endpoint_url": row.interactions_endpoint_url,
+        "verify_key": row
+            .interactions_signing_key
+            .as_deref()
+            .and_then(paracord_core::commands::verify_key_hex),
         "created_at": row.created_at.to_rfc3339(),
         "updated_at": row.updated_at.to_rfc3339(),
     });
@@ -118,11 +147,13 @@ async fn ensure_manage_guild(
 
     paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
 
-    let roles = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let perms =
-        paracord_core::permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
+    let perms = paracord_core::permissions::compute_guild_permissions(
+        &state.db,
+        guild_id,
+        guild.owner_id,
+        user_id,
+    )
+    .await?;
     paracord_core::permissions::require_permission(perms, Permissions::MANAGE_GUILD)?;
     Ok(())
 }
@@ -174,8 +205,8 @@ pub async fn create_bot_application(
         .transpose()?
         .unwrap_or(0);
 
-    let app_id = paracord_util::snowflake::generate(1);
-    let bot_user_id = paracord_util::snowflake::generate(1);
+    let app_id = paracord_util::snowflake::generate();
+    let bot_user_id = paracord_util::snowflake::generate();
     let bot_username = format!("bot-{}", app_id);
     let bot_email = format!("bot-{}@bots.paracord.local", bot_user_id);
     let discriminator = ((bot_user_id % 9000) + 1000) as i16;
@@ -291,6 +322,9 @@ pub struct UpdateBotApplicationRequest {
     pub name: Option<String>,
     pub description: Option<String>,
     pub redirect_uri: Option<String>,
+    pub interactions_endpoint_url: Option<String>,
+    /// Upper bound on what the bot may do in any space, whatever its roles.
+    pub permissions: Option<String>,
 }
 
 pub async fn update_bot_application(
@@ -335,16 +369,39 @@ pub async fn update_bot_application(
         .as_deref()
         .map(validate_redirect_uri)
         .transpose()?;
+    let interactions_endpoint_url = body
+        .interactions_endpoint_url
+        .as_deref()
+        .map(validate_interactions_endpoint_url)
+        .transpose()?;
+    let permissions = body
+        .permissions
+        .as_deref()
+        .map(|v| parse_permission_bits(v, "permissions"))
+        .transpose()?;
 
-    let updated = paracord_db::bot_applications::update_bot_application(
+    let mut updated = paracord_db::bot_applications::update_bot_application(
         &state.db,
         bot_app_id,
         body.name.as_deref().map(str::trim),
         body.description.as_deref().map(str::trim),
         redirect_uri.as_deref(),
+        interactions_endpoint_url.as_deref(),
+        permissions,
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    // Give the owner the verify key as soon as deliveries can be signed.
+    if updated.interactions_endpoint_url.is_some() && updated.interactions_signing_key.is_none() {
+        let key =
+            paracord_core::commands::interactions_signing_key(&state.db, updated.id, None).await?;
+        updated.interactions_signing_key = Some(paracord_util::hex::hex_encode(key.as_bytes()));
+    }
+
+    if updated.permissions != app.permissions {
+        paracord_core::permissions::invalidate_user(&state.permission_cache, app.bot_user_id)
+            .await;
+    }
 
     Ok(Json(bot_app_to_json(&updated, None)))
 }
@@ -368,6 +425,12 @@ pub async fn delete_bot_application(
     for install in installs {
         let _ =
             paracord_db::members::remove_member(&state.db, app.bot_user_id, install.guild_id).await;
+        paracord_core::permissions::invalidate_member(
+            &state.permission_cache,
+            install.guild_id,
+            app.bot_user_id,
+        )
+        .await;
         state.member_index.remove_member(install.guild_id, app.bot_user_id);
         state.event_bus.dispatch(
             "GUILD_MEMBER_REMOVE",
@@ -386,10 +449,13 @@ pub async fn delete_bot_application(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Issue a new bot token. The old hash is overwritten, so the previous token
+/// stops authenticating immediately.
 pub async fn regenerate_bot_token(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(bot_app_id): Path<i64>,
+    headers: HeaderMap,
 ) -> Result<Json<Value>, ApiError> {
     let app = paracord_db::bot_applications::get_bot_application(&state.db, bot_app_id)
         .await
@@ -405,6 +471,18 @@ pub async fn regenerate_bot_token(
         paracord_db::bot_applications::regenerate_bot_token(&state.db, bot_app_id, &token_hash)
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    security::log_security_event(
+        &state,
+        "bot.token.regenerate",
+        Some(auth.user_id),
+        Some(app.bot_user_id),
+        auth.session_id.as_deref(),
+        Some(&headers),
+        Some(json!({ "application_id": bot_app_id.to_string() })),
+    )
+    .await;
+
     Ok(Json(bot_app_to_json(&updated, Some(&token))))
 }
 
@@ -485,6 +563,12 @@ pub async fn remove_guild_bot(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
     let _ = paracord_db::members::remove_member(&state.db, app.bot_user_id, guild_id).await;
+    paracord_core::permissions::invalidate_member(
+        &state.permission_cache,
+        guild_id,
+        app.bot_user_id,
+    )
+    .await;
 
     state.member_index.remove_member(guild_id, app.bot_user_id);
     state.event_bus.dispatch(
@@ -569,6 +653,8 @@ pub async fn oauth2_authorize(
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
     let _ = paracord_db::members::add_member(&state.db, app.bot_user_id, guild_id).await;
+    paracord_core::permissions::invalidate_member(&state.permission_cache, guild_id, app.bot_user_id)
+        .await;
     state.member_index.add_member(guild_id, app.bot_user_id);
 
     let user_row = paracord_db::users::get_user_by_id(&state.db, app.bot_user_id)
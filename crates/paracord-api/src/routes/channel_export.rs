@@ -0,0 +1,233 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, State},
+    http::{header, HeaderMap},
+    response::Response,
+};
+use futures_util::stream;
+use paracord_core::{AppState, MESSAGE_FLAG_DM_E2EE};
+use paracord_db::attachments::AttachmentRow;
+use paracord_db::messages::MessageRow;
+use paracord_models::permissions::Permissions;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::error::ApiError;
+use crate::middleware::AuthUser;
+use crate::routes::security;
+
+/// Messages fetched per keyset page while streaming an export.
+const EXPORT_PAGE_SIZE: i64 = 500;
+/// Upper bound on attachment rows loaded for one page of messages.
+const EXPORT_ATTACHMENTS_PER_PAGE: i64 = EXPORT_PAGE_SIZE * 10;
+
+/// Only guild channels can be exported; DM history stays with its
+/// participants. Server admins may export any guild channel; otherwise the
+/// user needs MANAGE_GUILD in the guild and must be able to read the channel.
+async fn require_export_access(
+    state: &AppState,
+    user_id: i64,
+    channel: &paracord_db::channels::ChannelRow,
+) -> Result<(), ApiError> {
+    let guild_id = channel.guild_id().ok_or(ApiError::Forbidden)?;
+    let user = paracord_db::users::get_user_by_id(&state.db, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Unauthorized)?;
+    if paracord_core::is_admin(user.flags) {
+        return Ok(());
+    }
+
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let perms = paracord_core::permissions::compute_guild_permissions(
+        &state.db,
+        guild_id,
+        guild.owner_id,
+        user_id,
+    )
+    .await?;
+    paracord_core::permissions::require_permission(perms, Permissions::MANAGE_GUILD)?;
+    let channel_perms = paracord_core::permissions::compute_channel_permissions(
+        &state.db,
+        guild_id,
+        channel.id,
+        guild.owner_id,
+        user_id,
+    )
+    .await?;
+    paracord_core::permissions::require_permission(
+        channel_perms,
+        Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY,
+    )?;
+    Ok(())
+}
+
+struct ExportStreamState {
+    app_state: AppState,
+    channel_id: i64,
+    after: Option<i64>,
+    done: bool,
+    authors: HashMap<i64, Value>,
+}
+
+/// Stream the full message history of a channel as newline-delimited JSON,
+/// oldest first. Messages are read in keyset pages with their attachments
+/// loaded per page, so memory use does not grow with the channel.
+pub async fn export_channel(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    headers: HeaderMap,
+    Path(channel_id): Path<i64>,
+) -> Result<Response, ApiError> {
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    require_export_access(&state, auth.user_id, &channel).await?;
+
+    security::log_security_event(
+        &state,
+        "channel.export",
+        Some(auth.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({
+            "channel_id": channel_id.to_string(),
+            "guild_id": channel.guild_id().map(|id| id.to_string()),
+        })),
+    )
+    .await;
+
+    let stream_state = ExportStreamState {
+        app_state: state,
+        channel_id,
+        after: None,
+        done: false,
+        authors: HashMap::new(),
+    };
+    let body_stream = stream::unfold(stream_state, |mut st| async move {
+        if st.done {
+            return None;
+        }
+
+        let (messages, attachments) = match load_page(&st).await {
+            Ok(page) => page,
+            Err(err) => {
+                tracing::warn!("export of channel {} failed: {}", st.channel_id, err);
+                st.done = true;
+                return Some((Err(std::io::Error::other(err.to_string())), st));
+            }
+        };
+        st.after = Some(messages.last()?.id);
+        st.done = (messages.len() as i64) < EXPORT_PAGE_SIZE;
+
+        let mut attachments_by_message: HashMap<i64, Vec<Value>> = HashMap::new();
+        for attachment in &attachments {
+            if let Some(message_id) = attachment.message_id {
+                attachments_by_message
+                    .entry(message_id)
+                    .or_default()
+                    .push(attachment_json(attachment));
+            }
+        }
+
+        let mut chunk = String::new();
+        for message in &messages {
+            let author = resolve_author(&st.app_state, &mut st.authors, message.author_id).await;
+            let attachments = attachments_by_message
+                .remove(&message.id)
+                .unwrap_or_default();
+            chunk.push_str(&export_json_line(message, author, attachments).to_string());
+            chunk.push('\n');
+        }
+        Some((Ok(Bytes::from(chunk)), st))
+    });
+
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"channel-{channel_id}.ndjson\""),
+        )
+        .body(Body::from_stream(body_stream))
+        .unwrap())
+}
+
+async fn load_page(
+    st: &ExportStreamState,
+) -> Result<(Vec<MessageRow>, Vec<AttachmentRow>), paracord_db::DbError> {
+    let messages = paracord_db::messages::list_channel_messages_after(
+        &st.app_state.db,
+        st.channel_id,
+        st.after,
+        EXPORT_PAGE_SIZE,
+    )
+    .await?;
+    let message_ids: Vec<i64> = messages.iter().map(|m| m.id).collect();
+    let attachments = paracord_db::attachments::get_attachments_for_message_ids(
+        &st.app_state.db,
+        &message_ids,
+        EXPORT_ATTACHMENTS_PER_PAGE,
+    )
+    .await?;
+    Ok((messages, attachments))
+}
+
+async fn resolve_author(state: &AppState, cache: &mut HashMap<i64, Value>, user_id: i64) -> Value {
+    if let Some(author) = cache.get(&user_id) {
+        return author.clone();
+    }
+    let author = match paracord_db::users::get_user_by_id(&state.db, user_id).await {
+        Ok(Some(user)) => json!({
+            "id": user.id.to_string(),
+            "username": user.username,
+            "discriminator": user.discriminator,
+            "display_name": user.display_name,
+        }),
+        _ => json!({
+            "id": user_id.to_string(),
+            "username": null,
+            "discriminator": 0,
+            "display_name": null,
+        }),
+    };
+    cache.insert(user_id, author.clone());
+    author
+}
+
+fn attachment_json(attachment: &AttachmentRow) -> Value {
+    json!({
+        "id": attachment.id.to_string(),
+        "filename": attachment.filename,
+        "content_type": attachment.content_type,
+        "size": attachment.size,
+        "url": attachment.url,
+        "width": attachment.width,
+        "height": attachment.height,
+        "content_hash": attachment.content_hash,
+    })
+}
+
+fn export_json_line(message: &MessageRow, author: Value, attachments: Vec<Value>) -> Value {
+    // End-to-end encrypted DM content is ciphertext the server cannot read;
+    // export it as absent rather than as an opaque blob.
+    let encrypted = (message.flags & MESSAGE_FLAG_DM_E2EE) != 0;
+    json!({
+        "id": message.id.to_string(),
+        "channel_id": message.channel_id.to_string(),
+        "author": author,
+        "content": if encrypted { None } else { message.content.as_deref() },
+        "encrypted": encrypted,
+        "type": message.message_type,
+        "flags": message.flags,
+        "pinned": message.pinned,
+        "reference_id": message.reference_id.map(|id| id.to_string()),
+        "created_at": message.created_at.to_rfc3339(),
+        "edited_at": message.edited_at.map(|t| t.to_rfc3339()),
+        "attachments": attachments,
+    })
+}
@@ -3,22 +3,50 @@ use axum::{
     http::StatusCode,
     Json,
 };
+use paracord_core::rate_limit::SlowmodeReservation;
 use paracord_core::{AppState, MESSAGE_FLAG_DM_E2EE};
+use paracord_db::messages::PinOutcome;
+use paracord_db::read_states::UnreadMarker;
+use paracord_models::audit_log::AuditAction;
+use paracord_models::channel::ChannelType;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
-use crate::routes::audit;
+use crate::routes::{audit, files};
 
 const MAX_CHANNEL_TOPIC_LEN: usize = 1_024;
-const MAX_BULK_DELETE_REQUEST_IDS: usize = 500;
+const MAX_BULK_DELETE_REQUEST_IDS: usize = 100;
+const DEFAULT_MESSAGE_PAGE_LIMIT: i64 = 50;
+const MAX_MESSAGE_PAGE_LIMIT: i64 = 100;
+/// Messages older than this are skipped by bulk delete.
+const BULK_DELETE_MAX_AGE_DAYS: i64 = 14;
 const MAX_POLL_QUESTION_LEN: usize = 300;
 const MAX_POLL_OPTION_LEN: usize = 100;
 const MAX_POLL_OPTIONS: usize = 10;
 const MAX_POLL_DURATION_MINUTES: i64 = 60 * 24 * 14; // 14 days
 const MAX_MESSAGE_NONCE_LEN: usize = 64;
+/// Characters of the replied-to message included in a reply preview.
+const REPLY_EXCERPT_CHARS: usize = 100;
+/// Channel fields compared when recording audit log changes.
+const CHANNEL_AUDIT_FIELDS: &[&str] = &[
+    "name",
+    "type",
+    "topic",
+    "parent_id",
+    "position",
+    "nsfw",
+    "rate_limit_per_user",
+    "bitrate",
+    "user_limit",
+    "required_role_ids",
+    "message_retention_days",
+];
+/// Overwrite fields compared when recording audit log changes.
+const OVERWRITE_AUDIT_FIELDS: &[&str] = &["allow_perms", "deny_perms"];
 
 fn contains_dangerous_markup(value: &str) -> bool {
     let lower = value.to_ascii_lowercase();
@@ -36,6 +64,10 @@ pub struct CreateChannelRequest {
     pub channel_type: i16,
     pub parent_id: Option<i64>,
     pub required_role_ids: Option<Vec<String>>,
+    /// Voice channels only.
+    pub bitrate: Option<i32>,
+    /// Voice channels only; 0 means unlimited.
+    pub user_limit: Option<i32>,
 }
 
 #[derive(Deserialize)]
@@ -43,11 +75,19 @@ pub struct UpdateChannelRequest {
     pub name: Option<String>,
     pub topic: Option<String>,
     pub required_role_ids: Option<Vec<String>>,
+    /// Slowmode: seconds each member must wait between messages (0 disables).
+    pub rate_limit_per_user: Option<i32>,
+    /// Delete messages older than this many days (0 keeps them forever).
+    pub message_retention_days: Option<i32>,
 }
 
+/// Message history cursors. At most one of `before`, `after` and `around`
+/// may be given; pages are always returned newest first.
 #[derive(Deserialize)]
 pub struct MessageQuery {
     pub before: Option<i64>,
+    pub after: Option<i64>,
+    pub around: Option<i64>,
     pub limit: Option<i64>,
 }
 
@@ -55,6 +95,11 @@ pub struct MessageQuery {
 pub struct MessageSearchQuery {
     pub q: String,
     pub limit: Option<i64>,
+    pub author_id: Option<i64>,
+    /// RFC 3339 lower bound (inclusive) on the message creation time.
+    pub after: Option<chrono::DateTime<chrono::Utc>>,
+    /// RFC 3339 upper bound (exclusive) on the message creation time.
+    pub before: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(Deserialize)]
@@ -140,6 +185,8 @@ pub fn channel_to_json(c: &paracord_db::channels::ChannelRow) -> Value {
         "parent_id": c.parent_id.map(|id| id.to_string()),
         "nsfw": c.nsfw,
         "rate_limit_per_user": c.rate_limit_per_user,
+        "bitrate": c.bitrate,
+        "user_limit": c.user_limit,
         "last_message_id": c.last_message_id.map(|id| id.to_string()),
         "required_role_ids": required_role_ids,
         "thread_metadata": thread_metadata,
@@ -147,6 +194,7 @@ pub fn channel_to_json(c: &paracord_db::channels::ChannelRow) -> Value {
         "message_count": c.message_count,
         "applied_tags": applied_tags,
         "default_sort_order": c.default_sort_order,
+        "message_retention_days": c.message_retention_days,
         "created_at": c.created_at.to_rfc3339(),
     })
 }
@@ -183,14 +231,13 @@ async fn normalize_required_role_ids(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .ok_or(ApiError::NotFound)?;
-    let actor_roles = paracord_db::roles::get_member_roles(&state.db, actor_id, guild_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let actor_perms = paracord_core::permissions::compute_permissions_from_roles(
-        &actor_roles,
+    let actor_perms = paracord_core::permissions::compute_guild_permissions(
+        &state.db,
+        guild_id,
         guild.owner_id,
         actor_id,
-    );
+    )
+    .await?;
     if !paracord_core::permissions::is_server_admin(actor_perms) {
         return Err(ApiError::Forbidden);
     }
@@ -219,7 +266,7 @@ async fn normalize_required_role_ids(
     ))
 }
 
-async fn ensure_channel_permissions(
+pub(crate) async fn ensure_channel_permissions(
     state: &AppState,
     channel: &paracord_db::channels::ChannelRow,
     user_id: i64,
@@ -231,7 +278,8 @@ async fn ensure_channel_permissions(
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
             .ok_or(ApiError::NotFound)?;
-        let perms = paracord_core::permissions::compute_channel_permissions(
+        let perms = paracord_core::permissions::compute_channel_permissions_cached(
+            &state.permission_cache,
             &state.db,
             guild_id,
             channel.id,
@@ -279,6 +327,54 @@ async fn author_to_json(state: &AppState, author_id: i64) -> Value {
     }
 }
 
+/// Minimal snapshot of the message a reply points at, so clients can render
+/// the preview without fetching it. A reference to a message that has since
+/// been deleted resolves to `{ "id", "deleted": true }`.
+async fn referenced_message_to_json(
+    state: &AppState,
+    reference_id: i64,
+    preload: &MessagePreload,
+) -> Value {
+    let Some(referenced) = preload.referenced.get(&reference_id) else {
+        return json!({
+            "id": reference_id.to_string(),
+            "deleted": true,
+            "author": null,
+            "content": null,
+        });
+    };
+
+    let is_dm_e2ee = (referenced.flags & MESSAGE_FLAG_DM_E2EE) != 0;
+    let excerpt = if is_dm_e2ee {
+        None
+    } else {
+        referenced.content.as_deref().map(|content| {
+            let mut chars = content.chars();
+            let mut excerpt: String = chars.by_ref().take(REPLY_EXCERPT_CHARS).collect();
+            if chars.next().is_some() {
+                excerpt.push('…');
+            }
+            excerpt
+        })
+    };
+    let attachment_count = preload
+        .referenced_attachment_counts
+        .get(&referenced.id)
+        .copied()
+        .unwrap_or(0);
+
+    json!({
+        "id": referenced.id.to_string(),
+        "channel_id": referenced.channel_id.to_string(),
+        "deleted": false,
+        "author": author_to_json(state, referenced.author_id).await,
+        "content": excerpt,
+        "e2ee": is_dm_e2ee,
+        "attachment_count": attachment_count,
+        "created_at": referenced.created_at.to_rfc3339(),
+    })
+}
+
 fn poll_to_json(poll: &paracord_db::polls::PollWithOptions) -> Value {
     let options: Vec<Value> = poll
         .options
@@ -308,10 +404,84 @@ fn poll_to_json(poll: &paracord_db::polls::PollWithOptions) -> Value {
     })
 }
 
+/// What [`render_message`] needs beyond the message row, loaded once for a
+/// whole page of messages rather than once per message.
+#[derive(Default)]
+struct MessagePreload {
+    embeds: HashMap<i64, Vec<paracord_core::link_preview::LinkEmbed>>,
+    /// Messages replied to, by id. A reply whose target is missing here
+    /// points at a deleted message.
+    referenced: HashMap<i64, paracord_db::messages::MessageRow>,
+    referenced_attachment_counts: HashMap<i64, i64>,
+}
+
+impl MessagePreload {
+    async fn load(
+        state: &AppState,
+        messages: &[&paracord_db::messages::MessageRow],
+    ) -> Result<Self, ApiError> {
+        let previewable: Vec<&paracord_db::messages::MessageRow> = messages
+            .iter()
+            .copied()
+            .filter(|msg| (msg.flags & MESSAGE_FLAG_DM_E2EE) == 0)
+            .collect();
+        let contents: Vec<&str> = previewable
+            .iter()
+            .map(|msg| msg.content.as_deref().unwrap_or_default())
+            .collect();
+        let embeds = paracord_core::link_preview::cached_embeds(&state.db, &contents)
+            .await
+            .unwrap_or_default();
+
+        let mut reference_ids: Vec<i64> =
+            messages.iter().filter_map(|msg| msg.reference_id).collect();
+        reference_ids.sort_unstable();
+        reference_ids.dedup();
+        let referenced = paracord_db::messages::get_messages_by_ids(&state.db, &reference_ids)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        let referenced_ids: Vec<i64> = referenced.iter().map(|msg| msg.id).collect();
+        let referenced_attachment_counts =
+            paracord_db::attachments::count_attachments_for_message_ids(&state.db, &referenced_ids)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+        Ok(Self {
+            embeds: previewable.iter().map(|msg| msg.id).zip(embeds).collect(),
+            referenced: referenced.into_iter().map(|msg| (msg.id, msg)).collect(),
+            referenced_attachment_counts,
+        })
+    }
+}
+
 async fn message_to_json(
     state: &AppState,
     msg: &paracord_db::messages::MessageRow,
     viewer_id: i64,
+) -> Result<Value, ApiError> {
+    let preload = MessagePreload::load(state, &[msg]).await?;
+    Ok(render_message(state, msg, viewer_id, &preload).await)
+}
+
+/// [`message_to_json`] for a page of messages, in order.
+async fn messages_to_json(
+    state: &AppState,
+    messages: &[&paracord_db::messages::MessageRow],
+    viewer_id: i64,
+) -> Result<Vec<Value>, ApiError> {
+    let preload = MessagePreload::load(state, messages).await?;
+    let mut result = Vec::with_capacity(messages.len());
+    for msg in messages {
+        result.push(render_message(state, msg, viewer_id, &preload).await);
+    }
+    Ok(result)
+}
+
+async fn render_message(
+    state: &AppState,
+    msg: &paracord_db::messages::MessageRow,
+    viewer_id: i64,
+    preload: &MessagePreload,
 ) -> Value {
     let is_dm_e2ee = (msg.flags & MESSAGE_FLAG_DM_E2EE) != 0;
     let e2ee_payload = if is_dm_e2ee {
@@ -333,10 +503,15 @@ async fn message_to_json(
     } else {
         None
     };
-    let content = if is_dm_e2ee {
-        Value::Null
+    let (content, entities, embeds) = if is_dm_e2ee {
+        (Value::Null, Vec::new(), Vec::new())
     } else {
-        json!(msg.content)
+        let text = msg.content.as_deref().unwrap_or_default();
+        (
+            json!(msg.content),
+            paracord_core::entities::parse_entities(text),
+            preload.embeds.get(&msg.id).cloned().unwrap_or_default(),
+        )
     };
 
     let author = author_to_json(state, msg.author_id).await;
@@ -358,26 +533,21 @@ async fn message_to_json(
         })
         .collect();
 
-    let reactions = paracord_db::reactions::get_message_reactions(&state.db, msg.id)
-        .await
-        .unwrap_or_default();
-    let mut reaction_json = Vec::with_capacity(reactions.len());
-    for reaction in reactions {
-        let me = paracord_db::reactions::get_reaction_users(
-            &state.db,
-            msg.id,
-            &reaction.emoji_name,
-            1000,
-        )
-        .await
-        .map(|users| users.contains(&viewer_id))
-        .unwrap_or(false);
-        reaction_json.push(json!({
-            "emoji": reaction.emoji_name,
-            "count": reaction.count,
-            "me": me,
-        }));
-    }
+    let reactions =
+        paracord_db::reactions::get_message_reaction_summary(&state.db, msg.id, viewer_id)
+            .await
+            .unwrap_or_default();
+    let reaction_json: Vec<Value> = reactions
+        .iter()
+        .map(|reaction| {
+            json!({
+                "emoji": reaction.emoji_name,
+                "emoji_id": reaction.emoji_id.map(|id| id.to_string()),
+                "count": reaction.count,
+                "me": reaction.me,
+            })
+        })
+        .collect();
 
     let poll_json = paracord_db::polls::get_message_poll(&state.db, msg.id, viewer_id)
         .await
@@ -385,33 +555,162 @@ async fn message_to_json(
         .flatten()
         .map(|poll| poll_to_json(&poll));
 
+    let referenced_message = match msg.reference_id {
+        Some(reference_id) => referenced_message_to_json(state, reference_id, preload).await,
+        None => Value::Null,
+    };
+
     json!({
         "id": msg.id.to_string(),
         "channel_id": msg.channel_id.to_string(),
         "author": author,
         "content": content,
+        "entities": entities,
+        "embeds": embeds,
         "e2ee": e2ee_payload,
         "pinned": msg.pinned,
+        "pinned_at": msg.pinned_at.map(|t| t.to_rfc3339()),
+        "pinned_by": msg.pinned_by.map(|id| id.to_string()),
         "type": msg.message_type,
         "message_type": msg.message_type,
         "timestamp": msg.created_at.to_rfc3339(),
         "created_at": msg.created_at.to_rfc3339(),
         "edited_timestamp": msg.edited_at.map(|t| t.to_rfc3339()),
         "edited_at": msg.edited_at.map(|t| t.to_rfc3339()),
+        "edited_count": msg.edit_count,
+        "last_edited_at": msg.edited_at.map(|t| t.to_rfc3339()),
         "reference_id": msg.reference_id.map(|id| id.to_string()),
+        "referenced_message": referenced_message,
         "attachments": attachment_json,
         "reactions": reaction_json,
         "poll": poll_json,
     })
 }
 
+/// Post a join/leave announcement to the guild's system channel, if it has
+/// one, and broadcast it. Failures are logged; the membership change itself
+/// has already happened.
+pub(crate) async fn announce_member_event(
+    state: &AppState,
+    guild_id: i64,
+    user_id: i64,
+    event: paracord_core::system_messages::MemberEvent,
+) {
+    let created = paracord_core::system_messages::create_member_event_message(
+        &state.db,
+        guild_id,
+        user_id,
+        event,
+        chrono::Utc::now().timestamp_millis(),
+    )
+    .await;
+    match created {
+        Ok(Some(msg)) => match message_to_json(state, &msg, user_id).await {
+            Ok(msg_json) => {
+                state
+                    .event_bus
+                    .dispatch("MESSAGE_CREATE", msg_json, Some(guild_id));
+            }
+            Err(e) => tracing::warn!(
+                guild_id,
+                "Failed to broadcast member {:?} announcement: {}",
+                event,
+                e
+            ),
+        },
+        Ok(None) => {}
+        Err(e) => tracing::warn!(
+            guild_id,
+            "Failed to post member {:?} announcement: {}",
+            event,
+            e
+        ),
+    }
+}
+
+/// Check that a bot may post `content` as its reply to a slash command in
+/// `channel`. Run before the interaction is marked responded, so a reply
+/// that cannot be posted does not spend it.
+pub(crate) async fn check_interaction_reply(
+    state: &AppState,
+    channel: &paracord_db::channels::ChannelRow,
+    bot_user_id: i64,
+    content: &str,
+) -> Result<(), ApiError> {
+    if content.trim().is_empty() {
+        return Err(ApiError::invalid_field("content", "Content must not be empty"));
+    }
+    validate_content_length(state, content)?;
+    if contains_dangerous_markup(content) {
+        return Err(ApiError::BadRequest(
+            "Message contains unsafe markup".into(),
+        ));
+    }
+    ensure_channel_permissions(
+        state,
+        channel,
+        bot_user_id,
+        &[Permissions::VIEW_CHANNEL, Permissions::SEND_MESSAGES],
+    )
+    .await
+}
+
+/// Post a bot's reply to a slash command in the channel it was invoked in
+/// and broadcast it. `interaction` describes the invocation and is attached
+/// to the broadcast message. The reply must have passed
+/// [`check_interaction_reply`].
+pub(crate) async fn create_interaction_reply(
+    state: &AppState,
+    channel: &paracord_db::channels::ChannelRow,
+    bot_user_id: i64,
+    content: &str,
+    interaction: Value,
+) -> Result<Value, ApiError> {
+    let msg = paracord_core::message::create_message_with_options(
+        &state.db,
+        paracord_util::snowflake::generate(),
+        channel.id,
+        bot_user_id,
+        content,
+        paracord_core::message::CreateMessageOptions {
+            message_type: paracord_models::message::MessageType::ChatInputCommand as i16,
+            max_content_length: state.config.max_message_length as usize,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let guild_id = channel.guild_id();
+    let mut msg_json = message_to_json(state, &msg, bot_user_id).await?;
+    msg_json["interaction"] = interaction;
+    state
+        .event_bus
+        .dispatch("MESSAGE_CREATE", msg_json.clone(), guild_id);
+    state.push.message_created(paracord_core::push::PushJob {
+        message_id: msg.id,
+        channel_id: channel.id,
+        guild_id,
+        author_id: bot_user_id,
+    });
+    state
+        .link_previews
+        .queue(paracord_core::link_preview::LinkPreviewJob {
+            message_id: msg.id,
+            channel_id: channel.id,
+            guild_id,
+        });
+    Ok(msg_json)
+}
+
 pub async fn create_channel(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(guild_id): Path<i64>,
     Json(body): Json<CreateChannelRequest>,
 ) -> Result<(StatusCode, Json<Value>), ApiError> {
-    let channel_id = paracord_util::snowflake::generate(1);
+    let channel_type = ChannelType::try_from(body.channel_type)
+        .map_err(|code| ApiError::BadRequest(format!("Unknown channel type {code}")))?;
+    let channel_id = paracord_util::snowflake::generate();
     let required_role_ids = match body.required_role_ids.as_deref() {
         Some(raw_role_ids) => {
             Some(normalize_required_role_ids(&state, guild_id, auth.user_id, raw_role_ids).await?)
@@ -425,9 +724,13 @@ pub async fn create_channel(
         auth.user_id,
         channel_id,
         &body.name,
-        body.channel_type,
-        body.parent_id,
-        required_role_ids.as_deref(),
+        paracord_core::channel::CreateChannelOptions {
+            channel_type,
+            parent_id: body.parent_id,
+            required_role_ids: required_role_ids.as_deref(),
+            bitrate: body.bitrate,
+            user_limit: body.user_limit,
+        },
     )
     .await?;
 
@@ -440,10 +743,10 @@ pub async fn create_channel(
         &state,
         guild_id,
         auth.user_id,
-        audit::ACTION_CHANNEL_CREATE,
+        AuditAction::ChannelCreate,
         Some(channel.id),
         None,
-        Some(json!({ "name": channel.name, "type": channel.channel_type })),
+        audit::diff_changes(None, Some(&channel_json), CHANNEL_AUDIT_FIELDS),
     )
     .await;
 
@@ -481,11 +784,11 @@ pub async fn update_channel(
         }
     }
 
-    let guild_id = paracord_db::channels::get_channel(&state.db, channel_id)
+    let before = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .and_then(|c| c.guild_id())
         .ok_or(ApiError::NotFound)?;
+    let guild_id = before.guild_id().ok_or(ApiError::NotFound)?;
     let required_role_ids = match body.required_role_ids.as_deref() {
         Some(raw_role_ids) => {
             Some(normalize_required_role_ids(&state, guild_id, auth.user_id, raw_role_ids).await?)
@@ -500,23 +803,38 @@ pub async fn update_channel(
         body.name.as_deref(),
         body.topic.as_deref(),
         required_role_ids.as_deref(),
+        body.rate_limit_per_user,
+        body.message_retention_days,
     )
     .await?;
+    if required_role_ids.is_some() {
+        paracord_core::permissions::invalidate_channel_and_threads(
+            &state.permission_cache,
+            &state.db,
+            channel_id,
+        )
+        .await?;
+    }
 
     let channel_json = channel_to_json(&updated);
 
     state
         .event_bus
         .dispatch("CHANNEL_UPDATE", channel_json.clone(), updated.guild_id());
-    if let Some(guild_id) = updated.guild_id() {
+    let changes = audit::diff_changes(
+        Some(&channel_to_json(&before)),
+        Some(&channel_json),
+        CHANNEL_AUDIT_FIELDS,
+    );
+    if changes.is_some() {
         audit::log_action(
             &state,
             guild_id,
             auth.user_id,
-            audit::ACTION_CHANNEL_UPDATE,
+            AuditAction::ChannelUpdate,
             Some(updated.id),
             None,
-            Some(json!({ "name": updated.name, "topic": updated.topic })),
+            changes,
         )
         .await;
     }
@@ -529,8 +847,10 @@ pub async fn delete_channel(
     auth: AuthUser,
     Path(channel_id): Path<i64>,
 ) -> Result<StatusCode, ApiError> {
-    let channel =
+    let (channel, removed) =
         paracord_core::channel::delete_channel(&state.db, channel_id, auth.user_id).await?;
+    paracord_core::permissions::invalidate_channel(&state.permission_cache, channel_id).await;
+    files::remove_stored_files(&state, &removed).await;
 
     state.event_bus.dispatch(
         "CHANNEL_DELETE",
@@ -542,10 +862,10 @@ pub async fn delete_channel(
             &state,
             guild_id,
             auth.user_id,
-            audit::ACTION_CHANNEL_DELETE,
+            AuditAction::ChannelDelete,
             Some(channel_id),
             None,
-            None,
+            audit::diff_changes(Some(&channel_to_json(&channel)), None, CHANNEL_AUDIT_FIELDS),
         )
         .await;
     }
@@ -571,21 +891,36 @@ pub async fn get_messages(
     )
     .await?;
 
-    let limit = params.limit.unwrap_or(50).min(100);
-    let messages = paracord_db::messages::get_channel_messages(
-        &state.db,
-        channel_id,
-        params.before,
-        None,
-        limit,
-    )
-    .await
+    let cursors = [params.before, params.after, params.around];
+    if cursors.iter().filter(|cursor| cursor.is_some()).count() > 1 {
+        return Err(ApiError::BadRequest(
+            "Only one of before, after or around may be given".into(),
+        ));
+    }
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_MESSAGE_PAGE_LIMIT)
+        .clamp(1, MAX_MESSAGE_PAGE_LIMIT);
+    let messages = match params.around {
+        Some(around) => {
+            paracord_db::messages::get_channel_messages_around(&state.db, channel_id, around, limit)
+                .await
+        }
+        None => {
+            paracord_db::messages::get_channel_messages(
+                &state.db,
+                channel_id,
+                params.before,
+                params.after,
+                limit,
+            )
+            .await
+        }
+    }
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
-    let mut result = Vec::new();
-    for msg in &messages {
-        result.push(message_to_json(&state, msg, auth.user_id).await);
-    }
+    let messages: Vec<_> = messages.iter().collect();
+    let result = messages_to_json(&state, &messages, auth.user_id).await?;
 
     Ok(Json(json!(result)))
 }
@@ -611,13 +946,20 @@ pub async fn search_messages(
     )
     .await?;
 
-    let limit = params.limit.unwrap_or(20).min(100);
-    let messages = paracord_db::messages::search_messages(&state.db, channel_id, &params.q, limit)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let mut result = Vec::with_capacity(messages.len());
-    for msg in &messages {
-        result.push(message_to_json(&state, msg, auth.user_id).await);
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
+    let filter = paracord_db::messages::MessageSearchFilter {
+        author_id: params.author_id,
+        after: params.after,
+        before: params.before,
+    };
+    let hits =
+        paracord_db::messages::search_messages(&state.db, channel_id, &params.q, &filter, limit)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let messages: Vec<_> = hits.iter().map(|hit| &hit.message).collect();
+    let mut result = messages_to_json(&state, &messages, auth.user_id).await?;
+    for (msg_json, hit) in result.iter_mut().zip(&hits) {
+        msg_json["excerpt"] = json!(hit.excerpt);
     }
     Ok(Json(json!(result)))
 }
@@ -634,9 +976,9 @@ pub async fn bulk_delete_messages(
         ));
     }
     if body.message_ids.len() > MAX_BULK_DELETE_REQUEST_IDS {
-        return Err(ApiError::BadRequest(
-            "Too many message_ids in one request".into(),
-        ));
+        return Err(ApiError::BadRequest(format!(
+            "message_ids may contain at most {MAX_BULK_DELETE_REQUEST_IDS} messages"
+        )));
     }
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
@@ -652,18 +994,38 @@ pub async fn bulk_delete_messages(
 
     let mut ids = Vec::with_capacity(body.message_ids.len());
     for raw in &body.message_ids {
-        ids.push(
-            raw.parse::<i64>()
-                .map_err(|_| ApiError::BadRequest("Invalid message ID".into()))?,
-        );
+        let id = raw
+            .parse::<i64>()
+            .map_err(|_| ApiError::BadRequest("Invalid message ID".into()))?;
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+
+    // Messages that are too old, missing, or in another channel are skipped
+    // and reported back rather than failing the whole batch.
+    let timestamps = paracord_db::messages::get_message_timestamps(&state.db, channel_id, &ids)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(BULK_DELETE_MAX_AGE_DAYS);
+    let (deletable, skipped): (Vec<i64>, Vec<i64>) = ids.iter().partition(|id| {
+        timestamps
+            .iter()
+            .any(|(found, created_at)| found == *id && *created_at >= cutoff)
+    });
+    let skipped_ids: Vec<String> = skipped.iter().map(|id| id.to_string()).collect();
+    if deletable.is_empty() {
+        return Ok(Json(json!({ "deleted": 0, "skipped_ids": skipped_ids })));
     }
-    let deleted = paracord_db::messages::bulk_delete_messages(&state.db, channel_id, &ids)
+
+    let deleted = paracord_db::messages::bulk_delete_messages(&state.db, channel_id, &deletable)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let deleted_ids: Vec<String> = deletable.iter().map(|id| id.to_string()).collect();
     let guild_id = channel.guild_id();
     let bulk_payload = json!({
         "channel_id": channel_id.to_string(),
-        "ids": body.message_ids,
+        "ids": deleted_ids,
     });
     if guild_id.is_none() {
         let recipient_ids = paracord_db::dms::get_dm_recipient_ids(&state.db, channel_id)
@@ -677,7 +1039,114 @@ pub async fn bulk_delete_messages(
             .event_bus
             .dispatch("MESSAGE_DELETE_BULK", bulk_payload, guild_id);
     }
-    Ok(Json(json!({ "deleted": deleted })))
+    if let Some(guild_id) = guild_id {
+        audit::log_action(
+            &state,
+            guild_id,
+            auth.user_id,
+            AuditAction::MessageBulkDelete,
+            Some(channel_id),
+            None,
+            Some(json!({ "count": deleted, "skipped": skipped_ids.len() })),
+        )
+        .await;
+    }
+    Ok(Json(
+        json!({ "deleted": deleted, "skipped_ids": skipped_ids }),
+    ))
+}
+
+/// Reject the send if the author is still inside the channel's slowmode
+/// window. Members who can manage messages or the channel are exempt (which
+/// includes the space owner), as are bots when configured. A retry carrying
+/// the nonce of a message already sent is let through so it gets that
+/// message back.
+///
+/// A send that counts against slowmode reserves the author's window here;
+/// the caller hands the reservation to [`release_slowmode`] if the send ends
+/// up not creating a new message.
+async fn enforce_slowmode(
+    state: &AppState,
+    channel: &paracord_db::channels::ChannelRow,
+    user_id: i64,
+    nonce: Option<&str>,
+) -> Result<Option<SlowmodeReservation>, ApiError> {
+    let Some(guild_id) = channel.guild_id() else {
+        return Ok(None);
+    };
+    if channel.rate_limit_per_user <= 0 {
+        return Ok(None);
+    }
+
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let perms = paracord_core::permissions::compute_channel_permissions_cached(
+        &state.permission_cache,
+        &state.db,
+        guild_id,
+        channel.id,
+        guild.owner_id,
+        user_id,
+    )
+    .await?;
+    if perms.contains(Permissions::MANAGE_MESSAGES) || perms.contains(Permissions::MANAGE_CHANNELS)
+    {
+        return Ok(None);
+    }
+    if state.config.slowmode_exempt_bots {
+        let user = paracord_db::users::get_user_by_id(&state.db, user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .ok_or(ApiError::Unauthorized)?;
+        if paracord_core::is_bot(user.flags) {
+            return Ok(None);
+        }
+    }
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let remaining_ms = match paracord_core::rate_limit::slowmode().try_send(
+        channel.id,
+        user_id,
+        channel.rate_limit_per_user,
+        now_ms,
+    ) {
+        Ok(reservation) => return Ok(reservation),
+        Err(remaining_ms) => remaining_ms,
+    };
+    if let Some(nonce) = nonce {
+        let sent = paracord_db::messages::get_message_by_channel_author_nonce(
+            &state.db, channel.id, user_id, nonce,
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        if sent.is_some() {
+            return Ok(None);
+        }
+    }
+    Err(ApiError::Slowmode {
+        retry_after: remaining_ms as f64 / 1000.0,
+    })
+}
+
+/// Give back a slowmode reservation whose send did not create a message.
+fn release_slowmode(reservation: Option<SlowmodeReservation>) {
+    if let Some(reservation) = reservation {
+        paracord_core::rate_limit::slowmode().release(reservation);
+    }
+}
+
+/// Check plaintext content against the configured length limit, counted in
+/// grapheme clusters.
+pub(crate) fn validate_content_length(state: &AppState, content: &str) -> Result<(), ApiError> {
+    let max_length = state.config.max_message_length as usize;
+    paracord_util::validation::validate_message_content(content, max_length).map_err(|_| {
+        ApiError::invalid_field(
+            "content",
+            format!("Message content must be 1-{max_length} characters"),
+        )
+    })
 }
 
 pub async fn send_message(
@@ -712,9 +1181,14 @@ pub async fn send_message(
         ));
     }
     if body.e2ee.is_none() && !body.content.trim().is_empty() {
-        paracord_util::validation::validate_message_content(&body.content).map_err(|_| {
-            ApiError::BadRequest("Message content must be 1-2000 characters".into())
-        })?;
+        validate_content_length(&state, &body.content)?;
+    }
+    let max_attachments = state.config.max_attachments_per_message as usize;
+    if body.attachment_ids.len() > max_attachments {
+        return Err(ApiError::invalid_field(
+            "attachment_ids",
+            format!("A message can have at most {max_attachments} attachments"),
+        ));
     }
 
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
@@ -728,6 +1202,9 @@ pub async fn send_message(
         &[Permissions::VIEW_CHANNEL, Permissions::SEND_MESSAGES],
     )
     .await?;
+    if channel.channel_type == 6 {
+        reopen_thread_for_post(&state, &channel, auth.user_id).await?;
+    }
 
     let referenced_message_id = match body.referenced_message_id.as_deref() {
         Some(id) => Some(
@@ -736,6 +1213,16 @@ pub async fn send_message(
         ),
         None => None,
     };
+    if let Some(reference_id) = referenced_message_id {
+        let referenced = paracord_db::messages::get_message(&state.db, reference_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        if referenced.is_none_or(|referenced| referenced.channel_id != channel_id) {
+            return Err(ApiError::BadRequest(
+                "Referenced message must be in the same channel".into(),
+            ));
+        }
+    }
 
     let mut attachments = Vec::with_capacity(body.attachment_ids.len());
     let now = chrono::Utc::now();
@@ -766,7 +1253,9 @@ pub async fn send_message(
         attachments.push(attachment);
     }
 
-    let msg_id = paracord_util::snowflake::generate(1);
+    let slowmode = enforce_slowmode(&state, &channel, auth.user_id, nonce.as_deref()).await?;
+
+    let msg_id = paracord_util::snowflake::generate();
 
     let dm_e2ee = body
         .e2ee
@@ -777,7 +1266,7 @@ pub async fn send_message(
             header: payload.header,
         });
 
-    let msg = paracord_core::message::create_message_with_options(
+    let msg = match paracord_core::message::create_message_with_options(
         &state.db,
         msg_id,
         channel_id,
@@ -789,10 +1278,24 @@ pub async fn send_message(
             allow_empty_content: !body.attachment_ids.is_empty(),
             dm_e2ee,
             nonce,
+            max_content_length: state.config.max_message_length as usize,
         },
     )
-    .await?;
+    .await
+    {
+        Ok(msg) => msg,
+        Err(err) => {
+            release_slowmode(slowmode);
+            return Err(err.into());
+        }
+    };
     let created_new = msg.id == msg_id;
+    if !created_new {
+        // A nonce retry got the earlier message back; that send already
+        // counted against slowmode.
+        release_slowmode(slowmode);
+    }
+    paracord_core::typing::tracker().stop(channel_id, auth.user_id);
     for attachment in &attachments {
         if attachment.message_id == Some(msg.id) {
             continue;
@@ -830,10 +1333,12 @@ pub async fn send_message(
     // Increment thread message count if the channel is a thread
     if created_new && channel.channel_type == 6 {
         let _ = paracord_db::channels::increment_thread_message_count(&state.db, channel_id).await;
+        // Posting in a thread follows it.
+        add_thread_member_and_announce(&state, &channel, auth.user_id).await?;
     }
 
     let guild_id = channel.guild_id();
-    let msg_json = message_to_json(&state, &msg, auth.user_id).await;
+    let msg_json = message_to_json(&state, &msg, auth.user_id).await?;
 
     if created_new {
         if guild_id.is_none() {
@@ -849,6 +1354,21 @@ pub async fn send_message(
                 .event_bus
                 .dispatch("MESSAGE_CREATE", msg_json.clone(), guild_id);
         }
+        state.push.message_created(paracord_core::push::PushJob {
+            message_id: msg.id,
+            channel_id,
+            guild_id,
+            author_id: auth.user_id,
+        });
+        if msg.flags & MESSAGE_FLAG_DM_E2EE == 0 {
+            state
+                .link_previews
+                .queue(paracord_core::link_preview::LinkPreviewJob {
+                    message_id: msg.id,
+                    channel_id,
+                    guild_id,
+                });
+        }
 
         // Federation: forward message to peer servers (non-blocking)
         if let Some(gid) = guild_id {
@@ -949,9 +1469,11 @@ pub async fn create_poll(
         &[Permissions::VIEW_CHANNEL, Permissions::SEND_MESSAGES],
     )
     .await?;
+    // A poll posts a message, so it counts against slowmode like any send.
+    let slowmode = enforce_slowmode(&state, &channel, auth.user_id, None).await?;
 
-    let message_id = paracord_util::snowflake::generate(1);
-    let msg = paracord_core::message::create_message_with_type(
+    let message_id = paracord_util::snowflake::generate();
+    let msg = match paracord_core::message::create_message_with_type(
         &state.db,
         message_id,
         channel_id,
@@ -960,9 +1482,16 @@ pub async fn create_poll(
         20,
         None,
     )
-    .await?;
+    .await
+    {
+        Ok(msg) => msg,
+        Err(err) => {
+            release_slowmode(slowmode);
+            return Err(err.into());
+        }
+    };
 
-    let poll_id = paracord_util::snowflake::generate(1);
+    let poll_id = paracord_util::snowflake::generate();
     paracord_db::polls::create_poll(
         &state.db,
         poll_id,
@@ -977,7 +1506,7 @@ pub async fn create_poll(
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
     let guild_id = channel.guild_id();
-    let msg_json = message_to_json(&state, &msg, auth.user_id).await;
+    let msg_json = message_to_json(&state, &msg, auth.user_id).await?;
 
     if guild_id.is_none() {
         let recipient_ids = paracord_db::dms::get_dm_recipient_ids(&state.db, channel_id)
@@ -991,6 +1520,12 @@ pub async fn create_poll(
             .event_bus
             .dispatch("MESSAGE_CREATE", msg_json.clone(), guild_id);
     }
+    state.push.message_created(paracord_core::push::PushJob {
+        message_id: msg.id,
+        channel_id,
+        guild_id,
+        author_id: auth.user_id,
+    });
 
     Ok((StatusCode::CREATED, Json(msg_json)))
 }
@@ -1156,9 +1691,7 @@ pub async fn edit_message(
     Json(body): Json<EditMessageRequest>,
 ) -> Result<Json<Value>, ApiError> {
     if body.e2ee.is_none() {
-        paracord_util::validation::validate_message_content(&body.content).map_err(|_| {
-            ApiError::BadRequest("Message content must be 1-2000 characters".into())
-        })?;
+        validate_content_length(&state, &body.content)?;
     }
     if body.e2ee.is_none() && contains_dangerous_markup(&body.content) {
         return Err(ApiError::BadRequest(
@@ -1180,6 +1713,8 @@ pub async fn edit_message(
         auth.user_id,
         &body.content,
         dm_e2ee,
+        state.config.message_edit_history_limit,
+        state.config.max_message_length as usize,
     )
     .await?;
 
@@ -1189,7 +1724,16 @@ pub async fn edit_message(
         .flatten();
     let guild_id = channel.and_then(|c| c.guild_id());
 
-    let msg_json = message_to_json(&state, &updated, auth.user_id).await;
+    let msg_json = message_to_json(&state, &updated, auth.user_id).await?;
+    if updated.flags & MESSAGE_FLAG_DM_E2EE == 0 {
+        state
+            .link_previews
+            .queue(paracord_core::link_preview::LinkPreviewJob {
+                message_id,
+                channel_id,
+                guild_id,
+            });
+    }
 
     if guild_id.is_none() {
         let recipient_ids = paracord_db::dms::get_dm_recipient_ids(&state.db, channel_id)
@@ -1234,25 +1778,73 @@ pub async fn edit_message(
     Ok(Json(msg_json))
 }
 
-pub async fn delete_message(
+pub async fn get_message_history(
     State(state): State<AppState>,
     auth: AuthUser,
     Path((channel_id, message_id)): Path<(i64, i64)>,
-) -> Result<StatusCode, ApiError> {
-    paracord_core::message::delete_message(&state.db, message_id, channel_id, auth.user_id).await?;
-
+) -> Result<Json<Value>, ApiError> {
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
-        .ok()
-        .flatten();
-    let guild_id = channel.and_then(|c| c.guild_id());
-
-    let delete_payload =
-        json!({"id": message_id.to_string(), "channel_id": channel_id.to_string()});
-    if guild_id.is_none() {
-        let recipient_ids = paracord_db::dms::get_dm_recipient_ids(&state.db, channel_id)
-            .await
-            .unwrap_or_default();
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    ensure_channel_permissions(
+        &state,
+        &channel,
+        auth.user_id,
+        &[Permissions::VIEW_CHANNEL, Permissions::MANAGE_MESSAGES],
+    )
+    .await?;
+
+    let msg = paracord_db::messages::get_message(&state.db, message_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|m| m.channel_id == channel_id)
+        .ok_or(ApiError::NotFound)?;
+    let edits = paracord_db::message_edits::get_message_edits(&state.db, msg.id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let revisions: Vec<Value> = edits
+        .iter()
+        .map(|edit| {
+            json!({
+                "id": edit.id.to_string(),
+                "editor_id": edit.editor_id.to_string(),
+                "content": edit.previous_content,
+                "edited_at": edit.edited_at.to_rfc3339(),
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "message_id": msg.id.to_string(),
+        "channel_id": msg.channel_id.to_string(),
+        "content": msg.content,
+        "edited_count": msg.edit_count,
+        "last_edited_at": msg.edited_at.map(|t| t.to_rfc3339()),
+        "revisions": revisions,
+    })))
+}
+
+pub async fn delete_message(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((channel_id, message_id)): Path<(i64, i64)>,
+) -> Result<StatusCode, ApiError> {
+    paracord_core::message::delete_message(&state.db, message_id, channel_id, auth.user_id).await?;
+
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .ok()
+        .flatten();
+    let guild_id = channel.and_then(|c| c.guild_id());
+
+    let delete_payload =
+        json!({"id": message_id.to_string(), "channel_id": channel_id.to_string()});
+    if guild_id.is_none() {
+        let recipient_ids = paracord_db::dms::get_dm_recipient_ids(&state.db, channel_id)
+            .await
+            .unwrap_or_default();
         state
             .event_bus
             .dispatch_to_users("MESSAGE_DELETE", delete_payload, recipient_ids);
@@ -1312,10 +1904,8 @@ pub async fn get_pins(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
-    let mut pinned = Vec::with_capacity(messages.len());
-    for msg in &messages {
-        pinned.push(message_to_json(&state, msg, auth.user_id).await);
-    }
+    let messages: Vec<_> = messages.iter().collect();
+    let pinned = messages_to_json(&state, &messages, auth.user_id).await?;
 
     Ok(Json(json!(pinned)))
 }
@@ -1337,11 +1927,25 @@ pub async fn pin_message(
     )
     .await?;
 
-    let pinned = paracord_db::messages::pin_message(&state.db, message_id, channel_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    if !pinned {
-        return Err(ApiError::NotFound);
+    let max_pins = state.config.max_pins_per_channel;
+    let outcome = paracord_db::messages::pin_message(
+        &state.db,
+        message_id,
+        channel_id,
+        auth.user_id,
+        i64::from(max_pins),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    match outcome {
+        PinOutcome::Pinned => {}
+        PinOutcome::AlreadyPinned => return Ok(StatusCode::NO_CONTENT),
+        PinOutcome::LimitReached => {
+            return Err(ApiError::BadRequest(format!(
+                "Maximum number of pinned messages ({max_pins}) reached for this channel"
+            )));
+        }
+        PinOutcome::NotFound => return Err(ApiError::NotFound),
     }
 
     let guild_id = channel.guild_id();
@@ -1423,11 +2027,18 @@ pub async fn typing(
         &[Permissions::VIEW_CHANNEL, Permissions::SEND_MESSAGES],
     )
     .await?;
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let Some(expires_at_ms) =
+        paracord_core::typing::tracker().start(channel_id, auth.user_id, now_ms)
+    else {
+        return Ok(StatusCode::NO_CONTENT);
+    };
     let guild_id = channel.guild_id();
     let typing_payload = json!({
         "channel_id": channel_id.to_string(),
         "user_id": auth.user_id.to_string(),
-        "timestamp": chrono::Utc::now().timestamp(),
+        "timestamp": now_ms / 1000,
+        "expires_at": expires_at_ms / 1000,
     });
 
     if guild_id.is_none() {
@@ -1477,11 +2088,88 @@ pub async fn update_read_state(
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    Ok(Json(json!({
+    Ok(Json(read_state_to_json(&state, &read_state).await?))
+}
+
+/// Read state with unread and mention counts adjusted for the user's
+/// notification settings on the channel.
+pub(crate) async fn read_state_to_json(
+    state: &AppState,
+    read_state: &paracord_db::read_states::ReadStateRow,
+) -> Result<Value, ApiError> {
+    let mut result =
+        read_states_to_json(state, read_state.user_id, std::slice::from_ref(read_state)).await?;
+    Ok(result.pop().unwrap_or(Value::Null))
+}
+
+/// [`read_state_to_json`] for many of one user's read states, with the
+/// settings and counts for all of them loaded in one query each.
+pub(crate) async fn read_states_to_json(
+    state: &AppState,
+    user_id: i64,
+    read_states: &[paracord_db::read_states::ReadStateRow],
+) -> Result<Vec<Value>, ApiError> {
+    let channel_ids: Vec<i64> = read_states.iter().map(|row| row.channel_id).collect();
+    let settings = paracord_db::notification_settings::get_effective_settings_for_channels(
+        &state.db,
+        user_id,
+        &channel_ids,
+        chrono::Utc::now(),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let resolved: Vec<_> = read_states
+        .iter()
+        .map(|read_state| {
+            let settings = settings
+                .get(&read_state.channel_id)
+                .cloned()
+                .unwrap_or_default();
+            (read_state, settings)
+        })
+        .collect();
+    let markers: Vec<UnreadMarker> = resolved
+        .iter()
+        .map(|(read_state, settings)| UnreadMarker {
+            channel_id: read_state.channel_id,
+            after_message_id: read_state.last_message_id,
+            include_everyone: !settings.suppress_everyone,
+        })
+        .collect();
+    let counts = paracord_db::read_states::count_unread_for_channels(
+        &state.db,
+        user_id,
+        &markers,
+        paracord_db::read_states::UNREAD_COUNT_CAP,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(resolved
+        .iter()
+        .map(|(read_state, settings)| {
+            let counts = counts
+                .get(&read_state.channel_id)
+                .copied()
+                .unwrap_or_default();
+            read_state_json(read_state, settings, counts)
+        })
+        .collect())
+}
+
+/// [`read_state_to_json`] for settings and raw counts already in hand.
+pub(crate) fn read_state_json(
+    read_state: &paracord_db::read_states::ReadStateRow,
+    settings: &paracord_db::notification_settings::EffectiveNotificationSettings,
+    counts: paracord_db::read_states::UnreadCounts,
+) -> Value {
+    let counts = settings.visible_counts(counts);
+    json!({
         "channel_id": read_state.channel_id.to_string(),
         "last_message_id": read_state.last_message_id.to_string(),
-        "mention_count": read_state.mention_count,
-    })))
+        "unread_count": counts.unread_count,
+        "mention_count": counts.mention_count,
+        "muted": settings.muted,
+    })
 }
 
 pub async fn list_channel_overwrites(
@@ -1503,21 +2191,52 @@ pub async fn list_channel_overwrites(
     let overwrites = paracord_db::channel_overwrites::get_channel_overwrites(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let result: Vec<Value> = overwrites
-        .iter()
-        .map(|o| {
-            json!({
-                "channel_id": o.channel_id.to_string(),
-                "target_id": o.target_id.to_string(),
-                "target_type": o.target_type,
-                "allow_perms": o.allow_perms,
-                "deny_perms": o.deny_perms,
-            })
-        })
-        .collect();
+    let result: Vec<Value> = overwrites.iter().map(overwrite_to_json).collect();
     Ok(Json(json!(result)))
 }
 
+fn overwrite_to_json(o: &paracord_db::channel_overwrites::ChannelOverwriteRow) -> Value {
+    json!({
+        "channel_id": o.channel_id.to_string(),
+        "target_id": o.target_id.to_string(),
+        "target_type": o.target_type,
+        "allow_perms": o.allow_perms,
+        "deny_perms": o.deny_perms,
+    })
+}
+
+/// Audit changes for an overwrite. The entry targets the channel, so the
+/// overwritten role or member is always named even if only its bits changed.
+fn overwrite_audit_changes(before: Option<&Value>, after: Option<&Value>) -> Option<Value> {
+    let mut changes = audit::diff_changes(before, after, OVERWRITE_AUDIT_FIELDS)?;
+    for field in ["target_id", "target_type"] {
+        let mut pair = serde_json::Map::new();
+        if let Some(old) = before.and_then(|value| value.get(field)) {
+            pair.insert("old".into(), old.clone());
+        }
+        if let Some(new) = after.and_then(|value| value.get(field)) {
+            pair.insert("new".into(), new.clone());
+        }
+        changes[field] = Value::Object(pair);
+    }
+    Some(changes)
+}
+
+/// Current overwrite for `target_id` on a channel, as JSON.
+async fn find_overwrite(
+    state: &AppState,
+    channel_id: i64,
+    target_id: i64,
+) -> Result<Option<Value>, ApiError> {
+    let overwrites = paracord_db::channel_overwrites::get_channel_overwrites(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(overwrites
+        .iter()
+        .find(|o| o.target_id == target_id)
+        .map(overwrite_to_json))
+}
+
 pub async fn upsert_channel_overwrite(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1540,6 +2259,7 @@ pub async fn upsert_channel_overwrite(
     {
         return Err(ApiError::BadRequest("Invalid overwrite target type".into()));
     }
+    let before = find_overwrite(&state, channel_id, target_id).await?;
     paracord_db::channel_overwrites::upsert_channel_overwrite(
         &state.db,
         channel_id,
@@ -1551,12 +2271,43 @@ pub async fn upsert_channel_overwrite(
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
     // Invalidate permission cache when channel overwrites change
-    paracord_core::permissions::invalidate_channel(&state.permission_cache, channel_id).await;
+    paracord_core::permissions::invalidate_channel_and_threads(
+        &state.permission_cache,
+        &state.db,
+        channel_id,
+    )
+    .await?;
     state.event_bus.dispatch(
         "CHANNEL_UPDATE",
         json!({ "id": channel_id.to_string() }),
         channel.guild_id(),
     );
+    if let Some(guild_id) = channel.guild_id() {
+        let after = overwrite_to_json(&paracord_db::channel_overwrites::ChannelOverwriteRow {
+            channel_id,
+            target_id,
+            target_type: body.target_type,
+            allow_perms: body.allow_perms,
+            deny_perms: body.deny_perms,
+        });
+        let action = if before.is_some() {
+            AuditAction::ChannelOverwriteUpdate
+        } else {
+            AuditAction::ChannelOverwriteCreate
+        };
+        if let Some(changes) = overwrite_audit_changes(before.as_ref(), Some(&after)) {
+            audit::log_action(
+                &state,
+                guild_id,
+                auth.user_id,
+                action,
+                Some(channel_id),
+                None,
+                Some(changes),
+            )
+            .await;
+        }
+    }
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -1576,24 +2327,118 @@ pub async fn delete_channel_overwrite(
         &[Permissions::VIEW_CHANNEL, Permissions::MANAGE_CHANNELS],
     )
     .await?;
+    let before = find_overwrite(&state, channel_id, target_id).await?;
     paracord_db::channel_overwrites::delete_channel_overwrite(&state.db, channel_id, target_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
     // Invalidate permission cache when channel overwrites are removed
-    paracord_core::permissions::invalidate_channel(&state.permission_cache, channel_id).await;
+    paracord_core::permissions::invalidate_channel_and_threads(
+        &state.permission_cache,
+        &state.db,
+        channel_id,
+    )
+    .await?;
     state.event_bus.dispatch(
         "CHANNEL_UPDATE",
         json!({ "id": channel_id.to_string() }),
         channel.guild_id(),
     );
+    if let (Some(guild_id), Some(before)) = (channel.guild_id(), before) {
+        audit::log_action(
+            &state,
+            guild_id,
+            auth.user_id,
+            AuditAction::ChannelOverwriteDelete,
+            Some(channel_id),
+            None,
+            overwrite_audit_changes(Some(&before), None),
+        )
+        .await;
+    }
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Longest reaction identifier the reactions table can store.
+const MAX_REACTION_EMOJI_LEN: usize = 64;
+
+/// Split a reaction path segment into the stored identifier and, for custom
+/// emoji written as `name:id`, the emoji id. Anything else is taken as a
+/// unicode emoji.
+fn parse_reaction_emoji(raw: &str) -> Result<(String, Option<i64>), ApiError> {
+    let emoji = raw.trim();
+    if emoji.is_empty() || emoji.len() > MAX_REACTION_EMOJI_LEN {
+        return Err(ApiError::BadRequest("Invalid emoji".into()));
+    }
+    let emoji_id = emoji
+        .rsplit_once(':')
+        .filter(|(name, _)| !name.is_empty())
+        .and_then(|(_, id)| id.parse::<i64>().ok());
+    Ok((emoji.to_string(), emoji_id))
+}
+
+#[derive(Deserialize)]
+pub struct ReactionUsersQuery {
+    pub after: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+pub async fn get_reactions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((channel_id, message_id, emoji)): Path<(i64, i64, String)>,
+    Query(params): Query<ReactionUsersQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let (emoji, _) = parse_reaction_emoji(&emoji)?;
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    ensure_channel_permissions(
+        &state,
+        &channel,
+        auth.user_id,
+        &[Permissions::VIEW_CHANNEL, Permissions::READ_MESSAGE_HISTORY],
+    )
+    .await?;
+    let message = paracord_db::messages::get_message(&state.db, message_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    if message.channel_id != channel_id {
+        return Err(ApiError::NotFound);
+    }
+
+    let limit = params.limit.unwrap_or(25).clamp(1, 100);
+    let users = paracord_db::reactions::get_reaction_users_page(
+        &state.db,
+        message_id,
+        &emoji,
+        params.after,
+        limit,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let result: Vec<Value> = users
+        .iter()
+        .map(|u| {
+            json!({
+                "id": u.id.to_string(),
+                "username": u.username,
+                "discriminator": u.discriminator,
+                "avatar_hash": u.avatar_hash,
+            })
+        })
+        .collect();
+    Ok(Json(json!(result)))
+}
+
 pub async fn add_reaction(
     State(state): State<AppState>,
     auth: AuthUser,
     Path((channel_id, message_id, emoji)): Path<(i64, i64, String)>,
 ) -> Result<StatusCode, ApiError> {
+    let (emoji, emoji_id) = parse_reaction_emoji(&emoji)?;
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
@@ -1606,7 +2451,7 @@ pub async fn add_reaction(
     )
     .await?;
 
-    paracord_db::reactions::add_reaction(&state.db, message_id, auth.user_id, &emoji, None)
+    paracord_db::reactions::add_reaction(&state.db, message_id, auth.user_id, &emoji, emoji_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
@@ -1667,6 +2512,7 @@ pub async fn remove_reaction(
     auth: AuthUser,
     Path((channel_id, message_id, emoji)): Path<(i64, i64, String)>,
 ) -> Result<StatusCode, ApiError> {
+    let (emoji, _) = parse_reaction_emoji(&emoji)?;
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
@@ -1778,22 +2624,84 @@ pub struct UpdateForumSortOrderRequest {
     pub sort_order: i32,
 }
 
+/// Inactivity windows, in minutes, a thread may auto-archive after.
+const THREAD_AUTO_ARCHIVE_DURATIONS: [i64; 4] = [60, 1440, 4320, 10080];
+const THREAD_AUTO_ARCHIVE_BATCH: usize = 256;
+
+#[derive(Deserialize)]
+pub struct CreateMessageThreadRequest {
+    pub name: String,
+    pub auto_archive_duration: Option<i64>,
+}
+
 pub async fn create_thread(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(channel_id): Path<i64>,
     Json(body): Json<CreateThreadRequest>,
 ) -> Result<(StatusCode, Json<Value>), ApiError> {
-    if body.name.trim().is_empty() || body.name.len() > 100 {
+    let starter_message_id = match body.message_id.as_deref() {
+        Some(raw_message_id) => Some(
+            raw_message_id
+                .parse::<i64>()
+                .map_err(|_| ApiError::BadRequest("Invalid message_id".into()))?,
+        ),
+        None => None,
+    };
+    start_thread(
+        &state,
+        auth.user_id,
+        channel_id,
+        &body.name,
+        body.auto_archive_duration,
+        starter_message_id,
+    )
+    .await
+}
+
+/// Start a thread from an existing message. Each message can start at most
+/// one thread.
+pub async fn create_thread_from_message(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((channel_id, message_id)): Path<(i64, i64)>,
+    Json(body): Json<CreateMessageThreadRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    start_thread(
+        &state,
+        auth.user_id,
+        channel_id,
+        &body.name,
+        body.auto_archive_duration,
+        Some(message_id),
+    )
+    .await
+}
+
+async fn start_thread(
+    state: &AppState,
+    user_id: i64,
+    channel_id: i64,
+    name: &str,
+    auto_archive_duration: Option<i64>,
+    starter_message_id: Option<i64>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    if name.trim().is_empty() || name.len() > 100 {
         return Err(ApiError::BadRequest(
             "Thread name must be 1-100 characters".into(),
         ));
     }
-    if contains_dangerous_markup(&body.name) {
+    if contains_dangerous_markup(name) {
         return Err(ApiError::BadRequest(
             "Thread name contains unsafe markup".into(),
         ));
     }
+    let auto_archive_duration = auto_archive_duration.unwrap_or(1440);
+    if !THREAD_AUTO_ARCHIVE_DURATIONS.contains(&auto_archive_duration) {
+        return Err(ApiError::BadRequest(
+            "auto_archive_duration must be 60, 1440, 4320 or 10080 minutes".into(),
+        ));
+    }
 
     let parent_channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
@@ -1808,9 +2716,9 @@ pub async fn create_thread(
     }
 
     ensure_channel_permissions(
-        &state,
+        state,
         &parent_channel,
-        auth.user_id,
+        user_id,
         &[Permissions::VIEW_CHANNEL, Permissions::SEND_MESSAGES],
     )
     .await?;
@@ -1819,39 +2727,43 @@ pub async fn create_thread(
         .guild_id()
         .ok_or(ApiError::BadRequest("Cannot create threads in DMs".into()))?;
 
-    let auto_archive_duration = body.auto_archive_duration.unwrap_or(1440);
-    let starter_message_id = match body.message_id.as_deref() {
-        Some(raw_message_id) => {
-            let parsed_message_id = raw_message_id
-                .parse::<i64>()
-                .map_err(|_| ApiError::BadRequest("Invalid message_id".into()))?;
-            let starter_message = paracord_db::messages::get_message(&state.db, parsed_message_id)
-                .await
-                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-                .ok_or(ApiError::BadRequest("Starter message not found".into()))?;
-            if starter_message.channel_id != channel_id {
-                return Err(ApiError::BadRequest(
-                    "Starter message must belong to the parent channel".into(),
-                ));
-            }
-            Some(parsed_message_id)
+    if let Some(message_id) = starter_message_id {
+        let starter_message = paracord_db::messages::get_message(&state.db, message_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .ok_or(ApiError::BadRequest("Starter message not found".into()))?;
+        if starter_message.channel_id != channel_id {
+            return Err(ApiError::BadRequest(
+                "Starter message must belong to the parent channel".into(),
+            ));
         }
-        None => None,
-    };
+        if paracord_db::channels::get_thread_by_starter_message(&state.db, channel_id, message_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .is_some()
+        {
+            return Err(ApiError::Conflict(
+                "A thread was already started from this message".into(),
+            ));
+        }
+    }
 
-    let thread_id = paracord_util::snowflake::generate(1);
+    let thread_id = paracord_util::snowflake::generate();
     let thread = paracord_db::channels::create_thread(
         &state.db,
         thread_id,
         guild_id,
         channel_id,
-        body.name.trim(),
-        auth.user_id,
+        name.trim(),
+        user_id,
         auto_archive_duration,
         starter_message_id,
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    paracord_db::channels::add_thread_member(&state.db, thread_id, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
     let thread_json = channel_to_json(&thread);
 
@@ -1862,6 +2774,153 @@ pub async fn create_thread(
     Ok((StatusCode::CREATED, Json(thread_json)))
 }
 
+/// Load `thread_id`, failing with 404 unless it is a thread.
+async fn get_thread_channel(
+    state: &AppState,
+    thread_id: i64,
+) -> Result<paracord_db::channels::ChannelRow, ApiError> {
+    let thread = paracord_db::channels::get_channel(&state.db, thread_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    if thread.channel_type != 6 {
+        return Err(ApiError::NotFound);
+    }
+    Ok(thread)
+}
+
+/// Before a post lands in a thread: locked threads only take messages from
+/// moderators, and an archived thread is reopened by the post.
+async fn reopen_thread_for_post(
+    state: &AppState,
+    thread: &paracord_db::channels::ChannelRow,
+    user_id: i64,
+) -> Result<(), ApiError> {
+    if paracord_db::channels::thread_is_locked(thread.thread_metadata.as_deref()) {
+        ensure_channel_permissions(state, thread, user_id, &[Permissions::MANAGE_CHANNELS]).await?;
+    }
+    if !paracord_db::channels::thread_is_archived(thread.thread_metadata.as_deref()) {
+        return Ok(());
+    }
+    let reopened =
+        paracord_db::channels::update_thread(&state.db, thread.id, None, Some(false), None)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    state.event_bus.dispatch(
+        "THREAD_UPDATE",
+        channel_to_json(&reopened),
+        reopened.guild_id(),
+    );
+    Ok(())
+}
+
+async fn add_thread_member_and_announce(
+    state: &AppState,
+    thread: &paracord_db::channels::ChannelRow,
+    user_id: i64,
+) -> Result<(), ApiError> {
+    let added = paracord_db::channels::add_thread_member(&state.db, thread.id, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if added {
+        dispatch_thread_members_update(state, thread, &[user_id], &[]);
+    }
+    Ok(())
+}
+
+fn dispatch_thread_members_update(
+    state: &AppState,
+    thread: &paracord_db::channels::ChannelRow,
+    added: &[i64],
+    removed: &[i64],
+) {
+    state.event_bus.dispatch(
+        "THREAD_MEMBERS_UPDATE",
+        json!({
+            "id": thread.id.to_string(),
+            "guild_id": thread.guild_id().map(|id| id.to_string()),
+            "added_member_ids": added.iter().map(|id| id.to_string()).collect::<Vec<_>>(),
+            "removed_member_ids": removed.iter().map(|id| id.to_string()).collect::<Vec<_>>(),
+        }),
+        thread.guild_id(),
+    );
+}
+
+pub async fn join_thread(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(thread_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let thread = get_thread_channel(&state, thread_id).await?;
+    ensure_channel_permissions(&state, &thread, auth.user_id, &[Permissions::VIEW_CHANNEL]).await?;
+    add_thread_member_and_announce(&state, &thread, auth.user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn leave_thread(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(thread_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let thread = get_thread_channel(&state, thread_id).await?;
+    let removed = paracord_db::channels::remove_thread_member(&state.db, thread_id, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if removed {
+        dispatch_thread_members_update(&state, &thread, &[], &[auth.user_id]);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_thread_members(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(thread_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let thread = get_thread_channel(&state, thread_id).await?;
+    ensure_channel_permissions(&state, &thread, auth.user_id, &[Permissions::VIEW_CHANNEL]).await?;
+    let members = paracord_db::channels::get_thread_members(&state.db, thread_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let result: Vec<Value> = members
+        .iter()
+        .map(|m| {
+            json!({
+                "id": m.thread_id.to_string(),
+                "user_id": m.user_id.to_string(),
+                "join_timestamp": m.joined_at.to_rfc3339(),
+            })
+        })
+        .collect();
+    Ok(Json(json!(result)))
+}
+
+/// Archive threads that have been quiet for longer than their
+/// auto-archive duration. Returns how many threads were archived.
+pub async fn archive_inactive_threads_once(
+    state: &AppState,
+) -> Result<usize, paracord_db::DbError> {
+    let inactive = paracord_db::channels::get_inactive_threads(
+        &state.db,
+        chrono::Utc::now(),
+        THREAD_AUTO_ARCHIVE_BATCH,
+    )
+    .await?;
+    let mut archived = 0;
+    for thread in inactive {
+        let updated =
+            paracord_db::channels::update_thread(&state.db, thread.id, None, Some(true), None)
+                .await?;
+        archived += 1;
+        state.event_bus.dispatch(
+            "THREAD_UPDATE",
+            channel_to_json(&updated),
+            updated.guild_id(),
+        );
+    }
+    Ok(archived)
+}
+
 pub async fn get_threads(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -2024,9 +3083,10 @@ pub async fn delete_thread(
 
     let guild_id = thread.guild_id();
 
-    paracord_db::channels::delete_channel(&state.db, thread_id)
+    let removed = paracord_db::channels::delete_channel(&state.db, thread_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    files::remove_stored_files(&state, &removed).await;
 
     state.event_bus.dispatch(
         "THREAD_DELETE",
@@ -2136,7 +3196,7 @@ pub async fn create_forum_post(
         None => None,
     };
 
-    let post_id = paracord_util::snowflake::generate(1);
+    let post_id = paracord_util::snowflake::generate();
     let post = paracord_db::channels::create_forum_post(
         &state.db,
         post_id,
@@ -2155,7 +3215,7 @@ pub async fn create_forum_post(
         .map(str::trim)
         .filter(|value| !value.is_empty())
     {
-        let message_id = paracord_util::snowflake::generate(1);
+        let message_id = paracord_util::snowflake::generate();
         let _ = paracord_db::messages::create_message(
             &state.db,
             message_id,
@@ -2213,7 +3273,7 @@ pub async fn create_forum_tag(
 
     let tag = paracord_db::channels::create_forum_tag(
         &state.db,
-        paracord_util::snowflake::generate(1),
+        paracord_util::snowflake::generate(),
         channel_id,
         name,
         body.emoji.as_deref(),
@@ -0,0 +1,409 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use paracord_core::commands::{RESPONSE_CHANNEL_MESSAGE, RESPONSE_DEFERRED};
+use paracord_core::AppState;
+use paracord_models::bot::{
+    ApplicationCommandDefinition, CommandOption, CommandOptionType, CommandOptionValue,
+    InteractionOption, InteractionResponse,
+};
+use paracord_models::gateway::EVENT_INTERACTION_CREATE;
+use paracord_models::permissions::Permissions;
+use rand::RngCore;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::ApiError;
+use crate::middleware::AuthUser;
+use crate::routes::channels;
+
+/// Interaction type for an invoked slash command.
+const INTERACTION_TYPE_COMMAND: u8 = 2;
+
+fn generate_interaction_token() -> String {
+    let mut bytes = [0_u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    paracord_util::hex::hex_encode(&bytes)
+}
+
+fn command_to_json(row: &paracord_db::application_commands::ApplicationCommandRow) -> Value {
+    json!({
+        "id": row.id.to_string(),
+        "application_id": row.application_id.to_string(),
+        "name": row.name,
+        "description": row.description,
+        "options": row.options,
+        "created_at": row.created_at.to_rfc3339(),
+        "updated_at": row.updated_at.to_rfc3339(),
+    })
+}
+
+/// Load the application if the caller may manage its commands: its owner or
+/// the bot itself.
+async fn load_managed_application(
+    state: &AppState,
+    app_id: i64,
+    user_id: i64,
+) -> Result<paracord_db::bot_applications::BotApplicationRow, ApiError> {
+    let app = paracord_db::bot_applications::get_bot_application(&state.db, app_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    if app.owner_id != user_id && app.bot_user_id != user_id {
+        return Err(ApiError::Forbidden);
+    }
+    Ok(app)
+}
+
+pub async fn list_application_commands(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(app_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    load_managed_application(&state, app_id, auth.user_id).await?;
+    let rows = paracord_db::application_commands::list_application_commands(&state.db, app_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(json!(rows
+        .iter()
+        .map(command_to_json)
+        .collect::<Vec<Value>>())))
+}
+
+/// Replace the application's whole command set. Commands are matched by
+/// name, so existing ones keep their IDs.
+pub async fn overwrite_application_commands(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(app_id): Path<i64>,
+    Json(body): Json<Vec<ApplicationCommandDefinition>>,
+) -> Result<Json<Value>, ApiError> {
+    load_managed_application(&state, app_id, auth.user_id).await?;
+    paracord_core::commands::validate_commands(&body)?;
+
+    let commands: Vec<paracord_db::application_commands::NewApplicationCommand> = body
+        .into_iter()
+        .map(|mut command| {
+            for option in &mut command.options {
+                option.description = option.description.trim().to_string();
+            }
+            let options = serde_json::to_value(&command.options)
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+            Ok(paracord_db::application_commands::NewApplicationCommand {
+                id: paracord_util::snowflake::generate(),
+                name: command.name,
+                description: command.description.trim().to_string(),
+                options,
+            })
+        })
+        .collect::<Result<_, ApiError>>()?;
+
+    let rows = paracord_db::application_commands::overwrite_application_commands(
+        &state.db, app_id, &commands,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(json!(rows
+        .iter()
+        .map(command_to_json)
+        .collect::<Vec<Value>>())))
+}
+
+/// Commands of every bot installed in the space, for the command picker.
+pub async fn list_guild_commands(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
+    let rows =
+        paracord_db::application_commands::list_guild_application_commands(&state.db, guild_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(json!(rows
+        .iter()
+        .map(command_to_json)
+        .collect::<Vec<Value>>())))
+}
+
+#[derive(Deserialize)]
+pub struct InvokeCommandRequest {
+    pub command_id: String,
+    #[serde(default)]
+    pub options: Vec<CommandOptionValue>,
+}
+
+/// Users, channels and roles passed as options must belong to the space the
+/// command was invoked in.
+async fn ensure_option_targets_in_guild(
+    state: &AppState,
+    guild_id: i64,
+    options: &[InteractionOption],
+) -> Result<(), ApiError> {
+    for option in options {
+        let Some(id) = option.value.as_str().and_then(|v| v.parse::<i64>().ok()) else {
+            continue;
+        };
+        let in_guild = match option.option_type {
+            CommandOptionType::User => paracord_db::members::get_member(&state.db, id, guild_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+                .is_some(),
+            CommandOptionType::Channel => paracord_db::channels::get_channel(&state.db, id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+                .is_some_and(|channel| channel.guild_id() == Some(guild_id)),
+            CommandOptionType::Role => paracord_db::roles::get_role(&state.db, id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+                .is_some_and(|role| role.space_id == guild_id),
+            _ => continue,
+        };
+        if !in_guild {
+            return Err(ApiError::BadRequest(format!(
+                "Option '{}' does not refer to anything in this space",
+                option.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Invoke a slash command in a channel. The interaction goes to the bot's
+/// endpoint if it has one, otherwise over the gateway; the bot answers
+/// inline (endpoint only) or through the interaction callback.
+pub async fn invoke_command(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+    Json(body): Json<InvokeCommandRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let command_id = body
+        .command_id
+        .parse::<i64>()
+        .map_err(|_| ApiError::BadRequest("Invalid command_id".into()))?;
+
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let guild_id = channel.guild_id().ok_or(ApiError::BadRequest(
+        "Commands can only be used in a space".into(),
+    ))?;
+    channels::ensure_channel_permissions(
+        &state,
+        &channel,
+        auth.user_id,
+        &[Permissions::VIEW_CHANNEL, Permissions::SEND_MESSAGES],
+    )
+    .await?;
+
+    let command = paracord_db::application_commands::get_application_command(&state.db, command_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let installed =
+        paracord_db::bot_applications::is_bot_in_guild(&state.db, command.application_id, guild_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !installed {
+        return Err(ApiError::NotFound);
+    }
+    let app = paracord_db::bot_applications::get_bot_application(&state.db, command.application_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+
+    let definition: Vec<CommandOption> = serde_json::from_value(command.options.clone())
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let options = paracord_core::commands::resolve_options(&definition, &body.options)?;
+    ensure_option_targets_in_guild(&state, guild_id, &options).await?;
+
+    let user = paracord_db::users::get_user_by_id(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+
+    let interaction_id = paracord_util::snowflake::generate();
+    let token = generate_interaction_token();
+    let token_hash = paracord_db::bot_applications::hash_token(&token);
+    paracord_db::application_commands::create_interaction(
+        &state.db,
+        interaction_id,
+        app.id,
+        command.id,
+        guild_id,
+        channel_id,
+        auth.user_id,
+        &token_hash,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    // Tokens outlive their usefulness quickly; prune as we go.
+    let cutoff = chrono::Utc::now() - paracord_core::commands::INTERACTION_TOKEN_TTL;
+    if let Err(e) =
+        paracord_db::application_commands::delete_interactions_before(&state.db, cutoff).await
+    {
+        tracing::warn!("Failed to prune expired interactions: {}", e);
+    }
+
+    let payload = json!({
+        "id": interaction_id.to_string(),
+        "application_id": app.id.to_string(),
+        "type": INTERACTION_TYPE_COMMAND,
+        "token": token,
+        "guild_id": guild_id.to_string(),
+        "channel_id": channel_id.to_string(),
+        "user": {
+            "id": user.id.to_string(),
+            "username": user.username,
+            "discriminator": user.discriminator,
+            "avatar_hash": user.avatar_hash,
+        },
+        "data": {
+            "id": command.id.to_string(),
+            "name": command.name,
+            "options": options,
+        },
+    });
+    let interaction_json = json!({
+        "id": interaction_id.to_string(),
+        "application_id": app.id.to_string(),
+        "name": command.name,
+        "user": { "id": auth.user_id.to_string() },
+    });
+
+    let Some(endpoint) = app.interactions_endpoint_url.as_deref() else {
+        state
+            .event_bus
+            .dispatch_to_users(EVENT_INTERACTION_CREATE, payload, vec![app.bot_user_id]);
+        return Ok((StatusCode::ACCEPTED, Json(interaction_json)));
+    };
+
+    let signing_key = paracord_core::commands::interactions_signing_key(
+        &state.db,
+        app.id,
+        app.interactions_signing_key.as_deref(),
+    )
+    .await?;
+    let response = paracord_core::commands::deliver_to_endpoint(endpoint, &signing_key, &payload)
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                application_id = app.id,
+                "Interaction delivery failed: {}",
+                e
+            );
+            ApiError::ServiceUnavailable("The application did not respond".into())
+        })?;
+    match response {
+        Some(InteractionResponse {
+            response_type: RESPONSE_CHANNEL_MESSAGE,
+            data: Some(data),
+        }) => {
+            channels::check_interaction_reply(&state, &channel, app.bot_user_id, &data.content)
+                .await?;
+            paracord_db::application_commands::mark_interaction_responded(
+                &state.db,
+                interaction_id,
+            )
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+            let message = channels::create_interaction_reply(
+                &state,
+                &channel,
+                app.bot_user_id,
+                &data.content,
+                interaction_json.clone(),
+            )
+            .await?;
+            let mut result = interaction_json;
+            result["message"] = message;
+            Ok((StatusCode::OK, Json(result)))
+        }
+        Some(InteractionResponse {
+            response_type: RESPONSE_CHANNEL_MESSAGE,
+            data: None,
+        }) => Err(ApiError::ServiceUnavailable(
+            "The application sent an empty response".into(),
+        )),
+        _ => Ok((StatusCode::ACCEPTED, Json(interaction_json))),
+    }
+}
+
+/// The bot's reply to an interaction. Authorized by the interaction token,
+/// which stays valid for [`paracord_core::commands::INTERACTION_TOKEN_TTL`]
+/// and can post a single message.
+pub async fn interaction_callback(
+    State(state): State<AppState>,
+    Path((interaction_id, token)): Path<(i64, String)>,
+    Json(body): Json<InteractionResponse>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let interaction = paracord_db::application_commands::get_interaction(&state.db, interaction_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let expired = interaction.created_at + paracord_core::commands::INTERACTION_TOKEN_TTL
+        <= chrono::Utc::now();
+    if expired || paracord_db::bot_applications::hash_token(&token) != interaction.token_hash {
+        return Err(ApiError::NotFound);
+    }
+
+    let data = match (body.response_type, body.data) {
+        (RESPONSE_DEFERRED, _) => return Ok((StatusCode::ACCEPTED, Json(json!({})))),
+        (RESPONSE_CHANNEL_MESSAGE, Some(data)) => data,
+        (RESPONSE_CHANNEL_MESSAGE, None) => {
+            return Err(ApiError::invalid_field("data", "Response data is required"))
+        }
+        (other, _) => {
+            return Err(ApiError::BadRequest(format!(
+                "Unsupported interaction response type {other}"
+            )))
+        }
+    };
+    let app =
+        paracord_db::bot_applications::get_bot_application(&state.db, interaction.application_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .ok_or(ApiError::NotFound)?;
+    let command = paracord_db::application_commands::get_application_command(
+        &state.db,
+        interaction.command_id,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    .ok_or(ApiError::NotFound)?;
+    let channel = paracord_db::channels::get_channel(&state.db, interaction.channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    // Check before the token is spent on a reply that can't be posted.
+    channels::check_interaction_reply(&state, &channel, app.bot_user_id, &data.content).await?;
+
+    let claimed =
+        paracord_db::application_commands::mark_interaction_responded(&state.db, interaction.id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !claimed {
+        return Err(ApiError::Conflict(
+            "Interaction has already been responded to".into(),
+        ));
+    }
+
+    let message = channels::create_interaction_reply(
+        &state,
+        &channel,
+        app.bot_user_id,
+        &data.content,
+        json!({
+            "id": interaction.id.to_string(),
+            "application_id": app.id.to_string(),
+            "name": command.name,
+            "user": { "id": interaction.user_id.to_string() },
+        }),
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(message)))
+}
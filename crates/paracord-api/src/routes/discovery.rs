@@ -30,7 +30,7 @@ pub async fn list_discoverable_guilds(
 
     let mut discoverable: Vec<_> = all_guilds
         .into_iter()
-        .filter(|g| g.visibility.eq_ignore_ascii_case("public"))
+        .filter(|g| g.visibility() == paracord_db::guilds::SpaceVisibility::Public)
         .collect();
 
     // Filter by search query
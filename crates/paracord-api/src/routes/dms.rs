@@ -19,30 +19,29 @@ pub async fn list_dms(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
-    let result: Vec<Value> = channels
-        .iter()
-        .map(|c| {
-            json!({
-                "id": c.id.to_string(),
-                "type": c.channel_type,
-                "channel_type": c.channel_type,
-                "guild_id": null,
-                "name": null,
-                "last_message_id": c.last_message_id.map(|id| id.to_string()),
-                "recipient": {
-                    "id": c.recipient_id.to_string(),
-                    "username": c.recipient_username,
-                    "discriminator": c.recipient_discriminator,
-                    "avatar_hash": c.recipient_avatar_hash,
-                    "public_key": c.recipient_public_key,
-                }
-            })
-        })
-        .collect();
+    let result: Vec<Value> = channels.iter().map(dm_channel_json).collect();
 
     Ok(Json(json!(result)))
 }
 
+pub(crate) fn dm_channel_json(c: &paracord_db::dms::DmChannelWithRecipientRow) -> Value {
+    json!({
+        "id": c.id.to_string(),
+        "type": c.channel_type,
+        "channel_type": c.channel_type,
+        "guild_id": null,
+        "name": null,
+        "last_message_id": c.last_message_id.map(|id| id.to_string()),
+        "recipient": {
+            "id": c.recipient_id.to_string(),
+            "username": c.recipient_username,
+            "discriminator": c.recipient_discriminator,
+            "avatar_hash": c.recipient_avatar_hash,
+            "public_key": c.recipient_public_key,
+        }
+    })
+}
+
 pub async fn create_dm(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -67,7 +66,7 @@ pub async fn create_dm(
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
     if blocked {
-        return Err(ApiError::Forbidden);
+        return Err(ApiError::Blocked);
     }
 
     let are_friends =
@@ -93,7 +92,7 @@ pub async fn create_dm(
     {
         existing
     } else {
-        let channel_id = paracord_util::snowflake::generate(1);
+        let channel_id = paracord_util::snowflake::generate();
         paracord_db::dms::create_dm_channel(&state.db, channel_id, auth.user_id, recipient_id)
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
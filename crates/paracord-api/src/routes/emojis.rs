@@ -1,9 +1,11 @@
 use axum::{
     extract::{Multipart, Path, State},
-    http::StatusCode,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use paracord_core::AppState;
+use paracord_media::images;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
@@ -11,8 +13,18 @@ use serde_json::{json, Value};
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
 
-const MAX_EMOJI_NAME_LEN: usize = 32;
 const MAX_EMOJI_IMAGE_SIZE: usize = 256 * 1024; // 256 KB
+/// Largest accepted width or height of an uploaded emoji image.
+const MAX_EMOJI_DIMENSION: u32 = 512;
+
+fn validate_emoji_name(name: &str) -> Result<(), ApiError> {
+    paracord_util::validation::validate_emoji_name(name).map_err(|_| {
+        ApiError::invalid_field(
+            "name",
+            "Emoji name must be 2-32 characters of letters, digits or underscores",
+        )
+    })
+}
 
 fn emoji_to_json(e: &paracord_db::emojis::EmojiRow) -> Value {
     json!({
@@ -47,12 +59,13 @@ async fn ensure_emoji_permission(
     let perms = match perms {
         Ok(p) => p,
         Err(_) => {
-            let roles = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id).await?;
-            paracord_core::permissions::compute_permissions_from_roles(
-                &roles,
+            paracord_core::permissions::compute_guild_permissions(
+                &state.db,
+                guild_id,
                 guild.owner_id,
                 user_id,
             )
+            .await?
         }
     };
     paracord_core::permissions::require_permission(perms, Permissions::MANAGE_EMOJIS)?;
@@ -84,7 +97,6 @@ pub async fn create_emoji(
 
     let mut name: Option<String> = None;
     let mut image_data: Option<Vec<u8>> = None;
-    let mut content_type: Option<String> = None;
 
     while let Some(field) = multipart
         .next_field()
@@ -101,7 +113,6 @@ pub async fn create_emoji(
                 name = Some(text);
             }
             "image" | "file" => {
-                content_type = field.content_type().map(|s| s.to_string());
                 let data = field
                     .bytes()
                     .await
@@ -116,11 +127,7 @@ pub async fn create_emoji(
     let image_data =
         image_data.ok_or_else(|| ApiError::BadRequest("Missing emoji image".into()))?;
 
-    if name.is_empty() || name.len() > MAX_EMOJI_NAME_LEN {
-        return Err(ApiError::BadRequest(
-            "Emoji name must be between 1 and 32 characters".into(),
-        ));
-    }
+    validate_emoji_name(&name)?;
 
     if image_data.is_empty() {
         return Err(ApiError::BadRequest("Empty emoji image".into()));
@@ -132,52 +139,63 @@ pub async fn create_emoji(
         ));
     }
 
-    let content_type =
-        content_type.ok_or_else(|| ApiError::BadRequest("Missing emoji content type".into()))?;
-
-    let (animated, ext) = match content_type.as_str() {
-        "image/png" => (false, "png"),
-        "image/gif" => (true, "gif"),
-        _ => {
-            return Err(ApiError::BadRequest(
-                "Only PNG and GIF emoji uploads are supported".into(),
-            ))
-        }
-    };
-
-    let is_valid_signature = if animated {
-        image_data.starts_with(b"GIF87a") || image_data.starts_with(b"GIF89a")
-    } else {
-        image_data.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A])
-    };
-    if !is_valid_signature {
-        return Err(ApiError::BadRequest(
-            "Emoji file contents do not match the declared image type".into(),
-        ));
+    // The declared content type is ignored: the format and whether the emoji
+    // is animated are both read from the file itself.
+    let (width, height) = images::image_dimensions(&image_data).ok_or_else(|| {
+        ApiError::BadRequest("Emoji must be a PNG, GIF, JPEG or WebP image".into())
+    })?;
+    if width > MAX_EMOJI_DIMENSION || height > MAX_EMOJI_DIMENSION {
+        return Err(ApiError::BadRequest(format!(
+            "Emoji image must be at most {MAX_EMOJI_DIMENSION}x{MAX_EMOJI_DIMENSION} pixels"
+        )));
     }
-
-    // Store emoji image to disk
-    let emoji_id = paracord_util::snowflake::generate(1);
-    let storage_dir = std::path::Path::new(&state.config.storage_path).join("emojis");
-    tokio::fs::create_dir_all(&storage_dir)
+    let processed = tokio::task::spawn_blocking(move || images::process_emoji(&image_data))
         .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-
-    let file_path = storage_dir.join(format!("{}.{}", emoji_id, ext));
-    tokio::fs::write(&file_path, &image_data)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or_else(|| {
+            ApiError::BadRequest("Emoji must be a PNG, GIF, JPEG or WebP image".into())
+        })?;
+    let animated = processed.animated;
+
+    let emoji_id = paracord_util::snowflake::generate();
+    let storage_key = images::emoji_storage_key(emoji_id, animated);
+    state
+        .storage_backend
+        .store(&storage_key, &processed.data)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
-    let emoji = paracord_db::emojis::create_emoji(
+    let max_of_kind = if animated {
+        state.config.max_animated_emojis
+    } else {
+        state.config.max_static_emojis
+    };
+    let outcome = paracord_db::emojis::create_emoji_checked(
         &state.db,
         emoji_id,
         guild_id,
         &name,
         auth.user_id,
         animated,
+        i64::from(max_of_kind),
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let emoji = match outcome {
+        paracord_db::emojis::EmojiCreateOutcome::Created(emoji) => emoji,
+        rejected => {
+            let _ = state.storage_backend.delete(&storage_key).await;
+            return Err(match rejected {
+                paracord_db::emojis::EmojiCreateOutcome::NameTaken => ApiError::Conflict(format!(
+                    "An emoji named '{name}' already exists in this space"
+                )),
+                _ => ApiError::BadRequest(format!(
+                    "Maximum number of {} emojis reached ({max_of_kind})",
+                    if animated { "animated" } else { "static" }
+                )),
+            });
+        }
+    };
 
     let emoji_json = emoji_to_json(&emoji);
 
@@ -206,11 +224,7 @@ pub async fn update_emoji(
 ) -> Result<Json<Value>, ApiError> {
     ensure_emoji_permission(&state, guild_id, auth.user_id).await?;
 
-    if body.name.is_empty() || body.name.len() > MAX_EMOJI_NAME_LEN {
-        return Err(ApiError::BadRequest(
-            "Emoji name must be between 1 and 32 characters".into(),
-        ));
-    }
+    validate_emoji_name(&body.name)?;
 
     // Verify emoji belongs to guild
     let existing = paracord_db::emojis::get_emoji(&state.db, emoji_id)
@@ -221,6 +235,15 @@ pub async fn update_emoji(
     if existing.guild_id != guild_id {
         return Err(ApiError::NotFound);
     }
+    if paracord_db::emojis::emoji_name_taken(&state.db, guild_id, &body.name, Some(emoji_id))
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    {
+        return Err(ApiError::Conflict(format!(
+            "An emoji named '{}' already exists in this space",
+            body.name
+        )));
+    }
 
     let updated = paracord_db::emojis::update_emoji(&state.db, emoji_id, &body.name)
         .await
@@ -261,12 +284,10 @@ pub async fn delete_emoji(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
-    // Clean up file
-    let ext = if existing.animated { "gif" } else { "png" };
-    let file_path = std::path::Path::new(&state.config.storage_path)
-        .join("emojis")
-        .join(format!("{}.{}", emoji_id, ext));
-    let _ = tokio::fs::remove_file(file_path).await;
+    let _ = state
+        .storage_backend
+        .delete(&images::emoji_storage_key(emoji_id, existing.animated))
+        .await;
 
     state.event_bus.dispatch(
         "GUILD_EMOJIS_UPDATE",
@@ -280,48 +301,95 @@ pub async fn delete_emoji(
     Ok(StatusCode::NO_CONTENT)
 }
 
-pub async fn get_emoji_image(
-    State(state): State<AppState>,
-    Path((guild_id, emoji_id)): Path<(i64, i64)>,
-) -> Result<axum::response::Response, ApiError> {
-    let emoji = paracord_db::emojis::get_emoji(&state.db, emoji_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+/// Strong validator for an emoji image. Images are never rewritten after
+/// upload, so the id identifies the representation.
+fn emoji_etag(emoji_id: i64) -> String {
+    format!("\"emoji-{}\"", emoji_id)
+}
 
-    if emoji.guild_id != guild_id {
-        return Err(ApiError::NotFound);
-    }
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+        })
+}
 
-    let ext = if emoji.animated { "gif" } else { "png" };
-    let file_path = std::path::Path::new(&state.config.storage_path)
-        .join("emojis")
-        .join(format!("{}.{}", emoji_id, ext));
+async fn serve_emoji_image(
+    state: &AppState,
+    emoji: &paracord_db::emojis::EmojiRow,
+    headers: &HeaderMap,
+) -> Result<Response, ApiError> {
+    let etag = emoji_etag(emoji.id);
+    let etag_value = HeaderValue::from_str(&etag).map_err(|e| ApiError::Internal(e.into()))?;
+    let cache_control = HeaderValue::from_static("public, max-age=31536000, immutable");
+    if if_none_match(headers, &etag) {
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        response.headers_mut().insert(header::ETAG, etag_value);
+        response
+            .headers_mut()
+            .insert(header::CACHE_CONTROL, cache_control);
+        return Ok(response);
+    }
 
-    let data = tokio::fs::read(&file_path)
+    let data = state
+        .storage_backend
+        .retrieve(&images::emoji_storage_key(emoji.id, emoji.animated))
         .await
         .map_err(|_| ApiError::NotFound)?;
-
     let content_type = if emoji.animated {
         "image/gif"
     } else {
         "image/png"
     };
-
-    use axum::http::header;
-    use axum::response::IntoResponse;
     Ok((
         [
-            (
-                header::CONTENT_TYPE,
-                axum::http::HeaderValue::from_static(content_type),
-            ),
-            (
-                header::CACHE_CONTROL,
-                axum::http::HeaderValue::from_static("public, max-age=31536000, immutable"),
-            ),
+            (header::CONTENT_TYPE, HeaderValue::from_static(content_type)),
+            (header::CACHE_CONTROL, cache_control),
+            (header::ETAG, etag_value),
         ],
         data,
     )
         .into_response())
 }
+
+pub async fn get_emoji_image(
+    State(state): State<AppState>,
+    Path((guild_id, emoji_id)): Path<(i64, i64)>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let emoji = paracord_db::emojis::get_emoji(&state.db, emoji_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+
+    if emoji.guild_id != guild_id {
+        return Err(ApiError::NotFound);
+    }
+
+    serve_emoji_image(&state, &emoji, &headers).await
+}
+
+/// `GET /api/v1/emojis/{id}.{ext}` — the extension must match the stored
+/// format (`gif` for animated emojis, `png` otherwise).
+pub async fn get_emoji_file(
+    State(state): State<AppState>,
+    Path(file): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let (id, ext) = file.rsplit_once('.').ok_or(ApiError::NotFound)?;
+    let emoji_id: i64 = id.parse().map_err(|_| ApiError::NotFound)?;
+    let emoji = paracord_db::emojis::get_emoji(&state.db, emoji_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    if !ext.eq_ignore_ascii_case(images::emoji_extension(emoji.animated)) {
+        return Err(ApiError::NotFound);
+    }
+
+    serve_emoji_image(&state, &emoji, &headers).await
+}
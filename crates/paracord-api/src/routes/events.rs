@@ -87,11 +87,13 @@ async fn ensure_manage_events(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .ok_or(ApiError::NotFound)?;
-    let roles = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let perms =
-        paracord_core::permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
+    let perms = paracord_core::permissions::compute_guild_permissions(
+        &state.db,
+        guild_id,
+        guild.owner_id,
+        user_id,
+    )
+    .await?;
     // MANAGE_EVENTS maps to MANAGE_GUILD for now
     paracord_core::permissions::require_permission(perms, Permissions::MANAGE_GUILD)?;
     Ok(())
@@ -142,7 +144,7 @@ pub async fn create_event(
         None => None,
     };
 
-    let event_id = paracord_util::snowflake::generate(1);
+    let event_id = paracord_util::snowflake::generate();
     let event = paracord_db::scheduled_events::create_event(
         &state.db,
         event_id,
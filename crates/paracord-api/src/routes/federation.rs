@@ -4,6 +4,7 @@ use axum::{
     http::{HeaderMap, Uri},
     Json,
 };
+use dashmap::DashMap;
 use ed25519_dalek::SigningKey;
 use paracord_core::AppState;
 use paracord_federation::{
@@ -13,6 +14,9 @@ use paracord_federation::{
 use paracord_models::permissions::Permissions;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
 
 use crate::error::ApiError;
 use crate::middleware::AdminUser;
@@ -425,28 +429,21 @@ async fn verify_transport_request(
         return Err(ApiError::Forbidden);
     }
 
-    let keys = service
-        .list_server_keys(&state.db, &transport.origin)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let trusted_key = keys
-        .iter()
-        .find(|k| k.key_id == transport.key_id && k.valid_until >= now_ms)
-        .ok_or(ApiError::Forbidden)?;
-
     let canonical = paracord_federation::transport::canonical_transport_bytes_with_body(
         method,
         path,
         transport.timestamp_ms,
         body_bytes,
     );
-    service
-        .verify_payload(
-            &canonical,
-            &transport.signature_hex,
-            &trusted_key.public_key,
-        )
-        .map_err(|_| ApiError::Forbidden)?;
+    verify_origin_signature(
+        state,
+        service,
+        &transport.origin,
+        &transport.key_id,
+        &canonical,
+        &transport.signature_hex,
+    )
+    .await?;
 
     if enforce_replay_protection {
         let replay_material = format!(
@@ -532,7 +529,7 @@ async fn ensure_remote_user_mapping(
         &digest[..6]
     );
     let email = format!("fed+{}@remote.invalid", &digest[..24]);
-    let user_id = paracord_util::snowflake::generate(1);
+    let user_id = paracord_util::snowflake::generate();
 
     let created =
         paracord_db::users::create_user(&state.db, user_id, &username, 0, &email, "!federated!")
@@ -616,32 +613,135 @@ async fn verify_envelope_origin_signature(
     if !payload_origin_trusted {
         return Err(ApiError::Forbidden);
     }
-    let keys = service
-        .list_server_keys(&state.db, &payload.origin_server)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let trusted_key = keys
-        .iter()
-        .find(|k| k.key_id == payload_key_id && k.valid_until >= now_ms)
-        .ok_or(ApiError::Forbidden)?;
-
     let payload_bytes = canonical_event_payload_bytes(payload);
-    service
-        .verify_payload(&payload_bytes, &signature_hex, &trusted_key.public_key)
-        .map_err(|_| ApiError::Forbidden)?;
-    Ok(())
+    verify_origin_signature(
+        state,
+        service,
+        &payload.origin_server,
+        &payload_key_id,
+        &payload_bytes,
+        &signature_hex,
+    )
+    .await
+}
+
+/// Longest a key fetched from a remote origin is trusted before it must be
+/// fetched again, whatever `valid_until` the origin advertised.
+const REMOTE_KEY_CACHE_TTL_MS: i64 = 24 * 60 * 60 * 1000;
+/// Minimum gap between refetches of one origin's keys, so a stream of forged
+/// signatures can't be used to hammer the origin (or stall ingest).
+const REMOTE_KEY_REFETCH_INTERVAL: Duration = Duration::from_secs(60);
+const REMOTE_KEY_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+static REMOTE_KEY_LAST_FETCH: LazyLock<DashMap<String, Instant>> = LazyLock::new(DashMap::new);
+
+/// Verify an ed25519 signature made by `origin` with its key `key_id`.
+///
+/// Keys come from the stored copy of the origin's published key set. An
+/// unknown or expired key id, or a signature that doesn't verify, triggers
+/// one refetch of the origin's `/keys` endpoint before giving up, which is
+/// how rotated keys are picked up.
+async fn verify_origin_signature(
+    state: &AppState,
+    service: &FederationService,
+    origin: &str,
+    key_id: &str,
+    message: &[u8],
+    signature_hex: &str,
+) -> Result<(), ApiError> {
+    let mut refetched = false;
+    loop {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let keys = service
+            .list_server_keys(&state.db, origin)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        let verified = keys
+            .iter()
+            .find(|k| k.key_id == key_id && k.valid_until >= now_ms)
+            .is_some_and(|key| {
+                service
+                    .verify_payload(message, signature_hex, &key.public_key)
+                    .is_ok()
+            });
+        if verified {
+            return Ok(());
+        }
+        if refetched || !refetch_origin_keys(state, service, origin).await {
+            return Err(ApiError::Unauthorized);
+        }
+        refetched = true;
+    }
+}
+
+/// Fetch `origin`'s published keys and store them. Returns whether anything
+/// was fetched; refetches are throttled per origin.
+async fn refetch_origin_keys(state: &AppState, service: &FederationService, origin: &str) -> bool {
+    let throttled = REMOTE_KEY_LAST_FETCH
+        .get(origin)
+        .is_some_and(|at| at.elapsed() < REMOTE_KEY_REFETCH_INTERVAL);
+    if throttled {
+        return false;
+    }
+    REMOTE_KEY_LAST_FETCH.insert(origin.to_string(), Instant::now());
+
+    let endpoint = match paracord_db::federation::get_federated_server(&state.db, origin).await {
+        Ok(Some(server)) => server.federation_endpoint,
+        Ok(None) => return false,
+        Err(e) => {
+            tracing::warn!("federation: failed to look up origin {}: {}", origin, e);
+            return false;
+        }
+    };
+    let Ok(client) = FederationClient::new() else {
+        return false;
+    };
+    let response = match tokio::time::timeout(
+        REMOTE_KEY_FETCH_TIMEOUT,
+        client.fetch_server_keys(&endpoint),
+    )
+    .await
+    {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => {
+            tracing::warn!("federation: failed to refetch keys for {}: {}", origin, e);
+            return false;
+        }
+        Err(_) => {
+            tracing::warn!("federation: timed out refetching keys for {}", origin);
+            return false;
+        }
+    };
+    if response.server_name != origin {
+        tracing::warn!(
+            "federation: key set from {} claims to be for {}",
+            origin,
+            response.server_name
+        );
+        return false;
+    }
+
+    let max_valid_until = chrono::Utc::now().timestamp_millis() + REMOTE_KEY_CACHE_TTL_MS;
+    let mut stored = false;
+    for key in response.keys.iter().filter(|k| k.server_name == origin) {
+        let key = FederationServerKey {
+            valid_until: key.valid_until.min(max_valid_until),
+            ..key.clone()
+        };
+        match service.upsert_server_key(&state.db, &key).await {
+            Ok(()) => stored = true,
+            Err(e) => tracing::warn!("federation: failed to store key for {}: {}", origin, e),
+        }
+    }
+    stored
 }
 
 async fn ingest_verified_payload(
     state: &AppState,
     service: &FederationService,
-    mut payload: FederationEventEnvelope,
+    payload: FederationEventEnvelope,
     transport_origin: Option<&str>,
 ) -> Result<bool, ApiError> {
-    if payload.depth <= 0 {
-        payload.depth = payload.origin_ts.max(1);
-    }
-
     // Update last_seen_at for the envelope origin and immediate transport sender.
     let _ =
         paracord_db::federation::touch_federated_server(&state.db, &payload.origin_server).await;
@@ -651,46 +751,29 @@ async fn ingest_verified_payload(
         }
     }
 
+    // The event is stored exactly as signed so `get_event` and catch-up can
+    // serve it to other servers without breaking the origin's signature. The
+    // event id is unique, so a re-delivered event isn't inserted again.
     let inserted = service
         .persist_event(&state.db, &payload)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
-    // Forward the event to the local event bus so connected gateway clients see it
+    // Forward the event to the local event bus so connected gateway clients see
+    // it. A re-delivered event was already applied and is ignored.
     if inserted {
-        match payload.event_type.as_str() {
-            "m.message" => {
-                dispatch_federated_message(state, &payload).await;
-            }
-            "m.message.edit" => {
-                dispatch_federated_message_edit(state, &payload).await;
-            }
-            "m.message.delete" => {
-                dispatch_federated_message_delete(state, &payload).await;
-            }
-            "m.reaction.add" => {
-                dispatch_federated_reaction_add(state, &payload).await;
-            }
-            "m.reaction.remove" => {
-                dispatch_federated_reaction_remove(state, &payload).await;
-            }
-            "m.member.join" => {
-                dispatch_federated_member_join(state, &payload).await;
-            }
-            "m.member.leave" => {
-                dispatch_federated_member_leave(state, &payload).await;
-            }
-            _ => {
-                state.event_bus.dispatch(
-                    &format!("FEDERATION_{}", payload.event_type.to_uppercase()),
-                    json!({
-                        "event_id": payload.event_id,
-                        "origin_server": payload.origin_server,
-                        "sender": payload.sender,
-                        "content": payload.content,
-                    }),
-                    None,
-                );
+        if waits_on_unseen_message(state, &payload).await && hold_out_of_order_event(&payload) {
+            tracing::debug!(
+                "federation: holding {} {} until the message it refers to arrives",
+                payload.event_type,
+                payload.event_id
+            );
+        } else {
+            apply_federated_event(state, &payload).await;
+            if payload.event_type == "m.message" {
+                for held in take_held_events(&message_reference_keys(&payload)) {
+                    apply_federated_event(state, &held).await;
+                }
             }
         }
 
@@ -714,6 +797,135 @@ async fn ingest_verified_payload(
     Ok(inserted)
 }
 
+/// Apply an accepted event locally and tell connected gateway clients.
+async fn apply_federated_event(state: &AppState, payload: &FederationEventEnvelope) {
+    match payload.event_type.as_str() {
+        "m.message" => {
+            dispatch_federated_message(state, payload).await;
+        }
+        "m.message.edit" => {
+            dispatch_federated_message_edit(state, payload).await;
+        }
+        "m.message.delete" => {
+            dispatch_federated_message_delete(state, payload).await;
+        }
+        "m.reaction.add" => {
+            dispatch_federated_reaction_add(state, payload).await;
+        }
+        "m.reaction.remove" => {
+            dispatch_federated_reaction_remove(state, payload).await;
+        }
+        "m.member.join" => {
+            dispatch_federated_member_join(state, payload).await;
+        }
+        "m.member.leave" => {
+            dispatch_federated_member_leave(state, payload).await;
+        }
+        _ => {
+            state.event_bus.dispatch(
+                &format!("FEDERATION_{}", payload.event_type.to_uppercase()),
+                json!({
+                    "event_id": payload.event_id,
+                    "origin_server": payload.origin_server,
+                    "sender": payload.sender,
+                    "content": payload.content,
+                }),
+                None,
+            );
+        }
+    }
+}
+
+/// How long an event is held waiting for the message it refers to.
+const HELD_EVENT_TTL: Duration = Duration::from_secs(300);
+/// Cap on held events across all origins.
+const MAX_HELD_EVENTS: usize = 1_000;
+
+struct HeldEvent {
+    held_at: Instant,
+    payload: FederationEventEnvelope,
+}
+
+/// Events that arrived before the message they refer to (federated delivery
+/// isn't ordered), keyed by the reference they are waiting on.
+static HELD_EVENTS: LazyLock<Mutex<HashMap<String, Vec<HeldEvent>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Whether `payload` targets a message that hasn't been seen yet.
+async fn waits_on_unseen_message(state: &AppState, payload: &FederationEventEnvelope) -> bool {
+    matches!(
+        payload.event_type.as_str(),
+        "m.message.edit" | "m.message.delete" | "m.reaction.add" | "m.reaction.remove"
+    ) && resolve_local_message_id_from_payload(state, payload)
+        .await
+        .is_none()
+}
+
+/// Keys a held event can be waiting on: the origin's message id and the
+/// message's event id.
+fn message_reference_keys(payload: &FederationEventEnvelope) -> Vec<String> {
+    let mut keys = Vec::new();
+    if let Some(message_id) = content_str(&payload.content, "message_id") {
+        keys.push(format!("msg:{}:{}", payload.origin_server, message_id));
+    }
+    let event_id = if payload.event_type == "m.message" {
+        Some(payload.event_id.as_str())
+    } else {
+        content_str(&payload.content, "target_event_id")
+    };
+    if let Some(event_id) = event_id {
+        keys.push(format!("evt:{event_id}"));
+    }
+    keys
+}
+
+fn prune_held_events(held: &mut HashMap<String, Vec<HeldEvent>>) {
+    let mut expired = 0;
+    held.retain(|_, events| {
+        let before = events.len();
+        events.retain(|event| event.held_at.elapsed() < HELD_EVENT_TTL);
+        expired += before - events.len();
+        !events.is_empty()
+    });
+    if expired > 0 {
+        tracing::warn!(
+            "federation: dropped {} held event(s) whose target message never arrived",
+            expired
+        );
+    }
+}
+
+/// Hold an event until its target message arrives. Returns false when it
+/// can't be held, in which case it should be applied (and dropped) now.
+fn hold_out_of_order_event(payload: &FederationEventEnvelope) -> bool {
+    let Some(key) = message_reference_keys(payload).into_iter().next() else {
+        return false;
+    };
+    let mut held = HELD_EVENTS.lock().unwrap_or_else(|e| e.into_inner());
+    prune_held_events(&mut held);
+    if held.values().map(Vec::len).sum::<usize>() >= MAX_HELD_EVENTS {
+        return false;
+    }
+    held.entry(key).or_default().push(HeldEvent {
+        held_at: Instant::now(),
+        payload: payload.clone(),
+    });
+    true
+}
+
+/// Release the events waiting on any of `keys`, oldest first.
+fn take_held_events(keys: &[String]) -> Vec<FederationEventEnvelope> {
+    let mut held = HELD_EVENTS.lock().unwrap_or_else(|e| e.into_inner());
+    prune_held_events(&mut held);
+    let mut released: Vec<HeldEvent> = keys
+        .iter()
+        .filter_map(|key| held.remove(key))
+        .flatten()
+        .collect();
+    released.sort_by_key(|event| event.held_at);
+    released.into_iter().map(|event| event.payload).collect()
+}
+
 // ── Discovery & Key Exchange ────────────────────────────────────────────────
 
 pub async fn well_known() -> Result<Json<Value>, ApiError> {
@@ -812,8 +1024,14 @@ pub async fn ingest_event(
     let inserted =
         ingest_verified_payload(&state, &service, payload.clone(), Some(&transport.origin)).await?;
 
+    // Delivery is retried, so an event we already have is acknowledged as
+    // such rather than applied again.
     Ok((
-        StatusCode::ACCEPTED,
+        if inserted {
+            StatusCode::ACCEPTED
+        } else {
+            StatusCode::OK
+        },
         Json(json!({
             "event_id": payload.event_id,
             "inserted": inserted,
@@ -974,7 +1192,7 @@ async fn dispatch_federated_message(state: &AppState, payload: &FederationEventE
     let local_channel_id = channel.id;
 
     // Generate a local message ID for storage
-    let local_msg_id = paracord_util::snowflake::generate(1);
+    let local_msg_id = paracord_util::snowflake::generate();
 
     let author_id = match FederatedIdentity::parse(&payload.sender) {
         Some(identity) => match ensure_remote_user_mapping(state, &identity).await {
@@ -1215,7 +1433,7 @@ async fn ensure_federated_space_exists(
     {
         mapped
     } else {
-        paracord_util::snowflake::generate(1)
+        paracord_util::snowflake::generate()
     };
 
     if matches!(
@@ -1304,7 +1522,7 @@ async fn ensure_federated_channel_exists(
     {
         mapped
     } else {
-        paracord_util::snowflake::generate(1)
+        paracord_util::snowflake::generate()
     };
 
     if let Ok(Some(existing)) =
@@ -1710,6 +1928,12 @@ async fn dispatch_federated_member_leave(state: &AppState, payload: &FederationE
         payload.room_id.clone()
     };
     let _ = paracord_db::members::remove_member(&state.db, mapping.local_user_id, guild_id).await;
+    paracord_core::permissions::invalidate_member(
+        &state.permission_cache,
+        guild_id,
+        mapping.local_user_id,
+    )
+    .await;
     let _ = paracord_db::federation::delete_room_membership(
         &state.db,
         &room_id,
@@ -2207,6 +2431,12 @@ pub async fn leave(
         paracord_db::members::remove_member(&state.db, mapping.local_user_id, guild_id)
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        paracord_core::permissions::invalidate_member(
+            &state.permission_cache,
+            guild_id,
+            mapping.local_user_id,
+        )
+        .await;
         removed = true;
         state.member_index.remove_member(guild_id, mapping.local_user_id);
         state.event_bus.dispatch(
@@ -2515,7 +2745,7 @@ pub async fn add_server(
         }
     }
 
-    let id = paracord_util::snowflake::generate(1);
+    let id = paracord_util::snowflake::generate();
     paracord_db::federation::upsert_federated_server(
         &state.db,
         id,
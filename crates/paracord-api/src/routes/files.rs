@@ -1,22 +1,33 @@
 use axum::{
-    extract::{Multipart, Path, State},
+    body::{Body, Bytes},
+    extract::{Multipart, Path, Query, State},
     http::{header, HeaderMap, HeaderValue, StatusCode},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Json,
 };
 use chrono::{Duration, Utc};
 use jsonwebtoken::{Algorithm, EncodingKey, Header as JwtHeader};
 use paracord_core::AppState;
+use paracord_media::images;
 use paracord_models::permissions::Permissions;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
 
 const PENDING_ATTACHMENT_TTL_MINUTES: i64 = 15;
 const PENDING_ATTACHMENT_CLEANUP_BATCH: i64 = 128;
+/// Idle lifetime of a chunked upload session; every accepted chunk extends it.
+const UPLOAD_SESSION_TTL_MINUTES: i64 = 60;
+/// Leading bytes inspected when identifying an upload by its signature.
+const CONTENT_SNIFF_BYTES: usize = 8 * 1024;
 const MALWARE_SCAN_BIN_ENV: &str = "PARACORD_MALWARE_SCAN_BIN";
 const MALWARE_SCAN_ARGS_ENV: &str = "PARACORD_MALWARE_SCAN_ARGS";
 const MALWARE_SCAN_FAIL_CLOSED_ENV: &str = "PARACORD_MALWARE_SCAN_FAIL_CLOSED";
@@ -28,6 +39,10 @@ fn attachment_aad(attachment_id: i64) -> String {
     format!("{ATTACHMENT_AAD_PREFIX}{attachment_id}")
 }
 
+fn thumbnail_aad(attachment_id: i64) -> String {
+    format!("{}:thumb", attachment_aad(attachment_id))
+}
+
 fn sanitize_filename_for_disposition(filename: &str) -> String {
     filename
         .chars()
@@ -81,22 +96,24 @@ fn normalized_content_type(filename: &str, claimed: Option<&str>) -> String {
         .to_string()
 }
 
-fn is_inline_safe_content_type(content_type: &str) -> bool {
-    matches!(
-        content_type,
-        "image/jpeg"
-            | "image/png"
-            | "image/gif"
-            | "image/webp"
-            | "image/avif"
-            | "audio/mpeg"
-            | "audio/ogg"
-            | "audio/wav"
-            | "video/mp4"
-            | "video/webm"
-            | "text/plain"
-            | "application/pdf"
-    )
+fn is_inline_safe_content_type(content_type: &str, allowed: &[String]) -> bool {
+    allowed
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(content_type))
+}
+
+/// Media types that must be backed by a recognizable file signature. A claim
+/// of one of these without matching magic bytes is not trusted.
+fn requires_signature(content_type: &str) -> bool {
+    content_type.starts_with("image/")
+        || content_type.starts_with("audio/")
+        || content_type.starts_with("video/")
+        || content_type == "application/pdf"
+}
+
+fn sniff_content_type(data: &[u8]) -> Option<&'static str> {
+    let sample = &data[..data.len().min(CONTENT_SNIFF_BYTES)];
+    infer::get(sample).map(|kind| kind.mime_type())
 }
 
 fn is_active_content_type(content_type: &str) -> bool {
@@ -117,8 +134,16 @@ fn resolve_stored_content_type(filename: &str, claimed: Option<&str>, data: &[u8
         return "application/octet-stream".to_string();
     }
 
+    // The file signature wins over whatever the client claimed.
+    if let Some(sniffed) = sniff_content_type(data) {
+        if is_active_content_type(sniffed) {
+            return "application/octet-stream".to_string();
+        }
+        return sniffed.to_string();
+    }
+
     let normalized = normalized_content_type(filename, claimed);
-    if is_active_content_type(&normalized) {
+    if is_active_content_type(&normalized) || requires_signature(&normalized) {
         return "application/octet-stream".to_string();
     }
 
@@ -157,6 +182,19 @@ fn parse_infected_exit_codes() -> Vec<i32> {
         .unwrap_or_else(|| vec![1])
 }
 
+/// Device names Windows reserves regardless of extension (`CON`, `CON.txt`).
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+fn is_windows_reserved_name(filename: &str) -> bool {
+    let base = filename.split('.').next().unwrap_or(filename);
+    WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|reserved| base.eq_ignore_ascii_case(reserved))
+}
+
 fn sanitize_filename_for_path(filename: &str) -> String {
     let mut out = String::new();
     for ch in filename.chars() {
@@ -166,8 +204,14 @@ fn sanitize_filename_for_path(filename: &str) -> String {
             out.push('_');
         }
     }
+    // Windows silently drops trailing dots (spaces are already replaced), so
+    // `con.` would open `CON`.
+    let trimmed_len = out.trim_end_matches('.').len();
+    out.truncate(trimmed_len);
     if out.is_empty() {
         "upload.bin".to_string()
+    } else if is_windows_reserved_name(&out) {
+        format!("_{out}")
     } else {
         out
     }
@@ -254,7 +298,7 @@ async fn move_to_quarantine(
 }
 
 async fn scan_upload_with_malware_hook(
-    data: &[u8],
+    content: &UploadContent<'_>,
     filename: &str,
     storage_path: &str,
     attachment_id: i64,
@@ -277,9 +321,11 @@ async fn scan_upload_with_malware_hook(
         attachment_id,
         uuid::Uuid::new_v4()
     ));
-    tokio::fs::write(&temp_file, data)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    match content {
+        UploadContent::Bytes(data) => tokio::fs::write(&temp_file, data).await,
+        UploadContent::File { path, .. } => tokio::fs::copy(path, &temp_file).await.map(|_| ()),
+    }
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
     let (scan_bin, scan_args) = build_scanner_command(&temp_file, filename).unwrap_or_else(|| {
         (
@@ -466,6 +512,116 @@ async fn cleanup_expired_pending_attachments(state: &AppState) {
             .unwrap_or("bin");
         let storage_key = format!("attachments/{}.{}", attachment.id, ext);
         let _ = state.storage_backend.delete(&storage_key).await;
+        let _ = state
+            .storage_backend
+            .delete(&images::thumbnail_storage_key(attachment.id, ext))
+            .await;
+    }
+}
+
+/// Remove the stored files of a deleted channel or guild. Runs after the
+/// rows are gone, so a file that fails to delete is only left behind in
+/// storage and never referenced again.
+pub(crate) async fn remove_stored_files(
+    state: &AppState,
+    removed: &paracord_db::channels::RemovedFiles,
+) {
+    for (attachment_id, filename) in &removed.attachments {
+        let ext = std::path::Path::new(filename)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("bin");
+        let storage_key = format!("attachments/{}.{}", attachment_id, ext);
+        if let Err(err) = state.storage_backend.delete(&storage_key).await {
+            tracing::warn!("Failed deleting attachment file {}: {}", storage_key, err);
+        }
+        let _ = state
+            .storage_backend
+            .delete(&images::thumbnail_storage_key(*attachment_id, ext))
+            .await;
+    }
+    for (emoji_id, animated) in &removed.emojis {
+        let _ = state
+            .storage_backend
+            .delete(&images::emoji_storage_key(*emoji_id, *animated))
+            .await;
+    }
+    for (role_id, hash) in &removed.role_icons {
+        let _ = state
+            .storage_backend
+            .delete(&images::role_icon_storage_key(*role_id, hash))
+            .await;
+    }
+    for (guild_id, hash) in &removed.space_icons {
+        for size in images::AVATAR_VARIANT_SIZES {
+            let _ = state
+                .storage_backend
+                .delete(&images::guild_icon_storage_key(*guild_id, hash, size))
+                .await;
+        }
+    }
+    for (guild_id, hash) in &removed.space_banners {
+        let _ = state
+            .storage_backend
+            .delete(&images::guild_banner_storage_key(*guild_id, hash))
+            .await;
+    }
+}
+
+/// Record the pixel dimensions of an image upload and store a thumbnail next
+/// to the original. Malformed or oversized images only skip this step; they
+/// never fail the upload.
+async fn process_image_attachment(
+    state: &AppState,
+    attachment_id: i64,
+    ext: &str,
+    content_type: &str,
+    data: &[u8],
+) -> (Option<i32>, Option<i32>) {
+    if !content_type.starts_with("image/") {
+        return (None, None);
+    }
+    let owned = data.to_vec();
+    let Ok((dimensions, thumbnail)) = tokio::task::spawn_blocking(move || {
+        let dimensions = images::image_dimensions(&owned);
+        let thumbnail =
+            dimensions.and_then(|_| images::generate_thumbnail(&owned, images::THUMBNAIL_MAX_EDGE));
+        (dimensions, thumbnail)
+    })
+    .await
+    else {
+        return (None, None);
+    };
+
+    if let Some(thumbnail) = thumbnail {
+        let payload = match state.config.file_cryptor.as_ref() {
+            Some(cryptor) => cryptor
+                .encrypt_with_aad(&thumbnail.data, thumbnail_aad(attachment_id).as_bytes())
+                .map_err(|err| err.to_string()),
+            None => Ok(thumbnail.data),
+        };
+        let key = images::thumbnail_storage_key(attachment_id, ext);
+        let stored = match payload {
+            Ok(payload) => state
+                .storage_backend
+                .store(&key, &payload)
+                .await
+                .map(|_| ())
+                .map_err(|err| err.to_string()),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = stored {
+            tracing::warn!(
+                "Failed storing thumbnail for attachment {}: {}",
+                attachment_id,
+                err
+            );
+        }
+    }
+
+    match dimensions {
+        Some((width, height)) => (i32::try_from(width).ok(), i32::try_from(height).ok()),
+        None => (None, None),
     }
 }
 
@@ -488,7 +644,8 @@ pub async fn upload_file(
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
             .ok_or(ApiError::NotFound)?;
-        let perms = paracord_core::permissions::compute_channel_permissions(
+        let perms = paracord_core::permissions::compute_channel_permissions_cached(
+            &state.permission_cache,
             &state.db,
             guild_id,
             channel_id,
@@ -503,6 +660,8 @@ pub async fn upload_file(
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
     {
         return Err(ApiError::Forbidden);
+    } else {
+        paracord_core::message::ensure_dm_not_blocked(&state.db, channel_id, auth.user_id).await?;
     }
 
     let field = multipart
@@ -536,13 +695,20 @@ pub async fn upload_file(
     let content_hash = format!("{:x}", hasher.finalize());
 
     // Check guild-level upload policy (file size, quota, type restrictions)
-    let resolved_ct = normalized_content_type(&filename, claimed_content_type.as_deref());
-    check_guild_upload_policy(&state, channel_id, size, &resolved_ct).await?;
+    // against the type sniffed from the bytes, not the client's claim.
+    let content_type =
+        resolve_stored_content_type(&filename, claimed_content_type.as_deref(), &data);
+    check_guild_upload_policy(&state, channel_id, size, &content_type).await?;
 
     // Store file via storage backend
-    let attachment_id = paracord_util::snowflake::generate(1);
-    scan_upload_with_malware_hook(&data, &filename, &state.config.storage_path, attachment_id)
-        .await?;
+    let attachment_id = paracord_util::snowflake::generate();
+    scan_upload_with_malware_hook(
+        &UploadContent::Bytes(&data),
+        &filename,
+        &state.config.storage_path,
+        attachment_id,
+    )
+    .await?;
 
     let ext = std::path::Path::new(&filename)
         .extension()
@@ -566,8 +732,8 @@ pub async fn upload_file(
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
     let url = format!("/api/v1/attachments/{}", attachment_id);
-    let content_type =
-        resolve_stored_content_type(&filename, claimed_content_type.as_deref(), &data);
+    let (width, height) =
+        process_image_attachment(&state, attachment_id, ext, &content_type, &data).await;
     let expires_at = Utc::now() + Duration::minutes(PENDING_ATTACHMENT_TTL_MINUTES);
 
     let attachment = paracord_db::attachments::create_attachment(
@@ -578,8 +744,8 @@ pub async fn upload_file(
         Some(&content_type),
         db_size,
         &url,
-        None,
-        None,
+        width,
+        height,
         Some(auth.user_id),
         Some(channel_id),
         Some(expires_at),
@@ -596,15 +762,149 @@ pub async fn upload_file(
             "size": attachment.size,
             "content_type": attachment.content_type,
             "url": attachment.url,
+            "width": attachment.width,
+            "height": attachment.height,
         })),
     ))
 }
 
+/// Strong validator for an attachment. Stored attachments are immutable, so
+/// the id (plus which rendition) identifies the representation.
+fn attachment_etag(attachment_id: i64, thumbnail: bool) -> String {
+    if thumbnail {
+        format!("\"{}-thumb\"", attachment_id)
+    } else {
+        format!("\"{}\"", attachment_id)
+    }
+}
+
+fn header_matches_etag(headers: &HeaderMap, name: header::HeaderName, etag: &str) -> bool {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+        })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    Full,
+    Partial { start: u64, end: u64 },
+    Unsatisfiable,
+}
+
+/// Resolve a `Range` header against an object of `size` bytes. Only a single
+/// `bytes` range is supported; multi-range requests are unsatisfiable and
+/// malformed headers are ignored, as RFC 9110 allows.
+fn parse_range_header(value: &str, size: u64) -> ByteRange {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Unsatisfiable;
+    }
+    let Some((first, last)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+    let (first, last) = (first.trim(), last.trim());
+
+    if first.is_empty() {
+        // Suffix range: the final `last` bytes.
+        return match last.parse::<u64>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(_) if size == 0 => ByteRange::Unsatisfiable,
+            Ok(suffix) => ByteRange::Partial {
+                start: size.saturating_sub(suffix),
+                end: size - 1,
+            },
+            Err(_) => ByteRange::Full,
+        };
+    }
+
+    let Ok(start) = first.parse::<u64>() else {
+        return ByteRange::Full;
+    };
+    let end = if last.is_empty() {
+        u64::MAX
+    } else {
+        match last.parse::<u64>() {
+            Ok(end) if end >= start => end,
+            _ => return ByteRange::Full,
+        }
+    };
+    if start >= size {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial {
+        start,
+        end: end.min(size - 1),
+    }
+}
+
+/// Load an attachment payload and decrypt it, transparently re-encrypting
+/// legacy plaintext objects written before file encryption was enabled.
+async fn decrypt_attachment_payload(
+    state: &AppState,
+    cryptor: &paracord_util::at_rest::FileCryptor,
+    attachment_id: i64,
+    storage_key: &str,
+    aad: &str,
+) -> Result<Vec<u8>, ApiError> {
+    let stored_data = state
+        .storage_backend
+        .retrieve(storage_key)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    match cryptor.decrypt_with_aad(&stored_data, aad.as_bytes()) {
+        Ok(decrypted) => Ok(decrypted),
+        Err(paracord_util::at_rest::FileCryptoError::PlaintextReadDisabled)
+            if !paracord_util::at_rest::FileCryptor::payload_is_encrypted(&stored_data) =>
+        {
+            tracing::warn!(
+                "Serving legacy plaintext attachment {} while file encryption is enabled; re-encrypting in place",
+                attachment_id
+            );
+            match cryptor.encrypt_with_aad(&stored_data, aad.as_bytes()) {
+                Ok(reencrypted) => {
+                    if let Err(err) = state.storage_backend.store(storage_key, &reencrypted).await {
+                        tracing::warn!(
+                            "Failed to re-encrypt attachment {} in storage: {}",
+                            attachment_id,
+                            err
+                        );
+                    }
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to encrypt legacy plaintext attachment {}: {}",
+                        attachment_id,
+                        err
+                    );
+                }
+            }
+            Ok(stored_data)
+        }
+        Err(err) => Err(ApiError::Internal(anyhow::anyhow!(err.to_string()))),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DownloadQuery {
+    /// `thumb` selects the generated thumbnail instead of the original.
+    pub size: Option<String>,
+}
+
 pub async fn download_file(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(id): Path<i64>,
-) -> Result<impl IntoResponse, ApiError> {
+    Query(query): Query<DownloadQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
     let attachment = paracord_db::attachments::get_attachment(&state.db, id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
@@ -626,7 +926,8 @@ pub async fn download_file(
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
             .ok_or(ApiError::NotFound)?;
-        let perms = paracord_core::permissions::compute_channel_permissions(
+        let perms = paracord_core::permissions::compute_channel_permissions_cached(
+            &state.permission_cache,
             &state.db,
             guild_id,
             channel.id,
@@ -647,76 +948,145 @@ pub async fn download_file(
         .extension()
         .and_then(|e| e.to_str())
         .unwrap_or("bin");
-    let storage_key = format!("attachments/{}.{}", attachment.id, ext);
-    let stored_data = state
-        .storage_backend
-        .retrieve(&storage_key)
-        .await
-        .map_err(|_| ApiError::NotFound)?;
-    let data = if let Some(cryptor) = state.config.file_cryptor.as_ref() {
-        let aad = attachment_aad(attachment.id);
-        match cryptor.decrypt_with_aad(&stored_data, aad.as_bytes()) {
-            Ok(decrypted) => decrypted,
-            Err(paracord_util::at_rest::FileCryptoError::PlaintextReadDisabled)
-                if !paracord_util::at_rest::FileCryptor::payload_is_encrypted(&stored_data) =>
-            {
-                tracing::warn!(
-                    "Serving legacy plaintext attachment {} while file encryption is enabled; re-encrypting in place",
-                    attachment.id
-                );
-                match cryptor.encrypt_with_aad(&stored_data, aad.as_bytes()) {
-                    Ok(reencrypted) => {
-                        if let Err(err) = state.storage_backend.store(&storage_key, &reencrypted).await
-                        {
-                            tracing::warn!(
-                                "Failed to re-encrypt attachment {} in storage: {}",
-                                attachment.id,
-                                err
-                            );
-                        }
-                    }
-                    Err(err) => {
-                        tracing::warn!(
-                            "Failed to encrypt legacy plaintext attachment {}: {}",
-                            attachment.id,
-                            err
-                        );
-                    }
-                }
-                stored_data
-            }
-            Err(err) => return Err(ApiError::Internal(anyhow::anyhow!(err.to_string()))),
-        }
+    // Images small enough not to need a thumbnail have none stored; serve the
+    // original for them.
+    let thumb_key = images::thumbnail_storage_key(attachment.id, ext);
+    let use_thumbnail = query.size.as_deref() == Some("thumb")
+        && state
+            .storage_backend
+            .exists(&thumb_key)
+            .await
+            .unwrap_or(false);
+    let (storage_key, aad) = if use_thumbnail {
+        (thumb_key, thumbnail_aad(attachment.id))
     } else {
-        stored_data
+        (
+            format!("attachments/{}.{}", attachment.id, ext),
+            attachment_aad(attachment.id),
+        )
     };
+    let etag = attachment_etag(attachment.id, use_thumbnail);
+    let etag_value = HeaderValue::from_str(&etag).map_err(|e| ApiError::Internal(e.into()))?;
+    if header_matches_etag(&headers, header::IF_NONE_MATCH, &etag) {
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        response.headers_mut().insert(header::ETAG, etag_value);
+        return Ok(response);
+    }
+
     let content_type = attachment
         .content_type
         .clone()
         .unwrap_or_else(|| "application/octet-stream".to_string());
     let allow_inline =
-        is_inline_safe_content_type(&content_type) && !has_active_extension(&attachment.filename);
+        is_inline_safe_content_type(&content_type, &state.config.inline_content_types)
+            && !has_active_extension(&attachment.filename);
     let disposition = build_content_disposition(&attachment.filename, allow_inline);
 
-    Ok((
-        [
-            (
-                header::CONTENT_TYPE,
-                HeaderValue::from_str(&content_type)
-                    .unwrap_or(HeaderValue::from_static("application/octet-stream")),
-            ),
-            (
-                header::CONTENT_DISPOSITION,
-                HeaderValue::from_str(&disposition)
-                    .unwrap_or(HeaderValue::from_static("attachment")),
-            ),
-            (
-                header::X_CONTENT_TYPE_OPTIONS,
-                HeaderValue::from_static("nosniff"),
-            ),
-        ],
-        data,
-    ))
+    // Object stores configured for it serve the bytes directly. Encrypted
+    // payloads always go through the server, which holds the key.
+    if state.config.file_cryptor.is_none() {
+        let redirect = state
+            .storage_backend
+            .download_url(&storage_key, &content_type, &disposition)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        if let Some(url) = redirect {
+            let location = HeaderValue::from_str(&url).map_err(|e| ApiError::Internal(e.into()))?;
+            let mut response = StatusCode::TEMPORARY_REDIRECT.into_response();
+            let response_headers = response.headers_mut();
+            response_headers.insert(header::LOCATION, location);
+            // The presigned URL expires, so the redirect must not be cached.
+            response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+            return Ok(response);
+        }
+    }
+
+    // A stale If-Range means the client's partial copy is outdated: send it all.
+    let range_header = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|_| {
+            !headers.contains_key(header::IF_RANGE)
+                || header_matches_etag(&headers, header::IF_RANGE, &etag)
+        });
+
+    // Encrypted payloads can only be decrypted whole, so they are served from
+    // memory; plaintext payloads are streamed straight from storage.
+    let decrypted = if let Some(cryptor) = state.config.file_cryptor.as_ref() {
+        Some(decrypt_attachment_payload(&state, cryptor, attachment.id, &storage_key, &aad).await?)
+    } else {
+        None
+    };
+    let size = match decrypted.as_ref() {
+        Some(data) => data.len() as u64,
+        None => state
+            .storage_backend
+            .size(&storage_key)
+            .await
+            .map_err(|_| ApiError::NotFound)?,
+    };
+
+    let (status, start, len) = match range_header.map(|value| parse_range_header(value, size)) {
+        None | Some(ByteRange::Full) => (StatusCode::OK, 0, size),
+        Some(ByteRange::Partial { start, end }) => {
+            (StatusCode::PARTIAL_CONTENT, start, end - start + 1)
+        }
+        Some(ByteRange::Unsatisfiable) => {
+            let mut response = StatusCode::RANGE_NOT_SATISFIABLE.into_response();
+            let headers = response.headers_mut();
+            headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+            headers.insert(header::ETAG, etag_value);
+            if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", size)) {
+                headers.insert(header::CONTENT_RANGE, value);
+            }
+            return Ok(response);
+        }
+    };
+
+    let body = match decrypted {
+        Some(mut data) => {
+            data.truncate((start + len) as usize);
+            data.drain(..start as usize);
+            Body::from(data)
+        }
+        None if len == 0 => Body::empty(),
+        None => {
+            let reader = state
+                .storage_backend
+                .open_range(&storage_key, start, len)
+                .await
+                .map_err(|_| ApiError::NotFound)?;
+            Body::from_stream(ReaderStream::new(reader))
+        }
+    };
+
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    let response_headers = response.headers_mut();
+    response_headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str(&content_type)
+            .unwrap_or(HeaderValue::from_static("application/octet-stream")),
+    );
+    response_headers.insert(
+        header::CONTENT_DISPOSITION,
+        HeaderValue::from_str(&disposition).unwrap_or(HeaderValue::from_static("attachment")),
+    );
+    response_headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    response_headers.insert(header::ETAG, etag_value);
+    response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+    if status == StatusCode::PARTIAL_CONTENT {
+        if let Ok(value) =
+            HeaderValue::from_str(&format!("bytes {}-{}/{}", start, start + len - 1, size))
+        {
+            response_headers.insert(header::CONTENT_RANGE, value);
+        }
+    }
+    Ok(response)
 }
 
 pub async fn delete_file(
@@ -751,6 +1121,10 @@ pub async fn delete_file(
         .unwrap_or("bin");
     let storage_key = format!("attachments/{}.{}", attachment.id, ext);
     let _ = state.storage_backend.delete(&storage_key).await;
+    let _ = state
+        .storage_backend
+        .delete(&images::thumbnail_storage_key(attachment.id, ext))
+        .await;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -773,7 +1147,8 @@ pub async fn validate_upload_permissions(
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
             .ok_or(ApiError::NotFound)?;
-        let perms = paracord_core::permissions::compute_channel_permissions(
+        let perms = paracord_core::permissions::compute_channel_permissions_cached(
+            &state.permission_cache,
             &state.db,
             guild_id,
             channel_id,
@@ -788,10 +1163,74 @@ pub async fn validate_upload_permissions(
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
     {
         return Err(ApiError::Forbidden);
+    } else {
+        paracord_core::message::ensure_dm_not_blocked(&state.db, channel_id, user_id).await?;
     }
     Ok(())
 }
 
+/// The bytes of an upload being processed: in memory, or a file on disk
+/// (a finished chunked upload) that is streamed rather than read whole.
+enum UploadContent<'a> {
+    Bytes(&'a [u8]),
+    File {
+        path: &'a std::path::Path,
+        size: u64,
+    },
+}
+
+impl UploadContent<'_> {
+    fn size(&self) -> u64 {
+        match self {
+            UploadContent::Bytes(data) => data.len() as u64,
+            UploadContent::File { size, .. } => *size,
+        }
+    }
+
+    /// SHA-256 of the content, reading a file in fixed-size blocks.
+    async fn sha256_hex(&self) -> std::io::Result<String> {
+        let mut hasher = Sha256::new();
+        match self {
+            UploadContent::Bytes(data) => hasher.update(data),
+            UploadContent::File { path, .. } => {
+                let mut file = tokio::fs::File::open(path).await?;
+                let mut buf = vec![0u8; 64 * 1024];
+                loop {
+                    let read = file.read(&mut buf).await?;
+                    if read == 0 {
+                        break;
+                    }
+                    hasher.update(&buf[..read]);
+                }
+            }
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// The leading bytes used for content sniffing.
+    async fn head(&self) -> std::io::Result<Vec<u8>> {
+        match self {
+            UploadContent::Bytes(data) => Ok(data[..data.len().min(CONTENT_SNIFF_BYTES)].to_vec()),
+            UploadContent::File { path, .. } => {
+                let file = tokio::fs::File::open(path).await?;
+                let mut head = Vec::with_capacity(CONTENT_SNIFF_BYTES);
+                file.take(CONTENT_SNIFF_BYTES as u64)
+                    .read_to_end(&mut head)
+                    .await?;
+                Ok(head)
+            }
+        }
+    }
+
+    /// The whole content in memory, for steps that need all of it at once.
+    async fn to_vec(&self) -> std::io::Result<Vec<u8>> {
+        match self {
+            UploadContent::Bytes(data) => Ok(data.to_vec()),
+            UploadContent::File { path, .. } => tokio::fs::read(path).await,
+        }
+    }
+}
+
 /// Process an uploaded file: malware scan, encrypt, store, and create DB record.
 ///
 /// Returns the attachment JSON value on success.
@@ -803,8 +1242,26 @@ pub async fn process_uploaded_file(
     channel_id: i64,
     user_id: i64,
 ) -> Result<Value, ApiError> {
-    let size =
-        u64::try_from(data.len()).map_err(|_| ApiError::BadRequest("File too large".into()))?;
+    store_upload(
+        state,
+        UploadContent::Bytes(data),
+        filename,
+        claimed_content_type,
+        channel_id,
+        user_id,
+    )
+    .await
+}
+
+async fn store_upload(
+    state: &AppState,
+    content: UploadContent<'_>,
+    filename: &str,
+    claimed_content_type: Option<&str>,
+    channel_id: i64,
+    user_id: i64,
+) -> Result<Value, ApiError> {
+    let size = content.size();
     if size == 0 {
         return Err(ApiError::BadRequest("Empty file".into()));
     }
@@ -814,18 +1271,22 @@ pub async fn process_uploaded_file(
     let db_size =
         i32::try_from(size).map_err(|_| ApiError::BadRequest("File too large".into()))?;
 
-    // Compute SHA-256 content hash
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    let content_hash = format!("{:x}", hasher.finalize());
+    let io_error = |e: std::io::Error| ApiError::Internal(anyhow::anyhow!(e.to_string()));
+    let content_hash = content.sha256_hex().await.map_err(io_error)?;
 
     // Check guild-level upload policy
-    let resolved_ct = normalized_content_type(filename, claimed_content_type);
-    check_guild_upload_policy(state, channel_id, size, &resolved_ct).await?;
+    let head = content.head().await.map_err(io_error)?;
+    let content_type = resolve_stored_content_type(filename, claimed_content_type, &head);
+    check_guild_upload_policy(state, channel_id, size, &content_type).await?;
 
-    let attachment_id = paracord_util::snowflake::generate(1);
-    scan_upload_with_malware_hook(data, filename, &state.config.storage_path, attachment_id)
-        .await?;
+    let attachment_id = paracord_util::snowflake::generate();
+    scan_upload_with_malware_hook(
+        &content,
+        filename,
+        &state.config.storage_path,
+        attachment_id,
+    )
+    .await?;
 
     let ext = std::path::Path::new(filename)
         .extension()
@@ -833,23 +1294,31 @@ pub async fn process_uploaded_file(
         .unwrap_or("bin");
     let storage_key = format!("attachments/{}.{}", attachment_id, ext);
 
-    let stored_payload = if let Some(cryptor) = state.config.file_cryptor.as_ref() {
-        let aad = attachment_aad(attachment_id);
-        cryptor
-            .encrypt_with_aad(data, aad.as_bytes())
-            .map_err(|err| ApiError::Internal(anyhow::anyhow!(err.to_string())))?
-    } else {
-        data.to_vec()
+    // At-rest encryption seals the file as one message, so it needs the whole
+    // plaintext; otherwise a file on disk goes to storage without buffering.
+    let stored = match (state.config.file_cryptor.as_ref(), &content) {
+        (Some(cryptor), _) => {
+            let aad = attachment_aad(attachment_id);
+            let sealed = cryptor
+                .encrypt_with_aad(&content.to_vec().await.map_err(io_error)?, aad.as_bytes())
+                .map_err(|err| ApiError::Internal(anyhow::anyhow!(err.to_string())))?;
+            state.storage_backend.store(&storage_key, &sealed).await
+        }
+        (None, UploadContent::Bytes(data)) => state.storage_backend.store(&storage_key, data).await,
+        (None, UploadContent::File { path, .. }) => {
+            state.storage_backend.store_file(&storage_key, path).await
+        }
     };
-
-    state
-        .storage_backend
-        .store(&storage_key, &stored_payload)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    stored.map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
     let url = format!("/api/v1/attachments/{}", attachment_id);
-    let content_type = resolve_stored_content_type(filename, claimed_content_type, data);
+    // Thumbnails decode the whole image, so only images are read in full.
+    let (width, height) = if content_type.starts_with("image/") {
+        let data = content.to_vec().await.map_err(io_error)?;
+        process_image_attachment(state, attachment_id, ext, &content_type, &data).await
+    } else {
+        (None, None)
+    };
     let expires_at = Utc::now() + Duration::minutes(PENDING_ATTACHMENT_TTL_MINUTES);
 
     let attachment = paracord_db::attachments::create_attachment(
@@ -860,8 +1329,8 @@ pub async fn process_uploaded_file(
         Some(&content_type),
         db_size,
         &url,
-        None,
-        None,
+        width,
+        height,
         Some(user_id),
         Some(channel_id),
         Some(expires_at),
@@ -876,6 +1345,8 @@ pub async fn process_uploaded_file(
         "size": attachment.size,
         "content_type": attachment.content_type,
         "url": attachment.url,
+        "width": attachment.width,
+        "height": attachment.height,
     }))
 }
 
@@ -927,7 +1398,7 @@ pub async fn upload_token(
     check_guild_upload_policy(&state, channel_id, req.size, &resolved_ct).await?;
 
     // 3. Generate transfer ID
-    let transfer_id = paracord_util::snowflake::generate(1).to_string();
+    let transfer_id = paracord_util::snowflake::generate().to_string();
 
     // 4. Mint upload JWT (15 min expiry)
     let now = Utc::now();
@@ -980,6 +1451,344 @@ pub async fn upload_token(
     })))
 }
 
+// ── Chunked (resumable) uploads ─────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct InitChunkedUploadRequest {
+    pub filename: String,
+    pub size: u64,
+    pub content_type: Option<String>,
+}
+
+/// Sessions with a chunk write or finalize currently in flight. Chunks must
+/// land in order, so a second concurrent request for the same session is
+/// rejected rather than allowed to interleave writes into the temp file.
+static ACTIVE_UPLOAD_SESSIONS: OnceLock<std::sync::Mutex<HashSet<i64>>> = OnceLock::new();
+
+struct UploadSessionGuard(i64);
+
+impl UploadSessionGuard {
+    fn acquire(session_id: i64) -> Result<Self, ApiError> {
+        let mut active = ACTIVE_UPLOAD_SESSIONS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !active.insert(session_id) {
+            return Err(ApiError::Conflict(
+                "Another request for this upload is in progress".into(),
+            ));
+        }
+        Ok(Self(session_id))
+    }
+}
+
+impl Drop for UploadSessionGuard {
+    fn drop(&mut self) {
+        let mut active = ACTIVE_UPLOAD_SESSIONS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        active.remove(&self.0);
+    }
+}
+
+fn upload_session_temp_path(storage_path: &str, session_id: i64) -> PathBuf {
+    std::path::Path::new(storage_path)
+        .join("upload-sessions")
+        .join(format!("{}.part", session_id))
+}
+
+/// Parse `Content-Range: bytes <start>-<end>/<total|*>` into an inclusive
+/// byte range and the optional declared total.
+fn parse_content_range(value: &str) -> Option<(u64, u64, Option<u64>)> {
+    let spec = value.trim().strip_prefix("bytes ")?;
+    let (range, total) = spec.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let start: u64 = start.trim().parse().ok()?;
+    let end: u64 = end.trim().parse().ok()?;
+    if end < start {
+        return None;
+    }
+    let total = match total.trim() {
+        "*" => None,
+        raw => Some(raw.parse().ok()?),
+    };
+    Some((start, end, total))
+}
+
+fn upload_session_json(session: &paracord_db::upload_sessions::UploadSessionRow) -> Value {
+    json!({
+        "id": session.id.to_string(),
+        "filename": session.filename,
+        "size": session.total_size,
+        "offset": session.received_bytes,
+        "expires_at": session.expires_at.to_rfc3339(),
+    })
+}
+
+async fn cleanup_expired_upload_sessions(state: &AppState) {
+    let expired = match paracord_db::upload_sessions::get_expired_upload_sessions(
+        &state.db,
+        Utc::now(),
+        PENDING_ATTACHMENT_CLEANUP_BATCH,
+    )
+    .await
+    {
+        Ok(rows) => rows,
+        Err(err) => {
+            tracing::warn!("Failed loading expired upload sessions: {}", err);
+            return;
+        }
+    };
+
+    for session in expired {
+        if let Err(err) =
+            paracord_db::upload_sessions::delete_upload_session(&state.db, session.id).await
+        {
+            tracing::warn!(
+                "Failed deleting expired upload session {}: {}",
+                session.id,
+                err
+            );
+            continue;
+        }
+        let temp_path = upload_session_temp_path(&state.config.storage_path, session.id);
+        let _ = tokio::fs::remove_file(&temp_path).await;
+    }
+}
+
+/// Load a session, hiding sessions that belong to another user or channel.
+async fn load_upload_session(
+    state: &AppState,
+    channel_id: i64,
+    session_id: i64,
+    user_id: i64,
+) -> Result<paracord_db::upload_sessions::UploadSessionRow, ApiError> {
+    let session = paracord_db::upload_sessions::get_upload_session(&state.db, session_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    if session.user_id != user_id
+        || session.channel_id != channel_id
+        || session.expires_at <= Utc::now()
+    {
+        return Err(ApiError::NotFound);
+    }
+    Ok(session)
+}
+
+pub async fn init_chunked_upload(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+    Json(req): Json<InitChunkedUploadRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    cleanup_expired_pending_attachments(&state).await;
+    cleanup_expired_upload_sessions(&state).await;
+
+    validate_upload_permissions(&state, channel_id, auth.user_id).await?;
+
+    if req.filename.trim().is_empty() {
+        return Err(ApiError::BadRequest("Filename is required".into()));
+    }
+    if req.size == 0 {
+        return Err(ApiError::BadRequest("Empty file".into()));
+    }
+    // The declared size bounds every chunk written later, so checking it here
+    // enforces `max_upload_size` across the whole upload.
+    if req.size > state.config.max_upload_size || i32::try_from(req.size).is_err() {
+        return Err(ApiError::BadRequest("File too large".into()));
+    }
+    let total_size =
+        i64::try_from(req.size).map_err(|_| ApiError::BadRequest("File too large".into()))?;
+
+    // Fail fast on guild policy; it is checked again when the upload completes.
+    let resolved_ct = normalized_content_type(&req.filename, req.content_type.as_deref());
+    check_guild_upload_policy(&state, channel_id, req.size, &resolved_ct).await?;
+
+    let session_id = paracord_util::snowflake::generate();
+    let temp_path = upload_session_temp_path(&state.config.storage_path, session_id);
+    if let Some(parent) = temp_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
+    tokio::fs::File::create(&temp_path)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let expires_at = Utc::now() + Duration::minutes(UPLOAD_SESSION_TTL_MINUTES);
+    let session = match paracord_db::upload_sessions::create_upload_session(
+        &state.db,
+        session_id,
+        auth.user_id,
+        channel_id,
+        &req.filename,
+        req.content_type.as_deref(),
+        total_size,
+        expires_at,
+    )
+    .await
+    {
+        Ok(session) => session,
+        Err(err) => {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(ApiError::Internal(anyhow::anyhow!(err.to_string())));
+        }
+    };
+
+    Ok((StatusCode::CREATED, Json(upload_session_json(&session))))
+}
+
+pub async fn get_chunked_upload(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((channel_id, session_id)): Path<(i64, i64)>,
+) -> Result<Json<Value>, ApiError> {
+    let session = load_upload_session(&state, channel_id, session_id, auth.user_id).await?;
+    Ok(Json(upload_session_json(&session)))
+}
+
+pub async fn upload_chunk(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((channel_id, session_id)): Path<(i64, i64)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Value>, ApiError> {
+    let _guard = UploadSessionGuard::acquire(session_id)?;
+    let session = load_upload_session(&state, channel_id, session_id, auth.user_id).await?;
+
+    let (start, end, declared_total) = headers
+        .get(header::CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_content_range)
+        .ok_or_else(|| {
+            ApiError::BadRequest("A valid Content-Range: bytes start-end/total is required".into())
+        })?;
+    let chunk_len = (end - start)
+        .checked_add(1)
+        .ok_or_else(|| ApiError::BadRequest("Content-Range is out of range".into()))?;
+    if body.is_empty() || chunk_len != body.len() as u64 {
+        return Err(ApiError::BadRequest(
+            "Content-Range does not match the chunk length".into(),
+        ));
+    }
+    if declared_total.is_some_and(|total| total != session.total_size as u64) {
+        return Err(ApiError::BadRequest(
+            "Content-Range total does not match the upload size".into(),
+        ));
+    }
+    if start != session.received_bytes as u64 {
+        return Err(ApiError::Conflict(format!(
+            "Upload offset is {}",
+            session.received_bytes
+        )));
+    }
+    let new_offset = end
+        .checked_add(1)
+        .ok_or_else(|| ApiError::BadRequest("Content-Range is out of range".into()))?;
+    if new_offset > session.total_size as u64 {
+        return Err(ApiError::BadRequest("File too large".into()));
+    }
+
+    let temp_path = upload_session_temp_path(&state.config.storage_path, session.id);
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .open(&temp_path)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    file.seek(std::io::SeekFrom::Start(start))
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    file.write_all(&body)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    // Only advertise the new offset once the bytes are on disk.
+    file.sync_data()
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let expires_at = Utc::now() + Duration::minutes(UPLOAD_SESSION_TTL_MINUTES);
+    let advanced = paracord_db::upload_sessions::advance_upload_offset(
+        &state.db,
+        session.id,
+        start as i64,
+        new_offset as i64,
+        expires_at,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !advanced {
+        return Err(ApiError::Conflict("Upload offset changed".into()));
+    }
+
+    Ok(Json(json!({
+        "id": session.id.to_string(),
+        "size": session.total_size,
+        "offset": new_offset,
+        "expires_at": expires_at.to_rfc3339(),
+    })))
+}
+
+pub async fn complete_chunked_upload(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((channel_id, session_id)): Path<(i64, i64)>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let _guard = UploadSessionGuard::acquire(session_id)?;
+    let session = load_upload_session(&state, channel_id, session_id, auth.user_id).await?;
+    if session.received_bytes != session.total_size {
+        return Err(ApiError::BadRequest(format!(
+            "Upload incomplete: received {} of {} bytes",
+            session.received_bytes, session.total_size
+        )));
+    }
+
+    // Permissions may have changed since the session was opened.
+    validate_upload_permissions(&state, channel_id, auth.user_id).await?;
+
+    let temp_path = upload_session_temp_path(&state.config.storage_path, session.id);
+    let size = tokio::fs::metadata(&temp_path)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .len();
+    if size != session.total_size as u64 {
+        return Err(ApiError::Internal(anyhow::anyhow!(
+            "upload session {} temp file is {} bytes, expected {}",
+            session.id,
+            size,
+            session.total_size
+        )));
+    }
+
+    let attachment = store_upload(
+        &state,
+        UploadContent::File {
+            path: &temp_path,
+            size,
+        },
+        &session.filename,
+        session.content_type.as_deref(),
+        channel_id,
+        auth.user_id,
+    )
+    .await?;
+
+    if let Err(err) =
+        paracord_db::upload_sessions::delete_upload_session(&state.db, session.id).await
+    {
+        tracing::warn!(
+            "Failed deleting completed upload session {}: {}",
+            session.id,
+            err
+        );
+    }
+    let _ = tokio::fs::remove_file(&temp_path).await;
+
+    Ok((StatusCode::CREATED, Json(attachment)))
+}
+
 // ── Federated file proxy ────────────────────────────────────────────────────
 
 pub async fn download_federated_file(
@@ -1197,9 +2006,13 @@ pub async fn download_federated_file(
 #[cfg(test)]
 mod tests {
     use super::{
-        build_content_disposition, is_inline_safe_content_type, resolve_stored_content_type,
+        build_content_disposition, is_inline_safe_content_type, parse_content_range,
+        parse_range_header, resolve_stored_content_type, sanitize_filename_for_path, ByteRange,
+        WINDOWS_RESERVED_NAMES,
     };
 
+    const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
     #[test]
     fn forces_octet_stream_for_active_content() {
         let html = b"<!doctype html><html><script>alert(1)</script></html>";
@@ -1209,10 +2022,36 @@ mod tests {
 
     #[test]
     fn keeps_safe_image_content_type() {
-        let png_header = b"\x89PNG\r\n\x1a\n";
-        let content_type = resolve_stored_content_type("image.png", Some("image/png"), png_header);
+        let content_type = resolve_stored_content_type("image.png", Some("image/png"), PNG_HEADER);
         assert_eq!(content_type, "image/png");
-        assert!(is_inline_safe_content_type(&content_type));
+        assert!(is_inline_safe_content_type(
+            &content_type,
+            &["image/png".to_string()]
+        ));
+        assert!(!is_inline_safe_content_type(
+            &content_type,
+            &["image/jpeg".to_string()]
+        ));
+    }
+
+    #[test]
+    fn sniffed_signature_overrides_claimed_type() {
+        let content_type = resolve_stored_content_type("notes.txt", Some("text/plain"), PNG_HEADER);
+        assert_eq!(content_type, "image/png");
+
+        let pdf = b"%PDF-1.7\n";
+        let content_type = resolve_stored_content_type("cat.png", Some("image/png"), pdf);
+        assert_eq!(content_type, "application/pdf");
+    }
+
+    #[test]
+    fn unconfirmed_media_claims_fall_back_to_octet_stream() {
+        let text = b"just some text pretending to be a picture";
+        let content_type = resolve_stored_content_type("cat.png", Some("image/png"), text);
+        assert_eq!(content_type, "application/octet-stream");
+
+        let content_type = resolve_stored_content_type("notes.txt", Some("text/plain"), text);
+        assert_eq!(content_type, "text/plain");
     }
 
     #[test]
@@ -1220,4 +2059,91 @@ mod tests {
         let disposition = build_content_disposition("bad\"name\r\n.js", false);
         assert_eq!(disposition, "attachment; filename=\"badname.js\"");
     }
+
+    #[test]
+    fn path_filenames_escape_windows_reserved_names() {
+        for reserved in WINDOWS_RESERVED_NAMES {
+            assert_eq!(sanitize_filename_for_path(reserved), format!("_{reserved}"));
+            let lower = reserved.to_ascii_lowercase();
+            assert_eq!(
+                sanitize_filename_for_path(&format!("{lower}.txt")),
+                format!("_{lower}.txt")
+            );
+        }
+        assert_eq!(sanitize_filename_for_path("CON.txt"), "_CON.txt");
+        assert_eq!(sanitize_filename_for_path("con."), "_con");
+        assert_eq!(sanitize_filename_for_path("nul.tar.gz"), "_nul.tar.gz");
+        assert_eq!(sanitize_filename_for_path("console.log"), "console.log");
+        assert_eq!(sanitize_filename_for_path("COM10"), "COM10");
+    }
+
+    #[test]
+    fn path_filenames_drop_trailing_dots() {
+        assert_eq!(sanitize_filename_for_path("report.pdf..."), "report.pdf");
+        assert_eq!(sanitize_filename_for_path("notes. "), "notes._");
+        assert_eq!(sanitize_filename_for_path("..."), "upload.bin");
+        assert_eq!(sanitize_filename_for_path("a b.txt"), "a_b.txt");
+    }
+
+    #[test]
+    fn parses_chunk_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 0-99/1000"),
+            Some((0, 99, Some(1000)))
+        );
+        assert_eq!(
+            parse_content_range("bytes 100-199/*"),
+            Some((100, 199, None))
+        );
+        assert_eq!(parse_content_range("bytes 10-5/100"), None);
+        assert_eq!(parse_content_range("items 0-9/10"), None);
+        assert_eq!(parse_content_range("bytes 0-/10"), None);
+    }
+
+    #[test]
+    fn resolves_single_byte_ranges() {
+        assert_eq!(
+            parse_range_header("bytes=0-99", 1000),
+            ByteRange::Partial { start: 0, end: 99 }
+        );
+        assert_eq!(
+            parse_range_header("bytes=500-", 1000),
+            ByteRange::Partial {
+                start: 500,
+                end: 999
+            }
+        );
+        assert_eq!(
+            parse_range_header("bytes=-100", 1000),
+            ByteRange::Partial {
+                start: 900,
+                end: 999
+            }
+        );
+        assert_eq!(
+            parse_range_header("bytes=900-5000", 1000),
+            ByteRange::Partial {
+                start: 900,
+                end: 999
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_unsatisfiable_and_ignores_malformed_ranges() {
+        assert_eq!(
+            parse_range_header("bytes=0-1,5-6", 1000),
+            ByteRange::Unsatisfiable
+        );
+        assert_eq!(
+            parse_range_header("bytes=1000-", 1000),
+            ByteRange::Unsatisfiable
+        );
+        assert_eq!(
+            parse_range_header("bytes=-0", 1000),
+            ByteRange::Unsatisfiable
+        );
+        assert_eq!(parse_range_header("bytes=9-3", 1000), ByteRange::Full);
+        assert_eq!(parse_range_header("items=0-1", 1000), ByteRange::Full);
+    }
 }
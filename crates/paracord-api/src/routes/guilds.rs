@@ -1,18 +1,72 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    extract::{Multipart, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Response,
     Json,
 };
 use paracord_core::AppState;
+use paracord_media::images;
+use paracord_models::audit_log::AuditAction;
+use paracord_models::channel::ChannelType;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
-use crate::routes::audit;
+use crate::routes::{audit, files, users};
 
 const MAX_GUILD_DESCRIPTION_LEN: usize = 1_024;
+const MAX_GUILD_ICON_IMAGE_SIZE: usize = 1024 * 1024; // 1 MB
+const MAX_GUILD_BANNER_IMAGE_SIZE: usize = 1024 * 1024; // 1 MB
+const MIN_VANITY_CODE_LEN: usize = 2;
+const MAX_VANITY_CODE_LEN: usize = 32;
+/// Space fields compared when recording audit log changes.
+const GUILD_AUDIT_FIELDS: &[&str] = &[
+    "name",
+    "description",
+    "icon_hash",
+    "banner_hash",
+    "system_channel_id",
+    "system_channel_flags",
+    "hub_settings",
+    "bot_settings",
+];
+/// Vanity codes that would shadow routes or impersonate the instance.
+const RESERVED_VANITY_CODES: &[&str] = &[
+    "about",
+    "admin",
+    "api",
+    "app",
+    "assets",
+    "auth",
+    "discover",
+    "federation",
+    "gateway",
+    "guilds",
+    "health",
+    "help",
+    "invite",
+    "invites",
+    "livekit",
+    "login",
+    "logout",
+    "metrics",
+    "moderator",
+    "official",
+    "paracord",
+    "register",
+    "settings",
+    "spaces",
+    "static",
+    "staff",
+    "status",
+    "support",
+    "system",
+    "www",
+];
 
 fn contains_dangerous_markup(value: &str) -> bool {
     let lower = value.to_ascii_lowercase();
@@ -36,6 +90,15 @@ pub struct UpdateGuildRequest {
     pub icon: Option<String>,
     pub hub_settings: Option<Value>,
     pub bot_settings: Option<Value>,
+    /// Channel join/leave announcements go to; an empty string turns them off.
+    pub system_channel_id: Option<String>,
+    pub system_channel_flags: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateVanityUrlRequest {
+    /// New vanity code; `null` or an empty string clears it.
+    pub code: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -54,7 +117,7 @@ pub async fn create_guild(
         ));
     }
 
-    let guild_id = paracord_util::snowflake::generate(1);
+    let guild_id = paracord_util::snowflake::generate();
 
     let guild = paracord_core::guild::create_guild_full(
         &state.db,
@@ -93,25 +156,25 @@ pub async fn list_guilds(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
-    let result: Vec<Value> = guilds
-        .iter()
-        .map(|g| {
-            json!({
-                "id": g.id.to_string(),
-                "name": g.name,
-                "description": g.description,
-                "icon_hash": g.icon_hash,
-                "owner_id": g.owner_id.to_string(),
-                "created_at": g.created_at.to_rfc3339(),
-                "hub_settings": g.hub_settings.as_deref().and_then(|s| serde_json::from_str::<Value>(s).ok()),
-                "bot_settings": g.bot_settings.as_deref().and_then(|s| serde_json::from_str::<Value>(s).ok()),
-            })
-        })
-        .collect();
+    let result: Vec<Value> = guilds.iter().map(guild_list_json).collect();
 
     Ok(Json(json!(result)))
 }
 
+/// A guild as it appears in the user's guild list.
+pub(crate) fn guild_list_json(g: &paracord_db::guilds::SpaceRow) -> Value {
+    json!({
+        "id": g.id.to_string(),
+        "name": g.name,
+        "description": g.description,
+        "icon_hash": g.icon_hash,
+        "owner_id": g.owner_id.to_string(),
+        "created_at": g.created_at.to_rfc3339(),
+        "hub_settings": g.hub_settings.as_deref().and_then(|s| serde_json::from_str::<Value>(s).ok()),
+        "bot_settings": g.bot_settings.as_deref().and_then(|s| serde_json::from_str::<Value>(s).ok()),
+    })
+}
+
 pub async fn get_guild(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -133,8 +196,13 @@ pub async fn get_guild(
         "name": guild.name,
         "description": guild.description,
         "icon_hash": guild.icon_hash,
+        "banner_hash": guild.banner_hash,
+        "features": guild.feature_names(),
         "owner_id": guild.owner_id.to_string(),
         "member_count": member_count,
+        "vanity_url_code": guild.vanity_url_code,
+        "system_channel_id": guild.system_channel_id.map(|id| id.to_string()),
+        "system_channel_flags": guild.system_channel_flags,
         "created_at": guild.created_at.to_rfc3339(),
         "hub_settings": guild.hub_settings.as_deref().and_then(|s| serde_json::from_str::<Value>(s).ok()),
         "bot_settings": guild.bot_settings.as_deref().and_then(|s| serde_json::from_str::<Value>(s).ok()),
@@ -158,6 +226,13 @@ pub async fn update_guild(
         }
     }
 
+    if body.icon.as_deref().is_some_and(|icon| !icon.is_empty()) {
+        return Err(ApiError::invalid_field(
+            "icon",
+            "Upload icons to /guilds/{guild_id}/icon; only an empty value is accepted here",
+        ));
+    }
+
     let hub_settings_str = body
         .hub_settings
         .as_ref()
@@ -168,74 +243,224 @@ pub async fn update_guild(
         .as_ref()
         .map(|v| serde_json::to_string(v).unwrap_or_else(|_| "{}".to_string()));
 
+    let system_channel_id = match body.system_channel_id.as_deref().map(str::trim) {
+        Some("") => Some(None),
+        Some(raw) => Some(Some(raw.parse::<i64>().map_err(|_| {
+            ApiError::invalid_field("system_channel_id", "Invalid channel id")
+        })?)),
+        None => None,
+    };
+    let current = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    if system_channel_id.is_some() || body.system_channel_flags.is_some() {
+        paracord_core::guild::update_system_channel(
+            &state.db,
+            guild_id,
+            auth.user_id,
+            system_channel_id.unwrap_or(current.system_channel_id),
+            body.system_channel_flags
+                .unwrap_or(current.system_channel_flags),
+        )
+        .await?;
+    }
+
     let updated = paracord_core::guild::update_guild(
         &state.db,
         guild_id,
         auth.user_id,
         body.name.as_deref(),
         body.description.as_deref(),
-        body.icon.as_deref(),
+        None,
         hub_settings_str.as_deref(),
         bot_settings_str.as_deref(),
     )
     .await?;
+    // An empty `icon` removes the current icon, like DELETE /icon.
+    let updated = match (body.icon.as_deref(), current.icon_hash.as_deref()) {
+        (Some(""), Some(previous)) => {
+            let cleared = paracord_db::guilds::set_space_image(
+                &state.db,
+                guild_id,
+                paracord_db::guilds::SpaceImage::Icon,
+                None,
+            )
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+            delete_guild_icon_variants(&state, guild_id, previous).await;
+            cleared
+        }
+        _ => updated,
+    };
 
-    let guild_json = json!({
-        "id": updated.id.to_string(),
-        "name": updated.name,
-        "description": updated.description,
-        "icon_hash": updated.icon_hash,
-        "owner_id": updated.owner_id.to_string(),
-        "created_at": updated.created_at.to_rfc3339(),
-        "hub_settings": updated.hub_settings.as_deref().and_then(|s| serde_json::from_str::<Value>(s).ok()),
-        "bot_settings": updated.bot_settings.as_deref().and_then(|s| serde_json::from_str::<Value>(s).ok()),
-    });
+    let guild_json = publish_guild_update(&state, auth.user_id, &current, &updated).await;
+    Ok(Json(guild_json))
+}
+
+/// Dispatch GUILD_UPDATE for a changed guild and audit the fields that
+/// changed. Returns the updated guild's JSON.
+async fn publish_guild_update(
+    state: &AppState,
+    user_id: i64,
+    before: &paracord_db::guilds::GuildRow,
+    after: &paracord_db::guilds::GuildRow,
+) -> Value {
+    let guild_json = guild_update_json(after);
 
     state
         .event_bus
-        .dispatch("GUILD_UPDATE", guild_json.clone(), Some(guild_id));
+        .dispatch("GUILD_UPDATE", guild_json.clone(), Some(after.id));
+    let changes = audit::diff_changes(
+        Some(&guild_update_json(before)),
+        Some(&guild_json),
+        GUILD_AUDIT_FIELDS,
+    );
+    if changes.is_some() {
+        audit::log_action(
+            state,
+            after.id,
+            user_id,
+            AuditAction::GuildUpdate,
+            Some(after.id),
+            None,
+            changes,
+        )
+        .await;
+    }
+
+    guild_json
+}
+
+fn guild_update_json(guild: &paracord_db::guilds::GuildRow) -> Value {
+    json!({
+        "id": guild.id.to_string(),
+        "name": guild.name,
+        "description": guild.description,
+        "icon_hash": guild.icon_hash,
+        "banner_hash": guild.banner_hash,
+        "features": guild.feature_names(),
+        "owner_id": guild.owner_id.to_string(),
+        "system_channel_id": guild.system_channel_id.map(|id| id.to_string()),
+        "system_channel_flags": guild.system_channel_flags,
+        "created_at": guild.created_at.to_rfc3339(),
+        "hub_settings": guild.hub_settings.as_deref().and_then(|s| serde_json::from_str::<Value>(s).ok()),
+        "bot_settings": guild.bot_settings.as_deref().and_then(|s| serde_json::from_str::<Value>(s).ok()),
+    })
+}
+
+pub async fn delete_guild(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let removed = paracord_core::guild::delete_guild(&state.db, guild_id, auth.user_id).await?;
+    paracord_core::permissions::invalidate_guild(&state.permission_cache, guild_id).await;
+    files::remove_stored_files(&state, &removed).await;
+
+    state.member_index.remove_guild(guild_id);
+    state.event_bus.dispatch(
+        "GUILD_DELETE",
+        json!({"id": guild_id.to_string()}),
+        Some(guild_id),
+    );
     audit::log_action(
         &state,
         guild_id,
         auth.user_id,
-        audit::ACTION_GUILD_UPDATE,
+        AuditAction::GuildUpdate,
         Some(guild_id),
+        Some("guild deleted"),
         None,
-        Some(json!({
-            "name": updated.name,
-            "description": updated.description,
-        })),
     )
     .await;
 
-    Ok(Json(guild_json))
+    Ok(StatusCode::NO_CONTENT)
 }
 
-pub async fn delete_guild(
+fn normalize_vanity_code(raw: &str) -> Result<String, ApiError> {
+    let code = raw.trim().to_ascii_lowercase();
+    if code.len() < MIN_VANITY_CODE_LEN || code.len() > MAX_VANITY_CODE_LEN {
+        return Err(ApiError::BadRequest(format!(
+            "Vanity code must be between {MIN_VANITY_CODE_LEN} and {MAX_VANITY_CODE_LEN} characters"
+        )));
+    }
+    if !code
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        || code.starts_with('-')
+        || code.ends_with('-')
+    {
+        return Err(ApiError::BadRequest(
+            "Vanity code may only contain letters, digits and inner hyphens".into(),
+        ));
+    }
+    if RESERVED_VANITY_CODES.contains(&code.as_str()) {
+        return Err(ApiError::BadRequest("Vanity code is reserved".into()));
+    }
+    Ok(code)
+}
+
+pub async fn update_vanity_url(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(guild_id): Path<i64>,
-) -> Result<StatusCode, ApiError> {
-    paracord_core::guild::delete_guild(&state.db, guild_id, auth.user_id).await?;
+    Json(body): Json<UpdateVanityUrlRequest>,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild(&state, guild_id, auth.user_id).await?;
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+
+    let code = match body.code.as_deref().map(str::trim) {
+        Some(raw) if !raw.is_empty() => Some(normalize_vanity_code(raw)?),
+        _ => None,
+    };
+    if code == guild.vanity_url_code {
+        return Ok(Json(json!({ "code": code })));
+    }
+
+    if let Some(code) = code.as_deref() {
+        // Invite lookups try regular codes first, so a clash would hide the vanity.
+        let clashes_with_invite = paracord_db::invites::get_invite_unfiltered(&state.db, code)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .is_some();
+        if clashes_with_invite {
+            return Err(ApiError::Conflict("Vanity code is already in use".into()));
+        }
+    }
+    let updated = paracord_db::guilds::set_vanity_url_code(&state.db, guild_id, code.as_deref())
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or_else(|| ApiError::Conflict("Vanity code is already in use".into()))?;
 
-    state.member_index.remove_guild(guild_id);
     state.event_bus.dispatch(
-        "GUILD_DELETE",
-        json!({"id": guild_id.to_string()}),
+        "GUILD_UPDATE",
+        json!({
+            "id": guild_id.to_string(),
+            "vanity_url_code": updated.vanity_url_code,
+        }),
         Some(guild_id),
     );
     audit::log_action(
         &state,
         guild_id,
         auth.user_id,
-        audit::ACTION_GUILD_UPDATE,
+        AuditAction::GuildUpdate,
         Some(guild_id),
-        Some("guild deleted"),
         None,
+        Some(json!({
+            "vanity_url_code": {
+                "old": guild.vanity_url_code,
+                "new": updated.vanity_url_code,
+            },
+        })),
     )
     .await;
 
-    Ok(StatusCode::NO_CONTENT)
+    Ok(Json(json!({ "code": updated.vanity_url_code })))
 }
 
 pub async fn transfer_ownership(
@@ -267,6 +492,7 @@ pub async fn transfer_ownership(
     let updated = paracord_db::guilds::transfer_ownership(&state.db, guild_id, new_owner_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    paracord_core::permissions::invalidate_guild(&state.permission_cache, guild_id).await;
     let payload = json!({
         "id": updated.id.to_string(),
         "owner_id": updated.owner_id.to_string(),
@@ -278,7 +504,7 @@ pub async fn transfer_ownership(
         &state,
         guild_id,
         auth.user_id,
-        audit::ACTION_GUILD_UPDATE,
+        AuditAction::GuildUpdate,
         Some(new_owner_id),
         Some("ownership transferred"),
         Some(json!({ "new_owner_id": new_owner_id.to_string() })),
@@ -315,16 +541,26 @@ pub async fn update_channel_positions(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .ok_or(ApiError::NotFound)?;
-    let roles = paracord_db::roles::get_member_roles(&state.db, auth.user_id, guild_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let perms = paracord_core::permissions::compute_permissions_from_roles(
-        &roles,
+    let perms = paracord_core::permissions::compute_guild_permissions(
+        &state.db,
+        guild_id,
         guild.owner_id,
         auth.user_id,
-    );
+    )
+    .await?;
     paracord_core::permissions::require_permission(perms, Permissions::MANAGE_CHANNELS)?;
 
+    let existing = paracord_db::channels::get_guild_channels(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let channel_types: HashMap<i64, i16> = existing
+        .iter()
+        .map(|channel| (channel.id, channel.channel_type))
+        .collect();
+    let category_type = i16::from(ChannelType::Category);
+
+    let mut seen_ids = HashSet::with_capacity(body.len());
+    let mut seen_positions = HashSet::with_capacity(body.len());
     let mut updates = Vec::with_capacity(body.len());
     for entry in &body {
         let channel_id = entry
@@ -343,12 +579,45 @@ pub async fn update_channel_positions(
             }
             None => None,
         };
+
+        let Some(&channel_type) = channel_types.get(&channel_id) else {
+            return Err(ApiError::BadRequest(format!(
+                "Channel {channel_id} does not belong to this space"
+            )));
+        };
+        if !seen_ids.insert(channel_id) {
+            return Err(ApiError::BadRequest(format!(
+                "Channel {channel_id} appears more than once"
+            )));
+        }
+        if !seen_positions.insert(entry.position) {
+            return Err(ApiError::BadRequest(format!(
+                "Position {} is assigned to more than one channel",
+                entry.position
+            )));
+        }
+        if let Some(Some(parent)) = parent_id {
+            if channel_type == category_type {
+                return Err(ApiError::BadRequest(
+                    "Categories cannot be nested under a parent".into(),
+                ));
+            }
+            if channel_types.get(&parent) != Some(&category_type) {
+                return Err(ApiError::BadRequest("Parent must be a category".into()));
+            }
+        }
         updates.push((channel_id, entry.position, parent_id));
     }
 
     let changed = paracord_db::channels::update_channel_positions(&state.db, guild_id, &updates)
         .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        .map_err(|e| match e {
+            // A channel was deleted or moved after validation; nothing was applied.
+            paracord_db::DbError::NotFound => {
+                ApiError::Conflict("Channels changed while reordering; retry".into())
+            }
+            other => ApiError::Internal(anyhow::anyhow!(other.to_string())),
+        })?;
 
     for channel in &changed {
         let channel_json = crate::routes::channels::channel_to_json(channel);
@@ -357,7 +626,24 @@ pub async fn update_channel_positions(
             .dispatch("CHANNEL_UPDATE", channel_json, Some(guild_id));
     }
 
-    Ok(Json(json!({ "updated": changed.len() })))
+    let ordered = paracord_db::channels::get_guild_channels(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let channels: Vec<Value> = paracord_core::permissions::filter_visible_channels(
+        &state.db,
+        guild_id,
+        ordered,
+        guild.owner_id,
+        auth.user_id,
+    )
+    .await?
+    .iter()
+    .map(crate::routes::channels::channel_to_json)
+    .collect();
+
+    Ok(Json(
+        json!({ "updated": changed.len(), "channels": channels }),
+    ))
 }
 
 pub async fn get_channels(
@@ -374,20 +660,17 @@ pub async fn get_channels(
     let channels = paracord_db::channels::get_guild_channels(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let channels = paracord_core::permissions::filter_visible_channels(
+        &state.db,
+        guild_id,
+        channels,
+        guild.owner_id,
+        auth.user_id,
+    )
+    .await?;
 
     let mut result: Vec<Value> = Vec::with_capacity(channels.len());
     for c in channels {
-        let perms = paracord_core::permissions::compute_channel_permissions(
-            &state.db,
-            guild_id,
-            c.id,
-            guild.owner_id,
-            auth.user_id,
-        )
-        .await?;
-        if !perms.contains(Permissions::VIEW_CHANNEL) {
-            continue;
-        }
         let required_role_ids: Vec<String> =
             paracord_db::channels::parse_required_role_ids(&c.required_role_ids)
                 .into_iter()
@@ -404,6 +687,8 @@ pub async fn get_channels(
             "parent_id": c.parent_id.map(|id| id.to_string()),
             "nsfw": c.nsfw,
             "rate_limit_per_user": c.rate_limit_per_user,
+            "bitrate": c.bitrate,
+            "user_limit": c.user_limit,
             "last_message_id": c.last_message_id.map(|id| id.to_string()),
             "required_role_ids": required_role_ids,
         }));
@@ -418,22 +703,21 @@ async fn require_manage_guild(
     state: &AppState,
     guild_id: i64,
     user_id: i64,
-) -> Result<(), ApiError> {
+) -> Result<paracord_db::guilds::GuildRow, ApiError> {
     paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .ok_or(ApiError::NotFound)?;
-    let roles = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let perms = paracord_core::permissions::compute_permissions_from_roles(
-        &roles,
+    let perms = paracord_core::permissions::compute_guild_permissions(
+        &state.db,
+        guild_id,
         guild.owner_id,
         user_id,
-    );
+    )
+    .await?;
     paracord_core::permissions::require_permission(perms, Permissions::MANAGE_GUILD)?;
-    Ok(())
+    Ok(guild)
 }
 
 pub async fn get_storage(
@@ -658,9 +942,249 @@ pub async fn delete_files(
                 .unwrap_or("bin");
             let storage_key = format!("attachments/{}.{}", attachment.id, ext);
             let _ = state.storage_backend.delete(&storage_key).await;
+            let _ = state
+                .storage_backend
+                .delete(&paracord_media::images::thumbnail_storage_key(
+                    attachment.id,
+                    ext,
+                ))
+                .await;
             deleted += 1;
         }
     }
 
     Ok(Json(json!({ "deleted": deleted })))
 }
+
+// ── Guild Icon & Banner ──────────────────────────────────────────────────
+
+async fn delete_guild_icon_variants(state: &AppState, guild_id: i64, hash: &str) {
+    for size in images::AVATAR_VARIANT_SIZES {
+        let _ = state
+            .storage_backend
+            .delete(&images::guild_icon_storage_key(guild_id, hash, size))
+            .await;
+    }
+}
+
+/// PUT /api/v1/guilds/{guild_id}/icon — multipart upload with an `image` (or
+/// `file`) part, re-encoded at every icon size. Animated GIFs stay animated
+/// only in guilds with the animated icon feature. Replaces any previous icon.
+pub async fn upload_guild_icon(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    mut multipart: Multipart,
+) -> Result<Json<Value>, ApiError> {
+    let guild = require_manage_guild(&state, guild_id, auth.user_id).await?;
+    let image_data =
+        users::read_image_part(&mut multipart, "icon", MAX_GUILD_ICON_IMAGE_SIZE).await?;
+    let allow_animated = guild.has_feature(paracord_db::guilds::SPACE_FEATURE_ANIMATED_ICON);
+
+    let processed = tokio::task::spawn_blocking(move || {
+        if !allow_animated && images::is_animated(&image_data) {
+            return Err(ApiError::BadRequest(
+                "Animated icons are not enabled for this guild".into(),
+            ));
+        }
+        images::process_avatar(&image_data, allow_animated).ok_or_else(|| {
+            ApiError::BadRequest("Icon must be a PNG, GIF, JPEG or WebP image".into())
+        })
+    })
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))??;
+    let mut hash = paracord_util::hex::hex_encode(&Sha256::digest(processed.largest()));
+    if processed.animated {
+        hash.insert_str(0, images::ANIMATED_HASH_PREFIX);
+    }
+
+    for (size, data) in &processed.variants {
+        state
+            .storage_backend
+            .store(
+                &images::guild_icon_storage_key(guild_id, &hash, *size),
+                data,
+            )
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
+    let updated = paracord_db::guilds::set_space_image(
+        &state.db,
+        guild_id,
+        paracord_db::guilds::SpaceImage::Icon,
+        Some(&hash),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if let Some(previous) = guild
+        .icon_hash
+        .as_deref()
+        .filter(|previous| *previous != hash)
+    {
+        delete_guild_icon_variants(&state, guild_id, previous).await;
+    }
+
+    let guild_json = publish_guild_update(&state, auth.user_id, &guild, &updated).await;
+    Ok(Json(guild_json))
+}
+
+/// DELETE /api/v1/guilds/{guild_id}/icon
+pub async fn delete_guild_icon(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let guild = require_manage_guild(&state, guild_id, auth.user_id).await?;
+    let Some(previous) = guild.icon_hash.as_deref() else {
+        return Ok(StatusCode::NO_CONTENT);
+    };
+    let updated = paracord_db::guilds::set_space_image(
+        &state.db,
+        guild_id,
+        paracord_db::guilds::SpaceImage::Icon,
+        None,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    delete_guild_icon_variants(&state, guild_id, previous).await;
+
+    publish_guild_update(&state, auth.user_id, &guild, &updated).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/guilds/{guild_id}/icons/{hash}?size= — only the guild's
+/// current icon is served. The hash is derived from the image, so responses
+/// are cached indefinitely.
+pub async fn get_guild_icon(
+    State(state): State<AppState>,
+    Path((guild_id, hash)): Path<(i64, String)>,
+    Query(query): Query<users::ImageSizeQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let hash = hash
+        .strip_suffix(".png")
+        .or_else(|| hash.strip_suffix(".gif"))
+        .unwrap_or(&hash);
+    if guild.icon_hash.as_deref() != Some(hash) {
+        return Err(ApiError::NotFound);
+    }
+
+    let size = images::avatar_variant_size(query.size);
+    let content_type = format!("image/{}", images::avatar_extension(hash));
+    users::immutable_image_response(
+        &state,
+        &headers,
+        &format!("{hash}_{size}"),
+        &images::guild_icon_storage_key(guild_id, hash, size),
+        &content_type,
+    )
+    .await
+}
+
+/// PUT /api/v1/guilds/{guild_id}/banner — multipart upload with an `image`
+/// (or `file`) part. Replaces any previous banner.
+pub async fn upload_guild_banner(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    mut multipart: Multipart,
+) -> Result<Json<Value>, ApiError> {
+    let guild = require_manage_guild(&state, guild_id, auth.user_id).await?;
+    let image_data =
+        users::read_image_part(&mut multipart, "banner", MAX_GUILD_BANNER_IMAGE_SIZE).await?;
+
+    let processed = tokio::task::spawn_blocking(move || images::process_banner(&image_data))
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or_else(|| {
+            ApiError::BadRequest("Banner must be a PNG, GIF, JPEG or WebP image".into())
+        })?;
+    let hash = paracord_util::hex::hex_encode(&Sha256::digest(&processed));
+
+    state
+        .storage_backend
+        .store(
+            &images::guild_banner_storage_key(guild_id, &hash),
+            &processed,
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let updated = paracord_db::guilds::set_space_image(
+        &state.db,
+        guild_id,
+        paracord_db::guilds::SpaceImage::Banner,
+        Some(&hash),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if let Some(previous) = guild
+        .banner_hash
+        .as_deref()
+        .filter(|previous| *previous != hash)
+    {
+        let _ = state
+            .storage_backend
+            .delete(&images::guild_banner_storage_key(guild_id, previous))
+            .await;
+    }
+
+    let guild_json = publish_guild_update(&state, auth.user_id, &guild, &updated).await;
+    Ok(Json(guild_json))
+}
+
+/// DELETE /api/v1/guilds/{guild_id}/banner
+pub async fn delete_guild_banner(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let guild = require_manage_guild(&state, guild_id, auth.user_id).await?;
+    let Some(previous) = guild.banner_hash.as_deref() else {
+        return Ok(StatusCode::NO_CONTENT);
+    };
+    let updated = paracord_db::guilds::set_space_image(
+        &state.db,
+        guild_id,
+        paracord_db::guilds::SpaceImage::Banner,
+        None,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let _ = state
+        .storage_backend
+        .delete(&images::guild_banner_storage_key(guild_id, previous))
+        .await;
+
+    publish_guild_update(&state, auth.user_id, &guild, &updated).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/guilds/{guild_id}/banners/{hash} — only the guild's current
+/// banner is served, cached indefinitely.
+pub async fn get_guild_banner(
+    State(state): State<AppState>,
+    Path((guild_id, hash)): Path<(i64, String)>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let hash = hash.strip_suffix(".png").unwrap_or(&hash);
+    if guild.banner_hash.as_deref() != Some(hash) {
+        return Err(ApiError::NotFound);
+    }
+
+    users::immutable_image_response(
+        &state,
+        &headers,
+        hash,
+        &images::guild_banner_storage_key(guild_id, hash),
+        "image/png",
+    )
+    .await
+}
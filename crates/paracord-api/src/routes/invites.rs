@@ -1,10 +1,11 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
 use paracord_core::AppState;
 use paracord_federation::client::{FederationInviteRequest, FederationJoinRequest};
+use paracord_models::audit_log::AuditAction;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
@@ -13,12 +14,34 @@ use crate::error::ApiError;
 use crate::middleware::AuthUser;
 use crate::routes::audit;
 
+/// Most uses an invite can be limited to; `0` means unlimited.
+const MAX_INVITE_USES: i32 = 100;
+/// Longest `max_age` an invite can have (7 days); `0` means it never expires.
+const MAX_INVITE_AGE_SECONDS: i32 = 7 * 24 * 60 * 60;
+const INVITE_EXPIRY_BATCH: i64 = 256;
+/// Default and longest look-back window for invite analytics.
+const DEFAULT_ANALYTICS_DAYS: i64 = 7;
+const MAX_ANALYTICS_DAYS: i64 = 90;
+const MAX_ANALYTICS_INVITES: i64 = 50;
+/// Most recent joiners listed under each invite in the analytics summary.
+const RECENT_JOINS_PER_INVITE: i64 = 10;
+
 #[derive(Deserialize)]
 pub struct CreateInviteRequest {
     #[serde(default = "default_max_uses")]
     pub max_uses: i32,
     #[serde(default = "default_max_age")]
     pub max_age: i32,
+    /// Members who join through the invite are removed when they go offline
+    /// unless they have been given a role.
+    #[serde(default)]
+    pub temporary: bool,
+}
+
+#[derive(Deserialize)]
+pub struct InviteAnalyticsQuery {
+    pub days: Option<i64>,
+    pub limit: Option<i64>,
 }
 
 fn default_max_uses() -> i32 {
@@ -28,6 +51,54 @@ fn default_max_age() -> i32 {
     86400
 }
 
+fn invite_to_json(invite: &paracord_db::invites::InviteRow, guild_id: i64) -> Value {
+    json!({
+        "code": invite.code,
+        "guild_id": guild_id.to_string(),
+        "channel_id": invite.channel_id.to_string(),
+        "inviter_id": invite.inviter_id.map(|id| id.to_string()),
+        "max_uses": invite.max_uses,
+        "uses": invite.uses,
+        "remaining_uses": invite.remaining_uses(),
+        "max_age": invite.max_age,
+        "expires_at": invite.expires_at().map(|t| t.to_rfc3339()),
+        "temporary": invite.temporary,
+        "created_at": invite.created_at.to_rfc3339(),
+    })
+}
+
+async fn get_invite_unfiltered(
+    state: &AppState,
+    code: &str,
+) -> Result<Option<paracord_db::invites::InviteRow>, ApiError> {
+    paracord_db::invites::get_invite_unfiltered(&state.db, code)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))
+}
+
+/// Resolve a code that is not a regular invite as a space's vanity code.
+async fn get_vanity_space(
+    state: &AppState,
+    code: &str,
+) -> Result<paracord_db::guilds::SpaceRow, ApiError> {
+    paracord_db::guilds::get_space_by_vanity_code(&state.db, &code.to_ascii_lowercase())
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)
+}
+
+/// Tell expired and exhausted invites apart so clients can explain why an
+/// invite no longer works.
+fn ensure_invite_usable(invite: &paracord_db::invites::InviteRow) -> Result<(), ApiError> {
+    if invite.is_expired(chrono::Utc::now()) {
+        return Err(ApiError::InviteExpired);
+    }
+    if invite.remaining_uses() == Some(0) {
+        return Err(ApiError::InviteMaxUses);
+    }
+    Ok(())
+}
+
 async fn federation_send_join_rpc_for_mirrored_guild(
     state: &AppState,
     guild_id: i64,
@@ -160,6 +231,17 @@ pub async fn create_invite(
     .await?;
     paracord_core::permissions::require_permission(perms, Permissions::CREATE_INSTANT_INVITE)?;
 
+    if !(0..=MAX_INVITE_USES).contains(&body.max_uses) {
+        return Err(ApiError::BadRequest(format!(
+            "max_uses must be between 0 and {MAX_INVITE_USES}"
+        )));
+    }
+    if !(0..=MAX_INVITE_AGE_SECONDS).contains(&body.max_age) {
+        return Err(ApiError::BadRequest(format!(
+            "max_age must be between 0 and {MAX_INVITE_AGE_SECONDS} seconds"
+        )));
+    }
+
     let code = paracord_core::guild::generate_invite_code(8);
 
     let invite = paracord_db::invites::create_invite(
@@ -170,6 +252,7 @@ pub async fn create_invite(
         auth.user_id,
         Some(body.max_uses),
         Some(body.max_age),
+        body.temporary,
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -177,39 +260,49 @@ pub async fn create_invite(
         &state,
         space_id,
         auth.user_id,
-        audit::ACTION_INVITE_CREATE,
+        AuditAction::InviteCreate,
         None,
         None,
         Some(json!({
             "code": invite.code,
             "channel_id": invite.channel_id.to_string(),
+            "max_uses": invite.max_uses,
+            "max_age": invite.max_age,
+            "temporary": invite.temporary,
         })),
     )
     .await;
 
-    Ok((
-        StatusCode::CREATED,
-        Json(json!({
-            "code": invite.code,
-            "guild_id": space_id.to_string(),
-            "channel_id": invite.channel_id.to_string(),
-            "inviter_id": invite.inviter_id.map(|id| id.to_string()),
-            "max_uses": invite.max_uses,
-            "uses": invite.uses,
-            "max_age": invite.max_age,
-            "created_at": invite.created_at.to_rfc3339(),
-        })),
-    ))
+    Ok((StatusCode::CREATED, Json(invite_to_json(&invite, space_id))))
 }
 
 pub async fn get_invite(
     State(state): State<AppState>,
     Path(code): Path<String>,
 ) -> Result<Json<Value>, ApiError> {
-    let invite = paracord_db::invites::get_invite(&state.db, &code)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+    let Some(invite) = get_invite_unfiltered(&state, &code).await? else {
+        let space = get_vanity_space(&state, &code).await?;
+        let member_count = paracord_db::members::get_member_count(&state.db, space.id)
+            .await
+            .unwrap_or(0);
+        return Ok(Json(json!({
+            "code": space.vanity_url_code,
+            "vanity": true,
+            "uses": null,
+            "max_uses": null,
+            "remaining_uses": null,
+            "expires_at": null,
+            "expires_in": null,
+            "temporary": false,
+            "guild": {
+                "id": space.id.to_string(),
+                "name": space.name,
+                "icon_hash": space.icon_hash,
+                "member_count": member_count,
+            },
+        })));
+    };
+    ensure_invite_usable(&invite)?;
 
     // Look up the space via the invite's channel
     let channel = paracord_db::channels::get_channel(&state.db, invite.channel_id)
@@ -236,8 +329,19 @@ pub async fn get_invite(
         member_count
     };
 
+    let expires_in = invite
+        .expires_at()
+        .map(|expires_at| (expires_at - chrono::Utc::now()).num_seconds().max(0));
+
     Ok(Json(json!({
         "code": invite.code,
+        "vanity": false,
+        "uses": invite.uses,
+        "max_uses": invite.max_uses,
+        "remaining_uses": invite.remaining_uses(),
+        "expires_at": invite.expires_at().map(|t| t.to_rfc3339()),
+        "expires_in": expires_in,
+        "temporary": invite.temporary,
         "guild": guild.map(|g| json!({
             "id": g.id.to_string(),
             "name": g.name,
@@ -252,48 +356,80 @@ pub async fn accept_invite(
     auth: AuthUser,
     Path(code): Path<String>,
 ) -> Result<Json<Value>, ApiError> {
-    let preview = paracord_db::invites::get_invite(&state.db, &code)
+    // Regular invites resolve the space through their channel; anything else
+    // may be a space's vanity code.
+    let (space_id, preview) = match get_invite_unfiltered(&state, &code).await? {
+        Some(preview) => {
+            if preview.is_expired(chrono::Utc::now()) {
+                return Err(ApiError::InviteExpired);
+            }
+            let channel = paracord_db::channels::get_channel(&state.db, preview.channel_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+                .ok_or(ApiError::NotFound)?;
+            let space_id = channel.guild_id().ok_or(ApiError::BadRequest(
+                "Invite target must be a guild/space channel".into(),
+            ))?;
+            (space_id, Some(preview))
+        }
+        None => (get_vanity_space(&state, &code).await?.id, None),
+    };
+
+    if paracord_db::bans::is_banned(&state.db, auth.user_id, space_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+    {
+        return Err(ApiError::Forbidden);
+    }
 
-    // Resolve the space from the invite's channel
-    let channel = paracord_db::channels::get_channel(&state.db, preview.channel_id)
+    // Role-gated spaces only admit holders of an allowed role; to everyone
+    // else the invite looks unknown.
+    let space = paracord_db::guilds::get_guild(&state.db, space_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .ok_or(ApiError::NotFound)?;
-    let space_id = channel.guild_id().ok_or(ApiError::BadRequest(
-        "Invite target must be a guild/space channel".into(),
-    ))?;
+    if !paracord_db::guilds::can_access_space(&state.db, &space, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    {
+        return Err(ApiError::NotFound);
+    }
 
     let already_member = paracord_db::members::get_member(&state.db, auth.user_id, space_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .is_some();
 
-    let invite_state = if already_member {
-        Some(preview.clone())
-    } else {
-        paracord_db::invites::use_invite(&state.db, &code)
+    // Existing members don't consume a use. For everyone else the use, the
+    // membership and the join record are written in one transaction, so
+    // concurrent accepts can't overshoot max_uses and every use is traceable.
+    let invite = match preview {
+        Some(_) if !already_member => {
+            match paracord_db::invites::redeem_invite(
+                &state.db,
+                paracord_util::snowflake::generate(),
+                &code,
+                space_id,
+                auth.user_id,
+            )
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-    };
-    let _invite = if let Some(invite) = invite_state {
-        invite
-    } else {
-        let existing = paracord_db::invites::get_invite(&state.db, &code)
-            .await
-            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-        if existing.is_none() {
-            return Err(ApiError::NotFound);
+            {
+                Some(invite) => Some(invite),
+                None => {
+                    let current = get_invite_unfiltered(&state, &code)
+                        .await?
+                        .ok_or(ApiError::NotFound)?;
+                    ensure_invite_usable(&current)?;
+                    return Err(ApiError::InviteMaxUses);
+                }
+            }
         }
-        return Err(ApiError::BadRequest(
-            "Invite is expired or has reached max uses".into(),
-        ));
+        preview => preview,
     };
 
-    if !already_member {
-        // Add user membership only for the invited space.
+    if !already_member && invite.is_none() {
+        // Vanity codes have no use count, so the join is recorded on its own.
         paracord_db::members::add_member(&state.db, auth.user_id, space_id)
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -305,6 +441,8 @@ pub async fn accept_invite(
     {
         tracing::warn!("Failed to assign Member role: {e}");
     }
+    paracord_core::permissions::invalidate_member(&state.permission_cache, space_id, auth.user_id)
+        .await;
 
     let guild = paracord_db::guilds::get_guild(&state.db, space_id)
         .await
@@ -318,7 +456,7 @@ pub async fn accept_invite(
         .iter()
         .find(|c| c.channel_type == 0)
         .or_else(|| channels.first())
-        .map(|c| c.id.to_string());
+        .map(|c| c.id);
 
     let member_count = paracord_db::members::get_member_count(&state.db, space_id)
         .await
@@ -331,7 +469,7 @@ pub async fn accept_invite(
         "icon_hash": guild.icon_hash,
         "owner_id": guild.owner_id.to_string(),
         "created_at": guild.created_at.to_rfc3339(),
-        "default_channel_id": default_channel_id,
+        "default_channel_id": default_channel_id.map(|id| id.to_string()),
         "member_count": member_count,
     });
 
@@ -343,12 +481,24 @@ pub async fn accept_invite(
             json!({"guild_id": guild.id.to_string(), "user_id": auth.user_id.to_string()}),
             Some(guild.id),
         );
-
-        if paracord_federation::is_enabled() {
+        crate::routes::channels::announce_member_event(
+            &state,
+            guild.id,
+            auth.user_id,
+            paracord_core::system_messages::MemberEvent::Join,
+        )
+        .await;
+
+        let joined_channel_id = invite
+            .as_ref()
+            .map(|invite| invite.channel_id)
+            .or(default_channel_id);
+        if let (true, Some(joined_channel_id)) =
+            (paracord_federation::is_enabled(), joined_channel_id)
+        {
             let fed_state = state.clone();
             let joined_user_id = auth.user_id;
-            let joined_channel_id = preview.channel_id;
-            let invite_max_age = _invite.max_age.map(i64::from);
+            let invite_max_age = invite.and_then(|invite| invite.max_age).map(i64::from);
             tokio::spawn(async move {
                 federation_send_join_rpc_for_mirrored_guild(
                     &fed_state,
@@ -372,24 +522,32 @@ pub async fn accept_invite(
     Ok(Json(json!({ "guild": guild_json })))
 }
 
-pub async fn list_guild_invites(
-    State(state): State<AppState>,
-    auth: AuthUser,
-    Path(guild_id): Path<i64>,
-) -> Result<Json<Value>, ApiError> {
+async fn require_manage_guild(
+    state: &AppState,
+    user_id: i64,
+    guild_id: i64,
+) -> Result<(), ApiError> {
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .ok_or(ApiError::NotFound)?;
-    let roles = paracord_db::roles::get_member_roles(&state.db, auth.user_id, guild_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let perms = paracord_core::permissions::compute_permissions_from_roles(
-        &roles,
+    let perms = paracord_core::permissions::compute_guild_permissions(
+        &state.db,
+        guild_id,
         guild.owner_id,
-        auth.user_id,
-    );
+        user_id,
+    )
+    .await?;
     paracord_core::permissions::require_permission(perms, Permissions::MANAGE_GUILD)?;
+    Ok(())
+}
+
+pub async fn list_guild_invites(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild(&state, auth.user_id, guild_id).await?;
 
     let invites = paracord_db::invites::get_guild_invites(&state.db, guild_id)
         .await
@@ -397,29 +555,83 @@ pub async fn list_guild_invites(
 
     let result: Vec<Value> = invites
         .iter()
-        .map(|i| {
-            json!({
-                "code": i.code,
-                "guild_id": guild_id.to_string(),
-                "channel_id": i.channel_id.to_string(),
-                "inviter_id": i.inviter_id.map(|id| id.to_string()),
-                "max_uses": i.max_uses,
-                "uses": i.uses,
-                "max_age": i.max_age,
-                "created_at": i.created_at.to_rfc3339(),
-            })
-        })
+        .map(|invite| invite_to_json(invite, guild_id))
         .collect();
 
     Ok(Json(json!(result)))
 }
 
+/// GET /api/v1/guilds/{guild_id}/invites/analytics
+///
+/// The invites that brought in the most members over the last `days`, with
+/// who created each and who joined through it most recently. Deleted and
+/// expired invites are included (with `invite: null` once gone) so a wave of
+/// joins can be traced after the code is revoked.
+pub async fn get_invite_analytics(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Query(params): Query<InviteAnalyticsQuery>,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild(&state, auth.user_id, guild_id).await?;
+
+    let days = params
+        .days
+        .unwrap_or(DEFAULT_ANALYTICS_DAYS)
+        .clamp(1, MAX_ANALYTICS_DAYS);
+    let limit = params.limit.unwrap_or(10).clamp(1, MAX_ANALYTICS_INVITES);
+    let since = chrono::Utc::now() - chrono::Duration::days(days);
+
+    let top = paracord_db::invites::get_top_invites(&state.db, guild_id, since, limit)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let mut invites = Vec::with_capacity(top.len());
+    for stats in top {
+        let invite = paracord_db::invites::get_invite_unfiltered(&state.db, &stats.code)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        let recent = paracord_db::invites::get_invite_uses(
+            &state.db,
+            guild_id,
+            &stats.code,
+            RECENT_JOINS_PER_INVITE,
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        let recent_joins: Vec<Value> = recent
+            .iter()
+            .map(|join| {
+                json!({
+                    "user_id": join.user_id.to_string(),
+                    "joined_at": join.used_at.to_rfc3339(),
+                })
+            })
+            .collect();
+        invites.push(json!({
+            "code": stats.code,
+            "inviter_id": stats.inviter_id.map(|id| id.to_string()),
+            "joins": stats.joins,
+            "first_joined_at": stats.first_used_at.to_rfc3339(),
+            "last_joined_at": stats.last_used_at.to_rfc3339(),
+            "invite": invite.as_ref().map(|invite| invite_to_json(invite, guild_id)),
+            "recent_joins": recent_joins,
+        }));
+    }
+
+    Ok(Json(json!({
+        "guild_id": guild_id.to_string(),
+        "since": since.to_rfc3339(),
+        "invites": invites,
+    })))
+}
+
 pub async fn delete_invite(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(code): Path<String>,
 ) -> Result<StatusCode, ApiError> {
-    let invite = paracord_db::invites::get_invite(&state.db, &code)
+    let invite = paracord_db::invites::get_invite_unfiltered(&state.db, &code)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .ok_or(ApiError::NotFound)?;
@@ -435,14 +647,13 @@ pub async fn delete_invite(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .ok_or(ApiError::NotFound)?;
-    let roles = paracord_db::roles::get_member_roles(&state.db, auth.user_id, space_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let perms = paracord_core::permissions::compute_permissions_from_roles(
-        &roles,
+    let perms = paracord_core::permissions::compute_guild_permissions(
+        &state.db,
+        space_id,
         guild.owner_id,
         auth.user_id,
-    );
+    )
+    .await?;
     paracord_core::permissions::require_permission(perms, Permissions::MANAGE_GUILD)?;
     paracord_db::invites::delete_invite(&state.db, &code)
         .await
@@ -460,7 +671,7 @@ pub async fn delete_invite(
         &state,
         space_id,
         auth.user_id,
-        audit::ACTION_INVITE_DELETE,
+        AuditAction::InviteDelete,
         None,
         None,
         Some(json!({ "code": invite.code })),
@@ -468,3 +679,30 @@ pub async fn delete_invite(
     .await;
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Delete invites whose `max_age` has elapsed, announcing each removal.
+/// Returns how many invites were deleted.
+pub async fn delete_expired_invites_once(state: &AppState) -> Result<usize, paracord_db::DbError> {
+    let expired = paracord_db::invites::get_expired_invites(&state.db, INVITE_EXPIRY_BATCH).await?;
+    let mut deleted = 0;
+    for invite in expired {
+        let space_id = paracord_db::channels::get_channel(&state.db, invite.channel_id)
+            .await?
+            .and_then(|channel| channel.guild_id());
+        paracord_db::invites::delete_invite(&state.db, &invite.code).await?;
+        deleted += 1;
+
+        if let Some(space_id) = space_id {
+            state.event_bus.dispatch(
+                "INVITE_DELETE",
+                json!({
+                    "code": invite.code,
+                    "guild_id": space_id.to_string(),
+                    "channel_id": invite.channel_id.to_string(),
+                }),
+                Some(space_id),
+            );
+        }
+    }
+    Ok(deleted)
+}
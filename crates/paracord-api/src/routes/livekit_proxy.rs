@@ -3,23 +3,128 @@
 //! This lets users expose only port 8080 instead of also opening 7880.
 //! WebSocket connections to `/livekit/...` are forwarded to the local
 //! LiveKit server, and HTTP requests (Twirp API) are also proxied.
+//! `/health/livekit` pings the backend so operators (and the managed
+//! process launcher) can tell whether LiveKit is accepting connections.
 
 use axum::{
     body::Body,
     extract::{ws::WebSocket, FromRequestParts, Request, State, WebSocketUpgrade},
-    http::{header, HeaderMap, StatusCode},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
+    Json,
 };
 use futures_util::{SinkExt, StreamExt};
 use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
 use paracord_core::AppState;
 use serde::Deserialize;
+use serde_json::json;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+use crate::request_id::RequestId;
 
 const LIVEKIT_PROXY_MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
 const LIVEKIT_PROXY_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+/// Attempts made against the backend before giving up on a proxied request.
+const BACKEND_CONNECT_ATTEMPTS: u32 = 4;
+const BACKEND_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const BACKEND_RETRY_BASE_DELAY_MS: u64 = 200;
+const BACKEND_RETRY_MAX_DELAY_MS: u64 = 1_600;
+const HEALTH_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Close code sent to the client when the backend drops mid-session, so the
+/// LiveKit SDK resumes instead of treating it as a deliberate hang-up.
+const CLOSE_CODE_SERVICE_RESTART: u16 = 1012;
 static LIVEKIT_PROXY_CONN_SEQ: AtomicU64 = AtomicU64::new(1);
 
+/// Counts a proxied WebSocket in the active-connections gauge for as long as
+/// it is alive.
+struct ActiveProxyConnection;
+
+impl ActiveProxyConnection {
+    fn open() -> Self {
+        paracord_core::observability::livekit_proxy_connection_open();
+        Self
+    }
+}
+
+impl Drop for ActiveProxyConnection {
+    fn drop(&mut self) {
+        paracord_core::observability::livekit_proxy_connection_close();
+    }
+}
+
+/// Backoff before retry number `attempt` (0-based): 200ms, 400ms, 800ms, ...
+fn backend_retry_delay(attempt: u32) -> Duration {
+    let factor = 1u64 << attempt.min(16);
+    Duration::from_millis((BACKEND_RETRY_BASE_DELAY_MS * factor).min(BACKEND_RETRY_MAX_DELAY_MS))
+}
+
+/// On Windows, "localhost" can resolve to IPv6 [::1] which hangs if
+/// LiveKit only listens on IPv4.  Force 127.0.0.1 for reliability.
+fn force_ipv4_loopback(url: &str) -> String {
+    url.replace("://localhost:", "://127.0.0.1:")
+}
+
+/// Whether a failed backend WebSocket handshake is worth retrying. Refused
+/// or reset connections and 5xx responses happen while LiveKit is still
+/// starting; anything else (bad token, bad path) will fail the same way again.
+fn is_transient_ws_connect_error(err: &tokio_tungstenite::tungstenite::Error) -> bool {
+    use tokio_tungstenite::tungstenite::Error;
+    match err {
+        Error::Io(_) | Error::ConnectionClosed | Error::AlreadyClosed => true,
+        Error::Http(resp) => resp.status().is_server_error(),
+        _ => false,
+    }
+}
+
+/// Ping the LiveKit HTTP listener, which answers `GET /` once it is ready to
+/// accept signaling connections. Returns the round-trip time on success.
+pub async fn probe_livekit_backend(livekit_http_url: &str) -> Result<Duration, String> {
+    let url = format!(
+        "{}/",
+        force_ipv4_loopback(livekit_http_url.trim_end_matches('/'))
+    );
+    let client = reqwest::Client::builder()
+        .timeout(HEALTH_PROBE_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    let resp = client.get(&url).send().await.map_err(|e| e.to_string())?;
+    if resp.status().is_success() {
+        Ok(started.elapsed())
+    } else {
+        Err(format!("backend returned HTTP {}", resp.status().as_u16()))
+    }
+}
+
+/// Report whether the LiveKit backend behind the proxy is reachable.
+pub async fn livekit_health(State(state): State<AppState>) -> Response {
+    let active = paracord_core::observability::livekit_proxy_active_connections();
+    match probe_livekit_backend(&state.config.livekit_http_url).await {
+        Ok(latency) => (
+            StatusCode::OK,
+            Json(json!({
+                "status": "ok",
+                "latency_ms": latency.as_millis() as u64,
+                "active_ws_connections": active,
+            })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::debug!("LiveKit health probe failed: {}", e);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({
+                    "status": "unavailable",
+                    "active_ws_connections": active,
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct LiveKitProxyClaims {
     iss: Option<String>,
@@ -212,6 +317,7 @@ fn sanitize_target_for_log(target: &str) -> String {
 
 fn handle_ws(state: AppState, ws: WebSocketUpgrade, req: Request) -> Response {
     let target = build_target(&state.config.livekit_http_url, &req, true);
+    let request_id = req.extensions().get::<RequestId>().map(|id| id.0.clone());
     let conn_id = LIVEKIT_PROXY_CONN_SEQ.fetch_add(1, Ordering::Relaxed);
     tracing::info!(
         "LiveKit WS proxy[{}]: upgrading connection to {}",
@@ -221,7 +327,12 @@ fn handle_ws(state: AppState, ws: WebSocketUpgrade, req: Request) -> Response {
     // Keep signaling payload limits explicit and conservative.
     ws.max_message_size(LIVEKIT_PROXY_MAX_MESSAGE_SIZE)
         .max_frame_size(LIVEKIT_PROXY_MAX_FRAME_SIZE)
-        .on_upgrade(move |client_socket| proxy_ws(client_socket, target, conn_id))
+        .on_upgrade(move |client_socket| {
+            // The upgraded connection outlives the request; keep its span so
+            // proxy logs still carry the request id.
+            proxy_ws(client_socket, target, conn_id, request_id)
+                .instrument(tracing::Span::current())
+        })
 }
 
 fn axum_to_tungstenite_message(
@@ -293,14 +404,17 @@ fn decode_first_protobuf_tag(bytes: &[u8]) -> Option<(u32, u8)> {
 ///
 /// We keep one writer per side (client->backend and backend->client).
 /// Data, close, and control frames are forwarded transparently end-to-end.
-async fn proxy_ws(client_socket: WebSocket, target: String, conn_id: u64) {
+async fn proxy_ws(
+    client_socket: WebSocket,
+    target: String,
+    conn_id: u64,
+    request_id: Option<String>,
+) {
     use axum::extract::ws::Message as AMsg;
     use std::sync::Arc;
     use tokio_tungstenite::tungstenite::Message as TMsg;
 
-    // On Windows, "localhost" can resolve to IPv6 [::1] which hangs if
-    // LiveKit only listens on IPv4.  Force 127.0.0.1 for reliability.
-    let target = target.replace("://localhost:", "://127.0.0.1:");
+    let target = force_ipv4_loopback(&target);
     let redacted_target = sanitize_target_for_log(&target);
 
     // Use a custom config to allow large LiveKit signaling messages.
@@ -309,22 +423,26 @@ async fn proxy_ws(client_socket: WebSocket, target: String, conn_id: u64) {
         .max_frame_size(Some(LIVEKIT_PROXY_MAX_FRAME_SIZE));
 
     // Retry connecting to the LiveKit backend with backoff.  LiveKit can be
-    // slow to accept connections right after room creation, so retrying at
-    // the proxy level avoids burning through the client SDK's limited
-    // connect retries on transient backend delays.
-    const MAX_BACKEND_RETRIES: u32 = 2;
-    const BACKEND_CONNECT_TIMEOUT_SECS: u64 = 2;
-
+    // slow to accept connections right after room creation or while it is
+    // (re)starting, so retrying at the proxy level avoids burning through the
+    // client SDK's limited connect retries on transient backend delays.
     let mut backend_opt = None;
-    for attempt in 0..MAX_BACKEND_RETRIES {
+    for attempt in 0..BACKEND_CONNECT_ATTEMPTS {
+        let backend_request = match backend_ws_request(&target, request_id.as_deref()) {
+            Ok(request) => request,
+            Err(e) => {
+                tracing::error!(
+                    "LiveKit WS proxy[{}]: invalid backend target {}: {}",
+                    conn_id,
+                    redacted_target,
+                    e
+                );
+                break;
+            }
+        };
         let connect_fut =
-            tokio_tungstenite::connect_async_with_config(&target, Some(ws_config.clone()), true);
-        match tokio::time::timeout(
-            std::time::Duration::from_secs(BACKEND_CONNECT_TIMEOUT_SECS),
-            connect_fut,
-        )
-        .await
-        {
+            tokio_tungstenite::connect_async_with_config(backend_request, Some(ws_config), true);
+        match tokio::time::timeout(BACKEND_CONNECT_TIMEOUT, connect_fut).await {
             Ok(Ok((ws_stream, _))) => {
                 backend_opt = Some(ws_stream);
                 break;
@@ -334,24 +452,27 @@ async fn proxy_ws(client_socket: WebSocket, target: String, conn_id: u64) {
                     "LiveKit WS proxy[{}]: backend connect attempt {}/{} failed for {}: {}",
                     conn_id,
                     attempt + 1,
-                    MAX_BACKEND_RETRIES,
+                    BACKEND_CONNECT_ATTEMPTS,
                     redacted_target,
                     e
                 );
+                if !is_transient_ws_connect_error(&e) {
+                    break;
+                }
             }
             Err(_) => {
                 tracing::warn!(
                     "LiveKit WS proxy[{}]: backend connect attempt {}/{} timed out for {} ({}s)",
                     conn_id,
                     attempt + 1,
-                    MAX_BACKEND_RETRIES,
+                    BACKEND_CONNECT_ATTEMPTS,
                     redacted_target,
-                    BACKEND_CONNECT_TIMEOUT_SECS
+                    BACKEND_CONNECT_TIMEOUT.as_secs()
                 );
             }
         }
-        if attempt + 1 < MAX_BACKEND_RETRIES {
-            tokio::time::sleep(std::time::Duration::from_millis(250 * (attempt as u64 + 1))).await;
+        if attempt + 1 < BACKEND_CONNECT_ATTEMPTS {
+            tokio::time::sleep(backend_retry_delay(attempt)).await;
         }
     }
 
@@ -359,10 +480,9 @@ async fn proxy_ws(client_socket: WebSocket, target: String, conn_id: u64) {
         Some(b) => b,
         None => {
             tracing::error!(
-                "LiveKit WS proxy[{}]: all {} attempts to connect to LiveKit backend at {} failed. \
+                "LiveKit WS proxy[{}]: could not connect to LiveKit backend at {}. \
                  Check that LiveKit is running and accessible.",
                 conn_id,
-                MAX_BACKEND_RETRIES,
                 redacted_target
             );
             // Send a proper close frame so the client SDK gets a clear error
@@ -378,6 +498,8 @@ async fn proxy_ws(client_socket: WebSocket, target: String, conn_id: u64) {
         }
     };
 
+    let _active = ActiveProxyConnection::open();
+
     // Cancellation token: when one direction exits, signal the other to stop.
     let cancel = tokio_util::sync::CancellationToken::new();
 
@@ -463,16 +585,20 @@ async fn proxy_ws(client_socket: WebSocket, target: String, conn_id: u64) {
     let b2c_client_write = client_write.clone();
     let b2c_cancel = cancel.clone();
     let b2c = tokio::spawn(async move {
+        // Set when the backend vanishes without a close frame (crash/restart).
+        let mut backend_lost = false;
         loop {
             let msg = tokio::select! {
                 msg = backend_read.next() => match msg {
                     Some(Ok(m)) => m,
                     Some(Err(e)) => {
                         tracing::info!("LiveKit WS proxy[{}]: backend read error: {}", conn_id, e);
+                        backend_lost = true;
                         break;
                     }
                     None => {
                         tracing::info!("LiveKit WS proxy[{}]: backend socket closed", conn_id);
+                        backend_lost = true;
                         break;
                     }
                 },
@@ -527,7 +653,16 @@ async fn proxy_ws(client_socket: WebSocket, target: String, conn_id: u64) {
             }
         }
         b2c_cancel.cancel();
-        let _ = b2c_client_write.lock().await.close().await;
+        let mut client_write = b2c_client_write.lock().await;
+        if backend_lost {
+            let _ = client_write
+                .send(AMsg::Close(Some(axum::extract::ws::CloseFrame {
+                    code: CLOSE_CODE_SERVICE_RESTART,
+                    reason: "LiveKit backend restarting".into(),
+                })))
+                .await;
+        }
+        let _ = client_write.close().await;
     });
 
     // Wait for both directions to finish.
@@ -539,8 +674,29 @@ async fn proxy_ws(client_socket: WebSocket, target: String, conn_id: u64) {
     );
 }
 
+/// Backend handshake request, tagged with the client's request id so LiveKit
+/// logs can be matched to ours.
+fn backend_ws_request(
+    target: &str,
+    request_id: Option<&str>,
+) -> Result<
+    tokio_tungstenite::tungstenite::handshake::client::Request,
+    tokio_tungstenite::tungstenite::Error,
+> {
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+    let mut request = target.into_client_request()?;
+    if let Some(value) = request_id.and_then(|id| HeaderValue::from_str(id).ok()) {
+        request
+            .headers_mut()
+            .insert(crate::request_id::REQUEST_ID_HEADER, value);
+    }
+    Ok(request)
+}
+
 async fn handle_http(state: AppState, req: Request) -> Response {
-    let target_uri = build_target(&state.config.livekit_http_url, &req, false);
+    let target_uri =
+        force_ipv4_loopback(&build_target(&state.config.livekit_http_url, &req, false));
     let (parts, body) = req.into_parts();
 
     let client = reqwest::Client::new();
@@ -559,11 +715,30 @@ async fn handle_http(state: AppState, req: Request) -> Response {
         Err(_) => return StatusCode::BAD_REQUEST.into_response(),
     };
 
-    let resp = match builder.body(body_bytes).send().await {
-        Ok(r) => r,
-        Err(e) => {
-            tracing::error!("LiveKit proxy error: {}", e);
+    let builder = builder.body(body_bytes);
+    let mut attempt = 0;
+    let resp = loop {
+        let Some(request) = builder.try_clone() else {
             return StatusCode::BAD_GATEWAY.into_response();
+        };
+        match request.timeout(BACKEND_CONNECT_TIMEOUT).send().await {
+            Ok(r) => break r,
+            Err(e)
+                if (e.is_connect() || e.is_timeout()) && attempt + 1 < BACKEND_CONNECT_ATTEMPTS =>
+            {
+                tracing::warn!(
+                    "LiveKit proxy: backend request attempt {}/{} failed: {}",
+                    attempt + 1,
+                    BACKEND_CONNECT_ATTEMPTS,
+                    e
+                );
+                tokio::time::sleep(backend_retry_delay(attempt)).await;
+                attempt += 1;
+            }
+            Err(e) => {
+                tracing::error!("LiveKit proxy error: {}", e);
+                return StatusCode::BAD_GATEWAY.into_response();
+            }
         }
     };
 
@@ -588,8 +763,10 @@ async fn handle_http(state: AppState, req: Request) -> Response {
 
 #[cfg(test)]
 mod tests {
-    use super::is_allowed_livekit_request;
+    use super::{backend_retry_delay, is_allowed_livekit_request, is_transient_ws_connect_error};
     use axum::http::Method;
+    use std::time::Duration;
+    use tokio_tungstenite::tungstenite::Error;
 
     #[test]
     fn allows_livekit_signal_ws_paths() {
@@ -614,5 +791,34 @@ mod tests {
             false
         ));
     }
-}
 
+    #[test]
+    fn backend_retry_delay_backs_off_and_caps() {
+        assert_eq!(backend_retry_delay(0), Duration::from_millis(200));
+        assert_eq!(backend_retry_delay(1), Duration::from_millis(400));
+        assert_eq!(backend_retry_delay(2), Duration::from_millis(800));
+        assert_eq!(backend_retry_delay(10), Duration::from_millis(1_600));
+    }
+
+    #[test]
+    fn only_startup_failures_are_retried() {
+        let refused = Error::Io(std::io::Error::from(std::io::ErrorKind::ConnectionRefused));
+        assert!(is_transient_ws_connect_error(&refused));
+
+        let unavailable = axum::http::Response::builder()
+            .status(503)
+            .body(None)
+            .unwrap();
+        assert!(is_transient_ws_connect_error(&Error::Http(Box::new(
+            unavailable
+        ))));
+
+        let unauthorized = axum::http::Response::builder()
+            .status(401)
+            .body(None)
+            .unwrap();
+        assert!(!is_transient_ws_connect_error(&Error::Http(Box::new(
+            unauthorized
+        ))));
+    }
+}
@@ -1,10 +1,12 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
+use paracord_core::system_messages::MemberEvent;
 use paracord_core::AppState;
 use paracord_federation::client::FederationLeaveRequest;
+use paracord_models::audit_log::AuditAction;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
@@ -13,23 +15,88 @@ use crate::error::ApiError;
 use crate::middleware::AuthUser;
 use crate::routes::audit;
 
+const MAX_MEMBER_LIST_LIMIT: i64 = 1000;
+
+#[derive(Deserialize)]
+pub struct ListMembersQuery {
+    pub limit: Option<i64>,
+    pub after: Option<i64>,
+    /// Case-insensitive prefix of the username or nickname.
+    pub q: Option<String>,
+    pub role_id: Option<i64>,
+    pub timed_out: Option<bool>,
+    /// RFC 3339 lower bound (inclusive) on the join time.
+    pub joined_after: Option<chrono::DateTime<chrono::Utc>>,
+    /// RFC 3339 upper bound (exclusive) on the join time.
+    pub joined_before: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// One page of members as `{ members, total, has_more }`, filtered or not,
+/// where `total` counts every member matching the filters.
 pub async fn list_members(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(guild_id): Path<i64>,
+    Query(params): Query<ListMembersQuery>,
 ) -> Result<Json<Value>, ApiError> {
     paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
 
-    let members = paracord_db::members::get_guild_members(&state.db, guild_id, 1000, None)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let limit = params
+        .limit
+        .unwrap_or(MAX_MEMBER_LIST_LIMIT)
+        .clamp(1, MAX_MEMBER_LIST_LIMIT);
+    let filter = paracord_db::members::MemberSearchFilter {
+        query: params
+            .q
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .map(str::to_string),
+        role_id: params.role_id,
+        timed_out: params.timed_out,
+        joined_after: params.joined_after,
+        joined_before: params.joined_before,
+    };
+    let page = paracord_db::members::search_guild_members(
+        &state.db,
+        guild_id,
+        &filter,
+        limit,
+        params.after,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let members = page.members;
 
+    let statuses: std::collections::HashMap<i64, (String, Value)> = {
+        let presences = state.user_presences.read().await;
+        members
+            .iter()
+            .filter_map(|m| {
+                let presence = presences.get(&m.user_id)?;
+                let status = presence.get("status")?.as_str()?;
+                let custom_status = presence
+                    .get("custom_status")
+                    .cloned()
+                    .unwrap_or(Value::Null);
+                Some((m.user_id, (status.to_string(), custom_status)))
+            })
+            .collect()
+    };
     let mut result: Vec<Value> = Vec::with_capacity(members.len());
     for m in members {
         let roles = paracord_db::roles::get_member_roles(&state.db, m.user_id, guild_id)
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
         let role_ids: Vec<String> = roles.iter().map(|r| r.id.to_string()).collect();
+        // Clients group the sidebar by this role; members without a hoisted
+        // role are listed by status.
+        let hoisted_role_id =
+            paracord_core::permissions::displayed_role(&roles).map(|r| r.id.to_string());
+        let (status, custom_status) = statuses
+            .get(&m.user_id)
+            .map(|(status, custom_status)| (status.as_str(), custom_status.clone()))
+            .unwrap_or(("offline", Value::Null));
         result.push(json!({
             "user_id": m.user_id.to_string(),
             "guild_id": guild_id.to_string(),
@@ -39,6 +106,7 @@ pub async fn list_members(
             "mute": m.mute,
             "communication_disabled_until": m.communication_disabled_until.map(|v| v.to_rfc3339()),
             "roles": role_ids,
+            "hoisted_role_id": hoisted_role_id,
             "user": {
                 "id": m.user_id.to_string(),
                 "username": m.username,
@@ -47,11 +115,20 @@ pub async fn list_members(
                 "flags": m.user_flags,
                 "bot": paracord_core::is_bot(m.user_flags),
                 "system": false,
+            },
+            "presence": {
+                "status": status,
+                "custom_status": custom_status,
+                "last_seen_at": m.user_last_seen_at.map(|v| v.to_rfc3339()),
             }
         }));
     }
 
-    Ok(Json(json!(result)))
+    Ok(Json(json!({
+        "members": result,
+        "total": page.total,
+        "has_more": page.has_more,
+    })))
 }
 
 #[derive(Deserialize)]
@@ -79,6 +156,13 @@ pub async fn update_member(
         guild.owner_id,
         auth.user_id,
     );
+    let actor_perms = paracord_core::permissions::restrict_to_bot_scope(
+        &state.db,
+        guild_id,
+        auth.user_id,
+        actor_perms,
+    )
+    .await?;
 
     if body.nick.is_some() && auth.user_id != user_id {
         paracord_core::permissions::require_permission(
@@ -86,6 +170,19 @@ pub async fn update_member(
             paracord_models::permissions::Permissions::MANAGE_NICKNAMES,
         )?;
     }
+    // Checked before anything is written so a rejected role change does not
+    // leave a half-applied update behind.
+    if body.roles.is_some() && auth.user_id != user_id {
+        paracord_core::permissions::ensure_outranks_member(
+            &state.db,
+            guild_id,
+            guild.owner_id,
+            auth.user_id,
+            user_id,
+            "change the roles of",
+        )
+        .await?;
+    }
 
     let updated = paracord_db::members::update_member(
         &state.db,
@@ -145,19 +242,22 @@ pub async fn update_member(
         let existing_ids: std::collections::HashSet<i64> =
             existing_roles.iter().map(|r| r.id).collect();
 
-        if auth.user_id != guild.owner_id {
-            let actor_top_role_pos = actor_roles.iter().map(|r| r.position).max().unwrap_or(0);
-            for role_id in &requested_ids {
-                if *role_id == guild_id {
-                    continue;
-                }
-                let Some(role) = role_by_id.get(role_id) else {
-                    continue;
-                };
-                if role.position >= actor_top_role_pos {
-                    return Err(ApiError::Forbidden);
-                }
+        // Only roles actually being granted or revoked need to sit below the
+        // actor; roles the member keeps are left alone.
+        for role_id in requested_ids.symmetric_difference(&existing_ids) {
+            if *role_id == guild_id {
+                continue;
             }
+            let Some(role) = role_by_id.get(role_id) else {
+                continue;
+            };
+            paracord_core::permissions::ensure_outranks_role(
+                &actor_roles,
+                guild.owner_id,
+                auth.user_id,
+                role,
+                "assign or remove",
+            )?;
         }
 
         for role_id in requested_ids.difference(&existing_ids) {
@@ -174,7 +274,8 @@ pub async fn update_member(
         }
 
         // Invalidate permission cache when a user's roles change
-        paracord_core::permissions::invalidate_user(&state.permission_cache, user_id).await;
+        paracord_core::permissions::invalidate_member(&state.permission_cache, guild_id, user_id)
+            .await;
 
         role_ids = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id)
             .await
@@ -190,16 +291,15 @@ pub async fn update_member(
         if user_id == guild.owner_id {
             return Err(ApiError::Forbidden);
         }
-        if auth.user_id != guild.owner_id {
-            let actor_top_role_pos = actor_roles.iter().map(|r| r.position).max().unwrap_or(0);
-            let target_roles = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id)
-                .await
-                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-            let target_top_role_pos = target_roles.iter().map(|r| r.position).max().unwrap_or(0);
-            if target_top_role_pos >= actor_top_role_pos {
-                return Err(ApiError::Forbidden);
-            }
-        }
+        paracord_core::permissions::ensure_outranks_member(
+            &state.db,
+            guild_id,
+            guild.owner_id,
+            auth.user_id,
+            user_id,
+            "time out",
+        )
+        .await?;
 
         let parsed = if raw_until.trim().is_empty() {
             None
@@ -244,7 +344,7 @@ pub async fn update_member(
         &state,
         guild_id,
         auth.user_id,
-        audit::ACTION_MEMBER_UPDATE,
+        AuditAction::MemberUpdate,
         Some(user_id),
         None,
         Some(json!({
@@ -264,6 +364,7 @@ pub async fn kick_member(
     Path((guild_id, user_id)): Path<(i64, i64)>,
 ) -> Result<StatusCode, ApiError> {
     paracord_core::admin::kick_member(&state.db, guild_id, auth.user_id, user_id).await?;
+    paracord_core::permissions::invalidate_member(&state.permission_cache, guild_id, user_id).await;
 
     state.member_index.remove_member(guild_id, user_id);
     state.event_bus.dispatch(
@@ -278,12 +379,14 @@ pub async fn kick_member(
         &state,
         guild_id,
         auth.user_id,
-        audit::ACTION_MEMBER_KICK,
+        AuditAction::MemberKick,
         Some(user_id),
         None,
         None,
     )
     .await;
+    crate::routes::channels::announce_member_event(&state, guild_id, user_id, MemberEvent::Kick)
+        .await;
 
     if paracord_federation::is_enabled() {
         let fed_state = state.clone();
@@ -316,6 +419,8 @@ pub async fn leave_guild(
     paracord_db::members::remove_member(&state.db, auth.user_id, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    paracord_core::permissions::invalidate_member(&state.permission_cache, guild_id, auth.user_id)
+        .await;
 
     state.member_index.remove_member(guild_id, auth.user_id);
     state.event_bus.dispatch(
@@ -326,6 +431,13 @@ pub async fn leave_guild(
         }),
         Some(guild_id),
     );
+    crate::routes::channels::announce_member_event(
+        &state,
+        guild_id,
+        auth.user_id,
+        MemberEvent::Leave,
+    )
+    .await;
 
     if paracord_federation::is_enabled() {
         let fed_state = state.clone();
@@ -4,7 +4,9 @@ pub mod audit_logs;
 pub mod auth;
 pub mod bans;
 pub mod bots;
+pub mod channel_export;
 pub mod channels;
+pub mod commands;
 pub mod discovery;
 pub mod dms;
 pub mod emojis;
@@ -16,6 +18,8 @@ pub mod invites;
 pub mod keys;
 pub mod livekit_proxy;
 pub mod members;
+pub mod overview;
+pub mod push;
 pub mod realtime;
 pub mod relationships;
 pub mod roles;
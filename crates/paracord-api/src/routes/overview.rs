@@ -0,0 +1,193 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use paracord_core::AppState;
+use paracord_db::notification_settings::{EffectiveNotificationSettings, TARGET_GUILD};
+use paracord_db::read_states::UnreadMarker;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::error::ApiError;
+use crate::middleware::AuthUser;
+use crate::routes::{channels, dms, guilds, relationships};
+
+const DEFAULT_OVERVIEW_GUILD_LIMIT: i64 = 100;
+const MAX_OVERVIEW_GUILD_LIMIT: i64 = 200;
+const DEFAULT_OVERVIEW_DM_LIMIT: i64 = 50;
+const MAX_OVERVIEW_DM_LIMIT: i64 = 200;
+const MAX_OVERVIEW_RELATIONSHIPS: i64 = 1000;
+
+#[derive(Deserialize)]
+pub struct OverviewQuery {
+    pub guild_limit: Option<i64>,
+    /// `next_guild_after` from the previous page.
+    pub guild_after: Option<i64>,
+    pub dm_limit: Option<i64>,
+    /// `next_dm_before` from the previous page.
+    pub dm_before: Option<i64>,
+}
+
+/// Everything a client needs to draw its sidebar on startup: the user's
+/// guilds with the channels they can see, their DMs, their relationships and
+/// read states for every listed channel.
+///
+/// Guilds and DMs are paged; `next_guild_after` and `next_dm_before` are null
+/// on the last page. Threads are not listed. A page costs a fixed number of
+/// queries however many guilds and channels it holds.
+pub async fn get_overview(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(params): Query<OverviewQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let guild_limit = params
+        .guild_limit
+        .unwrap_or(DEFAULT_OVERVIEW_GUILD_LIMIT)
+        .clamp(1, MAX_OVERVIEW_GUILD_LIMIT);
+    let dm_limit = params
+        .dm_limit
+        .unwrap_or(DEFAULT_OVERVIEW_DM_LIMIT)
+        .clamp(1, MAX_OVERVIEW_DM_LIMIT);
+
+    let mut guild_rows = paracord_db::guilds::list_user_guilds_page(
+        &state.db,
+        auth.user_id,
+        params.guild_after,
+        guild_limit + 1,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let has_more_guilds = guild_rows.len() as i64 > guild_limit;
+    guild_rows.truncate(guild_limit as usize);
+    // The cursor is taken before visibility filtering so hidden guilds at the
+    // end of a page are not fetched again.
+    let next_guild_after = guild_rows.last().filter(|_| has_more_guilds).map(|g| g.id);
+    let guild_rows =
+        paracord_db::guilds::filter_accessible_spaces(&state.db, guild_rows, auth.user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let guild_owners: Vec<(i64, i64)> = guild_rows.iter().map(|g| (g.id, g.owner_id)).collect();
+    let mut visible_channels = paracord_core::permissions::visible_channels_in_guilds(
+        &state.db,
+        &guild_owners,
+        auth.user_id,
+    )
+    .await?;
+
+    let mut dm_rows = paracord_db::dms::list_user_dm_channels_page(
+        &state.db,
+        auth.user_id,
+        params.dm_before,
+        dm_limit + 1,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let has_more_dms = dm_rows.len() as i64 > dm_limit;
+    dm_rows.truncate(dm_limit as usize);
+    let next_dm_before = dm_rows
+        .last()
+        .filter(|_| has_more_dms)
+        .map(|c| c.last_message_id.unwrap_or(c.id));
+
+    let relationship_rows = paracord_db::relationships::get_relationships_limited(
+        &state.db,
+        auth.user_id,
+        MAX_OVERVIEW_RELATIONSHIPS,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    // Guild (if any) and latest message of every listed channel.
+    let mut listed_channels: HashMap<i64, (Option<i64>, Option<i64>)> = HashMap::new();
+    let mut guild_list = Vec::with_capacity(guild_rows.len());
+    for guild in &guild_rows {
+        let guild_channels = visible_channels.remove(&guild.id).unwrap_or_default();
+        for channel in &guild_channels {
+            listed_channels.insert(channel.id, (Some(guild.id), channel.last_message_id));
+        }
+        let mut guild_json = guilds::guild_list_json(guild);
+        guild_json["channels"] = guild_channels
+            .iter()
+            .map(channels::channel_to_json)
+            .collect();
+        guild_list.push(guild_json);
+    }
+    for dm in &dm_rows {
+        listed_channels.insert(dm.id, (None, dm.last_message_id));
+    }
+
+    let channel_ids: Vec<i64> = listed_channels.keys().copied().collect();
+    let read_state_rows = paracord_db::read_states::get_read_states_for_channels(
+        &state.db,
+        auth.user_id,
+        &channel_ids,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let notification_settings =
+        paracord_db::notification_settings::list_notification_settings(&state.db, auth.user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let settings_by_target: HashMap<i64, _> = notification_settings
+        .iter()
+        .map(|row| (row.target_id, row))
+        .collect();
+
+    let now = chrono::Utc::now();
+    let mut resolved = Vec::with_capacity(read_state_rows.len());
+    let mut markers = Vec::new();
+    for read_state in &read_state_rows {
+        let Some(&(guild_id, last_message_id)) = listed_channels.get(&read_state.channel_id) else {
+            continue;
+        };
+        let guild_setting = guild_id
+            .and_then(|id| settings_by_target.get(&id).copied())
+            .filter(|row| row.target_type == TARGET_GUILD);
+        let settings = EffectiveNotificationSettings::resolve(
+            guild_setting,
+            settings_by_target.get(&read_state.channel_id).copied(),
+            now,
+        );
+        // Nothing newer than the read marker means nothing to count.
+        if last_message_id.is_some_and(|id| id > read_state.last_message_id) {
+            markers.push(UnreadMarker {
+                channel_id: read_state.channel_id,
+                after_message_id: read_state.last_message_id,
+                include_everyone: !settings.suppress_everyone,
+            });
+        }
+        resolved.push((read_state, settings));
+    }
+    let unread_counts = paracord_db::read_states::count_unread_for_channels(
+        &state.db,
+        auth.user_id,
+        &markers,
+        paracord_db::read_states::UNREAD_COUNT_CAP,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let read_states: Vec<Value> = resolved
+        .iter()
+        .map(|(read_state, settings)| {
+            let counts = unread_counts
+                .get(&read_state.channel_id)
+                .copied()
+                .unwrap_or_default();
+            channels::read_state_json(read_state, settings, counts)
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "guilds": guild_list,
+        "next_guild_after": next_guild_after.map(|id| id.to_string()),
+        "dms": dm_rows.iter().map(dms::dm_channel_json).collect::<Vec<_>>(),
+        "next_dm_before": next_dm_before.map(|id| id.to_string()),
+        "relationships": relationship_rows
+            .iter()
+            .map(relationships::relationship_json)
+            .collect::<Vec<_>>(),
+        "read_states": read_states,
+    })))
+}
@@ -0,0 +1,136 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use paracord_core::AppState;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::ApiError;
+use crate::middleware::AuthUser;
+
+/// Subscriptions kept per user; registering another drops the oldest.
+const MAX_SUBSCRIPTIONS_PER_USER: i64 = 10;
+const MAX_ENDPOINT_LEN: usize = 2048;
+const MAX_USER_AGENT_LEN: usize = 256;
+
+#[derive(Deserialize)]
+pub struct PushSubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreatePushSubscriptionRequest {
+    pub endpoint: String,
+    pub keys: PushSubscriptionKeys,
+}
+
+#[derive(Deserialize)]
+pub struct DeletePushSubscriptionRequest {
+    pub endpoint: String,
+}
+
+/// Push endpoints are URLs the server will POST to, so only HTTPS URLs with
+/// public-looking host names are accepted. This is only a first filter: the
+/// name could still resolve anywhere, so deliveries go through the outbound
+/// guard, which checks the resolved addresses before connecting.
+fn validate_endpoint(endpoint: &str) -> Result<(), ApiError> {
+    let invalid = || ApiError::invalid_field("endpoint", "Must be an HTTPS push service URL");
+    if endpoint.len() > MAX_ENDPOINT_LEN {
+        return Err(invalid());
+    }
+    let parsed = url::Url::parse(endpoint).map_err(|_| invalid())?;
+    if parsed.scheme() != "https" || !parsed.username().is_empty() || parsed.password().is_some() {
+        return Err(invalid());
+    }
+    match parsed.host() {
+        Some(url::Host::Domain(host)) => {
+            let host = host.trim_end_matches('.').to_ascii_lowercase();
+            let internal = host == "localhost"
+                || !host.contains('.')
+                || [".localhost", ".local", ".internal", ".lan"]
+                    .iter()
+                    .any(|suffix| host.ends_with(suffix));
+            if internal {
+                return Err(invalid());
+            }
+        }
+        _ => return Err(invalid()),
+    }
+    Ok(())
+}
+
+/// GET /api/v1/push/vapid-public-key
+pub async fn get_vapid_public_key(
+    State(state): State<AppState>,
+    _auth: AuthUser,
+) -> Result<Json<Value>, ApiError> {
+    let public_key = state.push.vapid_public_key().ok_or(ApiError::NotFound)?;
+    Ok(Json(json!({ "public_key": public_key })))
+}
+
+/// POST /api/v1/users/@me/push-subscriptions
+pub async fn create_push_subscription(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    headers: HeaderMap,
+    Json(body): Json<CreatePushSubscriptionRequest>,
+) -> Result<StatusCode, ApiError> {
+    if state.push.vapid_public_key().is_none() {
+        return Err(ApiError::BadRequest(
+            "Push notifications are disabled on this server".into(),
+        ));
+    }
+    validate_endpoint(&body.endpoint)?;
+    // Encrypting an empty payload checks both keys the same way delivery will.
+    if let Err(err) = paracord_util::web_push::encrypt(b"", &body.keys.p256dh, &body.keys.auth) {
+        let field = match err {
+            paracord_util::web_push::WebPushError::InvalidAuthSecret => "keys.auth",
+            _ => "keys.p256dh",
+        };
+        return Err(ApiError::invalid_field(field, err.to_string()));
+    }
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.chars().take(MAX_USER_AGENT_LEN).collect::<String>());
+
+    let saved = paracord_db::push_subscriptions::upsert_push_subscription(
+        &state.db,
+        auth.user_id,
+        &body.endpoint,
+        &body.keys.p256dh,
+        &body.keys.auth,
+        user_agent.as_deref(),
+        MAX_SUBSCRIPTIONS_PER_USER,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !saved {
+        return Err(ApiError::Conflict(
+            "This push endpoint is registered to another account".into(),
+        ));
+    }
+    Ok(StatusCode::CREATED)
+}
+
+/// DELETE /api/v1/users/@me/push-subscriptions
+pub async fn delete_push_subscription(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<DeletePushSubscriptionRequest>,
+) -> Result<StatusCode, ApiError> {
+    let deleted = paracord_db::push_subscriptions::delete_push_subscription(
+        &state.db,
+        auth.user_id,
+        &body.endpoint,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !deleted {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -320,11 +320,12 @@ pub async fn post_command(
                 .get("activities")
                 .cloned()
                 .unwrap_or_else(|| json!([]));
-            let custom_status = req
-                .payload
-                .get("custom_status")
-                .and_then(|v| v.as_str())
-                .map(str::to_string);
+            let custom_status = if status == "offline" || status == "invisible" {
+                Value::Null
+            } else {
+                paracord_core::custom_status::current_custom_status_json(&state.db, auth.user_id)
+                    .await
+            };
             let presence_payload = json!({
                 "user_id": auth.user_id.to_string(),
                 "status": status,
@@ -546,22 +547,31 @@ pub async fn post_command(
                 return Err(ApiError::Forbidden);
             }
 
-            let typing_payload = json!({
-                "channel_id": channel_id.to_string(),
-                "user_id": auth.user_id.to_string(),
-                "timestamp": Utc::now().timestamp(),
-            });
-            if guild_id.is_none() {
-                let recipient_ids = paracord_db::dms::get_dm_recipient_ids(&state.db, channel_id)
-                    .await
-                    .unwrap_or_default();
-                state
-                    .event_bus
-                    .dispatch_to_users("TYPING_START", typing_payload, recipient_ids);
-            } else {
-                state
-                    .event_bus
-                    .dispatch("TYPING_START", typing_payload, guild_id);
+            let now_ms = Utc::now().timestamp_millis();
+            if let Some(expires_at_ms) =
+                paracord_core::typing::tracker().start(channel_id, auth.user_id, now_ms)
+            {
+                let typing_payload = json!({
+                    "channel_id": channel_id.to_string(),
+                    "user_id": auth.user_id.to_string(),
+                    "timestamp": now_ms / 1000,
+                    "expires_at": expires_at_ms / 1000,
+                });
+                if guild_id.is_none() {
+                    let recipient_ids =
+                        paracord_db::dms::get_dm_recipient_ids(&state.db, channel_id)
+                            .await
+                            .unwrap_or_default();
+                    state.event_bus.dispatch_to_users(
+                        "TYPING_START",
+                        typing_payload,
+                        recipient_ids,
+                    );
+                } else {
+                    state
+                        .event_bus
+                        .dispatch("TYPING_START", typing_payload, guild_id);
+                }
             }
         }
         _ => {
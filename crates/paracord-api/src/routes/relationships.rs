@@ -26,29 +26,28 @@ pub async fn list_relationships(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
-    let result: Vec<Value> = rels
-        .iter()
-        .map(|r| {
-            json!({
-                "id": format!("{}:{}", r.user_id, r.target_id),
-                "user_id": r.user_id.to_string(),
-                "target_id": r.target_id.to_string(),
-                "type": r.rel_type,
-                "rel_type": r.rel_type,
-                "created_at": r.created_at.to_rfc3339(),
-                "user": {
-                    "id": r.target_id.to_string(),
-                    "username": r.target_username,
-                    "discriminator": r.target_discriminator,
-                    "avatar_hash": r.target_avatar_hash,
-                }
-            })
-        })
-        .collect();
+    let result: Vec<Value> = rels.iter().map(relationship_json).collect();
 
     Ok(Json(json!(result)))
 }
 
+pub(crate) fn relationship_json(r: &paracord_db::relationships::RelationshipWithUserRow) -> Value {
+    json!({
+        "id": format!("{}:{}", r.user_id, r.target_id),
+        "user_id": r.user_id.to_string(),
+        "target_id": r.target_id.to_string(),
+        "type": r.rel_type,
+        "rel_type": r.rel_type,
+        "created_at": r.created_at.to_rfc3339(),
+        "user": {
+            "id": r.target_id.to_string(),
+            "username": r.target_username,
+            "discriminator": r.target_discriminator,
+            "avatar_hash": r.target_avatar_hash,
+        }
+    })
+}
+
 pub async fn add_friend(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -82,13 +81,45 @@ pub async fn add_friend(
     // Check if this is a block request
     let rel_type = body.rel_type.unwrap_or(1);
     if rel_type == 2 {
-        // Block: store directly
-        paracord_db::relationships::create_relationship(&state.db, auth.user_id, target_id, 2)
+        // Block: drop any friendship or pending request from the target first
+        let removed = paracord_db::relationships::block_user(&state.db, auth.user_id, target_id)
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        if removed {
+            state.event_bus.dispatch_to_users(
+                "RELATIONSHIP_REMOVE",
+                json!({ "user_id": auth.user_id.to_string() }),
+                vec![target_id],
+            );
+        }
+        if let Ok(Some(tu)) = paracord_db::users::get_user_by_id(&state.db, target_id).await {
+            state.event_bus.dispatch_to_users(
+                "RELATIONSHIP_ADD",
+                json!({
+                    "type": 2,
+                    "user": {
+                        "id": tu.id.to_string(),
+                        "username": tu.username,
+                        "discriminator": tu.discriminator,
+                        "avatar_hash": tu.avatar_hash,
+                    }
+                }),
+                vec![auth.user_id],
+            );
+        }
         return Ok(StatusCode::NO_CONTENT);
     }
 
+    let blocked =
+        paracord_db::relationships::is_blocked_either_direction(&state.db, auth.user_id, target_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if blocked {
+        return Err(ApiError::BadRequest(
+            "Unable to send a friend request to this user".into(),
+        ));
+    }
+
     // Check if the target already sent us a pending request
     let incoming = paracord_db::relationships::get_relationship(&state.db, target_id, auth.user_id)
         .await
@@ -1,16 +1,35 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Multipart, Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use paracord_core::AppState;
+use paracord_media::images;
+use paracord_models::audit_log::AuditAction;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
 use crate::routes::audit;
 
+const MAX_ROLE_ICON_SIZE: usize = 256 * 1024; // 256 KB
+/// Roles per space that may carry an icon at once.
+const MAX_ROLE_ICONS_PER_GUILD: i64 = 25;
+/// Role fields compared when recording audit log changes.
+const ROLE_AUDIT_FIELDS: &[&str] = &[
+    "name",
+    "color",
+    "hoist",
+    "icon",
+    "position",
+    "permissions",
+    "mentionable",
+];
+
 fn validate_role_permission_assignment(
     guild_owner_id: i64,
     actor_user_id: i64,
@@ -39,6 +58,7 @@ fn role_to_json(r: &paracord_db::roles::RoleRow) -> Value {
         "name": r.name,
         "color": r.color,
         "hoist": r.hoist,
+        "icon": r.icon_hash,
         "position": r.position,
         "permissions": r.permissions,
         "managed": r.managed,
@@ -94,12 +114,24 @@ pub async fn create_role(
         guild.owner_id,
         auth.user_id,
     );
+    let perms =
+        paracord_core::permissions::restrict_to_bot_scope(&state.db, guild_id, auth.user_id, perms)
+            .await?;
     if !paracord_core::permissions::is_server_admin(perms) {
         return Err(ApiError::Forbidden);
     }
     validate_role_permission_assignment(guild.owner_id, auth.user_id, perms, body.permissions)?;
+    // New roles start at the bottom of the hierarchy (position 0), which the
+    // creator must outrank.
+    if auth.user_id != guild.owner_id
+        && paracord_core::permissions::highest_role_position(&user_roles) <= 0
+    {
+        return Err(ApiError::RoleHierarchy(
+            "Cannot create a role: you have no role above the default member role".into(),
+        ));
+    }
 
-    let role_id = paracord_util::snowflake::generate(1);
+    let role_id = paracord_util::snowflake::generate();
     paracord_db::roles::create_role(&state.db, role_id, guild_id, &body.name, body.permissions)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -126,10 +158,10 @@ pub async fn create_role(
         &state,
         guild_id,
         auth.user_id,
-        audit::ACTION_ROLE_CREATE,
+        AuditAction::RoleCreate,
         Some(role_id),
         None,
-        Some(json!({ "name": body.name })),
+        audit::diff_changes(None, Some(&role_json), ROLE_AUDIT_FIELDS),
     )
     .await;
 
@@ -164,6 +196,9 @@ pub async fn update_role(
         guild.owner_id,
         auth.user_id,
     );
+    let perms =
+        paracord_core::permissions::restrict_to_bot_scope(&state.db, guild_id, auth.user_id, perms)
+            .await?;
     if !paracord_core::permissions::is_server_admin(perms) {
         return Err(ApiError::Forbidden);
     }
@@ -183,12 +218,13 @@ pub async fn update_role(
     if target_role.guild_id() != guild_id {
         return Err(ApiError::NotFound);
     }
-    if auth.user_id != guild.owner_id {
-        let actor_top_role_pos = user_roles.iter().map(|r| r.position).max().unwrap_or(0);
-        if target_role.position >= actor_top_role_pos {
-            return Err(ApiError::Forbidden);
-        }
-    }
+    paracord_core::permissions::ensure_outranks_role(
+        &user_roles,
+        guild.owner_id,
+        auth.user_id,
+        &target_role,
+        "edit",
+    )?;
 
     let updated = paracord_db::roles::update_role(
         &state.db,
@@ -203,7 +239,7 @@ pub async fn update_role(
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
     // Invalidate permission cache when role permissions change
-    paracord_core::permissions::invalidate_all(&state.permission_cache).await;
+    paracord_core::permissions::invalidate_guild(&state.permission_cache, guild_id).await;
 
     let role_json = role_to_json(&updated);
 
@@ -212,19 +248,23 @@ pub async fn update_role(
         json!({"guild_id": guild_id.to_string(), "role": &role_json}),
         Some(guild_id),
     );
-    audit::log_action(
-        &state,
-        guild_id,
-        auth.user_id,
-        audit::ACTION_ROLE_UPDATE,
-        Some(role_id),
-        None,
-        Some(json!({
-            "name": updated.name,
-            "permissions": updated.permissions,
-        })),
-    )
-    .await;
+    let changes = audit::diff_changes(
+        Some(&role_to_json(&target_role)),
+        Some(&role_json),
+        ROLE_AUDIT_FIELDS,
+    );
+    if changes.is_some() {
+        audit::log_action(
+            &state,
+            guild_id,
+            auth.user_id,
+            AuditAction::RoleUpdate,
+            Some(role_id),
+            None,
+            changes,
+        )
+        .await;
+    }
 
     Ok(Json(role_json))
 }
@@ -253,6 +293,9 @@ pub async fn delete_role(
         guild.owner_id,
         auth.user_id,
     );
+    let perms =
+        paracord_core::permissions::restrict_to_bot_scope(&state.db, guild_id, auth.user_id, perms)
+            .await?;
     if !paracord_core::permissions::is_server_admin(perms) {
         return Err(ApiError::Forbidden);
     }
@@ -264,19 +307,26 @@ pub async fn delete_role(
     if target_role.guild_id() != guild_id {
         return Err(ApiError::NotFound);
     }
-    if auth.user_id != guild.owner_id {
-        let actor_top_role_pos = user_roles.iter().map(|r| r.position).max().unwrap_or(0);
-        if target_role.position >= actor_top_role_pos {
-            return Err(ApiError::Forbidden);
-        }
-    }
+    paracord_core::permissions::ensure_outranks_role(
+        &user_roles,
+        guild.owner_id,
+        auth.user_id,
+        &target_role,
+        "delete",
+    )?;
 
     paracord_db::roles::delete_role(&state.db, role_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if let Some(hash) = &target_role.icon_hash {
+        let _ = state
+            .storage_backend
+            .delete(&images::role_icon_storage_key(role_id, hash))
+            .await;
+    }
 
     // Invalidate permission cache when a role is deleted
-    paracord_core::permissions::invalidate_all(&state.permission_cache).await;
+    paracord_core::permissions::invalidate_guild(&state.permission_cache, guild_id).await;
 
     state.event_bus.dispatch(
         "GUILD_ROLE_DELETE",
@@ -290,12 +340,381 @@ pub async fn delete_role(
         &state,
         guild_id,
         auth.user_id,
-        audit::ACTION_ROLE_DELETE,
+        AuditAction::RoleDelete,
         Some(role_id),
         None,
+        audit::diff_changes(Some(&role_to_json(&target_role)), None, ROLE_AUDIT_FIELDS),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Load a role of `guild_id` the actor may edit: they need server admin and
+/// must outrank the role.
+async fn editable_role(
+    state: &AppState,
+    guild_id: i64,
+    role_id: i64,
+    actor_id: i64,
+) -> Result<paracord_db::roles::RoleRow, ApiError> {
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let user_roles = paracord_db::roles::get_member_roles(&state.db, actor_id, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let perms = paracord_core::permissions::compute_permissions_from_roles(
+        &user_roles,
+        guild.owner_id,
+        actor_id,
+    );
+    let perms =
+        paracord_core::permissions::restrict_to_bot_scope(&state.db, guild_id, actor_id, perms)
+            .await?;
+    if !paracord_core::permissions::is_server_admin(perms) {
+        return Err(ApiError::Forbidden);
+    }
+    let role = paracord_db::roles::get_role(&state.db, role_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    if role.guild_id() != guild_id {
+        return Err(ApiError::NotFound);
+    }
+    paracord_core::permissions::ensure_outranks_role(
+        &user_roles,
+        guild.owner_id,
+        actor_id,
+        &role,
+        "edit",
+    )?;
+    Ok(role)
+}
+
+fn dispatch_role_update(state: &AppState, guild_id: i64, role: &paracord_db::roles::RoleRow) {
+    state.event_bus.dispatch(
+        "GUILD_ROLE_UPDATE",
+        json!({"guild_id": guild_id.to_string(), "role": role_to_json(role)}),
+        Some(guild_id),
+    );
+}
+
+/// PUT /api/v1/guilds/{guild_id}/roles/{role_id}/icon — multipart upload
+/// with an `image` (or `file`) part. Replaces any previous icon.
+pub async fn upload_role_icon(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, role_id)): Path<(i64, i64)>,
+    mut multipart: Multipart,
+) -> Result<Json<Value>, ApiError> {
+    let role = editable_role(&state, guild_id, role_id, auth.user_id).await?;
+
+    let mut image_data: Option<Vec<u8>> = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+    {
+        if matches!(field.name(), Some("image" | "file")) {
+            let data = field
+                .bytes()
+                .await
+                .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+            image_data = Some(data.to_vec());
+        }
+    }
+    let image_data =
+        image_data.ok_or_else(|| ApiError::BadRequest("Missing role icon image".into()))?;
+    if image_data.is_empty() {
+        return Err(ApiError::BadRequest("Empty role icon image".into()));
+    }
+    if image_data.len() > MAX_ROLE_ICON_SIZE {
+        return Err(ApiError::BadRequest(
+            "Role icon must be under 256 KB".into(),
+        ));
+    }
+    if role.icon_hash.is_none() {
+        let with_icons = paracord_db::roles::count_roles_with_icons(&state.db, guild_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        if with_icons >= MAX_ROLE_ICONS_PER_GUILD {
+            return Err(ApiError::BadRequest(format!(
+                "At most {MAX_ROLE_ICONS_PER_GUILD} roles can have an icon"
+            )));
+        }
+    }
+
+    let processed = tokio::task::spawn_blocking(move || images::process_role_icon(&image_data))
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or_else(|| {
+            ApiError::BadRequest("Role icon must be a PNG, GIF, JPEG or WebP image".into())
+        })?;
+    let hash = paracord_util::hex::hex_encode(&Sha256::digest(&processed));
+
+    state
+        .storage_backend
+        .store(&images::role_icon_storage_key(role_id, &hash), &processed)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let updated = paracord_db::roles::set_role_icon(&state.db, role_id, Some(&hash))
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let changes = audit::diff_changes(
+        Some(&role_to_json(&role)),
+        Some(&role_to_json(&updated)),
+        &["icon"],
+    );
+    if let Some(previous) = role.icon_hash.filter(|previous| *previous != hash) {
+        let _ = state
+            .storage_backend
+            .delete(&images::role_icon_storage_key(role_id, &previous))
+            .await;
+    }
+
+    dispatch_role_update(&state, guild_id, &updated);
+    audit::log_action(
+        &state,
+        guild_id,
+        auth.user_id,
+        AuditAction::RoleUpdate,
+        Some(role_id),
+        None,
+        changes,
+    )
+    .await;
+
+    Ok(Json(role_to_json(&updated)))
+}
+
+/// DELETE /api/v1/guilds/{guild_id}/roles/{role_id}/icon
+pub async fn delete_role_icon(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, role_id)): Path<(i64, i64)>,
+) -> Result<StatusCode, ApiError> {
+    let role = editable_role(&state, guild_id, role_id, auth.user_id).await?;
+    let Some(previous) = role.icon_hash else {
+        return Ok(StatusCode::NO_CONTENT);
+    };
+    let updated = paracord_db::roles::set_role_icon(&state.db, role_id, None)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let _ = state
+        .storage_backend
+        .delete(&images::role_icon_storage_key(role_id, &previous))
+        .await;
+
+    dispatch_role_update(&state, guild_id, &updated);
+    audit::log_action(
+        &state,
+        guild_id,
+        auth.user_id,
+        AuditAction::RoleUpdate,
+        Some(role_id),
         None,
+        Some(json!({ "icon": { "old": previous, "new": null } })),
     )
     .await;
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// GET /api/v1/guilds/{guild_id}/roles/{role_id}/icons/{hash} — only the
+/// role's current icon is served. The hash is derived from the image, so
+/// responses are cached indefinitely.
+pub async fn get_role_icon(
+    State(state): State<AppState>,
+    Path((guild_id, role_id, hash)): Path<(i64, i64, String)>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let role = paracord_db::roles::get_role(&state.db, role_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let hash = hash.strip_suffix(".png").unwrap_or(&hash);
+    if role.guild_id() != guild_id || role.icon_hash.as_deref() != Some(hash) {
+        return Err(ApiError::NotFound);
+    }
+
+    let etag = format!("\"{hash}\"");
+    let etag_value = HeaderValue::from_str(&etag).map_err(|e| ApiError::Internal(e.into()))?;
+    let cache_control = HeaderValue::from_static("public, max-age=31536000, immutable");
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|tag| tag.trim() == etag));
+    if not_modified {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [
+                (header::ETAG, etag_value),
+                (header::CACHE_CONTROL, cache_control),
+            ],
+        )
+            .into_response());
+    }
+
+    let data = state
+        .storage_backend
+        .retrieve(&images::role_icon_storage_key(role_id, hash))
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static("image/png")),
+            (header::CACHE_CONTROL, cache_control),
+            (header::ETAG, etag_value),
+        ],
+        data,
+    )
+        .into_response())
+}
+
+#[derive(Deserialize)]
+pub struct RolePositionEntry {
+    pub id: String,
+    pub position: i32,
+}
+
+/// `PATCH /guilds/{guild_id}/roles` — move roles within the hierarchy.
+///
+/// Requested positions only decide the relative order: after applying them
+/// every role except the default member role is renumbered to a contiguous
+/// `1..=n` sequence (ties go to the role that was explicitly moved), so gaps
+/// and duplicate positions never reach the database.
+pub async fn update_role_positions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Json(body): Json<Vec<RolePositionEntry>>,
+) -> Result<Json<Value>, ApiError> {
+    if body.is_empty() {
+        return Err(ApiError::BadRequest(
+            "positions array must not be empty".into(),
+        ));
+    }
+    if body.len() > 250 {
+        return Err(ApiError::BadRequest(
+            "too many role position updates".into(),
+        ));
+    }
+
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let user_roles = paracord_db::roles::get_member_roles(&state.db, auth.user_id, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let perms = paracord_core::permissions::compute_permissions_from_roles(
+        &user_roles,
+        guild.owner_id,
+        auth.user_id,
+    );
+    let perms =
+        paracord_core::permissions::restrict_to_bot_scope(&state.db, guild_id, auth.user_id, perms)
+            .await?;
+    if !paracord_core::permissions::is_server_admin(perms) {
+        return Err(ApiError::Forbidden);
+    }
+    let is_owner = auth.user_id == guild.owner_id;
+    let actor_top = paracord_core::permissions::highest_role_position(&user_roles);
+
+    let roles = paracord_db::roles::get_guild_roles(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let role_by_id: HashMap<i64, &paracord_db::roles::RoleRow> =
+        roles.iter().map(|role| (role.id, role)).collect();
+
+    let mut requested: HashMap<i64, i32> = HashMap::with_capacity(body.len());
+    for entry in &body {
+        let role_id = entry
+            .id
+            .parse::<i64>()
+            .map_err(|_| ApiError::BadRequest("Invalid role id".into()))?;
+        let Some(role) = role_by_id.get(&role_id) else {
+            return Err(ApiError::BadRequest(format!(
+                "Role {role_id} does not belong to this space"
+            )));
+        };
+        if role_id == guild_id {
+            return Err(ApiError::BadRequest(
+                "The default Member role cannot be moved".into(),
+            ));
+        }
+        if requested.insert(role_id, entry.position).is_some() {
+            return Err(ApiError::BadRequest(format!(
+                "Role {role_id} appears more than once"
+            )));
+        }
+        paracord_core::permissions::ensure_outranks_role(
+            &user_roles,
+            guild.owner_id,
+            auth.user_id,
+            role,
+            "move",
+        )?;
+        if !is_owner && entry.position >= actor_top {
+            return Err(ApiError::RoleHierarchy(format!(
+                "Cannot move the role '{}' to or above your highest role",
+                role.name
+            )));
+        }
+    }
+
+    let mut ordered: Vec<&paracord_db::roles::RoleRow> =
+        roles.iter().filter(|role| role.id != guild_id).collect();
+    ordered.sort_by_key(|role| match requested.get(&role.id) {
+        Some(&position) => (position, 1, role.id),
+        None => (role.position, 0, role.id),
+    });
+    // Every moved role starts and ends below the actor's highest role, so
+    // renumbering never changes the order of the roles at or above it.
+    let updates: Vec<(i64, i32)> = ordered
+        .iter()
+        .zip(1..)
+        .map(|(role, position)| (role.id, position))
+        .collect();
+
+    let changed = paracord_db::roles::update_role_positions(&state.db, guild_id, &updates)
+        .await
+        .map_err(|e| match e {
+            // A role was deleted after validation; nothing was applied.
+            paracord_db::DbError::NotFound => {
+                ApiError::Conflict("Roles changed while reordering; retry".into())
+            }
+            other => ApiError::Internal(anyhow::anyhow!(other.to_string())),
+        })?;
+
+    for role in &changed {
+        dispatch_role_update(&state, guild_id, role);
+    }
+    if !changed.is_empty() {
+        audit::log_action(
+            &state,
+            guild_id,
+            auth.user_id,
+            AuditAction::RoleUpdate,
+            None,
+            Some("roles reordered"),
+            Some(json!({
+                "positions": updates
+                    .iter()
+                    .map(|(id, position)| json!({ "id": id.to_string(), "position": position }))
+                    .collect::<Vec<_>>(),
+            })),
+        )
+        .await;
+    }
+
+    let ordered = paracord_db::roles::get_guild_roles(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(json!({
+        "updated": changed.len(),
+        "roles": ordered.iter().map(role_to_json).collect::<Vec<_>>(),
+    })))
+}
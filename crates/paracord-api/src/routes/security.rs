@@ -15,8 +15,11 @@ fn header_opt(headers: &HeaderMap, name: &str) -> Option<String> {
 fn request_metadata(
     headers: Option<&HeaderMap>,
 ) -> (Option<String>, Option<String>, Option<String>) {
+    // Resolved once per request, so this matches the address the rate
+    // limiter and auth guards saw.
+    let ip_address = crate::client_ip::current().map(|ip| ip.to_string());
     let Some(headers) = headers else {
-        return (None, None, None);
+        return (None, None, ip_address);
     };
     let device_id = header_opt(headers, "x-device-id");
     let user_agent = headers
@@ -25,19 +28,6 @@ fn request_metadata(
         .map(str::trim)
         .filter(|v| !v.is_empty())
         .map(str::to_string);
-    let trust_proxy = std::env::var("PARACORD_TRUST_PROXY")
-        .ok()
-        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
-        .unwrap_or(false);
-    let trusted_proxy_configured = std::env::var("PARACORD_TRUSTED_PROXY_IPS")
-        .ok()
-        .map(|raw| !raw.trim().is_empty())
-        .unwrap_or(false);
-    let ip_address = if trust_proxy && trusted_proxy_configured {
-        header_opt(headers, "x-forwarded-for")
-    } else {
-        None
-    };
     (device_id, user_agent, ip_address)
 }
 
@@ -51,7 +41,7 @@ pub async fn log_security_event(
     details: Option<Value>,
 ) {
     let (device_id, user_agent, ip_address) = request_metadata(headers);
-    let id = paracord_util::snowflake::generate(1);
+    let id = paracord_util::snowflake::generate();
     let details_ref = details.as_ref();
 
     if let Err(err) = paracord_db::security_events::create_event(
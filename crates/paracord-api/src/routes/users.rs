@@ -1,11 +1,15 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
+    extract::{Multipart, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use paracord_core::AppState;
+use paracord_db::users::{UserProfileUpdate, UserRow};
+use paracord_media::images;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
@@ -13,8 +17,13 @@ use crate::routes::security;
 
 const MAX_DISPLAY_NAME_LEN: usize = 64;
 const MAX_BIO_LEN: usize = 512;
-const MAX_CUSTOM_STATUS_LEN: usize = 128;
+const MAX_PRONOUNS_LEN: usize = 40;
+/// Accent colors are 24-bit RGB values.
+const MAX_ACCENT_COLOR: i64 = 0xFF_FF_FF;
+const MAX_BANNER_IMAGE_SIZE: usize = 1024 * 1024; // 1 MB
+const MAX_AVATAR_IMAGE_SIZE: usize = 1024 * 1024; // 1 MB
 const MAX_CUSTOM_CSS_LEN: usize = 10 * 1024;
+const MAX_NOTIFICATION_SETTINGS_PER_UPDATE: usize = 100;
 
 fn contains_dangerous_markup(value: &str) -> bool {
     let lower = value.to_ascii_lowercase();
@@ -47,16 +56,9 @@ fn sanitize_custom_css(value: &str) -> Result<Option<String>, ApiError> {
     Ok(Some(trimmed.to_string()))
 }
 
-pub async fn get_me(
-    State(state): State<AppState>,
-    auth: AuthUser,
-) -> Result<Json<Value>, ApiError> {
-    let user = paracord_db::users::get_user_by_id(&state.db, auth.user_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
-
-    Ok(Json(json!({
+/// The caller's own account, including private fields such as the email.
+fn me_json(user: &UserRow) -> Value {
+    json!({
         "id": user.id.to_string(),
         "username": user.username,
         "discriminator": user.discriminator,
@@ -65,18 +67,90 @@ pub async fn get_me(
         "avatar_hash": user.avatar_hash,
         "banner_hash": user.banner_hash,
         "bio": user.bio,
+        "pronouns": user.pronouns,
+        "accent_color": user.accent_color,
         "flags": user.flags,
         "bot": paracord_core::is_bot(user.flags),
         "system": false,
         "created_at": user.created_at.to_rfc3339(),
-    })))
+    })
+}
+
+/// Another user's profile as seen by a viewer. `show_about` is false when the
+/// user keeps their bio and pronouns to friends and the viewer isn't one.
+fn public_user_json(user: &UserRow, show_about: bool) -> Value {
+    json!({
+        "id": user.id.to_string(),
+        "username": user.username,
+        "discriminator": user.discriminator,
+        "display_name": user.display_name,
+        "avatar_hash": user.avatar_hash,
+        "banner_hash": user.banner_hash,
+        "bio": user.bio.as_ref().filter(|_| show_about),
+        "pronouns": user.pronouns.as_ref().filter(|_| show_about),
+        "accent_color": user.accent_color,
+        "flags": user.flags,
+        "bot": paracord_core::is_bot(user.flags),
+        "system": false,
+        "created_at": user.created_at.to_rfc3339(),
+    })
+}
+
+pub async fn get_me(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Value>, ApiError> {
+    let user = paracord_db::users::get_user_by_id(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(Json(me_json(&user)))
+}
+
+/// An accent color as a 24-bit integer or a `#rrggbb` string. An empty string
+/// clears the color.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum AccentColorInput {
+    Rgb(i64),
+    Hex(String),
+}
+
+fn parse_accent_color(input: &AccentColorInput) -> Result<Option<i32>, ApiError> {
+    let invalid = || ApiError::invalid_field("accent_color", "Must be a color like #5865f2");
+    let value = match input {
+        AccentColorInput::Rgb(value) => *value,
+        AccentColorInput::Hex(raw) => {
+            let raw = raw.trim();
+            if raw.is_empty() {
+                return Ok(None);
+            }
+            let digits = raw.strip_prefix('#').unwrap_or(raw);
+            if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(invalid());
+            }
+            i64::from_str_radix(digits, 16).map_err(|_| invalid())?
+        }
+    };
+    if !(0..=MAX_ACCENT_COLOR).contains(&value) {
+        return Err(invalid());
+    }
+    Ok(Some(value as i32))
 }
 
 #[derive(Deserialize)]
 pub struct UpdateMeRequest {
     pub display_name: Option<String>,
     pub bio: Option<String>,
+    pub pronouns: Option<String>,
+    /// Only an empty string is accepted, removing the avatar. New avatars are
+    /// uploaded to `/users/@me/avatar`.
     pub avatar_hash: Option<String>,
+    /// Only an empty string is accepted, removing the banner. New banners are
+    /// uploaded to `/users/@me/banner`.
+    pub banner_hash: Option<String>,
+    pub accent_color: Option<AccentColorInput>,
 }
 
 pub async fn update_me(
@@ -104,30 +178,398 @@ pub async fn update_me(
             return Err(ApiError::BadRequest("bio contains unsafe markup".into()));
         }
     }
+    let pronouns = body.pronouns.as_deref().map(str::trim);
+    if let Some(pronouns) = pronouns {
+        if pronouns.chars().count() > MAX_PRONOUNS_LEN {
+            return Err(ApiError::invalid_field(
+                "pronouns",
+                format!("Must be at most {MAX_PRONOUNS_LEN} characters"),
+            ));
+        }
+        if contains_dangerous_markup(pronouns) {
+            return Err(ApiError::invalid_field(
+                "pronouns",
+                "Contains unsafe markup",
+            ));
+        }
+    }
+    if body
+        .avatar_hash
+        .as_deref()
+        .is_some_and(|hash| !hash.is_empty())
+    {
+        return Err(ApiError::invalid_field(
+            "avatar_hash",
+            "Upload avatars to /users/@me/avatar; only an empty value is accepted here",
+        ));
+    }
+    if body
+        .banner_hash
+        .as_deref()
+        .is_some_and(|hash| !hash.is_empty())
+    {
+        return Err(ApiError::invalid_field(
+            "banner_hash",
+            "Upload banners to /users/@me/banner; only an empty value is accepted here",
+        ));
+    }
+    let accent_color = body
+        .accent_color
+        .as_ref()
+        .map(parse_accent_color)
+        .transpose()?;
+
+    let previous = if body.avatar_hash.is_some() || body.banner_hash.is_some() {
+        paracord_db::users::get_user_by_id(&state.db, auth.user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    } else {
+        None
+    };
+    let previous_avatar = previous
+        .as_ref()
+        .filter(|_| body.avatar_hash.is_some())
+        .and_then(|user| user.avatar_hash.clone());
+    let previous_banner = previous
+        .filter(|_| body.banner_hash.is_some())
+        .and_then(|user| user.banner_hash);
 
     let updated = paracord_core::user::update_profile(
         &state.db,
         auth.user_id,
-        body.display_name.as_deref(),
-        body.bio.as_deref(),
-        body.avatar_hash.as_deref(),
+        &UserProfileUpdate {
+            display_name: body.display_name.as_deref(),
+            bio: body.bio.as_deref(),
+            pronouns,
+            avatar_hash: body.avatar_hash.as_deref(),
+            banner_hash: body.banner_hash.as_deref(),
+            accent_color,
+        },
     )
     .await?;
+    if let Some(hash) = previous_avatar {
+        delete_avatar_variants(&state, auth.user_id, &hash).await;
+    }
+    if let Some(hash) = previous_banner {
+        let _ = state
+            .storage_backend
+            .delete(&images::banner_storage_key(auth.user_id, &hash))
+            .await;
+    }
 
-    Ok(Json(json!({
-        "id": updated.id.to_string(),
-        "username": updated.username,
-        "discriminator": updated.discriminator,
-        "email": updated.email,
-        "display_name": updated.display_name,
-        "avatar_hash": updated.avatar_hash,
-        "banner_hash": updated.banner_hash,
-        "bio": updated.bio,
-        "flags": updated.flags,
-        "bot": paracord_core::is_bot(updated.flags),
-        "system": false,
-        "created_at": updated.created_at.to_rfc3339(),
-    })))
+    Ok(Json(me_json(&updated)))
+}
+
+/// Read the `image` (or `file`) part of a multipart upload, rejecting an
+/// empty or oversized image. `what` names the image in error messages.
+pub(crate) async fn read_image_part(
+    multipart: &mut Multipart,
+    what: &str,
+    max_size: usize,
+) -> Result<Vec<u8>, ApiError> {
+    let mut image_data: Option<Vec<u8>> = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+    {
+        if matches!(field.name(), Some("image" | "file")) {
+            let data = field
+                .bytes()
+                .await
+                .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+            image_data = Some(data.to_vec());
+        }
+    }
+    let image_data =
+        image_data.ok_or_else(|| ApiError::BadRequest(format!("Missing {what} image")))?;
+    if image_data.is_empty() {
+        return Err(ApiError::BadRequest(format!("Empty {what} image")));
+    }
+    if image_data.len() > max_size {
+        return Err(ApiError::BadRequest(format!(
+            "The {what} image must be under {} MB",
+            max_size / (1024 * 1024)
+        )));
+    }
+    Ok(image_data)
+}
+
+/// PUT /api/v1/users/@me/banner — multipart upload with an `image` (or
+/// `file`) part. Replaces any previous banner.
+pub async fn upload_banner(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut multipart: Multipart,
+) -> Result<Json<Value>, ApiError> {
+    let image_data = read_image_part(&mut multipart, "banner", MAX_BANNER_IMAGE_SIZE).await?;
+
+    let processed = tokio::task::spawn_blocking(move || images::process_banner(&image_data))
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or_else(|| {
+            ApiError::BadRequest("Banner must be a PNG, GIF, JPEG or WebP image".into())
+        })?;
+    let hash = paracord_util::hex::hex_encode(&Sha256::digest(&processed));
+
+    let previous = paracord_db::users::get_user_by_id(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?
+        .banner_hash;
+    state
+        .storage_backend
+        .store(&images::banner_storage_key(auth.user_id, &hash), &processed)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let updated = paracord_core::user::update_profile(
+        &state.db,
+        auth.user_id,
+        &UserProfileUpdate {
+            banner_hash: Some(&hash),
+            ..Default::default()
+        },
+    )
+    .await?;
+    if let Some(previous) = previous.filter(|previous| *previous != hash) {
+        let _ = state
+            .storage_backend
+            .delete(&images::banner_storage_key(auth.user_id, &previous))
+            .await;
+    }
+
+    Ok(Json(me_json(&updated)))
+}
+
+/// DELETE /api/v1/users/@me/banner
+pub async fn delete_banner(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<StatusCode, ApiError> {
+    let previous = paracord_db::users::get_user_by_id(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?
+        .banner_hash;
+    let Some(previous) = previous else {
+        return Ok(StatusCode::NO_CONTENT);
+    };
+    paracord_core::user::update_profile(
+        &state.db,
+        auth.user_id,
+        &UserProfileUpdate {
+            banner_hash: Some(""),
+            ..Default::default()
+        },
+    )
+    .await?;
+    let _ = state
+        .storage_backend
+        .delete(&images::banner_storage_key(auth.user_id, &previous))
+        .await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/users/{user_id}/banners/{hash} — only the user's current
+/// banner is served. The hash is derived from the image, so responses are
+/// cached indefinitely.
+pub async fn get_banner(
+    State(state): State<AppState>,
+    Path((user_id, hash)): Path<(i64, String)>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let user = paracord_db::users::get_user_by_id(&state.db, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let hash = hash.strip_suffix(".png").unwrap_or(&hash);
+    if user.banner_hash.as_deref() != Some(hash) {
+        return Err(ApiError::NotFound);
+    }
+
+    immutable_image_response(
+        &state,
+        &headers,
+        hash,
+        &images::banner_storage_key(user_id, hash),
+        "image/png",
+    )
+    .await
+}
+
+/// PUT /api/v1/users/@me/avatar — multipart upload with an `image` (or
+/// `file`) part. The image is re-encoded at every avatar size; animated
+/// avatars are not supported. Replaces any previous avatar.
+pub async fn upload_avatar(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut multipart: Multipart,
+) -> Result<Json<Value>, ApiError> {
+    let image_data = read_image_part(&mut multipart, "avatar", MAX_AVATAR_IMAGE_SIZE).await?;
+
+    let processed = tokio::task::spawn_blocking(move || {
+        if images::is_animated(&image_data) {
+            return Err(ApiError::BadRequest(
+                "Animated avatars are not supported".into(),
+            ));
+        }
+        images::process_avatar(&image_data, false).ok_or_else(|| {
+            ApiError::BadRequest("Avatar must be a PNG, GIF, JPEG or WebP image".into())
+        })
+    })
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))??;
+    let hash = paracord_util::hex::hex_encode(&Sha256::digest(processed.largest()));
+
+    let previous = paracord_db::users::get_user_by_id(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?
+        .avatar_hash;
+    for (size, data) in &processed.variants {
+        state
+            .storage_backend
+            .store(
+                &images::avatar_storage_key(auth.user_id, &hash, *size),
+                data,
+            )
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
+    let updated = paracord_core::user::update_profile(
+        &state.db,
+        auth.user_id,
+        &UserProfileUpdate {
+            avatar_hash: Some(&hash),
+            ..Default::default()
+        },
+    )
+    .await?;
+    if let Some(previous) = previous.filter(|previous| *previous != hash) {
+        delete_avatar_variants(&state, auth.user_id, &previous).await;
+    }
+
+    Ok(Json(me_json(&updated)))
+}
+
+/// DELETE /api/v1/users/@me/avatar
+pub async fn delete_avatar(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<StatusCode, ApiError> {
+    let previous = paracord_db::users::get_user_by_id(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?
+        .avatar_hash;
+    let Some(previous) = previous else {
+        return Ok(StatusCode::NO_CONTENT);
+    };
+    paracord_core::user::update_profile(
+        &state.db,
+        auth.user_id,
+        &UserProfileUpdate {
+            avatar_hash: Some(""),
+            ..Default::default()
+        },
+    )
+    .await?;
+    delete_avatar_variants(&state, auth.user_id, &previous).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_avatar_variants(state: &AppState, user_id: i64, hash: &str) {
+    for size in images::AVATAR_VARIANT_SIZES {
+        let _ = state
+            .storage_backend
+            .delete(&images::avatar_storage_key(user_id, hash, size))
+            .await;
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ImageSizeQuery {
+    /// Requested edge length in pixels; the nearest stored size at least
+    /// this large is served.
+    pub size: Option<u32>,
+}
+
+/// GET /api/v1/users/{user_id}/avatars/{hash}?size= — only the user's current
+/// avatar is served. The hash is derived from the image, so responses are
+/// cached indefinitely.
+pub async fn get_avatar(
+    State(state): State<AppState>,
+    Path((user_id, hash)): Path<(i64, String)>,
+    Query(query): Query<ImageSizeQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let user = paracord_db::users::get_user_by_id(&state.db, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let hash = hash
+        .strip_suffix(".png")
+        .or_else(|| hash.strip_suffix(".gif"))
+        .unwrap_or(&hash);
+    if user.avatar_hash.as_deref() != Some(hash) {
+        return Err(ApiError::NotFound);
+    }
+
+    let size = images::avatar_variant_size(query.size);
+    let content_type = format!("image/{}", images::avatar_extension(hash));
+    immutable_image_response(
+        &state,
+        &headers,
+        &format!("{hash}_{size}"),
+        &images::avatar_storage_key(user_id, hash, size),
+        &content_type,
+    )
+    .await
+}
+
+/// Serve a stored content-addressed image with `etag` as its entity tag and
+/// an immutable cache policy, answering 304 when the client already has it.
+pub(crate) async fn immutable_image_response(
+    state: &AppState,
+    headers: &HeaderMap,
+    etag: &str,
+    storage_key: &str,
+    content_type: &str,
+) -> Result<Response, ApiError> {
+    let etag = format!("\"{etag}\"");
+    let etag_value = HeaderValue::from_str(&etag).map_err(|e| ApiError::Internal(e.into()))?;
+    let cache_control = HeaderValue::from_static("public, max-age=31536000, immutable");
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|tag| tag.trim() == etag));
+    if not_modified {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [
+                (header::ETAG, etag_value),
+                (header::CACHE_CONTROL, cache_control),
+            ],
+        )
+            .into_response());
+    }
+
+    let data = state
+        .storage_backend
+        .retrieve(storage_key)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    let content_type =
+        HeaderValue::from_str(content_type).map_err(|e| ApiError::Internal(e.into()))?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, cache_control),
+            (header::ETAG, etag_value),
+        ],
+        data,
+    )
+        .into_response())
 }
 
 pub async fn get_settings(
@@ -137,6 +579,9 @@ pub async fn get_settings(
     let settings = paracord_db::users::get_user_settings(&state.db, auth.user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let notification_settings = notification_settings_json(&state, auth.user_id).await?;
+    let custom_status =
+        paracord_core::custom_status::current_custom_status_json(&state.db, auth.user_id).await;
 
     if let Some(s) = settings {
         Ok(Json(json!({
@@ -146,9 +591,12 @@ pub async fn get_settings(
             "message_display_compact": s.message_display == "compact",
             "custom_css": s.custom_css,
             "status": "online",
-            "custom_status": null,
+            "custom_status": custom_status,
             "crypto_auth_enabled": s.crypto_auth_enabled,
+            "show_mutuals": s.show_mutuals,
+            "friends_only_profile": s.friends_only_profile,
             "notifications": s.notifications,
+            "notification_settings": notification_settings,
             "keybinds": s.keybinds,
         })))
     } else {
@@ -159,14 +607,229 @@ pub async fn get_settings(
             "message_display_compact": false,
             "custom_css": null,
             "status": "online",
-            "custom_status": null,
+            "custom_status": custom_status,
             "crypto_auth_enabled": false,
+            "show_mutuals": true,
+            "friends_only_profile": false,
             "notifications": {},
+            "notification_settings": notification_settings,
             "keybinds": {},
         })))
     }
 }
 
+#[derive(Deserialize)]
+pub struct SetCustomStatusRequest {
+    pub text: Option<String>,
+    pub emoji: Option<String>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// PUT /api/v1/users/@me/custom-status — replace the caller's custom status
+/// and publish it to their presence.
+pub async fn set_custom_status(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<SetCustomStatusRequest>,
+) -> Result<Json<Value>, ApiError> {
+    use paracord_core::custom_status::{sanitize, MAX_EMOJI_CHARS, MAX_TEXT_CHARS};
+
+    let text = body.text.as_deref().and_then(sanitize);
+    let emoji = body.emoji.as_deref().and_then(sanitize);
+    if text.is_none() && emoji.is_none() {
+        return Err(ApiError::invalid_field(
+            "text",
+            "A custom status needs text or an emoji",
+        ));
+    }
+    if let Some(text) = text.as_deref() {
+        if text.chars().count() > MAX_TEXT_CHARS {
+            return Err(ApiError::invalid_field(
+                "text",
+                format!("Must be at most {MAX_TEXT_CHARS} characters"),
+            ));
+        }
+        if contains_dangerous_markup(text) {
+            return Err(ApiError::invalid_field("text", "Contains unsafe markup"));
+        }
+    }
+    if emoji
+        .as_deref()
+        .is_some_and(|emoji| emoji.chars().count() > MAX_EMOJI_CHARS)
+    {
+        return Err(ApiError::invalid_field(
+            "emoji",
+            format!("Must be at most {MAX_EMOJI_CHARS} characters"),
+        ));
+    }
+    if body
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= chrono::Utc::now())
+    {
+        return Err(ApiError::invalid_field(
+            "expires_at",
+            "Must be in the future",
+        ));
+    }
+
+    let status = paracord_db::custom_statuses::set_custom_status(
+        &state.db,
+        auth.user_id,
+        text.as_deref(),
+        emoji.as_deref(),
+        body.expires_at,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    paracord_core::custom_status::publish(&state, auth.user_id, Some(&status)).await;
+    Ok(Json(paracord_core::custom_status::custom_status_json(
+        &status,
+    )))
+}
+
+/// DELETE /api/v1/users/@me/custom-status
+pub async fn clear_custom_status(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<StatusCode, ApiError> {
+    let cleared = paracord_db::custom_statuses::clear_custom_status(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if cleared {
+        paracord_core::custom_status::publish(&state, auth.user_id, None).await;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+pub struct NotificationSettingInput {
+    pub guild_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub level: Option<String>,
+    pub push_level: Option<String>,
+    #[serde(default)]
+    pub suppress_everyone: bool,
+    #[serde(default)]
+    pub muted: bool,
+    pub muted_until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+async fn notification_settings_json(state: &AppState, user_id: i64) -> Result<Value, ApiError> {
+    let rows = paracord_db::notification_settings::list_notification_settings(&state.db, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(json!(rows
+        .into_iter()
+        .map(|row| {
+            let target_key = if row.target_type == paracord_db::notification_settings::TARGET_GUILD
+            {
+                "guild_id"
+            } else {
+                "channel_id"
+            };
+            json!({
+                target_key: row.target_id.to_string(),
+                "level": row.level,
+                "push_level": row.push_level,
+                "suppress_everyone": row.suppress_everyone,
+                "muted": row.muted,
+                "muted_until": row.muted_until.map(|dt| dt.to_rfc3339()),
+            })
+        })
+        .collect::<Vec<Value>>()))
+}
+
+fn parse_notification_level(
+    field: &'static str,
+    raw: Option<&str>,
+) -> Result<Option<String>, ApiError> {
+    use paracord_db::notification_settings::{LEVEL_ALL, LEVEL_MENTIONS, LEVEL_NOTHING};
+    match raw {
+        None => Ok(None),
+        Some(level @ (LEVEL_ALL | LEVEL_MENTIONS | LEVEL_NOTHING)) => Ok(Some(level.to_string())),
+        Some(_) => Err(ApiError::invalid_field(
+            field,
+            "Must be one of all, mentions or nothing",
+        )),
+    }
+}
+
+/// Replace the caller's notification settings for each listed space or
+/// channel. Only spaces the user belongs to and channels they can reach
+/// (space channels or their own DMs) are accepted.
+async fn apply_notification_settings(
+    state: &AppState,
+    user_id: i64,
+    inputs: &[NotificationSettingInput],
+) -> Result<(), ApiError> {
+    use paracord_db::notification_settings::{TARGET_CHANNEL, TARGET_GUILD};
+
+    if inputs.len() > MAX_NOTIFICATION_SETTINGS_PER_UPDATE {
+        return Err(ApiError::invalid_field(
+            "notification_settings",
+            format!("At most {MAX_NOTIFICATION_SETTINGS_PER_UPDATE} entries per update"),
+        ));
+    }
+    let mut updates = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let (raw_id, target_type) = match (input.guild_id.as_deref(), input.channel_id.as_deref()) {
+            (Some(id), None) => (id, TARGET_GUILD),
+            (None, Some(id)) => (id, TARGET_CHANNEL),
+            _ => {
+                return Err(ApiError::invalid_field(
+                    "notification_settings",
+                    "Each entry needs exactly one of guild_id or channel_id",
+                ))
+            }
+        };
+        let target_id = raw_id
+            .parse::<i64>()
+            .map_err(|_| ApiError::invalid_field("notification_settings", "Invalid ID"))?;
+        if target_type == TARGET_GUILD {
+            paracord_core::permissions::ensure_guild_member(&state.db, target_id, user_id).await?;
+        } else {
+            let channel = paracord_db::channels::get_channel(&state.db, target_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+                .ok_or(ApiError::NotFound)?;
+            match channel.guild_id() {
+                Some(guild_id) => {
+                    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id)
+                        .await?
+                }
+                None => {
+                    if !paracord_db::dms::is_dm_recipient(&state.db, channel.id, user_id)
+                        .await
+                        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+                    {
+                        return Err(ApiError::NotFound);
+                    }
+                }
+            }
+        }
+        let update = paracord_db::notification_settings::NotificationSettingUpdate {
+            level: parse_notification_level("level", input.level.as_deref())?,
+            push_level: parse_notification_level("push_level", input.push_level.as_deref())?,
+            suppress_everyone: input.suppress_everyone,
+            muted: input.muted,
+            muted_until: input.muted_until.filter(|_| input.muted),
+        };
+        updates.push((target_id, target_type, update));
+    }
+    for (target_id, target_type, update) in &updates {
+        paracord_db::notification_settings::set_notification_setting(
+            &state.db,
+            user_id,
+            *target_id,
+            target_type,
+            update,
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
+    Ok(())
+}
+
 #[derive(Deserialize)]
 pub struct UpdateSettingsRequest {
     pub theme: Option<String>,
@@ -174,9 +837,11 @@ pub struct UpdateSettingsRequest {
     pub message_display_compact: Option<bool>,
     pub custom_css: Option<String>,
     pub status: Option<String>,
-    pub custom_status: Option<String>,
     pub crypto_auth_enabled: Option<bool>,
+    pub show_mutuals: Option<bool>,
+    pub friends_only_profile: Option<bool>,
     pub notifications: Option<serde_json::Value>,
+    pub notification_settings: Option<Vec<NotificationSettingInput>>,
     pub keybinds: Option<serde_json::Value>,
 }
 
@@ -216,23 +881,16 @@ pub async fn update_settings(
         "cozy"
     };
 
-    if let Some(status) = body.custom_status.as_deref() {
-        if status.trim().len() > MAX_CUSTOM_STATUS_LEN {
-            return Err(ApiError::BadRequest("custom_status is too long".into()));
-        }
-        if contains_dangerous_markup(status) {
-            return Err(ApiError::BadRequest(
-                "custom_status contains unsafe markup".into(),
-            ));
-        }
-    }
-
     let custom_css = if let Some(css) = body.custom_css.as_deref() {
         sanitize_custom_css(css)?
     } else {
         existing.as_ref().and_then(|s| s.custom_css.clone())
     };
 
+    if let Some(inputs) = body.notification_settings.as_deref() {
+        apply_notification_settings(&state, auth.user_id, inputs).await?;
+    }
+
     let settings = paracord_db::users::upsert_user_settings(
         &state.db,
         auth.user_id,
@@ -241,12 +899,17 @@ pub async fn update_settings(
         message_display,
         custom_css.as_deref(),
         body.crypto_auth_enabled,
+        body.show_mutuals,
+        body.friends_only_profile,
         body.notifications.as_ref(),
         body.keybinds.as_ref(),
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
+    let custom_status =
+        paracord_core::custom_status::current_custom_status_json(&state.db, auth.user_id).await;
+
     if let Some(enabled) = body.crypto_auth_enabled {
         security::log_security_event(
             &state,
@@ -267,9 +930,12 @@ pub async fn update_settings(
         "message_display_compact": settings.message_display == "compact",
         "custom_css": settings.custom_css,
         "status": body.status.unwrap_or_else(|| "online".to_string()),
-        "custom_status": body.custom_status,
+        "custom_status": custom_status,
         "crypto_auth_enabled": settings.crypto_auth_enabled,
+        "show_mutuals": settings.show_mutuals,
+        "friends_only_profile": settings.friends_only_profile,
         "notifications": settings.notifications,
+        "notification_settings": notification_settings_json(&state, auth.user_id).await?,
         "keybinds": settings.keybinds,
     })))
 }
@@ -281,16 +947,7 @@ pub async fn get_read_states(
     let rows = paracord_db::read_states::get_user_read_states(&state.db, auth.user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let result: Vec<Value> = rows
-        .iter()
-        .map(|row| {
-            json!({
-                "channel_id": row.channel_id.to_string(),
-                "last_message_id": row.last_message_id.to_string(),
-                "mention_count": row.mention_count,
-            })
-        })
-        .collect();
+    let result = crate::routes::channels::read_states_to_json(&state, auth.user_id, &rows).await?;
     Ok(Json(json!(result)))
 }
 
@@ -336,6 +993,8 @@ pub async fn export_my_data(
             "avatar_hash": user.avatar_hash,
             "banner_hash": user.banner_hash,
             "bio": user.bio,
+            "pronouns": user.pronouns,
+            "accent_color": user.accent_color,
             "flags": user.flags,
             "created_at": user.created_at.to_rfc3339(),
             "public_key": user.public_key,
@@ -346,6 +1005,8 @@ pub async fn export_my_data(
             "message_display": s.message_display,
             "custom_css": s.custom_css,
             "crypto_auth_enabled": s.crypto_auth_enabled,
+            "show_mutuals": s.show_mutuals,
+            "friends_only_profile": s.friends_only_profile,
             "notifications": s.notifications,
             "keybinds": s.keybinds,
             "updated_at": s.updated_at.to_rfc3339(),
@@ -417,6 +1078,7 @@ pub async fn get_user_profile(
     let mutual_friends = paracord_db::users::get_mutual_friends(&state.db, auth.user_id, user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let show_mutuals = target_shows_mutuals(&state, auth.user_id, user_id).await?;
 
     // Get roles from the first mutual guild (if any) for context
     let roles: Vec<Value> = if let Some(first_guild) = mutual_guilds.first() {
@@ -432,6 +1094,7 @@ pub async fn get_user_profile(
                     "name": r.name,
                     "color": r.color,
                     "hoist": r.hoist,
+                    "icon": r.icon_hash,
                     "position": r.position,
                     "permissions": r.permissions.to_string(),
                     "mentionable": r.mentionable,
@@ -443,27 +1106,17 @@ pub async fn get_user_profile(
         vec![]
     };
 
+    let show_about = target_shows_about(&state, auth.user_id, user_id).await?;
+
     Ok(Json(json!({
-        "user": {
-            "id": user.id.to_string(),
-            "username": user.username,
-            "discriminator": user.discriminator,
-            "display_name": user.display_name,
-            "avatar_hash": user.avatar_hash,
-            "banner_hash": user.banner_hash,
-            "bio": user.bio,
-            "flags": user.flags,
-            "bot": paracord_core::is_bot(user.flags),
-            "system": false,
-            "created_at": user.created_at.to_rfc3339(),
-        },
+        "user": public_user_json(&user, show_about),
         "roles": roles,
-        "mutual_guilds": mutual_guilds.iter().map(|g| json!({
+        "mutual_guilds": mutual_guilds.iter().filter(|_| show_mutuals).map(|g| json!({
             "id": g.id.to_string(),
             "name": g.name,
             "icon_url": g.icon_hash,
         })).collect::<Vec<Value>>(),
-        "mutual_friends": mutual_friends.iter().map(|f| json!({
+        "mutual_friends": mutual_friends.iter().filter(|_| show_mutuals).map(|f| json!({
             "id": f.id.to_string(),
             "username": f.username,
             "discriminator": f.discriminator,
@@ -473,6 +1126,138 @@ pub async fn get_user_profile(
     })))
 }
 
+/// GET /api/v1/users/{user_id} — another user's public profile. Users on
+/// either side of a block are reported as not found.
+pub async fn get_user(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(user_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let user = paracord_db::users::get_user_by_id(&state.db, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    if user_id != auth.user_id
+        && paracord_db::relationships::is_blocked_either_direction(&state.db, auth.user_id, user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    {
+        return Err(ApiError::NotFound);
+    }
+    let show_about = target_shows_about(&state, auth.user_id, user_id).await?;
+    Ok(Json(public_user_json(&user, show_about)))
+}
+
+/// Whether `viewer_id` may see the bio and pronouns of `target_id`, who can
+/// keep them to friends. Users always see their own.
+async fn target_shows_about(
+    state: &AppState,
+    viewer_id: i64,
+    target_id: i64,
+) -> Result<bool, ApiError> {
+    if viewer_id == target_id {
+        return Ok(true);
+    }
+    let friends_only = paracord_db::users::get_user_settings(&state.db, target_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .is_some_and(|s| s.friends_only_profile);
+    if !friends_only {
+        return Ok(true);
+    }
+    paracord_db::relationships::are_friends(&state.db, viewer_id, target_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))
+}
+
+/// Whether `target_id` lets `viewer_id` see the friends and spaces they share.
+/// Users always see their own.
+async fn target_shows_mutuals(
+    state: &AppState,
+    viewer_id: i64,
+    target_id: i64,
+) -> Result<bool, ApiError> {
+    if viewer_id == target_id {
+        return Ok(true);
+    }
+    let settings = paracord_db::users::get_user_settings(&state.db, target_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(settings.map(|s| s.show_mutuals).unwrap_or(true))
+}
+
+/// Resolve whether mutuals with `target_id` can be listed at all. Unknown
+/// users and users on either side of a block are reported as not found.
+async fn require_mutuals_target(
+    state: &AppState,
+    viewer_id: i64,
+    target_id: i64,
+) -> Result<bool, ApiError> {
+    paracord_db::users::get_user_by_id(&state.db, target_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let blocked =
+        paracord_db::relationships::is_blocked_either_direction(&state.db, viewer_id, target_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if blocked {
+        return Err(ApiError::NotFound);
+    }
+    target_shows_mutuals(state, viewer_id, target_id).await
+}
+
+pub async fn get_mutual_friends(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(user_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    if !require_mutuals_target(&state, auth.user_id, user_id).await? {
+        return Ok(Json(json!([])));
+    }
+    let friends = paracord_db::users::get_mutual_friends(&state.db, auth.user_id, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let result: Vec<Value> = friends
+        .iter()
+        .map(|f| {
+            json!({
+                "id": f.id.to_string(),
+                "username": f.username,
+                "discriminator": f.discriminator,
+                "avatar_hash": f.avatar_hash,
+            })
+        })
+        .collect();
+    Ok(Json(json!(result)))
+}
+
+pub async fn get_mutual_spaces(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(user_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    if !require_mutuals_target(&state, auth.user_id, user_id).await? {
+        return Ok(Json(json!([])));
+    }
+    let spaces = paracord_db::users::get_mutual_guilds(&state.db, auth.user_id, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let result: Vec<Value> = spaces
+        .iter()
+        .map(|g| {
+            json!({
+                "id": g.id.to_string(),
+                "name": g.name,
+                "icon_hash": g.icon_hash,
+            })
+        })
+        .collect();
+    Ok(Json(json!(result)))
+}
+
 pub async fn delete_me(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -500,6 +1285,7 @@ pub async fn delete_me(
     .await;
 
     paracord_core::admin::admin_delete_user(&state.db, auth.user_id).await?;
+    paracord_core::permissions::invalidate_user(&state.permission_cache, auth.user_id).await;
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -520,9 +1306,7 @@ pub async fn change_password(
             "new_password must differ from current_password".into(),
         ));
     }
-    paracord_util::validation::validate_password(&body.new_password).map_err(|_| {
-        ApiError::BadRequest("Password must be between 10 and 128 characters".into())
-    })?;
+    crate::routes::auth::enforce_password_policy(&state, &body.new_password).await?;
 
     let user = paracord_db::users::get_user_auth_by_id(&state.db, auth.user_id)
         .await
@@ -582,7 +1366,7 @@ pub async fn change_email(
 ) -> Result<StatusCode, ApiError> {
     let normalized_email = body.new_email.trim().to_ascii_lowercase();
     paracord_util::validation::validate_email(&normalized_email)
-        .map_err(|_| ApiError::BadRequest("Invalid email address".into()))?;
+        .map_err(|_| ApiError::invalid_field("new_email", "Invalid email address"))?;
 
     let user = paracord_db::users::get_user_auth_by_id(&state.db, auth.user_id)
         .await
@@ -162,15 +162,8 @@ fn verify_livekit_webhook_auth(
     if let Some(expected_hash) = decoded.claims.sha256.as_deref() {
         let mut hasher = Sha256::new();
         hasher.update(body);
-        let digest = hasher.finalize();
-        let actual_hash = digest
-            .iter()
-            .fold(String::with_capacity(64), |mut out, byte| {
-                use std::fmt::Write;
-                let _ = write!(out, "{:02x}", byte);
-                out
-            });
-        if actual_hash != expected_hash {
+        let actual_hash = paracord_util::hex::hex_encode(&hasher.finalize());
+        if !paracord_util::hex::hex_eq_ct(&actual_hash, expected_hash) {
             return Err(ApiError::Unauthorized);
         }
     }
@@ -12,6 +12,18 @@ use serde_json::{json, Value};
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
 
+const MAX_WEBHOOK_NAME_LEN: usize = 80;
+const MAX_WEBHOOK_AVATAR_URL_LEN: usize = 2048;
+
+fn contains_dangerous_markup(value: &str) -> bool {
+    let lower = value.to_ascii_lowercase();
+    lower.contains("<script")
+        || lower.contains("javascript:")
+        || lower.contains("onerror=")
+        || lower.contains("onload=")
+        || lower.contains("<iframe")
+}
+
 fn webhook_to_json(w: &paracord_db::webhooks::WebhookRow, token: Option<&str>) -> Value {
     let mut v = json!({
         "id": w.id.to_string(),
@@ -39,11 +51,13 @@ async fn require_manage_webhooks(
 
     paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
 
-    let roles = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let perms =
-        paracord_core::permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
+    let perms = paracord_core::permissions::compute_guild_permissions(
+        &state.db,
+        guild_id,
+        guild.owner_id,
+        user_id,
+    )
+    .await?;
     paracord_core::permissions::require_permission(perms, Permissions::MANAGE_WEBHOOKS)?;
     Ok(())
 }
@@ -62,13 +76,6 @@ pub async fn create_webhook(
 ) -> Result<(StatusCode, Json<Value>), ApiError> {
     require_manage_webhooks(&state, guild_id, auth.user_id).await?;
 
-    let name = body.name.trim();
-    if name.is_empty() || name.len() > 80 {
-        return Err(ApiError::BadRequest(
-            "Webhook name must be between 1 and 80 characters".into(),
-        ));
-    }
-
     // Determine target channel: either from body or first text channel in guild
     let channel_id = if let Some(ref raw) = body.channel_id {
         raw.parse::<i64>()
@@ -98,17 +105,51 @@ pub async fn create_webhook(
         ));
     }
 
-    let id = paracord_util::snowflake::generate(1);
+    insert_webhook(&state, guild_id, channel_id, &body.name, auth.user_id).await
+}
+
+#[derive(Deserialize)]
+pub struct CreateChannelWebhookRequest {
+    pub name: String,
+}
+
+pub async fn create_channel_webhook(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+    Json(body): Json<CreateChannelWebhookRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let guild_id = channel.guild_id().ok_or(ApiError::BadRequest(
+        "Webhooks can only be created in guild channels".into(),
+    ))?;
+    require_manage_webhooks(&state, guild_id, auth.user_id).await?;
+
+    insert_webhook(&state, guild_id, channel_id, &body.name, auth.user_id).await
+}
+
+async fn insert_webhook(
+    state: &AppState,
+    guild_id: i64,
+    channel_id: i64,
+    name: &str,
+    creator_id: i64,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > MAX_WEBHOOK_NAME_LEN {
+        return Err(ApiError::BadRequest(
+            "Webhook name must be between 1 and 80 characters".into(),
+        ));
+    }
+
+    let id = paracord_util::snowflake::generate();
     let token = generate_webhook_token();
 
     let webhook = paracord_db::webhooks::create_webhook(
-        &state.db,
-        id,
-        guild_id,
-        channel_id,
-        name,
-        &token,
-        auth.user_id,
+        &state.db, id, guild_id, channel_id, name, &token, creator_id,
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -191,7 +232,7 @@ pub async fn update_webhook(
 
     if let Some(ref name) = body.name {
         let trimmed = name.trim();
-        if trimmed.is_empty() || trimmed.len() > 80 {
+        if trimmed.is_empty() || trimmed.len() > MAX_WEBHOOK_NAME_LEN {
             return Err(ApiError::BadRequest(
                 "Webhook name must be between 1 and 80 characters".into(),
             ));
@@ -380,6 +421,37 @@ fn format_github_event(event_type: &str, payload: &Value) -> String {
     }
 }
 
+fn validate_username_override(raw: Option<String>) -> Result<Option<String>, ApiError> {
+    let Some(raw) = raw else {
+        return Ok(None);
+    };
+    let name = raw.trim();
+    if name.is_empty() || name.len() > MAX_WEBHOOK_NAME_LEN || contains_dangerous_markup(name) {
+        return Err(ApiError::invalid_field(
+            "username",
+            "Username override must be between 1 and 80 characters",
+        ));
+    }
+    Ok(Some(name.to_string()))
+}
+
+fn validate_avatar_url_override(raw: Option<String>) -> Result<Option<String>, ApiError> {
+    let Some(raw) = raw else {
+        return Ok(None);
+    };
+    let url = raw.trim();
+    let lower = url.to_ascii_lowercase();
+    if url.len() > MAX_WEBHOOK_AVATAR_URL_LEN
+        || !(lower.starts_with("https://") || lower.starts_with("http://"))
+        || url.chars().any(|c| c.is_whitespace() || c.is_control())
+    {
+        return Err(ApiError::BadRequest(
+            "Avatar URL override must be an http(s) URL".into(),
+        ));
+    }
+    Ok(Some(url.to_string()))
+}
+
 /// Execute a webhook - no auth required, uses token in path.
 pub async fn execute_webhook(
     State(state): State<AppState>,
@@ -392,32 +464,44 @@ pub async fn execute_webhook(
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .ok_or(ApiError::NotFound)?;
 
+    paracord_core::rate_limit::webhooks()
+        .try_execute(
+            webhook.id,
+            paracord_core::rate_limit::WEBHOOK_EXECUTE_LIMIT,
+            chrono::Utc::now().timestamp_millis(),
+        )
+        .map_err(|_| ApiError::RateLimited)?;
+
     // Check for GitHub webhook
-    let (content, display_name) = if let Some(github_event) = headers.get("X-GitHub-Event") {
-        let event_type = github_event.to_str().unwrap_or("unknown");
-        let payload: Value = serde_json::from_slice(&body)
-            .map_err(|_| ApiError::BadRequest("Invalid JSON payload".into()))?;
-        let content = format_github_event(event_type, &payload);
-        (content, "GitHub".to_string())
-    } else {
-        // Normal webhook execution
-        let req: ExecuteWebhookRequest = serde_json::from_slice(&body)
-            .map_err(|_| ApiError::BadRequest("Invalid JSON payload".into()))?;
-        let content = req.content.trim().to_string();
-        if content.is_empty() {
-            return Err(ApiError::BadRequest("Content must not be empty".into()));
-        }
-        if content.len() > 2000 {
-            return Err(ApiError::BadRequest(
-                "Content must be 2000 characters or fewer".into(),
-            ));
-        }
-        let name = req.username.unwrap_or_else(|| webhook.name.clone());
-        (content, name)
-    };
+    let (content, display_name, avatar_url) =
+        if let Some(github_event) = headers.get("X-GitHub-Event") {
+            let event_type = github_event.to_str().unwrap_or("unknown");
+            let payload: Value = serde_json::from_slice(&body)
+                .map_err(|_| ApiError::BadRequest("Invalid JSON payload".into()))?;
+            let content = format_github_event(event_type, &payload);
+            (content, "GitHub".to_string(), None)
+        } else {
+            // Normal webhook execution
+            let req: ExecuteWebhookRequest = serde_json::from_slice(&body)
+                .map_err(|_| ApiError::BadRequest("Invalid JSON payload".into()))?;
+            let content = req.content.trim().to_string();
+            if content.is_empty() {
+                return Err(ApiError::BadRequest("Content must not be empty".into()));
+            }
+            crate::routes::channels::validate_content_length(&state, &content)?;
+            if contains_dangerous_markup(&content) {
+                return Err(ApiError::BadRequest(
+                    "Content contains disallowed markup".into(),
+                ));
+            }
+            let name =
+                validate_username_override(req.username)?.unwrap_or_else(|| webhook.name.clone());
+            let avatar_url = validate_avatar_url_override(req.avatar_url)?;
+            (content, name, avatar_url)
+        };
 
     // Create the message using the webhook creator as the author
-    let msg_id = paracord_util::snowflake::generate(1);
+    let msg_id = paracord_util::snowflake::generate();
     let author_id = webhook.creator_id.unwrap_or(0);
 
     let msg = paracord_db::messages::create_message(
@@ -437,7 +521,19 @@ pub async fn execute_webhook(
         .ok()
         .flatten();
     let guild_id = channel.and_then(|c| c.guild_id());
+    if let Some(guild_id) = guild_id {
+        // Webhooks stand in for integrations, which may ping everyone.
+        if let Err(e) = paracord_core::mentions::store_message_mentions(
+            &state.db, msg.id, guild_id, &content, true,
+        )
+        .await
+        {
+            tracing::warn!("Failed to store mentions for message {}: {}", msg.id, e);
+        }
+    }
 
+    let entities =
+        paracord_core::entities::parse_entities(msg.content.as_deref().unwrap_or_default());
     let msg_json = json!({
         "id": msg.id.to_string(),
         "channel_id": msg.channel_id.to_string(),
@@ -446,9 +542,11 @@ pub async fn execute_webhook(
             "username": display_name,
             "discriminator": 0,
             "avatar_hash": null,
+            "avatar_url": avatar_url,
             "bot": true,
         },
         "content": msg.content,
+        "entities": entities,
         "pinned": msg.pinned,
         "type": msg.message_type,
         "message_type": msg.message_type,
@@ -465,6 +563,12 @@ pub async fn execute_webhook(
     state
         .event_bus
         .dispatch("MESSAGE_CREATE", msg_json.clone(), guild_id);
+    state.push.message_created(paracord_core::push::PushJob {
+        message_id: msg.id,
+        channel_id: msg.channel_id,
+        guild_id,
+        author_id,
+    });
 
     Ok((StatusCode::CREATED, Json(msg_json)))
 }
@@ -473,9 +577,5 @@ fn generate_webhook_token() -> String {
     use rand::RngCore;
     let mut bytes = [0_u8; 32];
     rand::rngs::OsRng.fill_bytes(&mut bytes);
-    let mut out = String::with_capacity(bytes.len() * 2);
-    for b in bytes {
-        out.push_str(&format!("{:02x}", b));
-    }
-    out
+    paracord_util::hex::hex_encode(&bytes)
 }
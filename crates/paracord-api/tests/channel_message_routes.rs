@@ -8,10 +8,12 @@ use axum::{
     Router,
 };
 use chrono::{Duration, Utc};
+use paracord_core::auth::JwtKeySet;
 use paracord_core::{build_permission_cache, AppConfig, AppState, RuntimeSettings};
 use paracord_media::{
     LiveKitConfig, LocalStorage, Storage, StorageConfig, StorageManager, VoiceManager,
 };
+use paracord_models::permissions::Permissions;
 use serde_json::{json, Value};
 use tempfile::TempDir;
 use tokio::sync::{Notify, RwLock};
@@ -20,6 +22,9 @@ use uuid::Uuid;
 
 struct TestContext {
     app: Router,
+    db: paracord_db::DbPool,
+    state: AppState,
+    jwt_keys: JwtKeySet,
     token: String,
     _storage_dir: TempDir,
     _media_dir: TempDir,
@@ -35,6 +40,7 @@ impl TestContext {
         let media_dir = tempfile::tempdir()?;
         let backup_dir = tempfile::tempdir()?;
         let jwt_secret = "integration-test-secret".to_string();
+        let jwt_keys = JwtKeySet::from_secret(&jwt_secret);
 
         let livekit = Arc::new(LiveKitConfig {
             api_key: "lk-test-key".to_string(),
@@ -48,10 +54,13 @@ impl TestContext {
             event_bus: paracord_core::events::EventBus::default(),
             config: AppConfig {
                 jwt_secret: jwt_secret.clone(),
+                jwt_keys: jwt_keys.clone(),
                 jwt_expiry_seconds: 3600,
                 registration_enabled: true,
                 allow_username_login: false,
                 require_email: true,
+                password_policy: Default::default(),
+                breached_password_api_url: None,
                 storage_path: storage_dir.path().to_string_lossy().into_owned(),
                 max_upload_size: 10 * 1024 * 1024,
                 livekit_api_key: livekit.api_key.clone(),
@@ -77,6 +86,17 @@ impl TestContext {
                 federation_file_cache_enabled: false,
                 federation_file_cache_max_size: 0,
                 federation_file_cache_ttl_hours: 0,
+                audit_log_retention_days: None,
+                security_event_retention_days: None,
+                http_rate_limits: paracord_core::rate_limit::HttpRateLimits::default(),
+                inline_content_types: vec!["image/png".to_string()],
+                message_edit_history_limit: 25,
+                slowmode_exempt_bots: true,
+                max_pins_per_channel: 50,
+                max_message_length: 4000,
+                max_attachments_per_message: 10,
+                max_static_emojis: 50,
+                max_animated_emojis: 50,
             },
             runtime: Arc::new(RwLock::new(RuntimeSettings::default())),
             voice: Arc::new(VoiceManager::new(livekit)),
@@ -93,15 +113,24 @@ impl TestContext {
             permission_cache: build_permission_cache(),
             federation_service: None,
             member_index: Arc::new(paracord_core::member_index::MemberIndex::empty()),
+            presence_manager: Arc::new(paracord_core::presence_manager::PresenceManager::new()),
             native_media: None,
+            mailer: paracord_mail::Mailer::disabled(),
+            push: paracord_core::push::PushDispatcher::disabled(),
+            link_previews: paracord_core::link_preview::LinkPreviewer::disabled(),
         };
 
-        paracord_api::install_http_rate_limiter();
-        let app = paracord_api::build_router().with_state(state);
-        let token = create_authenticated_user_token(&db, &jwt_secret).await?;
+        paracord_api::install_http_rate_limiter(
+            paracord_core::rate_limit::HttpRateLimits::default(),
+        );
+        let app = paracord_api::build_router().with_state(state.clone());
+        let token = create_authenticated_user_token(&db, &jwt_keys).await?;
 
         Ok(Self {
             app,
+            db,
+            state,
+            jwt_keys,
             token,
             _storage_dir: storage_dir,
             _media_dir: media_dir,
@@ -114,11 +143,32 @@ impl TestContext {
         method: Method,
         path: &str,
         body: Option<Value>,
+    ) -> anyhow::Result<(StatusCode, Value)> {
+        self.request_json_as(&self.token, method, path, body).await
+    }
+
+    async fn request_json_as(
+        &self,
+        token: &str,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> anyhow::Result<(StatusCode, Value)> {
+        self.request_json_with_authorization(&format!("Bearer {token}"), method, path, body)
+            .await
+    }
+
+    async fn request_json_with_authorization(
+        &self,
+        authorization: &str,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
     ) -> anyhow::Result<(StatusCode, Value)> {
         let mut builder = Request::builder()
             .method(method)
             .uri(path)
-            .header(header::AUTHORIZATION, format!("Bearer {}", self.token));
+            .header(header::AUTHORIZATION, authorization);
 
         let request = if let Some(payload) = body {
             builder = builder.header(header::CONTENT_TYPE, "application/json");
@@ -143,9 +193,9 @@ impl TestContext {
 
 async fn create_authenticated_user_token(
     db: &paracord_db::DbPool,
-    jwt_secret: &str,
+    jwt_keys: &JwtKeySet,
 ) -> anyhow::Result<String> {
-    let user_id = paracord_util::snowflake::generate(1);
+    let user_id = paracord_util::snowflake::generate();
     let nonce = Uuid::new_v4().simple().to_string();
     let username = format!("integration_{nonce}");
     let email = format!("{nonce}@example.com");
@@ -174,7 +224,7 @@ async fn create_authenticated_user_token(
     let token = paracord_core::auth::create_session_token(
         user.id,
         user.public_key.as_deref(),
-        jwt_secret,
+        jwt_keys,
         3600,
         &session_id,
         &jti,
@@ -183,6 +233,26 @@ async fn create_authenticated_user_token(
     Ok(token)
 }
 
+async fn upload_chunk(
+    ctx: &TestContext,
+    path: &str,
+    content_range: &str,
+    chunk: &[u8],
+) -> anyhow::Result<(StatusCode, Value)> {
+    let request = Request::builder()
+        .method(Method::PATCH)
+        .uri(path)
+        .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::CONTENT_RANGE, content_range)
+        .body(Body::from(chunk.to_vec()))?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    let status = response.status();
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await?;
+    let payload = serde_json::from_slice(&body_bytes).unwrap_or(Value::Null);
+    Ok((status, payload))
+}
+
 async fn create_guild(ctx: &TestContext, name: &str) -> anyhow::Result<String> {
     let (status, payload) = ctx
         .request_json(
@@ -307,6 +377,103 @@ async fn channel_crud_routes_work() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn channel_and_overwrite_audit_entries_record_field_diffs() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Audit Diff Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "general").await?;
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/channels/{channel_id}"),
+            Some(json!({ "name": "lobby" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    // A no-op edit records nothing.
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/channels/{channel_id}"),
+            Some(json!({ "name": "lobby" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let overwrite_path = format!("/api/v1/channels/{channel_id}/overwrites/{guild_id}");
+    for deny in [Permissions::SEND_MESSAGES, Permissions::ATTACH_FILES] {
+        let (status, _) = ctx
+            .request_json(
+                Method::PUT,
+                &overwrite_path,
+                Some(json!({ "target_type": 0, "allow_perms": 0, "deny_perms": deny.bits() })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+    let (status, _) = ctx
+        .request_json(Method::DELETE, &overwrite_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, audit) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/audit-logs"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let entries = audit["audit_log_entries"]
+        .as_array()
+        .context("audit log entries should be an array")?;
+    let entries_for = |action: &str| -> Vec<&Value> {
+        entries
+            .iter()
+            .filter(|entry| entry["action"] == action && entry["target_id"] == channel_id)
+            .collect()
+    };
+
+    let created = entries_for("channel_create");
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0]["changes"]["name"], json!({ "new": "general" }));
+
+    let updated = entries_for("channel_update");
+    assert_eq!(updated.len(), 1, "no-op edits should not be logged");
+    assert_eq!(
+        updated[0]["changes"],
+        json!({ "name": { "old": "general", "new": "lobby" } })
+    );
+
+    let overwrite_created = entries_for("channel_overwrite_create");
+    assert_eq!(overwrite_created.len(), 1);
+    assert_eq!(
+        overwrite_created[0]["changes"]["deny_perms"],
+        json!({ "new": Permissions::SEND_MESSAGES.bits() })
+    );
+    let overwrite_updated = entries_for("channel_overwrite_update");
+    assert_eq!(overwrite_updated.len(), 1);
+    let changes = &overwrite_updated[0]["changes"];
+    assert_eq!(
+        changes["deny_perms"],
+        json!({
+            "old": Permissions::SEND_MESSAGES.bits(),
+            "new": Permissions::ATTACH_FILES.bits(),
+        })
+    );
+    assert!(changes.get("allow_perms").is_none());
+    assert_eq!(changes["target_id"]["new"], guild_id);
+    let overwrite_deleted = entries_for("channel_overwrite_delete");
+    assert_eq!(overwrite_deleted.len(), 1);
+    assert_eq!(
+        overwrite_deleted[0]["changes"]["deny_perms"],
+        json!({ "old": Permissions::ATTACH_FILES.bits() })
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn message_crud_routes_work() -> anyhow::Result<()> {
     let ctx = TestContext::new().await?;
@@ -335,6 +502,27 @@ async fn message_crud_routes_work() -> anyhow::Result<()> {
         .await?;
     assert_eq!(status, StatusCode::OK, "unexpected PATCH payload: {edited}");
     assert_eq!(edited["content"], "edited body");
+    assert_eq!(edited["edited_count"], 1);
+    assert!(edited["last_edited_at"].is_string());
+
+    let (status, history) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/channels/{channel_id}/messages/{message_id}/history"),
+            None,
+        )
+        .await?;
+    assert_eq!(
+        status,
+        StatusCode::OK,
+        "unexpected history payload: {history}"
+    );
+    assert_eq!(history["content"], "edited body");
+    let revisions = history["revisions"]
+        .as_array()
+        .context("revisions should be an array")?;
+    assert_eq!(revisions.len(), 1);
+    assert_eq!(revisions[0]["content"], "original body");
 
     let (status, messages) = ctx
         .request_json(
@@ -378,6 +566,96 @@ async fn message_crud_routes_work() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn message_history_pages_in_either_direction_newest_first() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Pagination Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "history").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+
+    let mut ids = Vec::new();
+    for i in 0..7 {
+        let (status, message) = ctx
+            .request_json(
+                Method::POST,
+                &messages_path,
+                Some(json!({ "content": format!("message {i}") })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+        ids.push(message["id"].as_str().context("message id")?.to_string());
+    }
+    let page_ids = |page: &Value| -> Vec<String> {
+        page.as_array()
+            .map(|messages| {
+                messages
+                    .iter()
+                    .filter_map(|m| m["id"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let newest_first = |ids: &[String]| ids.iter().rev().cloned().collect::<Vec<_>>();
+
+    let (status, page) = ctx
+        .request_json(
+            Method::GET,
+            &format!("{messages_path}?after={}&limit=2", ids[1]),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page_ids(&page), newest_first(&ids[2..4]));
+
+    let (status, page) = ctx
+        .request_json(
+            Method::GET,
+            &format!("{messages_path}?before={}&limit=2", ids[3]),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page_ids(&page), newest_first(&ids[1..3]));
+
+    let (status, page) = ctx
+        .request_json(
+            Method::GET,
+            &format!("{messages_path}?around={}&limit=4", ids[3]),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page_ids(&page), newest_first(&ids[2..6]));
+
+    // Around the newest message the page is filled with older history.
+    let (status, page) = ctx
+        .request_json(
+            Method::GET,
+            &format!("{messages_path}?around={}&limit=3", ids[6]),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page_ids(&page), newest_first(&ids[4..7]));
+
+    let (status, page) = ctx
+        .request_json(Method::GET, &format!("{messages_path}?limit=1000"), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page_ids(&page).len(), ids.len());
+
+    let (status, _) = ctx
+        .request_json(
+            Method::GET,
+            &format!("{messages_path}?before={}&after={}", ids[5], ids[1]),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn thread_routes_work() -> anyhow::Result<()> {
     let ctx = TestContext::new().await?;
@@ -453,3 +731,4583 @@ async fn thread_routes_work() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn message_threads_track_members_inherit_permissions_and_reopen_on_post() -> anyhow::Result<()>
+{
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Message Thread Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "thread-parent").await?;
+
+    let (status, message) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            Some(json!({ "content": "start a thread here" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {message}");
+    let message_id = message["id"].as_str().context("message id")?.to_string();
+    let threads_path = format!("/api/v1/channels/{channel_id}/messages/{message_id}/threads");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &threads_path,
+            Some(json!({ "name": "bad", "auto_archive_duration": 5 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, thread) = ctx
+        .request_json(
+            Method::POST,
+            &threads_path,
+            Some(json!({ "name": "discussion", "auto_archive_duration": 60 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {thread}");
+    assert_eq!(thread["thread_metadata"]["starter_message_id"], message_id);
+    let thread_id = thread["id"].as_str().context("thread id")?.to_string();
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &threads_path,
+            Some(json!({ "name": "again" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, member_id, guild_id.parse()?).await?;
+
+    let members_path = format!("/api/v1/channels/{thread_id}/thread-members");
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::PUT,
+            &format!("{members_path}/@me"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, members) = ctx.request_json(Method::GET, &members_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(members.as_array().map(Vec::len), Some(2));
+
+    // Archived threads reopen when someone posts in them.
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/channels/{channel_id}/threads/{thread_id}"),
+            Some(json!({ "archived": true })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::POST,
+            &format!("/api/v1/channels/{thread_id}/messages"),
+            Some(json!({ "content": "bumping this" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, reopened) = ctx
+        .request_json(Method::GET, &format!("/api/v1/channels/{thread_id}"), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(reopened["thread_metadata"]["archived"], false);
+
+    // Hiding the parent channel hides its threads too.
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &format!("/api/v1/channels/{channel_id}/overwrites/{member_id}"),
+            Some(json!({
+                "target_type": 1,
+                "allow_perms": 0,
+                "deny_perms": Permissions::VIEW_CHANNEL.bits(),
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = ctx
+        .request_json_as(&member_token, Method::GET, &members_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::DELETE,
+            &format!("{members_path}/@me"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, members) = ctx.request_json(Method::GET, &members_path, None).await?;
+    assert_eq!(members.as_array().map(Vec::len), Some(1));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn chunked_upload_resumes_from_committed_offset() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Chunked Upload Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "uploads").await?;
+    let contents: Vec<u8> = (0..300u32).map(|i| (i % 251) as u8).collect();
+
+    let (status, session) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/attachments/init"),
+            Some(json!({ "filename": "data.bin", "size": contents.len() })),
+        )
+        .await?;
+    assert_eq!(
+        status,
+        StatusCode::CREATED,
+        "unexpected init payload: {session}"
+    );
+    assert_eq!(session["offset"], 0);
+    let session_id = session["id"]
+        .as_str()
+        .context("session id should be a string")?
+        .to_string();
+    let session_path = format!("/api/v1/channels/{channel_id}/attachments/{session_id}");
+
+    let (status, progress) =
+        upload_chunk(&ctx, &session_path, "bytes 0-99/300", &contents[..100]).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(progress["offset"], 100);
+
+    // A client that lost the response and retries the first chunk learns the
+    // committed offset instead of duplicating bytes.
+    let (status, _) = upload_chunk(&ctx, &session_path, "bytes 0-99/300", &contents[..100]).await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, resumed) = ctx.request_json(Method::GET, &session_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(resumed["offset"], 100);
+
+    // Completing early is rejected.
+    let (status, _) = ctx
+        .request_json(Method::POST, &format!("{session_path}/complete"), None)
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Chunks cannot push the upload past its declared size.
+    let oversized = vec![0u8; 201];
+    let (status, _) = upload_chunk(&ctx, &session_path, "bytes 100-300/*", &oversized).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    // A range whose length does not fit in a u64 is rejected, not wrapped.
+    let range = format!("bytes 0-{}/*", u64::MAX);
+    let (status, _) = upload_chunk(&ctx, &session_path, &range, &contents[100..]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, progress) =
+        upload_chunk(&ctx, &session_path, "bytes 100-299/300", &contents[100..]).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(progress["offset"], 300);
+
+    let (status, attachment) = ctx
+        .request_json(Method::POST, &format!("{session_path}/complete"), None)
+        .await?;
+    assert_eq!(
+        status,
+        StatusCode::CREATED,
+        "unexpected attachment: {attachment}"
+    );
+    assert_eq!(attachment["filename"], "data.bin");
+    assert_eq!(attachment["size"], 300);
+
+    let (status, _) = ctx.request_json(Method::GET, &session_path, None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn attachment_download_supports_range_requests() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Range Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "ranges").await?;
+    let contents: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+
+    let (status, session) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/attachments/init"),
+            Some(json!({ "filename": "video.bin", "size": contents.len() })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let session_path = format!(
+        "/api/v1/channels/{channel_id}/attachments/{}",
+        session["id"].as_str().context("session id")?
+    );
+    let (status, _) = upload_chunk(&ctx, &session_path, "bytes 0-999/1000", &contents).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, attachment) = ctx
+        .request_json(Method::POST, &format!("{session_path}/complete"), None)
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let attachment_id = attachment["id"]
+        .as_str()
+        .context("attachment id")?
+        .to_string();
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            Some(json!({ "content": "clip", "attachment_ids": [attachment_id] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let download = |range: Option<&'static str>| {
+        let mut builder = Request::builder()
+            .method(Method::GET)
+            .uri(format!("/api/v1/attachments/{attachment_id}"))
+            .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token));
+        if let Some(range) = range {
+            builder = builder.header(header::RANGE, range);
+        }
+        let request = builder.body(Body::empty()).expect("request");
+        ctx.app.clone().oneshot(request)
+    };
+
+    let response = download(None).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+    let etag = response.headers()[header::ETAG].clone();
+    assert_eq!(to_bytes(response.into_body(), usize::MAX).await?, contents);
+
+    let response = download(Some("bytes=100-199")).await?;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        response.headers()[header::CONTENT_RANGE],
+        "bytes 100-199/1000"
+    );
+    assert_eq!(
+        to_bytes(response.into_body(), usize::MAX).await?,
+        &contents[100..200]
+    );
+
+    let response = download(Some("bytes=900-")).await?;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        response.headers()[header::CONTENT_RANGE],
+        "bytes 900-999/1000"
+    );
+    assert_eq!(
+        to_bytes(response.into_body(), usize::MAX).await?,
+        &contents[900..]
+    );
+
+    let response = download(Some("bytes=0-1,5-6")).await?;
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes */1000");
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/api/v1/attachments/{attachment_id}"))
+        .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+        .header(header::IF_NONE_MATCH, etag)
+        .body(Body::empty())?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn deleting_a_channel_removes_its_messages_and_attachment_files() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Cleanup Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "doomed").await?;
+    let contents = b"attachment body".to_vec();
+
+    let (status, session) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/attachments/init"),
+            Some(json!({ "filename": "notes.bin", "size": contents.len() })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let session_path = format!(
+        "/api/v1/channels/{channel_id}/attachments/{}",
+        session["id"].as_str().context("session id")?
+    );
+    let range = format!("bytes 0-{}/{}", contents.len() - 1, contents.len());
+    let (status, _) = upload_chunk(&ctx, &session_path, &range, &contents).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, attachment) = ctx
+        .request_json(Method::POST, &format!("{session_path}/complete"), None)
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let attachment_id = attachment["id"]
+        .as_str()
+        .context("attachment id")?
+        .to_string();
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            Some(json!({ "content": "see attached", "attachment_ids": [attachment_id] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let storage_key = format!("attachments/{attachment_id}.bin");
+    assert!(ctx.state.storage_backend.exists(&storage_key).await?);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::DELETE,
+            &format!("/api/v1/channels/{channel_id}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    assert!(!ctx.state.storage_backend.exists(&storage_key).await?);
+    assert!(
+        paracord_db::channels::get_channel(&ctx.db, channel_id.parse()?)
+            .await?
+            .is_none()
+    );
+    assert!(
+        paracord_db::attachments::get_attachment(&ctx.db, attachment_id.parse()?)
+            .await?
+            .is_none()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn channel_export_streams_history_oldest_first_to_guild_managers() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Archive Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "history").await?;
+    for content in ["first", "second", "third"] {
+        let (status, _) = ctx
+            .request_json(
+                Method::POST,
+                &format!("/api/v1/channels/{channel_id}/messages"),
+                Some(json!({ "content": content })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    let export = |token: String| {
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!("/api/v1/channels/{channel_id}/export"))
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .body(Body::empty())
+            .expect("request");
+        ctx.app.clone().oneshot(request)
+    };
+
+    let response = export(ctx.token.clone()).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "application/x-ndjson"
+    );
+    let body = to_bytes(response.into_body(), usize::MAX).await?;
+    let lines: Vec<Value> = std::str::from_utf8(&body)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    let contents: Vec<&str> = lines
+        .iter()
+        .filter_map(|line| line["content"].as_str())
+        .collect();
+    assert_eq!(contents, vec!["first", "second", "third"]);
+    assert!(lines[0]["author"]["username"].is_string());
+    assert!(lines[0]["attachments"].as_array().is_some());
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, member_id, guild_id.parse()?).await?;
+    let response = export(member_token.clone()).await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // MANAGE_GUILD alone is not enough if the channel is hidden from them.
+    let space_id: i64 = guild_id.parse()?;
+    let role_id = paracord_util::snowflake::generate();
+    paracord_db::roles::create_role(
+        &ctx.db,
+        role_id,
+        space_id,
+        "Manager",
+        (Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY | Permissions::MANAGE_GUILD)
+            .bits(),
+    )
+    .await?;
+    paracord_db::roles::add_member_role(&ctx.db, member_id, space_id, role_id).await?;
+    let response = export(member_token.clone()).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    paracord_db::channel_overwrites::upsert_channel_overwrite(
+        &ctx.db,
+        channel_id.parse()?,
+        member_id,
+        1,
+        0,
+        Permissions::READ_MESSAGE_HISTORY.bits(),
+    )
+    .await?;
+    let response = export(member_token).await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // DMs can't be exported, even by a server admin.
+    let owner_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+    paracord_db::users::update_user_flags(&ctx.db, owner_id, paracord_core::USER_FLAG_ADMIN)
+        .await?;
+    let dm = paracord_db::dms::create_dm_channel(
+        &ctx.db,
+        paracord_util::snowflake::generate(),
+        owner_id,
+        member_id,
+    )
+    .await?;
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/api/v1/channels/{}/export", dm.id))
+        .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+        .body(Body::empty())?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn image_uploads_record_dimensions_and_serve_thumbnails() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Thumbnail Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "images").await?;
+
+    let mut png = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::new_rgb8(1600, 800).write_to(&mut png, image::ImageFormat::Png)?;
+    let png = png.into_inner();
+
+    let (status, session) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/attachments/init"),
+            Some(json!({ "filename": "wide.png", "size": png.len(), "content_type": "image/png" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let session_path = format!(
+        "/api/v1/channels/{channel_id}/attachments/{}",
+        session["id"].as_str().context("session id")?
+    );
+    let range = format!("bytes 0-{}/{}", png.len() - 1, png.len());
+    let (status, _) = upload_chunk(&ctx, &session_path, &range, &png).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, attachment) = ctx
+        .request_json(Method::POST, &format!("{session_path}/complete"), None)
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(attachment["width"], 1600);
+    assert_eq!(attachment["height"], 800);
+    let attachment_id = attachment["id"]
+        .as_str()
+        .context("attachment id")?
+        .to_string();
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            Some(json!({ "content": "pic", "attachment_ids": [attachment_id] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/api/v1/attachments/{attachment_id}?size=thumb"))
+        .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+        .body(Body::empty())?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+    let thumb = to_bytes(response.into_body(), usize::MAX).await?;
+    assert!(thumb.len() < png.len());
+    let thumb = image::load_from_memory(&thumb)?;
+    assert_eq!((thumb.width(), thumb.height()), (400, 200));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn channel_message_retention_can_be_set_and_cleared() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Retention Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "support").await?;
+    let channel_path = format!("/api/v1/channels/{channel_id}");
+
+    let (status, updated) = ctx
+        .request_json(
+            Method::PATCH,
+            &channel_path,
+            Some(json!({ "message_retention_days": 30 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {updated}");
+    assert_eq!(updated["message_retention_days"], 30);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &channel_path,
+            Some(json!({ "message_retention_days": 100_000 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, updated) = ctx
+        .request_json(
+            Method::PATCH,
+            &channel_path,
+            Some(json!({ "message_retention_days": 0 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(updated["message_retention_days"].is_null());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn overview_lists_visible_channels_read_states_and_dms_in_pages() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Overview Guild").await?;
+    let second_guild_id = create_guild(&ctx, "Second Guild").await?;
+    let general_id = create_text_channel(&ctx, &guild_id, "general").await?;
+    let secret_id = create_text_channel(&ctx, &guild_id, "secret").await?;
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &format!("/api/v1/channels/{secret_id}/overwrites/{guild_id}"),
+            Some(json!({
+                "target_type": 0,
+                "allow_perms": 0,
+                "deny_perms": Permissions::VIEW_CHANNEL.bits(),
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    for content in ["one", "two"] {
+        let (status, _) = ctx
+            .request_json(
+                Method::POST,
+                &format!("/api/v1/channels/{general_id}/messages"),
+                Some(json!({ "content": content })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    for id in [&guild_id, &second_guild_id] {
+        paracord_db::members::add_member(&ctx.db, member_id, id.parse()?).await?;
+    }
+    paracord_db::read_states::update_read_state(&ctx.db, member_id, general_id.parse()?, 0).await?;
+    let owner_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+    let (status, dm) = ctx
+        .request_json_as(
+            &member_token,
+            Method::POST,
+            "/api/v1/users/@me/dms",
+            Some(json!({ "recipient_id": owner_id.to_string() })),
+        )
+        .await?;
+    assert!(status.is_success(), "unexpected payload: {dm}");
+
+    let (status, overview) = ctx
+        .request_json_as(
+            &member_token,
+            Method::GET,
+            "/api/v1/users/@me/overview?guild_limit=1",
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {overview}");
+    assert_eq!(overview["guilds"].as_array().map(Vec::len), Some(1));
+    let guild = &overview["guilds"][0];
+    assert_eq!(guild["id"], guild_id.as_str());
+    let channel_ids: HashSet<&str> = guild["channels"]
+        .as_array()
+        .context("channels")?
+        .iter()
+        .filter_map(|c| c["id"].as_str())
+        .collect();
+    assert!(channel_ids.contains(general_id.as_str()));
+    assert!(!channel_ids.contains(secret_id.as_str()));
+    let read_state = overview["read_states"]
+        .as_array()
+        .context("read states")?
+        .iter()
+        .find(|rs| rs["channel_id"] == general_id.as_str())
+        .context("general read state")?;
+    assert_eq!(read_state["unread_count"], 2);
+    assert_eq!(overview["dms"][0]["id"], dm["id"]);
+    assert!(overview["next_dm_before"].is_null());
+    let next = overview["next_guild_after"]
+        .as_str()
+        .context("next guild cursor")?;
+
+    let (status, rest) = ctx
+        .request_json_as(
+            &member_token,
+            Method::GET,
+            &format!("/api/v1/users/@me/overview?guild_limit=1&guild_after={next}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rest["guilds"][0]["id"], second_guild_id.as_str());
+    assert!(rest["next_guild_after"].is_null());
+
+    // The owner sees the channel hidden from members.
+    let (status, owner_view) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me/overview", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let owner_channels = owner_view["guilds"]
+        .as_array()
+        .context("guilds")?
+        .iter()
+        .flat_map(|g| g["channels"].as_array().cloned().unwrap_or_default())
+        .filter_map(|c| c["id"].as_str().map(str::to_string))
+        .collect::<HashSet<_>>();
+    assert!(owner_channels.contains(&secret_id));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn slowmode_limits_members_but_not_moderators() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Slowmode Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "slow-chat").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+
+    let (status, updated) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/channels/{channel_id}"),
+            Some(json!({ "rate_limit_per_user": 30 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {updated}");
+    assert_eq!(updated["rate_limit_per_user"], 30);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/channels/{channel_id}"),
+            Some(json!({ "rate_limit_per_user": 999_999 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // The space owner can manage the channel and is never slowed down.
+    for content in ["first", "second"] {
+        let (status, _) = ctx
+            .request_json(
+                Method::POST,
+                &messages_path,
+                Some(json!({ "content": content })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_claims = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?;
+    paracord_db::members::add_member(&ctx.db, member_claims.sub, guild_id.parse()?).await?;
+
+    let (status, sent) = ctx
+        .request_json_as(
+            &member_token,
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "member hello", "nonce": "slow-1" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // Retrying the send with its nonce returns the stored message instead of
+    // tripping the window that send opened.
+    let (status, retried) = ctx
+        .request_json_as(
+            &member_token,
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "member hello", "nonce": "slow-1" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {retried}");
+    assert_eq!(retried["id"], sent["id"]);
+
+    let (status, rejected) = ctx
+        .request_json_as(
+            &member_token,
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "too soon" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(rejected["code"], "SLOWMODE");
+    let retry_after = rejected["retry_after"]
+        .as_f64()
+        .context("retry_after should be a number")?;
+    assert!(retry_after > 0.0 && retry_after <= 30.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn slowmode_admits_one_of_several_concurrent_sends() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Slowmode Race Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "slow-race").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/channels/{channel_id}"),
+            Some(json!({ "rate_limit_per_user": 30 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_claims = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?;
+    paracord_db::members::add_member(&ctx.db, member_claims.sub, guild_id.parse()?).await?;
+
+    let sends = (0..5).map(|n| {
+        ctx.request_json_as(
+            &member_token,
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": format!("race {n}") })),
+        )
+    });
+    let mut statuses = Vec::new();
+    for result in futures_util::future::join_all(sends).await {
+        statuses.push(result?.0);
+    }
+    let created = statuses
+        .iter()
+        .filter(|status| **status == StatusCode::CREATED)
+        .count();
+    let limited = statuses
+        .iter()
+        .filter(|status| **status == StatusCode::TOO_MANY_REQUESTS)
+        .count();
+    assert_eq!(
+        (created, limited),
+        (1, 4),
+        "unexpected statuses: {statuses:?}"
+    );
+
+    // A poll posts a message too, so it waits out the same window.
+    let (status, rejected) = ctx
+        .request_json_as(
+            &member_token,
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/polls"),
+            Some(json!({
+                "question": "Lunch?",
+                "options": [{ "text": "Yes" }, { "text": "No" }],
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(rejected["code"], "SLOWMODE");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn bulk_delete_reports_skipped_ids_and_enforces_batch_limit() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Bulk Delete Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "bulk").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+
+    let mut message_ids = Vec::new();
+    for content in ["one", "two"] {
+        let (status, message) = ctx
+            .request_json(
+                Method::POST,
+                &messages_path,
+                Some(json!({ "content": content })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+        message_ids.push(
+            message["id"]
+                .as_str()
+                .context("message id should be a string")?
+                .to_string(),
+        );
+    }
+
+    let mut requested = message_ids.clone();
+    requested.push("123".to_string());
+    let (status, result) = ctx
+        .request_json(
+            Method::POST,
+            &format!("{messages_path}/bulk-delete"),
+            Some(json!({ "message_ids": requested })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {result}");
+    assert_eq!(result["deleted"], 2);
+    assert_eq!(result["skipped_ids"], json!(["123"]));
+
+    let too_many: Vec<String> = (1..=101).map(|id| id.to_string()).collect();
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &format!("{messages_path}/bulk-delete"),
+            Some(json!({ "message_ids": too_many })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, audit) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/audit-logs"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let entries = audit["audit_log_entries"]
+        .as_array()
+        .context("audit log entries should be an array")?;
+    let bulk_entries: Vec<&Value> = entries
+        .iter()
+        .filter(|entry| entry["action"] == "message_bulk_delete")
+        .collect();
+    assert_eq!(bulk_entries.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn channel_creation_validates_type_voice_settings_and_parent() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Channel Types Guild").await?;
+    let channels_path = format!("/api/v1/guilds/{guild_id}/channels");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &channels_path,
+            Some(json!({ "name": "mystery", "channel_type": 42 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &channels_path,
+            Some(json!({ "name": "text", "channel_type": 0, "bitrate": 64000 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, voice) = ctx
+        .request_json(
+            Method::POST,
+            &channels_path,
+            Some(json!({ "name": "voice", "channel_type": 2, "user_limit": 5 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {voice}");
+    assert_eq!(voice["bitrate"], 64000);
+    assert_eq!(voice["user_limit"], 5);
+
+    let text_id = create_text_channel(&ctx, &guild_id, "not-a-category").await?;
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &channels_path,
+            Some(
+                json!({ "name": "child", "channel_type": 0, "parent_id": text_id.parse::<i64>()? }),
+            ),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, category) = ctx
+        .request_json(
+            Method::POST,
+            &channels_path,
+            Some(json!({ "name": "Category", "channel_type": 4 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let category_id = category["id"]
+        .as_str()
+        .context("category id should be a string")?
+        .parse::<i64>()?;
+    let (status, child) = ctx
+        .request_json(
+            Method::POST,
+            &channels_path,
+            Some(json!({ "name": "child", "channel_type": 0, "parent_id": category_id })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(child["parent_id"], category_id.to_string());
+    assert!(child["bitrate"].is_null());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn channel_reorder_is_validated_and_returns_ordered_channels() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Reorder Guild").await?;
+    let first = create_text_channel(&ctx, &guild_id, "first").await?;
+    let second = create_text_channel(&ctx, &guild_id, "second").await?;
+    let channels_path = format!("/api/v1/guilds/{guild_id}/channels");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &channels_path,
+            Some(json!([
+                { "id": first, "position": 3 },
+                { "id": second, "position": 3 },
+            ])),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &channels_path,
+            Some(json!([
+                { "id": first, "position": 50 },
+                { "id": "123456789", "position": 51 },
+            ])),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, result) = ctx
+        .request_json(
+            Method::PATCH,
+            &channels_path,
+            Some(json!([
+                { "id": first, "position": 100 },
+                { "id": second, "position": 90 },
+            ])),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {result}");
+    assert_eq!(result["updated"], 2);
+    let ordered: Vec<&str> = result["channels"]
+        .as_array()
+        .context("channels should be an array")?
+        .iter()
+        .filter_map(|channel| channel["id"].as_str())
+        .collect();
+    let first_idx = ordered.iter().position(|id| *id == first);
+    let second_idx = ordered.iter().position(|id| *id == second);
+    assert!(second_idx.is_some() && first_idx > second_idx);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn member_list_search_returns_page_with_total_and_has_more() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Member Search Guild").await?;
+    let members_path = format!("/api/v1/guilds/{guild_id}/members");
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_claims = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?;
+    paracord_db::members::add_member(&ctx.db, member_claims.sub, guild_id.parse()?).await?;
+    let member = paracord_db::users::get_user_by_id(&ctx.db, member_claims.sub)
+        .await?
+        .context("member user should exist")?;
+
+    // Unfiltered requests get the same page shape.
+    let (status, all) = ctx.request_json(Method::GET, &members_path, None).await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {all}");
+    assert_eq!(all["members"].as_array().map(Vec::len), Some(2));
+    assert_eq!(all["total"], 2);
+    assert_eq!(all["has_more"], false);
+
+    let username = member.username.to_uppercase();
+    let (prefix, rest) = username.split_at(username.len() - 1);
+    let (status, found) = ctx
+        .request_json(Method::GET, &format!("{members_path}?q={prefix}"), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {found}");
+    assert_eq!(found["total"], 1);
+    assert_eq!(found["members"][0]["user_id"], member.id.to_string());
+
+    // Names match from their start only.
+    let (status, inside) = ctx
+        .request_json(Method::GET, &format!("{members_path}?q={rest}"), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {inside}");
+    assert_eq!(inside["total"], 0);
+
+    let (status, none) = ctx
+        .request_json(Method::GET, &format!("{members_path}?timed_out=true"), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(none["total"], 0);
+    assert_eq!(none["members"].as_array().map(Vec::len), Some(0));
+    assert_eq!(none["has_more"], false);
+
+    let (status, first) = ctx
+        .request_json(Method::GET, &format!("{members_path}?limit=1"), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(first["total"], 2);
+    assert_eq!(first["has_more"], true);
+    let last_id = first["members"][0]["user_id"]
+        .as_str()
+        .context("member id should be a string")?;
+    let (status, second) = ctx
+        .request_json(
+            Method::GET,
+            &format!("{members_path}?limit=1&after={last_id}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(second["total"], 2);
+    assert_eq!(second["has_more"], false);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn member_list_reports_presence_and_last_seen() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Presence Guild").await?;
+    let owner_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, member_id, guild_id.parse()?).await?;
+
+    ctx.state.user_presences.write().await.insert(
+        owner_id,
+        json!({ "user_id": owner_id.to_string(), "status": "dnd", "activities": [] }),
+    );
+    let last_seen = Utc::now() - Duration::minutes(5);
+    paracord_db::users::set_last_seen(&ctx.db, member_id, last_seen).await?;
+
+    let (status, members) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/members"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {members}");
+    let members = members["members"]
+        .as_array()
+        .context("member list should be an array")?;
+    let presence_of = |user_id: String| {
+        members
+            .iter()
+            .find(|m| m["user_id"] == user_id)
+            .map(|m| m["presence"].clone())
+            .unwrap_or(Value::Null)
+    };
+
+    let owner = presence_of(owner_id.to_string());
+    assert_eq!(owner["status"], "dnd");
+    assert!(owner["last_seen_at"].is_null());
+
+    let member = presence_of(member_id.to_string());
+    assert_eq!(member["status"], "offline");
+    let reported = member["last_seen_at"]
+        .as_str()
+        .context("offline member should report last_seen_at")?;
+    assert_eq!(
+        chrono::DateTime::parse_from_rfc3339(reported)?.timestamp(),
+        last_seen.timestamp()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn temporary_bans_expire_and_are_lifted() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Temp Ban Guild").await?;
+    let space_id: i64 = guild_id.parse()?;
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, member_id, space_id).await?;
+    let ban_path = format!("/api/v1/guilds/{guild_id}/bans/{member_id}");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &ban_path,
+            Some(json!({ "duration_seconds": 0 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &ban_path,
+            Some(json!({ "reason": "cool off", "duration_seconds": 7 * 24 * 60 * 60 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, bans) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/bans"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {bans}");
+    assert!(bans["bans"][0]["expires_at"].is_string());
+    assert!(bans["bans"][0]["user"]["username"].is_string());
+    assert_eq!(bans["has_more"], false);
+
+    let (status, page) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/bans?limit=1&reason=COOL"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {page}");
+    assert_eq!(page["bans"][0]["user_id"], member_id.to_string());
+    assert_eq!(page["has_more"], false);
+    let (status, page) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/bans?reason=off"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {page}");
+    assert_eq!(page["bans"].as_array().map(Vec::len), Some(0));
+    assert_eq!(
+        paracord_api::routes::bans::lift_expired_bans_once(&ctx.state).await?,
+        0
+    );
+
+    // Backdate the expiry: the ban stops applying before the sweeper runs.
+    let owner_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+    paracord_db::bans::create_ban(
+        &ctx.db,
+        member_id,
+        space_id,
+        None,
+        owner_id,
+        Some(Utc::now() - Duration::seconds(1)),
+    )
+    .await?;
+    assert!(!paracord_db::bans::is_banned(&ctx.db, member_id, space_id).await?);
+    assert_eq!(
+        paracord_api::routes::bans::lift_expired_bans_once(&ctx.state).await?,
+        1
+    );
+
+    let entries =
+        paracord_db::audit_log::get_guild_entries(&ctx.db, space_id, None, None, None, 10).await?;
+    assert!(entries.iter().any(|entry| {
+        entry.action() == paracord_models::audit_log::AuditAction::MemberBanRemove
+            && entry.target_id == Some(member_id)
+            && entry.user_id == owner_id
+    }));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn ban_can_delete_recent_messages() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Ban Purge Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "spam").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, member_id, guild_id.parse()?).await?;
+    for content in ["buy now", "limited offer"] {
+        let (status, _) = ctx
+            .request_json_as(
+                &member_token,
+                Method::POST,
+                &messages_path,
+                Some(json!({ "content": content })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "owner stays" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let ban_path = format!("/api/v1/guilds/{guild_id}/bans/{member_id}");
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &ban_path,
+            Some(json!({ "delete_message_seconds": 8 * 24 * 60 * 60 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, banned) = ctx
+        .request_json(
+            Method::PUT,
+            &ban_path,
+            Some(json!({ "delete_message_seconds": 3600 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {banned}");
+    assert_eq!(banned["deleted_message_count"], 2);
+
+    let (status, remaining) = ctx.request_json(Method::GET, &messages_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let remaining = remaining
+        .as_array()
+        .context("messages should be an array")?;
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0]["content"], "owner stays");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn role_hierarchy_blocks_moderating_equal_or_higher_members() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Hierarchy Guild").await?;
+    let guild: i64 = guild_id.parse()?;
+
+    let (status, role) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/guilds/{guild_id}/roles"),
+            Some(json!({
+                "name": "Moderator",
+                "permissions": (Permissions::KICK_MEMBERS | Permissions::BAN_MEMBERS).bits(),
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {role}");
+    let mod_role_id: i64 = role["id"].as_str().context("role id")?.parse()?;
+
+    let mod_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let mod_id = paracord_core::auth::validate_token(&mod_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, mod_id, guild).await?;
+    paracord_db::roles::add_member_role(&ctx.db, mod_id, guild, mod_role_id).await?;
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, member_id, guild).await?;
+
+    // New roles sit at the bottom of the hierarchy, level with the default
+    // member role, so the moderator does not outrank the member yet.
+    let kick_path = format!("/api/v1/guilds/{guild_id}/members/{member_id}");
+    let (status, rejected) = ctx
+        .request_json_as(&mod_token, Method::DELETE, &kick_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(rejected["code"], "ROLE_HIERARCHY");
+
+    let (status, rejected) = ctx
+        .request_json_as(
+            &mod_token,
+            Method::PUT,
+            &format!("/api/v1/guilds/{guild_id}/bans/{member_id}"),
+            Some(json!({})),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(rejected["code"], "ROLE_HIERARCHY");
+
+    // The owner bypasses the hierarchy entirely.
+    let (status, _) = ctx.request_json(Method::DELETE, &kick_path, None).await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn role_reorder_normalizes_positions_and_respects_hierarchy() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Reorder Guild").await?;
+    let guild: i64 = guild_id.parse()?;
+    let roles_path = format!("/api/v1/guilds/{guild_id}/roles");
+
+    let mut role_ids = Vec::new();
+    for (name, permissions) in [
+        ("Admin", Permissions::ADMINISTRATOR.bits()),
+        ("Helper", 0),
+        ("Muted", 0),
+    ] {
+        let (status, role) = ctx
+            .request_json(
+                Method::POST,
+                &roles_path,
+                Some(json!({ "name": name, "permissions": permissions })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED, "unexpected payload: {role}");
+        role_ids.push(role["id"].as_str().context("role id")?.to_string());
+    }
+    let (admin_role, helper_role, muted_role) = (&role_ids[0], &role_ids[1], &role_ids[2]);
+
+    // Gaps and a duplicate position collapse to a contiguous 1..=n order.
+    let (status, reordered) = ctx
+        .request_json(
+            Method::PATCH,
+            &roles_path,
+            Some(json!([
+                { "id": admin_role, "position": 50 },
+                { "id": helper_role, "position": 7 },
+                { "id": muted_role, "position": 7 },
+            ])),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {reordered}");
+    let positions: HashMap<String, i64> = reordered["roles"]
+        .as_array()
+        .context("roles should be an array")?
+        .iter()
+        .map(|role| {
+            (
+                role["id"].as_str().unwrap_or_default().to_string(),
+                role["position"].as_i64().unwrap_or_default(),
+            )
+        })
+        .collect();
+    assert_eq!(positions[&guild_id], 0);
+    assert_eq!(positions[admin_role], 3);
+    let mut lower = [positions[helper_role], positions[muted_role]];
+    lower.sort_unstable();
+    assert_eq!(lower, [1, 2]);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &roles_path,
+            Some(json!([{ "id": guild_id, "position": 4 }])),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let admin_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let admin_id = paracord_core::auth::validate_token(&admin_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, admin_id, guild).await?;
+    paracord_db::roles::add_member_role(&ctx.db, admin_id, guild, admin_role.parse()?).await?;
+
+    let (status, rejected) = ctx
+        .request_json_as(
+            &admin_token,
+            Method::PATCH,
+            &roles_path,
+            Some(json!([{ "id": helper_role, "position": 3 }])),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(rejected["code"], "ROLE_HIERARCHY");
+
+    let (status, _) = ctx
+        .request_json_as(
+            &admin_token,
+            Method::PATCH,
+            &roles_path,
+            Some(json!([{ "id": muted_role, "position": 2 }])),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // Now above the default member role, the admin can moderate members.
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, member_id, guild).await?;
+    let (status, _) = ctx
+        .request_json_as(
+            &admin_token,
+            Method::DELETE,
+            &format!("/api/v1/guilds/{guild_id}/members/{member_id}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let entries =
+        paracord_db::audit_log::get_guild_entries(&ctx.db, guild, None, None, None, 20).await?;
+    assert!(entries.iter().any(|entry| {
+        entry.action() == paracord_models::audit_log::AuditAction::RoleUpdate
+            && entry.reason.as_deref() == Some("roles reordered")
+    }));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cached_channel_permissions_follow_role_changes_immediately() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Permission Cache Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "general").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, member_id, guild_id.parse()?).await?;
+
+    for content in ["first", "second"] {
+        let (status, _) = ctx
+            .request_json_as(
+                &member_token,
+                Method::POST,
+                &messages_path,
+                Some(json!({ "content": content })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    assert!(paracord_core::observability::permission_cache_snapshot().hits > 0);
+
+    let (status, role) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/guilds/{guild_id}/roles"),
+            Some(json!({ "name": "Muted" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {role}");
+    let muted_role = role["id"].as_str().context("role id")?.to_string();
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &format!("/api/v1/channels/{channel_id}/overwrites/{muted_role}"),
+            Some(json!({
+                "target_type": 0,
+                "allow_perms": 0,
+                "deny_perms": Permissions::SEND_MESSAGES.bits(),
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/guilds/{guild_id}/members/{member_id}"),
+            Some(json!({ "roles": [muted_role] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // The member's cached permissions were computed before the role was
+    // assigned; they must not be served afterwards.
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "still muted?" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn channel_list_hides_categories_whose_children_are_all_hidden() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Visibility Guild").await?;
+    let channels_path = format!("/api/v1/guilds/{guild_id}/channels");
+
+    let mut category_ids = Vec::new();
+    for name in ["staff", "empty"] {
+        let (status, category) = ctx
+            .request_json(
+                Method::POST,
+                &channels_path,
+                Some(json!({ "name": name, "channel_type": 4 })),
+            )
+            .await?;
+        assert_eq!(
+            status,
+            StatusCode::CREATED,
+            "unexpected payload: {category}"
+        );
+        category_ids.push(category["id"].as_str().context("category id")?.to_string());
+    }
+    let (status, secret) = ctx
+        .request_json(
+            Method::POST,
+            &channels_path,
+            Some(json!({
+                "name": "secret",
+                "channel_type": 0,
+                "parent_id": category_ids[0].parse::<i64>()?,
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {secret}");
+    let secret_id = secret["id"].as_str().context("channel id")?.to_string();
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &format!("/api/v1/channels/{secret_id}/overwrites/{guild_id}"),
+            Some(json!({
+                "target_type": 0,
+                "allow_perms": 0,
+                "deny_perms": Permissions::VIEW_CHANNEL.bits(),
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, member_id, guild_id.parse()?).await?;
+
+    let listed_ids = |payload: &Value| -> HashSet<String> {
+        payload
+            .as_array()
+            .map(|channels| {
+                channels
+                    .iter()
+                    .filter_map(|c| c["id"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let (status, owner_view) = ctx.request_json(Method::GET, &channels_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let owner_ids = listed_ids(&owner_view);
+    assert!(owner_ids.contains(&category_ids[0]) && owner_ids.contains(&secret_id));
+
+    let (status, member_view) = ctx
+        .request_json_as(&member_token, Method::GET, &channels_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let member_ids = listed_ids(&member_view);
+    assert!(!member_ids.contains(&secret_id));
+    // The category's only child is hidden, so the category is too; a
+    // category with no children at all is still listed.
+    assert!(!member_ids.contains(&category_ids[0]));
+    assert!(member_ids.contains(&category_ids[1]));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn channel_webhooks_execute_with_overrides_and_are_rate_limited() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Webhook Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "deploys").await?;
+    let webhooks_path = format!("/api/v1/channels/{channel_id}/webhooks");
+
+    let (status, created) = ctx
+        .request_json(Method::POST, &webhooks_path, Some(json!({ "name": "CI" })))
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {created}");
+    assert_eq!(created["channel_id"], channel_id.clone());
+    let webhook_id = created["id"]
+        .as_str()
+        .context("missing webhook id")?
+        .to_string();
+    let token = created["token"]
+        .as_str()
+        .context("missing webhook token")?
+        .to_string();
+
+    // Members without MANAGE_WEBHOOKS cannot create or list webhooks.
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_claims = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?;
+    paracord_db::members::add_member(&ctx.db, member_claims.sub, guild_id.parse()?).await?;
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::POST,
+            &webhooks_path,
+            Some(json!({ "name": "Nope" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, listed) = ctx.request_json(Method::GET, &webhooks_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let listed = listed
+        .as_array()
+        .context("webhook list should be an array")?;
+    assert_eq!(listed.len(), 1);
+    assert!(listed[0].get("token").is_none());
+
+    // Execution needs only the token; the user credential is irrelevant.
+    let execute_path = format!("/api/v1/webhooks/{webhook_id}/{token}");
+    let (status, message) = ctx
+        .request_json_as(
+            "",
+            Method::POST,
+            &execute_path,
+            Some(json!({
+                "content": "build passed",
+                "username": "Release Bot",
+                "avatar_url": "https://cdn.example.com/bot.png",
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {message}");
+    assert_eq!(message["author"]["username"], "Release Bot");
+    assert_eq!(
+        message["author"]["avatar_url"],
+        "https://cdn.example.com/bot.png"
+    );
+    assert_eq!(message["webhook_id"], webhook_id.clone());
+
+    let (status, _) = ctx
+        .request_json_as(
+            "",
+            Method::POST,
+            &execute_path,
+            Some(json!({ "content": "hi", "avatar_url": "javascript:alert(1)" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = ctx
+        .request_json_as(
+            "",
+            Method::POST,
+            &format!("/api/v1/webhooks/{webhook_id}/not-the-token"),
+            Some(json!({ "content": "hi" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Each webhook has its own execution budget.
+    let mut limited = false;
+    for attempt in 0..10 {
+        let (status, _) = ctx
+            .request_json_as(
+                "",
+                Method::POST,
+                &execute_path,
+                Some(json!({ "content": format!("burst {attempt}") })),
+            )
+            .await?;
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            limited = true;
+            break;
+        }
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    assert!(limited, "webhook execution should be rate limited");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::DELETE,
+            &format!("/api/v1/webhooks/{webhook_id}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = ctx
+        .request_json_as(
+            "",
+            Method::POST,
+            &execute_path,
+            Some(json!({ "content": "gone" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn replies_embed_a_snapshot_of_the_referenced_message() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Reply Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "chat").await?;
+    let other_channel_id = create_text_channel(&ctx, &guild_id, "elsewhere").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+
+    let long_content = "x".repeat(150);
+    let (status, original) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": long_content })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let original_id = original["id"]
+        .as_str()
+        .context("message id should be a string")?
+        .to_string();
+
+    let (status, reply) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "agreed", "referenced_message_id": original_id })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {reply}");
+    assert_eq!(reply["reference_id"], original_id.clone());
+    let snapshot = &reply["referenced_message"];
+    assert_eq!(snapshot["id"], original_id.clone());
+    assert_eq!(snapshot["deleted"], false);
+    assert_eq!(snapshot["author"]["id"], original["author"]["id"]);
+    let excerpt = snapshot["content"]
+        .as_str()
+        .context("excerpt should be a string")?;
+    assert_eq!(excerpt.chars().count(), 101);
+    assert!(excerpt.ends_with('…'));
+    let reply_id = reply["id"]
+        .as_str()
+        .context("reply id should be a string")?
+        .to_string();
+
+    // Replies must point at a message in the same channel.
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{other_channel_id}/messages"),
+            Some(json!({ "content": "cross-channel", "referenced_message_id": original_id })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "dangling", "referenced_message_id": "12345" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, messages) = ctx.request_json(Method::GET, &messages_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let listed = messages
+        .as_array()
+        .context("messages list should be an array")?
+        .iter()
+        .find(|m| m["id"] == reply_id)
+        .context("reply should be listed")?;
+    assert_eq!(listed["referenced_message"], reply["referenced_message"]);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::DELETE,
+            &format!("{messages_path}/{original_id}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, messages) = ctx.request_json(Method::GET, &messages_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let reply = messages
+        .as_array()
+        .context("messages list should be an array")?
+        .iter()
+        .find(|m| m["id"] == reply_id)
+        .context("reply should still be listed")?;
+    assert_eq!(reply["reference_id"], original_id);
+    assert_eq!(reply["referenced_message"]["deleted"], true);
+    assert!(reply["referenced_message"]["author"].is_null());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn invites_enforce_use_limits_expiry_and_temporary_membership() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Invite Guild").await?;
+    let space_id: i64 = guild_id.parse()?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "lobby").await?;
+    let invites_path = format!("/api/v1/channels/{channel_id}/invites");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &invites_path,
+            Some(json!({ "max_uses": 1000 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, invite) = ctx
+        .request_json(
+            Method::POST,
+            &invites_path,
+            Some(json!({ "max_uses": 1, "max_age": 3600 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {invite}");
+    let code = invite["code"]
+        .as_str()
+        .context("missing invite code")?
+        .to_string();
+    let invite_path = format!("/api/v1/invites/{code}");
+
+    // Previewing reports what is left without consuming a use.
+    for _ in 0..2 {
+        let (status, preview) = ctx.request_json(Method::GET, &invite_path, None).await?;
+        assert_eq!(status, StatusCode::OK, "unexpected payload: {preview}");
+        assert_eq!(preview["uses"], 0);
+        assert_eq!(preview["remaining_uses"], 1);
+        let expires_in = preview["expires_in"]
+            .as_i64()
+            .context("expires_in should be a number")?;
+        assert!(expires_in > 3500 && expires_in <= 3600);
+    }
+
+    let first_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let second_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let (status, _) = ctx
+        .request_json_as(&first_token, Method::POST, &invite_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    // Accepting again as an existing member does not count as a use.
+    let (status, _) = ctx
+        .request_json_as(&first_token, Method::POST, &invite_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, exhausted) = ctx
+        .request_json_as(&second_token, Method::POST, &invite_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::GONE);
+    assert_eq!(exhausted["code"], "INVITE_MAX_USES");
+
+    // Temporary invites create memberships that are dropped on disconnect.
+    let (status, temporary) = ctx
+        .request_json(
+            Method::POST,
+            &invites_path,
+            Some(json!({ "max_age": 1, "temporary": true })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(temporary["temporary"], true);
+    let temporary_path = format!(
+        "/api/v1/invites/{}",
+        temporary["code"].as_str().context("missing invite code")?
+    );
+    let (status, _) = ctx
+        .request_json_as(&second_token, Method::POST, &temporary_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let second_id = paracord_core::auth::validate_token(&second_token, &ctx.jwt_keys)?.sub;
+    assert_eq!(
+        paracord_db::members::remove_temporary_memberships(&ctx.db, second_id).await?,
+        vec![space_id]
+    );
+
+    tokio::time::sleep(std::time::Duration::from_millis(2_100)).await;
+    let (status, expired) = ctx.request_json(Method::GET, &temporary_path, None).await?;
+    assert_eq!(status, StatusCode::GONE);
+    assert_eq!(expired["code"], "INVITE_EXPIRED");
+    let (status, expired) = ctx
+        .request_json_as(&second_token, Method::POST, &temporary_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::GONE);
+    assert_eq!(expired["code"], "INVITE_EXPIRED");
+
+    assert_eq!(
+        paracord_api::routes::invites::delete_expired_invites_once(&ctx.state).await?,
+        1
+    );
+    let (status, _) = ctx.request_json(Method::GET, &temporary_path, None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn vanity_codes_are_validated_and_resolve_like_invites() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Vanity Guild").await?;
+    let other_guild_id = create_guild(&ctx, "Other Vanity Guild").await?;
+    let space_id: i64 = guild_id.parse()?;
+    let vanity_path = format!("/api/v1/guilds/{guild_id}/vanity-url");
+
+    for bad in ["api", "a", "has space", "-edge", "ünicode"] {
+        let (status, _) = ctx
+            .request_json(Method::PATCH, &vanity_path, Some(json!({ "code": bad })))
+            .await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{bad} should be rejected");
+    }
+
+    let (status, updated) = ctx
+        .request_json(
+            Method::PATCH,
+            &vanity_path,
+            Some(json!({ "code": "Rust-Fans" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {updated}");
+    assert_eq!(updated["code"], "rust-fans");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/guilds/{other_guild_id}/vanity-url"),
+            Some(json!({ "code": "rust-fans" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::PATCH,
+            &vanity_path,
+            Some(json!({ "code": "hijacked" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, preview) = ctx
+        .request_json(Method::GET, "/api/v1/invites/rust-fans", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {preview}");
+    assert_eq!(preview["vanity"], true);
+    assert_eq!(preview["guild"]["id"], guild_id.clone());
+
+    let (status, joined) = ctx
+        .request_json_as(
+            &member_token,
+            Method::POST,
+            "/api/v1/invites/rust-fans",
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {joined}");
+    assert_eq!(joined["guild"]["id"], guild_id.clone());
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    assert!(
+        paracord_db::members::get_member(&ctx.db, member_id, space_id)
+            .await?
+            .is_some()
+    );
+
+    let entries =
+        paracord_db::audit_log::get_guild_entries(&ctx.db, space_id, None, None, None, 10).await?;
+    assert!(entries.iter().any(|entry| {
+        entry.action() == paracord_models::audit_log::AuditAction::GuildUpdate
+            && entry
+                .changes
+                .as_ref()
+                .is_some_and(|changes| changes["vanity_url_code"]["new"] == "rust-fans")
+    }));
+
+    let (status, cleared) = ctx
+        .request_json(Method::PATCH, &vanity_path, Some(json!({ "code": null })))
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(cleared["code"].is_null());
+    let (status, _) = ctx
+        .request_json(Method::GET, "/api/v1/invites/rust-fans", None)
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn role_gated_spaces_are_hidden_from_users_without_an_allowed_role() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let lobby_id = create_guild(&ctx, "Lobby").await?;
+    let gated_id = create_guild(&ctx, "Inner Circle").await?;
+    let lobby_space_id: i64 = lobby_id.parse()?;
+    let gated_space_id: i64 = gated_id.parse()?;
+    let channel_id = create_text_channel(&ctx, &gated_id, "secrets").await?;
+
+    let (status, invite) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/invites"),
+            Some(json!({})),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {invite}");
+    let code = invite["code"].as_str().context("missing invite code")?;
+    let invite_path = format!("/api/v1/invites/{code}");
+
+    let verified_role_id = 9_100_001;
+    paracord_db::roles::create_role(&ctx.db, verified_role_id, lobby_space_id, "Verified", 0)
+        .await?;
+    paracord_db::guilds::update_space_visibility(
+        &ctx.db,
+        gated_space_id,
+        "roles",
+        &format!("[{verified_role_id}]"),
+    )
+    .await?;
+
+    let outsider_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let outsider_id = paracord_core::auth::validate_token(&outsider_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, outsider_id, lobby_space_id).await?;
+
+    // Without the role the space looks like it doesn't exist.
+    let (status, _) = ctx
+        .request_json_as(&outsider_token, Method::POST, &invite_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = ctx
+        .request_json_as(
+            &outsider_token,
+            Method::GET,
+            &format!("/api/v1/guilds/{gated_id}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    paracord_db::roles::add_member_role(&ctx.db, outsider_id, lobby_space_id, verified_role_id)
+        .await?;
+    let (status, accepted) = ctx
+        .request_json_as(&outsider_token, Method::POST, &invite_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {accepted}");
+    let (status, guilds) = ctx
+        .request_json_as(
+            &outsider_token,
+            Method::GET,
+            "/api/v1/users/@me/guilds",
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let listed: Vec<&str> = guilds
+        .as_array()
+        .context("guild list should be an array")?
+        .iter()
+        .filter_map(|guild| guild["id"].as_str())
+        .collect();
+    assert!(listed.contains(&gated_id.as_str()));
+
+    // Losing the role hides the space again even though membership remains.
+    paracord_db::roles::remove_member_role(&ctx.db, outsider_id, lobby_space_id, verified_role_id)
+        .await?;
+    let (status, _) = ctx
+        .request_json_as(
+            &outsider_token,
+            Method::GET,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn mention_counts_come_from_parsed_mentions() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Mention Guild").await?;
+    let space_id: i64 = guild_id.parse()?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "pings").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+    let read_path = format!("/api/v1/channels/{channel_id}/read");
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, member_id, space_id).await?;
+
+    let mut plain_message_id = String::new();
+    for content in [
+        format!("hey <@{member_id}>"),
+        format!("`<@{member_id}>` in code"),
+        format!("escaped \\<@{member_id}>"),
+        "@everyone standup".to_string(),
+        "plain".to_string(),
+    ] {
+        let (status, message) = ctx
+            .request_json(
+                Method::POST,
+                &messages_path,
+                Some(json!({ "content": content })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED, "unexpected payload: {message}");
+        plain_message_id = message["id"].as_str().unwrap_or_default().to_string();
+    }
+    // Edits are re-parsed.
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("{messages_path}/{plain_message_id}"),
+            Some(json!({ "content": format!("now <@!{member_id}>") })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, counts) = ctx
+        .request_json_as(
+            &member_token,
+            Method::PUT,
+            &read_path,
+            Some(json!({ "last_message_id": "0" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {counts}");
+    assert_eq!(counts["unread_count"], 5);
+    assert_eq!(counts["mention_count"], 3);
+
+    // Without MENTION_EVERYONE, @everyone is just text.
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "@everyone look" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, counts) = ctx
+        .request_json(
+            Method::PUT,
+            &read_path,
+            Some(json!({ "last_message_id": "0" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(counts["unread_count"], 1);
+    assert_eq!(counts["mention_count"], 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn mentions_are_backfilled_for_messages_sent_before_tracking() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Backfill Guild").await?;
+    let space_id: i64 = guild_id.parse()?;
+    let channel_id: i64 = create_text_channel(&ctx, &guild_id, "history")
+        .await?
+        .parse()?;
+    let owner_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, member_id, space_id).await?;
+
+    // Stored straight to the database, like messages from before mention
+    // tracking: no mention rows yet.
+    let mut ids = Vec::new();
+    for (author_id, content) in [
+        (owner_id, format!("@everyone meet <@{member_id}>")),
+        (member_id, format!("@everyone ask <@{owner_id}>")),
+        (owner_id, "plain".to_string()),
+    ] {
+        let id = paracord_util::snowflake::generate();
+        paracord_db::messages::create_message(
+            &ctx.db, id, channel_id, author_id, &content, 0, None,
+        )
+        .await?;
+        ids.push(id);
+    }
+    paracord_db::server_settings::set_setting(
+        &ctx.db,
+        paracord_core::mentions::MENTION_BACKFILL_SETTING,
+        &(ids[2] + 1).to_string(),
+    )
+    .await?;
+
+    // Batches of two: a full batch, a short one that finishes the backfill,
+    // then nothing left to do.
+    assert_eq!(
+        paracord_core::mentions::backfill_message_mentions(&ctx.db, 2).await?,
+        2
+    );
+    assert_eq!(
+        paracord_core::mentions::backfill_message_mentions(&ctx.db, 2).await?,
+        1
+    );
+    assert_eq!(
+        paracord_core::mentions::backfill_message_mentions(&ctx.db, 2).await?,
+        0
+    );
+
+    let owner_message =
+        paracord_db::message_mentions::get_message_mentions(&ctx.db, ids[0]).await?;
+    assert_eq!(owner_message.user_ids, vec![member_id]);
+    assert!(owner_message.everyone);
+    // The member lacks MENTION_EVERYONE, so only the user mention counts.
+    let member_message =
+        paracord_db::message_mentions::get_message_mentions(&ctx.db, ids[1]).await?;
+    assert_eq!(member_message.user_ids, vec![owner_id]);
+    assert!(!member_message.everyone);
+    assert!(
+        paracord_db::message_mentions::get_message_mentions(&ctx.db, ids[2])
+            .await?
+            .is_empty()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn login_upgrades_password_hashes_made_with_weaker_params() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let user_id = paracord_util::snowflake::generate();
+    let nonce = Uuid::new_v4().simple().to_string();
+    let email = format!("{nonce}@example.com");
+    let weak_hash = paracord_core::auth::hash_password_with(
+        "IntegrationPass123!",
+        paracord_core::auth::PasswordHashParams {
+            memory_kib: 8 * 1024,
+            iterations: 1,
+            parallelism: 1,
+        },
+    )?;
+    paracord_db::users::create_user(
+        &ctx.db,
+        user_id,
+        &format!("rehash_{nonce}"),
+        1,
+        &email,
+        &weak_hash,
+    )
+    .await?;
+
+    // Login reads the peer address, which only a real listener provides.
+    let mut request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/auth/login")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            json!({ "email": email, "password": "IntegrationPass123!" }).to_string(),
+        ))?;
+    request
+        .extensions_mut()
+        .insert(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            40_000,
+        ))));
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let user = paracord_db::users::get_user_by_email(&ctx.db, &email)
+        .await?
+        .context("user should exist")?;
+    assert_ne!(user.password_hash, weak_hash);
+    assert!(!paracord_core::auth::password_needs_rehash(
+        &user.password_hash
+    ));
+    assert!(paracord_core::auth::verify_password(
+        "IntegrationPass123!",
+        &user.password_hash
+    )?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn signed_challenges_cannot_be_replayed() -> anyhow::Result<()> {
+    use ed25519_dalek::Signer;
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+    fn with_peer(mut request: Request<Body>) -> Request<Body> {
+        request
+            .extensions_mut()
+            .insert(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+                [127, 0, 0, 2],
+                40_001,
+            ))));
+        request
+    }
+
+    let ctx = TestContext::new().await?;
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+    let public_key = to_hex(signing_key.verifying_key().as_bytes());
+
+    let response = ctx
+        .app
+        .clone()
+        .oneshot(with_peer(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/v1/auth/challenge")
+                .body(Body::empty())?,
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let challenge: Value =
+        serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await?)?;
+    let nonce = challenge["nonce"].as_str().context("nonce")?;
+    let timestamp = challenge["timestamp"].as_i64().context("timestamp")?;
+    let server_origin = challenge["server_origin"]
+        .as_str()
+        .context("server_origin")?;
+    let signature = signing_key.sign(format!("{nonce}:{timestamp}:{server_origin}").as_bytes());
+    let verify_body = json!({
+        "public_key": public_key,
+        "nonce": nonce,
+        "timestamp": timestamp,
+        "signature": to_hex(&signature.to_bytes()),
+        "username": "challenger",
+    })
+    .to_string();
+    let verify_request = || -> anyhow::Result<Request<Body>> {
+        Ok(with_peer(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/v1/auth/verify")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(verify_body.clone()))?,
+        ))
+    };
+
+    let response = ctx.app.clone().oneshot(verify_request()?).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The same nonce and signature are rejected the second time around.
+    let response = ctx.app.clone().oneshot(verify_request()?).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn registration_reports_failed_password_rules() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+
+    // Stand-in for the Pwned Passwords range API listing one breached hash.
+    let (prefix, suffix) = paracord_util::validation::breached_password_range("Breached-Pass-1");
+    let range_body = format!("{suffix}:42\r\n{}:0\r\n", "0".repeat(35));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let breach_api = format!("http://{}/range/", listener.local_addr()?);
+    let range_path = format!("/range/{prefix}");
+    tokio::spawn(async move {
+        let stub = axum::Router::new().route(
+            &range_path,
+            axum::routing::get(move || async move { range_body }),
+        );
+        let _ = axum::serve(listener, stub).await;
+    });
+
+    let mut state = ctx.state.clone();
+    state.config.password_policy = paracord_util::validation::PasswordPolicy {
+        min_length: 12,
+        require_uppercase: true,
+        require_digit: true,
+        ..Default::default()
+    };
+    state.config.breached_password_api_url = Some(breach_api);
+    let app = paracord_api::build_router().with_state(state.clone());
+
+    let register =
+        |app: axum::Router, password: &str| {
+            let nonce = Uuid::new_v4().simple().to_string();
+            let mut request = Request::builder()
+                .method(Method::POST)
+                .uri("/api/v1/auth/register")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    json!({
+                        "email": format!("{nonce}@example.com"),
+                        "username": format!("pw_{}", &nonce[..12]),
+                        "password": password,
+                    })
+                    .to_string(),
+                ))
+                .expect("request");
+            // Auth routes are rate limited per peer address across the
+            // process, so each test that hits them uses its own.
+            request.extensions_mut().insert(axum::extract::ConnectInfo(
+                std::net::SocketAddr::from(([127, 0, 0, 3], 40_002)),
+            ));
+            async move {
+                let response = app.oneshot(request).await?;
+                let status = response.status();
+                let body: Value =
+                    serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await?)?;
+                anyhow::Ok((status, body))
+            }
+        };
+
+    let (status, body) = register(app.clone(), "lowercase").await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "WEAK_PASSWORD");
+    assert_eq!(
+        body["details"]["failed_rules"],
+        json!(["min_length", "uppercase", "digit"])
+    );
+
+    let (status, body) = register(app.clone(), "Breached-Pass-1").await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["details"]["failed_rules"], json!(["breached"]));
+
+    let (status, _) = register(app.clone(), "Unbreached-Pass-2").await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // The breach check fails open when the service can't be reached.
+    state.config.breached_password_api_url = Some("http://127.0.0.1:1/range/".to_string());
+    let offline_app = paracord_api::build_router().with_state(state);
+    let (status, _) = register(offline_app, "Breached-Pass-1").await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn readiness_probe_reports_component_status() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let probe = |app: axum::Router| async move {
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri("/health/ready")
+                    .body(Body::empty())?,
+            )
+            .await?;
+        let status = response.status();
+        let body: Value =
+            serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await?)?;
+        anyhow::Ok((status, body))
+    };
+
+    let (status, body) = probe(ctx.app.clone()).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["components"]["database"]["status"], "ok");
+    assert_eq!(body["components"]["database"]["pool"]["max"], 1);
+    assert_eq!(body["components"]["database"]["pool"]["min"], 0);
+    assert_eq!(body["components"]["livekit"]["status"], "disabled");
+
+    // Voice backed by an unreachable LiveKit makes the instance unready.
+    let mut state = ctx.state.clone();
+    state.config.livekit_available = true;
+    state.config.livekit_http_url = "http://127.0.0.1:1".to_string();
+    let (status, body) = probe(paracord_api::build_router().with_state(state)).await?;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "unavailable");
+    assert_eq!(body["components"]["database"]["status"], "ok");
+    assert_eq!(body["components"]["livekit"]["status"], "unavailable");
+
+    ctx.db.close().await;
+    let (status, body) = probe(ctx.app.clone()).await?;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_ne!(body["components"]["database"]["status"], "ok");
+
+    // Liveness is unaffected.
+    let response = ctx
+        .app
+        .clone()
+        .oneshot(Request::builder().uri("/health").body(Body::empty())?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn error_responses_share_a_coded_json_shape() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Error Shape Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "errors").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+
+    let (status, invalid) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "x".repeat(4001) })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(invalid["code"], "VALIDATION_FAILED");
+    assert_eq!(
+        invalid["details"]["fields"]["content"],
+        "Message content must be 1-4000 characters"
+    );
+    assert_eq!(invalid["message"], invalid["details"]["fields"]["content"]);
+
+    // Extractor rejections carry the same shape as handler errors.
+    let raw_request = |content_type: Option<&str>| -> anyhow::Result<Request<Body>> {
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri(&messages_path)
+            .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token));
+        if let Some(content_type) = content_type {
+            builder = builder.header(header::CONTENT_TYPE, content_type);
+        }
+        Ok(builder.body(Body::from("{not json"))?)
+    };
+    for (content_type, expected_status, expected_code) in [
+        (
+            Some("application/json"),
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+        ),
+        (
+            None,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "UNSUPPORTED_MEDIA_TYPE",
+        ),
+    ] {
+        let response = ctx.app.clone().oneshot(raw_request(content_type)?).await?;
+        assert_eq!(response.status(), expected_status);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let body: Value =
+            serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await?)?;
+        assert_eq!(body["code"], expected_code);
+        assert!(
+            body["message"].as_str().is_some_and(|m| !m.is_empty()),
+            "missing message: {body}"
+        );
+        assert!(body["details"].is_null());
+    }
+
+    let (status, missing) = ctx
+        .request_json(Method::GET, "/api/v1/no-such-route", None)
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(missing["code"], "NOT_FOUND");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn request_ids_are_echoed_and_included_in_error_bodies() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+
+    let response = ctx
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/health")
+                .header("x-request-id", "lb-req-42")
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-request-id"], "lb-req-42");
+
+    // Missing or unusable ids are replaced with a generated one.
+    let response = ctx
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/channels/1/messages")
+                .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+                .header("x-request-id", "not a valid id")
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert!(response.status().is_client_error());
+    let request_id = response.headers()["x-request-id"].to_str()?.to_string();
+    assert!(Uuid::parse_str(&request_id).is_ok(), "got {request_id}");
+    let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await?)?;
+    assert_eq!(body["request_id"], request_id.as_str());
+
+    Ok(())
+}
+
+async fn create_extra_session_token(ctx: &TestContext, user_id: i64) -> anyhow::Result<String> {
+    let session_id = format!("sess-{}", Uuid::new_v4().simple());
+    let jti = format!("jti-{}", Uuid::new_v4().simple());
+    let refresh_hash = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    paracord_db::sessions::create_session(
+        &ctx.db,
+        &session_id,
+        user_id,
+        &refresh_hash,
+        &jti,
+        None,
+        Some("laptop"),
+        Some("IntegrationAgent/1.0"),
+        Some("198.51.100.7"),
+        Utc::now() + Duration::days(1),
+    )
+    .await?;
+    Ok(paracord_core::auth::create_session_token(
+        user_id,
+        None,
+        &ctx.jwt_keys,
+        3600,
+        &session_id,
+        &jti,
+    )?)
+}
+
+#[tokio::test]
+async fn sessions_can_be_listed_and_revoked_remotely() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let user_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+    let laptop_token = create_extra_session_token(&ctx, user_id).await?;
+    let laptop_session = paracord_core::auth::validate_token(&laptop_token, &ctx.jwt_keys)?
+        .sid
+        .context("missing session id")?;
+
+    let (status, sessions) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me/sessions", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let sessions = sessions.as_array().context("sessions should be an array")?;
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions.iter().filter(|s| s["current"] == true).count(), 1);
+    let laptop = sessions
+        .iter()
+        .find(|s| s["id"] == laptop_session.as_str())
+        .context("laptop session should be listed")?;
+    assert_eq!(laptop["device_id"], "laptop");
+    assert_eq!(laptop["user_agent"], "IntegrationAgent/1.0");
+    assert_eq!(laptop["ip_address"], "198.51.100.7");
+    assert!(laptop["last_seen_at"].is_string());
+
+    // Revocation takes effect on the very next request.
+    let (status, _) = ctx
+        .request_json(
+            Method::DELETE,
+            &format!("/api/v1/users/@me/sessions/{laptop_session}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = ctx
+        .request_json_as(&laptop_token, Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let phone_token = create_extra_session_token(&ctx, user_id).await?;
+    let tablet_token = create_extra_session_token(&ctx, user_id).await?;
+    let (status, result) = ctx
+        .request_json(Method::DELETE, "/api/v1/users/@me/sessions", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {result}");
+    assert_eq!(result["revoked"], 2);
+    for token in [&phone_token, &tablet_token] {
+        let (status, _) = ctx
+            .request_json_as(token, Method::GET, "/api/v1/users/@me", None)
+            .await?;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    let (status, _) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let events =
+        paracord_db::security_events::list_events(&ctx.db, Some("auth.session.revoke"), None, 10)
+            .await?;
+    assert_eq!(events.len(), 3);
+    assert!(events.iter().all(|e| e.target_user_id == Some(user_id)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_bulk_user_actions_report_per_user_results() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let admin_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+    paracord_db::users::update_user_flags(&ctx.db, admin_id, paracord_core::USER_FLAG_ADMIN)
+        .await?;
+    let first_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let first_id = paracord_core::auth::validate_token(&first_token, &ctx.jwt_keys)?.sub;
+    let second_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let second_id = paracord_core::auth::validate_token(&second_token, &ctx.jwt_keys)?.sub;
+    let second_email = paracord_db::users::get_user_by_id(&ctx.db, second_id)
+        .await?
+        .context("user should exist")?
+        .email;
+    let login_request =
+        |email: &str| -> anyhow::Result<Request<Body>> {
+            let mut request = Request::builder()
+                .method(Method::POST)
+                .uri("/api/v1/auth/login")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    json!({ "email": email, "password": "IntegrationPass123!" }).to_string(),
+                ))?;
+            request.extensions_mut().insert(axum::extract::ConnectInfo(
+                std::net::SocketAddr::from(([127, 0, 0, 4], 40_002)),
+            ));
+            Ok(request)
+        };
+
+    let (status, body) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/admin/users/bulk",
+            Some(json!({
+                "action": "force_logout",
+                "user_ids": [first_id.to_string(), "bogus", "999", first_id.to_string()],
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body["results"],
+        json!([
+            { "id": first_id.to_string(), "success": true },
+            { "id": "bogus", "success": false, "error": "invalid_id" },
+            { "id": "999", "success": false, "error": "not_found" },
+        ])
+    );
+    let (status, _) = ctx
+        .request_json_as(&first_token, Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Disabling signs the user out and keeps them out until unbanned.
+    let (status, body) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/admin/users/bulk",
+            Some(json!({
+                "action": "disable",
+                "user_ids": [second_id.to_string(), admin_id.to_string()],
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["results"][0]["success"], true);
+    assert_eq!(body["results"][1]["error"], "cannot_target_self");
+    let (status, _) = ctx
+        .request_json_as(&second_token, Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let response = ctx
+        .app
+        .clone()
+        .oneshot(login_request(&second_email)?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/admin/users/bulk",
+            Some(json!({ "action": "unban", "user_ids": [second_id.to_string()] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let response = ctx
+        .app
+        .clone()
+        .oneshot(login_request(&second_email)?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let (status, body) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/admin/users/bulk",
+            Some(json!({ "action": "delete", "user_ids": [first_id.to_string()] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["results"][0]["success"], true);
+    assert!(paracord_db::users::get_user_by_id(&ctx.db, first_id)
+        .await?
+        .is_none());
+
+    let (status, body) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/admin/users/bulk",
+            Some(json!({ "action": "suspend", "user_ids": [first_id.to_string()] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "VALIDATION_FAILED");
+
+    let events =
+        paracord_db::security_events::list_events(&ctx.db, Some("admin.users.bulk"), None, 10)
+            .await?;
+    assert_eq!(events.len(), 4);
+    let force_logout = events
+        .iter()
+        .find(|e| {
+            e.details
+                .as_ref()
+                .is_some_and(|d| d["action"] == "force_logout")
+        })
+        .context("force logout should be logged")?;
+    let details = force_logout.details.as_ref().unwrap();
+    assert_eq!(
+        (details["succeeded"].as_u64(), details["failed"].as_u64()),
+        (Some(1), Some(2))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn registration_waits_for_email_verification_when_required() -> anyhow::Result<()> {
+    use sha2::{Digest, Sha256};
+
+    let ctx = TestContext::new().await?;
+    ctx.state.runtime.write().await.require_email_verification = true;
+    let nonce = Uuid::new_v4().simple().to_string();
+    let email = format!("{nonce}@example.com");
+    let send =
+        |uri: &str, method: Method, body: Option<Value>| -> anyhow::Result<_> {
+            let mut builder = Request::builder().method(method).uri(uri);
+            if body.is_some() {
+                builder = builder.header(header::CONTENT_TYPE, "application/json");
+            }
+            let mut request =
+                builder.body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))?;
+            request.extensions_mut().insert(axum::extract::ConnectInfo(
+                std::net::SocketAddr::from(([127, 0, 0, 5], 40_003)),
+            ));
+            Ok(ctx.app.clone().oneshot(request))
+        };
+    let login_body = json!({ "email": email, "password": "IntegrationPass123!" });
+
+    let response = send(
+        "/api/v1/auth/register",
+        Method::POST,
+        Some(json!({
+            "email": email,
+            "username": format!("verify_{}", &nonce[..12]),
+            "password": "IntegrationPass123!",
+        })),
+    )?
+    .await?;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await?)?;
+    assert_eq!(body["verification_required"], true);
+    assert!(body.get("token").is_none());
+    let user_id: i64 = body["user"]["id"].as_str().context("user id")?.parse()?;
+
+    let response = send("/api/v1/auth/login", Method::POST, Some(login_body.clone()))?.await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await?)?;
+    assert_eq!(body["code"], "EMAIL_NOT_VERIFIED");
+
+    // The emailed token is random, so plant a known one for the account.
+    let token = "known-verification-token";
+    paracord_db::email_verifications::create_email_verification(
+        &ctx.db,
+        &paracord_util::hex::hex_encode(&Sha256::digest(token.as_bytes())),
+        user_id,
+        Utc::now().timestamp() + 600,
+    )
+    .await?;
+    let verify_uri = format!("/api/v1/auth/verify-email?token={token}");
+    let response = send(&verify_uri, Method::GET, None)?.await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send(&verify_uri, Method::GET, None)?.await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = send("/api/v1/auth/login", Method::POST, Some(login_body))?.await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Fresh unverified accounts survive the sweep.
+    paracord_db::users::update_user_flags(&ctx.db, user_id, paracord_core::USER_FLAG_UNVERIFIED)
+        .await?;
+    assert_eq!(
+        paracord_api::routes::auth::prune_unverified_accounts_once(&ctx.state).await?,
+        0
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn login_from_new_device_is_flagged() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let nonce = Uuid::new_v4().simple().to_string();
+    let email = format!("{nonce}@example.com");
+    let send =
+        |uri: &str, user_agent: &str, body: Value| -> anyhow::Result<_> {
+            let mut request = Request::builder()
+                .method(Method::POST)
+                .uri(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::USER_AGENT, user_agent)
+                .body(Body::from(body.to_string()))?;
+            request.extensions_mut().insert(axum::extract::ConnectInfo(
+                std::net::SocketAddr::from(([127, 0, 0, 6], 40_004)),
+            ));
+            Ok(ctx.app.clone().oneshot(request))
+        };
+    let login_body = json!({ "email": email, "password": "IntegrationPass123!" });
+
+    let response = send(
+        "/api/v1/auth/register",
+        "LaptopBrowser/1.0",
+        json!({
+            "email": email,
+            "username": format!("device_{}", &nonce[..12]),
+            "password": "IntegrationPass123!",
+        }),
+    )?
+    .await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let new_device_events = || async {
+        paracord_db::security_events::list_events(&ctx.db, Some("auth.login.new_device"), None, 10)
+            .await
+            .map(|events| events.len())
+    };
+
+    let response = send(
+        "/api/v1/auth/login",
+        "LaptopBrowser/1.0",
+        login_body.clone(),
+    )?
+    .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(new_device_events().await?, 0);
+
+    let response = send("/api/v1/auth/login", "PhoneApp/2.0", login_body.clone())?.await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(new_device_events().await?, 1);
+
+    let response = send("/api/v1/auth/login", "PhoneApp/2.0", login_body)?.await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(new_device_events().await?, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn notification_settings_shape_unread_counts() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Quiet Guild").await?;
+    let space_id: i64 = guild_id.parse()?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "busy").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+    let read_path = format!("/api/v1/channels/{channel_id}/read");
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, member_id, space_id).await?;
+
+    for content in [
+        "@everyone standup".to_string(),
+        format!("hey <@{member_id}>"),
+        "plain".to_string(),
+    ] {
+        let (status, _) = ctx
+            .request_json(
+                Method::POST,
+                &messages_path,
+                Some(json!({ "content": content })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    let read_counts = || async {
+        let (status, counts) = ctx
+            .request_json_as(
+                &member_token,
+                Method::PUT,
+                &read_path,
+                Some(json!({ "last_message_id": "0" })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::OK, "unexpected payload: {counts}");
+        anyhow::Ok(counts)
+    };
+
+    let counts = read_counts().await?;
+    assert_eq!(counts["unread_count"], 3);
+    assert_eq!(counts["mention_count"], 2);
+    assert_eq!(counts["muted"], false);
+
+    // Space-wide @everyone suppression plus a channel mute that has not expired.
+    let muted_until = (Utc::now() + chrono::Duration::hours(1)).to_rfc3339();
+    let (status, settings) = ctx
+        .request_json_as(
+            &member_token,
+            Method::PATCH,
+            "/api/v1/users/@me/settings",
+            Some(json!({
+                "notification_settings": [
+                    { "guild_id": guild_id, "level": "mentions", "suppress_everyone": true },
+                    { "channel_id": channel_id, "muted": true, "muted_until": muted_until },
+                ],
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {settings}");
+    assert_eq!(
+        settings["notification_settings"].as_array().map(Vec::len),
+        Some(2)
+    );
+
+    let counts = read_counts().await?;
+    assert_eq!(counts["unread_count"], 0);
+    assert_eq!(counts["mention_count"], 1);
+    assert_eq!(counts["muted"], true);
+    let (status, read_states) = ctx
+        .request_json_as(
+            &member_token,
+            Method::GET,
+            "/api/v1/users/@me/read-states",
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {read_states}");
+    assert_eq!(read_states, json!([counts]));
+
+    // An expired mute no longer hides unread messages, which were kept.
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::PATCH,
+            "/api/v1/users/@me/settings",
+            Some(json!({
+                "notification_settings": [{
+                    "channel_id": channel_id,
+                    "muted": true,
+                    "muted_until": (Utc::now() - chrono::Duration::minutes(1)).to_rfc3339(),
+                }],
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let counts = read_counts().await?;
+    assert_eq!(counts["unread_count"], 3);
+    assert_eq!(counts["muted"], false);
+
+    let (status, body) = ctx
+        .request_json_as(
+            &member_token,
+            Method::PATCH,
+            "/api/v1/users/@me/settings",
+            Some(json!({
+                "notification_settings": [{ "channel_id": channel_id, "level": "loud" }],
+            })),
+        )
+        .await?;
+    assert_eq!(
+        status,
+        StatusCode::BAD_REQUEST,
+        "unexpected payload: {body}"
+    );
+
+    // Settings for spaces the user is not in are rejected.
+    let other_guild_id = create_guild(&ctx, "Elsewhere").await?;
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::PATCH,
+            "/api/v1/users/@me/settings",
+            Some(json!({
+                "notification_settings": [{ "guild_id": other_guild_id, "muted": true }],
+            })),
+        )
+        .await?;
+    assert_ne!(status, StatusCode::OK);
+
+    let (status, settings) = ctx
+        .request_json_as(
+            &member_token,
+            Method::GET,
+            "/api/v1/users/@me/settings",
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(settings["notification_settings"][0]["level"], "mentions");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn push_subscriptions_target_mentions_per_notification_settings() -> anyhow::Result<()> {
+    let mut ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Push Guild").await?;
+    let space_id: i64 = guild_id.parse()?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "pings").await?;
+    let author_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, member_id, space_id).await?;
+
+    // A browser's p256dh key is an uncompressed P-256 point, like a VAPID public key.
+    let p256dh = paracord_util::web_push::VapidKey::generate()
+        .public_key()
+        .to_string();
+    let endpoint = "https://push.example.com/send/abc123";
+    let subscription = json!({
+        "endpoint": endpoint,
+        "keys": { "p256dh": p256dh, "auth": "AAECAwQFBgcICQoLDA0ODw" },
+    });
+    let subscriptions_path = "/api/v1/users/@me/push-subscriptions";
+
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::POST,
+            subscriptions_path,
+            Some(subscription.clone()),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "push is disabled");
+
+    let disabled_app = ctx.app.clone();
+    let mut push_state = ctx.state.clone();
+    push_state.push = paracord_core::push::PushDispatcher::start(
+        ctx.db.clone(),
+        paracord_util::web_push::VapidKey::generate(),
+        "mailto:admin@example.com".to_string(),
+    )?;
+    ctx.app = paracord_api::build_router().with_state(push_state);
+
+    let (status, body) = ctx
+        .request_json_as(
+            &member_token,
+            Method::GET,
+            "/api/v1/push/vapid-public-key",
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["public_key"]
+        .as_str()
+        .is_some_and(|key| !key.is_empty()));
+
+    for rejected in [
+        json!({ "endpoint": "http://push.example.com/send", "keys": subscription["keys"] }),
+        json!({ "endpoint": "https://127.0.0.1/send", "keys": subscription["keys"] }),
+        json!({ "endpoint": "https://push.internal/send", "keys": subscription["keys"] }),
+        json!({ "endpoint": endpoint, "keys": { "p256dh": "not-a-key", "auth": "AAECAwQFBgcICQoLDA0ODw" } }),
+    ] {
+        let (status, body) = ctx
+            .request_json_as(
+                &member_token,
+                Method::POST,
+                subscriptions_path,
+                Some(rejected),
+            )
+            .await?;
+        assert_eq!(
+            status,
+            StatusCode::BAD_REQUEST,
+            "unexpected payload: {body}"
+        );
+    }
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::POST,
+            subscriptions_path,
+            Some(subscription.clone()),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    // Another account can't take over a registered endpoint.
+    let (status, _) = ctx
+        .request_json(Method::POST, subscriptions_path, Some(subscription.clone()))
+        .await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    ctx.app = disabled_app;
+
+    let channel: i64 = channel_id.parse()?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+    let send = |content: String| {
+        let ctx = &ctx;
+        let path = &messages_path;
+        async move {
+            let (status, body) = ctx
+                .request_json(Method::POST, path, Some(json!({ "content": content })))
+                .await?;
+            assert_eq!(status, StatusCode::CREATED, "unexpected payload: {body}");
+            let message_id: i64 = body["id"].as_str().context("message id")?.parse()?;
+            anyhow::Ok(paracord_core::push::PushJob {
+                message_id,
+                channel_id: channel,
+                guild_id: Some(space_id),
+                author_id,
+            })
+        }
+    };
+    let recipients = |job: paracord_core::push::PushJob| {
+        let db = ctx.db.clone();
+        async move {
+            let resolved = paracord_core::push::resolve_recipients(&db, &job).await?;
+            anyhow::Ok(resolved.iter().map(|r| r.user_id).collect::<Vec<_>>())
+        }
+    };
+
+    let plain = send("no pings here".to_string()).await?;
+    let mention = send(format!("hey <@{member_id}>")).await?;
+    let everyone = send("@everyone standup".to_string()).await?;
+    assert!(recipients(plain).await?.is_empty());
+    assert_eq!(recipients(mention).await?, vec![member_id]);
+    assert_eq!(recipients(everyone).await?, vec![member_id]);
+
+    // Suppressing @everyone in the space keeps direct mentions only.
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::PATCH,
+            "/api/v1/users/@me/settings",
+            Some(json!({
+                "notification_settings": [
+                    { "guild_id": guild_id, "level": "mentions", "suppress_everyone": true },
+                ],
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(recipients(mention).await?, vec![member_id]);
+    assert!(recipients(everyone).await?.is_empty());
+
+    // Muting the channel silences pushes entirely.
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::PATCH,
+            "/api/v1/users/@me/settings",
+            Some(json!({
+                "notification_settings": [{ "channel_id": channel_id, "muted": true }],
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(recipients(mention).await?.is_empty());
+
+    let delete_body = Some(json!({ "endpoint": endpoint }));
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::DELETE,
+            subscriptions_path,
+            delete_body.clone(),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::DELETE,
+            subscriptions_path,
+            delete_body,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+#[tokio::test]
+async fn blocks_stop_new_dm_content_but_keep_history() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Shared Guild").await?;
+    let owner_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    paracord_db::members::add_member(&ctx.db, member_id, guild_id.parse()?).await?;
+
+    let (status, dm) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/users/@me/dms",
+            Some(json!({ "recipient_id": member_id.to_string() })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {dm}");
+    let dm_id = dm["id"].as_str().context("dm id")?.to_string();
+    let messages_path = format!("/api/v1/channels/{dm_id}/messages");
+    let encrypted = json!({
+        "content": "",
+        "e2ee": { "version": 1, "nonce": "bm9uY2U", "ciphertext": "Y2lwaGVydGV4dA" },
+    });
+    let (status, _) = ctx
+        .request_json(Method::POST, &messages_path, Some(encrypted.clone()))
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // The recipient blocks the sender after the conversation started.
+    paracord_db::relationships::block_user(&ctx.db, member_id, owner_id).await?;
+
+    for token in [&ctx.token, &member_token] {
+        let (status, body) = ctx
+            .request_json_as(token, Method::POST, &messages_path, Some(encrypted.clone()))
+            .await?;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["code"], "USER_BLOCKED");
+    }
+    let (status, body) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{dm_id}/attachments/init"),
+            Some(json!({ "filename": "data.bin", "size": 10 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "USER_BLOCKED");
+    let (status, body) = ctx
+        .request_json_as(
+            &member_token,
+            Method::POST,
+            "/api/v1/users/@me/dms",
+            Some(json!({ "recipient_id": owner_id.to_string() })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "USER_BLOCKED");
+
+    // History from before the block is still readable by both sides.
+    for token in [&ctx.token, &member_token] {
+        let (status, history) = ctx
+            .request_json_as(token, Method::GET, &messages_path, None)
+            .await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(history.as_array().map(Vec::len), Some(1));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn profile_fields_are_validated_and_bio_can_be_kept_to_friends() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let owner_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+    let viewer_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let viewer_id = paracord_core::auth::validate_token(&viewer_token, &ctx.jwt_keys)?.sub;
+
+    let (status, me) = ctx
+        .request_json(
+            Method::PATCH,
+            "/api/v1/users/@me",
+            Some(
+                json!({ "bio": "Hello there", "pronouns": "they/them", "accent_color": "#5865F2" }),
+            ),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {me}");
+    assert_eq!(me["pronouns"], "they/them");
+    assert_eq!(me["accent_color"], 0x5865F2);
+    for invalid in [
+        json!({ "accent_color": "#12345" }),
+        json!({ "accent_color": 0x1000000 }),
+        json!({ "pronouns": "x".repeat(41) }),
+        json!({ "banner_hash": "deadbeef" }),
+    ] {
+        let (status, _) = ctx
+            .request_json(Method::PATCH, "/api/v1/users/@me", Some(invalid))
+            .await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    let mut png = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::new_rgb8(3000, 1200).write_to(&mut png, image::ImageFormat::Png)?;
+    let boundary = "paracord-banner-boundary";
+    let mut body = format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"banner.png\"\r\nContent-Type: image/png\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(&png.into_inner());
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    let request = Request::builder()
+        .method(Method::PUT)
+        .uri("/api/v1/users/@me/banner")
+        .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+        .header(
+            header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={boundary}"),
+        )
+        .body(Body::from(body))?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let me: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await?)?;
+    let banner_hash = me["banner_hash"]
+        .as_str()
+        .context("banner hash")?
+        .to_string();
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/api/v1/users/{owner_id}/banners/{banner_hash}"))
+        .header(header::AUTHORIZATION, format!("Bearer {viewer_token}"))
+        .body(Body::empty())?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+    let banner = image::load_from_memory(&to_bytes(response.into_body(), usize::MAX).await?)?;
+    assert_eq!((banner.width(), banner.height()), (1500, 600));
+
+    let profile_path = format!("/api/v1/users/{owner_id}");
+    let (status, profile) = ctx
+        .request_json_as(&viewer_token, Method::GET, &profile_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(profile["bio"], "Hello there");
+    assert_eq!(profile["banner_hash"], banner_hash.as_str());
+    assert!(profile.get("email").is_none());
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            "/api/v1/users/@me/settings",
+            Some(json!({ "friends_only_profile": true })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (_, profile) = ctx
+        .request_json_as(&viewer_token, Method::GET, &profile_path, None)
+        .await?;
+    assert!(profile["bio"].is_null());
+    assert!(profile["pronouns"].is_null());
+    assert_eq!(profile["accent_color"], 0x5865F2);
+    let (_, own) = ctx.request_json(Method::GET, &profile_path, None).await?;
+    assert_eq!(own["bio"], "Hello there");
+
+    paracord_db::relationships::create_relationship(&ctx.db, viewer_id, owner_id, 1).await?;
+    paracord_db::relationships::create_relationship(&ctx.db, owner_id, viewer_id, 1).await?;
+    let (_, profile) = ctx
+        .request_json_as(&viewer_token, Method::GET, &profile_path, None)
+        .await?;
+    assert_eq!(profile["bio"], "Hello there");
+    assert_eq!(profile["pronouns"], "they/them");
+
+    paracord_db::relationships::block_user(&ctx.db, owner_id, viewer_id).await?;
+    let (status, _) = ctx
+        .request_json_as(&viewer_token, Method::GET, &profile_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = ctx
+        .request_json(Method::DELETE, "/api/v1/users/@me/banner", None)
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    assert!(me["banner_hash"].is_null());
+    Ok(())
+}
+
+async fn put_image(
+    ctx: &TestContext,
+    path: &str,
+    data: Vec<u8>,
+) -> anyhow::Result<(StatusCode, Value)> {
+    let boundary = "paracord-image-boundary";
+    let mut body = format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"upload\"\r\nContent-Type: application/octet-stream\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(&data);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    let request = Request::builder()
+        .method(Method::PUT)
+        .uri(path)
+        .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+        .header(
+            header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={boundary}"),
+        )
+        .body(Body::from(body))?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await?;
+    Ok((
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    ))
+}
+
+#[tokio::test]
+async fn avatars_and_guild_icons_are_served_as_sized_variants_without_metadata(
+) -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let user_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+
+    // A JPEG carrying an EXIF segment with a recognizable payload.
+    let mut jpeg = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::new_rgb8(300, 200).write_to(&mut jpeg, image::ImageFormat::Jpeg)?;
+    let jpeg = jpeg.into_inner();
+    let exif = b"Exif\0\0PARACORD-GPS-SECRET";
+    let mut with_exif = jpeg[..2].to_vec();
+    with_exif.extend_from_slice(&[0xFF, 0xE1]);
+    with_exif.extend_from_slice(&(exif.len() as u16 + 2).to_be_bytes());
+    with_exif.extend_from_slice(exif);
+    with_exif.extend_from_slice(&jpeg[2..]);
+
+    let (status, me) = put_image(&ctx, "/api/v1/users/@me/avatar", with_exif).await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {me}");
+    let avatar_hash = me["avatar_hash"]
+        .as_str()
+        .context("avatar hash")?
+        .to_string();
+
+    let avatar_path = format!("/api/v1/users/{user_id}/avatars/{avatar_hash}");
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("{avatar_path}?size=30"))
+        .body(Body::empty())?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+    assert_eq!(
+        response.headers()[header::CACHE_CONTROL],
+        "public, max-age=31536000, immutable"
+    );
+    let etag = response.headers()[header::ETAG].clone();
+    let body = to_bytes(response.into_body(), usize::MAX).await?;
+    assert!(!body.windows(6).any(|window| window == b"SECRET"));
+    let avatar = image::load_from_memory(&body)?;
+    assert_eq!((avatar.width(), avatar.height()), (32, 32));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("{avatar_path}?size=30"))
+        .header(header::IF_NONE_MATCH, etag)
+        .body(Body::empty())?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(&avatar_path)
+        .body(Body::empty())?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    let avatar = image::load_from_memory(&to_bytes(response.into_body(), usize::MAX).await?)?;
+    assert_eq!((avatar.width(), avatar.height()), (256, 256));
+
+    let mut gif = Vec::new();
+    {
+        let mut encoder = image::codecs::gif::GifEncoder::new(&mut gif);
+        for shade in [0u8, 255] {
+            let frame = image::RgbaImage::from_pixel(40, 40, image::Rgba([shade, 0, 0, 255]));
+            encoder.encode_frame(image::Frame::new(frame))?;
+        }
+    }
+    let (status, _) = put_image(&ctx, "/api/v1/users/@me/avatar", gif.clone()).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let guild_id = create_guild(&ctx, "Icon Guild").await?;
+    let icon_path = format!("/api/v1/guilds/{guild_id}/icon");
+    let (status, _) = put_image(&ctx, &icon_path, gif.clone()).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    paracord_db::guilds::set_space_features(
+        &ctx.db,
+        guild_id.parse()?,
+        paracord_db::guilds::SPACE_FEATURE_ANIMATED_ICON,
+    )
+    .await?;
+    let (status, guild) = put_image(&ctx, &icon_path, gif).await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {guild}");
+    let icon_hash = guild["icon_hash"].as_str().context("icon hash")?;
+    assert!(icon_hash.starts_with("a_"));
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!(
+            "/api/v1/guilds/{guild_id}/icons/{icon_hash}?size=64"
+        ))
+        .body(Body::empty())?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/gif");
+    let icon = image::load_from_memory(&to_bytes(response.into_body(), usize::MAX).await?)?;
+    assert_eq!((icon.width(), icon.height()), (64, 64));
+
+    // The icon can only be cleared, not set to an arbitrary hash, by PATCH.
+    let guild_path = format!("/api/v1/guilds/{guild_id}");
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &guild_path,
+            Some(json!({ "icon": "0123456789abcdef" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, cleared) = ctx
+        .request_json(Method::PATCH, &guild_path, Some(json!({ "icon": "" })))
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {cleared}");
+    assert!(cleared["icon_hash"].is_null());
+
+    let (status, _) = ctx
+        .request_json(Method::DELETE, "/api/v1/users/@me/avatar", None)
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(&avatar_path)
+        .body(Body::empty())?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+#[tokio::test]
+async fn custom_status_is_stored_published_and_expires() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Status Guild").await?;
+    let owner_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+
+    for invalid in [
+        json!({ "text": "\n\t" }),
+        json!({ "text": "x".repeat(129) }),
+        json!({ "text": "Back soon", "expires_at": (Utc::now() - Duration::minutes(1)).to_rfc3339() }),
+    ] {
+        let (status, _) = ctx
+            .request_json(
+                Method::PUT,
+                "/api/v1/users/@me/custom-status",
+                Some(invalid),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    // Without a gateway session the status is only stored.
+    let (status, stored) = ctx
+        .request_json(
+            Method::PUT,
+            "/api/v1/users/@me/custom-status",
+            Some(json!({ "text": "Heads\u{0} down\n", "emoji": "🎧" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {stored}");
+    assert_eq!(stored["text"], "Heads down");
+    assert!(ctx
+        .state
+        .user_presences
+        .read()
+        .await
+        .get(&owner_id)
+        .is_none());
+    let (_, settings) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me/settings", None)
+        .await?;
+    assert_eq!(settings["custom_status"]["emoji"], "🎧");
+
+    // Once connected, changes reach the live presence and the member list.
+    ctx.state.user_presences.write().await.insert(
+        owner_id,
+        json!({ "user_id": owner_id.to_string(), "status": "online", "activities": [] }),
+    );
+    let expires_at = Utc::now() + Duration::minutes(30);
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            "/api/v1/users/@me/custom-status",
+            Some(json!({ "text": "In a meeting", "expires_at": expires_at.to_rfc3339() })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (_, members) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/members"),
+            None,
+        )
+        .await?;
+    let owner_key = owner_id.to_string();
+    let owner = members["members"]
+        .as_array()
+        .and_then(|members| members.iter().find(|m| m["user_id"] == owner_key))
+        .context("owner in member list")?;
+    assert_eq!(owner["presence"]["custom_status"]["text"], "In a meeting");
+    assert!(owner["presence"]["custom_status"]["emoji"].is_null());
+
+    paracord_db::custom_statuses::set_custom_status(
+        &ctx.db,
+        owner_id,
+        Some("In a meeting"),
+        None,
+        Some(Utc::now() - Duration::seconds(1)),
+    )
+    .await?;
+    assert_eq!(
+        paracord_core::custom_status::sweep_expired(&ctx.state).await,
+        1
+    );
+    assert!(ctx.state.user_presences.read().await[&owner_id]["custom_status"].is_null());
+    let (_, settings) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me/settings", None)
+        .await?;
+    assert!(settings["custom_status"].is_null());
+    Ok(())
+}
+
+#[tokio::test]
+async fn hoisted_roles_group_members_and_carry_icons() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let owner_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+    let guild_id = create_guild(&ctx, "Hoist Guild").await?;
+    let roles_path = format!("/api/v1/guilds/{guild_id}/roles");
+
+    let mut role_ids = Vec::new();
+    for (name, hoist) in [("Staff", true), ("Regular", true), ("Pinged", false)] {
+        let (status, role) = ctx
+            .request_json(
+                Method::POST,
+                &roles_path,
+                Some(json!({ "name": name, "hoist": hoist })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED, "unexpected payload: {role}");
+        assert_eq!(role["hoist"], hoist);
+        assert!(role["icon"].is_null());
+        role_ids.push(role["id"].as_str().context("role id")?.to_string());
+    }
+    let (staff_role, regular_role, pinged_role) = (&role_ids[0], &role_ids[1], &role_ids[2]);
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &roles_path,
+            Some(json!([
+                { "id": pinged_role, "position": 3 },
+                { "id": staff_role, "position": 2 },
+                { "id": regular_role, "position": 1 },
+            ])),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/guilds/{guild_id}/members/{owner_id}"),
+            Some(json!({ "roles": [regular_role, staff_role, pinged_role] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // The highest hoisted role wins, even with a higher non-hoisted role.
+    let (status, members) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/members"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        members["members"][0]["hoisted_role_id"],
+        staff_role.as_str()
+    );
+
+    let mut png = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::new_rgb8(300, 120).write_to(&mut png, image::ImageFormat::Png)?;
+    let boundary = "paracord-role-icon-boundary";
+    let mut body = format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"icon.png\"\r\nContent-Type: image/png\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(&png.into_inner());
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    let icon_path = format!("{roles_path}/{staff_role}/icon");
+    let request = Request::builder()
+        .method(Method::PUT)
+        .uri(&icon_path)
+        .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+        .header(
+            header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={boundary}"),
+        )
+        .body(Body::from(body))?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let role: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await?)?;
+    let icon_hash = role["icon"].as_str().context("icon hash")?.to_string();
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("{roles_path}/{staff_role}/icons/{icon_hash}"))
+        .body(Body::empty())?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let icon = image::load_from_memory(&to_bytes(response.into_body(), usize::MAX).await?)?;
+    assert_eq!((icon.width(), icon.height()), (64, 64));
+
+    let (status, _) = ctx.request_json(Method::DELETE, &icon_path, None).await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, roles) = ctx.request_json(Method::GET, &roles_path, None).await?;
+    let staff = roles
+        .as_array()
+        .and_then(|roles| roles.iter().find(|r| r["id"] == staff_role.as_str()))
+        .context("staff role")?;
+    assert!(staff["icon"].is_null());
+    Ok(())
+}
+
+#[tokio::test]
+async fn member_joins_and_leaves_are_announced_in_the_system_channel() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Announce Guild").await?;
+    let space_id: i64 = guild_id.parse()?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "welcome").await?;
+    let guild_path = format!("/api/v1/guilds/{guild_id}");
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &guild_path,
+            Some(json!({ "system_channel_id": "12345" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, guild) = ctx
+        .request_json(
+            Method::PATCH,
+            &guild_path,
+            Some(json!({ "system_channel_id": channel_id })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {guild}");
+    assert_eq!(guild["system_channel_id"], channel_id.as_str());
+    assert_eq!(guild["system_channel_flags"], 0);
+
+    let (status, invite) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/invites"),
+            Some(json!({})),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let invite_path = format!(
+        "/api/v1/invites/{}",
+        invite["code"].as_str().unwrap_or_default()
+    );
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let member_id = paracord_core::auth::validate_token(&member_token, &ctx.jwt_keys)?.sub;
+    let (status, _) = ctx
+        .request_json_as(&member_token, Method::POST, &invite_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = ctx
+        .request_json_as(
+            &member_token,
+            Method::DELETE,
+            &format!("{guild_path}/members/@me"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // Joins are suppressed from here on; removals are still announced.
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &guild_path,
+            Some(json!({ "system_channel_flags": 1 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = ctx
+        .request_json_as(&member_token, Method::POST, &invite_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = ctx
+        .request_json(
+            Method::DELETE,
+            &format!("{guild_path}/members/{member_id}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, messages) = ctx.request_json(Method::GET, &messages_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let mut announced: Vec<(i64, String)> = messages
+        .as_array()
+        .context("messages should be an array")?
+        .iter()
+        .map(|m| {
+            (
+                m["type"].as_i64().unwrap_or_default(),
+                m["author"]["id"].as_str().unwrap_or_default().to_string(),
+            )
+        })
+        .collect();
+    announced.sort();
+    let member_key = member_id.to_string();
+    assert_eq!(
+        announced,
+        vec![
+            (7, member_key.clone()),
+            (9, member_key.clone()),
+            (10, member_key)
+        ]
+    );
+
+    // A burst of removals stops being announced once the budget is spent.
+    let limit = paracord_core::system_messages::ANNOUNCEMENT_LIMIT.max_requests as usize;
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let mut posted = 0;
+    for _ in 0..limit + 5 {
+        if paracord_core::system_messages::create_member_event_message(
+            &ctx.db,
+            space_id,
+            member_id,
+            paracord_core::system_messages::MemberEvent::Kick,
+            now_ms,
+        )
+        .await?
+        .is_some()
+        {
+            posted += 1;
+        }
+    }
+    assert_eq!(posted, limit - 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn invite_analytics_trace_joins_back_to_their_invite() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Traced Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "lobby").await?;
+    let owner_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+    let analytics_path = format!("/api/v1/guilds/{guild_id}/invites/analytics");
+
+    let mut codes = Vec::new();
+    for _ in 0..2 {
+        let (status, invite) = ctx
+            .request_json(
+                Method::POST,
+                &format!("/api/v1/channels/{channel_id}/invites"),
+                Some(json!({})),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+        codes.push(invite["code"].as_str().unwrap_or_default().to_string());
+    }
+
+    let mut joiners = Vec::new();
+    for code in [&codes[0], &codes[0], &codes[1]] {
+        let token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+        joiners.push(paracord_core::auth::validate_token(&token, &ctx.jwt_keys)?.sub);
+        let path = format!("/api/v1/invites/{code}");
+        let (status, _) = ctx
+            .request_json_as(&token, Method::POST, &path, None)
+            .await?;
+        assert_eq!(status, StatusCode::OK);
+        // Accepting again as a member neither counts nor records a use.
+        let (status, _) = ctx
+            .request_json_as(&token, Method::POST, &path, None)
+            .await?;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let member_token = create_authenticated_user_token(&ctx.db, &ctx.jwt_keys).await?;
+    let (status, _) = ctx
+        .request_json_as(&member_token, Method::GET, &analytics_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, invites) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/invites"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let busiest = invites
+        .as_array()
+        .and_then(|list| list.iter().find(|i| i["code"] == codes[0].as_str()))
+        .cloned()
+        .unwrap_or_default();
+    assert_eq!(busiest["uses"], 2);
+    assert_eq!(busiest["inviter_id"], owner_id.to_string());
+
+    let (status, _) = ctx
+        .request_json(
+            Method::DELETE,
+            &format!("/api/v1/invites/{}", codes[0]),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, analytics) = ctx.request_json(Method::GET, &analytics_path, None).await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {analytics}");
+    let top = analytics["invites"].as_array().cloned().unwrap_or_default();
+    assert_eq!(top.len(), 2);
+    assert_eq!(top[0]["code"], codes[0].as_str());
+    assert_eq!(top[0]["joins"], 2);
+    assert_eq!(top[0]["inviter_id"], owner_id.to_string());
+    // The revoked invite is still attributed, newest joiner first.
+    assert!(top[0]["invite"].is_null());
+    assert_eq!(top[0]["recent_joins"][0]["user_id"], joiners[1].to_string());
+    assert_eq!(top[0]["recent_joins"][1]["user_id"], joiners[0].to_string());
+    assert_eq!(top[1]["code"], codes[1].as_str());
+    assert_eq!(top[1]["joins"], 1);
+    assert_eq!(top[1]["invite"]["uses"], 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn message_length_and_attachment_caps_come_from_config() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Limits Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "limits").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+
+    let (status, limits) = ctx
+        .request_json(Method::GET, "/api/v1/limits", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(limits["max_message_length"], 4000);
+    assert_eq!(limits["max_attachments_per_message"], 10);
+
+    // Each ZWJ family is 25 bytes but a single character.
+    let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}";
+    let (status, message) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": family.repeat(4000) })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {message}");
+    let message_id = message["id"].as_str().unwrap_or_default().to_string();
+
+    let (status, rejected) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("{messages_path}/{message_id}"),
+            Some(json!({ "content": family.repeat(4001) })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        rejected["details"]["fields"]["content"],
+        "Message content must be 1-4000 characters"
+    );
+
+    let attachment_ids: Vec<String> = (1..=11).map(|id| id.to_string()).collect();
+    let (status, rejected) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "files", "attachment_ids": attachment_ids })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        rejected["details"]["fields"]["attachment_ids"],
+        "A message can have at most 10 attachments"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn messages_carry_entities_without_rewriting_content() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Entity Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "links").await?;
+    let content = format!("hi <#{channel_id}>: https://example.com/docs. `https://not.a.link`");
+
+    let (status, message) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            Some(json!({ "content": content })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {message}");
+    assert_eq!(message["content"], content.as_str());
+    let entities = message["entities"].as_array().cloned().unwrap_or_default();
+    let kinds: Vec<&str> = entities
+        .iter()
+        .filter_map(|entity| entity["type"].as_str())
+        .collect();
+    assert_eq!(kinds, vec!["channel_mention", "url", "inline_code"]);
+    assert_eq!(entities[0]["id"], channel_id.as_str());
+    assert_eq!(entities[1]["url"], "https://example.com/docs");
+    let url_offset = content.find("https").unwrap_or_default();
+    assert_eq!(entities[1]["offset"], url_offset);
+    assert_eq!(entities[1]["length"], "https://example.com/docs".len());
+    Ok(())
+}
+
+#[tokio::test]
+async fn cached_link_previews_are_served_as_embeds() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Preview Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "links").await?;
+    let cached = "https://example.com/article";
+    let failed = "https://example.org/missing";
+    for (url, title) in [(cached, Some("An article")), (failed, None)] {
+        paracord_db::link_previews::upsert_link_preview(
+            &ctx.db,
+            &paracord_db::link_previews::LinkPreviewRow {
+                url_hash: paracord_core::link_preview::url_hash(url),
+                url: url.to_string(),
+                title: title.map(str::to_string),
+                description: title.map(|_| "Read all about it".to_string()),
+                site_name: title.map(|_| "Example".to_string()),
+                image_url: None,
+                fetched_at: Utc::now(),
+            },
+        )
+        .await?;
+    }
+
+    let (status, message) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            Some(json!({ "content": format!("{failed} and {cached}") })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {message}");
+    assert_eq!(
+        message["embeds"],
+        json!([{
+            "type": "link",
+            "url": cached,
+            "title": "An article",
+            "description": "Read all about it",
+            "site_name": "Example",
+        }])
+    );
+
+    let message_id = message["id"].as_str().unwrap_or_default();
+    let (status, edited) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/channels/{channel_id}/messages/{message_id}"),
+            Some(json!({ "content": "no links any more" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {edited}");
+    assert_eq!(edited["embeds"], json!([]));
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            Some(json!({ "content": format!("again: {cached}") })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, page) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {page}");
+    let embed_counts: Vec<usize> = page
+        .as_array()
+        .expect("message page")
+        .iter()
+        .map(|message| message["embeds"].as_array().map_or(0, Vec::len))
+        .collect();
+    assert_eq!(embed_counts, vec![1, 0]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn cors_origins_added_in_admin_settings_apply_without_restart() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    paracord_api::install_cors_runtime_settings(ctx.state.runtime.clone());
+    let admin_id = paracord_core::auth::validate_token(&ctx.token, &ctx.jwt_keys)?.sub;
+    paracord_db::users::update_user_flags(&ctx.db, admin_id, paracord_core::USER_FLAG_ADMIN)
+        .await?;
+
+    let origin = "https://chat.example.com";
+    let preflight = || {
+        Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/api/v1/users/@me")
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .body(Body::empty())
+    };
+    let response = ctx.app.clone().oneshot(preflight()?).await?;
+    assert!(response
+        .headers()
+        .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        .is_none());
+
+    let (status, payload) = ctx
+        .request_json(
+            Method::PATCH,
+            "/api/v1/admin/settings",
+            Some(json!({ "cors_allowed_origins": "https://*.example.com" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "unexpected payload: {payload}");
+
+    let (status, payload) = ctx
+        .request_json(
+            Method::PATCH,
+            "/api/v1/admin/settings",
+            Some(json!({ "cors_allowed_origins": format!(" {origin}/ , http://localhost:8080") })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {payload}");
+    assert_eq!(
+        payload["cors_allowed_origins"],
+        format!("{origin}/,http://localhost:8080")
+    );
+
+    let response = ctx.app.clone().oneshot(preflight()?).await?;
+    assert_eq!(
+        response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+        Some(&header::HeaderValue::from_static(origin))
+    );
+    assert_eq!(
+        response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS),
+        Some(&header::HeaderValue::from_static("true"))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn slash_commands_dispatch_interactions_and_accept_one_reply() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Command Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "games").await?;
+
+    let (status, app) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/bots/applications",
+            Some(json!({
+                "name": "Dice",
+                "permissions": (Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES)
+                    .bits()
+                    .to_string(),
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {app}");
+    let app_id = app["id"].as_str().context("missing app id")?.to_string();
+    let bot_user_id: i64 = app["bot_user_id"]
+        .as_str()
+        .context("missing bot user id")?
+        .parse()?;
+    let (status, payload) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/oauth2/authorize",
+            Some(json!({ "application_id": app_id, "guild_id": guild_id })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {payload}");
+
+    let commands_path = format!("/api/v1/applications/{app_id}/commands");
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &commands_path,
+            Some(json!([{
+                "name": "roll",
+                "description": "Roll a die",
+                "options": [
+                    { "type": 3, "name": "label", "description": "Label" },
+                    { "type": 4, "name": "sides", "description": "Sides", "required": true },
+                ],
+            }])),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, commands) = ctx
+        .request_json(
+            Method::PUT,
+            &commands_path,
+            Some(json!([{
+                "name": "roll",
+                "description": "Roll a die",
+                "options": [
+                    {
+                        "type": 4,
+                        "name": "sides",
+                        "description": "Sides",
+                        "required": true,
+                        "choices": [{ "name": "d6", "value": 6 }, { "name": "d20", "value": 20 }],
+                    },
+                    { "type": 6, "name": "for", "description": "Roll for someone" },
+                ],
+            }])),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {commands}");
+    let command_id = commands[0]["id"]
+        .as_str()
+        .context("missing command id")?
+        .to_string();
+
+    let (status, listed) = ctx
+        .request_json(Method::GET, &format!("/api/v1/guilds/{guild_id}/commands"), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed[0]["id"], command_id.clone());
+
+    let mut bot_events =
+        ctx.state
+            .event_bus
+            .register_session("bot-session", bot_user_id, &[guild_id.parse()?]);
+
+    let interactions_path = format!("/api/v1/channels/{channel_id}/interactions");
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &interactions_path,
+            Some(json!({
+                "command_id": command_id,
+                "options": [{ "name": "sides", "value": 7 }],
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &interactions_path,
+            Some(json!({
+                "command_id": command_id,
+                "options": [{ "name": "sides", "value": 20 }, { "name": "for", "value": "12345" }],
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "non-members are not valid user options");
+
+    let (status, invoked) = ctx
+        .request_json(
+            Method::POST,
+            &interactions_path,
+            Some(json!({
+                "command_id": command_id,
+                "options": [{ "name": "sides", "value": 20 }],
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::ACCEPTED, "unexpected payload: {invoked}");
+    assert!(invoked.get("token").is_none());
+
+    let event = loop {
+        let event = bot_events.try_recv()?;
+        if event.event_type == "INTERACTION_CREATE" {
+            break event;
+        }
+    };
+    assert_eq!(event.target_user_ids.as_deref(), Some(&[bot_user_id][..]));
+    let interaction = event.payload.as_ref();
+    assert_eq!(interaction["id"], invoked["id"]);
+    assert_eq!(interaction["data"]["name"], "roll");
+    assert_eq!(
+        interaction["data"]["options"],
+        json!([{ "name": "sides", "type": 4, "value": 20 }])
+    );
+    let interaction_id = interaction["id"].as_str().context("missing id")?;
+    let token = interaction["token"].as_str().context("missing token")?;
+
+    let reply = json!({ "type": 4, "data": { "content": "You rolled a 17" } });
+    let (status, _) = ctx
+        .request_json_as(
+            "",
+            Method::POST,
+            &format!("/api/v1/interactions/{interaction_id}/not-the-token/callback"),
+            Some(reply.clone()),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let callback_path = format!("/api/v1/interactions/{interaction_id}/{token}/callback");
+    // A reply that cannot be posted leaves the token usable.
+    let (status, _) = ctx
+        .request_json_as(
+            "",
+            Method::POST,
+            &callback_path,
+            Some(json!({ "type": 4, "data": { "content": "  " } })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, message) = ctx
+        .request_json_as("", Method::POST, &callback_path, Some(reply.clone()))
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {message}");
+    assert_eq!(message["author"]["id"], bot_user_id.to_string());
+    assert_eq!(message["content"], "You rolled a 17");
+    assert_eq!(message["type"], 21);
+    assert_eq!(message["interaction"]["name"], "roll");
+
+    let (status, _) = ctx
+        .request_json_as("", Method::POST, &callback_path, Some(reply))
+        .await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    Ok(())
+}
+
+#[tokio::test]
+async fn bot_permissions_are_capped_by_scope_and_tokens_can_be_rotated() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Scoped Bot Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "general").await?;
+
+    let (status, app) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/bots/applications",
+            Some(json!({
+                "name": "Reader",
+                "permissions": (Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY)
+                    .bits()
+                    .to_string(),
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {app}");
+    let app_id = app["id"].as_str().context("missing app id")?.to_string();
+    let bot_user_id = app["bot_user_id"]
+        .as_str()
+        .context("missing bot user id")?
+        .to_string();
+    let old_token = app["token"].as_str().context("missing token")?.to_string();
+    let (status, payload) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/oauth2/authorize",
+            Some(json!({ "application_id": app_id, "guild_id": guild_id })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {payload}");
+
+    let (status, role) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/guilds/{guild_id}/roles"),
+            Some(json!({ "name": "Admin", "permissions": Permissions::ADMINISTRATOR.bits() })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {role}");
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/guilds/{guild_id}/members/{bot_user_id}"),
+            Some(json!({ "roles": [role["id"]] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // An administrator role does not lift the bot past its scope.
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+    let (status, _) = ctx
+        .request_json_with_authorization(
+            &format!("Bot {old_token}"),
+            Method::GET,
+            &messages_path,
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = ctx
+        .request_json_with_authorization(
+            &format!("Bot {old_token}"),
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "hello" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = ctx
+        .request_json_with_authorization(
+            &format!("Bot {old_token}"),
+            Method::POST,
+            &format!("/api/v1/guilds/{guild_id}/channels"),
+            Some(json!({ "name": "bot-made", "channel_type": 0 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let regenerate_path = format!("/api/v1/applications/{app_id}/token/regenerate");
+    let (status, _) = ctx
+        .request_json_with_authorization(
+            &format!("Bot {old_token}"),
+            Method::POST,
+            &regenerate_path,
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN, "only the owner may rotate the token");
+
+    let (status, rotated) = ctx.request_json(Method::POST, &regenerate_path, None).await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {rotated}");
+    let new_token = rotated["token"].as_str().context("missing token")?;
+    assert_ne!(new_token, old_token);
+
+    let (status, _) = ctx
+        .request_json_with_authorization(
+            &format!("Bot {old_token}"),
+            Method::GET,
+            "/api/v1/users/@me",
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, me) = ctx
+        .request_json_with_authorization(
+            &format!("Bot {new_token}"),
+            Method::GET,
+            "/api/v1/users/@me",
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["id"], bot_user_id);
+
+    let logged = paracord_db::security_events::list_events(
+        &ctx.db,
+        Some("bot.token.regenerate"),
+        None,
+        10,
+    )
+    .await?;
+    assert_eq!(logged.len(), 1);
+    assert_eq!(logged[0].target_user_id, Some(bot_user_id.parse()?));
+    Ok(())
+}
@@ -24,7 +24,9 @@ struct TestHarness {
 impl TestHarness {
     async fn new_without_migrations() -> anyhow::Result<Self> {
         let db = paracord_db::create_pool("sqlite::memory:", 1).await?;
-        paracord_api::install_http_rate_limiter();
+        paracord_api::install_http_rate_limiter(
+            paracord_core::rate_limit::HttpRateLimits::default(),
+        );
 
         let storage_dir = tempfile::tempdir()?;
         let media_dir = tempfile::tempdir()?;
@@ -41,10 +43,13 @@ impl TestHarness {
             event_bus: paracord_core::events::EventBus::default(),
             config: AppConfig {
                 jwt_secret: "integration-test-secret".to_string(),
+                jwt_keys: paracord_core::auth::JwtKeySet::from_secret("integration-test-secret"),
                 jwt_expiry_seconds: 3600,
                 registration_enabled: true,
                 allow_username_login: false,
                 require_email: true,
+                password_policy: Default::default(),
+                breached_password_api_url: None,
                 storage_path: storage_dir.path().to_string_lossy().into_owned(),
                 max_upload_size: 10 * 1024 * 1024,
                 livekit_api_key: livekit.api_key.clone(),
@@ -70,6 +75,17 @@ impl TestHarness {
                 federation_file_cache_enabled: false,
                 federation_file_cache_max_size: 0,
                 federation_file_cache_ttl_hours: 0,
+                audit_log_retention_days: None,
+                security_event_retention_days: None,
+                http_rate_limits: paracord_core::rate_limit::HttpRateLimits::default(),
+                inline_content_types: vec!["image/png".to_string()],
+                message_edit_history_limit: 25,
+                slowmode_exempt_bots: true,
+                max_pins_per_channel: 50,
+                max_message_length: 4000,
+                max_attachments_per_message: 10,
+                max_static_emojis: 50,
+                max_animated_emojis: 50,
             },
             runtime: Arc::new(RwLock::new(RuntimeSettings::default())),
             voice: Arc::new(VoiceManager::new(livekit)),
@@ -86,7 +102,11 @@ impl TestHarness {
             permission_cache: build_permission_cache(),
             federation_service: None,
             member_index: Arc::new(paracord_core::member_index::MemberIndex::empty()),
+            presence_manager: Arc::new(paracord_core::presence_manager::PresenceManager::new()),
             native_media: None,
+            mailer: paracord_mail::Mailer::disabled(),
+            push: paracord_core::push::PushDispatcher::disabled(),
+            link_previews: paracord_core::link_preview::LinkPreviewer::disabled(),
         };
 
         let app = paracord_api::build_router().with_state(state);
@@ -112,3 +132,135 @@ async fn auth_refresh_without_migrations_still_reaches_handler() -> anyhow::Resu
 
     Ok(())
 }
+
+#[tokio::test]
+async fn login_burst_is_limited_without_starving_other_categories() -> anyhow::Result<()> {
+    let harness = TestHarness::new_without_migrations().await?;
+    let auth_limit = paracord_core::rate_limit::HttpRateLimits::default()
+        .get(paracord_core::rate_limit::RouteCategory::Auth)
+        .max_requests;
+
+    for _ in 0..auth_limit {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/v1/auth/login")
+            .header("content-type", "application/json")
+            .body(Body::from("{}"))?;
+        let response = harness.app.clone().oneshot(request).await?;
+        assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/auth/login")
+        .header("content-type", "application/json")
+        .body(Body::from("{}"))?;
+    let response = harness.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let headers = response.headers();
+    assert_eq!(
+        headers
+            .get("x-ratelimit-limit")
+            .and_then(|v| v.to_str().ok()),
+        Some(auth_limit.to_string().as_str())
+    );
+    assert_eq!(
+        headers
+            .get("x-ratelimit-remaining")
+            .and_then(|v| v.to_str().ok()),
+        Some("0")
+    );
+    let retry_after: i64 = headers
+        .get("retry-after")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .expect("retry-after header");
+    assert!((1..=60).contains(&retry_after));
+    assert!(headers.contains_key("x-ratelimit-reset"));
+
+    // Reads are charged to their own bucket and are unaffected.
+    let request = Request::builder()
+        .method("GET")
+        .uri("/api/v1/auth/options")
+        .body(Body::empty())?;
+    let response = harness.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key("x-ratelimit-remaining"));
+    assert!(!response.headers().contains_key("retry-after"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn metrics_are_labeled_by_route_and_include_pool_stats() -> anyhow::Result<()> {
+    std::env::set_var("PARACORD_METRICS_TOKEN", "metrics-test-token");
+    let harness = TestHarness::new_without_migrations().await?;
+
+    let request = Request::builder()
+        .method("GET")
+        .uri("/api/v1/auth/options")
+        .body(Body::empty())?;
+    let response = harness.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = Request::builder()
+        .method("GET")
+        .uri("/metrics")
+        .header("authorization", "Bearer metrics-test-token")
+        .body(Body::empty())?;
+    let response = harness.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let body = String::from_utf8(body.to_vec())?;
+
+    assert!(body.contains(
+        "paracord_http_requests_total{method=\"GET\",route=\"/api/v1/auth/options\",status=\"200\"}"
+    ));
+    assert!(body.contains(
+        "paracord_http_request_duration_seconds_bucket{method=\"GET\",route=\"/api/v1/auth/options\",le=\"+Inf\"}"
+    ));
+    assert!(body.contains("paracord_http_rate_limit_checks_total{category=\"read\"}"));
+    assert!(body.contains("paracord_http_rate_limit_max_requests{category=\"auth\"}"));
+    let tracked_keys: i64 = body
+        .lines()
+        .find_map(|line| line.strip_prefix("paracord_http_rate_limit_tracked_keys "))
+        .and_then(|value| value.trim().parse().ok())
+        .expect("tracked key gauge");
+    assert!(tracked_keys >= 1, "the read above holds a bucket");
+    assert!(body.contains("paracord_db_pool_max_connections 1"));
+    assert!(body.contains("paracord_db_pool_connections{state=\"in_use\"}"));
+    assert!(body.contains("paracord_ws_connections_active"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn forged_forwarded_for_does_not_evade_rate_limit() -> anyhow::Result<()> {
+    let harness = TestHarness::new_without_migrations().await?;
+    let auth_limit = paracord_core::rate_limit::HttpRateLimits::default()
+        .get(paracord_core::rate_limit::RouteCategory::Auth)
+        .max_requests;
+    let peer = std::net::SocketAddr::from(([198, 51, 100, 77], 40_000));
+
+    // No trusted proxies are configured, so every request is charged to the
+    // socket address whatever X-Forwarded-For claims.
+    for attempt in 0..=auth_limit {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri("/api/v1/auth/login")
+            .header("content-type", "application/json")
+            .header("x-forwarded-for", format!("203.0.113.{attempt}"))
+            .body(Body::from("{}"))?;
+        request
+            .extensions_mut()
+            .insert(axum::extract::ConnectInfo(peer));
+        let response = harness.app.clone().oneshot(request).await?;
+        if attempt < auth_limit {
+            assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        } else {
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        }
+    }
+
+    Ok(())
+}
@@ -50,10 +50,13 @@ impl TestHarness {
             event_bus: paracord_core::events::EventBus::default(),
             config: AppConfig {
                 jwt_secret: "integration-test-secret".to_string(),
+                jwt_keys: paracord_core::auth::JwtKeySet::from_secret("integration-test-secret"),
                 jwt_expiry_seconds: 3600,
                 registration_enabled: true,
                 allow_username_login: false,
                 require_email: true,
+                password_policy: Default::default(),
+                breached_password_api_url: None,
                 storage_path: storage_dir.path().to_string_lossy().into_owned(),
                 max_upload_size: 10 * 1024 * 1024,
                 livekit_api_key: livekit.api_key.clone(),
@@ -79,6 +82,17 @@ impl TestHarness {
                 federation_file_cache_enabled: false,
                 federation_file_cache_max_size: 0,
                 federation_file_cache_ttl_hours: 0,
+                audit_log_retention_days: None,
+                security_event_retention_days: None,
+                http_rate_limits: paracord_core::rate_limit::HttpRateLimits::default(),
+                inline_content_types: vec!["image/png".to_string()],
+                message_edit_history_limit: 25,
+                slowmode_exempt_bots: true,
+                max_pins_per_channel: 50,
+                max_message_length: 4000,
+                max_attachments_per_message: 10,
+                max_static_emojis: 50,
+                max_animated_emojis: 50,
             },
             runtime: Arc::new(RwLock::new(RuntimeSettings::default())),
             voice: Arc::new(VoiceManager::new(livekit)),
@@ -95,7 +109,11 @@ impl TestHarness {
             permission_cache: build_permission_cache(),
             federation_service: None,
             member_index: Arc::new(paracord_core::member_index::MemberIndex::empty()),
+            presence_manager: Arc::new(paracord_core::presence_manager::PresenceManager::new()),
             native_media: None,
+            mailer: paracord_mail::Mailer::disabled(),
+            push: paracord_core::push::PushDispatcher::disabled(),
+            link_previews: paracord_core::link_preview::LinkPreviewer::disabled(),
         };
 
         let app = paracord_api::build_router().with_state(state);
@@ -222,12 +240,12 @@ async fn federation_media_token_requires_existing_room_membership() -> anyhow::R
         )
         .await?;
 
-    let body = json!({
-        "origin_server": origin_server,
-        "channel_id": "7002",
-        "user_id": "@alice:remote.example"
-    });
-    let body_bytes = serde_json::to_vec(&body)?;
+    // Signed bytes must match the request struct's field order, which is how
+    // the server re-serializes the body before verifying.
+    let body_bytes = format!(
+        r#"{{"origin_server":"{origin_server}","channel_id":"7002","user_id":"@alice:remote.example"}}"#
+    )
+    .into_bytes();
     let timestamp_ms = chrono::Utc::now().timestamp_millis();
     let canonical = paracord_federation::transport::canonical_transport_bytes_with_body(
         "POST",
@@ -705,3 +723,297 @@ async fn federation_room_namespace_mapping_is_used_even_when_sender_differs() ->
     std::env::remove_var("PARACORD_FEDERATION_ENABLED");
     Ok(())
 }
+
+/// The receiving server's federation service, for seeding and reading back
+/// federation state directly.
+fn test_service() -> paracord_federation::FederationService {
+    paracord_federation::FederationService::new(paracord_federation::FederationConfig {
+        enabled: true,
+        server_name: "local.example".to_string(),
+        domain: "local.example".to_string(),
+        key_id: "ed25519:local".to_string(),
+        signing_key: None,
+        allow_discovery: false,
+    })
+}
+
+fn signed_envelope(
+    origin_server: &str,
+    key_id: &str,
+    signing_key: &ed25519_dalek::SigningKey,
+    event_id: &str,
+    event_type: &str,
+    content: Value,
+) -> paracord_federation::FederationEventEnvelope {
+    let mut envelope = paracord_federation::FederationEventEnvelope {
+        event_id: event_id.to_string(),
+        room_id: format!("!7110:{origin_server}"),
+        event_type: event_type.to_string(),
+        sender: format!("@alice:{origin_server}"),
+        origin_server: origin_server.to_string(),
+        origin_ts: chrono::Utc::now().timestamp_millis(),
+        content,
+        depth: chrono::Utc::now().timestamp_millis(),
+        state_key: None,
+        signatures: json!({}),
+    };
+    let payload_sig = paracord_federation::signing::sign(
+        signing_key,
+        &paracord_federation::canonical_envelope_bytes(&envelope),
+    );
+    envelope.signatures = json!({ origin_server: { key_id: payload_sig } });
+    envelope
+}
+
+fn event_delivery_request(
+    envelope: &paracord_federation::FederationEventEnvelope,
+    key_id: &str,
+    signing_key: &ed25519_dalek::SigningKey,
+) -> anyhow::Result<Request<Body>> {
+    let body_bytes = serde_json::to_vec(envelope)?;
+    let timestamp_ms = chrono::Utc::now().timestamp_millis();
+    let canonical = paracord_federation::transport::canonical_transport_bytes_with_body(
+        "POST",
+        "/_paracord/federation/v1/event",
+        timestamp_ms,
+        &body_bytes,
+    );
+    Ok(Request::builder()
+        .method("POST")
+        .uri("/_paracord/federation/v1/event")
+        .header("content-type", "application/json")
+        .header("x-paracord-origin", &envelope.origin_server)
+        .header("x-paracord-key-id", key_id)
+        .header("x-paracord-timestamp", timestamp_ms.to_string())
+        .header(
+            "x-paracord-signature",
+            paracord_federation::signing::sign(signing_key, &canonical),
+        )
+        .body(Body::from(body_bytes))?)
+}
+
+#[tokio::test]
+async fn federation_ingest_refetches_rotated_keys_and_rejects_bad_signatures() -> anyhow::Result<()>
+{
+    let _guard = env_lock().lock().unwrap_or_else(|e| e.into_inner());
+    std::env::set_var("PARACORD_FEDERATION_ENABLED", "true");
+
+    let harness = TestHarness::new(true).await?;
+    let origin_server = "rotating.example";
+    let (old_signing_key, old_public_key) = paracord_federation::signing::generate_keypair();
+    let (new_signing_key, new_public_key) = paracord_federation::signing::generate_keypair();
+
+    // The origin now only publishes its rotated key.
+    let published = json!({
+        "server_name": origin_server,
+        "keys": [{
+            "server_name": origin_server,
+            "key_id": "ed25519:new",
+            "public_key": new_public_key,
+            "valid_until": chrono::Utc::now().timestamp_millis() + 600_000,
+        }],
+    });
+    let key_server = Router::new().route(
+        "/_paracord/federation/v1/keys",
+        axum::routing::get(move || {
+            let published = published.clone();
+            async move { axum::Json(published) }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let endpoint = format!("http://{}/_paracord/federation/v1", listener.local_addr()?);
+    tokio::spawn(async move { axum::serve(listener, key_server).await });
+
+    paracord_db::federation::upsert_federated_server(
+        &harness.db,
+        9301,
+        origin_server,
+        origin_server,
+        &endpoint,
+        Some(&old_public_key),
+        Some("ed25519:old"),
+        true,
+    )
+    .await?;
+    test_service()
+        .upsert_server_key(
+            &harness.db,
+            &paracord_federation::FederationServerKey {
+                server_name: origin_server.to_string(),
+                key_id: "ed25519:old".to_string(),
+                public_key: old_public_key.to_string(),
+                valid_until: chrono::Utc::now().timestamp_millis() + 600_000,
+            },
+        )
+        .await?;
+
+    let typing = |event_id: &str, key_id: &str, key: &ed25519_dalek::SigningKey| {
+        signed_envelope(
+            origin_server,
+            key_id,
+            key,
+            event_id,
+            "m.typing",
+            json!({ "channel_id": "7120" }),
+        )
+    };
+
+    // The stored key still verifies without a refetch.
+    let envelope = typing("$old:rotating.example", "ed25519:old", &old_signing_key);
+    let (status, _) = harness
+        .request(event_delivery_request(
+            &envelope,
+            "ed25519:old",
+            &old_signing_key,
+        )?)
+        .await?;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    // An unknown key id makes the server refetch the origin's keys.
+    let envelope = typing("$new:rotating.example", "ed25519:new", &new_signing_key);
+    let (status, body) = harness
+        .request(event_delivery_request(
+            &envelope,
+            "ed25519:new",
+            &new_signing_key,
+        )?)
+        .await?;
+    assert_eq!(status, StatusCode::ACCEPTED, "unexpected body: {body}");
+
+    // Unsigned and badly-signed events are rejected outright.
+    let mut unsigned = typing(
+        "$unsigned:rotating.example",
+        "ed25519:new",
+        &new_signing_key,
+    );
+    unsigned.signatures = json!({});
+    let (status, _) = harness
+        .request(event_delivery_request(
+            &unsigned,
+            "ed25519:new",
+            &new_signing_key,
+        )?)
+        .await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let mut forged = typing("$forged:rotating.example", "ed25519:new", &new_signing_key);
+    forged.signatures = json!({
+        origin_server: {
+            "ed25519:new": paracord_federation::signing::sign(&old_signing_key, b"something else"),
+        }
+    });
+    let (status, _) = harness
+        .request(event_delivery_request(
+            &forged,
+            "ed25519:new",
+            &new_signing_key,
+        )?)
+        .await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    std::env::remove_var("PARACORD_FEDERATION_ENABLED");
+    Ok(())
+}
+
+#[tokio::test]
+async fn federation_ingest_is_idempotent_and_holds_out_of_order_events() -> anyhow::Result<()> {
+    let _guard = env_lock().lock().unwrap_or_else(|e| e.into_inner());
+    std::env::set_var("PARACORD_FEDERATION_ENABLED", "true");
+
+    let harness = TestHarness::new(true).await?;
+    let origin_server = "ordering.example";
+    let key_id = "ed25519:test";
+    let (signing_key, public_key_hex) = paracord_federation::signing::generate_keypair();
+    paracord_db::federation::upsert_federated_server(
+        &harness.db,
+        9401,
+        origin_server,
+        origin_server,
+        "https://ordering.example/_paracord/federation/v1",
+        Some(&public_key_hex),
+        Some(key_id),
+        true,
+    )
+    .await?;
+    test_service()
+        .upsert_server_key(
+            &harness.db,
+            &paracord_federation::FederationServerKey {
+                server_name: origin_server.to_string(),
+                key_id: key_id.to_string(),
+                public_key: public_key_hex.to_string(),
+                valid_until: chrono::Utc::now().timestamp_millis() + 600_000,
+            },
+        )
+        .await?;
+
+    // The edit arrives before the message it edits and is held.
+    let edit = signed_envelope(
+        origin_server,
+        key_id,
+        &signing_key,
+        "$edit:ordering.example",
+        "m.message.edit",
+        json!({ "message_id": "91001", "channel_id": "7120", "body": "edited body" }),
+    );
+    let (status, _) = harness
+        .request(event_delivery_request(&edit, key_id, &signing_key)?)
+        .await?;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    let message = signed_envelope(
+        origin_server,
+        key_id,
+        &signing_key,
+        "$msg:ordering.example",
+        "m.message",
+        json!({
+            "body": "original body",
+            "msgtype": "m.text",
+            "guild_id": "7110",
+            "guild_name": "Ordering Guild",
+            "channel_id": "7120",
+            "channel_name": "general",
+            "channel_type": 0,
+            "message_id": "91001",
+        }),
+    );
+    let (status, _) = harness
+        .request(event_delivery_request(&message, key_id, &signing_key)?)
+        .await?;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    // Re-delivery is acknowledged without being applied again.
+    let (status, body) = harness
+        .request(event_delivery_request(&message, key_id, &signing_key)?)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["inserted"], false);
+
+    let channel_mapping =
+        paracord_db::federation::get_channel_mapping_by_remote(&harness.db, origin_server, "7120")
+            .await?
+            .expect("channel mapping should be created");
+    let msgs = paracord_db::messages::get_channel_messages(
+        &harness.db,
+        channel_mapping.local_channel_id,
+        None,
+        None,
+        10,
+    )
+    .await?;
+    assert_eq!(msgs.len(), 1, "re-delivery must not duplicate messages");
+    assert_eq!(msgs[0].content.as_deref(), Some("edited body"));
+
+    // The stored event is the signed original.
+    let stored = test_service()
+        .fetch_event(&harness.db, &message.event_id)
+        .await?
+        .expect("event should be stored");
+    assert_eq!(
+        paracord_federation::canonical_envelope_bytes(&stored),
+        paracord_federation::canonical_envelope_bytes(&message)
+    );
+
+    std::env::remove_var("PARACORD_FEDERATION_ENABLED");
+    Ok(())
+}
@@ -8,6 +8,7 @@ use axum::{
     Router,
 };
 use chrono::{Duration, Utc};
+use paracord_core::auth::JwtKeySet;
 use paracord_core::{build_permission_cache, AppConfig, AppState, RuntimeSettings};
 use paracord_media::{
     LiveKitConfig, LocalStorage, Storage, StorageConfig, StorageManager, VoiceManager,
@@ -37,6 +38,7 @@ impl VoiceTestContext {
         let media_dir = tempfile::tempdir()?;
         let backup_dir = tempfile::tempdir()?;
         let jwt_secret = "voice-test-secret".to_string();
+        let jwt_keys = JwtKeySet::from_secret(&jwt_secret);
 
         let livekit = Arc::new(LiveKitConfig {
             api_key: "lk-test-key".to_string(),
@@ -50,10 +52,13 @@ impl VoiceTestContext {
             event_bus: paracord_core::events::EventBus::default(),
             config: AppConfig {
                 jwt_secret: jwt_secret.clone(),
+                jwt_keys: jwt_keys.clone(),
                 jwt_expiry_seconds: 3600,
                 registration_enabled: true,
                 allow_username_login: false,
                 require_email: true,
+                password_policy: Default::default(),
+                breached_password_api_url: None,
                 storage_path: storage_dir.path().to_string_lossy().into_owned(),
                 max_upload_size: 10 * 1024 * 1024,
                 livekit_api_key: livekit.api_key.clone(),
@@ -79,6 +84,17 @@ impl VoiceTestContext {
                 federation_file_cache_enabled: false,
                 federation_file_cache_max_size: 0,
                 federation_file_cache_ttl_hours: 0,
+                audit_log_retention_days: None,
+                security_event_retention_days: None,
+                http_rate_limits: paracord_core::rate_limit::HttpRateLimits::default(),
+                inline_content_types: vec!["image/png".to_string()],
+                message_edit_history_limit: 25,
+                slowmode_exempt_bots: true,
+                max_pins_per_channel: 50,
+                max_message_length: 4000,
+                max_attachments_per_message: 10,
+                max_static_emojis: 50,
+                max_animated_emojis: 50,
             },
             runtime: Arc::new(RwLock::new(RuntimeSettings::default())),
             voice: Arc::new(VoiceManager::new(livekit)),
@@ -94,12 +110,19 @@ impl VoiceTestContext {
             user_presences: Arc::new(RwLock::new(HashMap::new())),
             permission_cache: build_permission_cache(),
             federation_service: None,
+            member_index: Arc::new(paracord_core::member_index::MemberIndex::empty()),
+            presence_manager: Arc::new(paracord_core::presence_manager::PresenceManager::new()),
             native_media: None,
+            mailer: paracord_mail::Mailer::disabled(),
+            push: paracord_core::push::PushDispatcher::disabled(),
+            link_previews: paracord_core::link_preview::LinkPreviewer::disabled(),
         };
 
-        paracord_api::install_http_rate_limiter();
+        paracord_api::install_http_rate_limiter(
+            paracord_core::rate_limit::HttpRateLimits::default(),
+        );
         let app = paracord_api::build_router().with_state(state);
-        let token = create_voice_test_user_token(&db, &jwt_secret).await?;
+        let token = create_voice_test_user_token(&db, &jwt_keys).await?;
 
         Ok(Self {
             app,
@@ -145,9 +168,9 @@ impl VoiceTestContext {
 
 async fn create_voice_test_user_token(
     db: &paracord_db::DbPool,
-    jwt_secret: &str,
+    jwt_keys: &JwtKeySet,
 ) -> anyhow::Result<String> {
-    let user_id = paracord_util::snowflake::generate(1);
+    let user_id = paracord_util::snowflake::generate();
     let nonce = Uuid::new_v4().simple().to_string();
     let username = format!("voicetest_{nonce}");
     let email = format!("{nonce}@example.com");
@@ -176,7 +199,7 @@ async fn create_voice_test_user_token(
     let token = paracord_core::auth::create_session_token(
         user.id,
         user.public_key.as_deref(),
-        jwt_secret,
+        jwt_keys,
         3600,
         &session_id,
         &jti,
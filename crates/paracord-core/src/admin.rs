@@ -20,9 +20,18 @@ pub async fn kick_member(
         return Err(CoreError::BadRequest("Cannot kick the guild owner".into()));
     }
 
-    let roles = paracord_db::roles::get_member_roles(pool, actor_id, guild_id).await?;
-    let perms = permissions::compute_permissions_from_roles(&roles, guild.owner_id, actor_id);
+    let perms =
+        permissions::compute_guild_permissions(pool, guild_id, guild.owner_id, actor_id).await?;
     permissions::require_permission(perms, Permissions::KICK_MEMBERS)?;
+    permissions::ensure_outranks_member(
+        pool,
+        guild_id,
+        guild.owner_id,
+        actor_id,
+        target_id,
+        "kick",
+    )
+    .await?;
 
     // Verify target is actually a member
     paracord_db::members::get_member(pool, target_id, guild_id)
@@ -33,14 +42,43 @@ pub async fn kick_member(
     Ok(())
 }
 
+/// Longest temporary ban; anything longer should be a permanent ban.
+pub const MAX_BAN_DURATION_SECONDS: i64 = 365 * 24 * 60 * 60;
+/// How far back a ban may delete the user's messages.
+pub const MAX_BAN_DELETE_MESSAGE_SECONDS: i64 = 7 * 24 * 60 * 60;
+
 /// Ban a member from a guild. Requires BAN_MEMBERS permission.
+///
+/// With a `duration_seconds` the ban lifts itself once it has elapsed;
+/// without one it is permanent. A non-zero `delete_message_seconds` also
+/// deletes everything the member posted in the guild within that window,
+/// atomically with the ban. Returns the deleted `(channel_id, message_id)`
+/// pairs.
 pub async fn ban_member(
     pool: &DbPool,
     guild_id: i64,
     actor_id: i64,
     target_id: i64,
     reason: Option<&str>,
-) -> Result<(), CoreError> {
+    duration_seconds: Option<i64>,
+    delete_message_seconds: i64,
+) -> Result<Vec<(i64, i64)>, CoreError> {
+    let now = chrono::Utc::now();
+    let expires_at = match duration_seconds {
+        Some(seconds) if !(1..=MAX_BAN_DURATION_SECONDS).contains(&seconds) => {
+            return Err(CoreError::BadRequest(format!(
+                "Ban duration must be between 1 and {MAX_BAN_DURATION_SECONDS} seconds"
+            )));
+        }
+        Some(seconds) => Some(now + chrono::Duration::seconds(seconds)),
+        None => None,
+    };
+    if !(0..=MAX_BAN_DELETE_MESSAGE_SECONDS).contains(&delete_message_seconds) {
+        return Err(CoreError::BadRequest(format!(
+            "delete_message_seconds must be between 0 and {MAX_BAN_DELETE_MESSAGE_SECONDS}"
+        )));
+    }
+
     let guild = paracord_db::guilds::get_guild(pool, guild_id)
         .await?
         .ok_or(CoreError::NotFound)?;
@@ -49,17 +87,33 @@ pub async fn ban_member(
         return Err(CoreError::BadRequest("Cannot ban the guild owner".into()));
     }
 
-    let roles = paracord_db::roles::get_member_roles(pool, actor_id, guild_id).await?;
-    let perms = permissions::compute_permissions_from_roles(&roles, guild.owner_id, actor_id);
+    let perms =
+        permissions::compute_guild_permissions(pool, guild_id, guild.owner_id, actor_id).await?;
     permissions::require_permission(perms, Permissions::BAN_MEMBERS)?;
+    permissions::ensure_outranks_member(pool, guild_id, guild.owner_id, actor_id, target_id, "ban")
+        .await?;
 
     // Remove from members if present
     let _ = paracord_db::members::remove_member(pool, target_id, guild_id).await;
 
     // Create ban entry
-    paracord_db::bans::create_ban(pool, target_id, guild_id, reason, actor_id).await?;
-
-    Ok(())
+    if delete_message_seconds == 0 {
+        paracord_db::bans::create_ban(pool, target_id, guild_id, reason, actor_id, expires_at)
+            .await?;
+        return Ok(Vec::new());
+    }
+    let (_, deleted) = paracord_db::bans::create_ban_and_delete_messages(
+        pool,
+        target_id,
+        guild_id,
+        reason,
+        actor_id,
+        expires_at,
+        now - chrono::Duration::seconds(delete_message_seconds),
+    )
+    .await?;
+
+    Ok(deleted)
 }
 
 /// Unban a member. Requires BAN_MEMBERS permission.
@@ -73,8 +127,8 @@ pub async fn unban_member(
         .await?
         .ok_or(CoreError::NotFound)?;
 
-    let roles = paracord_db::roles::get_member_roles(pool, actor_id, guild_id).await?;
-    let perms = permissions::compute_permissions_from_roles(&roles, guild.owner_id, actor_id);
+    let perms =
+        permissions::compute_guild_permissions(pool, guild_id, guild.owner_id, actor_id).await?;
     permissions::require_permission(perms, Permissions::BAN_MEMBERS)?;
 
     paracord_db::bans::delete_ban(pool, target_id, guild_id).await?;
@@ -139,27 +193,35 @@ pub async fn demote_from_admin(
 }
 
 /// Force-delete a guild (server admin action, no permission checks).
-pub async fn admin_delete_guild(pool: &DbPool, guild_id: i64) -> Result<(), CoreError> {
+pub async fn admin_delete_guild(
+    pool: &DbPool,
+    guild_id: i64,
+) -> Result<paracord_db::channels::RemovedFiles, CoreError> {
     paracord_db::guilds::get_guild(pool, guild_id)
         .await?
         .ok_or(CoreError::NotFound)?;
-    paracord_db::guilds::delete_guild(pool, guild_id).await?;
-    Ok(())
+    let removed = paracord_db::guilds::delete_guild(pool, guild_id).await?;
+    Ok(removed)
 }
 
 /// Force-update a guild (server admin action, no permission checks).
+/// `features` replaces the guild's feature bits when given.
 pub async fn admin_update_guild(
     pool: &DbPool,
     guild_id: i64,
     name: Option<&str>,
     description: Option<&str>,
     icon_hash: Option<&str>,
+    features: Option<i32>,
 ) -> Result<paracord_db::guilds::GuildRow, CoreError> {
     paracord_db::guilds::get_guild(pool, guild_id)
         .await?
         .ok_or(CoreError::NotFound)?;
-    let updated =
+    let mut updated =
         paracord_db::guilds::update_guild(pool, guild_id, name, description, icon_hash, None, None).await?;
+    if let Some(features) = features {
+        updated = paracord_db::guilds::set_space_features(pool, guild_id, features).await?;
+    }
     Ok(updated)
 }
 
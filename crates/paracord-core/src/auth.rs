@@ -1,11 +1,14 @@
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
+    Argon2, Params,
 };
 use ed25519_dalek::{Signature, VerifyingKey};
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -35,10 +38,133 @@ pub struct Claims {
     pub pub_key: Option<String>,
 }
 
+/// An HMAC key used to sign or verify JWTs, named by the `kid` header.
+#[derive(Clone)]
+pub struct JwtKey {
+    pub kid: String,
+    secret: String,
+}
+
+impl JwtKey {
+    pub fn new(kid: impl Into<String>, secret: impl Into<String>) -> Self {
+        Self {
+            kid: kid.into(),
+            secret: secret.into(),
+        }
+    }
+}
+
+impl std::fmt::Debug for JwtKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JwtKey")
+            .field("kid", &self.kid)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Keys for issuing and checking JWTs. New tokens are signed with the
+/// signing key and stamped with its `kid`; tokens are accepted when their
+/// `kid` names any key in the set.
+///
+/// To rotate: add a new key and make it the signing key, wait for tokens
+/// signed with the old key to expire, then remove the old key.
+#[derive(Debug, Clone)]
+pub struct JwtKeySet {
+    signing_kid: String,
+    keys: Vec<JwtKey>,
+}
+
+impl JwtKeySet {
+    /// Key id used for the single-secret setup. Tokens issued before key ids
+    /// existed carry no `kid` and are checked against this key.
+    pub const DEFAULT_KID: &'static str = "default";
+
+    pub fn new(signing_kid: &str, keys: Vec<JwtKey>) -> Result<Self, AuthError> {
+        let mut seen = std::collections::HashSet::new();
+        for key in &keys {
+            if key.kid.trim().is_empty() || key.kid.len() > 64 {
+                return Err(AuthError::Internal(
+                    "JWT key ids must be 1-64 characters".into(),
+                ));
+            }
+            if !seen.insert(key.kid.as_str()) {
+                return Err(AuthError::Internal(format!(
+                    "duplicate JWT key id '{}'",
+                    key.kid
+                )));
+            }
+            if key.secret.trim().len() < 32 {
+                return Err(AuthError::Internal(format!(
+                    "JWT key '{}' must be at least 32 characters",
+                    key.kid
+                )));
+            }
+        }
+        if !seen.contains(signing_kid) {
+            return Err(AuthError::Internal(format!(
+                "JWT signing key '{signing_kid}' is not in the key set"
+            )));
+        }
+        Ok(Self {
+            signing_kid: signing_kid.to_string(),
+            keys,
+        })
+    }
+
+    /// A set holding just `secret`, under [`Self::DEFAULT_KID`].
+    pub fn from_secret(secret: &str) -> Self {
+        Self {
+            signing_kid: Self::DEFAULT_KID.to_string(),
+            keys: vec![JwtKey::new(Self::DEFAULT_KID, secret)],
+        }
+    }
+
+    pub fn signing_kid(&self) -> &str {
+        &self.signing_kid
+    }
+
+    fn key(&self, kid: &str) -> Option<&JwtKey> {
+        self.keys.iter().find(|key| key.kid == kid)
+    }
+
+    fn encode<T: Serialize>(&self, claims: &T) -> Result<String, AuthError> {
+        let key = self
+            .key(&self.signing_kid)
+            .ok_or_else(|| AuthError::Internal("JWT signing key is missing".into()))?;
+        let header = Header {
+            kid: Some(key.kid.clone()),
+            ..Header::default()
+        };
+        encode(
+            &header,
+            claims,
+            &EncodingKey::from_secret(key.secret.as_bytes()),
+        )
+        .map_err(|e| AuthError::Internal(e.to_string()))
+    }
+
+    fn decode<T: serde::de::DeserializeOwned>(
+        &self,
+        token: &str,
+        validation: &Validation,
+    ) -> Result<T, AuthError> {
+        let header = decode_header(token).map_err(|_| AuthError::InvalidToken)?;
+        let kid = header.kid.as_deref().unwrap_or(Self::DEFAULT_KID);
+        let key = self.key(kid).ok_or(AuthError::InvalidToken)?;
+        decode::<T>(
+            token,
+            &DecodingKey::from_secret(key.secret.as_bytes()),
+            validation,
+        )
+        .map(|data| data.claims)
+        .map_err(|_| AuthError::InvalidToken)
+    }
+}
+
 fn create_token_internal(
     user_id: i64,
     public_key: Option<&str>,
-    secret: &str,
+    keys: &JwtKeySet,
     expiry_secs: u64,
     session_id: Option<&str>,
     jti: Option<&str>,
@@ -52,23 +178,96 @@ fn create_token_internal(
         jti: jti.map(str::to_string),
         pub_key: public_key.map(str::to_string),
     };
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )
-    .map_err(|e| AuthError::Internal(e.to_string()))
+    keys.encode(&claims)
+}
+
+/// Upper bounds on configurable Argon2 costs. Every login pays the full cost,
+/// so anything past these would let a burst of logins exhaust the server.
+pub const MAX_ARGON2_MEMORY_KIB: u32 = 256 * 1024;
+pub const MAX_ARGON2_ITERATIONS: u32 = 10;
+pub const MAX_ARGON2_PARALLELISM: u32 = 8;
+
+/// Argon2id cost parameters used for new password hashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PasswordHashParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for PasswordHashParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl PasswordHashParams {
+    /// Check the parameters are usable and within the `MAX_ARGON2_*` bounds.
+    pub fn validate(self) -> Result<Self, AuthError> {
+        if self.parallelism == 0 || self.parallelism > MAX_ARGON2_PARALLELISM {
+            return Err(AuthError::Internal(format!(
+                "argon2 parallelism must be between 1 and {MAX_ARGON2_PARALLELISM}"
+            )));
+        }
+        if self.iterations == 0 || self.iterations > MAX_ARGON2_ITERATIONS {
+            return Err(AuthError::Internal(format!(
+                "argon2 iterations must be between 1 and {MAX_ARGON2_ITERATIONS}"
+            )));
+        }
+        let min_memory = (8 * self.parallelism).max(Params::MIN_M_COST);
+        if self.memory_kib < min_memory || self.memory_kib > MAX_ARGON2_MEMORY_KIB {
+            return Err(AuthError::Internal(format!(
+                "argon2 memory must be between {min_memory} and {MAX_ARGON2_MEMORY_KIB} KiB"
+            )));
+        }
+        Ok(self)
+    }
+
+    fn hasher(self) -> Result<Argon2<'static>, AuthError> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| AuthError::Internal(e.to_string()))?;
+        Ok(Argon2::new(
+            argon2::Algorithm::Argon2id,
+            argon2::Version::V0x13,
+            params,
+        ))
+    }
+}
+
+static PASSWORD_HASH_PARAMS: OnceLock<PasswordHashParams> = OnceLock::new();
+
+/// Set the parameters used for new password hashes. Call once at startup;
+/// until then (and in tests) the Argon2 defaults apply.
+pub fn configure_password_hashing(params: PasswordHashParams) -> Result<(), AuthError> {
+    let params = params.validate()?;
+    PASSWORD_HASH_PARAMS
+        .set(params)
+        .map_err(|_| AuthError::Internal("password hashing is already configured".into()))
+}
+
+pub fn password_hash_params() -> PasswordHashParams {
+    PASSWORD_HASH_PARAMS.get().copied().unwrap_or_default()
 }
 
 pub fn hash_password(password: &str) -> Result<String, AuthError> {
+    hash_password_with(password, password_hash_params())
+}
+
+pub fn hash_password_with(password: &str, params: PasswordHashParams) -> Result<String, AuthError> {
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    argon2
+    params
+        .hasher()?
         .hash_password(password.as_bytes(), &salt)
         .map(|h| h.to_string())
         .map_err(|e| AuthError::Internal(e.to_string()))
 }
 
+/// Verify against the algorithm and parameters recorded in the hash itself,
+/// so hashes made under older settings keep working.
 pub fn verify_password(password: &str, hash: &str) -> Result<bool, AuthError> {
     let parsed = PasswordHash::new(hash).map_err(|e| AuthError::Internal(e.to_string()))?;
     Ok(Argon2::default()
@@ -76,23 +275,43 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool, AuthError> {
         .is_ok())
 }
 
-pub fn create_token(user_id: i64, secret: &str, expiry_secs: u64) -> Result<String, AuthError> {
-    create_token_internal(user_id, None, secret, expiry_secs, None, None)
+/// Whether a stored hash is weaker than the current parameters (or not
+/// Argon2id at all) and should be replaced after the next successful login.
+pub fn password_needs_rehash(hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(hash) else {
+        return false;
+    };
+    if parsed.algorithm != argon2::Algorithm::Argon2id.ident()
+        || parsed.version != Some(argon2::Version::V0x13.into())
+    {
+        return true;
+    }
+    let Ok(stored) = Params::try_from(&parsed) else {
+        return false;
+    };
+    let current = password_hash_params();
+    stored.m_cost() < current.memory_kib
+        || stored.t_cost() < current.iterations
+        || stored.p_cost() < current.parallelism
+}
+
+pub fn create_token(user_id: i64, keys: &JwtKeySet, expiry_secs: u64) -> Result<String, AuthError> {
+    create_token_internal(user_id, None, keys, expiry_secs, None, None)
 }
 
 pub fn create_token_with_pubkey(
     user_id: i64,
     public_key: &str,
-    secret: &str,
+    keys: &JwtKeySet,
     expiry_secs: u64,
 ) -> Result<String, AuthError> {
-    create_token_internal(user_id, Some(public_key), secret, expiry_secs, None, None)
+    create_token_internal(user_id, Some(public_key), keys, expiry_secs, None, None)
 }
 
 pub fn create_session_token(
     user_id: i64,
     public_key: Option<&str>,
-    secret: &str,
+    keys: &JwtKeySet,
     expiry_secs: u64,
     session_id: &str,
     jti: &str,
@@ -100,22 +319,49 @@ pub fn create_session_token(
     create_token_internal(
         user_id,
         public_key,
-        secret,
+        keys,
         expiry_secs,
         Some(session_id),
         Some(jti),
     )
 }
 
-pub fn validate_token(token: &str, secret: &str) -> Result<Claims, AuthError> {
-    let validation = Validation::new(Algorithm::HS256);
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &validation,
-    )
-    .map(|data| data.claims)
-    .map_err(|_| AuthError::InvalidToken)
+/// Validate an access token against the key named by its `kid` header.
+pub fn validate_token(token: &str, keys: &JwtKeySet) -> Result<Claims, AuthError> {
+    keys.decode(token, &Validation::new(Algorithm::HS256))
+}
+
+const MFA_TICKET_AUDIENCE: &str = "paracord:mfa";
+
+/// Short-lived proof that a user passed the password step and still owes a
+/// second factor. Carries an audience so it can never pass as an access token.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaTicketClaims {
+    pub sub: i64,
+    pub exp: usize,
+    pub iat: usize,
+    pub aud: String,
+}
+
+pub fn create_mfa_ticket(
+    user_id: i64,
+    keys: &JwtKeySet,
+    expiry_secs: u64,
+) -> Result<String, AuthError> {
+    let now = chrono::Utc::now().timestamp() as usize;
+    let claims = MfaTicketClaims {
+        sub: user_id,
+        iat: now,
+        exp: now + expiry_secs as usize,
+        aud: MFA_TICKET_AUDIENCE.to_string(),
+    };
+    keys.encode(&claims)
+}
+
+pub fn validate_mfa_ticket(token: &str, keys: &JwtKeySet) -> Result<MfaTicketClaims, AuthError> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.set_audience(&[MFA_TICKET_AUDIENCE]);
+    keys.decode(token, &validation)
 }
 
 /// Generate a challenge nonce (32 random bytes as hex)
@@ -146,19 +392,17 @@ pub fn verify_challenge(
     let message = format!("{}:{}:{}", nonce, timestamp, server_origin);
 
     // Decode public key
-    let public_key_bytes =
-        hex_decode(public_key_hex).ok_or(AuthError::Internal("invalid public key hex".into()))?;
-    let key_bytes: [u8; 32] = public_key_bytes
-        .try_into()
-        .map_err(|_| AuthError::Internal("invalid public key length".into()))?;
+    let mut key_bytes = [0u8; 32];
+    hex_decode_into(public_key_hex, &mut key_bytes)
+        .ok_or(AuthError::Internal("invalid public key hex".into()))?;
     let verifying_key = VerifyingKey::from_bytes(&key_bytes)
         .map_err(|_| AuthError::Internal("invalid public key".into()))?;
 
     // Decode signature
-    let sig_bytes =
-        hex_decode(signature_hex).ok_or(AuthError::Internal("invalid signature hex".into()))?;
-    let signature = Signature::from_slice(&sig_bytes)
-        .map_err(|_| AuthError::Internal("invalid signature".into()))?;
+    let mut sig_bytes = [0u8; 64];
+    hex_decode_into(signature_hex, &mut sig_bytes)
+        .ok_or(AuthError::Internal("invalid signature hex".into()))?;
+    let signature = Signature::from_bytes(&sig_bytes);
 
     // Verify
     Ok(verifying_key
@@ -166,38 +410,21 @@ pub fn verify_challenge(
         .is_ok())
 }
 
-fn hex_encode(bytes: &[u8]) -> String {
-    let mut out = String::with_capacity(bytes.len() * 2);
-    for b in bytes {
-        out.push_str(&format!("{:02x}", b));
-    }
-    out
-}
-
-fn hex_decode(value: &str) -> Option<Vec<u8>> {
-    if value.len() % 2 != 0 {
-        return None;
-    }
-    let mut out = Vec::with_capacity(value.len() / 2);
-    let mut i = 0;
-    while i < value.len() {
-        let byte = u8::from_str_radix(&value[i..i + 2], 16).ok()?;
-        out.push(byte);
-        i += 2;
-    }
-    Some(out)
-}
+use paracord_util::hex::hex_decode_into;
+pub(crate) use paracord_util::hex::{hex_decode, hex_encode};
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const TEST_SECRET: &str = "test-secret-0123456789abcdef0123456789";
+
     #[test]
     fn session_tokens_include_sid_and_jti_claims() {
-        let secret = "test-secret";
+        let keys = JwtKeySet::from_secret(TEST_SECRET);
         let token =
-            create_session_token(42, None, secret, 60, "sid-1", "jti-1").expect("create token");
-        let claims = validate_token(&token, secret).expect("validate token");
+            create_session_token(42, None, &keys, 60, "sid-1", "jti-1").expect("create token");
+        let claims = validate_token(&token, &keys).expect("validate token");
         assert_eq!(claims.sub, 42);
         assert_eq!(claims.sid.as_deref(), Some("sid-1"));
         assert_eq!(claims.jti.as_deref(), Some("jti-1"));
@@ -205,55 +432,134 @@ mod tests {
 
     #[test]
     fn legacy_tokens_do_not_require_session_claims() {
-        let secret = "test-secret";
-        let token = create_token(7, secret, 60).expect("create token");
-        let claims = validate_token(&token, secret).expect("validate token");
+        let keys = JwtKeySet::from_secret(TEST_SECRET);
+        let token = create_token(7, &keys, 60).expect("create token");
+        let claims = validate_token(&token, &keys).expect("validate token");
         assert_eq!(claims.sub, 7);
         assert!(claims.sid.is_none());
         assert!(claims.jti.is_none());
     }
 
+    #[test]
+    fn mfa_tickets_are_not_access_tokens() {
+        let keys = JwtKeySet::from_secret(TEST_SECRET);
+        let ticket = create_mfa_ticket(9, &keys, 300).expect("create ticket");
+        assert_eq!(validate_mfa_ticket(&ticket, &keys).expect("ticket").sub, 9);
+        assert!(validate_token(&ticket, &keys).is_err());
+
+        let access = create_session_token(9, None, &keys, 60, "sid", "jti").expect("token");
+        assert!(validate_mfa_ticket(&access, &keys).is_err());
+    }
+
     #[test]
     fn create_token_produces_valid_jwt() {
-        let secret = "my-secret-key";
-        let token = create_token(1, secret, 3600).expect("create token");
+        let keys = JwtKeySet::from_secret(TEST_SECRET);
+        let token = create_token(1, &keys, 3600).expect("create token");
         assert!(!token.is_empty());
-        let claims = validate_token(&token, secret).expect("validate");
+        let claims = validate_token(&token, &keys).expect("validate");
         assert_eq!(claims.sub, 1);
     }
 
     #[test]
     fn validate_token_wrong_secret_fails() {
-        let token = create_token(1, "secret-a", 3600).expect("create token");
-        let result = validate_token(&token, "secret-b");
+        let key_a = JwtKeySet::from_secret("secret-a-0123456789abcdef0123456789");
+        let key_b = JwtKeySet::from_secret("secret-b-0123456789abcdef0123456789");
+        let token = create_token(1, &key_a, 3600).expect("create token");
+        let result = validate_token(&token, &key_b);
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), AuthError::InvalidToken));
     }
 
     #[test]
     fn validate_token_garbage_input_fails() {
-        let result = validate_token("not.a.real.token", "secret");
+        let result = validate_token("not.a.real.token", &JwtKeySet::from_secret(TEST_SECRET));
         assert!(matches!(result.unwrap_err(), AuthError::InvalidToken));
     }
 
     #[test]
     fn token_with_pubkey_roundtrips() {
-        let secret = "test-secret";
-        let token = create_token_with_pubkey(5, "deadbeef", secret, 3600).expect("create token");
-        let claims = validate_token(&token, secret).expect("validate");
+        let keys = JwtKeySet::from_secret(TEST_SECRET);
+        let token = create_token_with_pubkey(5, "deadbeef", &keys, 3600).expect("create token");
+        let claims = validate_token(&token, &keys).expect("validate");
         assert_eq!(claims.sub, 5);
         assert_eq!(claims.pub_key.as_deref(), Some("deadbeef"));
     }
 
     #[test]
     fn token_expiry_is_set_correctly() {
-        let secret = "test-secret";
-        let token = create_token(1, secret, 7200).expect("create token");
-        let claims = validate_token(&token, secret).expect("validate");
+        let keys = JwtKeySet::from_secret(TEST_SECRET);
+        let token = create_token(1, &keys, 7200).expect("create token");
+        let claims = validate_token(&token, &keys).expect("validate");
         assert!(claims.exp > claims.iat);
         assert_eq!(claims.exp - claims.iat, 7200);
     }
 
+    #[test]
+    fn tokens_are_stamped_with_and_validated_by_kid() {
+        let old = JwtKey::new("2026-01", "old-secret-0123456789abcdef0123456789");
+        let new = JwtKey::new("2026-07", "new-secret-0123456789abcdef0123456789");
+        let before = JwtKeySet::new("2026-01", vec![old.clone()]).expect("key set");
+        let during = JwtKeySet::new("2026-07", vec![old.clone(), new.clone()]).expect("key set");
+        let after = JwtKeySet::new("2026-07", vec![new]).expect("key set");
+
+        let old_token = create_session_token(1, None, &before, 60, "sid", "jti").expect("token");
+        let header = decode_header(&old_token).expect("header");
+        assert_eq!(header.kid.as_deref(), Some("2026-01"));
+
+        // While both keys are loaded, old tokens still pass and new ones use the new kid.
+        assert_eq!(validate_token(&old_token, &during).expect("valid").sub, 1);
+        let new_token = create_session_token(2, None, &during, 60, "sid", "jti").expect("token");
+        assert_eq!(
+            decode_header(&new_token).expect("header").kid.as_deref(),
+            Some("2026-07")
+        );
+        assert!(validate_token(&new_token, &before).is_err());
+
+        // Retiring the old key invalidates what it signed.
+        assert!(validate_token(&old_token, &after).is_err());
+        assert_eq!(validate_token(&new_token, &after).expect("valid").sub, 2);
+    }
+
+    #[test]
+    fn tokens_without_kid_use_the_default_key() {
+        let keys = JwtKeySet::from_secret(TEST_SECRET);
+        let now = chrono::Utc::now().timestamp() as usize;
+        let legacy = encode(
+            &Header::default(),
+            &Claims {
+                sub: 3,
+                exp: now + 60,
+                iat: now,
+                sid: None,
+                jti: None,
+                pub_key: None,
+            },
+            &EncodingKey::from_secret(TEST_SECRET.as_bytes()),
+        )
+        .expect("encode");
+        assert_eq!(validate_token(&legacy, &keys).expect("valid").sub, 3);
+
+        let rotated = JwtKeySet::new(
+            "next",
+            vec![JwtKey::new(
+                "next",
+                "next-secret-0123456789abcdef0123456789",
+            )],
+        )
+        .expect("key set");
+        assert!(validate_token(&legacy, &rotated).is_err());
+    }
+
+    #[test]
+    fn key_sets_reject_bad_configuration() {
+        let key = |kid: &str| JwtKey::new(kid, "secret-0123456789abcdef0123456789abc");
+        assert!(JwtKeySet::new("a", vec![key("a"), key("a")]).is_err());
+        assert!(JwtKeySet::new("missing", vec![key("a")]).is_err());
+        assert!(JwtKeySet::new("", vec![key("")]).is_err());
+        assert!(JwtKeySet::new("a", vec![JwtKey::new("a", "short")]).is_err());
+        assert!(JwtKeySet::new("b", vec![key("a"), key("b")]).is_ok());
+    }
+
     #[test]
     fn hash_and_verify_password() {
         let password = "my_secure_password";
@@ -269,6 +575,62 @@ mod tests {
         assert!(!result);
     }
 
+    #[test]
+    fn weaker_hashes_need_rehash() {
+        let password = "my_secure_password";
+        let current = hash_password(password).expect("hash");
+        assert!(!password_needs_rehash(&current));
+
+        let weak = PasswordHashParams {
+            memory_kib: 8 * 1024,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let weak_hash = hash_password_with(password, weak).expect("hash");
+        assert!(verify_password(password, &weak_hash).expect("verify"));
+        assert!(password_needs_rehash(&weak_hash));
+
+        let argon2i = Argon2::new(
+            argon2::Algorithm::Argon2i,
+            argon2::Version::V0x13,
+            Params::default(),
+        )
+        .hash_password(password.as_bytes(), &SaltString::generate(&mut OsRng))
+        .expect("hash")
+        .to_string();
+        assert!(password_needs_rehash(&argon2i));
+        assert!(!password_needs_rehash("not-a-valid-hash"));
+    }
+
+    #[test]
+    fn password_hash_params_reject_extreme_values() {
+        assert!(PasswordHashParams::default().validate().is_ok());
+        for params in [
+            PasswordHashParams {
+                memory_kib: MAX_ARGON2_MEMORY_KIB + 1,
+                ..Default::default()
+            },
+            PasswordHashParams {
+                memory_kib: 4,
+                ..Default::default()
+            },
+            PasswordHashParams {
+                iterations: 0,
+                ..Default::default()
+            },
+            PasswordHashParams {
+                iterations: MAX_ARGON2_ITERATIONS + 1,
+                ..Default::default()
+            },
+            PasswordHashParams {
+                parallelism: MAX_ARGON2_PARALLELISM + 1,
+                ..Default::default()
+            },
+        ] {
+            assert!(params.validate().is_err(), "{params:?} should be rejected");
+        }
+    }
+
     #[test]
     fn verify_password_invalid_hash_returns_error() {
         let result = verify_password("anything", "not-a-valid-hash");
@@ -1,8 +1,66 @@
 use crate::error::CoreError;
 use crate::permissions;
 use paracord_db::DbPool;
+use paracord_models::channel::{
+    ChannelType, DEFAULT_VOICE_BITRATE, MAX_MESSAGE_RETENTION_DAYS, MAX_VOICE_BITRATE,
+    MAX_VOICE_USER_LIMIT, MIN_VOICE_BITRATE,
+};
 use paracord_models::permissions::Permissions;
 
+#[derive(Debug, Clone)]
+pub struct CreateChannelOptions<'a> {
+    pub channel_type: ChannelType,
+    pub parent_id: Option<i64>,
+    pub required_role_ids: Option<&'a str>,
+    /// Voice only; defaults to [`DEFAULT_VOICE_BITRATE`].
+    pub bitrate: Option<i32>,
+    /// Voice only; 0 or absent means unlimited.
+    pub user_limit: Option<i32>,
+}
+
+impl Default for CreateChannelOptions<'_> {
+    fn default() -> Self {
+        Self {
+            channel_type: ChannelType::Text,
+            parent_id: None,
+            required_role_ids: None,
+            bitrate: None,
+            user_limit: None,
+        }
+    }
+}
+
+/// Check `bitrate`/`user_limit` against the channel type and fill in voice
+/// defaults. Non-voice channels carry neither.
+fn resolve_voice_settings(
+    channel_type: ChannelType,
+    bitrate: Option<i32>,
+    user_limit: Option<i32>,
+) -> Result<(Option<i32>, Option<i32>), CoreError> {
+    if !channel_type.is_voice() {
+        if bitrate.is_some() || user_limit.is_some() {
+            return Err(CoreError::BadRequest(
+                "bitrate and user_limit are only valid for voice channels".into(),
+            ));
+        }
+        return Ok((None, None));
+    }
+
+    let bitrate = bitrate.unwrap_or(DEFAULT_VOICE_BITRATE);
+    if !(MIN_VOICE_BITRATE..=MAX_VOICE_BITRATE).contains(&bitrate) {
+        return Err(CoreError::BadRequest(format!(
+            "bitrate must be between {MIN_VOICE_BITRATE} and {MAX_VOICE_BITRATE}"
+        )));
+    }
+    let user_limit = user_limit.unwrap_or(0);
+    if !(0..=MAX_VOICE_USER_LIMIT).contains(&user_limit) {
+        return Err(CoreError::BadRequest(format!(
+            "user_limit must be between 0 and {MAX_VOICE_USER_LIMIT}"
+        )));
+    }
+    Ok((Some(bitrate), Some(user_limit)))
+}
+
 /// Create a channel in a guild, requires MANAGE_CHANNELS.
 pub async fn create_channel(
     pool: &DbPool,
@@ -10,43 +68,76 @@ pub async fn create_channel(
     user_id: i64,
     channel_id: i64,
     name: &str,
-    channel_type: i16,
-    parent_id: Option<i64>,
-    required_role_ids: Option<&str>,
+    options: CreateChannelOptions<'_>,
 ) -> Result<paracord_db::channels::ChannelRow, CoreError> {
+    let channel_type = options.channel_type;
+    if !channel_type.is_space_channel() {
+        return Err(CoreError::BadRequest(
+            "channel_type cannot be created in a space".into(),
+        ));
+    }
+    let (bitrate, user_limit) =
+        resolve_voice_settings(channel_type, options.bitrate, options.user_limit)?;
+
     let guild = paracord_db::guilds::get_guild(pool, guild_id)
         .await?
         .ok_or(CoreError::NotFound)?;
 
-    let roles = paracord_db::roles::get_member_roles(pool, user_id, guild_id).await?;
-    let perms = permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
+    let perms =
+        permissions::compute_guild_permissions(pool, guild_id, guild.owner_id, user_id).await?;
     permissions::require_permission(perms, Permissions::MANAGE_CHANNELS)?;
 
+    if let Some(parent_id) = options.parent_id {
+        if channel_type == ChannelType::Category {
+            return Err(CoreError::BadRequest(
+                "Categories cannot be nested under a parent".into(),
+            ));
+        }
+        let parent = paracord_db::channels::get_channel(pool, parent_id)
+            .await?
+            .filter(|parent| parent.guild_id() == Some(guild_id))
+            .ok_or(CoreError::BadRequest(
+                "Parent channel does not exist".into(),
+            ))?;
+        if parent.channel_type != i16::from(ChannelType::Category) {
+            return Err(CoreError::BadRequest("Parent must be a category".into()));
+        }
+    }
+
     // Compute next position
     let channels = paracord_db::channels::get_guild_channels(pool, guild_id).await?;
     let position = channels.len() as i32;
 
-    let channel = paracord_db::channels::create_channel(
+    let channel = paracord_db::channels::create_channel_with_settings(
         pool,
         channel_id,
         guild_id,
         name,
-        channel_type,
+        channel_type.into(),
         position,
-        parent_id,
-        required_role_ids,
+        options.parent_id,
+        options.required_role_ids,
+        bitrate,
+        user_limit,
     )
     .await?;
 
     Ok(channel)
 }
 
-/// Delete a channel, requires MANAGE_CHANNELS.
+/// Delete a channel, requires MANAGE_CHANNELS. Also returns the stored files
+/// that went away with it.
 pub async fn delete_channel(
     pool: &DbPool,
     channel_id: i64,
     user_id: i64,
-) -> Result<paracord_db::channels::ChannelRow, CoreError> {
+) -> Result<
+    (
+        paracord_db::channels::ChannelRow,
+        paracord_db::channels::RemovedFiles,
+    ),
+    CoreError,
+> {
     let channel = paracord_db::channels::get_channel(pool, channel_id)
         .await?
         .ok_or(CoreError::NotFound)?;
@@ -59,12 +150,12 @@ pub async fn delete_channel(
         .await?
         .ok_or(CoreError::NotFound)?;
 
-    let roles = paracord_db::roles::get_member_roles(pool, user_id, guild_id).await?;
-    let perms = permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
+    let perms =
+        permissions::compute_guild_permissions(pool, guild_id, guild.owner_id, user_id).await?;
     permissions::require_permission(perms, Permissions::MANAGE_CHANNELS)?;
 
-    paracord_db::channels::delete_channel(pool, channel_id).await?;
-    Ok(channel)
+    let removed = paracord_db::channels::delete_channel(pool, channel_id).await?;
+    Ok((channel, removed))
 }
 
 /// Update a channel, requires MANAGE_CHANNELS.
@@ -75,7 +166,26 @@ pub async fn update_channel(
     name: Option<&str>,
     topic: Option<&str>,
     required_role_ids: Option<&str>,
+    rate_limit_per_user: Option<i32>,
+    message_retention_days: Option<i32>,
 ) -> Result<paracord_db::channels::ChannelRow, CoreError> {
+    if let Some(seconds) = rate_limit_per_user {
+        if !(0..=crate::rate_limit::MAX_SLOWMODE_SECONDS).contains(&seconds) {
+            return Err(CoreError::BadRequest(format!(
+                "rate_limit_per_user must be between 0 and {} seconds",
+                crate::rate_limit::MAX_SLOWMODE_SECONDS
+            )));
+        }
+    }
+    if let Some(days) = message_retention_days {
+        if !(0..=MAX_MESSAGE_RETENTION_DAYS).contains(&days) {
+            return Err(CoreError::BadRequest(format!(
+                "message_retention_days must be between 0 and {}",
+                MAX_MESSAGE_RETENTION_DAYS
+            )));
+        }
+    }
+
     let channel = paracord_db::channels::get_channel(pool, channel_id)
         .await?
         .ok_or(CoreError::NotFound)?;
@@ -88,12 +198,48 @@ pub async fn update_channel(
         .await?
         .ok_or(CoreError::NotFound)?;
 
-    let roles = paracord_db::roles::get_member_roles(pool, user_id, guild_id).await?;
-    let perms = permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
+    let perms =
+        permissions::compute_guild_permissions(pool, guild_id, guild.owner_id, user_id).await?;
     permissions::require_permission(perms, Permissions::MANAGE_CHANNELS)?;
 
-    let updated =
-        paracord_db::channels::update_channel(pool, channel_id, name, topic, required_role_ids)
-            .await?;
+    let updated = paracord_db::channels::update_channel(
+        pool,
+        channel_id,
+        name,
+        topic,
+        required_role_ids,
+        rate_limit_per_user,
+        message_retention_days,
+    )
+    .await?;
     Ok(updated)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn voice_channels_get_default_settings() {
+        assert_eq!(
+            resolve_voice_settings(ChannelType::Voice, None, None).unwrap(),
+            (Some(DEFAULT_VOICE_BITRATE), Some(0))
+        );
+        assert_eq!(
+            resolve_voice_settings(ChannelType::Voice, Some(96_000), Some(10)).unwrap(),
+            (Some(96_000), Some(10))
+        );
+        assert!(resolve_voice_settings(ChannelType::Voice, Some(1_000), None).is_err());
+        assert!(resolve_voice_settings(ChannelType::Voice, None, Some(100)).is_err());
+    }
+
+    #[test]
+    fn non_voice_channels_reject_voice_settings() {
+        assert_eq!(
+            resolve_voice_settings(ChannelType::Text, None, None).unwrap(),
+            (None, None)
+        );
+        assert!(resolve_voice_settings(ChannelType::Text, Some(64_000), None).is_err());
+        assert!(resolve_voice_settings(ChannelType::Category, None, Some(5)).is_err());
+    }
+}
@@ -0,0 +1,541 @@
+//! Slash commands: validation of the command definitions bots register, of
+//! the option values members invoke them with, and delivery of interactions
+//! to a bot's HTTP endpoint.
+//!
+//! A bot either keeps a gateway connection open and receives
+//! `INTERACTION_CREATE`, or sets an interactions endpoint URL and gets each
+//! interaction POSTed to it. The endpoint may answer inline with an
+//! [`InteractionResponse`]; otherwise the bot replies later through the
+//! interaction callback route while the token is still valid.
+//!
+//! Endpoint deliveries are signed with a per-application Ed25519 key: the
+//! signature covers the timestamp header followed by the raw body, so the
+//! bot can check a request came from this server with its `verify_key`.
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use ed25519_dalek::{Signer, SigningKey};
+use paracord_db::DbPool;
+use paracord_models::bot::{
+    ApplicationCommandDefinition, CommandOption, CommandOptionType, CommandOptionValue,
+    InteractionOption, InteractionResponse,
+};
+use serde_json::Value;
+
+use crate::error::CoreError;
+use crate::outbound::{OutboundClient, OutboundPolicy};
+use reqwest::header::{HeaderMap, HeaderValue};
+
+pub const MAX_COMMANDS_PER_APPLICATION: usize = 100;
+pub const MAX_OPTIONS_PER_COMMAND: usize = 25;
+pub const MAX_CHOICES_PER_OPTION: usize = 25;
+pub const MAX_NAME_LEN: usize = 32;
+pub const MAX_DESCRIPTION_LEN: usize = 100;
+/// Longest string a member can pass for a `STRING` option, in characters.
+pub const MAX_STRING_VALUE_LEN: usize = 2000;
+
+/// How long a bot can still respond to an interaction.
+pub const INTERACTION_TOKEN_TTL: chrono::Duration = chrono::Duration::minutes(15);
+
+/// The bot replies with a message posted in the invoking channel.
+pub const RESPONSE_CHANNEL_MESSAGE: u8 = 4;
+/// The bot acknowledges now and responds through the callback later.
+pub const RESPONSE_DEFERRED: u8 = 5;
+
+/// Hex Ed25519 signature of the timestamp header followed by the body.
+pub const SIGNATURE_HEADER: &str = "X-Signature-Ed25519";
+/// Unix seconds at which the delivery was signed.
+pub const SIGNATURE_TIMESTAMP_HEADER: &str = "X-Signature-Timestamp";
+
+/// Command and option names: 1-32 lowercase letters, digits, `-` or `_`.
+fn validate_name(field: &str, name: &str) -> Result<(), CoreError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+    if valid {
+        Ok(())
+    } else {
+        Err(CoreError::BadRequest(format!(
+            "{field} must be 1-{MAX_NAME_LEN} lowercase letters, digits, '-' or '_'"
+        )))
+    }
+}
+
+fn validate_description(field: &str, description: &str) -> Result<(), CoreError> {
+    let len = description.trim().chars().count();
+    if len == 0 || len > MAX_DESCRIPTION_LEN {
+        return Err(CoreError::BadRequest(format!(
+            "{field} must be between 1 and {MAX_DESCRIPTION_LEN} characters"
+        )));
+    }
+    Ok(())
+}
+
+/// Whether `value` has the JSON shape `option_type` expects. Users, channels
+/// and roles are passed as snowflake strings.
+fn value_matches_type(option_type: CommandOptionType, value: &Value) -> bool {
+    match option_type {
+        CommandOptionType::String => value
+            .as_str()
+            .is_some_and(|s| s.chars().count() <= MAX_STRING_VALUE_LEN),
+        CommandOptionType::Integer => value.as_i64().is_some(),
+        CommandOptionType::Number => value.is_number(),
+        CommandOptionType::Boolean => value.is_boolean(),
+        CommandOptionType::User | CommandOptionType::Channel | CommandOptionType::Role => value
+            .as_str()
+            .and_then(|s| s.parse::<i64>().ok())
+            .is_some_and(|id| id > 0),
+    }
+}
+
+fn validate_option(option: &CommandOption) -> Result<(), CoreError> {
+    validate_name("Option name", &option.name)?;
+    validate_description("Option description", &option.description)?;
+    if option.choices.is_empty() {
+        return Ok(());
+    }
+    if !matches!(
+        option.option_type,
+        CommandOptionType::String | CommandOptionType::Integer | CommandOptionType::Number
+    ) {
+        return Err(CoreError::BadRequest(format!(
+            "Option '{}' cannot have choices",
+            option.name
+        )));
+    }
+    if option.choices.len() > MAX_CHOICES_PER_OPTION {
+        return Err(CoreError::BadRequest(format!(
+            "Option '{}' has more than {MAX_CHOICES_PER_OPTION} choices",
+            option.name
+        )));
+    }
+    for choice in &option.choices {
+        let len = choice.name.trim().chars().count();
+        if len == 0 || len > MAX_DESCRIPTION_LEN {
+            return Err(CoreError::BadRequest(format!(
+                "Choice names must be between 1 and {MAX_DESCRIPTION_LEN} characters"
+            )));
+        }
+        if !value_matches_type(option.option_type, &choice.value) {
+            return Err(CoreError::BadRequest(format!(
+                "Choice '{}' does not match the type of option '{}'",
+                choice.name, option.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub fn validate_command(command: &ApplicationCommandDefinition) -> Result<(), CoreError> {
+    validate_name("Command name", &command.name)?;
+    validate_description("Command description", &command.description)?;
+    if command.options.len() > MAX_OPTIONS_PER_COMMAND {
+        return Err(CoreError::BadRequest(format!(
+            "Commands can have at most {MAX_OPTIONS_PER_COMMAND} options"
+        )));
+    }
+    let mut names = HashSet::new();
+    let mut seen_optional = false;
+    for option in &command.options {
+        validate_option(option)?;
+        if !names.insert(option.name.as_str()) {
+            return Err(CoreError::BadRequest(format!(
+                "Duplicate option name '{}'",
+                option.name
+            )));
+        }
+        if option.required && seen_optional {
+            return Err(CoreError::BadRequest(
+                "Required options must come before optional ones".into(),
+            ));
+        }
+        seen_optional |= !option.required;
+    }
+    Ok(())
+}
+
+/// Validate a bot's full command set, as submitted for a bulk overwrite.
+pub fn validate_commands(commands: &[ApplicationCommandDefinition]) -> Result<(), CoreError> {
+    if commands.len() > MAX_COMMANDS_PER_APPLICATION {
+        return Err(CoreError::BadRequest(format!(
+            "Applications can register at most {MAX_COMMANDS_PER_APPLICATION} commands"
+        )));
+    }
+    let mut names = HashSet::new();
+    for command in commands {
+        validate_command(command)?;
+        if !names.insert(command.name.as_str()) {
+            return Err(CoreError::BadRequest(format!(
+                "Duplicate command name '{}'",
+                command.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Check the values a member supplied against the command's options and
+/// return them typed, in definition order. Unknown, repeated, mistyped and
+/// missing required options are rejected, as are values outside the choices.
+pub fn resolve_options(
+    definition: &[CommandOption],
+    provided: &[CommandOptionValue],
+) -> Result<Vec<InteractionOption>, CoreError> {
+    let mut seen = HashSet::new();
+    for value in provided {
+        if !definition.iter().any(|option| option.name == value.name) {
+            return Err(CoreError::BadRequest(format!(
+                "Unknown option '{}'",
+                value.name
+            )));
+        }
+        if !seen.insert(value.name.as_str()) {
+            return Err(CoreError::BadRequest(format!(
+                "Option '{}' was given more than once",
+                value.name
+            )));
+        }
+    }
+
+    let mut resolved = Vec::with_capacity(provided.len());
+    for option in definition {
+        let Some(value) = provided.iter().find(|value| value.name == option.name) else {
+            if option.required {
+                return Err(CoreError::BadRequest(format!(
+                    "Missing required option '{}'",
+                    option.name
+                )));
+            }
+            continue;
+        };
+        if !value_matches_type(option.option_type, &value.value) {
+            return Err(CoreError::BadRequest(format!(
+                "Invalid value for option '{}'",
+                option.name
+            )));
+        }
+        if !option.choices.is_empty()
+            && !option
+                .choices
+                .iter()
+                .any(|choice| choice.value == value.value)
+        {
+            return Err(CoreError::BadRequest(format!(
+                "Value for option '{}' is not one of its choices",
+                option.name
+            )));
+        }
+        resolved.push(InteractionOption {
+            name: option.name.clone(),
+            option_type: option.option_type,
+            value: value.value.clone(),
+        });
+    }
+    Ok(resolved)
+}
+
+fn endpoint_client() -> Option<&'static OutboundClient> {
+    static CLIENT: OnceLock<Option<OutboundClient>> = OnceLock::new();
+    CLIENT
+        .get_or_init(|| {
+            let policy = OutboundPolicy {
+                timeout: Duration::from_secs(3),
+                max_body_bytes: 64 * 1024,
+                max_redirects: 0,
+                user_agent: "Paracord-Interactions".to_string(),
+            };
+            OutboundClient::new(policy)
+                .map_err(|e| tracing::warn!("Failed to build interactions client: {}", e))
+                .ok()
+        })
+        .as_ref()
+}
+
+fn parse_signing_key(seed_hex: &str) -> Option<SigningKey> {
+    let mut seed = [0u8; 32];
+    paracord_util::hex::hex_decode_into(seed_hex, &mut seed)?;
+    Some(SigningKey::from_bytes(&seed))
+}
+
+/// Public key (hex) a bot verifies interaction signatures with, for a stored
+/// signing key seed.
+pub fn verify_key_hex(seed_hex: &str) -> Option<String> {
+    parse_signing_key(seed_hex)
+        .map(|key| paracord_util::hex::hex_encode(key.verifying_key().as_bytes()))
+}
+
+/// The application's interaction signing key, created on first use.
+pub async fn interactions_signing_key(
+    pool: &DbPool,
+    application_id: i64,
+    stored: Option<&str>,
+) -> Result<SigningKey, CoreError> {
+    let seed_hex = match stored {
+        Some(seed_hex) => seed_hex.to_string(),
+        None => {
+            let mut seed = [0u8; 32];
+            rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut seed);
+            paracord_db::bot_applications::ensure_interactions_signing_key(
+                pool,
+                application_id,
+                &paracord_util::hex::hex_encode(&seed),
+            )
+            .await?
+        }
+    };
+    parse_signing_key(&seed_hex)
+        .ok_or_else(|| CoreError::Internal("invalid interaction signing key".into()))
+}
+
+/// Signature headers for an interaction body signed at `timestamp`.
+fn signature_headers(key: &SigningKey, timestamp: i64, body: &str) -> HeaderMap {
+    let timestamp = timestamp.to_string();
+    let mut message = Vec::with_capacity(timestamp.len() + body.len());
+    message.extend_from_slice(timestamp.as_bytes());
+    message.extend_from_slice(body.as_bytes());
+    let signature = paracord_util::hex::hex_encode(&key.sign(&message).to_bytes());
+
+    let mut headers = HeaderMap::new();
+    // Hex digits and ASCII numerals are always valid header values.
+    headers.insert(
+        SIGNATURE_HEADER,
+        HeaderValue::from_str(&signature).expect("hex is a valid header value"),
+    );
+    headers.insert(
+        SIGNATURE_TIMESTAMP_HEADER,
+        HeaderValue::from_str(&timestamp).expect("digits are a valid header value"),
+    );
+    headers
+}
+
+/// POST an interaction to the bot's endpoint, signed with the application's
+/// key. Returns the inline response, or `None` if the endpoint accepted it
+/// without one.
+pub async fn deliver_to_endpoint(
+    endpoint: &str,
+    signing_key: &SigningKey,
+    payload: &Value,
+) -> Result<Option<InteractionResponse>, CoreError> {
+    let client = endpoint_client()
+        .ok_or_else(|| CoreError::Internal("interactions client unavailable".into()))?;
+    // `post_json` sends `payload.to_string()`, so this is the exact body.
+    let headers = signature_headers(
+        signing_key,
+        chrono::Utc::now().timestamp(),
+        &payload.to_string(),
+    );
+    let response = client
+        .post_json(endpoint, payload, headers)
+        .await
+        .map_err(|e| CoreError::Internal(format!("interaction delivery failed: {e}")))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(CoreError::Internal(format!(
+            "interactions endpoint returned {status}"
+        )));
+    }
+    let body = response
+        .body_within_limit()
+        .await
+        .map_err(|e| CoreError::Internal(format!("interaction delivery failed: {e}")))?;
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| CoreError::Internal(format!("invalid interaction response: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use paracord_models::bot::CommandOptionChoice;
+    use serde_json::json;
+
+    fn option(option_type: CommandOptionType, name: &str, required: bool) -> CommandOption {
+        CommandOption {
+            option_type,
+            name: name.to_string(),
+            description: "An option".to_string(),
+            required,
+            choices: Vec::new(),
+        }
+    }
+
+    fn command(name: &str, options: Vec<CommandOption>) -> ApplicationCommandDefinition {
+        ApplicationCommandDefinition {
+            name: name.to_string(),
+            description: "A command".to_string(),
+            options,
+        }
+    }
+
+    fn value(name: &str, value: Value) -> CommandOptionValue {
+        CommandOptionValue {
+            name: name.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn command_names_are_restricted() {
+        assert!(validate_command(&command("roll-dice_2", vec![])).is_ok());
+        assert!(validate_command(&command("Roll", vec![])).is_err());
+        assert!(validate_command(&command("roll dice", vec![])).is_err());
+        assert!(validate_command(&command("", vec![])).is_err());
+        assert!(validate_command(&command(&"a".repeat(33), vec![])).is_err());
+    }
+
+    #[test]
+    fn required_options_come_first_and_names_are_unique() {
+        let ok = command(
+            "ban",
+            vec![
+                option(CommandOptionType::User, "user", true),
+                option(CommandOptionType::String, "reason", false),
+            ],
+        );
+        assert!(validate_command(&ok).is_ok());
+
+        let misordered = command(
+            "ban",
+            vec![
+                option(CommandOptionType::String, "reason", false),
+                option(CommandOptionType::User, "user", true),
+            ],
+        );
+        assert!(validate_command(&misordered).is_err());
+
+        let duplicate = command(
+            "ban",
+            vec![
+                option(CommandOptionType::User, "user", true),
+                option(CommandOptionType::User, "user", false),
+            ],
+        );
+        assert!(validate_command(&duplicate).is_err());
+
+        assert!(validate_commands(&[command("a", vec![]), command("a", vec![])]).is_err());
+    }
+
+    #[test]
+    fn choices_must_match_the_option_type() {
+        let mut size = option(CommandOptionType::Integer, "size", true);
+        size.choices = vec![CommandOptionChoice {
+            name: "Small".into(),
+            value: json!(1),
+        }];
+        assert!(validate_command(&command("pizza", vec![size.clone()])).is_ok());
+
+        size.choices.push(CommandOptionChoice {
+            name: "Large".into(),
+            value: json!("large"),
+        });
+        assert!(validate_command(&command("pizza", vec![size])).is_err());
+
+        let mut flag = option(CommandOptionType::Boolean, "flag", true);
+        flag.choices = vec![CommandOptionChoice {
+            name: "Yes".into(),
+            value: json!(true),
+        }];
+        assert!(validate_command(&command("toggle", vec![flag])).is_err());
+    }
+
+    #[test]
+    fn invocation_values_are_checked_against_the_definition() {
+        let mut sides = option(CommandOptionType::Integer, "sides", true);
+        sides.choices = vec![
+            CommandOptionChoice {
+                name: "d6".into(),
+                value: json!(6),
+            },
+            CommandOptionChoice {
+                name: "d20".into(),
+                value: json!(20),
+            },
+        ];
+        let definition = vec![
+            sides,
+            option(CommandOptionType::User, "target", false),
+            option(CommandOptionType::Boolean, "secret", false),
+        ];
+
+        let resolved = resolve_options(
+            &definition,
+            &[value("secret", json!(true)), value("sides", json!(20))],
+        )
+        .unwrap();
+        assert_eq!(
+            resolved,
+            vec![
+                InteractionOption {
+                    name: "sides".into(),
+                    option_type: CommandOptionType::Integer,
+                    value: json!(20),
+                },
+                InteractionOption {
+                    name: "secret".into(),
+                    option_type: CommandOptionType::Boolean,
+                    value: json!(true),
+                },
+            ]
+        );
+
+        assert!(resolve_options(&definition, &[]).is_err());
+        assert!(resolve_options(&definition, &[value("sides", json!(7))]).is_err());
+        assert!(resolve_options(&definition, &[value("sides", json!("6"))]).is_err());
+        assert!(resolve_options(
+            &definition,
+            &[
+                value("sides", json!(6)),
+                value("target", json!("not-an-id"))
+            ]
+        )
+        .is_err());
+        assert!(resolve_options(
+            &definition,
+            &[value("sides", json!(6)), value("sides", json!(20))]
+        )
+        .is_err());
+        assert!(resolve_options(
+            &definition,
+            &[value("sides", json!(6)), value("color", json!("red"))]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn signature_headers_verify_against_the_verify_key() {
+        use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+        let seed_hex = "11".repeat(32);
+        let key = parse_signing_key(&seed_hex).unwrap();
+        let body = json!({ "type": 2, "token": "abc" }).to_string();
+        let headers = signature_headers(&key, 1_700_000_000, &body);
+        assert_eq!(headers[SIGNATURE_TIMESTAMP_HEADER], "1700000000");
+
+        let mut public = [0u8; 32];
+        paracord_util::hex::hex_decode_into(&verify_key_hex(&seed_hex).unwrap(), &mut public)
+            .unwrap();
+        let verifying_key = VerifyingKey::from_bytes(&public).unwrap();
+        let mut signature = [0u8; 64];
+        paracord_util::hex::hex_decode_into(
+            headers[SIGNATURE_HEADER].to_str().unwrap(),
+            &mut signature,
+        )
+        .unwrap();
+        let signature = Signature::from_bytes(&signature);
+
+        let signed = format!("1700000000{body}");
+        assert!(verifying_key.verify(signed.as_bytes(), &signature).is_ok());
+        let replayed = format!("1700000001{body}");
+        assert!(verifying_key
+            .verify(replayed.as_bytes(), &signature)
+            .is_err());
+        assert!(verify_key_hex("not hex").is_none());
+    }
+}
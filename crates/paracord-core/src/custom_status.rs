@@ -0,0 +1,122 @@
+//! Custom statuses: a short text and/or emoji a user shows next to their
+//! presence, optionally until an expiry time.
+//!
+//! The status is stored in the database, so it survives reconnects and works
+//! without a gateway. Connected users also carry it in their in-memory
+//! presence, which is what `PRESENCE_UPDATE` broadcasts. Users who appear
+//! offline (disconnected or invisible) never expose it.
+
+use chrono::Utc;
+use paracord_db::custom_statuses::CustomStatusRow;
+use paracord_db::DbPool;
+use paracord_models::gateway::EVENT_PRESENCE_UPDATE;
+use serde_json::{json, Value};
+
+use crate::AppState;
+
+/// Longest custom status text, in characters.
+pub const MAX_TEXT_CHARS: usize = 128;
+/// Longest emoji value, in characters. Fits a unicode sequence or a custom
+/// emoji reference such as `name:id`.
+pub const MAX_EMOJI_CHARS: usize = 64;
+
+/// Drop control characters (newlines included) and surrounding whitespace.
+/// Returns `None` when nothing is left.
+pub fn sanitize(raw: &str) -> Option<String> {
+    let cleaned: String = raw.chars().filter(|c| !c.is_control()).collect();
+    let trimmed = cleaned.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+pub fn custom_status_json(row: &CustomStatusRow) -> Value {
+    json!({
+        "text": row.text,
+        "emoji": row.emoji,
+        "expires_at": row.expires_at.map(|dt| dt.to_rfc3339()),
+    })
+}
+
+/// The user's unexpired custom status as presence JSON, or `null`.
+pub async fn current_custom_status_json(pool: &DbPool, user_id: i64) -> Value {
+    match paracord_db::custom_statuses::get_custom_status(pool, user_id, Utc::now()).await {
+        Ok(Some(row)) => custom_status_json(&row),
+        Ok(None) => Value::Null,
+        Err(err) => {
+            tracing::warn!("Failed to load custom status for user {}: {}", user_id, err);
+            Value::Null
+        }
+    }
+}
+
+/// Put `status` (or no status) into the user's live presence and broadcast a
+/// `PRESENCE_UPDATE` to everyone who shares a space or a friendship with
+/// them. Users without a visible gateway presence are left alone; their
+/// stored status is picked up when they next connect or go visible.
+pub async fn publish(state: &AppState, user_id: i64, status: Option<&CustomStatusRow>) {
+    let presence = {
+        let mut presences = state.user_presences.write().await;
+        let Some(value) = presences.get_mut(&user_id) else {
+            return;
+        };
+        if value.get("status").and_then(|v| v.as_str()) == Some("offline") {
+            return;
+        }
+        value["custom_status"] = status.map(custom_status_json).unwrap_or(Value::Null);
+        value.clone()
+    };
+
+    let guild_ids: Vec<i64> = paracord_db::guilds::get_user_guilds(&state.db, user_id)
+        .await
+        .unwrap_or_default()
+        .iter()
+        .map(|g| g.id)
+        .collect();
+    let mut recipients = state
+        .member_index
+        .get_presence_recipients(user_id, &guild_ids);
+    recipients.insert(user_id);
+    if let Ok(friend_ids) =
+        paracord_db::relationships::get_friend_user_ids(&state.db, user_id).await
+    {
+        recipients.extend(friend_ids);
+    }
+    state.event_bus.dispatch_to_users(
+        EVENT_PRESENCE_UPDATE,
+        presence,
+        recipients.into_iter().collect(),
+    );
+}
+
+/// Clear custom statuses whose expiry has passed and tell the affected
+/// users' audiences. Returns how many were cleared.
+pub async fn sweep_expired(state: &AppState) -> usize {
+    let expired =
+        match paracord_db::custom_statuses::delete_expired_custom_statuses(&state.db, Utc::now())
+            .await
+        {
+            Ok(user_ids) => user_ids,
+            Err(err) => {
+                tracing::warn!("Failed to clear expired custom statuses: {}", err);
+                return 0;
+            }
+        };
+    for user_id in &expired {
+        publish(state, *user_id, None).await;
+    }
+    expired.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_strips_control_characters() {
+        assert_eq!(
+            sanitize("  Out\u{0}\nto lunch\u{7} ").as_deref(),
+            Some("Outto lunch")
+        );
+        assert_eq!(sanitize("\t\r\n"), None);
+        assert_eq!(sanitize("🍕").as_deref(), Some("🍕"));
+    }
+}
@@ -0,0 +1,354 @@
+//! Rendering metadata for message content.
+//!
+//! [`parse_entities`] finds the spans clients render specially (mentions,
+//! links and code) so every client styles a message the same way instead of
+//! re-parsing it with its own rules. Stored content is never rewritten.
+//!
+//! Offsets and lengths are in UTF-16 code units, the unit JavaScript string
+//! indices use, so web clients can slice the content directly.
+
+use paracord_util::snowflake::Snowflake;
+use serde::Serialize;
+
+/// Entities returned for one message; anything past this is left unstyled.
+pub const MAX_ENTITIES: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EntityKind {
+    UserMention {
+        id: Snowflake,
+    },
+    RoleMention {
+        id: Snowflake,
+    },
+    ChannelMention {
+        id: Snowflake,
+    },
+    Everyone,
+    Here,
+    Url {
+        url: String,
+    },
+    InlineCode,
+    /// A fenced block; `language` is the info string after the opening fence.
+    CodeBlock {
+        language: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MessageEntity {
+    #[serde(flatten)]
+    pub kind: EntityKind,
+    pub offset: usize,
+    pub length: usize,
+}
+
+/// Entities in `content`, in order of appearance.
+///
+/// Mentions are `<@id>` / `<@!id>` for users, `<@&id>` for roles, `<#id>`
+/// for channels, plus `@everyone` and `@here`. Links are bare `http(s)://`
+/// URLs starting at a word boundary, without trailing punctuation or an
+/// unbalanced closing bracket. Nothing is detected inside inline code or
+/// fenced code blocks, or after a backslash escape.
+pub fn parse_entities(content: &str) -> Vec<MessageEntity> {
+    let mut entities = Vec::new();
+    // Byte offsets arrive in order, so UTF-16 offsets are counted as we go.
+    let mut byte_pos = 0;
+    let mut utf16_pos = 0;
+    scan(content, |start, end, kind| {
+        if entities.len() >= MAX_ENTITIES {
+            return false;
+        }
+        utf16_pos += utf16_len(&content[byte_pos..start]);
+        let length = utf16_len(&content[start..end]);
+        entities.push(MessageEntity {
+            kind,
+            offset: utf16_pos,
+            length,
+        });
+        utf16_pos += length;
+        byte_pos = end;
+        true
+    });
+    entities
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.chars().map(char::len_utf16).sum()
+}
+
+/// Walk `content`, calling `visit(start, end, kind)` with the byte range of
+/// each entity until it returns `false`.
+pub(crate) fn scan(content: &str, mut visit: impl FnMut(usize, usize, EntityKind) -> bool) {
+    let bytes = content.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let found = match bytes[i] {
+            b'\\' => {
+                // Skip the escaped character (which may be multi-byte).
+                i += 1;
+                if let Some(ch) = content[i..].chars().next() {
+                    i += ch.len_utf8();
+                }
+                None
+            }
+            b'`' => {
+                let start = i;
+                let run = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+                i += run;
+                // An unclosed run is just literal backticks.
+                closing_fence(content, i, run).map(|end| {
+                    let kind = if run >= 3 {
+                        EntityKind::CodeBlock {
+                            language: fence_language(&content[start + run..end - run]),
+                        }
+                    } else {
+                        EntityKind::InlineCode
+                    };
+                    (start, end, kind)
+                })
+            }
+            b'<' => match parse_tag(&content[i..]) {
+                Some((kind, len)) => Some((i, i + len, kind)),
+                None => {
+                    i += 1;
+                    None
+                }
+            },
+            b'@' => {
+                let rest = &content[i + 1..];
+                if let Some(len) = keyword_len(rest, "everyone") {
+                    Some((i, i + 1 + len, EntityKind::Everyone))
+                } else if let Some(len) = keyword_len(rest, "here") {
+                    Some((i, i + 1 + len, EntityKind::Here))
+                } else {
+                    i += 1;
+                    None
+                }
+            }
+            b'h' | b'H' if at_word_boundary(content, i) => match url_len(&content[i..]) {
+                Some(len) => Some((
+                    i,
+                    i + len,
+                    EntityKind::Url {
+                        url: content[i..i + len].to_string(),
+                    },
+                )),
+                None => {
+                    i += 1;
+                    None
+                }
+            },
+            _ => {
+                i += content[i..].chars().next().map_or(1, char::len_utf8);
+                None
+            }
+        };
+        if let Some((start, end, kind)) = found {
+            if !visit(start, end, kind) {
+                return;
+            }
+            i = end;
+        }
+    }
+}
+
+/// End (exclusive) of the code span whose opening run of `run` backticks
+/// ends at `from`: the next run of exactly the same length.
+fn closing_fence(content: &str, from: usize, run: usize) -> Option<usize> {
+    let bytes = content.as_bytes();
+    let mut search = from;
+    while let Some(offset) = content[search..].find('`') {
+        let start = search + offset;
+        let end_run = bytes[start..].iter().take_while(|&&b| b == b'`').count();
+        if end_run == run {
+            return Some(start + run);
+        }
+        search = start + end_run;
+    }
+    None
+}
+
+/// Language named on the opening line of a fenced block, e.g. `rust` in
+/// "```rust\n...". A block written on a single line has none.
+fn fence_language(inner: &str) -> Option<String> {
+    let (first_line, _) = inner.split_once('\n')?;
+    let language = first_line.trim();
+    let valid = !language.is_empty()
+        && language.len() <= 32
+        && language
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+-#._".contains(c));
+    valid.then(|| language.to_ascii_lowercase())
+}
+
+/// Parse a `<@id>`, `<@!id>`, `<@&id>` or `<#id>` tag at the start of `s`,
+/// returning its entity and byte length.
+fn parse_tag(s: &str) -> Option<(EntityKind, usize)> {
+    let (prefix_len, kind): (usize, fn(Snowflake) -> EntityKind) = if s.starts_with("<@!") {
+        (3, |id| EntityKind::UserMention { id })
+    } else if s.starts_with("<@&") {
+        (3, |id| EntityKind::RoleMention { id })
+    } else if s.starts_with("<@") {
+        (2, |id| EntityKind::UserMention { id })
+    } else if s.starts_with("<#") {
+        (2, |id| EntityKind::ChannelMention { id })
+    } else {
+        return None;
+    };
+    let digits = s[prefix_len..]
+        .bytes()
+        .take_while(u8::is_ascii_digit)
+        .count();
+    if digits == 0 || s.as_bytes().get(prefix_len + digits) != Some(&b'>') {
+        return None;
+    }
+    let id: i64 = s[prefix_len..prefix_len + digits].parse().ok()?;
+    (id > 0).then(|| (kind(Snowflake::new(id)), prefix_len + digits + 1))
+}
+
+/// `keyword` at the start of `s`, not followed by another word character.
+fn keyword_len(s: &str, keyword: &str) -> Option<usize> {
+    let rest = s.strip_prefix(keyword)?;
+    match rest.chars().next() {
+        Some(c) if c.is_alphanumeric() || c == '_' => None,
+        _ => Some(keyword.len()),
+    }
+}
+
+fn at_word_boundary(content: &str, i: usize) -> bool {
+    content[..i]
+        .chars()
+        .next_back()
+        .is_none_or(|c| !c.is_alphanumeric() && c != '_')
+}
+
+/// Byte length of the `http://` or `https://` URL at the start of `s`.
+fn url_len(s: &str) -> Option<usize> {
+    let scheme_len = ["https://", "http://"].iter().find_map(|scheme| {
+        s.get(..scheme.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(scheme))
+            .map(str::len)
+    })?;
+    let mut len = s
+        .char_indices()
+        .find(|(_, c)| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | '"' | '`'))
+        .map_or(s.len(), |(idx, _)| idx);
+
+    // Sentence punctuation and closing brackets that were opened before the
+    // link (as in "(see https://example.com)") aren't part of it.
+    while let Some(last) = s[..len].chars().next_back() {
+        let strip = match last {
+            '.' | ',' | ':' | ';' | '!' | '?' | '\'' | '*' | '_' | '~' => true,
+            ')' => s[..len].matches('(').count() < s[..len].matches(')').count(),
+            ']' => s[..len].matches('[').count() < s[..len].matches(']').count(),
+            _ => false,
+        };
+        if !strip {
+            break;
+        }
+        len -= last.len_utf8();
+    }
+
+    let authority = s[scheme_len..len]
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let valid_host = !host.is_empty()
+        && !host.starts_with('.')
+        && host
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']' | '_'));
+    valid_host.then_some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(content: &str) -> Vec<String> {
+        parse_entities(content)
+            .into_iter()
+            .filter_map(|entity| match entity.kind {
+                EntityKind::Url { url } => Some(url),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn spans_are_utf16_offsets_into_the_content() {
+        let content = "👋 <@12> see https://example.com/a?b=1 `x` @here";
+        let entities = parse_entities(content);
+        let utf16: Vec<u16> = content.encode_utf16().collect();
+        let texts: Vec<String> = entities
+            .iter()
+            .map(|e| String::from_utf16(&utf16[e.offset..e.offset + e.length]).unwrap())
+            .collect();
+        assert_eq!(
+            texts,
+            vec!["<@12>", "https://example.com/a?b=1", "`x`", "@here"]
+        );
+        assert_eq!(
+            entities[0].kind,
+            EntityKind::UserMention {
+                id: Snowflake::new(12)
+            }
+        );
+        assert_eq!(entities[0].offset, 3);
+        assert_eq!(entities[2].kind, EntityKind::InlineCode);
+    }
+
+    #[test]
+    fn links_inside_code_and_escapes_are_ignored() {
+        assert!(urls("`https://a.example` ``x https://b.example x``").is_empty());
+        assert!(urls("```\nhttps://c.example\n```").is_empty());
+        assert!(urls("\\https://d.example xhttps://e.example").is_empty());
+        // An unclosed backtick is literal.
+        assert_eq!(urls("it`s https://f.example"), vec!["https://f.example"]);
+    }
+
+    #[test]
+    fn link_ends_exclude_punctuation_and_unbalanced_brackets() {
+        assert_eq!(
+            urls("(see https://example.com/wiki/Rust_(language)). Or HTTP://Example.org/x, <https://y.example>"),
+            vec![
+                "https://example.com/wiki/Rust_(language)",
+                "HTTP://Example.org/x",
+                "https://y.example",
+            ]
+        );
+        assert!(urls("https:// http://.x https://").is_empty());
+    }
+
+    #[test]
+    fn code_blocks_carry_their_language() {
+        let entities = parse_entities("```Rust\nfn main() {}\n``` ```one line```");
+        assert_eq!(
+            entities[0].kind,
+            EntityKind::CodeBlock {
+                language: Some("rust".into())
+            }
+        );
+        assert_eq!(entities[0].length, 24);
+        assert_eq!(entities[1].kind, EntityKind::CodeBlock { language: None });
+    }
+
+    #[test]
+    fn serializes_with_a_type_tag_and_string_ids() {
+        let entity = &parse_entities("<@&7>")[0];
+        assert_eq!(
+            serde_json::to_value(entity).unwrap(),
+            serde_json::json!({ "type": "role_mention", "id": "7", "offset": 0, "length": 5 })
+        );
+    }
+
+    #[test]
+    fn caps_the_entity_count() {
+        let content = "@here ".repeat(MAX_ENTITIES + 10);
+        assert_eq!(parse_entities(&content).len(), MAX_ENTITIES);
+    }
+}
@@ -6,12 +6,18 @@ pub enum CoreError {
     NotFound,
     #[error("forbidden")]
     Forbidden,
+    /// One of the users in a DM has blocked the other.
+    #[error("you cannot message this user")]
+    Blocked,
     #[error("missing permission")]
     MissingPermission,
     #[error("bad request: {0}")]
     BadRequest(String),
     #[error("conflict: {0}")]
     Conflict(String),
+    /// The actor does not outrank the member or role they tried to act on.
+    #[error("{0}")]
+    RoleHierarchy(String),
     #[error("database error: {0}")]
     Database(#[from] paracord_db::DbError),
     #[error("internal error: {0}")]
@@ -40,20 +40,25 @@ pub async fn create_guild_full(
     paracord_db::roles::add_member_role(pool, owner_id, guild_id, guild_id).await?;
 
     // Create #general text channel
-    let general_id = paracord_util::snowflake::generate(1);
+    let general_id = paracord_util::snowflake::generate();
     paracord_db::channels::create_channel(pool, general_id, guild_id, "general", 0, 0, None, None)
         .await?;
 
     // Create General voice channel
-    let voice_id = paracord_util::snowflake::generate(1);
+    let voice_id = paracord_util::snowflake::generate();
     paracord_db::channels::create_channel(pool, voice_id, guild_id, "General", 2, 1, None, None)
         .await?;
 
     Ok(guild)
 }
 
-/// Delete a guild, only allowed by the owner.
-pub async fn delete_guild(pool: &DbPool, guild_id: i64, user_id: i64) -> Result<(), CoreError> {
+/// Delete a guild, only allowed by the owner. Returns the stored files that
+/// went away with it.
+pub async fn delete_guild(
+    pool: &DbPool,
+    guild_id: i64,
+    user_id: i64,
+) -> Result<paracord_db::channels::RemovedFiles, CoreError> {
     let guild = paracord_db::guilds::get_guild(pool, guild_id)
         .await?
         .ok_or(CoreError::NotFound)?;
@@ -62,8 +67,8 @@ pub async fn delete_guild(pool: &DbPool, guild_id: i64, user_id: i64) -> Result<
         return Err(CoreError::Forbidden);
     }
 
-    paracord_db::guilds::delete_guild(pool, guild_id).await?;
-    Ok(())
+    let removed = paracord_db::guilds::delete_guild(pool, guild_id).await?;
+    Ok(removed)
 }
 
 /// Update guild fields, requires MANAGE_GUILD permission.
@@ -81,11 +86,49 @@ pub async fn update_guild(
         .await?
         .ok_or(CoreError::NotFound)?;
 
-    let roles = paracord_db::roles::get_member_roles(pool, user_id, guild_id).await?;
-    let perms = permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
+    let perms =
+        permissions::compute_guild_permissions(pool, guild_id, guild.owner_id, user_id).await?;
     permissions::require_permission(perms, Permissions::MANAGE_GUILD)?;
 
     let updated =
         paracord_db::guilds::update_guild(pool, guild_id, name, description, icon_hash, hub_settings, bot_settings).await?;
     Ok(updated)
 }
+
+/// Point join/leave announcements at `channel_id` (or turn them off with
+/// `None`) and set which are suppressed. Requires MANAGE_GUILD; the channel
+/// must be a text or announcement channel of this guild.
+pub async fn update_system_channel(
+    pool: &DbPool,
+    guild_id: i64,
+    user_id: i64,
+    channel_id: Option<i64>,
+    flags: i32,
+) -> Result<paracord_db::guilds::GuildRow, CoreError> {
+    let guild = paracord_db::guilds::get_guild(pool, guild_id)
+        .await?
+        .ok_or(CoreError::NotFound)?;
+
+    let perms =
+        permissions::compute_guild_permissions(pool, guild_id, guild.owner_id, user_id).await?;
+    permissions::require_permission(perms, Permissions::MANAGE_GUILD)?;
+
+    if flags & !crate::system_messages::ALL_SYSTEM_CHANNEL_FLAGS != 0 {
+        return Err(CoreError::BadRequest("Unknown system channel flags".into()));
+    }
+    if let Some(channel_id) = channel_id {
+        let channel = paracord_db::channels::get_channel(pool, channel_id).await?;
+        if !channel.is_some_and(|c| {
+            c.guild_id() == Some(guild_id)
+                && crate::system_messages::is_announcement_channel(c.channel_type)
+        }) {
+            return Err(CoreError::BadRequest(
+                "System channel must be a text channel in this guild".into(),
+            ));
+        }
+    }
+
+    let updated =
+        paracord_db::guilds::update_system_channel(pool, guild_id, channel_id, flags).await?;
+    Ok(updated)
+}
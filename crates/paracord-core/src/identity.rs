@@ -223,11 +223,13 @@ pub async fn import_identity(
 
     // 1. Update user profile with exported data
     let profile_updated = {
-        let display_name = bundle.user.display_name.as_deref();
-        let bio = bundle.user.bio.as_deref();
-        let avatar = bundle.user.avatar_hash.as_deref();
-        let result =
-            paracord_db::users::update_user(pool, target_user_id, display_name, bio, avatar).await;
+        let update = paracord_db::users::UserProfileUpdate {
+            display_name: bundle.user.display_name.as_deref(),
+            bio: bundle.user.bio.as_deref(),
+            avatar_hash: bundle.user.avatar_hash.as_deref(),
+            ..Default::default()
+        };
+        let result = paracord_db::users::update_user(pool, target_user_id, &update).await;
         match result {
             Ok(_) => true,
             Err(e) => {
@@ -241,7 +243,7 @@ pub async fn import_identity(
     let mut messages_imported: u64 = 0;
     const IMPORTED_FLAG: i32 = 1 << 4; // bit 4 = imported message
     for msg in &bundle.messages {
-        let msg_id = paracord_util::snowflake::generate(0);
+        let msg_id = paracord_util::snowflake::generate();
         let channel_id: i64 = match msg.channel_id.parse() {
             Ok(id) => id,
             Err(_) => {
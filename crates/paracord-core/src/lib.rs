@@ -2,15 +2,26 @@ pub mod admin;
 pub mod auth;
 pub mod backup;
 pub mod channel;
+pub mod commands;
+pub mod custom_status;
+pub mod entities;
 pub mod error;
 pub mod events;
 pub mod guild;
 pub mod identity;
+pub mod link_preview;
 pub mod member_index;
+pub mod mentions;
+pub mod mfa;
 pub mod message;
 pub mod observability;
+pub mod outbound;
 pub mod permissions;
 pub mod presence_manager;
+pub mod push;
+pub mod rate_limit;
+pub mod system_messages;
+pub mod typing;
 pub mod user;
 
 use paracord_db::DbPool;
@@ -29,6 +40,11 @@ use tokio::sync::{Notify, RwLock};
 pub const USER_FLAG_ADMIN: i32 = 1 << 0;
 /// Bit flag: user is a bot account.
 pub const USER_FLAG_BOT: i32 = 1 << 1;
+/// Bit flag: account has been disabled by an admin and cannot sign in.
+pub const USER_FLAG_DISABLED: i32 = 1 << 2;
+/// Bit flag: account was registered while email verification was required
+/// and its address has not been confirmed yet.
+pub const USER_FLAG_UNVERIFIED: i32 = 1 << 3;
 /// Bit flag: message content is DM end-to-end encrypted ciphertext.
 pub const MESSAGE_FLAG_DM_E2EE: i32 = 1 << 0;
 
@@ -40,6 +56,14 @@ pub fn is_bot(flags: i32) -> bool {
     flags & USER_FLAG_BOT != 0
 }
 
+pub fn is_disabled(flags: i32) -> bool {
+    flags & USER_FLAG_DISABLED != 0
+}
+
+pub fn is_unverified(flags: i32) -> bool {
+    flags & USER_FLAG_UNVERIFIED != 0
+}
+
 /// Settings that can be changed at runtime via the admin dashboard.
 #[derive(Clone, Debug)]
 pub struct RuntimeSettings {
@@ -48,6 +72,11 @@ pub struct RuntimeSettings {
     pub server_description: String,
     pub max_guilds_per_user: u32,
     pub max_members_per_guild: u32,
+    /// New password accounts must confirm their email before signing in.
+    pub require_email_verification: bool,
+    /// Browser origins allowed for CORS and gateway upgrades, on top of the
+    /// ones from the config file and environment.
+    pub cors_allowed_origins: Vec<String>,
 }
 
 impl Default for RuntimeSettings {
@@ -58,18 +87,36 @@ impl Default for RuntimeSettings {
             server_description: String::new(),
             max_guilds_per_user: 100,
             max_members_per_guild: 1000,
+            require_email_verification: false,
+            cors_allowed_origins: Vec::new(),
         }
     }
 }
 
-/// Cache key for computed channel permissions: (user_id, channel_id).
-pub type PermissionCacheKey = (i64, i64);
+/// Split a comma-separated `cors_allowed_origins` setting into origins.
+pub fn parse_origin_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Cache key for computed channel permissions: (guild_id, channel_id, user_id).
+pub type PermissionCacheKey = (i64, i64, i64);
+
+/// How long a computed permission set may be served from the cache. Role,
+/// membership and overwrite changes invalidate entries explicitly; the TTL
+/// bounds staleness for anything that slips past invalidation (e.g. a
+/// computation racing a change).
+pub const PERMISSION_CACHE_TTL_SECONDS: u64 = 30;
 
-/// Build the permission cache with a 5-minute TTL and 10k max entries.
+/// Build the permission cache with a short TTL and 10k max entries.
 pub fn build_permission_cache() -> moka::future::Cache<PermissionCacheKey, Permissions> {
     moka::future::Cache::builder()
         .max_capacity(10_000)
-        .time_to_live(std::time::Duration::from_secs(300))
+        .time_to_live(std::time::Duration::from_secs(PERMISSION_CACHE_TTL_SECONDS))
         .build()
 }
 
@@ -98,6 +145,12 @@ pub struct AppState {
     pub presence_manager: Arc<presence_manager::PresenceManager>,
     /// Native QUIC media relay state (None when using LiveKit).
     pub native_media: Option<NativeMediaState>,
+    /// Queue for transactional email (logs instead of sending without SMTP).
+    pub mailer: paracord_mail::Mailer,
+    /// Web Push delivery for mentions and DMs (no-op when disabled).
+    pub push: push::PushDispatcher,
+    /// Background link preview fetching (no-op when disabled).
+    pub link_previews: link_preview::LinkPreviewer,
 }
 
 /// State for the native QUIC-based media server.
@@ -115,11 +168,20 @@ pub struct NativeMediaState {
 
 #[derive(Clone, Debug)]
 pub struct AppConfig {
+    /// Server master secret. Other server-side keys (TOTP sealing, media and
+    /// file tokens) are derived from it; it is also the `default` JWT key.
     pub jwt_secret: String,
+    /// Keys for signing and verifying access tokens, selected by `kid`.
+    pub jwt_keys: auth::JwtKeySet,
     pub jwt_expiry_seconds: u64,
     pub registration_enabled: bool,
     pub allow_username_login: bool,
     pub require_email: bool,
+    /// Rules new passwords must satisfy on registration and change.
+    pub password_policy: paracord_util::validation::PasswordPolicy,
+    /// Pwned Passwords range API base URL; the 5-character hash prefix is
+    /// appended. `None` disables the breach check.
+    pub breached_password_api_url: Option<String>,
     pub storage_path: String,
     pub max_upload_size: u64,
     pub livekit_api_key: String,
@@ -159,4 +221,29 @@ pub struct AppConfig {
     pub federation_file_cache_max_size: u64,
     /// TTL for cached federation files in hours.
     pub federation_file_cache_ttl_hours: u64,
+    /// Configured audit log retention in days (`None` = keep forever).
+    /// The `audit_log_retention_days` admin setting overrides it.
+    pub audit_log_retention_days: Option<i64>,
+    /// Configured security event retention in days (`None` = keep forever).
+    /// The `security_event_retention_days` admin setting overrides it.
+    pub security_event_retention_days: Option<i64>,
+    /// Per-category HTTP rate limits applied by the API middleware.
+    pub http_rate_limits: rate_limit::HttpRateLimits,
+    /// Attachment content types that may be rendered inline by browsers.
+    /// Everything else is served with `Content-Disposition: attachment`.
+    pub inline_content_types: Vec<String>,
+    /// Prior revisions kept per edited message (0 disables edit history).
+    pub message_edit_history_limit: u32,
+    /// Bot accounts are not held to channel slowmode.
+    pub slowmode_exempt_bots: bool,
+    /// Pinned messages allowed per channel.
+    pub max_pins_per_channel: u32,
+    /// Longest message content accepted, in grapheme clusters.
+    pub max_message_length: u32,
+    /// Attachments allowed on a single message.
+    pub max_attachments_per_message: u32,
+    /// Static custom emojis allowed per space.
+    pub max_static_emojis: u32,
+    /// Animated custom emojis allowed per space.
+    pub max_animated_emojis: u32,
 }
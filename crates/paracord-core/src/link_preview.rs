@@ -0,0 +1,672 @@
+//! Link previews: OpenGraph / Twitter card embeds for links in messages.
+//!
+//! Message handlers only enqueue a [`LinkPreviewJob`]. A background worker
+//! fetches the linked pages, stores what it found in `link_previews` keyed by
+//! the URL's hash, and dispatches `MESSAGE_EMBEDS_UPDATE` so clients can
+//! render the embeds. Fetch results (including failures) are reused for
+//! [`LinkPreviewConfig::cache_ttl`], so a link posted over and over is only
+//! fetched once per window.
+//!
+//! Pages are fetched through [`crate::outbound`], so they never reach
+//! private networks, and the domain policy is applied to every redirect hop.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::Utc;
+use paracord_db::link_previews::LinkPreviewRow;
+use paracord_db::DbPool;
+use paracord_models::gateway::EVENT_MESSAGE_EMBEDS_UPDATE;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE};
+use reqwest::Url;
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+use crate::entities::{self, EntityKind};
+use crate::error::CoreError;
+use crate::events::EventBus;
+use crate::outbound::{OutboundClient, OutboundError, OutboundPolicy};
+
+/// Links previewed per message; later ones are left as plain links.
+pub const MAX_PREVIEWS_PER_MESSAGE: usize = 5;
+/// Messages waiting for previews before new ones are dropped.
+const QUEUE_CAPACITY: usize = 256;
+const BATCH_SIZE: usize = 32;
+/// Messages whose links are being fetched at once.
+const MAX_CONCURRENT_JOBS: usize = 8;
+const MAX_TITLE_CHARS: usize = 256;
+const MAX_DESCRIPTION_CHARS: usize = 1024;
+const MAX_URL_LEN: usize = 2048;
+const USER_AGENT: &str = "Mozilla/5.0 (compatible; Paracord link preview)";
+
+#[derive(Debug, Clone)]
+pub struct LinkPreviewConfig {
+    /// When non-empty, only these domains (and their subdomains) are fetched.
+    pub allowed_domains: Vec<String>,
+    /// Domains (and their subdomains) that are never fetched.
+    pub denied_domains: Vec<String>,
+    /// Bytes of a page read before parsing; metadata lives in `<head>`, so
+    /// the rest of a large page is never downloaded.
+    pub max_body_bytes: usize,
+    /// Limit on one whole fetch, redirects included.
+    pub timeout: Duration,
+    /// How long a fetch result is reused before the page is fetched again.
+    pub cache_ttl: Duration,
+}
+
+impl Default for LinkPreviewConfig {
+    fn default() -> Self {
+        Self {
+            allowed_domains: Vec::new(),
+            denied_domains: Vec::new(),
+            max_body_bytes: 512 * 1024,
+            timeout: Duration::from_secs(5),
+            cache_ttl: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+impl LinkPreviewConfig {
+    /// Whether the domain policy lets previews be fetched from `host`.
+    pub fn allows_host(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        let matches = |domain: &String| {
+            let domain = domain.trim_matches('.').to_ascii_lowercase();
+            !domain.is_empty()
+                && (host == domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|prefix| prefix.ends_with('.')))
+        };
+        if self.denied_domains.iter().any(matches) {
+            return false;
+        }
+        self.allowed_domains.is_empty() || self.allowed_domains.iter().any(matches)
+    }
+}
+
+/// A message whose links should be previewed.
+#[derive(Debug, Clone, Copy)]
+pub struct LinkPreviewJob {
+    pub message_id: i64,
+    pub channel_id: i64,
+    pub guild_id: Option<i64>,
+}
+
+/// Handle for queueing link previews. Cheap to clone.
+#[derive(Clone)]
+pub struct LinkPreviewer {
+    queue: Option<mpsc::Sender<LinkPreviewJob>>,
+}
+
+impl LinkPreviewer {
+    /// A previewer that accepts jobs and discards them.
+    pub fn disabled() -> Self {
+        Self { queue: None }
+    }
+
+    /// Spawn the fetch worker.
+    pub fn start(
+        pool: DbPool,
+        event_bus: EventBus,
+        config: LinkPreviewConfig,
+    ) -> Result<Self, CoreError> {
+        let policy = OutboundPolicy {
+            timeout: config.timeout,
+            max_body_bytes: config.max_body_bytes,
+            user_agent: USER_AGENT.to_string(),
+            ..Default::default()
+        };
+        let domains = config.clone();
+        let client =
+            OutboundClient::with_host_filter(policy, move |host| domains.allows_host(host))
+                .map_err(|err| CoreError::Internal(err.to_string()))?;
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(run_worker(pool, event_bus, config, client, rx));
+        Ok(Self { queue: Some(tx) })
+    }
+
+    /// Queue previews for a new or edited message. Never blocks; when the
+    /// queue is full the message goes without previews.
+    pub fn queue(&self, job: LinkPreviewJob) {
+        let Some(queue) = &self.queue else {
+            return;
+        };
+        if let Err(mpsc::error::TrySendError::Full(job)) = queue.try_send(job) {
+            tracing::warn!(
+                message_id = job.message_id,
+                "Link preview queue full; skipping previews"
+            );
+        }
+    }
+}
+
+/// An embed as sent to clients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LinkEmbed {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+impl LinkEmbed {
+    fn from_row(row: LinkPreviewRow) -> Option<Self> {
+        row.has_metadata().then_some(Self {
+            kind: "link",
+            url: row.url,
+            title: row.title,
+            description: row.description,
+            site_name: row.site_name,
+            image: row.image_url,
+        })
+    }
+}
+
+/// Cache key for `url`: its hex SHA-256.
+pub fn url_hash(url: &str) -> String {
+    paracord_util::hex::hex_encode(&Sha256::digest(url.as_bytes()))
+}
+
+/// Distinct links in `content` that get previews, in order of appearance.
+pub fn preview_urls(content: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for entity in entities::parse_entities(content) {
+        if let EntityKind::Url { url } = entity.kind {
+            if url.len() <= MAX_URL_LEN && !urls.contains(&url) {
+                urls.push(url);
+                if urls.len() == MAX_PREVIEWS_PER_MESSAGE {
+                    break;
+                }
+            }
+        }
+    }
+    urls
+}
+
+/// Embeds for the links in each of `contents` that already have a cached
+/// preview, in link order, loaded with one query for the whole page. Never
+/// fetches anything.
+pub async fn cached_embeds(
+    pool: &DbPool,
+    contents: &[&str],
+) -> Result<Vec<Vec<LinkEmbed>>, CoreError> {
+    let urls: Vec<Vec<String>> = contents
+        .iter()
+        .map(|content| preview_urls(content))
+        .collect();
+    let mut distinct: Vec<String> = urls.iter().flatten().cloned().collect();
+    distinct.sort();
+    distinct.dedup();
+    let rows = load_previews(pool, &distinct).await?;
+    Ok(urls
+        .iter()
+        .map(|urls| {
+            urls.iter()
+                .filter_map(|url| rows.get(url).cloned().and_then(LinkEmbed::from_row))
+                .collect()
+        })
+        .collect())
+}
+
+/// Delete cached previews older than `ttl`, which would be fetched afresh
+/// anyway. Messages linking to them show no embed until the link is
+/// previewed again.
+pub async fn purge_expired_previews(pool: &DbPool, ttl: Duration) -> Result<u64, CoreError> {
+    let cutoff =
+        Utc::now() - chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::days(365));
+    Ok(paracord_db::link_previews::purge_link_previews_fetched_before(pool, cutoff).await?)
+}
+
+async fn load_previews(
+    pool: &DbPool,
+    urls: &[String],
+) -> Result<HashMap<String, LinkPreviewRow>, CoreError> {
+    let hashes: Vec<String> = urls.iter().map(|url| url_hash(url)).collect();
+    let rows = paracord_db::link_previews::get_link_previews(pool, &hashes).await?;
+    Ok(rows.into_iter().map(|row| (row.url.clone(), row)).collect())
+}
+
+async fn run_worker(
+    pool: DbPool,
+    event_bus: EventBus,
+    config: LinkPreviewConfig,
+    client: OutboundClient,
+    mut rx: mpsc::Receiver<LinkPreviewJob>,
+) {
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    while rx.recv_many(&mut batch, BATCH_SIZE).await > 0 {
+        let mut in_flight = JoinSet::new();
+        for job in batch.drain(..) {
+            if in_flight.len() >= MAX_CONCURRENT_JOBS {
+                in_flight.join_next().await;
+            }
+            let pool = pool.clone();
+            let event_bus = event_bus.clone();
+            let config = config.clone();
+            let client = client.clone();
+            in_flight.spawn(async move {
+                if let Err(err) = preview_message(&pool, &event_bus, &config, &client, job).await {
+                    tracing::warn!(
+                        message_id = job.message_id,
+                        "Failed to build link previews: {}",
+                        err
+                    );
+                }
+            });
+        }
+        while in_flight.join_next().await.is_some() {}
+    }
+}
+
+/// Fetch previews for the links in one message that have no fresh cache
+/// entry, then tell clients about the message's embeds if any were fetched.
+async fn preview_message(
+    pool: &DbPool,
+    event_bus: &EventBus,
+    config: &LinkPreviewConfig,
+    client: &OutboundClient,
+    job: LinkPreviewJob,
+) -> Result<(), CoreError> {
+    let Some(message) = paracord_db::messages::get_message(pool, job.message_id).await? else {
+        return Ok(());
+    };
+    let Some(content) = message.content.as_deref() else {
+        return Ok(());
+    };
+    let urls: Vec<String> = preview_urls(content)
+        .into_iter()
+        .filter(|url| {
+            Url::parse(url)
+                .ok()
+                .and_then(|parsed| parsed.host_str().map(|host| config.allows_host(host)))
+                .unwrap_or(false)
+        })
+        .collect();
+    if urls.is_empty() {
+        return Ok(());
+    }
+
+    let mut previews = load_previews(pool, &urls).await?;
+    let stale_before = Utc::now()
+        - chrono::Duration::from_std(config.cache_ttl).unwrap_or(chrono::Duration::days(365));
+    let mut fetched_any = false;
+    for url in &urls {
+        if previews
+            .get(url)
+            .is_some_and(|row| row.fetched_at > stale_before)
+        {
+            continue;
+        }
+        let metadata = match fetch_metadata(client, url).await {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                tracing::debug!(url = %url, "Link preview fetch failed: {}", err);
+                PageMetadata::default()
+            }
+        };
+        let row = LinkPreviewRow {
+            url_hash: url_hash(url),
+            url: url.clone(),
+            title: metadata.title,
+            description: metadata.description,
+            site_name: metadata.site_name,
+            image_url: metadata.image,
+            fetched_at: Utc::now(),
+        };
+        paracord_db::link_previews::upsert_link_preview(pool, &row).await?;
+        fetched_any |= row.has_metadata();
+        previews.insert(url.clone(), row);
+    }
+    // Embeds that were already cached went out with the message itself.
+    if !fetched_any {
+        return Ok(());
+    }
+
+    let embeds: Vec<LinkEmbed> = urls
+        .iter()
+        .filter_map(|url| previews.remove(url).and_then(LinkEmbed::from_row))
+        .collect();
+    let payload = json!({
+        "id": job.message_id.to_string(),
+        "channel_id": job.channel_id.to_string(),
+        "guild_id": job.guild_id.map(|id| id.to_string()),
+        "embeds": embeds,
+    });
+    match job.guild_id {
+        Some(guild_id) => event_bus.dispatch(EVENT_MESSAGE_EMBEDS_UPDATE, payload, Some(guild_id)),
+        None => {
+            let recipient_ids =
+                paracord_db::dms::get_dm_recipient_ids(pool, job.channel_id).await?;
+            event_bus.dispatch_to_users(EVENT_MESSAGE_EMBEDS_UPDATE, payload, recipient_ids);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+enum FetchError {
+    #[error("unexpected status {0}")]
+    Status(reqwest::StatusCode),
+    #[error("not an HTML page")]
+    NotHtml,
+    #[error(transparent)]
+    Outbound(#[from] OutboundError),
+}
+
+/// GET `url` and parse the metadata in the first `max_body_bytes` of the
+/// HTML it ends up at.
+async fn fetch_metadata(client: &OutboundClient, url: &str) -> Result<PageMetadata, FetchError> {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        ACCEPT,
+        HeaderValue::from_static("text/html,application/xhtml+xml"),
+    );
+    let response = client.get(url, headers).await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(FetchError::Status(status));
+    }
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            let mime = value.split(';').next().unwrap_or_default().trim();
+            mime.eq_ignore_ascii_case("text/html")
+                || mime.eq_ignore_ascii_case("application/xhtml+xml")
+        });
+    if !is_html {
+        return Err(FetchError::NotHtml);
+    }
+    let final_url = response.url().clone();
+    // A cut-off page is fine: the metadata lives in `<head>`.
+    let (body, _truncated) = response.body().await?;
+    Ok(parse_metadata(&String::from_utf8_lossy(&body), &final_url))
+}
+
+/// Preview fields found in a page.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct PageMetadata {
+    title: Option<String>,
+    description: Option<String>,
+    site_name: Option<String>,
+    image: Option<String>,
+}
+
+/// OpenGraph and Twitter card metadata from `html`, falling back to the
+/// `<title>` element and the plain `description` meta tag. Relative image
+/// URLs are resolved against `base`.
+fn parse_metadata(html: &str, base: &Url) -> PageMetadata {
+    let mut meta: HashMap<String, String> = HashMap::new();
+    let mut title_element = None;
+    let lower = html.to_ascii_lowercase();
+    let mut pos = 0;
+    while let Some(offset) = lower[pos..].find('<') {
+        let start = pos + offset;
+        let rest = &lower[start..];
+        if rest.starts_with("</head") || rest.starts_with("<body") {
+            break;
+        }
+        if rest.starts_with("<!--") {
+            pos = lower[start..]
+                .find("-->")
+                .map_or(lower.len(), |end| start + end + 3);
+            continue;
+        }
+        let Some(tag_len) = rest.find('>') else {
+            break;
+        };
+        let tag_end = start + tag_len + 1;
+        if rest.starts_with("<title") && title_element.is_none() {
+            if let Some(close) = lower[tag_end..].find("</title") {
+                title_element = Some(decode_entities(&html[tag_end..tag_end + close]));
+            }
+        } else if rest.starts_with("<meta")
+            && rest
+                .as_bytes()
+                .get(5)
+                .is_some_and(|b| !b.is_ascii_alphanumeric())
+        {
+            let attrs = parse_attributes(&html[start + 5..tag_end - 1]);
+            let key = attrs
+                .get("property")
+                .or_else(|| attrs.get("name"))
+                .map(|key| key.to_ascii_lowercase());
+            if let (Some(key), Some(content)) = (key, attrs.get("content")) {
+                meta.entry(key).or_insert_with(|| content.clone());
+            }
+        }
+        pos = tag_end;
+    }
+
+    let first = |keys: &[&str]| {
+        keys.iter()
+            .filter_map(|key| meta.get(*key))
+            .map(|value| clean_text(value))
+            .find(|value| !value.is_empty())
+    };
+    let image = [
+        "og:image:secure_url",
+        "og:image:url",
+        "og:image",
+        "twitter:image",
+        "twitter:image:src",
+    ]
+    .iter()
+    .filter_map(|key| meta.get(*key))
+    .filter_map(|value| base.join(value.trim()).ok())
+    .find(|url| matches!(url.scheme(), "http" | "https") && url.as_str().len() <= MAX_URL_LEN)
+    .map(String::from);
+
+    PageMetadata {
+        title: first(&["og:title", "twitter:title"])
+            .or_else(|| {
+                title_element
+                    .map(|t| clean_text(&t))
+                    .filter(|t| !t.is_empty())
+            })
+            .map(|t| truncate_chars(t, MAX_TITLE_CHARS)),
+        description: first(&["og:description", "twitter:description", "description"])
+            .map(|d| truncate_chars(d, MAX_DESCRIPTION_CHARS)),
+        site_name: first(&["og:site_name"]).map(|s| truncate_chars(s, MAX_TITLE_CHARS)),
+        image,
+    }
+}
+
+/// Attributes of a tag, given the text between its name and `>`. Names are
+/// lowercased; values are entity-decoded.
+fn parse_attributes(s: &str) -> HashMap<String, String> {
+    let mut attrs = HashMap::new();
+    let mut rest = s;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        let name_len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '=' | '/' | '>'))
+            .unwrap_or(rest.len());
+        if name_len == 0 {
+            break;
+        }
+        let name = rest[..name_len].to_ascii_lowercase();
+        rest = rest[name_len..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, remaining) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let inner = &after[1..];
+                        let end = inner.find(quote).unwrap_or(inner.len());
+                        (&inner[..end], inner.get(end + 1..).unwrap_or_default())
+                    }
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        (&after[..end], &after[end..])
+                    }
+                };
+                rest = remaining;
+                decode_entities(value)
+            }
+            None => String::new(),
+        };
+        attrs.entry(name).or_insert(value);
+    }
+    attrs
+}
+
+/// Decode the character references that show up in titles and descriptions.
+/// Unknown named references are left as they are.
+fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| {
+                let name = &rest[1..1 + end];
+                let ch = match name {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some(' '),
+                    _ => {
+                        let code = if let Some(hex) =
+                            name.strip_prefix("#x").or_else(|| name.strip_prefix("#X"))
+                        {
+                            u32::from_str_radix(hex, 16).ok()
+                        } else {
+                            name.strip_prefix('#').and_then(|dec| dec.parse().ok())
+                        };
+                        code.and_then(char::from_u32)
+                    }
+                };
+                ch.map(|ch| (ch, end + 2))
+            });
+        match decoded {
+            Some((ch, len)) => {
+                out.push(ch);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Collapse whitespace runs and drop control characters.
+fn clean_text(s: &str) -> String {
+    s.split_whitespace()
+        .map(|word| word.chars().filter(|c| !c.is_control()).collect::<String>())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn truncate_chars(s: String, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((idx, _)) => format!("{}…", s[..idx].trim_end()),
+        None => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> Url {
+        Url::parse("https://example.com/articles/1").unwrap()
+    }
+
+    #[test]
+    fn prefers_opengraph_and_falls_back_to_twitter_and_title() {
+        let html = r#"<!doctype html><html><head>
+            <title>Plain  title</title>
+            <!-- <meta property="og:title" content="commented out"> -->
+            <meta name="twitter:title" content="Card title">
+            <meta property='og:title' content="Tom &amp; Jerry&#39;s &#x1F600;" />
+            <meta name=description content="Fallback description">
+            <meta property="og:site_name" content="Example">
+            <meta property="og:image" content="/img/cover.png">
+            </head><body><meta property="og:description" content="too late"></body></html>"#;
+        assert_eq!(
+            parse_metadata(html, &base()),
+            PageMetadata {
+                title: Some("Tom & Jerry's 😀".into()),
+                description: Some("Fallback description".into()),
+                site_name: Some("Example".into()),
+                image: Some("https://example.com/img/cover.png".into()),
+            }
+        );
+
+        let plain = parse_metadata("<title>\n  Just a\n page </title>", &base());
+        assert_eq!(plain.title.as_deref(), Some("Just a page"));
+        assert_eq!(plain.image, None);
+
+        let card = parse_metadata(
+            r#"<meta name="twitter:image" content="javascript:alert(1)"><meta name="twitter:description" content="Card">"#,
+            &base(),
+        );
+        assert_eq!(card.description.as_deref(), Some("Card"));
+        assert_eq!(card.image, None);
+    }
+
+    #[test]
+    fn long_text_is_truncated_on_char_boundaries() {
+        let title = "é".repeat(MAX_TITLE_CHARS + 10);
+        let html = format!(r#"<meta property="og:title" content="{title}">"#);
+        let parsed = parse_metadata(&html, &base()).title.unwrap();
+        assert_eq!(parsed.chars().count(), MAX_TITLE_CHARS + 1);
+        assert!(parsed.ends_with('…'));
+    }
+
+    #[test]
+    fn domain_policy_matches_subdomains() {
+        let config = LinkPreviewConfig {
+            allowed_domains: vec!["example.com".into(), "Docs.RS".into()],
+            denied_domains: vec!["private.example.com".into()],
+            ..Default::default()
+        };
+        assert!(config.allows_host("example.com"));
+        assert!(config.allows_host("www.example.com."));
+        assert!(config.allows_host("docs.rs"));
+        assert!(!config.allows_host("notexample.com"));
+        assert!(!config.allows_host("a.private.example.com"));
+        assert!(!config.allows_host("other.org"));
+        assert!(LinkPreviewConfig::default().allows_host("other.org"));
+    }
+
+    #[test]
+    fn preview_urls_are_distinct_and_capped() {
+        let content = "https://a.example https://a.example `https://code.example` \
+                       https://b.example https://c.example https://d.example https://e.example https://f.example";
+        assert_eq!(
+            preview_urls(content),
+            vec![
+                "https://a.example",
+                "https://b.example",
+                "https://c.example",
+                "https://d.example",
+                "https://e.example",
+            ]
+        );
+        assert_eq!(url_hash("https://a.example").len(), 64);
+    }
+}
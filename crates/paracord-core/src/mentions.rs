@@ -0,0 +1,191 @@
+use crate::entities::EntityKind;
+use crate::error::CoreError;
+use crate::permissions;
+use paracord_db::message_mentions::MessageMentions;
+use paracord_db::DbPool;
+use paracord_models::permissions::Permissions;
+use std::collections::HashMap;
+
+/// Server setting holding the message id the mention backfill works down
+/// from; seeded by migration, `0` once every older message has been parsed.
+pub const MENTION_BACKFILL_SETTING: &str = "message_mentions_backfill_before";
+
+/// Distinct users, roles or channels taken from one message; the rest are
+/// ignored so a mention-spam message can't fan out without bound.
+pub const MAX_MENTIONS_PER_KIND: usize = 100;
+
+/// Extract mentions from message content: `<@id>` / `<@!id>` for users,
+/// `<@&id>` for roles, `<#id>` for channels, plus `@everyone` and `@here`.
+///
+/// Anything inside inline code or fenced code blocks is skipped, as is a
+/// mention escaped with a backslash (`\<@id>`, `\@everyone`). Uses the same
+/// scanner as [`crate::entities::parse_entities`], so what is highlighted is
+/// exactly what notifies.
+pub fn parse_mentions(content: &str) -> MessageMentions {
+    let mut mentions = MessageMentions::default();
+    crate::entities::scan(content, |_, _, kind| {
+        let (ids, id) = match kind {
+            EntityKind::UserMention { id } => (&mut mentions.user_ids, id),
+            EntityKind::RoleMention { id } => (&mut mentions.role_ids, id),
+            EntityKind::ChannelMention { id } => (&mut mentions.channel_ids, id),
+            EntityKind::Everyone => {
+                mentions.everyone = true;
+                return true;
+            }
+            EntityKind::Here => {
+                mentions.here = true;
+                return true;
+            }
+            _ => return true,
+        };
+        let id = id.get();
+        if ids.len() < MAX_MENTIONS_PER_KIND && !ids.contains(&id) {
+            ids.push(id);
+        }
+        true
+    });
+    mentions
+}
+
+/// Parse `content` and store the mentions of a message in `space_id`.
+///
+/// `@everyone`, `@here` and mentions of roles that aren't mentionable only
+/// count when `can_mention_everyone` is set (the author holds
+/// MENTION_EVERYONE). Returns the mentions that were stored.
+pub async fn store_message_mentions(
+    pool: &DbPool,
+    message_id: i64,
+    space_id: i64,
+    content: &str,
+    can_mention_everyone: bool,
+) -> Result<MessageMentions, CoreError> {
+    let mut mentions = parse_mentions(content);
+    if !can_mention_everyone {
+        mentions.everyone = false;
+        mentions.here = false;
+    }
+    let stored = paracord_db::message_mentions::replace_message_mentions(
+        pool,
+        message_id,
+        space_id,
+        &mentions,
+        can_mention_everyone,
+    )
+    .await?;
+    Ok(stored)
+}
+
+/// Parse and store the mentions of up to `limit` messages sent before
+/// mention tracking existed, newest first, and move the backfill cursor past
+/// them. Returns how many messages were looked at; `0` means the backfill is
+/// done.
+///
+/// `@everyone` is judged by the author's current permissions, as it is when
+/// a message is edited. An author whose permissions can't be resolved any
+/// more (they left, or the space was deleted) is treated as not holding
+/// MENTION_EVERYONE.
+pub async fn backfill_message_mentions(pool: &DbPool, limit: i64) -> Result<u64, CoreError> {
+    let before_id = paracord_db::server_settings::get_setting(pool, MENTION_BACKFILL_SETTING)
+        .await?
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(0);
+    if before_id <= 0 {
+        return Ok(0);
+    }
+
+    let rows =
+        paracord_db::message_mentions::list_messages_for_mention_backfill(pool, before_id, limit)
+            .await?;
+    let mut owners: HashMap<i64, Option<i64>> = HashMap::new();
+    let mut can_mention_everyone: HashMap<(i64, i64, i64), bool> = HashMap::new();
+    for row in &rows {
+        let content = row.content.as_deref().unwrap_or_default();
+        if parse_mentions(content).is_empty() {
+            continue;
+        }
+        let key = (row.space_id, row.channel_id, row.author_id);
+        let allowed = match can_mention_everyone.get(&key) {
+            Some(allowed) => *allowed,
+            None => {
+                let owner_id = match owners.get(&row.space_id) {
+                    Some(owner_id) => *owner_id,
+                    None => {
+                        let owner_id = paracord_db::guilds::get_guild(pool, row.space_id)
+                            .await?
+                            .map(|guild| guild.owner_id);
+                        owners.insert(row.space_id, owner_id);
+                        owner_id
+                    }
+                };
+                let allowed = match owner_id {
+                    Some(owner_id) => permissions::compute_channel_permissions(
+                        pool,
+                        row.space_id,
+                        row.channel_id,
+                        owner_id,
+                        row.author_id,
+                    )
+                    .await
+                    .map(|perms| perms.contains(Permissions::MENTION_EVERYONE))
+                    .unwrap_or(false),
+                    None => false,
+                };
+                can_mention_everyone.insert(key, allowed);
+                allowed
+            }
+        };
+        store_message_mentions(pool, row.id, row.space_id, content, allowed).await?;
+    }
+
+    let next_before_id = if (rows.len() as i64) < limit {
+        0
+    } else {
+        rows.last().map(|row| row.id).unwrap_or(0)
+    };
+    paracord_db::server_settings::set_setting(
+        pool,
+        MENTION_BACKFILL_SETTING,
+        &next_before_id.to_string(),
+    )
+    .await?;
+    Ok(rows.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_users_roles_channels_and_broadcasts() {
+        let mentions = parse_mentions("hey <@1> and <@!2>, <@&3> see <#4> @everyone @here <@1>");
+        assert_eq!(mentions.user_ids, vec![1, 2]);
+        assert_eq!(mentions.role_ids, vec![3]);
+        assert_eq!(mentions.channel_ids, vec![4]);
+        assert!(mentions.everyone);
+        assert!(mentions.here);
+    }
+
+    #[test]
+    fn ignores_code_escapes_and_malformed_tags() {
+        let mentions = parse_mentions(
+            "`<@1>` ``a ` <@2> `` ```\n@everyone <#3>\n``` \\<@4> \\@here <@> <@x5> <@6 @everyones",
+        );
+        assert!(mentions.is_empty(), "unexpected mentions: {mentions:?}");
+
+        // An unclosed backtick is literal, so what follows still counts.
+        let mentions = parse_mentions("it`s <@7>, ü @here");
+        assert_eq!(mentions.user_ids, vec![7]);
+        assert!(mentions.here);
+    }
+
+    #[test]
+    fn caps_distinct_ids_per_kind() {
+        let content: String = (1..=MAX_MENTIONS_PER_KIND as i64 + 10)
+            .map(|id| format!("<@{id}>"))
+            .collect();
+        assert_eq!(
+            parse_mentions(&content).user_ids.len(),
+            MAX_MENTIONS_PER_KIND
+        );
+    }
+}
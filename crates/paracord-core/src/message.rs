@@ -72,6 +72,8 @@ pub struct CreateMessageOptions {
     pub allow_empty_content: bool,
     pub dm_e2ee: Option<DmE2eePayload>,
     pub nonce: Option<String>,
+    /// Longest plaintext content accepted, in grapheme clusters.
+    pub max_content_length: usize,
 }
 
 impl Default for CreateMessageOptions {
@@ -82,6 +84,7 @@ impl Default for CreateMessageOptions {
             allow_empty_content: false,
             dm_e2ee: None,
             nonce: None,
+            max_content_length: paracord_util::validation::DEFAULT_MAX_MESSAGE_LENGTH,
         }
     }
 }
@@ -105,8 +108,7 @@ pub async fn create_message(
             message_type: 0,
             reference_id,
             allow_empty_content: false,
-            dm_e2ee: None,
-            nonce: None,
+            ..Default::default()
         },
     )
     .await
@@ -132,13 +134,40 @@ pub async fn create_message_with_type(
             message_type,
             reference_id,
             allow_empty_content: false,
-            dm_e2ee: None,
-            nonce: None,
+            ..Default::default()
         },
     )
     .await
 }
 
+/// Reject writes to a DM channel when `user_id` and any other participant
+/// have blocked each other in either direction. Existing history stays
+/// readable; only new content is refused.
+pub async fn ensure_dm_not_blocked(
+    pool: &DbPool,
+    channel_id: i64,
+    user_id: i64,
+) -> Result<(), CoreError> {
+    let recipients = paracord_db::dms::get_dm_recipient_ids(pool, channel_id).await?;
+    for recipient_id in recipients {
+        if recipient_id == user_id {
+            continue;
+        }
+        if paracord_db::relationships::is_blocked_either_direction(pool, user_id, recipient_id)
+            .await?
+        {
+            return Err(CoreError::Blocked);
+        }
+    }
+    Ok(())
+}
+
+fn content_length_error(max_content_length: usize) -> CoreError {
+    CoreError::BadRequest(format!(
+        "Content must be between 1 and {max_content_length} characters"
+    ))
+}
+
 /// Create a message with explicit options (message type, attachment-only allowance, DM E2EE payload).
 pub async fn create_message_with_options(
     pool: &DbPool,
@@ -166,6 +195,9 @@ pub async fn create_message_with_options(
         .await?
         .ok_or(CoreError::NotFound)?;
 
+    // Set for guild channels: the space and whether the author may ping everyone.
+    let mut mention_scope: Option<(i64, bool)> = None;
+
     // Check permissions if guild channel
     if let Some(guild_id) = channel.guild_id() {
         if options.dm_e2ee.is_some() {
@@ -174,13 +206,13 @@ pub async fn create_message_with_options(
             ));
         }
         if !content.trim().is_empty() {
-            paracord_util::validation::validate_message_content(content).map_err(|_| {
-                CoreError::BadRequest("Content must be between 1 and 2000 characters".into())
-            })?;
+            paracord_util::validation::validate_message_content(
+                content,
+                options.max_content_length,
+            )
+            .map_err(|_| content_length_error(options.max_content_length))?;
         } else if !options.allow_empty_content {
-            return Err(CoreError::BadRequest(
-                "Content must be between 1 and 2000 characters".into(),
-            ));
+            return Err(content_length_error(options.max_content_length));
         }
 
         permissions::ensure_guild_member(pool, guild_id, author_id).await?;
@@ -207,25 +239,12 @@ pub async fn create_message_with_options(
         .await?;
         permissions::require_permission(perms, Permissions::VIEW_CHANNEL)?;
         permissions::require_permission(perms, Permissions::SEND_MESSAGES)?;
+        mention_scope = Some((guild_id, perms.contains(Permissions::MENTION_EVERYONE)));
     } else {
         if !paracord_db::dms::is_dm_recipient(pool, channel_id, author_id).await? {
             return Err(CoreError::Forbidden);
         }
-        let recipients = paracord_db::dms::get_dm_recipient_ids(pool, channel_id).await?;
-        for recipient_id in recipients {
-            if recipient_id == author_id {
-                continue;
-            }
-            if paracord_db::relationships::is_blocked_either_direction(
-                pool,
-                author_id,
-                recipient_id,
-            )
-            .await?
-            {
-                return Err(CoreError::Forbidden);
-            }
-        }
+        ensure_dm_not_blocked(pool, channel_id, author_id).await?;
 
         if let Some(dm_e2ee) = options.dm_e2ee.as_ref() {
             dm_e2ee.validate()?;
@@ -242,9 +261,7 @@ pub async fn create_message_with_options(
                 "Plaintext DM messages are disabled; update your client for encrypted DMs".into(),
             ));
         } else if !options.allow_empty_content {
-            return Err(CoreError::BadRequest(
-                "Message content must be between 1 and 2000 characters".into(),
-            ));
+            return Err(content_length_error(options.max_content_length));
         }
     }
 
@@ -267,9 +284,28 @@ pub async fn create_message_with_options(
     )
     .await?;
 
+    if let Some((guild_id, can_mention_everyone)) = mention_scope {
+        // The message is already stored; missing mentions only affect
+        // mention counts, so don't fail the send over them.
+        if let Err(e) = crate::mentions::store_message_mentions(
+            pool,
+            msg.id,
+            guild_id,
+            &stored_content,
+            can_mention_everyone,
+        )
+        .await
+        {
+            tracing::warn!("Failed to store mentions for message {}: {}", msg.id, e);
+        }
+    }
+
     Ok(msg)
 }
 
+/// Prior revisions kept per message when the caller has no configured limit.
+pub const DEFAULT_EDIT_HISTORY_LIMIT: u32 = 25;
+
 /// Edit a message. Only the author can edit, unless user has MANAGE_MESSAGES.
 pub async fn edit_message(
     pool: &DbPool,
@@ -278,10 +314,25 @@ pub async fn edit_message(
     user_id: i64,
     content: &str,
 ) -> Result<paracord_db::messages::MessageRow, CoreError> {
-    edit_message_with_options(pool, channel_id, message_id, user_id, content, None).await
+    edit_message_with_options(
+        pool,
+        channel_id,
+        message_id,
+        user_id,
+        content,
+        None,
+        DEFAULT_EDIT_HISTORY_LIMIT,
+        paracord_util::validation::DEFAULT_MAX_MESSAGE_LENGTH,
+    )
+    .await
 }
 
 /// Edit a message with optional DM E2EE payload.
+///
+/// In guild channels the previous content is kept as a revision, with at
+/// most `edit_history_limit` revisions per message (0 keeps none). New
+/// plaintext content may be at most `max_content_length` grapheme clusters.
+#[allow(clippy::too_many_arguments)]
 pub async fn edit_message_with_options(
     pool: &DbPool,
     channel_id: i64,
@@ -289,6 +340,8 @@ pub async fn edit_message_with_options(
     user_id: i64,
     content: &str,
     dm_e2ee: Option<DmE2eePayload>,
+    edit_history_limit: u32,
+    max_content_length: usize,
 ) -> Result<paracord_db::messages::MessageRow, CoreError> {
     let mut stored_content = content.to_string();
     let mut nonce: Option<String> = None;
@@ -310,9 +363,8 @@ pub async fn edit_message_with_options(
                 "DM E2EE payloads are only valid for direct messages".into(),
             ));
         }
-        paracord_util::validation::validate_message_content(content).map_err(|_| {
-            CoreError::BadRequest("Content must be between 1 and 2000 characters".into())
-        })?;
+        paracord_util::validation::validate_message_content(content, max_content_length)
+            .map_err(|_| content_length_error(max_content_length))?;
     } else {
         if !paracord_db::dms::is_dm_recipient(pool, channel_id, user_id).await? {
             return Err(CoreError::Forbidden);
@@ -332,9 +384,7 @@ pub async fn edit_message_with_options(
                 "Plaintext DM messages are disabled; update your client for encrypted DMs".into(),
             ));
         } else {
-            return Err(CoreError::BadRequest(
-                "Content must be between 1 and 2000 characters".into(),
-            ));
+            return Err(content_length_error(max_content_length));
         }
     }
 
@@ -349,6 +399,31 @@ pub async fn edit_message_with_options(
     )
     .await?;
     if let Some(updated) = updated {
+        if let Some(guild_id) = channel.guild_id() {
+            if let Err(e) = refresh_message_mentions(pool, guild_id, channel_id, &updated).await {
+                tracing::warn!("Failed to store mentions for message {}: {}", message_id, e);
+            }
+        }
+        // DM contents are end-to-end encrypted, so a stored revision would
+        // be unreadable ciphertext; only guild messages keep history.
+        if channel.guild_id().is_some() && edit_history_limit > 0 {
+            if let Err(e) = paracord_db::message_edits::record_message_edit(
+                pool,
+                paracord_util::snowflake::generate(),
+                message_id,
+                user_id,
+                msg.content.as_deref(),
+                i64::from(edit_history_limit),
+            )
+            .await
+            {
+                tracing::warn!(
+                    "Failed to record edit history for message {}: {}",
+                    message_id,
+                    e
+                );
+            }
+        }
         return Ok(updated);
     }
 
@@ -364,6 +439,36 @@ pub async fn edit_message_with_options(
     Err(CoreError::MissingPermission)
 }
 
+/// Re-parse an edited guild message's mentions, judging `@everyone` by the
+/// author's current permissions.
+async fn refresh_message_mentions(
+    pool: &DbPool,
+    guild_id: i64,
+    channel_id: i64,
+    msg: &paracord_db::messages::MessageRow,
+) -> Result<(), CoreError> {
+    let guild = paracord_db::guilds::get_guild(pool, guild_id)
+        .await?
+        .ok_or(CoreError::NotFound)?;
+    let perms = permissions::compute_channel_permissions(
+        pool,
+        guild_id,
+        channel_id,
+        guild.owner_id,
+        msg.author_id,
+    )
+    .await?;
+    crate::mentions::store_message_mentions(
+        pool,
+        msg.id,
+        guild_id,
+        msg.content.as_deref().unwrap_or_default(),
+        perms.contains(Permissions::MENTION_EVERYONE),
+    )
+    .await?;
+    Ok(())
+}
+
 /// Delete a message. Author can delete own, or MANAGE_MESSAGES can delete any.
 pub async fn delete_message(
     pool: &DbPool,
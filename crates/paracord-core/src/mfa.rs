@@ -0,0 +1,88 @@
+use crate::auth::{hex_decode, hex_encode, AuthError};
+use paracord_util::at_rest::{derive_key_from_secret, FileCryptor};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Number of single-use recovery codes issued when 2FA is enabled.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+/// Accept codes from one step before/after the current one to absorb clock skew.
+pub const TOTP_WINDOW_STEPS: i64 = 1;
+
+fn totp_cryptor(jwt_secret: &str) -> FileCryptor {
+    FileCryptor::from_master_key(&derive_key_from_secret(jwt_secret, b"totp"), false)
+}
+
+/// Encrypt a TOTP secret for storage on the user row. The user id is bound as
+/// associated data so a ciphertext cannot be copied onto another account.
+pub fn seal_totp_secret(
+    jwt_secret: &str,
+    user_id: i64,
+    secret: &[u8],
+) -> Result<String, AuthError> {
+    let sealed = totp_cryptor(jwt_secret)
+        .encrypt_with_aad(secret, user_id.to_string().as_bytes())
+        .map_err(|e| AuthError::Internal(e.to_string()))?;
+    Ok(hex_encode(&sealed))
+}
+
+pub fn open_totp_secret(
+    jwt_secret: &str,
+    user_id: i64,
+    stored: &str,
+) -> Result<Vec<u8>, AuthError> {
+    let sealed =
+        hex_decode(stored).ok_or_else(|| AuthError::Internal("invalid totp secret".into()))?;
+    totp_cryptor(jwt_secret)
+        .decrypt_with_aad(&sealed, user_id.to_string().as_bytes())
+        .map_err(|e| AuthError::Internal(e.to_string()))
+}
+
+/// Generate human-friendly recovery codes in `xxxxx-xxxxx` form.
+pub fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0_u8; 5];
+            rng.fill_bytes(&mut bytes);
+            let hex = hex_encode(&bytes);
+            format!("{}-{}", &hex[..5], &hex[5..])
+        })
+        .collect()
+}
+
+/// Hash a recovery code for storage or lookup. Input is normalized so that
+/// case, spaces and dashes do not matter.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    let digest = Sha256::digest(normalized.as_bytes());
+    hex_encode(&digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_secret_roundtrips_for_same_user_only() {
+        let sealed = seal_totp_secret("jwt", 42, b"0123456789").expect("seal");
+        assert_eq!(open_totp_secret("jwt", 42, &sealed).unwrap(), b"0123456789");
+        assert!(open_totp_secret("jwt", 43, &sealed).is_err());
+        assert!(open_totp_secret("other", 42, &sealed).is_err());
+    }
+
+    #[test]
+    fn recovery_codes_are_unique_and_normalized_for_hashing() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        let unique: std::collections::HashSet<_> = codes.iter().collect();
+        assert_eq!(unique.len(), codes.len());
+        assert_eq!(
+            hash_recovery_code("ABCDE-12345"),
+            hash_recovery_code("abcde 12345")
+        );
+    }
+}
@@ -1,24 +1,329 @@
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Mutex, OnceLock};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::collections::HashSet;
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::Duration;
 
+const METRICS_NAMESPACE: &str = "paracord";
 const EVENT_TYPE_FALLBACK: &str = "OTHER";
 const MAX_EVENT_TYPE_LEN: usize = 64;
 const MAX_EVENT_TYPE_KEYS: usize = 128;
+/// Route label for requests that matched no route, so unknown paths can't
+/// blow up label cardinality.
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+const RETENTION_TABLES: [&str; 5] = [
+    "messages",
+    "attachments",
+    "audit_log",
+    "security_events",
+    "sessions",
+];
 
-static WS_CONNECTIONS_ACTIVE: AtomicU64 = AtomicU64::new(0);
-static WS_EVENTS_TOTAL: AtomicU64 = AtomicU64::new(0);
-static WS_EVENTS_BY_TYPE: OnceLock<Mutex<HashMap<String, u64>>> = OnceLock::new();
+/// Every metric served on `/metrics`, registered once per process.
+struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    http_rate_limit_checks: IntCounterVec,
+    http_rate_limited: IntCounterVec,
+    http_rate_limit_remaining_sum: IntCounterVec,
+    http_rate_limit_max_requests: IntGaugeVec,
+    http_rate_limit_window_seconds: IntGaugeVec,
+    http_rate_limit_tracked_keys: IntGauge,
+    http_rate_limit_evictions: IntCounterVec,
+    ws_connections_active: IntGauge,
+    ws_events: IntCounter,
+    ws_events_by_type: IntCounterVec,
+    livekit_proxy_connections_active: IntGauge,
+    permission_cache_lookups: IntCounterVec,
+    retention_runs: IntCounter,
+    retention_rows_purged: IntCounterVec,
+    retention_last_run_rows_purged: IntGaugeVec,
+    channel_retention_rows_purged: IntCounterVec,
+    db_pool_connections: IntGaugeVec,
+    db_pool_max_connections: IntGauge,
+}
+
+fn opts(name: &str, help: &str) -> Opts {
+    Opts::new(name, help).namespace(METRICS_NAMESPACE)
+}
+
+/// Register a metric built by [`Metrics::new`]. Names, help text and labels
+/// there are all constants, so an error here is a bug in that table, not a
+/// runtime condition to recover from.
+fn register<T: prometheus::core::Collector + Clone + 'static>(
+    registry: &Registry,
+    metric: prometheus::Result<T>,
+) -> T {
+    let metric = metric.expect("metric names and labels are valid");
+    registry
+        .register(Box::new(metric.clone()))
+        .expect("metric names are unique");
+    metric
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        let up = register(
+            &registry,
+            IntGauge::with_opts(opts("up", "Whether the server is up.")),
+        );
+        up.set(1);
+        Self {
+            http_requests: register(
+                &registry,
+                IntCounterVec::new(
+                    opts("http_requests_total", "HTTP requests by method, matched route and status code."),
+                    &["method", "route", "status"],
+                ),
+            ),
+            http_request_duration: register(
+                &registry,
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "http_request_duration_seconds",
+                        "HTTP request latency by method and matched route.",
+                    )
+                    .namespace(METRICS_NAMESPACE),
+                    &["method", "route"],
+                ),
+            ),
+            http_rate_limit_checks: register(
+                &registry,
+                IntCounterVec::new(
+                    opts("http_rate_limit_checks_total", "Requests checked by the rate limiter, by route category."),
+                    &["category"],
+                ),
+            ),
+            http_rate_limited: register(
+                &registry,
+                IntCounterVec::new(
+                    opts("http_rate_limited_total", "Requests rejected by the rate limiter, by route category."),
+                    &["category"],
+                ),
+            ),
+            http_rate_limit_remaining_sum: register(
+                &registry,
+                IntCounterVec::new(
+                    opts(
+                        "http_rate_limit_remaining_sum",
+                        "Sum of remaining quota observed at each check (divide by checks_total for average headroom).",
+                    ),
+                    &["category"],
+                ),
+            ),
+            http_rate_limit_max_requests: register(
+                &registry,
+                IntGaugeVec::new(
+                    opts("http_rate_limit_max_requests", "Configured requests allowed per window, by route category."),
+                    &["category"],
+                ),
+            ),
+            http_rate_limit_window_seconds: register(
+                &registry,
+                IntGaugeVec::new(
+                    opts("http_rate_limit_window_seconds", "Configured rate limit window, by route category."),
+                    &["category"],
+                ),
+            ),
+            http_rate_limit_tracked_keys: register(
+                &registry,
+                IntGauge::with_opts(opts("http_rate_limit_tracked_keys", "Client buckets currently held by the HTTP rate limiter.")),
+            ),
+            http_rate_limit_evictions: register(
+                &registry,
+                IntCounterVec::new(
+                    opts("http_rate_limit_evictions_total", "Rate limiter buckets dropped, by reason (idle or capacity)."),
+                    &["reason"],
+                ),
+            ),
+            ws_connections_active: register(
+                &registry,
+                IntGauge::with_opts(opts("ws_connections_active", "Active WebSocket gateway connections.")),
+            ),
+            ws_events: register(
+                &registry,
+                IntCounter::with_opts(opts("ws_events_total", "Total WebSocket events dispatched.")),
+            ),
+            ws_events_by_type: register(
+                &registry,
+                IntCounterVec::new(
+                    opts("ws_events_by_type_total", "Total WebSocket events dispatched by event type."),
+                    &["event_type"],
+                ),
+            ),
+            livekit_proxy_connections_active: register(
+                &registry,
+                IntGauge::with_opts(opts(
+                    "livekit_proxy_connections_active",
+                    "Active WebSocket connections proxied to LiveKit.",
+                )),
+            ),
+            permission_cache_lookups: register(
+                &registry,
+                IntCounterVec::new(
+                    opts("permission_cache_lookups_total", "Channel permission cache lookups, by result."),
+                    &["result"],
+                ),
+            ),
+            retention_runs: register(
+                &registry,
+                IntCounter::with_opts(opts("retention_runs_total", "Completed retention worker passes.")),
+            ),
+            retention_rows_purged: register(
+                &registry,
+                IntCounterVec::new(
+                    opts("retention_rows_purged_total", "Rows deleted by the retention worker, by table."),
+                    &["table"],
+                ),
+            ),
+            retention_last_run_rows_purged: register(
+                &registry,
+                IntGaugeVec::new(
+                    opts(
+                        "retention_last_run_rows_purged",
+                        "Rows deleted by the most recent retention pass, by table.",
+                    ),
+                    &["table"],
+                ),
+            ),
+            channel_retention_rows_purged: register(
+                &registry,
+                IntCounterVec::new(
+                    opts(
+                        "channel_retention_rows_purged_total",
+                        "Rows deleted by per-channel message retention, by channel and table.",
+                    ),
+                    &["channel_id", "table"],
+                ),
+            ),
+            db_pool_connections: register(
+                &registry,
+                IntGaugeVec::new(
+                    opts("db_pool_connections", "Database pool connections, by state (idle or in_use)."),
+                    &["state"],
+                ),
+            ),
+            db_pool_max_connections: register(
+                &registry,
+                IntGauge::with_opts(opts("db_pool_max_connections", "Configured database pool size.")),
+            ),
+            registry,
+        }
+    }
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+/// Event types that already have a `ws_events_by_type_total` series.
+static WS_EVENT_TYPES: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+static LIVEKIT_STATUS: Mutex<LiveKitStatusSnapshot> = Mutex::new(LiveKitStatusSnapshot {
+    status: LiveKitStatus::Unavailable,
+    restarts: 0,
+});
 static WIRE_TRACE_ENABLED: OnceLock<bool> = OnceLock::new();
 static WIRE_TRACE_PAYLOADS_ENABLED: OnceLock<bool> = OnceLock::new();
 static WIRE_TRACE_PAYLOAD_MAX_BYTES: OnceLock<usize> = OnceLock::new();
 
-fn ws_events_by_type() -> &'static Mutex<HashMap<String, u64>> {
-    WS_EVENTS_BY_TYPE.get_or_init(|| Mutex::new(HashMap::new()))
+/// Render every registered metric in the Prometheus text exposition format.
+pub fn render_prometheus() -> String {
+    let mut buffer = Vec::new();
+    let encoder = TextEncoder::new();
+    if let Err(err) = encoder.encode(&METRICS.registry.gather(), &mut buffer) {
+        tracing::warn!("Failed to encode metrics: {}", err);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// Label value for an HTTP method; anything non-standard collapses to
+/// `OTHER`.
+fn method_label(method: &str) -> &str {
+    match method {
+        "GET" | "HEAD" | "POST" | "PUT" | "PATCH" | "DELETE" | "OPTIONS" => method,
+        _ => "OTHER",
+    }
+}
+
+/// Record a finished HTTP request. `route` must be a matched route pattern
+/// (or [`UNMATCHED_ROUTE`]), never a raw request path.
+pub fn http_request_completed(method: &str, route: &str, status: u16, elapsed: Duration) {
+    let method = method_label(method);
+    METRICS
+        .http_requests
+        .with_label_values(&[method, route, &status.to_string()])
+        .inc();
+    METRICS
+        .http_request_duration
+        .with_label_values(&[method, route])
+        .observe(elapsed.as_secs_f64());
+}
+
+/// Record one rate limiter check for a route category.
+pub fn http_rate_limit_checked(category: &str, remaining: u32, allowed: bool) {
+    METRICS
+        .http_rate_limit_checks
+        .with_label_values(&[category])
+        .inc();
+    METRICS
+        .http_rate_limit_remaining_sum
+        .with_label_values(&[category])
+        .inc_by(u64::from(remaining));
+    if !allowed {
+        METRICS
+            .http_rate_limited
+            .with_label_values(&[category])
+            .inc();
+    }
+}
+
+/// Publish the configured limit for a route category.
+pub fn set_http_rate_limit(category: &str, max_requests: u32, window_seconds: i64) {
+    METRICS
+        .http_rate_limit_max_requests
+        .with_label_values(&[category])
+        .set(i64::from(max_requests));
+    METRICS
+        .http_rate_limit_window_seconds
+        .with_label_values(&[category])
+        .set(window_seconds);
+}
+
+/// Publish how many client buckets the HTTP rate limiter is tracking.
+pub fn set_http_rate_limit_tracked_keys(count: usize) {
+    METRICS
+        .http_rate_limit_tracked_keys
+        .set(i64::try_from(count).unwrap_or(i64::MAX));
+}
+
+/// Record rate limiter buckets dropped for `reason` (`idle` or `capacity`).
+pub fn http_rate_limit_evicted(reason: &str, count: usize) {
+    if count > 0 {
+        METRICS
+            .http_rate_limit_evictions
+            .with_label_values(&[reason])
+            .inc_by(count as u64);
+    }
 }
 
-fn lock_ws_events_by_type() -> std::sync::MutexGuard<'static, HashMap<String, u64>> {
-    match ws_events_by_type().lock() {
+/// Publish database pool utilization: open connections, how many of them
+/// are idle, and the configured maximum.
+pub fn set_db_pool_stats(open: u32, idle: usize, max: u32) {
+    let idle = i64::try_from(idle).unwrap_or(i64::MAX);
+    METRICS
+        .db_pool_connections
+        .with_label_values(&["idle"])
+        .set(idle);
+    METRICS
+        .db_pool_connections
+        .with_label_values(&["in_use"])
+        .set((i64::from(open) - idle).max(0));
+    METRICS.db_pool_max_connections.set(i64::from(max));
+}
+
+fn lock_ws_event_types() -> std::sync::MutexGuard<'static, Option<HashSet<String>>> {
+    match WS_EVENT_TYPES.lock() {
         Ok(guard) => guard,
         Err(poisoned) => poisoned.into_inner(),
     }
@@ -92,25 +397,47 @@ pub fn wire_trace_payload_preview(raw: &str) -> Option<String> {
 }
 
 pub fn ws_connection_open() {
-    WS_CONNECTIONS_ACTIVE.fetch_add(1, Ordering::Relaxed);
+    METRICS.ws_connections_active.inc();
 }
 
 pub fn ws_connection_close() {
-    let _ = WS_CONNECTIONS_ACTIVE.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
-        Some(current.saturating_sub(1))
-    });
+    if METRICS.ws_connections_active.get() > 0 {
+        METRICS.ws_connections_active.dec();
+    }
+}
+
+pub fn livekit_proxy_connection_open() {
+    METRICS.livekit_proxy_connections_active.inc();
+}
+
+pub fn livekit_proxy_connection_close() {
+    if METRICS.livekit_proxy_connections_active.get() > 0 {
+        METRICS.livekit_proxy_connections_active.dec();
+    }
+}
+
+pub fn livekit_proxy_active_connections() -> u64 {
+    METRICS.livekit_proxy_connections_active.get().max(0) as u64
 }
 
 pub fn ws_event_dispatched(event_type: &str) {
-    WS_EVENTS_TOTAL.fetch_add(1, Ordering::Relaxed);
+    METRICS.ws_events.inc();
 
     let mut normalized = normalize_event_type(event_type);
-    let mut by_type = lock_ws_events_by_type();
-    if !by_type.contains_key(&normalized) && by_type.len() >= MAX_EVENT_TYPE_KEYS {
-        normalized = EVENT_TYPE_FALLBACK.to_string();
+    {
+        let mut guard = lock_ws_event_types();
+        let known = guard.get_or_insert_with(HashSet::new);
+        if !known.contains(&normalized) {
+            if known.len() >= MAX_EVENT_TYPE_KEYS {
+                normalized = EVENT_TYPE_FALLBACK.to_string();
+            }
+            known.insert(normalized.clone());
+        }
     }
-    let entry = by_type.entry(normalized).or_insert(0);
-    *entry = entry.saturating_add(1);
+    METRICS
+        .ws_events_by_type
+        .with_label_values(&[normalized.as_str()])
+        .inc();
 }
 
 #[derive(Clone, Debug, Default)]
@@ -121,32 +448,218 @@ pub struct WsMetricsSnapshot {
 }
 
 pub fn ws_metrics_snapshot() -> WsMetricsSnapshot {
-    let active_connections = WS_CONNECTIONS_ACTIVE.load(Ordering::Relaxed);
-    let total_events = WS_EVENTS_TOTAL.load(Ordering::Relaxed);
-    let mut events_by_type: Vec<(String, u64)> = lock_ws_events_by_type()
+    let mut events_by_type: Vec<(String, u64)> = lock_ws_event_types()
         .iter()
-        .map(|(event_type, count)| (event_type.clone(), *count))
+        .flatten()
+        .map(|event_type| {
+            let count = METRICS
+                .ws_events_by_type
+                .with_label_values(&[event_type.as_str()])
+                .get();
+            (event_type.clone(), count)
+        })
         .collect();
     events_by_type.sort_by(|a, b| a.0.cmp(&b.0));
 
     WsMetricsSnapshot {
-        active_connections,
-        total_events,
+        active_connections: METRICS.ws_connections_active.get().max(0) as u64,
+        total_events: METRICS.ws_events.get(),
         events_by_type,
     }
 }
 
+pub fn permission_cache_hit() {
+    METRICS
+        .permission_cache_lookups
+        .with_label_values(&["hit"])
+        .inc();
+}
+
+pub fn permission_cache_miss() {
+    METRICS
+        .permission_cache_lookups
+        .with_label_values(&["miss"])
+        .inc();
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PermissionCacheSnapshot {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+pub fn permission_cache_snapshot() -> PermissionCacheSnapshot {
+    PermissionCacheSnapshot {
+        hits: METRICS
+            .permission_cache_lookups
+            .with_label_values(&["hit"])
+            .get(),
+        misses: METRICS
+            .permission_cache_lookups
+            .with_label_values(&["miss"])
+            .get(),
+    }
+}
+
+/// Rows removed by one retention pass, per table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RetentionPurgeCounts {
+    pub messages: u64,
+    pub attachments: u64,
+    pub audit_log: u64,
+    pub security_events: u64,
+    pub sessions: u64,
+}
+
+impl RetentionPurgeCounts {
+    /// `(table label, rows)` pairs in a stable order for metrics output.
+    pub fn by_table(&self) -> [(&'static str, u64); 5] {
+        [
+            (RETENTION_TABLES[0], self.messages),
+            (RETENTION_TABLES[1], self.attachments),
+            (RETENTION_TABLES[2], self.audit_log),
+            (RETENTION_TABLES[3], self.security_events),
+            (RETENTION_TABLES[4], self.sessions),
+        ]
+    }
+
+    fn from_by_table(mut rows: impl FnMut(&'static str) -> u64) -> Self {
+        Self {
+            messages: rows(RETENTION_TABLES[0]),
+            attachments: rows(RETENTION_TABLES[1]),
+            audit_log: rows(RETENTION_TABLES[2]),
+            security_events: rows(RETENTION_TABLES[3]),
+            sessions: rows(RETENTION_TABLES[4]),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RetentionMetricsSnapshot {
+    pub runs: u64,
+    pub total: RetentionPurgeCounts,
+    pub last_run: RetentionPurgeCounts,
+}
+
+pub fn retention_run_completed(purged: RetentionPurgeCounts) {
+    METRICS.retention_runs.inc();
+    for (table, rows) in purged.by_table() {
+        METRICS
+            .retention_rows_purged
+            .with_label_values(&[table])
+            .inc_by(rows);
+        METRICS
+            .retention_last_run_rows_purged
+            .with_label_values(&[table])
+            .set(i64::try_from(rows).unwrap_or(i64::MAX));
+    }
+}
+
+/// Record messages and attachments reaped from one channel by its own
+/// retention setting. Only channels with retention configured get a series.
+pub fn channel_retention_purged(channel_id: i64, messages: u64, attachments: u64) {
+    let channel_id = channel_id.to_string();
+    for (table, rows) in [("messages", messages), ("attachments", attachments)] {
+        METRICS
+            .channel_retention_rows_purged
+            .with_label_values(&[channel_id.as_str(), table])
+            .inc_by(rows);
+    }
+}
+
+pub fn retention_metrics_snapshot() -> RetentionMetricsSnapshot {
+    RetentionMetricsSnapshot {
+        runs: METRICS.retention_runs.get(),
+        total: RetentionPurgeCounts::from_by_table(|table| {
+            METRICS
+                .retention_rows_purged
+                .with_label_values(&[table])
+                .get()
+        }),
+        last_run: RetentionPurgeCounts::from_by_table(|table| {
+            METRICS
+                .retention_last_run_rows_purged
+                .with_label_values(&[table])
+                .get()
+                .max(0) as u64
+        }),
+    }
+}
+
+/// Lifecycle of the LiveKit server backing voice, as seen by this process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LiveKitStatus {
+    /// No LiveKit binary or endpoint is available.
+    Unavailable,
+    /// LiveKit is run by someone else; we do not supervise it.
+    External,
+    /// The managed LiveKit process is up.
+    Running,
+    /// The managed process exited and is waiting to be respawned.
+    Restarting,
+    /// The managed process kept crashing and supervision gave up.
+    Failed,
+}
+
+impl LiveKitStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unavailable => "unavailable",
+            Self::External => "external",
+            Self::Running => "running",
+            Self::Restarting => "restarting",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LiveKitStatusSnapshot {
+    pub status: LiveKitStatus,
+    /// Times the managed process has been respawned after exiting.
+    pub restarts: u64,
+}
+
+fn lock_livekit_status() -> std::sync::MutexGuard<'static, LiveKitStatusSnapshot> {
+    match LIVEKIT_STATUS.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+pub fn set_livekit_status(status: LiveKitStatus) {
+    lock_livekit_status().status = status;
+}
+
+pub fn livekit_restarted() {
+    let mut snapshot = lock_livekit_status();
+    snapshot.restarts = snapshot.restarts.saturating_add(1);
+}
+
+pub fn livekit_status_snapshot() -> LiveKitStatusSnapshot {
+    *lock_livekit_status()
+}
+
 #[cfg(test)]
 fn reset_for_tests() {
-    WS_CONNECTIONS_ACTIVE.store(0, Ordering::Relaxed);
-    WS_EVENTS_TOTAL.store(0, Ordering::Relaxed);
-    lock_ws_events_by_type().clear();
+    METRICS.ws_connections_active.set(0);
+    METRICS.ws_events.reset();
+    METRICS.ws_events_by_type.reset();
+    *lock_ws_event_types() = None;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn metric_table_registers_cleanly() {
+        // Building a second copy checks every definition without touching
+        // the process-wide registry.
+        let metrics = Metrics::new();
+        assert!(!metrics.registry.gather().is_empty());
+    }
+
     #[test]
     fn ws_connection_close_is_saturating() {
         reset_for_tests();
@@ -179,4 +692,65 @@ mod tests {
         assert_eq!(message, Some(1));
         assert_eq!(other, Some(2));
     }
+
+    #[test]
+    fn http_requests_are_exported_with_route_labels_and_latency_buckets() {
+        http_request_completed("GET", "/api/v1/test/{id}", 404, Duration::from_millis(30));
+        http_request_completed("BREW", UNMATCHED_ROUTE, 418, Duration::from_millis(1));
+        http_rate_limit_checked("write", 3, false);
+
+        let text = render_prometheus();
+        assert!(text.contains(
+            "paracord_http_requests_total{method=\"GET\",route=\"/api/v1/test/{id}\",status=\"404\"} 1"
+        ));
+        assert!(text.contains(
+            "paracord_http_request_duration_seconds_bucket{method=\"GET\",route=\"/api/v1/test/{id}\",le=\"0.025\"} 0"
+        ));
+        assert!(text.contains(
+            "paracord_http_request_duration_seconds_bucket{method=\"GET\",route=\"/api/v1/test/{id}\",le=\"0.05\"} 1"
+        ));
+        assert!(text.contains(
+            "paracord_http_requests_total{method=\"OTHER\",route=\"unmatched\",status=\"418\"} 1"
+        ));
+        assert!(text.contains("paracord_http_rate_limited_total{category=\"write\"}"));
+        assert!(text.contains("paracord_up 1"));
+    }
+
+    #[test]
+    fn retention_runs_accumulate_totals_and_keep_last_run() {
+        let before = retention_metrics_snapshot();
+        retention_run_completed(RetentionPurgeCounts {
+            audit_log: 5,
+            security_events: 2,
+            ..Default::default()
+        });
+        retention_run_completed(RetentionPurgeCounts {
+            audit_log: 1,
+            ..Default::default()
+        });
+
+        let snapshot = retention_metrics_snapshot();
+        assert_eq!(snapshot.runs - before.runs, 2);
+        assert_eq!(snapshot.total.audit_log - before.total.audit_log, 6);
+        assert_eq!(
+            snapshot.total.security_events - before.total.security_events,
+            2
+        );
+        assert_eq!(snapshot.last_run.audit_log, 1);
+        assert_eq!(snapshot.last_run.security_events, 0);
+    }
+
+    #[test]
+    fn channel_retention_is_reported_per_channel() {
+        channel_retention_purged(424242, 3, 1);
+        channel_retention_purged(424242, 2, 0);
+
+        let text = render_prometheus();
+        assert!(text.contains(
+            "paracord_channel_retention_rows_purged_total{channel_id=\"424242\",table=\"messages\"} 5"
+        ));
+        assert!(text.contains(
+            "paracord_channel_retention_rows_purged_total{channel_id=\"424242\",table=\"attachments\"} 1"
+        ));
+    }
 }
@@ -0,0 +1,454 @@
+//! Guarded outbound HTTP for URLs that users or remote servers control.
+//!
+//! Link previews, federation requests, Web Push deliveries and bot
+//! interaction endpoints go to hosts we don't trust, so a crafted URL must
+//! not be able to reach loopback, the LAN, or cloud metadata endpoints.
+//! Every fetch made through [`OutboundClient`] (or a client from
+//! [`guarded_client_builder`]):
+//!
+//! - resolves names itself and refuses hosts with any non-public address,
+//!   and the connection uses exactly the addresses that were checked;
+//! - refuses IP literals in non-public ranges before connecting;
+//! - re-checks every redirect target and stops after a fixed number of hops;
+//! - is bounded in total time and, via [`OutboundResponse::body`], size.
+//!
+//! The LiveKit proxy talks to the locally managed media server and is not
+//! routed through here.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::{StatusCode, Url};
+use tokio::time::Instant;
+
+/// Redirects followed before a fetch is abandoned.
+pub const DEFAULT_MAX_REDIRECTS: usize = 3;
+
+type HostFilterFn = dyn Fn(&str) -> bool + Send + Sync;
+type HostFilter = Arc<HostFilterFn>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum OutboundError {
+    #[error("unsupported URL")]
+    UnsupportedUrl,
+    #[error("host is not allowed")]
+    HostNotAllowed,
+    #[error("address {0} is not public")]
+    PrivateAddress(IpAddr),
+    #[error("host did not resolve")]
+    Unresolved,
+    #[error("too many redirects")]
+    TooManyRedirects,
+    #[error("response body exceeds {0} bytes")]
+    TooLarge(usize),
+    #[error("timed out")]
+    Timeout,
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+}
+
+/// Limits applied to every fetch made by an [`OutboundClient`].
+#[derive(Debug, Clone)]
+pub struct OutboundPolicy {
+    /// Limit on one whole fetch: connecting, redirects and reading the body.
+    pub timeout: Duration,
+    /// Body bytes read before the response is cut off.
+    pub max_body_bytes: usize,
+    pub max_redirects: usize,
+    pub user_agent: String,
+}
+
+impl Default for OutboundPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            max_body_bytes: 1024 * 1024,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            user_agent: "Paracord".to_string(),
+        }
+    }
+}
+
+/// HTTP client that only fetches from public addresses. Cheap to clone.
+#[derive(Clone)]
+pub struct OutboundClient {
+    http: reqwest::Client,
+    policy: OutboundPolicy,
+    host_filter: Option<HostFilter>,
+}
+
+impl OutboundClient {
+    pub fn new(policy: OutboundPolicy) -> Result<Self, OutboundError> {
+        Self::build(policy, None)
+    }
+
+    /// Like [`OutboundClient::new`], but hosts (including redirect targets)
+    /// must also pass `filter`.
+    pub fn with_host_filter(
+        policy: OutboundPolicy,
+        filter: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Result<Self, OutboundError> {
+        Self::build(policy, Some(Arc::new(filter)))
+    }
+
+    fn build(
+        policy: OutboundPolicy,
+        host_filter: Option<HostFilter>,
+    ) -> Result<Self, OutboundError> {
+        let http = builder(policy.max_redirects, host_filter.clone())
+            .timeout(policy.timeout)
+            .user_agent(policy.user_agent.as_str())
+            .build()?;
+        Ok(Self {
+            http,
+            policy,
+            host_filter,
+        })
+    }
+
+    pub fn policy(&self) -> &OutboundPolicy {
+        &self.policy
+    }
+
+    /// GET `url` with `headers`. The body is not read yet; the returned
+    /// response carries the rest of the time budget for that.
+    pub async fn get(
+        &self,
+        url: &str,
+        headers: HeaderMap,
+    ) -> Result<OutboundResponse, OutboundError> {
+        let url = self.checked_url(url)?;
+        self.send(self.http.get(url).headers(headers)).await
+    }
+
+    /// POST `body` as JSON to `url`. Like [`OutboundClient::get`], the
+    /// response body is left unread.
+    pub async fn post_json(
+        &self,
+        url: &str,
+        body: &serde_json::Value,
+        headers: HeaderMap,
+    ) -> Result<OutboundResponse, OutboundError> {
+        let url = self.checked_url(url)?;
+        let request = self
+            .http
+            .post(url)
+            .headers(headers)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+        self.send(request).await
+    }
+
+    /// POST a raw `body` to `url` with `headers`, which should include the
+    /// content type. The response body is left unread.
+    pub async fn post(
+        &self,
+        url: &str,
+        body: Vec<u8>,
+        headers: HeaderMap,
+    ) -> Result<OutboundResponse, OutboundError> {
+        let url = self.checked_url(url)?;
+        self.send(self.http.post(url).headers(headers).body(body))
+            .await
+    }
+
+    fn checked_url(&self, url: &str) -> Result<Url, OutboundError> {
+        let url = Url::parse(url).map_err(|_| OutboundError::UnsupportedUrl)?;
+        check_url(&url, self.host_filter.as_deref())?;
+        Ok(url)
+    }
+
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<OutboundResponse, OutboundError> {
+        let deadline = Instant::now() + self.policy.timeout;
+        let response = tokio::time::timeout_at(deadline, request.send())
+            .await
+            .map_err(|_| OutboundError::Timeout)??;
+        Ok(OutboundResponse {
+            response,
+            deadline,
+            max_body_bytes: self.policy.max_body_bytes,
+        })
+    }
+}
+
+/// A response whose body has not been read yet.
+pub struct OutboundResponse {
+    response: reqwest::Response,
+    deadline: Instant,
+    max_body_bytes: usize,
+}
+
+impl OutboundResponse {
+    /// The URL the response came from, after redirects.
+    pub fn url(&self) -> &Url {
+        self.response.url()
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.response.status()
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        self.response.headers()
+    }
+
+    /// Read up to the policy's body limit. Returns the bytes and whether
+    /// the body was cut off; the rest of a larger body is never downloaded.
+    pub async fn body(mut self) -> Result<(Vec<u8>, bool), OutboundError> {
+        let max = self.max_body_bytes;
+        let read = async {
+            let mut body = Vec::new();
+            while let Some(chunk) = self.response.chunk().await? {
+                let take = chunk.len().min(max - body.len());
+                body.extend_from_slice(&chunk[..take]);
+                if take < chunk.len() {
+                    return Ok((body, true));
+                }
+                if body.len() == max {
+                    // Only cut off if there is actually more to come.
+                    let more = self.response.chunk().await?.is_some_and(|c| !c.is_empty());
+                    return Ok((body, more));
+                }
+            }
+            Ok((body, false))
+        };
+        tokio::time::timeout_at(self.deadline, read)
+            .await
+            .map_err(|_| OutboundError::Timeout)?
+    }
+
+    /// Read the whole body, failing if it is over the policy's limit.
+    pub async fn body_within_limit(self) -> Result<Vec<u8>, OutboundError> {
+        let max = self.max_body_bytes;
+        match self.body().await? {
+            (_, true) => Err(OutboundError::TooLarge(max)),
+            (body, false) => Ok(body),
+        }
+    }
+}
+
+/// A client builder with the address and redirect checks installed, for
+/// callers that manage their own `reqwest::Client` (federation). Initial
+/// URLs with IP-literal hosts still need [`is_fetchable_url`] before sending.
+pub fn guarded_client_builder() -> reqwest::ClientBuilder {
+    builder(DEFAULT_MAX_REDIRECTS, None)
+}
+
+/// Whether `url` is an http(s) URL whose host, if an IP literal, is public.
+/// Named hosts are checked when they are resolved.
+pub fn is_fetchable_url(url: &Url) -> bool {
+    check_url(url, None).is_ok()
+}
+
+fn builder(max_redirects: usize, host_filter: Option<HostFilter>) -> reqwest::ClientBuilder {
+    let redirect = reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() > max_redirects {
+            return attempt.error(OutboundError::TooManyRedirects);
+        }
+        match check_url(attempt.url(), host_filter.as_deref()) {
+            Ok(()) => attempt.follow(),
+            Err(err) => attempt.error(err),
+        }
+    });
+    reqwest::Client::builder()
+        .dns_resolver(Arc::new(PublicResolver))
+        .redirect(redirect)
+}
+
+fn check_url(url: &Url, host_filter: Option<&HostFilterFn>) -> Result<(), OutboundError> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(OutboundError::UnsupportedUrl);
+    }
+    let host = url.host_str().ok_or(OutboundError::UnsupportedUrl)?;
+    if host_filter.is_some_and(|allows| !allows(host)) {
+        return Err(OutboundError::HostNotAllowed);
+    }
+    let literal = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+    match literal.parse::<IpAddr>() {
+        Ok(ip) => check_public(ip),
+        // Names are checked when the client resolves them.
+        Err(_) => Ok(()),
+    }
+}
+
+fn check_public(ip: IpAddr) -> Result<(), OutboundError> {
+    if is_public_ip(ip) {
+        Ok(())
+    } else {
+        Err(OutboundError::PrivateAddress(ip))
+    }
+}
+
+/// Resolves names with the system resolver and fails unless every address
+/// is public, so the connection can only go to checked addresses.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(resolve_public(name.as_str().to_string()))
+    }
+}
+
+async fn resolve_public(host: String) -> Result<Addrs, Box<dyn std::error::Error + Send + Sync>> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+    if addrs.is_empty() {
+        return Err(OutboundError::Unresolved.into());
+    }
+    for addr in &addrs {
+        check_public(addr.ip())?;
+    }
+    Ok(Box::new(addrs.into_iter()))
+}
+
+/// Whether `ip` is a globally routable unicast address.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_public_ipv4(mapped);
+            }
+            let segments = ip.segments();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                // Unique local fc00::/7 and link-local fe80::/10.
+                || (segments[0] & 0xfe00) == 0xfc00
+                || (segments[0] & 0xffc0) == 0xfe80
+                // Site-local fec0::/10 (deprecated, still routed by some stacks).
+                || (segments[0] & 0xffc0) == 0xfec0
+                // Documentation 2001:db8::/32.
+                || (segments[0] == 0x2001 && segments[1] == 0x0db8)
+                // 6to4 2002::/16 and Teredo 2001::/32 tunnel to embedded
+                // IPv4 addresses, which may be private.
+                || segments[0] == 0x2002
+                || (segments[0] == 0x2001 && segments[1] == 0)
+                // IPv4-compatible ::/96 and NAT64 64:ff9b::/96, which can
+                // reach IPv4 addresses we'd otherwise refuse.
+                || segments[..6] == [0; 6]
+                || segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0])
+        }
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        // Shared address space (carrier-grade NAT) 100.64.0.0/10.
+        || (a == 100 && (b & 0xc0) == 64)
+        // IETF protocol assignments 192.0.0.0/24.
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking 198.18.0.0/15.
+        || (a == 198 && (b & 0xfe) == 18)
+        // Reserved 240.0.0.0/4.
+        || a >= 240)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn private_ipv4_ranges_are_rejected() {
+        for blocked in [
+            "0.0.0.0",
+            "0.1.2.3",
+            "10.1.2.3",
+            "100.64.0.1",
+            "100.127.255.254",
+            "127.0.0.1",
+            "127.255.255.255",
+            "169.254.169.254",
+            "172.16.0.1",
+            "172.31.255.255",
+            "192.0.0.8",
+            "192.0.2.1",
+            "192.168.1.1",
+            "198.18.0.1",
+            "198.51.100.7",
+            "203.0.113.9",
+            "224.0.0.1",
+            "240.0.0.1",
+            "255.255.255.255",
+        ] {
+            assert!(!is_public_ip(blocked.parse().unwrap()), "{blocked}");
+        }
+        for allowed in [
+            "1.1.1.1",
+            "8.8.8.8",
+            "93.184.216.34",
+            "100.128.0.1",
+            "172.32.0.1",
+        ] {
+            assert!(is_public_ip(allowed.parse().unwrap()), "{allowed}");
+        }
+    }
+
+    #[test]
+    fn private_ipv6_ranges_are_rejected() {
+        for blocked in [
+            "::",
+            "::1",
+            "fc00::1",
+            "fd12:3456::1",
+            "fe80::1",
+            "fec0::1",
+            "ff02::1",
+            "2001:db8::1",
+            "::ffff:127.0.0.1",
+            "::ffff:10.0.0.1",
+            "::ffff:169.254.169.254",
+            "::7f00:1",
+            "64:ff9b::a00:1",
+            "2002:7f00:1::1",
+            "2002:c0a8:101::",
+            "2001:0:4136:e378:8000:63bf:3fff:fdd2",
+        ] {
+            assert!(!is_public_ip(blocked.parse().unwrap()), "{blocked}");
+        }
+        for allowed in [
+            "2606:4700:4700::1111",
+            "2001:4860:4860::8888",
+            "::ffff:93.184.216.34",
+        ] {
+            assert!(is_public_ip(allowed.parse().unwrap()), "{allowed}");
+        }
+    }
+
+    #[test]
+    fn urls_are_checked_before_connecting() {
+        let check = |url: &str| is_fetchable_url(&Url::parse(url).unwrap());
+        assert!(check("https://example.com/page"));
+        assert!(check("http://93.184.216.34:8080/"));
+        assert!(check("https://[2606:4700:4700::1111]/"));
+        assert!(!check("http://127.0.0.1/"));
+        assert!(!check("http://[::1]:8080/"));
+        assert!(!check("http://[::ffff:a00:1]/"));
+        assert!(!check("http://169.254.169.254/latest/meta-data/"));
+        assert!(!check("ftp://example.com/"));
+        assert!(!check("file:///etc/passwd"));
+
+        let only_example: &HostFilterFn = &|host: &str| host == "example.com";
+        let url = Url::parse("https://other.org/").unwrap();
+        assert!(matches!(
+            check_url(&url, Some(only_example)),
+            Err(OutboundError::HostNotAllowed)
+        ));
+    }
+}
@@ -1,49 +1,47 @@
 use crate::error::CoreError;
 use crate::PermissionCacheKey;
+use paracord_db::guilds::SpaceVisibility;
 use paracord_db::DbPool;
 use paracord_models::permissions::Permissions;
 
 pub const OVERWRITE_TARGET_ROLE: i16 = 0;
 pub const OVERWRITE_TARGET_MEMBER: i16 = 1;
 
+type PermissionCache = moka::future::Cache<PermissionCacheKey, Permissions>;
+
 /// Compute channel permissions with cache lookup.  Falls back to
 /// `compute_channel_permissions` on cache miss and stores the result.
+///
+/// Callers that mutate roles, memberships or overwrites must invalidate the
+/// affected entries (see the `invalidate_*` helpers below); anything missed
+/// is bounded by [`crate::PERMISSION_CACHE_TTL_SECONDS`].
 pub async fn compute_channel_permissions_cached(
-    cache: &moka::future::Cache<PermissionCacheKey, Permissions>,
+    cache: &PermissionCache,
     pool: &DbPool,
     guild_id: i64,
     channel_id: i64,
     guild_owner_id: i64,
     user_id: i64,
 ) -> Result<Permissions, CoreError> {
-    let key = (user_id, channel_id);
+    let key = (guild_id, channel_id, user_id);
     if let Some(perms) = cache.get(&key).await {
+        crate::observability::permission_cache_hit();
         return Ok(perms);
     }
+    crate::observability::permission_cache_miss();
     let perms =
         compute_channel_permissions(pool, guild_id, channel_id, guild_owner_id, user_id).await?;
     cache.insert(key, perms).await;
     Ok(perms)
 }
 
-/// Invalidate cached permissions for a specific user in a specific channel.
-pub async fn invalidate_user_channel(
-    cache: &moka::future::Cache<PermissionCacheKey, Permissions>,
-    user_id: i64,
-    channel_id: i64,
-) {
-    cache.invalidate(&(user_id, channel_id)).await;
-}
-
-/// Invalidate all cached permissions for a specific channel (all users).
-/// Uses targeted invalidation to only remove entries for this channel.
-pub async fn invalidate_channel(
-    cache: &moka::future::Cache<PermissionCacheKey, Permissions>,
-    channel_id: i64,
+async fn invalidate_matching(
+    cache: &PermissionCache,
+    matches: impl Fn(&PermissionCacheKey) -> bool,
 ) {
     let keys_to_invalidate: Vec<PermissionCacheKey> = cache
         .iter()
-        .filter(|(k, _)| k.1 == channel_id)
+        .filter(|(k, _)| matches(k))
         .map(|(k, _)| *k)
         .collect();
     for key in keys_to_invalidate {
@@ -51,24 +49,48 @@ pub async fn invalidate_channel(
     }
 }
 
-/// Invalidate all cached permissions for a user across all channels.
-/// Used when a user's roles change.
-pub async fn invalidate_user(
-    cache: &moka::future::Cache<PermissionCacheKey, Permissions>,
-    user_id: i64,
-) {
-    let keys_to_invalidate: Vec<PermissionCacheKey> = cache
-        .iter()
-        .filter(|(k, _)| k.0 == user_id)
-        .map(|(k, _)| *k)
-        .collect();
-    for key in keys_to_invalidate {
-        cache.invalidate(&key).await;
-    }
+/// Invalidate cached permissions for a specific user in a specific channel.
+pub async fn invalidate_user_channel(cache: &PermissionCache, user_id: i64, channel_id: i64) {
+    invalidate_matching(cache, |k| k.1 == channel_id && k.2 == user_id).await;
+}
+
+/// Invalidate all cached permissions for a specific channel (all users).
+/// Used when the channel's overwrites or required roles change.
+pub async fn invalidate_channel(cache: &PermissionCache, channel_id: i64) {
+    invalidate_matching(cache, |k| k.1 == channel_id).await;
+}
+
+/// Invalidate a channel and the threads under it, which inherit its
+/// overwrites and required roles.
+pub async fn invalidate_channel_and_threads(
+    cache: &PermissionCache,
+    pool: &DbPool,
+    channel_id: i64,
+) -> Result<(), CoreError> {
+    let thread_ids = paracord_db::channels::get_thread_ids(pool, channel_id).await?;
+    invalidate_matching(cache, |k| k.1 == channel_id || thread_ids.contains(&k.1)).await;
+    Ok(())
+}
+
+/// Invalidate a member's cached permissions across a guild's channels.
+/// Used when the member joins, leaves or has their roles changed.
+pub async fn invalidate_member(cache: &PermissionCache, guild_id: i64, user_id: i64) {
+    invalidate_matching(cache, |k| k.0 == guild_id && k.2 == user_id).await;
 }
 
-/// Invalidate the entire permission cache (e.g. when roles are modified).
-pub async fn invalidate_all(cache: &moka::future::Cache<PermissionCacheKey, Permissions>) {
+/// Invalidate all cached permissions for a user across all guilds.
+pub async fn invalidate_user(cache: &PermissionCache, user_id: i64) {
+    invalidate_matching(cache, |k| k.2 == user_id).await;
+}
+
+/// Invalidate every cached permission in a guild. Used when a role is
+/// created, edited or deleted, or the guild changes owner.
+pub async fn invalidate_guild(cache: &PermissionCache, guild_id: i64) {
+    invalidate_matching(cache, |k| k.0 == guild_id).await;
+}
+
+/// Invalidate the entire permission cache.
+pub async fn invalidate_all(cache: &PermissionCache) {
     cache.invalidate_all();
 }
 
@@ -128,6 +150,74 @@ pub fn compute_permissions_from_roles(
     perms
 }
 
+/// Position of the highest role in `roles`; 0 (the default member role's
+/// position) when there are none.
+pub fn highest_role_position(roles: &[paracord_db::roles::RoleRow]) -> i32 {
+    roles.iter().map(|r| r.position).max().unwrap_or(0)
+}
+
+/// The role a member is grouped under in the member list: their
+/// highest-position hoisted role, if any.
+pub fn displayed_role(
+    roles: &[paracord_db::roles::RoleRow],
+) -> Option<&paracord_db::roles::RoleRow> {
+    roles
+        .iter()
+        .filter(|r| r.hoist)
+        .max_by_key(|r| (r.position, std::cmp::Reverse(r.id)))
+}
+
+/// Require the actor's highest role to sit strictly above `role`. The guild
+/// owner outranks every role.
+pub fn ensure_outranks_role(
+    actor_roles: &[paracord_db::roles::RoleRow],
+    guild_owner_id: i64,
+    actor_id: i64,
+    role: &paracord_db::roles::RoleRow,
+    action: &str,
+) -> Result<(), CoreError> {
+    if actor_id == guild_owner_id || role.position < highest_role_position(actor_roles) {
+        return Ok(());
+    }
+    Err(CoreError::RoleHierarchy(format!(
+        "Cannot {action} the role '{}': it is not below your highest role",
+        role.name
+    )))
+}
+
+/// Require the actor to outrank `target_id`: the target's highest role must
+/// sit strictly below the actor's. The guild owner outranks everyone and
+/// can never be acted on; users who are not members have no roles to
+/// compare and are always outranked.
+pub async fn ensure_outranks_member(
+    pool: &DbPool,
+    guild_id: i64,
+    guild_owner_id: i64,
+    actor_id: i64,
+    target_id: i64,
+    action: &str,
+) -> Result<(), CoreError> {
+    if actor_id == guild_owner_id {
+        return Ok(());
+    }
+    if target_id == guild_owner_id {
+        return Err(CoreError::RoleHierarchy(format!(
+            "Cannot {action} the space owner"
+        )));
+    }
+    if !is_guild_member(pool, guild_id, target_id).await? {
+        return Ok(());
+    }
+    let actor_roles = paracord_db::roles::get_member_roles(pool, actor_id, guild_id).await?;
+    let target_roles = paracord_db::roles::get_member_roles(pool, target_id, guild_id).await?;
+    if highest_role_position(&target_roles) >= highest_role_position(&actor_roles) {
+        return Err(CoreError::RoleHierarchy(format!(
+            "Cannot {action} a member whose highest role is not below your own"
+        )));
+    }
+    Ok(())
+}
+
 pub async fn is_guild_member(
     pool: &DbPool,
     guild_id: i64,
@@ -137,23 +227,88 @@ pub async fn is_guild_member(
     Ok(member.is_some())
 }
 
+/// Require `user_id` to be a member who may currently see the space. Spaces
+/// the user cannot see (non-public ones they aren't in, or role-gated ones
+/// whose allowed roles they lack) report `NotFound` so their existence does
+/// not leak.
 pub async fn ensure_guild_member(
     pool: &DbPool,
     guild_id: i64,
     user_id: i64,
 ) -> Result<(), CoreError> {
+    let space = paracord_db::guilds::get_guild(pool, guild_id)
+        .await?
+        .ok_or(CoreError::NotFound)?;
     if !is_guild_member(pool, guild_id, user_id).await? {
-        return Err(CoreError::Forbidden);
+        return Err(match space.visibility() {
+            SpaceVisibility::Public => CoreError::Forbidden,
+            SpaceVisibility::Private | SpaceVisibility::Roles => CoreError::NotFound,
+        });
+    }
+    if !paracord_db::guilds::can_access_space(pool, &space, user_id).await? {
+        return Err(CoreError::NotFound);
     }
     Ok(())
 }
 
+/// The most a bot user may do in a guild, whatever its roles grant: the
+/// application's permissions intersected with what the bot was granted when
+/// it was installed there. `None` for regular users.
+pub async fn bot_permission_scope(
+    pool: &DbPool,
+    guild_id: i64,
+    user_id: i64,
+) -> Result<Option<Permissions>, CoreError> {
+    let scope =
+        paracord_db::bot_applications::get_bot_permission_scope(pool, user_id, guild_id).await?;
+    Ok(scope.map(Permissions::from_bits_truncate))
+}
+
+/// Limit `perms` to the user's bot scope, if they are a bot.
+pub async fn restrict_to_bot_scope(
+    pool: &DbPool,
+    guild_id: i64,
+    user_id: i64,
+    perms: Permissions,
+) -> Result<Permissions, CoreError> {
+    Ok(match bot_permission_scope(pool, guild_id, user_id).await? {
+        Some(scope) => perms & scope,
+        None => perms,
+    })
+}
+
+/// Guild-level permissions for a member: the union of their roles (all
+/// permissions for the owner and administrators), limited to the bot scope
+/// for bot users.
+pub async fn compute_guild_permissions(
+    pool: &DbPool,
+    guild_id: i64,
+    guild_owner_id: i64,
+    user_id: i64,
+) -> Result<Permissions, CoreError> {
+    let roles = paracord_db::roles::get_member_roles(pool, user_id, guild_id).await?;
+    let perms = compute_permissions_from_roles(&roles, guild_owner_id, user_id);
+    restrict_to_bot_scope(pool, guild_id, user_id, perms).await
+}
+
 pub async fn compute_channel_permissions(
     pool: &DbPool,
     guild_id: i64,
     channel_id: i64,
     guild_owner_id: i64,
     user_id: i64,
+) -> Result<Permissions, CoreError> {
+    let perms =
+        channel_permissions_from_roles(pool, guild_id, channel_id, guild_owner_id, user_id).await?;
+    restrict_to_bot_scope(pool, guild_id, user_id, perms).await
+}
+
+async fn channel_permissions_from_roles(
+    pool: &DbPool,
+    guild_id: i64,
+    channel_id: i64,
+    guild_owner_id: i64,
+    user_id: i64,
 ) -> Result<Permissions, CoreError> {
     let roles = paracord_db::roles::get_member_roles(pool, user_id, guild_id).await?;
     let mut perms = compute_permissions_from_roles(&roles, guild_owner_id, user_id);
@@ -164,21 +319,55 @@ pub async fn compute_channel_permissions(
     let channel = paracord_db::channels::get_channel(pool, channel_id)
         .await?
         .ok_or(CoreError::NotFound)?;
+    let parent = match thread_parent_id(&channel) {
+        Some(parent_id) => paracord_db::channels::get_channel(pool, parent_id).await?,
+        None => None,
+    };
 
     let role_ids: std::collections::HashSet<i64> = roles.iter().map(|r| r.id).collect();
-    let required_role_ids =
-        paracord_db::channels::parse_required_role_ids(&channel.required_role_ids);
-    if !required_role_ids.is_empty() && !required_role_ids.iter().any(|id| role_ids.contains(id)) {
-        perms.remove(Permissions::VIEW_CHANNEL);
-        return Ok(perms);
+    // A thread starts from its parent channel's permissions; overwrites set on
+    // the thread itself apply on top.
+    for scope in parent.iter().chain(std::iter::once(&channel)) {
+        if is_role_gated(scope, &role_ids) {
+            perms.remove(Permissions::VIEW_CHANNEL);
+            return Ok(perms);
+        }
+        let overwrites =
+            paracord_db::channel_overwrites::get_channel_overwrites(pool, scope.id).await?;
+        perms = apply_overwrites(perms, &overwrites, guild_id, &role_ids, user_id);
     }
 
-    let overwrites =
-        paracord_db::channel_overwrites::get_channel_overwrites(pool, channel_id).await?;
-    if overwrites.is_empty() {
-        return Ok(perms);
+    Ok(perms)
+}
+
+/// The channel a thread inherits permissions from, if `channel` is a thread.
+fn thread_parent_id(channel: &paracord_db::channels::ChannelRow) -> Option<i64> {
+    if channel.channel_type == i16::from(paracord_models::channel::ChannelType::Thread) {
+        channel.parent_id
+    } else {
+        None
     }
+}
+
+/// Whether `channel` is restricted to roles the member holds none of.
+fn is_role_gated(
+    channel: &paracord_db::channels::ChannelRow,
+    role_ids: &std::collections::HashSet<i64>,
+) -> bool {
+    let required_role_ids =
+        paracord_db::channels::parse_required_role_ids(&channel.required_role_ids);
+    !required_role_ids.is_empty() && !required_role_ids.iter().any(|id| role_ids.contains(id))
+}
 
+/// Apply one channel's overwrites: @everyone first, then the union of the
+/// member's role overwrites, then the member's own overwrite.
+fn apply_overwrites(
+    mut perms: Permissions,
+    overwrites: &[paracord_db::channel_overwrites::ChannelOverwriteRow],
+    guild_id: i64,
+    role_ids: &std::collections::HashSet<i64>,
+    user_id: i64,
+) -> Permissions {
     if let Some(everyone) = overwrites
         .iter()
         .find(|o| o.target_type == OVERWRITE_TARGET_ROLE && o.target_id == guild_id)
@@ -211,7 +400,112 @@ pub async fn compute_channel_permissions(
         perms |= allow;
     }
 
-    Ok(perms)
+    perms
+}
+
+/// A member's standing in one guild, loaded once and applied to any number
+/// of that guild's channels in memory.
+struct MemberScope {
+    guild_id: i64,
+    guild_owner_id: i64,
+    user_id: i64,
+    base_perms: Permissions,
+    role_ids: std::collections::HashSet<i64>,
+    bot_scope: Permissions,
+}
+
+impl MemberScope {
+    fn new(
+        guild_id: i64,
+        guild_owner_id: i64,
+        user_id: i64,
+        roles: &[paracord_db::roles::RoleRow],
+        bot_scope: Option<Permissions>,
+    ) -> Self {
+        Self {
+            guild_id,
+            guild_owner_id,
+            user_id,
+            base_perms: compute_permissions_from_roles(roles, guild_owner_id, user_id),
+            role_ids: roles.iter().map(|r| r.id).collect(),
+            bot_scope: bot_scope.unwrap_or(Permissions::all()),
+        }
+    }
+
+    fn is_owner(&self) -> bool {
+        self.user_id == self.guild_owner_id
+    }
+
+    /// Whether channel overwrites can change anything for this member.
+    fn bypasses_overwrites(&self) -> bool {
+        self.is_owner() || self.base_perms.contains(Permissions::ADMINISTRATOR)
+    }
+
+    /// Permissions in each of `channels`. Thread parents are looked up in
+    /// `channels` and then `parents`; `overwrites_by_channel` must hold the
+    /// overwrites of both.
+    fn channel_permissions(
+        &self,
+        channels: &[paracord_db::channels::ChannelRow],
+        parents: &[paracord_db::channels::ChannelRow],
+        overwrites_by_channel: &std::collections::HashMap<
+            i64,
+            Vec<paracord_db::channel_overwrites::ChannelOverwriteRow>,
+        >,
+    ) -> std::collections::HashMap<i64, Permissions> {
+        if self.is_owner() {
+            return channels
+                .iter()
+                .map(|c| (c.id, Permissions::all()))
+                .collect();
+        }
+        if self.base_perms.contains(Permissions::ADMINISTRATOR) {
+            return channels.iter().map(|c| (c.id, self.bot_scope)).collect();
+        }
+
+        let by_id: std::collections::HashMap<i64, &paracord_db::channels::ChannelRow> = channels
+            .iter()
+            .chain(parents.iter())
+            .map(|c| (c.id, c))
+            .collect();
+        let parent_of = |channel: &paracord_db::channels::ChannelRow| {
+            by_id.get(&thread_parent_id(channel)?).copied()
+        };
+        let mut result = std::collections::HashMap::with_capacity(channels.len());
+        for channel in channels {
+            let mut perms = self.base_perms;
+            for scope in parent_of(channel)
+                .into_iter()
+                .chain(std::iter::once(channel))
+            {
+                if is_role_gated(scope, &self.role_ids) {
+                    perms.remove(Permissions::VIEW_CHANNEL);
+                    break;
+                }
+                if let Some(overwrites) = overwrites_by_channel.get(&scope.id) {
+                    perms = apply_overwrites(
+                        perms,
+                        overwrites,
+                        self.guild_id,
+                        &self.role_ids,
+                        self.user_id,
+                    );
+                }
+            }
+            result.insert(channel.id, perms & self.bot_scope);
+        }
+        result
+    }
+}
+
+fn group_overwrites_by_channel(
+    overwrites: Vec<paracord_db::channel_overwrites::ChannelOverwriteRow>,
+) -> std::collections::HashMap<i64, Vec<paracord_db::channel_overwrites::ChannelOverwriteRow>> {
+    let mut by_channel: std::collections::HashMap<i64, Vec<_>> = std::collections::HashMap::new();
+    for ow in overwrites {
+        by_channel.entry(ow.channel_id).or_default().push(ow);
+    }
+    by_channel
 }
 
 /// Compute channel permissions for multiple channels in a single batch.
@@ -223,103 +517,168 @@ pub async fn compute_all_channel_permissions(
     guild_owner_id: i64,
     user_id: i64,
 ) -> Result<std::collections::HashMap<i64, Permissions>, CoreError> {
-    use std::collections::HashMap;
+    let no_overwrites = std::collections::HashMap::new();
 
     // Owner fast path
     if user_id == guild_owner_id {
-        return Ok(channels
-            .iter()
-            .map(|c| (c.id, Permissions::all()))
-            .collect());
+        let member = MemberScope::new(guild_id, guild_owner_id, user_id, &[], None);
+        return Ok(member.channel_permissions(channels, &[], &no_overwrites));
     }
 
     // Load roles once
     let roles = paracord_db::roles::get_member_roles(pool, user_id, guild_id).await?;
-    let base_perms = compute_permissions_from_roles(&roles, guild_owner_id, user_id);
-    if base_perms.contains(Permissions::ADMINISTRATOR) {
-        return Ok(channels
-            .iter()
-            .map(|c| (c.id, Permissions::all()))
-            .collect());
+    let bot_scope = bot_permission_scope(pool, guild_id, user_id).await?;
+    let member = MemberScope::new(guild_id, guild_owner_id, user_id, &roles, bot_scope);
+    if member.bypasses_overwrites() {
+        return Ok(member.channel_permissions(channels, &[], &no_overwrites));
     }
 
-    let role_ids: std::collections::HashSet<i64> = roles.iter().map(|r| r.id).collect();
+    // Threads inherit from their parent, which may not be in `channels`.
+    let mut known_ids: std::collections::HashSet<i64> = channels.iter().map(|c| c.id).collect();
+    let mut missing_parents: Vec<paracord_db::channels::ChannelRow> = Vec::new();
+    for parent_id in channels.iter().filter_map(thread_parent_id) {
+        if !known_ids.insert(parent_id) {
+            continue;
+        }
+        if let Some(parent) = paracord_db::channels::get_channel(pool, parent_id).await? {
+            missing_parents.push(parent);
+        }
+    }
 
     // Load all overwrites for all channels in one query
-    let channel_ids: Vec<i64> = channels.iter().map(|c| c.id).collect();
-    let all_overwrites =
-        paracord_db::channel_overwrites::get_overwrites_for_channels(pool, &channel_ids).await?;
-
-    // Group overwrites by channel_id
-    let mut overwrites_by_channel: HashMap<
-        i64,
-        Vec<paracord_db::channel_overwrites::ChannelOverwriteRow>,
-    > = HashMap::new();
-    for ow in all_overwrites {
-        overwrites_by_channel
-            .entry(ow.channel_id)
-            .or_default()
-            .push(ow);
-    }
-
-    // Compute permissions per channel
-    let mut result = HashMap::with_capacity(channels.len());
-    for channel in channels {
-        let mut perms = base_perms;
-
-        // Check required_role_ids
-        let required_role_ids =
-            paracord_db::channels::parse_required_role_ids(&channel.required_role_ids);
-        if !required_role_ids.is_empty()
-            && !required_role_ids.iter().any(|id| role_ids.contains(id))
-        {
-            perms.remove(Permissions::VIEW_CHANNEL);
-            result.insert(channel.id, perms);
-            continue;
+    let channel_ids: Vec<i64> = channels
+        .iter()
+        .chain(missing_parents.iter())
+        .map(|c| c.id)
+        .collect();
+    let overwrites_by_channel = group_overwrites_by_channel(
+        paracord_db::channel_overwrites::get_overwrites_for_channels(pool, &channel_ids).await?,
+    );
+
+    Ok(member.channel_permissions(channels, &missing_parents, &overwrites_by_channel))
+}
+
+/// Keep only the channels `user_id` may see, computing every channel's
+/// permissions from one role fetch and one batched overwrite fetch.
+///
+/// A channel needs VIEW_CHANNEL to be listed. Categories also need at least
+/// one visible child when they have any children, so a category whose
+/// contents are all hidden is not listed as an empty shell.
+pub async fn filter_visible_channels(
+    pool: &DbPool,
+    guild_id: i64,
+    channels: Vec<paracord_db::channels::ChannelRow>,
+    guild_owner_id: i64,
+    user_id: i64,
+) -> Result<Vec<paracord_db::channels::ChannelRow>, CoreError> {
+    let perms =
+        compute_all_channel_permissions(pool, guild_id, &channels, guild_owner_id, user_id).await?;
+    Ok(retain_visible_channels(channels, &perms))
+}
+
+/// The visible channels of each guild in `guilds` (`(guild_id, owner_id)`
+/// pairs), threads excluded, keyed by guild id. Channels, roles, overwrites
+/// and bot scopes are each loaded with one query across every guild, so the
+/// number of queries does not grow with the number of guilds. Visibility
+/// follows [`filter_visible_channels`].
+pub async fn visible_channels_in_guilds(
+    pool: &DbPool,
+    guilds: &[(i64, i64)],
+    user_id: i64,
+) -> Result<std::collections::HashMap<i64, Vec<paracord_db::channels::ChannelRow>>, CoreError> {
+    use std::collections::HashMap;
+
+    let guild_ids: Vec<i64> = guilds.iter().map(|(guild_id, _)| *guild_id).collect();
+    let mut channels_by_guild: HashMap<i64, Vec<paracord_db::channels::ChannelRow>> =
+        HashMap::new();
+    for channel in
+        paracord_db::channels::get_non_thread_channels_for_spaces(pool, &guild_ids).await?
+    {
+        if let Some(guild_id) = channel.guild_id() {
+            channels_by_guild.entry(guild_id).or_default().push(channel);
         }
+    }
+    let mut roles_by_guild: HashMap<i64, Vec<paracord_db::roles::RoleRow>> = HashMap::new();
+    for role in paracord_db::roles::get_member_roles_for_spaces(pool, user_id, &guild_ids).await? {
+        roles_by_guild.entry(role.space_id).or_default().push(role);
+    }
+    let bot_scopes =
+        paracord_db::bot_applications::get_bot_permission_scopes(pool, user_id, &guild_ids).await?;
 
-        // Apply overwrites
-        let overwrites = overwrites_by_channel.get(&channel.id);
-        if let Some(overwrites) = overwrites {
-            // @everyone role overwrite
-            if let Some(everyone) = overwrites
-                .iter()
-                .find(|o| o.target_type == OVERWRITE_TARGET_ROLE && o.target_id == guild_id)
-            {
-                let deny = Permissions::from_bits_truncate(everyone.deny_perms);
-                let allow = Permissions::from_bits_truncate(everyone.allow_perms);
-                perms &= !deny;
-                perms |= allow;
-            }
+    let members: Vec<MemberScope> = guilds
+        .iter()
+        .map(|(guild_id, owner_id)| {
+            let roles = roles_by_guild
+                .get(guild_id)
+                .map(Vec::as_slice)
+                .unwrap_or(&[]);
+            let bot_scope = bot_scopes.as_ref().map(|scopes| {
+                Permissions::from_bits_truncate(scopes.get(guild_id).copied().unwrap_or(0))
+            });
+            MemberScope::new(*guild_id, *owner_id, user_id, roles, bot_scope)
+        })
+        .collect();
 
-            // Role overwrites
-            let mut role_deny = Permissions::empty();
-            let mut role_allow = Permissions::empty();
-            for overwrite in overwrites.iter().filter(|o| {
-                o.target_type == OVERWRITE_TARGET_ROLE && role_ids.contains(&o.target_id)
-            }) {
-                role_deny |= Permissions::from_bits_truncate(overwrite.deny_perms);
-                role_allow |= Permissions::from_bits_truncate(overwrite.allow_perms);
-            }
-            perms &= !role_deny;
-            perms |= role_allow;
+    // Only guilds where overwrites can matter need them loaded.
+    let overwritten_channel_ids: Vec<i64> = members
+        .iter()
+        .filter(|member| !member.bypasses_overwrites())
+        .filter_map(|member| channels_by_guild.get(&member.guild_id))
+        .flatten()
+        .map(|c| c.id)
+        .collect();
+    let overwrites_by_channel = group_overwrites_by_channel(
+        paracord_db::channel_overwrites::get_overwrites_for_channels(
+            pool,
+            &overwritten_channel_ids,
+        )
+        .await?,
+    );
+
+    let mut visible = HashMap::with_capacity(members.len());
+    for member in &members {
+        let channels = channels_by_guild
+            .remove(&member.guild_id)
+            .unwrap_or_default();
+        let perms = member.channel_permissions(&channels, &[], &overwrites_by_channel);
+        visible.insert(member.guild_id, retain_visible_channels(channels, &perms));
+    }
+    Ok(visible)
+}
 
-            // Member overwrite
-            if let Some(member_ow) = overwrites
-                .iter()
-                .find(|o| o.target_type == OVERWRITE_TARGET_MEMBER && o.target_id == user_id)
-            {
-                let deny = Permissions::from_bits_truncate(member_ow.deny_perms);
-                let allow = Permissions::from_bits_truncate(member_ow.allow_perms);
-                perms &= !deny;
-                perms |= allow;
+fn retain_visible_channels(
+    channels: Vec<paracord_db::channels::ChannelRow>,
+    perms: &std::collections::HashMap<i64, Permissions>,
+) -> Vec<paracord_db::channels::ChannelRow> {
+    let can_view = |channel_id: i64| {
+        perms
+            .get(&channel_id)
+            .is_some_and(|p| p.contains(Permissions::VIEW_CHANNEL))
+    };
+
+    let category_type = i16::from(paracord_models::channel::ChannelType::Category);
+    let mut categories_with_children = std::collections::HashSet::new();
+    let mut categories_with_visible_children = std::collections::HashSet::new();
+    for channel in &channels {
+        if let Some(parent_id) = channel.parent_id {
+            categories_with_children.insert(parent_id);
+            if can_view(channel.id) {
+                categories_with_visible_children.insert(parent_id);
             }
         }
-
-        result.insert(channel.id, perms);
     }
 
-    Ok(result)
+    channels
+        .into_iter()
+        .filter(|channel| {
+            if !can_view(channel.id) {
+                return false;
+            }
+            channel.channel_type != category_type
+                || !categories_with_children.contains(&channel.id)
+                || categories_with_visible_children.contains(&channel.id)
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -329,17 +688,22 @@ mod tests {
     use paracord_db::roles::RoleRow;
 
     fn make_role(id: i64, space_id: i64, permissions: i64) -> RoleRow {
+        make_positioned_role(id, space_id, permissions, 0)
+    }
+
+    fn make_positioned_role(id: i64, space_id: i64, permissions: i64, position: i32) -> RoleRow {
         RoleRow {
             id,
             space_id,
             name: format!("role-{}", id),
             color: 0,
             hoist: false,
-            position: 0,
+            position,
             permissions,
             managed: false,
             mentionable: false,
             server_wide: false,
+            icon_hash: None,
             created_at: Utc::now(),
         }
     }
@@ -436,4 +800,56 @@ mod tests {
         let perms = compute_permissions_from_roles(&roles, 99, 1);
         assert_eq!(perms, Permissions::empty());
     }
+
+    #[test]
+    fn role_hierarchy_requires_a_strictly_higher_role() {
+        let actor_roles = vec![make_positioned_role(1, 100, 0, 3), make_role(100, 100, 0)];
+        assert_eq!(highest_role_position(&actor_roles), 3);
+        assert_eq!(highest_role_position(&[]), 0);
+
+        let below = make_positioned_role(2, 100, 0, 2);
+        let equal = make_positioned_role(3, 100, 0, 3);
+        assert!(ensure_outranks_role(&actor_roles, 99, 1, &below, "edit").is_ok());
+        let err = ensure_outranks_role(&actor_roles, 99, 1, &equal, "edit").unwrap_err();
+        assert!(matches!(err, CoreError::RoleHierarchy(_)));
+        // The owner bypasses the hierarchy.
+        assert!(ensure_outranks_role(&[], 1, 1, &equal, "edit").is_ok());
+    }
+
+    #[test]
+    fn displayed_role_is_the_highest_hoisted_role() {
+        let hoisted = |id, position| RoleRow {
+            hoist: true,
+            ..make_positioned_role(id, 100, 0, position)
+        };
+        let roles = vec![
+            hoisted(1, 2),
+            make_positioned_role(2, 100, 0, 5),
+            hoisted(3, 4),
+            make_role(100, 100, 0),
+        ];
+        assert_eq!(displayed_role(&roles).map(|r| r.id), Some(3));
+        assert!(displayed_role(&roles[1..2]).is_none());
+        assert!(displayed_role(&[]).is_none());
+    }
+
+    #[tokio::test]
+    async fn permission_cache_invalidation_is_scoped() {
+        let cache = crate::build_permission_cache();
+        for key in [(1, 10, 100), (1, 11, 100), (1, 10, 101), (2, 20, 100)] {
+            cache.insert(key, Permissions::VIEW_CHANNEL).await;
+        }
+
+        invalidate_member(&cache, 1, 100).await;
+        assert!(cache.get(&(1, 10, 100)).await.is_none());
+        assert!(cache.get(&(1, 11, 100)).await.is_none());
+        assert!(cache.get(&(1, 10, 101)).await.is_some());
+        assert!(cache.get(&(2, 20, 100)).await.is_some());
+
+        invalidate_channel(&cache, 10).await;
+        assert!(cache.get(&(1, 10, 101)).await.is_none());
+
+        invalidate_guild(&cache, 2).await;
+        assert!(cache.get(&(2, 20, 100)).await.is_none());
+    }
 }
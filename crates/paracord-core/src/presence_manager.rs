@@ -1,7 +1,7 @@
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
 use std::future::Future;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::task::JoinHandle;
 
 /// Manages deferred offline presence transitions to avoid race conditions
@@ -10,9 +10,16 @@ use tokio::task::JoinHandle;
 /// When a user disconnects, instead of immediately marking them offline,
 /// the handler schedules a delayed check via this manager. If the user
 /// reconnects within the grace period, the pending offline task is cancelled.
+///
+/// It also tracks when each connected user last did something on the
+/// gateway, so users who stay quiet past the idle timeout can be moved from
+/// online to idle and back again once they return.
 pub struct PresenceManager {
     pending_offlines: Arc<DashMap<i64, JoinHandle<()>>>,
     grace_period: Duration,
+    last_activity: DashMap<i64, Instant>,
+    auto_idle: DashSet<i64>,
+    idle_timeout: Option<Duration>,
 }
 
 impl PresenceManager {
@@ -20,9 +27,56 @@ impl PresenceManager {
         Self {
             pending_offlines: Arc::new(DashMap::new()),
             grace_period: Duration::from_millis(1500),
+            last_activity: DashMap::new(),
+            auto_idle: DashSet::new(),
+            idle_timeout: None,
         }
     }
 
+    /// Move users to idle after `timeout` without gateway activity. `None`
+    /// disables automatic idling.
+    pub fn with_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_timeout = timeout.filter(|t| !t.is_zero());
+        self
+    }
+
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+
+    /// Note activity from `user_id`. Returns `true` when the user had been
+    /// idled automatically and should be restored to online.
+    pub fn record_activity(&self, user_id: i64) -> bool {
+        self.last_activity.insert(user_id, Instant::now());
+        self.auto_idle.remove(&user_id).is_some()
+    }
+
+    /// Users quiet for longer than the idle timeout who have not already
+    /// been idled automatically.
+    pub fn idle_candidates(&self) -> Vec<i64> {
+        let Some(timeout) = self.idle_timeout else {
+            return Vec::new();
+        };
+        let now = Instant::now();
+        self.last_activity
+            .iter()
+            .filter(|entry| now.duration_since(*entry.value()) >= timeout)
+            .map(|entry| *entry.key())
+            .filter(|user_id| !self.auto_idle.contains(user_id))
+            .collect()
+    }
+
+    /// Remember that `user_id` was idled by the server rather than by choice.
+    pub fn mark_auto_idle(&self, user_id: i64) {
+        self.auto_idle.insert(user_id);
+    }
+
+    /// Drop activity tracking once the user's last session is gone.
+    pub fn clear_activity(&self, user_id: i64) {
+        self.last_activity.remove(&user_id);
+        self.auto_idle.remove(&user_id);
+    }
+
     /// Schedule a deferred offline check for `user_id`.
     ///
     /// Any previously pending offline task for the same user is cancelled first.
@@ -56,3 +110,30 @@ impl Default for PresenceManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idle_candidates_respect_timeout_and_auto_idle_flag() {
+        let manager = PresenceManager::new().with_idle_timeout(Some(Duration::ZERO));
+        assert!(manager.idle_timeout().is_none());
+        manager.record_activity(1);
+        assert!(manager.idle_candidates().is_empty());
+
+        let manager = PresenceManager::new().with_idle_timeout(Some(Duration::from_millis(1)));
+        assert!(!manager.record_activity(1));
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(manager.idle_candidates(), vec![1]);
+
+        manager.mark_auto_idle(1);
+        assert!(manager.idle_candidates().is_empty());
+        assert!(manager.record_activity(1));
+        assert!(!manager.record_activity(1));
+
+        manager.clear_activity(1);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(manager.idle_candidates().is_empty());
+    }
+}
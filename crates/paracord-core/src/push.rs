@@ -0,0 +1,383 @@
+//! Web Push delivery for mentions and DMs.
+//!
+//! Message handlers only enqueue a [`PushJob`]; a single background worker
+//! drains the queue in batches, works out who should be notified, and sends
+//! the encrypted pushes with a fixed cap on in-flight requests. A burst of
+//! messages therefore never fans out into an unbounded number of tasks.
+
+use std::time::Duration;
+
+use paracord_db::push_subscriptions::PushSubscriptionRow;
+use paracord_db::DbPool;
+use paracord_models::permissions::Permissions;
+use paracord_util::web_push::VapidKey;
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_ENCODING, CONTENT_TYPE};
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+use crate::error::CoreError;
+use crate::outbound::{OutboundClient, OutboundPolicy};
+use crate::permissions;
+
+/// Messages waiting for push fan-out before new ones are dropped.
+const QUEUE_CAPACITY: usize = 1024;
+/// Jobs taken from the queue per worker iteration.
+const BATCH_SIZE: usize = 64;
+/// Push requests in flight at once.
+const MAX_CONCURRENT_SENDS: usize = 16;
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long push services should hold a notification for an offline device.
+const PUSH_TTL_SECONDS: u32 = 24 * 60 * 60;
+/// Characters of message content included in a guild push.
+const PREVIEW_CHARS: usize = 140;
+
+/// A newly created message that may warrant push notifications.
+#[derive(Debug, Clone, Copy)]
+pub struct PushJob {
+    pub message_id: i64,
+    pub channel_id: i64,
+    pub guild_id: Option<i64>,
+    pub author_id: i64,
+}
+
+/// Handle for queueing push notifications. Cheap to clone.
+#[derive(Clone)]
+pub struct PushDispatcher {
+    queue: Option<mpsc::Sender<PushJob>>,
+    public_key: Option<String>,
+}
+
+impl PushDispatcher {
+    /// A dispatcher that accepts jobs and discards them.
+    pub fn disabled() -> Self {
+        Self {
+            queue: None,
+            public_key: None,
+        }
+    }
+
+    /// Spawn the delivery worker. `subject` is the VAPID contact (a
+    /// `mailto:` or `https:` URL) push services use to reach the operator.
+    /// Push endpoints are user-supplied, so deliveries go through the
+    /// guarded [`OutboundClient`].
+    pub fn start(pool: DbPool, vapid: VapidKey, subject: String) -> Result<Self, CoreError> {
+        let policy = OutboundPolicy {
+            timeout: SEND_TIMEOUT,
+            max_body_bytes: 16 * 1024,
+            max_redirects: 0,
+            user_agent: "Paracord-Push".to_string(),
+        };
+        let client = OutboundClient::new(policy)
+            .map_err(|e| CoreError::Internal(format!("failed to build push client: {e}")))?;
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        let public_key = vapid.public_key().to_string();
+        tokio::spawn(run_worker(pool, client, vapid, subject, rx));
+        Ok(Self {
+            queue: Some(tx),
+            public_key: Some(public_key),
+        })
+    }
+
+    /// VAPID public key clients subscribe with, or `None` when disabled.
+    pub fn vapid_public_key(&self) -> Option<&str> {
+        self.public_key.as_deref()
+    }
+
+    /// Queue push fan-out for a new message. Never blocks; when the queue is
+    /// full the notification is skipped.
+    pub fn message_created(&self, job: PushJob) {
+        let Some(queue) = &self.queue else {
+            return;
+        };
+        if let Err(mpsc::error::TrySendError::Full(job)) = queue.try_send(job) {
+            tracing::warn!(
+                message_id = job.message_id,
+                "Push queue full; skipping notifications"
+            );
+        }
+    }
+}
+
+/// A recipient who should get a push for a message, and why.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PushRecipient {
+    pub user_id: i64,
+    pub mentions_user: bool,
+    pub mentions_everyone: bool,
+}
+
+/// Users with push subscriptions who should be notified about `job`: the
+/// other participants of a DM, or guild members the message mentions
+/// directly, by role or with `@everyone`/`@here`, who can see the channel
+/// and whose notification settings allow a push.
+pub async fn resolve_recipients(
+    pool: &DbPool,
+    job: &PushJob,
+) -> Result<Vec<PushRecipient>, CoreError> {
+    let mut candidates: Vec<PushRecipient> = Vec::new();
+    let mut add = |user_id: i64, mentions_user: bool, mentions_everyone: bool| {
+        if user_id == job.author_id {
+            return;
+        }
+        match candidates.iter_mut().find(|c| c.user_id == user_id) {
+            Some(existing) => {
+                existing.mentions_user |= mentions_user;
+                existing.mentions_everyone |= mentions_everyone;
+            }
+            None => candidates.push(PushRecipient {
+                user_id,
+                mentions_user,
+                mentions_everyone,
+            }),
+        }
+    };
+
+    match job.guild_id {
+        None => {
+            for user_id in paracord_db::dms::get_dm_recipient_ids(pool, job.channel_id).await? {
+                add(user_id, true, false);
+            }
+        }
+        Some(guild_id) => {
+            let mentions =
+                paracord_db::message_mentions::get_message_mentions(pool, job.message_id).await?;
+            for user_id in &mentions.user_ids {
+                add(*user_id, true, false);
+            }
+            if !mentions.role_ids.is_empty() {
+                for user_id in paracord_db::push_subscriptions::list_subscribed_member_ids(
+                    pool,
+                    guild_id,
+                    Some(&mentions.role_ids),
+                )
+                .await?
+                {
+                    add(user_id, true, false);
+                }
+            }
+            if mentions.everyone || mentions.here {
+                for user_id in paracord_db::push_subscriptions::list_subscribed_member_ids(
+                    pool, guild_id, None,
+                )
+                .await?
+                {
+                    add(user_id, false, true);
+                }
+            }
+        }
+    }
+
+    let guild_owner = match job.guild_id {
+        Some(guild_id) => Some(
+            paracord_db::guilds::get_guild(pool, guild_id)
+                .await?
+                .ok_or(CoreError::NotFound)?
+                .owner_id,
+        ),
+        None => None,
+    };
+    let now = chrono::Utc::now();
+    let mut recipients = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        if let (Some(guild_id), Some(owner_id)) = (job.guild_id, guild_owner) {
+            let perms = permissions::compute_channel_permissions(
+                pool,
+                guild_id,
+                job.channel_id,
+                owner_id,
+                candidate.user_id,
+            )
+            .await?;
+            if !perms.contains(Permissions::VIEW_CHANNEL) {
+                continue;
+            }
+        }
+        let settings = paracord_db::notification_settings::get_effective_settings(
+            pool,
+            candidate.user_id,
+            job.channel_id,
+            now,
+        )
+        .await?;
+        let notify = if candidate.mentions_user {
+            settings.should_notify(&settings.push_level, true, false)
+        } else {
+            settings.should_notify(&settings.push_level, false, candidate.mentions_everyone)
+                && !settings.suppress_everyone
+        };
+        if notify {
+            recipients.push(candidate);
+        }
+    }
+    Ok(recipients)
+}
+
+async fn run_worker(
+    pool: DbPool,
+    client: OutboundClient,
+    vapid: VapidKey,
+    subject: String,
+    mut rx: mpsc::Receiver<PushJob>,
+) {
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    while rx.recv_many(&mut batch, BATCH_SIZE).await > 0 {
+        let mut deliveries = Vec::new();
+        for job in batch.drain(..) {
+            match build_deliveries(&pool, &job).await {
+                Ok(mut built) => deliveries.append(&mut built),
+                Err(err) => tracing::warn!(
+                    message_id = job.message_id,
+                    "Failed to resolve push recipients: {}",
+                    err
+                ),
+            }
+        }
+
+        let mut in_flight = JoinSet::new();
+        for (subscription, payload) in deliveries {
+            if in_flight.len() >= MAX_CONCURRENT_SENDS {
+                in_flight.join_next().await;
+            }
+            let client = client.clone();
+            let pool = pool.clone();
+            let authorization = match vapid.authorization(
+                &subscription.endpoint,
+                &subject,
+                chrono::Utc::now().timestamp(),
+            ) {
+                Ok(value) => value,
+                Err(err) => {
+                    tracing::debug!("Skipping push subscription: {}", err);
+                    continue;
+                }
+            };
+            in_flight.spawn(async move {
+                send_push(&client, &pool, &subscription, &payload, &authorization).await;
+            });
+        }
+        while in_flight.join_next().await.is_some() {}
+    }
+}
+
+async fn build_deliveries(
+    pool: &DbPool,
+    job: &PushJob,
+) -> Result<Vec<(PushSubscriptionRow, serde_json::Value)>, CoreError> {
+    let recipients = resolve_recipients(pool, job).await?;
+    if recipients.is_empty() {
+        return Ok(Vec::new());
+    }
+    let author = paracord_db::users::get_user_by_id(pool, job.author_id).await?;
+    let author_name = author
+        .as_ref()
+        .map(|a| a.display_name.clone().unwrap_or_else(|| a.username.clone()))
+        .unwrap_or_default();
+    // DM content is end-to-end encrypted, so only guild pushes carry a preview.
+    let (body, channel_name) = match job.guild_id {
+        Some(_) => {
+            let message = paracord_db::messages::get_message(pool, job.message_id).await?;
+            let channel = paracord_db::channels::get_channel(pool, job.channel_id).await?;
+            (
+                message
+                    .and_then(|m| m.content)
+                    .map(|content| content.chars().take(PREVIEW_CHARS).collect::<String>()),
+                channel.and_then(|c| c.name),
+            )
+        }
+        None => (None, None),
+    };
+
+    let mut deliveries = Vec::new();
+    for recipient in recipients {
+        let payload = json!({
+            "type": "message",
+            "message_id": job.message_id.to_string(),
+            "channel_id": job.channel_id.to_string(),
+            "guild_id": job.guild_id.map(|id| id.to_string()),
+            "channel_name": channel_name,
+            "author": { "id": job.author_id.to_string(), "name": author_name },
+            "body": body,
+            "mention": recipient.mentions_user || recipient.mentions_everyone,
+        });
+        for subscription in
+            paracord_db::push_subscriptions::list_user_push_subscriptions(pool, recipient.user_id)
+                .await?
+        {
+            deliveries.push((subscription, payload.clone()));
+        }
+    }
+    Ok(deliveries)
+}
+
+async fn send_push(
+    client: &OutboundClient,
+    pool: &DbPool,
+    subscription: &PushSubscriptionRow,
+    payload: &serde_json::Value,
+    authorization: &str,
+) {
+    let body = match paracord_util::web_push::encrypt(
+        payload.to_string().as_bytes(),
+        &subscription.p256dh,
+        &subscription.auth_secret,
+    ) {
+        Ok(body) => body,
+        Err(err) => {
+            tracing::debug!("Dropping push subscription with unusable keys: {}", err);
+            let _ = paracord_db::push_subscriptions::delete_push_subscription_by_endpoint(
+                pool,
+                &subscription.endpoint,
+            )
+            .await;
+            return;
+        }
+    };
+    let Ok(authorization) = HeaderValue::from_str(authorization) else {
+        tracing::debug!("Skipping push with an unusable VAPID header");
+        return;
+    };
+    let mut headers = HeaderMap::new();
+    headers.insert(reqwest::header::AUTHORIZATION, authorization);
+    headers.insert(CONTENT_ENCODING, HeaderValue::from_static("aes128gcm"));
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+    headers.insert("TTL", HeaderValue::from(PUSH_TTL_SECONDS));
+    headers.insert("Urgency", HeaderValue::from_static("high"));
+    let response = client.post(&subscription.endpoint, body, headers).await;
+    match response {
+        Ok(response) if response.status().is_success() => {}
+        Ok(response)
+            if matches!(
+                response.status(),
+                reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::GONE
+            ) =>
+        {
+            // The browser unsubscribed or the subscription expired.
+            if let Err(err) = paracord_db::push_subscriptions::delete_push_subscription_by_endpoint(
+                pool,
+                &subscription.endpoint,
+            )
+            .await
+            {
+                tracing::warn!("Failed to prune push subscription: {}", err);
+            }
+        }
+        Ok(response) => {
+            tracing::debug!(
+                user_id = subscription.user_id,
+                "Push service rejected notification: {}",
+                response.status()
+            );
+        }
+        Err(err) => {
+            tracing::debug!(
+                user_id = subscription.user_id,
+                "Push delivery failed: {}",
+                err
+            );
+        }
+    }
+}
@@ -0,0 +1,441 @@
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Buckets that HTTP routes are grouped into for rate limiting. Each category
+/// has its own per-client limit so credential endpoints can be strict while
+/// read traffic stays generous.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RouteCategory {
+    /// Credential-bearing endpoints: login, register, MFA, key verification.
+    Auth,
+    /// Creating messages (channel sends, webhook executes and slash command
+    /// interactions).
+    MessageSend,
+    /// Any other mutating request.
+    Write,
+    /// Safe methods (GET/HEAD) and everything not otherwise classified.
+    Read,
+}
+
+impl RouteCategory {
+    pub const ALL: [RouteCategory; 4] = [
+        RouteCategory::Auth,
+        RouteCategory::MessageSend,
+        RouteCategory::Write,
+        RouteCategory::Read,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RouteCategory::Auth => "auth",
+            RouteCategory::MessageSend => "message_send",
+            RouteCategory::Write => "write",
+            RouteCategory::Read => "read",
+        }
+    }
+}
+
+/// At most `max_requests` per `window_seconds`. HTTP limits enforce this as
+/// a [`TokenBucket`]; the webhook and announcement limiters use fixed windows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RouteLimit {
+    pub max_requests: u32,
+    pub window_seconds: i64,
+}
+
+impl RouteLimit {
+    pub const fn per_second(max_requests: u32) -> Self {
+        Self {
+            max_requests,
+            window_seconds: 1,
+        }
+    }
+
+    pub const fn per_minute(max_requests: u32) -> Self {
+        Self {
+            max_requests,
+            window_seconds: 60,
+        }
+    }
+}
+
+/// Default cap on client buckets held by the HTTP rate limiter.
+pub const DEFAULT_MAX_TRACKED_KEYS: usize = 100_000;
+
+#[derive(Clone, Debug)]
+pub struct HttpRateLimits {
+    limits: HashMap<RouteCategory, RouteLimit>,
+    /// Additional per-token limit applied to `Bot` authenticated requests.
+    pub bot: RouteLimit,
+    /// Most client buckets kept at once; the least recently used are evicted
+    /// beyond this.
+    pub max_tracked_keys: usize,
+}
+
+impl Default for HttpRateLimits {
+    fn default() -> Self {
+        let limits = HashMap::from([
+            (RouteCategory::Auth, RouteLimit::per_minute(5)),
+            (RouteCategory::MessageSend, RouteLimit::per_second(10)),
+            (RouteCategory::Write, RouteLimit::per_second(60)),
+            (RouteCategory::Read, RouteLimit::per_second(120)),
+        ]);
+        Self {
+            limits,
+            bot: RouteLimit::per_minute(300),
+            max_tracked_keys: DEFAULT_MAX_TRACKED_KEYS,
+        }
+    }
+}
+
+impl HttpRateLimits {
+    pub fn get(&self, category: RouteCategory) -> RouteLimit {
+        self.limits
+            .get(&category)
+            .copied()
+            .unwrap_or(RouteLimit::per_second(120))
+    }
+
+    pub fn set(&mut self, category: RouteCategory, limit: RouteLimit) {
+        self.limits.insert(category, limit);
+    }
+
+    /// The longest window of any limit. A bucket idle for this long has
+    /// refilled completely and can be forgotten.
+    pub fn longest_window_seconds(&self) -> i64 {
+        self.limits
+            .values()
+            .map(|limit| limit.window_seconds)
+            .chain(std::iter::once(self.bot.window_seconds))
+            .max()
+            .unwrap_or(1)
+    }
+}
+
+/// Result of charging one request against a [`TokenBucket`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BucketCheck {
+    pub allowed: bool,
+    /// Whole tokens left after this request.
+    pub remaining: u32,
+    /// Milliseconds until the next token is available; zero if allowed.
+    pub retry_after_ms: i64,
+    /// Milliseconds until the bucket is full again.
+    pub full_after_ms: i64,
+}
+
+/// A token bucket holding up to `max_requests` tokens and refilling at
+/// `max_requests / window_seconds` per second. Unlike a fixed window there is
+/// no boundary at which the whole quota comes back at once, so a client
+/// cannot double its rate by bursting on either side of a reset.
+#[derive(Clone, Copy, Debug)]
+pub struct TokenBucket {
+    tokens: f64,
+    updated_ms: i64,
+}
+
+impl TokenBucket {
+    /// A bucket that starts with its full burst available.
+    pub fn full(limit: RouteLimit, now_ms: i64) -> Self {
+        Self {
+            tokens: f64::from(limit.max_requests),
+            updated_ms: now_ms,
+        }
+    }
+
+    /// When the bucket was last charged.
+    pub fn updated_ms(&self) -> i64 {
+        self.updated_ms
+    }
+
+    /// Refill for the time elapsed since the last call, then take one token
+    /// if there is one.
+    pub fn try_take(&mut self, limit: RouteLimit, now_ms: i64) -> BucketCheck {
+        let capacity = f64::from(limit.max_requests);
+        let window_ms = limit.window_seconds.max(1).saturating_mul(1000);
+        if limit.max_requests == 0 {
+            return BucketCheck {
+                allowed: false,
+                remaining: 0,
+                retry_after_ms: window_ms,
+                full_after_ms: window_ms,
+            };
+        }
+        let per_ms = capacity / window_ms as f64;
+
+        let elapsed_ms = now_ms.saturating_sub(self.updated_ms).max(0);
+        self.tokens = (self.tokens + elapsed_ms as f64 * per_ms).min(capacity);
+        self.updated_ms = self.updated_ms.max(now_ms);
+
+        let allowed = self.tokens >= 1.0;
+        if allowed {
+            self.tokens -= 1.0;
+        }
+        let retry_after_ms = if allowed {
+            0
+        } else {
+            ((1.0 - self.tokens) / per_ms).ceil() as i64
+        };
+        BucketCheck {
+            allowed,
+            remaining: self.tokens.floor() as u32,
+            retry_after_ms,
+            full_after_ms: ((capacity - self.tokens) / per_ms).ceil() as i64,
+        }
+    }
+}
+
+/// Longest slowmode (`rate_limit_per_user`) a channel can be given: 6 hours.
+pub const MAX_SLOWMODE_SECONDS: i32 = 21_600;
+
+static SLOWMODE: LazyLock<SlowmodeTracker> = LazyLock::new(SlowmodeTracker::default);
+
+/// Process-wide slowmode state shared by every message send path.
+pub fn slowmode() -> &'static SlowmodeTracker {
+    &SLOWMODE
+}
+
+/// A send let through by [`SlowmodeTracker::try_send`], kept until the send
+/// finishes so it can be released if no message came of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlowmodeReservation {
+    channel_id: i64,
+    user_id: i64,
+    previous_ms: i64,
+    until_ms: i64,
+}
+
+/// When each user may next post in each slowmode channel, keyed by
+/// `(channel_id, user_id)`.
+#[derive(Default)]
+pub struct SlowmodeTracker {
+    next_allowed_ms: DashMap<(i64, i64), i64>,
+}
+
+impl SlowmodeTracker {
+    /// Reserve a send at `now_ms` unless the user is still inside the
+    /// channel's `window_seconds`; then return the milliseconds left instead.
+    /// Checking and reserving happen under one entry lock, so concurrent
+    /// sends can't both get in. Returns `Ok(None)` when the channel has no
+    /// slowmode.
+    pub fn try_send(
+        &self,
+        channel_id: i64,
+        user_id: i64,
+        window_seconds: i32,
+        now_ms: i64,
+    ) -> Result<Option<SlowmodeReservation>, i64> {
+        if window_seconds <= 0 {
+            return Ok(None);
+        }
+        let mut next_allowed = self
+            .next_allowed_ms
+            .entry((channel_id, user_id))
+            .or_insert(i64::MIN);
+        if now_ms < *next_allowed {
+            return Err(*next_allowed - now_ms);
+        }
+        let reservation = SlowmodeReservation {
+            channel_id,
+            user_id,
+            previous_ms: *next_allowed,
+            until_ms: now_ms.saturating_add(i64::from(window_seconds) * 1000),
+        };
+        *next_allowed = reservation.until_ms;
+        Ok(Some(reservation))
+    }
+
+    /// Give back a reservation whose send did not create a message, unless a
+    /// later send has reserved since.
+    pub fn release(&self, reservation: SlowmodeReservation) {
+        let key = (reservation.channel_id, reservation.user_id);
+        self.next_allowed_ms.remove_if_mut(&key, |_, next_allowed| {
+            if *next_allowed != reservation.until_ms {
+                return false;
+            }
+            *next_allowed = reservation.previous_ms;
+            reservation.previous_ms == i64::MIN
+        });
+    }
+
+    /// Drop entries whose window has already elapsed.
+    pub fn prune(&self, now_ms: i64) {
+        self.next_allowed_ms
+            .retain(|_, next_allowed| *next_allowed > now_ms);
+    }
+
+    pub fn len(&self) -> usize {
+        self.next_allowed_ms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.next_allowed_ms.is_empty()
+    }
+}
+
+/// How often a single webhook may be executed, independent of the per-client
+/// HTTP limits (webhook callers are often shared CI runners behind one IP).
+pub const WEBHOOK_EXECUTE_LIMIT: RouteLimit = RouteLimit {
+    max_requests: 5,
+    window_seconds: 2,
+};
+
+static WEBHOOKS: LazyLock<WebhookRateLimiter> = LazyLock::new(WebhookRateLimiter::default);
+
+/// Process-wide execution limiter shared by every webhook.
+pub fn webhooks() -> &'static WebhookRateLimiter {
+    &WEBHOOKS
+}
+
+static SYSTEM_MESSAGES: LazyLock<WebhookRateLimiter> = LazyLock::new(WebhookRateLimiter::default);
+
+/// Process-wide budget for join/leave announcements, keyed by space id. See
+/// [`crate::system_messages`].
+pub fn system_messages() -> &'static WebhookRateLimiter {
+    &SYSTEM_MESSAGES
+}
+
+/// Fixed-window execution counts keyed by webhook id.
+#[derive(Default)]
+pub struct WebhookRateLimiter {
+    windows: DashMap<i64, (i64, u32)>,
+}
+
+impl WebhookRateLimiter {
+    /// Count an execution at `now_ms` against `limit`; once the window is
+    /// full, return the milliseconds until it resets instead.
+    pub fn try_execute(&self, webhook_id: i64, limit: RouteLimit, now_ms: i64) -> Result<(), i64> {
+        let window_ms = limit.window_seconds.max(1).saturating_mul(1000);
+        let mut window = self.windows.entry(webhook_id).or_insert((now_ms, 0));
+        let (started_ms, count) = &mut *window;
+        if now_ms.saturating_sub(*started_ms) >= window_ms {
+            *started_ms = now_ms;
+            *count = 0;
+        }
+        if *count >= limit.max_requests {
+            return Err(*started_ms + window_ms - now_ms);
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    /// Drop windows that started more than `max_window_seconds` ago.
+    pub fn prune(&self, now_ms: i64, max_window_seconds: i64) {
+        let cutoff = now_ms.saturating_sub(max_window_seconds.saturating_mul(1000));
+        self.windows
+            .retain(|_, (started_ms, _)| *started_ms > cutoff);
+    }
+
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slowmode_rejects_sends_inside_the_window() {
+        let tracker = SlowmodeTracker::default();
+        assert!(tracker.try_send(1, 10, 5, 1_000).unwrap().is_some());
+        assert_eq!(tracker.try_send(1, 10, 5, 3_500), Err(2_500));
+        // Other users and other channels are tracked separately.
+        assert!(tracker.try_send(1, 11, 5, 3_500).is_ok());
+        assert!(tracker.try_send(2, 10, 5, 3_500).is_ok());
+        // A rejected send does not extend the window.
+        assert!(tracker.try_send(1, 10, 5, 6_000).is_ok());
+        // Channels without slowmode are never limited.
+        assert_eq!(tracker.try_send(3, 10, 0, 6_000), Ok(None));
+        assert_eq!(tracker.try_send(3, 10, 0, 6_001), Ok(None));
+    }
+
+    #[test]
+    fn slowmode_release_restores_the_previous_window() {
+        let tracker = SlowmodeTracker::default();
+        let first = tracker.try_send(1, 10, 5, 0).unwrap().unwrap();
+        tracker.release(first);
+        assert!(tracker.is_empty());
+
+        tracker.try_send(1, 10, 5, 0).unwrap();
+        let second = tracker.try_send(1, 10, 5, 5_000).unwrap().unwrap();
+        tracker.release(second);
+        assert_eq!(tracker.try_send(1, 10, 5, 4_000), Err(1_000));
+
+        // A send that outlived its window can't release a newer reservation.
+        let stale = tracker.try_send(1, 10, 5, 5_000).unwrap().unwrap();
+        let current = tracker.try_send(1, 10, 5, 10_000).unwrap().unwrap();
+        tracker.release(stale);
+        assert_eq!(tracker.try_send(1, 10, 5, 11_000), Err(4_000));
+        tracker.release(current);
+        assert!(tracker.try_send(1, 10, 5, 11_000).is_ok());
+    }
+
+    #[test]
+    fn slowmode_prune_drops_elapsed_windows() {
+        let tracker = SlowmodeTracker::default();
+        tracker.try_send(1, 10, 5, 0).unwrap();
+        tracker.try_send(1, 11, 60, 0).unwrap();
+        tracker.prune(10_000);
+        assert_eq!(tracker.len(), 1);
+        assert!(tracker.try_send(1, 11, 60, 10_000).is_err());
+    }
+
+    #[test]
+    fn token_bucket_refills_gradually_instead_of_resetting() {
+        let limit = RouteLimit::per_second(10);
+        let mut bucket = TokenBucket::full(limit, 0);
+        for expected_remaining in (0..10).rev() {
+            let check = bucket.try_take(limit, 900);
+            assert!(check.allowed);
+            assert_eq!(check.remaining, expected_remaining);
+        }
+        let check = bucket.try_take(limit, 900);
+        assert!(!check.allowed);
+        assert_eq!(check.retry_after_ms, 100);
+        assert_eq!(check.full_after_ms, 1_000);
+
+        // A fixed window would hand back all ten at t=1000; the bucket has
+        // only refilled one token.
+        assert!(bucket.try_take(limit, 1_000).allowed);
+        assert!(!bucket.try_take(limit, 1_000).allowed);
+
+        // Idle long enough and the burst is available again, but no more.
+        let check = bucket.try_take(limit, 60_000);
+        assert!(check.allowed);
+        assert_eq!(check.remaining, 9);
+    }
+
+    #[test]
+    fn token_bucket_with_zero_capacity_never_admits() {
+        let limit = RouteLimit::per_minute(0);
+        let mut bucket = TokenBucket::full(limit, 0);
+        let check = bucket.try_take(limit, 120_000);
+        assert!(!check.allowed);
+        assert_eq!(check.retry_after_ms, 60_000);
+    }
+
+    #[test]
+    fn webhook_limiter_counts_executions_per_webhook() {
+        let limiter = WebhookRateLimiter::default();
+        let limit = RouteLimit {
+            max_requests: 2,
+            window_seconds: 2,
+        };
+        assert_eq!(limiter.try_execute(1, limit, 0), Ok(()));
+        assert_eq!(limiter.try_execute(1, limit, 500), Ok(()));
+        assert_eq!(limiter.try_execute(1, limit, 1_500), Err(500));
+        // Another webhook has its own window.
+        assert_eq!(limiter.try_execute(2, limit, 1_500), Ok(()));
+        // The window resets once it has elapsed.
+        assert_eq!(limiter.try_execute(1, limit, 2_000), Ok(()));
+
+        limiter.prune(10_000, 2);
+        assert!(limiter.is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+//! Join/leave announcements posted to a space's system channel.
+//!
+//! Each announcement is an empty message authored by the member it is about,
+//! with a dedicated message type so clients can render it as a notice. Spaces
+//! opt in by setting `system_channel_id` and can suppress either kind with
+//! `system_channel_flags`. A burst of joins or removals (a raid, a mass kick)
+//! posts at most [`ANNOUNCEMENT_LIMIT`] announcements per space; the rest are
+//! dropped rather than flooding the channel.
+
+use paracord_db::messages::MessageRow;
+use paracord_db::DbPool;
+use paracord_models::channel::ChannelType;
+use paracord_models::message::MessageType;
+
+use crate::error::CoreError;
+use crate::rate_limit::{self, RouteLimit};
+
+/// `system_channel_flags` bit: don't announce members joining.
+pub const SUPPRESS_JOIN_NOTIFICATIONS: i32 = 1 << 0;
+/// `system_channel_flags` bit: don't announce members leaving, being kicked
+/// or being banned.
+pub const SUPPRESS_LEAVE_NOTIFICATIONS: i32 = 1 << 1;
+pub const ALL_SYSTEM_CHANNEL_FLAGS: i32 =
+    SUPPRESS_JOIN_NOTIFICATIONS | SUPPRESS_LEAVE_NOTIFICATIONS;
+
+/// Announcements each space may post per window.
+pub const ANNOUNCEMENT_LIMIT: RouteLimit = RouteLimit::per_minute(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberEvent {
+    Join,
+    Leave,
+    Kick,
+    Ban,
+}
+
+impl MemberEvent {
+    pub fn message_type(self) -> MessageType {
+        match self {
+            Self::Join => MessageType::GuildMemberJoin,
+            Self::Leave => MessageType::GuildMemberLeave,
+            Self::Kick => MessageType::GuildMemberKick,
+            Self::Ban => MessageType::GuildMemberBan,
+        }
+    }
+
+    fn suppressed_by(self) -> i32 {
+        match self {
+            Self::Join => SUPPRESS_JOIN_NOTIFICATIONS,
+            Self::Leave | Self::Kick | Self::Ban => SUPPRESS_LEAVE_NOTIFICATIONS,
+        }
+    }
+}
+
+/// Whether a channel can hold announcements.
+pub fn is_announcement_channel(channel_type: i16) -> bool {
+    channel_type == ChannelType::Text as i16 || channel_type == ChannelType::Announcement as i16
+}
+
+/// Store the announcement for `event` about `user_id`, if the space has a
+/// system channel, hasn't suppressed this kind of event and is within its
+/// announcement budget. Returns the stored message for the caller to
+/// dispatch.
+pub async fn create_member_event_message(
+    pool: &DbPool,
+    guild_id: i64,
+    user_id: i64,
+    event: MemberEvent,
+    now_ms: i64,
+) -> Result<Option<MessageRow>, CoreError> {
+    let Some(guild) = paracord_db::guilds::get_guild(pool, guild_id).await? else {
+        return Ok(None);
+    };
+    let Some(channel_id) = guild.system_channel_id else {
+        return Ok(None);
+    };
+    if guild.system_channel_flags & event.suppressed_by() != 0 {
+        return Ok(None);
+    }
+    // The channel may have been deleted or moved since it was configured.
+    let channel = paracord_db::channels::get_channel(pool, channel_id).await?;
+    if !channel
+        .is_some_and(|c| c.guild_id() == Some(guild_id) && is_announcement_channel(c.channel_type))
+    {
+        return Ok(None);
+    }
+    if rate_limit::system_messages()
+        .try_execute(guild_id, ANNOUNCEMENT_LIMIT, now_ms)
+        .is_err()
+    {
+        tracing::debug!(guild_id, ?event, "Suppressing member announcement");
+        return Ok(None);
+    }
+
+    let message = paracord_db::messages::create_message(
+        pool,
+        paracord_util::snowflake::generate(),
+        channel_id,
+        user_id,
+        "",
+        event.message_type() as i16,
+        None,
+    )
+    .await?;
+    Ok(Some(message))
+}
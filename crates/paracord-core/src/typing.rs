@@ -0,0 +1,88 @@
+use dashmap::DashMap;
+use std::sync::LazyLock;
+
+/// Minimum gap between two `TYPING_START` broadcasts for the same user in the
+/// same channel. Clients re-send while the user keeps typing.
+pub const TYPING_DEBOUNCE_MS: i64 = 5_000;
+/// How long a typing indicator stays visible without being refreshed.
+pub const TYPING_TTL_MS: i64 = 10_000;
+
+static TYPING: LazyLock<TypingTracker> = LazyLock::new(TypingTracker::default);
+
+/// Process-wide typing state shared by the HTTP, realtime and gateway paths.
+pub fn tracker() -> &'static TypingTracker {
+    &TYPING
+}
+
+/// Last broadcast typing-start per `(channel_id, user_id)`. Entries are
+/// dropped once their indicator has expired, on every call to `start`.
+#[derive(Default)]
+pub struct TypingTracker {
+    last_broadcast_ms: DashMap<(i64, i64), i64>,
+}
+
+impl TypingTracker {
+    /// Register a typing-start at `now_ms`. Returns the indicator's expiry
+    /// (unix ms) when it should be broadcast, or `None` when the previous
+    /// broadcast is recent enough that subscribers still show it.
+    pub fn start(&self, channel_id: i64, user_id: i64, now_ms: i64) -> Option<i64> {
+        self.last_broadcast_ms
+            .retain(|_, last| now_ms.saturating_sub(*last) < TYPING_TTL_MS);
+
+        let mut last = self
+            .last_broadcast_ms
+            .entry((channel_id, user_id))
+            .or_insert(i64::MIN);
+        if now_ms.saturating_sub(*last) < TYPING_DEBOUNCE_MS {
+            return None;
+        }
+        *last = now_ms;
+        Some(now_ms + TYPING_TTL_MS)
+    }
+
+    /// Forget the user's indicator, e.g. after they sent the message.
+    pub fn stop(&self, channel_id: i64, user_id: i64) {
+        self.last_broadcast_ms.remove(&(channel_id, user_id));
+    }
+
+    pub fn len(&self) -> usize {
+        self.last_broadcast_ms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.last_broadcast_ms.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_starts_are_debounced() {
+        let tracker = TypingTracker::default();
+        assert_eq!(tracker.start(1, 10, 0), Some(TYPING_TTL_MS));
+        assert_eq!(tracker.start(1, 10, 1_000), None);
+        assert_eq!(tracker.start(1, 10, TYPING_DEBOUNCE_MS - 1), None);
+        assert_eq!(
+            tracker.start(1, 10, TYPING_DEBOUNCE_MS),
+            Some(TYPING_DEBOUNCE_MS + TYPING_TTL_MS)
+        );
+        // Other users and channels are independent.
+        assert!(tracker.start(1, 11, 1_000).is_some());
+        assert!(tracker.start(2, 10, 1_000).is_some());
+
+        tracker.stop(1, 10);
+        assert!(tracker.start(1, 10, TYPING_DEBOUNCE_MS + 1).is_some());
+    }
+
+    #[test]
+    fn expired_indicators_are_pruned() {
+        let tracker = TypingTracker::default();
+        tracker.start(1, 10, 0);
+        tracker.start(1, 11, 0);
+        assert_eq!(tracker.len(), 2);
+        tracker.start(2, 12, TYPING_TTL_MS);
+        assert_eq!(tracker.len(), 1);
+    }
+}
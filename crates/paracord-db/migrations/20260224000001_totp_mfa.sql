@@ -0,0 +1,16 @@
+-- TOTP two-factor authentication: encrypted shared secret on the user row plus
+-- single-use hashed recovery codes.
+
+ALTER TABLE users ADD COLUMN totp_secret_enc TEXT;
+ALTER TABLE users ADD COLUMN totp_last_step BIGINT;
+
+CREATE TABLE IF NOT EXISTS user_recovery_codes (
+    id          BIGINT PRIMARY KEY,
+    user_id     BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash   TEXT NOT NULL UNIQUE,
+    used_at     TEXT,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_recovery_codes_user
+    ON user_recovery_codes (user_id);
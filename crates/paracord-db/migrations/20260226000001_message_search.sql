@@ -0,0 +1,36 @@
+-- Full-text search over message content. messages_fts is keyed by message id
+-- (its rowid) and kept in step by triggers, so edits, bulk deletes, and
+-- cascading deletes from channels/spaces all update the index. E2EE payloads
+-- (flags & 1) are never indexed.
+
+CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+    content,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO messages_fts (rowid, content)
+SELECT id, content
+FROM messages
+WHERE content IS NOT NULL AND (flags & 1) = 0;
+
+CREATE TRIGGER IF NOT EXISTS messages_fts_after_insert
+AFTER INSERT ON messages
+WHEN NEW.content IS NOT NULL AND (NEW.flags & 1) = 0
+BEGIN
+    INSERT INTO messages_fts (rowid, content) VALUES (NEW.id, NEW.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS messages_fts_after_delete
+AFTER DELETE ON messages
+BEGIN
+    DELETE FROM messages_fts WHERE rowid = OLD.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS messages_fts_after_update
+AFTER UPDATE OF content, flags ON messages
+BEGIN
+    DELETE FROM messages_fts WHERE rowid = OLD.id;
+    INSERT INTO messages_fts (rowid, content)
+    SELECT NEW.id, NEW.content
+    WHERE NEW.content IS NOT NULL AND (NEW.flags & 1) = 0;
+END;
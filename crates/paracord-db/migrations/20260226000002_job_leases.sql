@@ -0,0 +1,9 @@
+-- Leases for background jobs that must run on only one server at a time when
+-- several instances share a database. A lease is held until expires_at; a
+-- crashed holder's lease simply lapses.
+
+CREATE TABLE IF NOT EXISTS job_leases (
+    name        TEXT PRIMARY KEY,
+    holder      TEXT NOT NULL,
+    expires_at  TEXT NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE user_settings
+ADD COLUMN show_mutuals BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,7 @@
+-- Confusable skeleton of the username (UTS #39) so look-alike names can be
+-- found through an index. Written by the application; NULL until backfilled.
+
+ALTER TABLE users
+    ADD COLUMN username_skeleton TEXT;
+
+CREATE INDEX idx_users_username_skeleton ON users(username_skeleton);
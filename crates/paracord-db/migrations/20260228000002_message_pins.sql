@@ -0,0 +1,12 @@
+-- Record when and by whom a message was pinned so pins list in pin order.
+
+ALTER TABLE messages
+    ADD COLUMN pinned_at TEXT;
+ALTER TABLE messages
+    ADD COLUMN pinned_by INTEGER REFERENCES users(id) ON DELETE SET NULL;
+
+-- Existing pins have no recorded pin time; fall back to the message time.
+UPDATE messages SET pinned_at = created_at WHERE pinned = TRUE AND pinned_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_messages_channel_pins
+    ON messages(channel_id, pinned_at) WHERE pinned = TRUE;
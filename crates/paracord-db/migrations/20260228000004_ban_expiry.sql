@@ -0,0 +1,6 @@
+-- Temporary bans: NULL keeps the ban permanent.
+ALTER TABLE bans
+    ADD COLUMN expires_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_bans_expires_at
+    ON bans(expires_at) WHERE expires_at IS NOT NULL;
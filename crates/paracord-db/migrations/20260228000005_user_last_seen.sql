@@ -0,0 +1,2 @@
+-- When the user's last gateway session closed; NULL while never seen.
+ALTER TABLE users ADD COLUMN last_seen_at TEXT;
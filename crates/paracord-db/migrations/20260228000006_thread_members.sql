@@ -0,0 +1,10 @@
+-- Users following a thread: its creator, anyone who posted in it and anyone
+-- who joined explicitly.
+
+CREATE TABLE IF NOT EXISTS thread_members (
+    thread_id   INTEGER NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+    user_id     INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    joined_at   TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (thread_id, user_id)
+);
+CREATE INDEX IF NOT EXISTS idx_thread_members_user ON thread_members(user_id);
@@ -0,0 +1,3 @@
+-- Members who joined through a temporary invite; they are removed when
+-- their last gateway session closes unless they were given a role.
+ALTER TABLE members ADD COLUMN temporary BOOLEAN NOT NULL DEFAULT FALSE;
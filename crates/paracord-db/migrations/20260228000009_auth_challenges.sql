@@ -0,0 +1,11 @@
+-- Nonces issued by /auth/challenge. A nonce can be consumed by exactly one
+-- /auth/verify call before it expires; rows are swept after expiry.
+CREATE TABLE IF NOT EXISTS auth_challenges (
+    nonce TEXT PRIMARY KEY,
+    issued_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL,
+    consumed_at INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_auth_challenges_expires_at
+    ON auth_challenges (expires_at);
@@ -0,0 +1,27 @@
+-- Outbound federation events that ran out of delivery attempts. They are
+-- moved here from federation_outbound_queue instead of being dropped so an
+-- operator can see what a remote server missed.
+CREATE TABLE IF NOT EXISTS federation_outbound_dead_letters (
+    id                      INTEGER PRIMARY KEY AUTOINCREMENT,
+    destination_server      VARCHAR(255) NOT NULL,
+    event_id                VARCHAR(255) NOT NULL,
+    room_id                 VARCHAR(255) NOT NULL,
+    event_type              VARCHAR(255) NOT NULL,
+    sender                  VARCHAR(255) NOT NULL,
+    origin_server           VARCHAR(255) NOT NULL,
+    origin_ts               BIGINT NOT NULL,
+    content                 TEXT NOT NULL,
+    depth                   BIGINT NOT NULL,
+    state_key               VARCHAR(255),
+    signatures              TEXT NOT NULL,
+    attempt_count           INTEGER NOT NULL,
+    last_error              TEXT,
+    created_at_ms           BIGINT NOT NULL,
+    dead_lettered_at_ms     BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_fed_dead_letters_destination
+    ON federation_outbound_dead_letters(destination_server, dead_lettered_at_ms);
+
+CREATE INDEX IF NOT EXISTS idx_fed_delivery_attempts_time
+    ON federation_delivery_attempts(attempted_at_ms);
@@ -0,0 +1,16 @@
+-- Per-user notification preferences for a whole space or a single channel
+-- (DMs included). target_type is 'guild' or 'channel'. On a channel row a
+-- NULL level or push_level inherits the space row; muting either one mutes
+-- the channel. A muted target with no muted_until stays muted until cleared.
+CREATE TABLE IF NOT EXISTS notification_settings (
+    user_id            INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    target_id          INTEGER NOT NULL,
+    target_type        TEXT NOT NULL,
+    level              TEXT,
+    push_level         TEXT,
+    suppress_everyone  BOOLEAN NOT NULL DEFAULT FALSE,
+    muted              BOOLEAN NOT NULL DEFAULT FALSE,
+    muted_until        TEXT,
+    updated_at         TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (user_id, target_id)
+);
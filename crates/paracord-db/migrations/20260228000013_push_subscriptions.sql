@@ -0,0 +1,13 @@
+-- Web Push subscriptions, one per browser or device. The endpoint is the
+-- push service URL for the subscription; p256dh and auth_secret are the
+-- browser's keys for encrypting payloads to it (base64url).
+CREATE TABLE IF NOT EXISTS push_subscriptions (
+    endpoint     TEXT PRIMARY KEY,
+    user_id      INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    p256dh       TEXT NOT NULL,
+    auth_secret  TEXT NOT NULL,
+    user_agent   TEXT,
+    created_at   TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_push_subscriptions_user ON push_subscriptions(user_id);
@@ -0,0 +1,8 @@
+-- Pronouns shown on user profiles, next to the existing bio, banner and
+-- accent color.
+ALTER TABLE users
+ADD COLUMN pronouns VARCHAR(40);
+
+-- When set, bio and pronouns are only shown to friends.
+ALTER TABLE user_settings
+ADD COLUMN friends_only_profile BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,4 @@
+-- Small image shown next to a role holder's name. The hash names the stored
+-- PNG under role-icons/{role_id}/.
+ALTER TABLE roles
+ADD COLUMN icon_hash VARCHAR(64);
@@ -0,0 +1,34 @@
+-- Slash commands registered by bot applications, and the interactions created
+-- when a member invokes one. `options` holds the JSON option definitions as
+-- submitted (after validation).
+--
+-- Bots receive interactions over the gateway, or by HTTP POST when they set an
+-- interactions endpoint URL. The interaction token is stored hashed and is
+-- single-use: `responded_at` is set when the bot posts its reply.
+
+ALTER TABLE bot_applications
+ADD COLUMN interactions_endpoint_url TEXT;
+
+CREATE TABLE IF NOT EXISTS application_commands (
+    id              BIGINT PRIMARY KEY,
+    application_id  BIGINT NOT NULL REFERENCES bot_applications(id) ON DELETE CASCADE,
+    name            TEXT NOT NULL,
+    description     TEXT NOT NULL,
+    options         TEXT NOT NULL DEFAULT '[]',
+    created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at      TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (application_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS interactions (
+    id              BIGINT PRIMARY KEY,
+    application_id  BIGINT NOT NULL REFERENCES bot_applications(id) ON DELETE CASCADE,
+    command_id      BIGINT NOT NULL REFERENCES application_commands(id) ON DELETE CASCADE,
+    guild_id        BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    channel_id      BIGINT NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+    user_id         BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash      TEXT NOT NULL,
+    responded_at    TEXT,
+    created_at      TEXT NOT NULL DEFAULT (datetime('now'))
+);
+CREATE INDEX IF NOT EXISTS idx_interactions_created ON interactions(created_at);
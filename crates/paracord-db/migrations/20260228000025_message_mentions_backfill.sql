@@ -0,0 +1,7 @@
+-- Messages sent before mention tracking have no message_mentions rows. The
+-- server parses them in the background, newest first, working down from the
+-- id stored here; everything at or above it was parsed when it was sent.
+-- The value drops to 0 once the backfill has finished.
+INSERT OR IGNORE INTO server_settings (key, value)
+SELECT 'message_mentions_backfill_before', CAST(COALESCE(MAX(id), 0) + 1 AS TEXT)
+FROM messages;
@@ -0,0 +1,23 @@
+-- The auto-archive sweep looks for unarchived threads that have been quiet
+-- for a while. Keep both facts in columns so it can read them from an index
+-- instead of parsing thread_metadata for every thread on the server.
+-- last_message_at is the thread's latest activity: its creation, its latest
+-- message or the last change to its archived state.
+ALTER TABLE channels ADD COLUMN archived BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE channels ADD COLUMN last_message_at TEXT;
+
+UPDATE channels SET archived = TRUE
+WHERE channel_type = 6
+  AND json_valid(thread_metadata)
+  AND json_extract(thread_metadata, '$.archived') = 1;
+
+UPDATE channels SET last_message_at = COALESCE(
+    (SELECT MAX(m.created_at) FROM messages m WHERE m.channel_id = channels.id),
+    created_at
+);
+UPDATE channels SET last_message_at = json_extract(thread_metadata, '$.archive_timestamp')
+WHERE channel_type = 6
+  AND json_valid(thread_metadata)
+  AND json_extract(thread_metadata, '$.archive_timestamp') > last_message_at;
+
+CREATE INDEX idx_channels_archived_last_message_at ON channels(archived, last_message_at);
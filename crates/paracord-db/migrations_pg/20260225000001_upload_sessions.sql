@@ -0,0 +1,17 @@
+-- Resumable chunked uploads: one row per in-progress upload, tracking how many
+-- bytes have been durably written to the session's temp file.
+
+CREATE TABLE IF NOT EXISTS upload_sessions (
+    id              BIGINT PRIMARY KEY,
+    user_id         BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    channel_id      BIGINT NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+    filename        TEXT NOT NULL,
+    content_type    TEXT,
+    total_size      BIGINT NOT NULL,
+    received_bytes  BIGINT NOT NULL DEFAULT 0,
+    created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+    expires_at      TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_upload_sessions_expires
+    ON upload_sessions (expires_at);
@@ -0,0 +1,13 @@
+-- Full-text search over message content. The generated tsvector column is
+-- maintained by Postgres on every insert and edit; E2EE payloads (flags & 1)
+-- are left unindexed.
+
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (
+        CASE
+            WHEN (flags & 1) = 0 THEN to_tsvector('simple', coalesce(content, ''))
+        END
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_messages_search_vector
+    ON messages USING GIN (search_vector);
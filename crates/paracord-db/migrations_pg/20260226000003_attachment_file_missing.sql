@@ -0,0 +1,5 @@
+-- Set by storage reconciliation when an attachment row's file is missing from
+-- the storage backend; cleared again if the file reappears.
+
+ALTER TABLE attachments
+    ADD COLUMN IF NOT EXISTS file_missing_at TEXT;
@@ -0,0 +1,14 @@
+-- Prior revisions of edited messages so moderators can see what changed.
+-- The application caps the rows kept per message, dropping the oldest.
+
+ALTER TABLE messages
+    ADD COLUMN IF NOT EXISTS edit_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS message_edits (
+    id                BIGINT PRIMARY KEY,
+    message_id        BIGINT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    editor_id         BIGINT NOT NULL REFERENCES users(id),
+    previous_content  TEXT,
+    edited_at         TEXT NOT NULL DEFAULT (datetime('now'))
+);
+CREATE INDEX IF NOT EXISTS idx_message_edits_message ON message_edits(message_id, id);
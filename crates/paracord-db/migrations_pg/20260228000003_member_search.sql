@@ -0,0 +1,3 @@
+-- Member list filters: join-date ranges within a space and role lookups.
+CREATE INDEX IF NOT EXISTS idx_members_guild_joined ON members(guild_id, joined_at);
+CREATE INDEX IF NOT EXISTS idx_member_roles_role ON member_roles(role_id);
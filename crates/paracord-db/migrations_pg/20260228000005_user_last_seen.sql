@@ -0,0 +1,2 @@
+-- When the user's last gateway session closed; NULL while never seen.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS last_seen_at TEXT;
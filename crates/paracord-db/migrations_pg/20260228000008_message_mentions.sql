@@ -0,0 +1,11 @@
+-- Mentions parsed from message content when a message is sent or edited.
+-- mention_type: 0 = user, 1 = role, 2 = channel, 3 = @everyone, 4 = @here
+-- (target_id is 0 for the last two).
+
+CREATE TABLE IF NOT EXISTS message_mentions (
+    message_id    BIGINT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    mention_type  SMALLINT NOT NULL,
+    target_id     BIGINT NOT NULL,
+    PRIMARY KEY (message_id, mention_type, target_id)
+);
+CREATE INDEX IF NOT EXISTS idx_message_mentions_target ON message_mentions(mention_type, target_id);
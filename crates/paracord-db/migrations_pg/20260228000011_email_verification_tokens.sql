@@ -0,0 +1,11 @@
+-- One-time tokens sent to new accounts when email verification is required.
+-- Only the SHA-256 hash of a token is stored. Using a token clears the
+-- account's unverified flag and removes every token for that account.
+CREATE TABLE IF NOT EXISTS email_verification_tokens (
+    token_hash TEXT PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    expires_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_verification_tokens_user
+    ON email_verification_tokens (user_id);
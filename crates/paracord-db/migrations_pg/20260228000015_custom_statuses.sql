@@ -0,0 +1,12 @@
+-- A user's custom status: free text and/or an emoji shown next to their
+-- presence. A row past its expires_at is treated as cleared and removed by the
+-- presence sweeper.
+CREATE TABLE IF NOT EXISTS user_custom_statuses (
+    user_id     BIGINT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    text        VARCHAR(128),
+    emoji       VARCHAR(64),
+    expires_at  TEXT,
+    updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_custom_statuses_expires ON user_custom_statuses(expires_at);
@@ -0,0 +1,4 @@
+-- Bit flags suppressing the join/leave announcements posted to a space's
+-- system channel: 1 = joins, 2 = leaves, kicks and bans.
+ALTER TABLE spaces
+ADD COLUMN system_channel_flags INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,14 @@
+-- One row per accepted invite, so moderators can see who joined through which
+-- invite and when. Rows outlive the invite itself: expired or deleted codes
+-- still explain where a wave of joins came from.
+
+CREATE TABLE IF NOT EXISTS invite_uses (
+    id          BIGINT PRIMARY KEY,
+    code        TEXT NOT NULL,
+    guild_id    BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    inviter_id  BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    user_id     BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    used_at     TEXT NOT NULL DEFAULT (datetime('now'))
+);
+CREATE INDEX IF NOT EXISTS idx_invite_uses_guild ON invite_uses(guild_id, used_at);
+CREATE INDEX IF NOT EXISTS idx_invite_uses_code ON invite_uses(code, used_at);
@@ -0,0 +1,16 @@
+-- OpenGraph metadata fetched for links posted in messages, keyed by the
+-- SHA-256 of the URL so repeated links are served from here instead of being
+-- fetched again. A fetch that found nothing usable is stored with empty
+-- fields, so a dead or preview-less link isn't retried until the entry goes
+-- stale either.
+
+CREATE TABLE IF NOT EXISTS link_previews (
+    url_hash    TEXT PRIMARY KEY,
+    url         TEXT NOT NULL,
+    title       TEXT,
+    description TEXT,
+    site_name   TEXT,
+    image_url   TEXT,
+    fetched_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+CREATE INDEX IF NOT EXISTS idx_link_previews_fetched ON link_previews(fetched_at);
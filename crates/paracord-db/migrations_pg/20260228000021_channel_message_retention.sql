@@ -0,0 +1,4 @@
+-- Days after which messages in a channel are deleted by the retention
+-- sweeper. NULL keeps messages forever; pinned messages are always kept.
+ALTER TABLE channels
+ADD COLUMN message_retention_days INTEGER;
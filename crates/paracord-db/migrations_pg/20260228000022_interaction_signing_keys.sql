@@ -0,0 +1,5 @@
+-- Ed25519 seed (hex) used to sign interactions POSTed to a bot's
+-- interactions endpoint. Created on first use; the public half is shown to
+-- the owner as `verify_key`.
+ALTER TABLE bot_applications
+ADD COLUMN interactions_signing_key TEXT;
@@ -0,0 +1,6 @@
+-- Member search matches a prefix of the lower-cased username or nickname as
+-- a range on these expressions, so a search reads a slice of an index rather
+-- than every member of the space. The searches compare with the "C"
+-- collation, which orders by code point, so the indexes use it too.
+CREATE INDEX idx_users_username_lower ON users((LOWER(username)) COLLATE "C");
+CREATE INDEX idx_members_guild_nick_lower ON members(guild_id, (LOWER(nick)) COLLATE "C");
@@ -0,0 +1,5 @@
+-- Ban search matches a prefix of the lower-cased reason as a range on this
+-- expression, so a search reads a slice of the index rather than every ban
+-- in the space. The search compares with the "C" collation, like the member
+-- search indexes.
+CREATE INDEX idx_bans_guild_reason_lower ON bans(guild_id, (LOWER(reason)) COLLATE "C");
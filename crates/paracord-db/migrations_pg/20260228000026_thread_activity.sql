@@ -0,0 +1,19 @@
+-- The auto-archive sweep looks for unarchived threads that have been quiet
+-- for a while. Keep both facts in columns so it can read them from an index
+-- instead of parsing thread_metadata for every thread on the server.
+-- last_message_at is the thread's latest activity: its creation, its latest
+-- message or the last change to its archived state.
+ALTER TABLE channels ADD COLUMN archived BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE channels ADD COLUMN last_message_at TEXT;
+
+UPDATE channels SET archived = TRUE
+WHERE channel_type = 6
+  AND (thread_metadata::jsonb ->> 'archived') = 'true';
+
+UPDATE channels SET last_message_at = GREATEST(
+    created_at,
+    (SELECT MAX(m.created_at) FROM messages m WHERE m.channel_id = channels.id),
+    CASE WHEN channel_type = 6 THEN thread_metadata::jsonb ->> 'archive_timestamp' END
+);
+
+CREATE INDEX idx_channels_archived_last_message_at ON channels(archived, last_message_at);
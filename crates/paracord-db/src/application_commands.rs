@@ -0,0 +1,270 @@
+use crate::{datetime_from_db_text, datetime_to_db_text, json_from_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+#[derive(Debug, Clone)]
+pub struct ApplicationCommandRow {
+    pub id: i64,
+    pub application_id: i64,
+    pub name: String,
+    pub description: String,
+    pub options: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ApplicationCommandRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let options_raw: String = row.try_get("options")?;
+        let created_at_raw: String = row.try_get("created_at")?;
+        let updated_at_raw: String = row.try_get("updated_at")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            application_id: row.try_get("application_id")?,
+            name: row.try_get("name")?,
+            description: row.try_get("description")?,
+            options: json_from_db_text(&options_raw)?,
+            created_at: datetime_from_db_text(&created_at_raw)?,
+            updated_at: datetime_from_db_text(&updated_at_raw)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InteractionRow {
+    pub id: i64,
+    pub application_id: i64,
+    pub command_id: i64,
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub user_id: i64,
+    pub token_hash: String,
+    pub responded_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for InteractionRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let responded_at_raw: Option<String> = row.try_get("responded_at")?;
+        let created_at_raw: String = row.try_get("created_at")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            application_id: row.try_get("application_id")?,
+            command_id: row.try_get("command_id")?,
+            guild_id: row.try_get("guild_id")?,
+            channel_id: row.try_get("channel_id")?,
+            user_id: row.try_get("user_id")?,
+            token_hash: row.try_get("token_hash")?,
+            responded_at: responded_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+            created_at: datetime_from_db_text(&created_at_raw)?,
+        })
+    }
+}
+
+/// One command in a bulk overwrite. `id` is only used if no command with
+/// this name exists yet; existing commands keep their ID.
+#[derive(Debug, Clone)]
+pub struct NewApplicationCommand {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub options: serde_json::Value,
+}
+
+pub async fn list_application_commands(
+    pool: &DbPool,
+    application_id: i64,
+) -> Result<Vec<ApplicationCommandRow>, DbError> {
+    let rows = sqlx::query_as::<_, ApplicationCommandRow>(
+        "SELECT id, application_id, name, description, options, created_at, updated_at
+         FROM application_commands WHERE application_id = $1 ORDER BY name",
+    )
+    .bind(application_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn get_application_command(
+    pool: &DbPool,
+    id: i64,
+) -> Result<Option<ApplicationCommandRow>, DbError> {
+    let row = sqlx::query_as::<_, ApplicationCommandRow>(
+        "SELECT id, application_id, name, description, options, created_at, updated_at
+         FROM application_commands WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_application_command_by_name(
+    pool: &DbPool,
+    application_id: i64,
+    name: &str,
+) -> Result<Option<ApplicationCommandRow>, DbError> {
+    let row = sqlx::query_as::<_, ApplicationCommandRow>(
+        "SELECT id, application_id, name, description, options, created_at, updated_at
+         FROM application_commands WHERE application_id = $1 AND name = $2",
+    )
+    .bind(application_id)
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Commands of every bot installed in the space, for the client's command
+/// picker.
+pub async fn list_guild_application_commands(
+    pool: &DbPool,
+    guild_id: i64,
+) -> Result<Vec<ApplicationCommandRow>, DbError> {
+    let rows = sqlx::query_as::<_, ApplicationCommandRow>(
+        "SELECT c.id, c.application_id, c.name, c.description, c.options, c.created_at, c.updated_at
+         FROM application_commands c
+         INNER JOIN bot_guild_installs i ON i.bot_app_id = c.application_id
+         WHERE i.guild_id = $1
+         ORDER BY c.name, c.application_id",
+    )
+    .bind(guild_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Replace the application's command set with `commands` in one transaction.
+/// Commands matched by name are updated in place, missing ones are deleted
+/// (along with their pending interactions) and new ones are inserted.
+pub async fn overwrite_application_commands(
+    pool: &DbPool,
+    application_id: i64,
+    commands: &[NewApplicationCommand],
+) -> Result<Vec<ApplicationCommandRow>, DbError> {
+    let mut tx = pool.begin().await?;
+
+    let existing: Vec<(i64, String)> =
+        sqlx::query_as("SELECT id, name FROM application_commands WHERE application_id = $1")
+            .bind(application_id)
+            .fetch_all(&mut *tx)
+            .await?;
+    for (id, name) in &existing {
+        if !commands.iter().any(|command| &command.name == name) {
+            sqlx::query("DELETE FROM application_commands WHERE id = $1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    let now = datetime_to_db_text(Utc::now());
+    for command in commands {
+        let options = command.options.to_string();
+        if let Some((id, _)) = existing.iter().find(|(_, name)| name == &command.name) {
+            sqlx::query(
+                "UPDATE application_commands
+                 SET description = $2, options = $3, updated_at = $4
+                 WHERE id = $1",
+            )
+            .bind(id)
+            .bind(&command.description)
+            .bind(options)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+        } else {
+            sqlx::query(
+                "INSERT INTO application_commands (id, application_id, name, description, options)
+                 VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(command.id)
+            .bind(application_id)
+            .bind(&command.name)
+            .bind(&command.description)
+            .bind(options)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    let rows = sqlx::query_as::<_, ApplicationCommandRow>(
+        "SELECT id, application_id, name, description, options, created_at, updated_at
+         FROM application_commands WHERE application_id = $1 ORDER BY name",
+    )
+    .bind(application_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(rows)
+}
+
+// --- Interactions ---
+
+#[allow(clippy::too_many_arguments)]
+pub async fn create_interaction(
+    pool: &DbPool,
+    id: i64,
+    application_id: i64,
+    command_id: i64,
+    guild_id: i64,
+    channel_id: i64,
+    user_id: i64,
+    token_hash: &str,
+) -> Result<InteractionRow, DbError> {
+    let row = sqlx::query_as::<_, InteractionRow>(
+        "INSERT INTO interactions (id, application_id, command_id, guild_id, channel_id, user_id, token_hash)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING id, application_id, command_id, guild_id, channel_id, user_id, token_hash, responded_at, created_at",
+    )
+    .bind(id)
+    .bind(application_id)
+    .bind(command_id)
+    .bind(guild_id)
+    .bind(channel_id)
+    .bind(user_id)
+    .bind(token_hash)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_interaction(pool: &DbPool, id: i64) -> Result<Option<InteractionRow>, DbError> {
+    let row = sqlx::query_as::<_, InteractionRow>(
+        "SELECT id, application_id, command_id, guild_id, channel_id, user_id, token_hash, responded_at, created_at
+         FROM interactions WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Claim the interaction's single response. Returns `false` if it was
+/// already answered.
+pub async fn mark_interaction_responded(pool: &DbPool, id: i64) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE interactions SET responded_at = $2
+         WHERE id = $1 AND responded_at IS NULL",
+    )
+    .bind(id)
+    .bind(datetime_to_db_text(Utc::now()))
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn delete_interactions_before(
+    pool: &DbPool,
+    cutoff: DateTime<Utc>,
+) -> Result<u64, DbError> {
+    let result = sqlx::query("DELETE FROM interactions WHERE created_at < $1")
+        .bind(datetime_to_db_text(cutoff))
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
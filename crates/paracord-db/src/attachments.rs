@@ -1,6 +1,7 @@
 use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
 use chrono::{DateTime, Utc};
 use sqlx::Row;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub struct AttachmentRow {
@@ -127,6 +128,38 @@ pub async fn get_message_attachments(
     Ok(rows)
 }
 
+/// Attachment counts for any of `message_ids`, keyed by message id.
+/// Messages without attachments are left out.
+pub async fn count_attachments_for_message_ids(
+    pool: &DbPool,
+    message_ids: &[i64],
+) -> Result<HashMap<i64, i64>, DbError> {
+    const MAX_MESSAGE_IDS: usize = 500;
+    if message_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    if message_ids.len() > MAX_MESSAGE_IDS {
+        return Err(DbError::Sqlx(sqlx::Error::Protocol(
+            "too many message ids in attachment count".to_string(),
+        )));
+    }
+
+    let placeholders: Vec<String> = (1..=message_ids.len()).map(|i| format!("${}", i)).collect();
+    let sql = format!(
+        "SELECT message_id, COUNT(*) AS attachment_count
+         FROM attachments
+         WHERE message_id IN ({})
+         GROUP BY message_id",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query_as::<_, (i64, i64)>(&sql);
+    for message_id in message_ids {
+        query = query.bind(message_id);
+    }
+    let rows = query.fetch_all(pool).await?;
+    Ok(rows.into_iter().collect())
+}
+
 pub async fn attach_to_message(
     pool: &DbPool,
     id: i64,
@@ -239,6 +272,73 @@ pub async fn get_unlinked_attachments_older_than(
     Ok(rows)
 }
 
+/// Just enough of an attachment row to check its file against storage.
+#[derive(Debug, Clone)]
+pub struct AttachmentFileRef {
+    pub id: i64,
+    pub filename: String,
+    pub file_missing: bool,
+}
+
+/// Page through all attachments in id order, starting after `after_id`.
+pub async fn list_attachment_files_after(
+    pool: &DbPool,
+    after_id: i64,
+    limit: i64,
+) -> Result<Vec<AttachmentFileRef>, DbError> {
+    let rows: Vec<(i64, String, Option<String>)> = sqlx::query_as(
+        "SELECT id, filename, file_missing_at
+         FROM attachments
+         WHERE id > $1
+         ORDER BY id ASC
+         LIMIT $2",
+    )
+    .bind(after_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, filename, missing_at)| AttachmentFileRef {
+            id,
+            filename,
+            file_missing: missing_at.is_some(),
+        })
+        .collect())
+}
+
+/// Of `ids`, return the ones that still have an attachment row.
+pub async fn get_existing_attachment_ids(pool: &DbPool, ids: &[i64]) -> Result<Vec<i64>, DbError> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders: Vec<String> = (1..=ids.len()).map(|i| format!("${}", i)).collect();
+    let sql = format!(
+        "SELECT id FROM attachments WHERE id IN ({})",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query_as::<_, (i64,)>(&sql);
+    for id in ids {
+        query = query.bind(id);
+    }
+    let rows = query.fetch_all(pool).await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+/// Flag (`Some`) or clear (`None`) an attachment whose stored file is gone.
+pub async fn set_attachment_file_missing(
+    pool: &DbPool,
+    id: i64,
+    missing_since: Option<DateTime<Utc>>,
+) -> Result<(), DbError> {
+    sqlx::query("UPDATE attachments SET file_missing_at = $2 WHERE id = $1")
+        .bind(id)
+        .bind(missing_since.map(datetime_to_db_text))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -322,4 +422,59 @@ mod tests {
             .expect("attach correct");
         assert!(ok);
     }
+
+    #[tokio::test]
+    async fn reconcile_helpers_page_and_flag_attachments() {
+        let db = setup_db().await;
+        for id in [6001, 6002, 6003] {
+            create_attachment(
+                &db,
+                id,
+                None,
+                "file.bin",
+                None,
+                1,
+                &format!("/api/v1/attachments/{id}"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .expect("create attachment");
+        }
+
+        let first = list_attachment_files_after(&db, 0, 2)
+            .await
+            .expect("first page");
+        assert_eq!(first.iter().map(|a| a.id).collect::<Vec<_>>(), [6001, 6002]);
+        let rest = list_attachment_files_after(&db, 6002, 2)
+            .await
+            .expect("second page");
+        assert_eq!(rest.len(), 1);
+        assert!(!rest[0].file_missing);
+
+        set_attachment_file_missing(&db, 6003, Some(Utc::now()))
+            .await
+            .expect("flag");
+        let flagged = list_attachment_files_after(&db, 6002, 2)
+            .await
+            .expect("flagged page");
+        assert!(flagged[0].file_missing);
+        set_attachment_file_missing(&db, 6003, None)
+            .await
+            .expect("clear");
+        let cleared = list_attachment_files_after(&db, 6002, 2)
+            .await
+            .expect("cleared page");
+        assert!(!cleared[0].file_missing);
+
+        let mut existing = get_existing_attachment_ids(&db, &[6001, 6003, 9999])
+            .await
+            .expect("existing ids");
+        existing.sort_unstable();
+        assert_eq!(existing, [6001, 6003]);
+    }
 }
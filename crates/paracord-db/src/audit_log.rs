@@ -1,5 +1,6 @@
 use crate::{datetime_from_db_text, datetime_to_db_text, json_from_db_text, DbError, DbPool};
 use chrono::{DateTime, Utc};
+use paracord_models::audit_log::AuditAction;
 use sqlx::Row;
 
 #[derive(Debug, Clone)]
@@ -36,6 +37,10 @@ impl AuditLogEntryRow {
     pub fn guild_id(&self) -> i64 {
         self.space_id
     }
+
+    pub fn action(&self) -> AuditAction {
+        AuditAction::from(self.action_type)
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -70,6 +75,31 @@ pub async fn create_entry(
     Ok(row)
 }
 
+/// Typed wrapper around [`create_entry`].
+#[allow(clippy::too_many_arguments)]
+pub async fn create_action_entry(
+    pool: &DbPool,
+    id: i64,
+    space_id: i64,
+    user_id: i64,
+    action: AuditAction,
+    target_id: Option<i64>,
+    reason: Option<&str>,
+    changes: Option<&serde_json::Value>,
+) -> Result<AuditLogEntryRow, DbError> {
+    create_entry(
+        pool,
+        id,
+        space_id,
+        user_id,
+        action.into(),
+        target_id,
+        reason,
+        changes,
+    )
+    .await
+}
+
 /// Get entries for a space. Kept as get_guild_entries for API compat.
 pub async fn get_guild_entries(
     pool: &DbPool,
@@ -217,3 +247,53 @@ pub async fn purge_entries_older_than(
     .await?;
     Ok(result.rows_affected())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        crate::users::create_user(&pool, 1, "owner", 1, "owner@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 10, "space", 1, None)
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_actions_round_trip_and_unknown_codes_still_read() {
+        let pool = test_pool().await;
+        let entry = create_action_entry(
+            &pool,
+            100,
+            10,
+            1,
+            AuditAction::MemberKick,
+            Some(2),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(entry.action_type, 21);
+        assert_eq!(entry.action(), AuditAction::MemberKick);
+
+        // A code written by another build that this one does not know.
+        create_entry(&pool, 101, 10, 1, 99, None, None, None)
+            .await
+            .unwrap();
+
+        let entries = get_space_entries(&pool, 10, None, None, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action(), AuditAction::Unknown(99));
+        assert_eq!(entries[0].action().name(), "unknown");
+        assert_eq!(i16::from(entries[0].action()), 99);
+        assert_eq!(entries[1].action().name(), "member_kick");
+    }
+}
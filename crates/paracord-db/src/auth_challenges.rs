@@ -0,0 +1,153 @@
+use crate::{DbError, DbPool};
+
+/// Record a freshly issued challenge nonce, valid until `expires_at` (unix
+/// seconds).
+pub async fn create_auth_challenge(
+    pool: &DbPool,
+    nonce: &str,
+    issued_at: i64,
+    expires_at: i64,
+) -> Result<(), DbError> {
+    sqlx::query(
+        "INSERT INTO auth_challenges (nonce, issued_at, expires_at)
+         VALUES ($1, $2, $3)",
+    )
+    .bind(nonce)
+    .bind(issued_at)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Evict the oldest unconsumed challenges so at most `max_outstanding`
+/// remain, bounding the table between sweeps when challenges are requested
+/// faster than they expire. Returns how many were evicted.
+pub async fn cap_outstanding_auth_challenges(
+    pool: &DbPool,
+    max_outstanding: i64,
+) -> Result<u64, DbError> {
+    let (outstanding,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM auth_challenges WHERE consumed_at IS NULL")
+            .fetch_one(pool)
+            .await?;
+    let overflow = outstanding - max_outstanding;
+    if overflow <= 0 {
+        return Ok(0);
+    }
+    let result = sqlx::query(
+        "DELETE FROM auth_challenges WHERE nonce IN (
+             SELECT nonce FROM auth_challenges
+             WHERE consumed_at IS NULL
+             ORDER BY issued_at, nonce
+             LIMIT $1
+         )",
+    )
+    .bind(overflow)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Atomically mark a challenge consumed. Returns false when the nonce is
+/// unknown, was issued with a different timestamp, has expired or was
+/// already consumed.
+pub async fn consume_auth_challenge(
+    pool: &DbPool,
+    nonce: &str,
+    issued_at: i64,
+    now: i64,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE auth_challenges SET consumed_at = $3
+         WHERE nonce = $1 AND issued_at = $2 AND expires_at >= $3 AND consumed_at IS NULL",
+    )
+    .bind(nonce)
+    .bind(issued_at)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete challenges that expired before `now`, consumed or not.
+pub async fn purge_expired_auth_challenges(pool: &DbPool, now: i64) -> Result<u64, DbError> {
+    let result = sqlx::query("DELETE FROM auth_challenges WHERE expires_at < $1")
+        .bind(now)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_challenges_are_consumed_once_and_purged_after_expiry() {
+        let pool = test_pool().await;
+        create_auth_challenge(&pool, "fresh", 1_000, 1_120)
+            .await
+            .unwrap();
+        create_auth_challenge(&pool, "stale", 800, 920)
+            .await
+            .unwrap();
+
+        assert!(!consume_auth_challenge(&pool, "fresh", 999, 1_010)
+            .await
+            .unwrap());
+        assert!(consume_auth_challenge(&pool, "fresh", 1_000, 1_010)
+            .await
+            .unwrap());
+        assert!(!consume_auth_challenge(&pool, "fresh", 1_000, 1_011)
+            .await
+            .unwrap());
+        assert!(!consume_auth_challenge(&pool, "stale", 800, 1_010)
+            .await
+            .unwrap());
+        assert!(!consume_auth_challenge(&pool, "unknown", 1_000, 1_010)
+            .await
+            .unwrap());
+
+        assert_eq!(
+            purge_expired_auth_challenges(&pool, 1_010).await.unwrap(),
+            1
+        );
+        assert_eq!(
+            purge_expired_auth_challenges(&pool, 1_200).await.unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cap_outstanding_auth_challenges_evicts_oldest_unconsumed() {
+        let pool = test_pool().await;
+        for (nonce, issued_at) in [("a", 1_000), ("b", 1_001), ("c", 1_002), ("d", 1_003)] {
+            create_auth_challenge(&pool, nonce, issued_at, issued_at + 120)
+                .await
+                .unwrap();
+        }
+        // Consumed challenges don't count towards the cap.
+        assert!(consume_auth_challenge(&pool, "a", 1_000, 1_010)
+            .await
+            .unwrap());
+
+        assert_eq!(cap_outstanding_auth_challenges(&pool, 3).await.unwrap(), 0);
+        assert_eq!(cap_outstanding_auth_challenges(&pool, 2).await.unwrap(), 1);
+        assert!(!consume_auth_challenge(&pool, "b", 1_001, 1_010)
+            .await
+            .unwrap());
+        assert!(consume_auth_challenge(&pool, "c", 1_002, 1_010)
+            .await
+            .unwrap());
+        assert!(consume_auth_challenge(&pool, "d", 1_003, 1_010)
+            .await
+            .unwrap());
+    }
+}
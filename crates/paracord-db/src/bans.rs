@@ -1,4 +1,7 @@
-use crate::{datetime_from_db_text, DbError, DbPool};
+use crate::{
+    datetime_from_db_text, datetime_to_db_text, prefix_range_bounds, prefix_range_condition,
+    DbError, DbPool,
+};
 use chrono::{DateTime, Utc};
 use sqlx::Row;
 
@@ -9,60 +12,149 @@ pub struct BanRow {
     pub reason: Option<String>,
     pub banned_by: Option<i64>,
     pub created_at: DateTime<Utc>,
+    /// `None` for a permanent ban.
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for BanRow {
     fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
         let created_at_raw: String = row.try_get("created_at")?;
+        let expires_at_raw: Option<String> = row.try_get("expires_at")?;
         Ok(Self {
             user_id: row.try_get("user_id")?,
             guild_id: row.try_get("guild_id")?,
             reason: row.try_get("reason")?,
             banned_by: row.try_get("banned_by")?,
             created_at: datetime_from_db_text(&created_at_raw)?,
+            expires_at: expires_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
         })
     }
 }
 
+const BAN_COLUMNS: &str = "user_id, guild_id, reason, banned_by, created_at, expires_at";
+
+/// Matches bans that have not yet run out at the time bound to `now_param`.
+fn active_ban_condition(now_param: usize) -> String {
+    format!("(expires_at IS NULL OR expires_at > ${now_param})")
+}
+
+fn upsert_ban_sql() -> String {
+    format!(
+        "INSERT INTO bans (user_id, guild_id, reason, banned_by, expires_at)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (user_id, guild_id)
+         DO UPDATE SET reason = $3, banned_by = $4, expires_at = $5, created_at = $6
+         RETURNING {BAN_COLUMNS}"
+    )
+}
+
+/// Create or replace a ban. A ban with `expires_at` is lifted automatically
+/// once that instant passes; re-banning replaces the previous expiry.
 pub async fn create_ban(
     pool: &DbPool,
     user_id: i64,
     guild_id: i64,
     reason: Option<&str>,
     banned_by: i64,
+    expires_at: Option<DateTime<Utc>>,
 ) -> Result<BanRow, DbError> {
-    let row = sqlx::query_as::<_, BanRow>(
-        "INSERT INTO bans (user_id, guild_id, reason, banned_by)
-         VALUES ($1, $2, $3, $4)
-         ON CONFLICT (user_id, guild_id)
-         DO UPDATE SET reason = $3, banned_by = $4, created_at = datetime('now')
-         RETURNING user_id, guild_id, reason, banned_by, created_at",
+    let sql = upsert_ban_sql();
+    let row = sqlx::query_as::<_, BanRow>(&sql)
+        .bind(user_id)
+        .bind(guild_id)
+        .bind(reason)
+        .bind(banned_by)
+        .bind(expires_at.map(datetime_to_db_text))
+        .bind(datetime_to_db_text(Utc::now()))
+        .fetch_one(pool)
+        .await?;
+    Ok(row)
+}
+
+/// Ban a user and, in the same transaction, delete everything they posted in
+/// the space's channels since `delete_messages_since`. Returns the ban and
+/// the `(channel_id, message_id)` pairs that were deleted.
+///
+/// Search indexes follow through the message delete triggers.
+pub async fn create_ban_and_delete_messages(
+    pool: &DbPool,
+    user_id: i64,
+    guild_id: i64,
+    reason: Option<&str>,
+    banned_by: i64,
+    expires_at: Option<DateTime<Utc>>,
+    delete_messages_since: DateTime<Utc>,
+) -> Result<(BanRow, Vec<(i64, i64)>), DbError> {
+    let mut tx = pool.begin().await?;
+
+    let deleted: Vec<(i64, i64)> = sqlx::query_as(
+        "SELECT channel_id, id
+         FROM messages
+         WHERE author_id = $1
+           AND created_at >= $3
+           AND channel_id IN (SELECT id FROM channels WHERE space_id = $2)
+         ORDER BY channel_id, id",
     )
     .bind(user_id)
     .bind(guild_id)
-    .bind(reason)
-    .bind(banned_by)
-    .fetch_one(pool)
+    .bind(datetime_to_db_text(delete_messages_since))
+    .fetch_all(&mut *tx)
     .await?;
-    Ok(row)
+    sqlx::query(
+        "DELETE FROM messages
+         WHERE author_id = $1
+           AND created_at >= $3
+           AND channel_id IN (SELECT id FROM channels WHERE space_id = $2)",
+    )
+    .bind(user_id)
+    .bind(guild_id)
+    .bind(datetime_to_db_text(delete_messages_since))
+    .execute(&mut *tx)
+    .await?;
+
+    let sql = upsert_ban_sql();
+    let ban = sqlx::query_as::<_, BanRow>(&sql)
+        .bind(user_id)
+        .bind(guild_id)
+        .bind(reason)
+        .bind(banned_by)
+        .bind(expires_at.map(datetime_to_db_text))
+        .bind(datetime_to_db_text(Utc::now()))
+        .fetch_one(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok((ban, deleted))
 }
 
+/// The user's ban in this space, if one is in force. Expired bans are
+/// ignored even before the expiry sweeper has removed them.
 pub async fn get_ban(
     pool: &DbPool,
     user_id: i64,
     guild_id: i64,
 ) -> Result<Option<BanRow>, DbError> {
-    let row = sqlx::query_as::<_, BanRow>(
-        "SELECT user_id, guild_id, reason, banned_by, created_at
-         FROM bans WHERE user_id = $1 AND guild_id = $2",
-    )
-    .bind(user_id)
-    .bind(guild_id)
-    .fetch_optional(pool)
-    .await?;
+    let sql = format!(
+        "SELECT {BAN_COLUMNS}
+         FROM bans WHERE user_id = $1 AND guild_id = $2 AND {}",
+        active_ban_condition(3)
+    );
+    let row = sqlx::query_as::<_, BanRow>(&sql)
+        .bind(user_id)
+        .bind(guild_id)
+        .bind(datetime_to_db_text(Utc::now()))
+        .fetch_optional(pool)
+        .await?;
     Ok(row)
 }
 
+pub async fn is_banned(pool: &DbPool, user_id: i64, guild_id: i64) -> Result<bool, DbError> {
+    Ok(get_ban(pool, user_id, guild_id).await?.is_some())
+}
+
 pub async fn delete_ban(pool: &DbPool, user_id: i64, guild_id: i64) -> Result<(), DbError> {
     sqlx::query("DELETE FROM bans WHERE user_id = $1 AND guild_id = $2")
         .bind(user_id)
@@ -73,26 +165,162 @@ pub async fn delete_ban(pool: &DbPool, user_id: i64, guild_id: i64) -> Result<()
 }
 
 pub async fn get_guild_bans(pool: &DbPool, guild_id: i64) -> Result<Vec<BanRow>, DbError> {
-    let rows = sqlx::query_as::<_, BanRow>(
-        "SELECT user_id, guild_id, reason, banned_by, created_at
+    let sql = format!(
+        "SELECT {BAN_COLUMNS}
          FROM bans
-         WHERE guild_id = $1
+         WHERE guild_id = $1 AND {}
          ORDER BY created_at DESC",
-    )
-    .bind(guild_id)
-    .fetch_all(pool)
-    .await?;
+        active_ban_condition(2)
+    );
+    let rows = sqlx::query_as::<_, BanRow>(&sql)
+        .bind(guild_id)
+        .bind(datetime_to_db_text(Utc::now()))
+        .fetch_all(pool)
+        .await?;
     Ok(rows)
 }
 
+/// Optional narrowing applied to a ban list.
+#[derive(Debug, Clone, Default)]
+pub struct BanListFilter {
+    /// Only bans placed by this moderator.
+    pub banned_by: Option<i64>,
+    /// Case-insensitive prefix of the ban reason.
+    pub reason_query: Option<String>,
+}
+
+/// A ban joined with the banned user's current profile.
+#[derive(Debug, Clone)]
+pub struct BanListEntry {
+    pub ban: BanRow,
+    pub username: String,
+    pub discriminator: i16,
+    pub avatar_hash: Option<String>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for BanListEntry {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            ban: BanRow::from_row(row)?,
+            username: row.try_get("username")?,
+            discriminator: row.try_get("discriminator")?,
+            avatar_hash: row.try_get("avatar_hash")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BanPage {
+    pub bans: Vec<BanListEntry>,
+    /// Whether another page follows this one.
+    pub has_more: bool,
+}
+
+/// Active bans in a space matching `filter`, keyset-paginated by user id.
+pub async fn list_guild_bans(
+    pool: &DbPool,
+    guild_id: i64,
+    filter: &BanListFilter,
+    limit: i64,
+    after: Option<i64>,
+) -> Result<BanPage, DbError> {
+    // $1 space, $2 now, $3 after, $4 limit; filter parameters follow.
+    let mut conditions = String::new();
+    let mut next_param = 5;
+    if filter.banned_by.is_some() {
+        conditions.push_str(&format!(" AND b.banned_by = ${next_param}"));
+        next_param += 1;
+    }
+    if filter.reason_query.is_some() {
+        conditions.push_str(&format!(
+            " AND {}",
+            prefix_range_condition("b.reason", next_param)
+        ));
+    }
+    let sql = format!(
+        "SELECT b.user_id, b.guild_id, b.reason, b.banned_by, b.created_at, b.expires_at,
+                u.username, u.discriminator, u.avatar_hash
+         FROM bans b
+         INNER JOIN users u ON u.id = b.user_id
+         WHERE b.guild_id = $1
+           AND (b.expires_at IS NULL OR b.expires_at > $2)
+           AND b.user_id > $3{conditions}
+         ORDER BY b.user_id
+         LIMIT $4"
+    );
+
+    let limit = limit.max(1);
+    let mut query = sqlx::query_as::<_, BanListEntry>(&sql)
+        .bind(guild_id)
+        .bind(datetime_to_db_text(Utc::now()))
+        .bind(after.unwrap_or(0))
+        .bind(limit + 1);
+    if let Some(banned_by) = filter.banned_by {
+        query = query.bind(banned_by);
+    }
+    if let Some(reason_query) = filter.reason_query.as_deref() {
+        let (low, high) = prefix_range_bounds(reason_query);
+        query = query.bind(low).bind(high);
+    }
+    let mut bans = query.fetch_all(pool).await?;
+    let has_more = bans.len() as i64 > limit;
+    bans.truncate(limit as usize);
+    Ok(BanPage { bans, has_more })
+}
+
 pub async fn get_all_bans(pool: &DbPool) -> Result<Vec<BanRow>, DbError> {
-    let rows = sqlx::query_as::<_, BanRow>(
-        "SELECT user_id, guild_id, reason, banned_by, created_at
-         FROM bans ORDER BY created_at DESC",
+    let sql = format!(
+        "SELECT {BAN_COLUMNS}
+         FROM bans WHERE {} ORDER BY created_at DESC",
+        active_ban_condition(1)
+    );
+    let rows = sqlx::query_as::<_, BanRow>(&sql)
+        .bind(datetime_to_db_text(Utc::now()))
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/// Temporary bans whose expiry is at or before `now`, oldest first.
+pub async fn get_expired_bans(
+    pool: &DbPool,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<BanRow>, DbError> {
+    let sql = format!(
+        "SELECT {BAN_COLUMNS}
+         FROM bans
+         WHERE expires_at IS NOT NULL AND expires_at <= $1
+         ORDER BY expires_at ASC
+         LIMIT $2"
+    );
+    let rows = sqlx::query_as::<_, BanRow>(&sql)
+        .bind(datetime_to_db_text(now))
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/// Lift a ban only if it is still expired at `now`. Returns `false` when the
+/// ban was already removed or replaced by a newer ban in the meantime.
+pub async fn delete_expired_ban(
+    pool: &DbPool,
+    user_id: i64,
+    guild_id: i64,
+    now: DateTime<Utc>,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "DELETE FROM bans
+         WHERE user_id = $1 AND guild_id = $2
+           AND expires_at IS NOT NULL AND expires_at <= $3",
     )
-    .fetch_all(pool)
+    .bind(user_id)
+    .bind(guild_id)
+    .bind(datetime_to_db_text(now))
+    .execute(pool)
     .await?;
-    Ok(rows)
+    Ok(result.rows_affected() > 0)
 }
 
 #[cfg(test)]
@@ -125,7 +353,7 @@ mod tests {
     async fn test_create_ban() {
         let pool = test_pool().await;
         let (owner_id, target_id, guild_id) = setup_guild(&pool).await;
-        let ban = create_ban(&pool, target_id, guild_id, Some("Spamming"), owner_id)
+        let ban = create_ban(&pool, target_id, guild_id, Some("Spamming"), owner_id, None)
             .await
             .unwrap();
         assert_eq!(ban.user_id, target_id);
@@ -138,7 +366,7 @@ mod tests {
     async fn test_create_ban_without_reason() {
         let pool = test_pool().await;
         let (owner_id, target_id, guild_id) = setup_guild(&pool).await;
-        let ban = create_ban(&pool, target_id, guild_id, None, owner_id)
+        let ban = create_ban(&pool, target_id, guild_id, None, owner_id, None)
             .await
             .unwrap();
         assert!(ban.reason.is_none());
@@ -148,10 +376,10 @@ mod tests {
     async fn test_create_ban_upserts_on_conflict() {
         let pool = test_pool().await;
         let (owner_id, target_id, guild_id) = setup_guild(&pool).await;
-        create_ban(&pool, target_id, guild_id, Some("first"), owner_id)
+        create_ban(&pool, target_id, guild_id, Some("first"), owner_id, None)
             .await
             .unwrap();
-        let ban = create_ban(&pool, target_id, guild_id, Some("updated"), owner_id)
+        let ban = create_ban(&pool, target_id, guild_id, Some("updated"), owner_id, None)
             .await
             .unwrap();
         assert_eq!(ban.reason.as_deref(), Some("updated"));
@@ -164,7 +392,7 @@ mod tests {
     async fn test_get_ban() {
         let pool = test_pool().await;
         let (owner_id, target_id, guild_id) = setup_guild(&pool).await;
-        create_ban(&pool, target_id, guild_id, Some("Bad"), owner_id)
+        create_ban(&pool, target_id, guild_id, Some("Bad"), owner_id, None)
             .await
             .unwrap();
         let ban = get_ban(&pool, target_id, guild_id).await.unwrap().unwrap();
@@ -182,7 +410,7 @@ mod tests {
     async fn test_delete_ban() {
         let pool = test_pool().await;
         let (owner_id, target_id, guild_id) = setup_guild(&pool).await;
-        create_ban(&pool, target_id, guild_id, None, owner_id)
+        create_ban(&pool, target_id, guild_id, None, owner_id, None)
             .await
             .unwrap();
         delete_ban(&pool, target_id, guild_id).await.unwrap();
@@ -198,10 +426,10 @@ mod tests {
         crate::users::create_user(&pool, 3, "user3", 1, "u3@example.com", "hash")
             .await
             .unwrap();
-        create_ban(&pool, 2, guild_id, Some("reason1"), owner_id)
+        create_ban(&pool, 2, guild_id, Some("reason1"), owner_id, None)
             .await
             .unwrap();
-        create_ban(&pool, 3, guild_id, Some("reason2"), owner_id)
+        create_ban(&pool, 3, guild_id, Some("reason2"), owner_id, None)
             .await
             .unwrap();
         let bans = get_guild_bans(&pool, guild_id).await.unwrap();
@@ -214,4 +442,193 @@ mod tests {
         let bans = get_guild_bans(&pool, 999).await.unwrap();
         assert!(bans.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_expired_ban_is_not_enforced_and_gets_swept() {
+        let pool = test_pool().await;
+        let (owner_id, target_id, guild_id) = setup_guild(&pool).await;
+        let now = Utc::now();
+        create_ban(
+            &pool,
+            target_id,
+            guild_id,
+            Some("cool off"),
+            owner_id,
+            Some(now - chrono::Duration::minutes(1)),
+        )
+        .await
+        .unwrap();
+        assert!(!is_banned(&pool, target_id, guild_id).await.unwrap());
+        assert!(get_guild_bans(&pool, guild_id).await.unwrap().is_empty());
+
+        let expired = get_expired_bans(&pool, now, 10).await.unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].user_id, target_id);
+        assert!(delete_expired_ban(&pool, target_id, guild_id, now)
+            .await
+            .unwrap());
+        assert!(!delete_expired_ban(&pool, target_id, guild_id, now)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_rebanning_replaces_expiry() {
+        let pool = test_pool().await;
+        let (owner_id, target_id, guild_id) = setup_guild(&pool).await;
+        let now = Utc::now();
+        let ban = create_ban(
+            &pool,
+            target_id,
+            guild_id,
+            None,
+            owner_id,
+            Some(now + chrono::Duration::days(7)),
+        )
+        .await
+        .unwrap();
+        assert!(ban.expires_at.is_some());
+        assert!(is_banned(&pool, target_id, guild_id).await.unwrap());
+        assert!(get_expired_bans(&pool, now, 10).await.unwrap().is_empty());
+
+        // Made permanent: the sweeper must leave it alone.
+        create_ban(&pool, target_id, guild_id, None, owner_id, None)
+            .await
+            .unwrap();
+        let later = now + chrono::Duration::days(8);
+        assert!(get_expired_bans(&pool, later, 10).await.unwrap().is_empty());
+        assert!(!delete_expired_ban(&pool, target_id, guild_id, later)
+            .await
+            .unwrap());
+        assert!(is_banned(&pool, target_id, guild_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_ban_deletes_recent_messages_in_space_only() {
+        let pool = test_pool().await;
+        let (owner_id, target_id, guild_id) = setup_guild(&pool).await;
+        crate::guilds::create_guild(&pool, 200, "Other Guild", owner_id, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 10, guild_id, "general", 0, 0, None, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 11, guild_id, "random", 0, 1, None, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 20, 200, "elsewhere", 0, 0, None, None)
+            .await
+            .unwrap();
+        for (id, channel_id, author_id) in [
+            (1000, 10, target_id),
+            (1001, 11, target_id),
+            (1002, 20, target_id),
+            (1003, 10, owner_id),
+        ] {
+            crate::messages::create_message(&pool, id, channel_id, author_id, "hi", 0, None)
+                .await
+                .unwrap();
+        }
+
+        let since = Utc::now() - chrono::Duration::days(1);
+        let (ban, deleted) =
+            create_ban_and_delete_messages(&pool, target_id, guild_id, None, owner_id, None, since)
+                .await
+                .unwrap();
+        assert_eq!(ban.user_id, target_id);
+        assert_eq!(deleted, vec![(10, 1000), (11, 1001)]);
+        assert!(crate::messages::get_message(&pool, 1000)
+            .await
+            .unwrap()
+            .is_none());
+        // Other spaces and other authors are untouched.
+        assert!(crate::messages::get_message(&pool, 1002)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(crate::messages::get_message(&pool, 1003)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(is_banned(&pool, target_id, guild_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_list_guild_bans_pages_and_filters() {
+        let pool = test_pool().await;
+        let (owner_id, _target_id, guild_id) = setup_guild(&pool).await;
+        crate::users::create_user(&pool, 3, "mod", 1, "mod@example.com", "hash")
+            .await
+            .unwrap();
+        for id in 4..=6 {
+            crate::users::create_user(
+                &pool,
+                id,
+                &format!("user{}", id),
+                1,
+                &format!("u{}@example.com", id),
+                "hash",
+            )
+            .await
+            .unwrap();
+        }
+        create_ban(&pool, 2, guild_id, Some("Spam links"), owner_id, None)
+            .await
+            .unwrap();
+        create_ban(&pool, 4, guild_id, Some("100% spam"), 3, None)
+            .await
+            .unwrap();
+        create_ban(&pool, 5, guild_id, Some("harassment"), 3, None)
+            .await
+            .unwrap();
+        let past = Utc::now() - chrono::Duration::minutes(1);
+        create_ban(&pool, 6, guild_id, Some("spam"), owner_id, Some(past))
+            .await
+            .unwrap();
+
+        let all = BanListFilter::default();
+        let page = list_guild_bans(&pool, guild_id, &all, 2, None)
+            .await
+            .unwrap();
+        assert!(page.has_more);
+        assert_eq!(
+            page.bans.iter().map(|b| b.ban.user_id).collect::<Vec<_>>(),
+            [2, 4]
+        );
+        assert_eq!(page.bans[0].username, "target");
+        // Expired bans are not listed.
+        let page = list_guild_bans(&pool, guild_id, &all, 2, Some(4))
+            .await
+            .unwrap();
+        assert!(!page.has_more);
+        assert_eq!(
+            page.bans.iter().map(|b| b.ban.user_id).collect::<Vec<_>>(),
+            [5]
+        );
+
+        let by_mod = BanListFilter {
+            banned_by: Some(3),
+            reason_query: Some("100%".into()),
+        };
+        let page = list_guild_bans(&pool, guild_id, &by_mod, 50, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            page.bans.iter().map(|b| b.ban.user_id).collect::<Vec<_>>(),
+            [4]
+        );
+
+        // Reasons match from their start only.
+        let by_reason = BanListFilter {
+            reason_query: Some("SPAM".into()),
+            ..Default::default()
+        };
+        let page = list_guild_bans(&pool, guild_id, &by_reason, 50, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            page.bans.iter().map(|b| b.ban.user_id).collect::<Vec<_>>(),
+            [2]
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{datetime_from_db_text, DbError, DbPool};
+use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
 use chrono::{DateTime, Utc};
 use sha2::{Digest, Sha256};
 use sqlx::Row;
@@ -13,6 +13,10 @@ pub struct BotApplicationRow {
     pub token_hash: String,
     pub redirect_uri: Option<String>,
     pub permissions: i64,
+    /// When set, interactions are POSTed here instead of over the gateway.
+    pub interactions_endpoint_url: Option<String>,
+    /// Hex Ed25519 seed interactions to the endpoint are signed with.
+    pub interactions_signing_key: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -39,6 +43,8 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for BotApplicationRow {
             token_hash: row.try_get("token_hash")?,
             redirect_uri: row.try_get("redirect_uri")?,
             permissions: row.try_get("permissions")?,
+            interactions_endpoint_url: row.try_get("interactions_endpoint_url")?,
+            interactions_signing_key: row.try_get("interactions_signing_key")?,
             created_at: datetime_from_db_text(&created_at_raw)?,
             updated_at: datetime_from_db_text(&updated_at_raw)?,
         })
@@ -62,11 +68,7 @@ pub fn hash_token(token: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(token.as_bytes());
     let digest = hasher.finalize();
-    let mut out = String::with_capacity(digest.len() * 2);
-    for b in digest {
-        out.push_str(&format!("{:02x}", b));
-    }
-    out
+    paracord_util::hex::hex_encode(&digest)
 }
 
 pub async fn create_bot_application(
@@ -83,7 +85,7 @@ pub async fn create_bot_application(
     let row = sqlx::query_as::<_, BotApplicationRow>(
         "INSERT INTO bot_applications (id, name, description, owner_id, bot_user_id, token_hash, redirect_uri, permissions)
          VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-         RETURNING id, name, description, owner_id, bot_user_id, token_hash, redirect_uri, permissions, created_at, updated_at",
+         RETURNING id, name, description, owner_id, bot_user_id, token_hash, redirect_uri, permissions, interactions_endpoint_url, interactions_signing_key, created_at, updated_at",
     )
     .bind(id)
     .bind(name)
@@ -103,7 +105,7 @@ pub async fn get_bot_application(
     id: i64,
 ) -> Result<Option<BotApplicationRow>, DbError> {
     let row = sqlx::query_as::<_, BotApplicationRow>(
-        "SELECT id, name, description, owner_id, bot_user_id, token_hash, redirect_uri, permissions, created_at, updated_at
+        "SELECT id, name, description, owner_id, bot_user_id, token_hash, redirect_uri, permissions, interactions_endpoint_url, interactions_signing_key, created_at, updated_at
          FROM bot_applications WHERE id = $1",
     )
     .bind(id)
@@ -117,7 +119,7 @@ pub async fn get_bot_application_by_token_hash(
     token_hash: &str,
 ) -> Result<Option<BotApplicationRow>, DbError> {
     let row = sqlx::query_as::<_, BotApplicationRow>(
-        "SELECT id, name, description, owner_id, bot_user_id, token_hash, redirect_uri, permissions, created_at, updated_at
+        "SELECT id, name, description, owner_id, bot_user_id, token_hash, redirect_uri, permissions, interactions_endpoint_url, interactions_signing_key, created_at, updated_at
          FROM bot_applications WHERE token_hash = $1",
     )
     .bind(token_hash)
@@ -131,7 +133,7 @@ pub async fn list_user_bot_applications(
     owner_id: i64,
 ) -> Result<Vec<BotApplicationRow>, DbError> {
     let rows = sqlx::query_as::<_, BotApplicationRow>(
-        "SELECT id, name, description, owner_id, bot_user_id, token_hash, redirect_uri, permissions, created_at, updated_at
+        "SELECT id, name, description, owner_id, bot_user_id, token_hash, redirect_uri, permissions, interactions_endpoint_url, interactions_signing_key, created_at, updated_at
          FROM bot_applications WHERE owner_id = $1 ORDER BY created_at",
     )
     .bind(owner_id)
@@ -146,37 +148,67 @@ pub async fn update_bot_application(
     name: Option<&str>,
     description: Option<&str>,
     redirect_uri: Option<&str>,
+    interactions_endpoint_url: Option<&str>,
+    permissions: Option<i64>,
 ) -> Result<BotApplicationRow, DbError> {
+    // An empty `interactions_endpoint_url` clears it (back to gateway delivery).
     let row = sqlx::query_as::<_, BotApplicationRow>(
         "UPDATE bot_applications SET
             name = COALESCE($2, name),
             description = COALESCE($3, description),
             redirect_uri = COALESCE($4, redirect_uri),
-            updated_at = datetime('now')
+            interactions_endpoint_url = NULLIF(COALESCE($5, interactions_endpoint_url), ''),
+            permissions = COALESCE($6, permissions),
+            updated_at = $7
          WHERE id = $1
-         RETURNING id, name, description, owner_id, bot_user_id, token_hash, redirect_uri, permissions, created_at, updated_at",
+         RETURNING id, name, description, owner_id, bot_user_id, token_hash, redirect_uri, permissions, interactions_endpoint_url, interactions_signing_key, created_at, updated_at",
     )
     .bind(id)
     .bind(name)
     .bind(description)
     .bind(redirect_uri)
+    .bind(interactions_endpoint_url)
+    .bind(permissions)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_one(pool)
     .await?;
     Ok(row)
 }
 
+/// Store `candidate` as the application's interaction signing key unless it
+/// already has one, and return the key in effect.
+pub async fn ensure_interactions_signing_key(
+    pool: &DbPool,
+    id: i64,
+    candidate: &str,
+) -> Result<String, DbError> {
+    let row = sqlx::query(
+        "UPDATE bot_applications
+         SET interactions_signing_key = COALESCE(interactions_signing_key, $2)
+         WHERE id = $1
+         RETURNING interactions_signing_key",
+    )
+    .bind(id)
+    .bind(candidate)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(DbError::NotFound)?;
+    Ok(row.get("interactions_signing_key"))
+}
+
 pub async fn regenerate_bot_token(
     pool: &DbPool,
     id: i64,
     new_token_hash: &str,
 ) -> Result<BotApplicationRow, DbError> {
     let row = sqlx::query_as::<_, BotApplicationRow>(
-        "UPDATE bot_applications SET token_hash = $2, updated_at = datetime('now')
+        "UPDATE bot_applications SET token_hash = $2, updated_at = $3
          WHERE id = $1
-         RETURNING id, name, description, owner_id, bot_user_id, token_hash, redirect_uri, permissions, created_at, updated_at",
+         RETURNING id, name, description, owner_id, bot_user_id, token_hash, redirect_uri, permissions, interactions_endpoint_url, interactions_signing_key, created_at, updated_at",
     )
     .bind(id)
     .bind(new_token_hash)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -269,3 +301,64 @@ pub async fn is_bot_in_guild(
     .await?;
     Ok(count.0 > 0)
 }
+
+/// Permission bits a bot user is limited to in a guild: its application's
+/// permissions intersected with those granted when it was installed there
+/// (nothing if it isn't installed). `None` if `user_id` is not a bot.
+pub async fn get_bot_permission_scope(
+    pool: &DbPool,
+    user_id: i64,
+    guild_id: i64,
+) -> Result<Option<i64>, DbError> {
+    let row: Option<(i64, Option<i64>)> = sqlx::query_as(
+        "SELECT a.permissions, i.permissions
+         FROM bot_applications a
+         LEFT JOIN bot_guild_installs i ON i.bot_app_id = a.id AND i.guild_id = $2
+         WHERE a.bot_user_id = $1",
+    )
+    .bind(user_id)
+    .bind(guild_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|(app_permissions, install_permissions)| {
+        app_permissions & install_permissions.unwrap_or(0)
+    }))
+}
+
+/// [`get_bot_permission_scope`] for several guilds at once, keyed by guild
+/// id. `None` if `user_id` is not a bot.
+pub async fn get_bot_permission_scopes(
+    pool: &DbPool,
+    user_id: i64,
+    guild_ids: &[i64],
+) -> Result<Option<std::collections::HashMap<i64, i64>>, DbError> {
+    let app: Option<(i64, i64)> =
+        sqlx::query_as("SELECT id, permissions FROM bot_applications WHERE bot_user_id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+    let Some((app_id, app_permissions)) = app else {
+        return Ok(None);
+    };
+    let mut scopes: std::collections::HashMap<i64, i64> =
+        guild_ids.iter().map(|guild_id| (*guild_id, 0)).collect();
+    if guild_ids.is_empty() {
+        return Ok(Some(scopes));
+    }
+    let placeholders: Vec<String> = (2..=guild_ids.len() + 1)
+        .map(|i| format!("${}", i))
+        .collect();
+    let sql = format!(
+        "SELECT guild_id, permissions FROM bot_guild_installs
+         WHERE bot_app_id = $1 AND guild_id IN ({})",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query_as::<_, (i64, i64)>(&sql).bind(app_id);
+    for guild_id in guild_ids {
+        query = query.bind(*guild_id);
+    }
+    for (guild_id, install_permissions) in query.fetch_all(pool).await? {
+        scopes.insert(guild_id, app_permissions & install_permissions);
+    }
+    Ok(Some(scopes))
+}
@@ -1,21 +1,37 @@
-use crate::{bool_from_any_row, datetime_from_db_text, DbError, DbPool};
+use crate::{bool_from_any_row, datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
 use chrono::{DateTime, Utc};
+use paracord_models::channel::{ChannelType, DEFAULT_VOICE_BITRATE};
 use sqlx::Row;
 use std::collections::BTreeSet;
 
-fn thread_is_archived(thread_metadata: Option<&str>) -> bool {
-    let Some(raw) = thread_metadata else {
-        return false;
-    };
-    let Ok(value) = serde_json::from_str::<serde_json::Value>(raw) else {
-        return false;
-    };
-    value
-        .get("archived")
-        .and_then(|archived| archived.as_bool())
+fn thread_metadata_value(thread_metadata: Option<&str>) -> Option<serde_json::Value> {
+    serde_json::from_str::<serde_json::Value>(thread_metadata?).ok()
+}
+
+/// Whether the thread has been archived, by hand or by inactivity.
+pub fn thread_is_archived(thread_metadata: Option<&str>) -> bool {
+    thread_metadata_value(thread_metadata)
+        .and_then(|value| {
+            value
+                .get("archived")
+                .and_then(|archived| archived.as_bool())
+        })
+        .unwrap_or(false)
+}
+
+/// Whether new messages in the thread are limited to moderators.
+pub fn thread_is_locked(thread_metadata: Option<&str>) -> bool {
+    thread_metadata_value(thread_metadata)
+        .and_then(|value| value.get("locked").and_then(|locked| locked.as_bool()))
         .unwrap_or(false)
 }
 
+fn thread_auto_archive_minutes(thread_metadata: Option<&str>) -> i64 {
+    thread_metadata_value(thread_metadata)
+        .and_then(|value| value.get("auto_archive_duration").and_then(|v| v.as_i64()))
+        .unwrap_or(1440)
+}
+
 #[derive(Debug, Clone)]
 pub struct ChannelRow {
     pub id: i64,
@@ -36,6 +52,8 @@ pub struct ChannelRow {
     pub message_count: Option<i32>,
     pub applied_tags: Option<String>,
     pub default_sort_order: Option<i32>,
+    /// Messages older than this many days are swept; `None` keeps them.
+    pub message_retention_days: Option<i32>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -72,6 +90,7 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ChannelRow {
             message_count: row.try_get("message_count")?,
             applied_tags: row.try_get("applied_tags")?,
             default_sort_order: row.try_get("default_sort_order")?,
+            message_retention_days: row.try_get("message_retention_days")?,
             created_at: datetime_from_db_text(&created_at_raw)?,
         })
     }
@@ -99,6 +118,9 @@ impl ChannelRow {
     }
 }
 
+/// Create a channel; voice channels get the default bitrate and no user
+/// limit, every other type has neither.
+#[allow(clippy::too_many_arguments)]
 pub async fn create_channel(
     pool: &DbPool,
     id: i64,
@@ -108,11 +130,44 @@ pub async fn create_channel(
     position: i32,
     parent_id: Option<i64>,
     required_role_ids: Option<&str>,
+) -> Result<ChannelRow, DbError> {
+    let (bitrate, user_limit) = if channel_type == i16::from(ChannelType::Voice) {
+        (Some(DEFAULT_VOICE_BITRATE), Some(0))
+    } else {
+        (None, None)
+    };
+    create_channel_with_settings(
+        pool,
+        id,
+        space_id,
+        name,
+        channel_type,
+        position,
+        parent_id,
+        required_role_ids,
+        bitrate,
+        user_limit,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn create_channel_with_settings(
+    pool: &DbPool,
+    id: i64,
+    space_id: i64,
+    name: &str,
+    channel_type: i16,
+    position: i32,
+    parent_id: Option<i64>,
+    required_role_ids: Option<&str>,
+    bitrate: Option<i32>,
+    user_limit: Option<i32>,
 ) -> Result<ChannelRow, DbError> {
     let row = sqlx::query_as::<_, ChannelRow>(
-        "INSERT INTO channels (id, space_id, name, channel_type, position, parent_id, required_role_ids)
-         VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, '[]'))
-         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at"
+        "INSERT INTO channels (id, space_id, name, channel_type, position, parent_id, required_role_ids, bitrate, user_limit)
+         VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, '[]'), $8, $9)
+         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at"
     )
     .bind(id)
     .bind(space_id)
@@ -121,6 +176,8 @@ pub async fn create_channel(
     .bind(position)
     .bind(parent_id)
     .bind(required_role_ids)
+    .bind(bitrate)
+    .bind(user_limit)
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -128,7 +185,7 @@ pub async fn create_channel(
 
 pub async fn get_channel(pool: &DbPool, id: i64) -> Result<Option<ChannelRow>, DbError> {
     let row = sqlx::query_as::<_, ChannelRow>(
-        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at
          FROM channels WHERE id = $1"
     )
     .bind(id)
@@ -144,7 +201,7 @@ pub async fn get_guild_channels(pool: &DbPool, space_id: i64) -> Result<Vec<Chan
 
 pub async fn get_space_channels(pool: &DbPool, space_id: i64) -> Result<Vec<ChannelRow>, DbError> {
     let rows = sqlx::query_as::<_, ChannelRow>(
-        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at
          FROM channels WHERE space_id = $1 ORDER BY position"
     )
     .bind(space_id)
@@ -153,36 +210,149 @@ pub async fn get_space_channels(pool: &DbPool, space_id: i64) -> Result<Vec<Chan
     Ok(rows)
 }
 
+/// Channels of several spaces in one query, threads excluded, ordered by
+/// space and then position.
+pub async fn get_non_thread_channels_for_spaces(
+    pool: &DbPool,
+    space_ids: &[i64],
+) -> Result<Vec<ChannelRow>, DbError> {
+    if space_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders: Vec<String> = (1..=space_ids.len()).map(|i| format!("${}", i)).collect();
+    let sql = format!(
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at
+         FROM channels
+         WHERE space_id IN ({}) AND channel_type != 6
+         ORDER BY space_id, position",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query_as::<_, ChannelRow>(&sql);
+    for space_id in space_ids {
+        query = query.bind(*space_id);
+    }
+    Ok(query.fetch_all(pool).await?)
+}
+
 pub async fn update_channel(
     pool: &DbPool,
     id: i64,
     name: Option<&str>,
     topic: Option<&str>,
     required_role_ids: Option<&str>,
+    rate_limit_per_user: Option<i32>,
+    message_retention_days: Option<i32>,
 ) -> Result<ChannelRow, DbError> {
+    // A retention of 0 clears the setting, keeping messages forever.
     let row = sqlx::query_as::<_, ChannelRow>(
         "UPDATE channels
          SET name = COALESCE($2, name),
              topic = COALESCE($3, topic),
              required_role_ids = COALESCE($4, required_role_ids),
-             updated_at = datetime('now')
+             rate_limit_per_user = COALESCE($5, rate_limit_per_user),
+             message_retention_days = CASE WHEN $6 IS NULL THEN message_retention_days ELSE NULLIF($6, 0) END,
+             updated_at = $7
          WHERE id = $1
-         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at"
+         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at"
     )
     .bind(id)
     .bind(name)
     .bind(topic)
     .bind(required_role_ids)
+    .bind(rate_limit_per_user)
+    .bind(message_retention_days)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_one(pool)
     .await?;
     Ok(row)
 }
 
-pub async fn delete_channel(pool: &DbPool, id: i64) -> Result<(), DbError> {
-    sqlx::query("DELETE FROM channels WHERE id = $1")
+/// Stored files whose rows went away with a deleted channel or space. The
+/// database cannot remove them; callers delete them from storage once the
+/// transaction has committed.
+#[derive(Debug, Clone, Default)]
+pub struct RemovedFiles {
+    /// `(attachment id, filename)`.
+    pub attachments: Vec<(i64, String)>,
+    /// `(emoji id, animated)`.
+    pub emojis: Vec<(i64, bool)>,
+    /// `(role id, icon hash)`.
+    pub role_icons: Vec<(i64, String)>,
+    /// `(space id, icon hash)`.
+    pub space_icons: Vec<(i64, String)>,
+    /// `(space id, banner hash)`.
+    pub space_banners: Vec<(i64, String)>,
+}
+
+/// Rows that belong to a channel or its messages, in dependency order. Each
+/// statement takes the channel id as `$1`. Cascades would cover most of
+/// these, but only while SQLite enforces foreign keys.
+const CHANNEL_CLEANUP_STATEMENTS: &[&str] = &[
+    "DELETE FROM reactions WHERE message_id IN (SELECT id FROM messages WHERE channel_id = $1)",
+    "DELETE FROM message_embeds WHERE message_id IN (SELECT id FROM messages WHERE channel_id = $1)",
+    "DELETE FROM message_edits WHERE message_id IN (SELECT id FROM messages WHERE channel_id = $1)",
+    "DELETE FROM message_mentions WHERE message_id IN (SELECT id FROM messages WHERE channel_id = $1)",
+    "DELETE FROM poll_votes WHERE poll_id IN (SELECT id FROM polls WHERE channel_id = $1)",
+    "DELETE FROM poll_options WHERE poll_id IN (SELECT id FROM polls WHERE channel_id = $1)",
+    "DELETE FROM polls WHERE channel_id = $1",
+    "DELETE FROM federation_message_map WHERE channel_id = $1",
+    "DELETE FROM attachments
+     WHERE message_id IN (SELECT id FROM messages WHERE channel_id = $1) OR upload_channel_id = $1",
+    "UPDATE messages SET thread_id = NULL WHERE thread_id = $1",
+    "DELETE FROM messages WHERE channel_id = $1",
+    "DELETE FROM channel_overwrites WHERE channel_id = $1",
+    "DELETE FROM read_states WHERE channel_id = $1",
+    "DELETE FROM invites WHERE channel_id = $1",
+    "DELETE FROM webhooks WHERE channel_id = $1",
+    "DELETE FROM forum_tags WHERE channel_id = $1",
+    "DELETE FROM thread_members WHERE thread_id = $1",
+    "DELETE FROM upload_sessions WHERE channel_id = $1",
+    "DELETE FROM voice_states WHERE channel_id = $1",
+    "DELETE FROM dm_recipients WHERE channel_id = $1",
+    "DELETE FROM interactions WHERE channel_id = $1",
+    "DELETE FROM federation_channel_map WHERE local_channel_id = $1",
+    "DELETE FROM notification_settings WHERE target_id = $1",
+    "UPDATE scheduled_events SET channel_id = NULL WHERE channel_id = $1",
+    // Channels left in a deleted category become uncategorized.
+    "UPDATE channels SET parent_id = NULL WHERE parent_id = $1",
+    "DELETE FROM channels WHERE id = $1",
+];
+
+/// Delete a channel, its threads and everything stored under them in one
+/// transaction. Returns the files to remove from storage.
+pub async fn delete_channel(pool: &DbPool, id: i64) -> Result<RemovedFiles, DbError> {
+    let mut tx = pool.begin().await?;
+    let mut removed = RemovedFiles::default();
+    purge_channel(&mut tx, id, &mut removed).await?;
+    tx.commit().await?;
+    Ok(removed)
+}
+
+/// Remove a channel and its threads on `conn`, collecting attachment files.
+pub(crate) async fn purge_channel(
+    conn: &mut sqlx::AnyConnection,
+    channel_id: i64,
+    removed: &mut RemovedFiles,
+) -> Result<(), DbError> {
+    let thread_ids: Vec<i64> =
+        sqlx::query_scalar("SELECT id FROM channels WHERE parent_id = $1 AND channel_type = 6")
+            .bind(channel_id)
+            .fetch_all(&mut *conn)
+            .await?;
+    for id in thread_ids.into_iter().chain(std::iter::once(channel_id)) {
+        let attachments: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT id, filename FROM attachments
+             WHERE message_id IN (SELECT id FROM messages WHERE channel_id = $1)
+                OR upload_channel_id = $1",
+        )
         .bind(id)
-        .execute(pool)
+        .fetch_all(&mut *conn)
         .await?;
+        removed.attachments.extend(attachments);
+        for statement in CHANNEL_CLEANUP_STATEMENTS {
+            sqlx::query(statement).bind(id).execute(&mut *conn).await?;
+        }
+    }
     Ok(())
 }
 
@@ -193,39 +363,46 @@ pub async fn count_channels(pool: &DbPool) -> Result<i64, DbError> {
     Ok(row.0)
 }
 
-pub async fn reorder_channels(pool: &DbPool, updates: &[(i64, i32)]) -> Result<(), DbError> {
-    for (channel_id, position) in updates {
-        sqlx::query(
-            "UPDATE channels SET position = $2, updated_at = datetime('now') WHERE id = $1",
-        )
-        .bind(channel_id)
-        .bind(position)
-        .execute(pool)
-        .await?;
-    }
+/// Set channel positions within a space in one transaction.
+pub async fn reorder_channels(
+    pool: &DbPool,
+    space_id: i64,
+    updates: &[(i64, i32)],
+) -> Result<(), DbError> {
+    let positions: Vec<(i64, i32, Option<Option<i64>>)> = updates
+        .iter()
+        .map(|&(channel_id, position)| (channel_id, position, None))
+        .collect();
+    update_channel_positions(pool, space_id, &positions).await?;
     Ok(())
 }
 
 /// Bulk update channel positions and optionally parent_id within a guild.
 /// Each entry is (channel_id, position, optional parent_id).
+///
+/// Runs in a single transaction: if any channel is missing or belongs to
+/// another guild the whole batch is rolled back with `DbError::NotFound`.
 /// Returns the list of channels that were actually changed.
 pub async fn update_channel_positions(
     pool: &DbPool,
     guild_id: i64,
     positions: &[(i64, i32, Option<Option<i64>>)],
 ) -> Result<Vec<ChannelRow>, DbError> {
+    let mut tx = pool.begin().await?;
     let mut changed = Vec::new();
     for &(channel_id, position, ref parent_id) in positions {
         let existing = sqlx::query_as::<_, ChannelRow>(
-            "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at
+            "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at
              FROM channels WHERE id = $1 AND space_id = $2"
         )
         .bind(channel_id)
         .bind(guild_id)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *tx)
         .await?;
 
-        let Some(existing) = existing else { continue };
+        let Some(existing) = existing else {
+            return Err(DbError::NotFound);
+        };
 
         let new_parent = match parent_id {
             Some(pid) => *pid,
@@ -237,17 +414,19 @@ pub async fn update_channel_positions(
         }
 
         let row = sqlx::query_as::<_, ChannelRow>(
-            "UPDATE channels SET position = $2, parent_id = $3, updated_at = datetime('now')
+            "UPDATE channels SET position = $2, parent_id = $3, updated_at = $4
              WHERE id = $1
-             RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at"
+             RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at"
         )
         .bind(channel_id)
         .bind(position)
         .bind(new_parent)
-        .fetch_one(pool)
+        .bind(datetime_to_db_text(Utc::now()))
+        .fetch_one(&mut *tx)
         .await?;
         changed.push(row);
     }
+    tx.commit().await?;
     Ok(changed)
 }
 
@@ -282,9 +461,9 @@ pub async fn create_thread(
     .to_string();
 
     let row = sqlx::query_as::<_, ChannelRow>(
-        "INSERT INTO channels (id, space_id, name, channel_type, position, parent_id, required_role_ids, thread_metadata, owner_id, message_count)
-         VALUES ($1, $2, $3, 6, 0, $4, '[]', $5, $6, 0)
-         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at"
+        "INSERT INTO channels (id, space_id, name, channel_type, position, parent_id, required_role_ids, thread_metadata, owner_id, message_count, last_message_at)
+         VALUES ($1, $2, $3, 6, 0, $4, '[]', $5, $6, 0, $7)
+         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at"
     )
     .bind(id)
     .bind(space_id)
@@ -292,6 +471,7 @@ pub async fn create_thread(
     .bind(parent_channel_id)
     .bind(&thread_metadata)
     .bind(owner_id)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -303,7 +483,7 @@ pub async fn get_channel_threads(
     parent_channel_id: i64,
 ) -> Result<Vec<ChannelRow>, DbError> {
     let rows = sqlx::query_as::<_, ChannelRow>(
-        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at
          FROM channels
          WHERE parent_id = $1 AND channel_type = 6
          ORDER BY created_at DESC"
@@ -323,7 +503,7 @@ pub async fn get_archived_threads(
     parent_channel_id: i64,
 ) -> Result<Vec<ChannelRow>, DbError> {
     let rows = sqlx::query_as::<_, ChannelRow>(
-        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at
          FROM channels
          WHERE parent_id = $1 AND channel_type = 6
          ORDER BY created_at DESC"
@@ -346,7 +526,7 @@ pub async fn update_thread(
     locked: Option<bool>,
 ) -> Result<ChannelRow, DbError> {
     let existing = sqlx::query_as::<_, ChannelRow>(
-        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at
          FROM channels
          WHERE id = $1 AND channel_type = 6",
     )
@@ -363,14 +543,17 @@ pub async fn update_thread(
         .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
         .unwrap_or_else(|| serde_json::json!({}));
 
+    let now = datetime_to_db_text(Utc::now());
     if let Some(archived_val) = archived {
         metadata["archived"] = serde_json::Value::Bool(archived_val);
-        if archived_val {
-            metadata["archive_timestamp"] = serde_json::Value::String(
-                chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-            );
-        }
+        // Unarchiving counts as activity too, so the auto-archive clock
+        // restarts from here rather than from the last message.
+        metadata["archive_timestamp"] = serde_json::Value::String(now.clone());
     }
+    let now_archived = metadata
+        .get("archived")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
     if let Some(locked_val) = locked {
         metadata["locked"] = serde_json::Value::Bool(locked_val);
     }
@@ -381,13 +564,18 @@ pub async fn update_thread(
         "UPDATE channels
          SET name = COALESCE($2, name),
              thread_metadata = $3,
-             updated_at = datetime('now')
+             updated_at = $4,
+             archived = $5,
+             last_message_at = COALESCE($6, last_message_at)
          WHERE id = $1 AND channel_type = 6
-         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at",
+         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at",
     )
     .bind(thread_id)
     .bind(name)
     .bind(metadata_raw)
+    .bind(&now)
+    .bind(now_archived)
+    .bind(archived.map(|_| now.clone()))
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -404,6 +592,172 @@ pub async fn increment_thread_message_count(pool: &DbPool, thread_id: i64) -> Re
     Ok(())
 }
 
+/// Find the thread started from `message_id`, if one exists.
+pub async fn get_thread_by_starter_message(
+    pool: &DbPool,
+    parent_channel_id: i64,
+    message_id: i64,
+) -> Result<Option<ChannelRow>, DbError> {
+    let rows = sqlx::query_as::<_, ChannelRow>(
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at
+         FROM channels
+         WHERE parent_id = $1 AND channel_type = 6"
+    )
+    .bind(parent_channel_id)
+    .fetch_all(pool)
+    .await?;
+    let starter = message_id.to_string();
+    Ok(rows.into_iter().find(|row| {
+        thread_metadata_value(row.thread_metadata.as_deref())
+            .and_then(|value| {
+                value
+                    .get("starter_message_id")
+                    .and_then(|v| v.as_str())
+                    .map(|id| id == starter)
+            })
+            .unwrap_or(false)
+    }))
+}
+
+/// Ids of every thread under a parent channel, archived or not.
+pub async fn get_thread_ids(pool: &DbPool, parent_channel_id: i64) -> Result<Vec<i64>, DbError> {
+    let ids: Vec<i64> =
+        sqlx::query_scalar("SELECT id FROM channels WHERE parent_id = $1 AND channel_type = 6")
+            .bind(parent_channel_id)
+            .fetch_all(pool)
+            .await?;
+    Ok(ids)
+}
+
+/// Auto-archive durations are at least an hour, so a thread active more
+/// recently than that can't be due yet.
+const MIN_THREAD_AUTO_ARCHIVE_MINUTES: i64 = 60;
+const INACTIVE_THREAD_SCAN_BATCH: i64 = 200;
+
+/// Unarchived threads whose auto-archive duration has run out since their
+/// last activity, oldest activity first.
+///
+/// Only threads quiet for at least the shortest duration are read, walking
+/// the (archived, last_message_at) index; each one's own duration is then
+/// checked here.
+pub async fn get_inactive_threads(
+    pool: &DbPool,
+    now: DateTime<Utc>,
+    limit: usize,
+) -> Result<Vec<ChannelRow>, DbError> {
+    let cutoff =
+        datetime_to_db_text(now - chrono::Duration::minutes(MIN_THREAD_AUTO_ARCHIVE_MINUTES));
+    let mut after = (String::new(), 0i64);
+    let mut due: Vec<i64> = Vec::new();
+    while due.len() < limit {
+        let candidates: Vec<(i64, String, Option<String>)> = sqlx::query_as(
+            "SELECT id, last_message_at, thread_metadata
+             FROM channels
+             WHERE archived = FALSE AND last_message_at <= $1 AND channel_type = 6
+               AND (last_message_at > $2 OR (last_message_at = $2 AND id > $3))
+             ORDER BY last_message_at, id
+             LIMIT $4",
+        )
+        .bind(&cutoff)
+        .bind(&after.0)
+        .bind(after.1)
+        .bind(INACTIVE_THREAD_SCAN_BATCH)
+        .fetch_all(pool)
+        .await?;
+        let Some((last_id, last_at, _)) = candidates.last() else {
+            break;
+        };
+        after = (last_at.clone(), *last_id);
+        let exhausted = (candidates.len() as i64) < INACTIVE_THREAD_SCAN_BATCH;
+
+        for (id, last_message_at, thread_metadata) in candidates {
+            let last_activity = datetime_from_db_text(&last_message_at)?;
+            let minutes = thread_auto_archive_minutes(thread_metadata.as_deref());
+            if last_activity + chrono::Duration::minutes(minutes) <= now {
+                due.push(id);
+                if due.len() == limit {
+                    break;
+                }
+            }
+        }
+        if exhausted {
+            break;
+        }
+    }
+
+    let mut threads = Vec::with_capacity(due.len());
+    for id in due {
+        if let Some(row) = get_channel(pool, id).await? {
+            threads.push(row);
+        }
+    }
+    Ok(threads)
+}
+
+#[derive(Debug, Clone)]
+pub struct ThreadMemberRow {
+    pub thread_id: i64,
+    pub user_id: i64,
+    pub joined_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ThreadMemberRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let joined_at_raw: String = row.try_get("joined_at")?;
+        Ok(Self {
+            thread_id: row.try_get("thread_id")?,
+            user_id: row.try_get("user_id")?,
+            joined_at: datetime_from_db_text(&joined_at_raw)?,
+        })
+    }
+}
+
+/// Add a user to a thread. Returns `false` when they already belonged to it.
+pub async fn add_thread_member(
+    pool: &DbPool,
+    thread_id: i64,
+    user_id: i64,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "INSERT INTO thread_members (thread_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+    )
+    .bind(thread_id)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Remove a user from a thread. Returns `false` when they were not in it.
+pub async fn remove_thread_member(
+    pool: &DbPool,
+    thread_id: i64,
+    user_id: i64,
+) -> Result<bool, DbError> {
+    let result = sqlx::query("DELETE FROM thread_members WHERE thread_id = $1 AND user_id = $2")
+        .bind(thread_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_thread_members(
+    pool: &DbPool,
+    thread_id: i64,
+) -> Result<Vec<ThreadMemberRow>, DbError> {
+    let rows = sqlx::query_as::<_, ThreadMemberRow>(
+        "SELECT thread_id, user_id, joined_at
+         FROM thread_members
+         WHERE thread_id = $1
+         ORDER BY joined_at, user_id",
+    )
+    .bind(thread_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
 // ============ Forum channel helpers ============
 
 /// Create a forum post (a thread under a forum channel).
@@ -428,9 +782,9 @@ pub async fn create_forum_post(
     let tags = applied_tags.unwrap_or("[]");
 
     let row = sqlx::query_as::<_, ChannelRow>(
-        "INSERT INTO channels (id, space_id, name, channel_type, position, parent_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags)
-         VALUES ($1, $2, $3, 6, 0, $4, '[]', $5, $6, 0, $7)
-         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at"
+        "INSERT INTO channels (id, space_id, name, channel_type, position, parent_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, last_message_at)
+         VALUES ($1, $2, $3, 6, 0, $4, '[]', $5, $6, 0, $7, $8)
+         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at"
     )
     .bind(id)
     .bind(space_id)
@@ -439,6 +793,7 @@ pub async fn create_forum_post(
     .bind(&thread_metadata)
     .bind(owner_id)
     .bind(tags)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -459,7 +814,7 @@ pub async fn get_forum_posts(
     };
 
     let sql = format!(
-        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, message_retention_days, created_at
          FROM channels
          WHERE parent_id = $1 AND channel_type = 6
          ORDER BY {}",
@@ -542,23 +897,40 @@ pub async fn update_post_tags(
     thread_id: i64,
     applied_tags: &str,
 ) -> Result<(), DbError> {
-    sqlx::query("UPDATE channels SET applied_tags = $2, updated_at = datetime('now') WHERE id = $1 AND channel_type = 6")
-        .bind(thread_id)
-        .bind(applied_tags)
-        .execute(pool)
-        .await?;
+    sqlx::query(
+        "UPDATE channels SET applied_tags = $2, updated_at = $3 WHERE id = $1 AND channel_type = 6",
+    )
+    .bind(thread_id)
+    .bind(applied_tags)
+    .bind(datetime_to_db_text(Utc::now()))
+    .execute(pool)
+    .await?;
     Ok(())
 }
 
+/// Channels with a message retention set, as `(channel id, days)`.
+pub async fn list_channels_with_message_retention(
+    pool: &DbPool,
+) -> Result<Vec<(i64, i32)>, DbError> {
+    let rows: Vec<(i64, i32)> = sqlx::query_as(
+        "SELECT id, message_retention_days FROM channels
+         WHERE message_retention_days IS NOT NULL AND message_retention_days > 0",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
 /// Update default_sort_order on a forum channel.
 pub async fn update_forum_sort_order(
     pool: &DbPool,
     channel_id: i64,
     sort_order: i32,
 ) -> Result<(), DbError> {
-    sqlx::query("UPDATE channels SET default_sort_order = $2, updated_at = datetime('now') WHERE id = $1 AND channel_type = 7")
+    sqlx::query("UPDATE channels SET default_sort_order = $2, updated_at = $3 WHERE id = $1 AND channel_type = 7")
         .bind(channel_id)
         .bind(sort_order)
+        .bind(datetime_to_db_text(Utc::now()))
         .execute(pool)
         .await?;
     Ok(())
@@ -640,11 +1012,31 @@ mod tests {
         create_channel(&pool, 40, guild_id, "old-name", 0, 0, None, None)
             .await
             .unwrap();
-        let updated = update_channel(&pool, 40, Some("new-name"), Some("A topic"), None)
-            .await
-            .unwrap();
+        let updated = update_channel(
+            &pool,
+            40,
+            Some("new-name"),
+            Some("A topic"),
+            None,
+            Some(30),
+            Some(7),
+        )
+        .await
+        .unwrap();
         assert_eq!(updated.name.as_deref(), Some("new-name"));
         assert_eq!(updated.topic.as_deref(), Some("A topic"));
+        assert_eq!(updated.rate_limit_per_user, 30);
+        assert_eq!(updated.message_retention_days, Some(7));
+
+        // 0 clears the retention; leaving it out keeps the current value.
+        let kept = update_channel(&pool, 40, None, None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(kept.message_retention_days, Some(7));
+        let cleared = update_channel(&pool, 40, None, None, None, None, Some(0))
+            .await
+            .unwrap();
+        assert_eq!(cleared.message_retention_days, None);
     }
 
     #[tokio::test]
@@ -654,7 +1046,7 @@ mod tests {
         create_channel(&pool, 41, guild_id, "keep-name", 0, 0, None, None)
             .await
             .unwrap();
-        let updated = update_channel(&pool, 41, None, Some("topic only"), None)
+        let updated = update_channel(&pool, 41, None, Some("topic only"), None, None, None)
             .await
             .unwrap();
         assert_eq!(updated.name.as_deref(), Some("keep-name"));
@@ -673,6 +1065,73 @@ mod tests {
         assert!(channel.is_none());
     }
 
+    #[tokio::test]
+    async fn delete_channel_leaves_no_orphan_rows() {
+        let pool = test_pool().await;
+        let guild_id = setup_guild(&pool).await;
+        create_channel(&pool, 70, guild_id, "doomed", 0, 0, None, None)
+            .await
+            .unwrap();
+        create_thread(&pool, 71, guild_id, 70, "side", 1, 1440, None)
+            .await
+            .unwrap();
+        crate::messages::create_message(&pool, 700, 70, 1, "hello", 0, None)
+            .await
+            .unwrap();
+        crate::messages::create_message(&pool, 710, 71, 1, "in thread", 0, None)
+            .await
+            .unwrap();
+        crate::reactions::add_reaction(&pool, 700, 1, "👍", None)
+            .await
+            .unwrap();
+        for (id, message_id, upload_channel_id) in [
+            (7000, Some(700), None),
+            (7100, Some(710), None),
+            (7200, None, Some(70)),
+        ] {
+            crate::attachments::create_attachment(
+                &pool,
+                id,
+                message_id,
+                &format!("file{id}.png"),
+                Some("image/png"),
+                10,
+                "/x",
+                None,
+                None,
+                Some(1),
+                upload_channel_id,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        crate::channel_overwrites::upsert_channel_overwrite(&pool, 70, guild_id, 0, 0, 1024)
+            .await
+            .unwrap();
+        crate::invites::create_invite(&pool, "doomed", guild_id, 70, 1, None, None, false)
+            .await
+            .unwrap();
+
+        let removed = delete_channel(&pool, 70).await.unwrap();
+        let mut attachment_ids: Vec<i64> = removed.attachments.iter().map(|(id, _)| *id).collect();
+        attachment_ids.sort_unstable();
+        assert_eq!(attachment_ids, vec![7000, 7100, 7200]);
+
+        for sql in [
+            "SELECT COUNT(*) FROM channels WHERE id IN (70, 71)",
+            "SELECT COUNT(*) FROM messages WHERE channel_id IN (70, 71)",
+            "SELECT COUNT(*) FROM attachments",
+            "SELECT COUNT(*) FROM reactions",
+            "SELECT COUNT(*) FROM channel_overwrites WHERE channel_id = 70",
+            "SELECT COUNT(*) FROM invites WHERE channel_id = 70",
+        ] {
+            let (count,): (i64,) = sqlx::query_as(sql).fetch_one(&pool).await.unwrap();
+            assert_eq!(count, 0, "{sql}");
+        }
+    }
+
     #[tokio::test]
     async fn test_count_channels() {
         let pool = test_pool().await;
@@ -697,7 +1156,9 @@ mod tests {
         create_channel(&pool, 71, guild_id, "second", 0, 1, None, None)
             .await
             .unwrap();
-        reorder_channels(&pool, &[(70, 1), (71, 0)]).await.unwrap();
+        reorder_channels(&pool, guild_id, &[(70, 1), (71, 0)])
+            .await
+            .unwrap();
         let channels = get_guild_channels(&pool, guild_id).await.unwrap();
         assert_eq!(channels[0].id, 71);
         assert_eq!(channels[0].position, 0);
@@ -705,6 +1166,21 @@ mod tests {
         assert_eq!(channels[1].position, 1);
     }
 
+    #[tokio::test]
+    async fn test_reorder_channels_rolls_back_on_foreign_channel() {
+        let pool = test_pool().await;
+        let guild_id = setup_guild(&pool).await;
+        create_channel(&pool, 72, guild_id, "first", 0, 0, None, None)
+            .await
+            .unwrap();
+        let err = reorder_channels(&pool, guild_id, &[(72, 5), (99_999, 0)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DbError::NotFound));
+        let channel = get_channel(&pool, 72).await.unwrap().unwrap();
+        assert_eq!(channel.position, 0);
+    }
+
     #[tokio::test]
     async fn test_channel_with_parent() {
         let pool = test_pool().await;
@@ -764,6 +1240,95 @@ mod tests {
         assert_eq!(threads.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_thread_members_and_starter_lookup() {
+        let pool = test_pool().await;
+        let guild_id = setup_guild(&pool).await;
+        create_channel(&pool, 96, guild_id, "parent", 0, 0, None, None)
+            .await
+            .unwrap();
+        create_thread(&pool, 97, guild_id, 96, "from-message", 1, 60, Some(500))
+            .await
+            .unwrap();
+
+        let found = get_thread_by_starter_message(&pool, 96, 500)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, 97);
+        assert!(get_thread_by_starter_message(&pool, 96, 501)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(get_thread_ids(&pool, 96).await.unwrap(), vec![97]);
+
+        assert!(add_thread_member(&pool, 97, 1).await.unwrap());
+        assert!(!add_thread_member(&pool, 97, 1).await.unwrap());
+        let members = get_thread_members(&pool, 97).await.unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].user_id, 1);
+        assert!(remove_thread_member(&pool, 97, 1).await.unwrap());
+        assert!(!remove_thread_member(&pool, 97, 1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_get_inactive_threads_honors_auto_archive_duration() {
+        let pool = test_pool().await;
+        let guild_id = setup_guild(&pool).await;
+        create_channel(&pool, 98, guild_id, "parent", 0, 0, None, None)
+            .await
+            .unwrap();
+        let thread = create_thread(&pool, 99, guild_id, 98, "quiet", 1, 60, None)
+            .await
+            .unwrap();
+        let created = thread.created_at;
+        create_thread(&pool, 100, guild_id, 98, "slow", 1, 1440, None)
+            .await
+            .unwrap();
+
+        let soon = created + chrono::Duration::minutes(30);
+        assert!(get_inactive_threads(&pool, soon, 10)
+            .await
+            .unwrap()
+            .is_empty());
+        let later = created + chrono::Duration::minutes(61);
+        let inactive = get_inactive_threads(&pool, later, 10).await.unwrap();
+        assert_eq!(inactive.len(), 1);
+        assert_eq!(inactive[0].id, 99);
+
+        // A message restarts the clock.
+        crate::messages::create_message(&pool, 101, 99, 1, "still here", 0, None)
+            .await
+            .unwrap();
+        let after_message = Utc::now();
+        assert!(
+            get_inactive_threads(&pool, after_message + chrono::Duration::minutes(30), 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let much_later = after_message + chrono::Duration::minutes(24 * 60 + 1);
+        let mut ids: Vec<i64> = get_inactive_threads(&pool, much_later, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| row.id)
+            .collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![99, 100]);
+
+        update_thread(&pool, 99, None, Some(true), None)
+            .await
+            .unwrap();
+        let ids: Vec<i64> = get_inactive_threads(&pool, much_later, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| row.id)
+            .collect();
+        assert_eq!(ids, vec![100]);
+    }
+
     #[tokio::test]
     async fn test_guild_id_backward_compat() {
         let pool = test_pool().await;
@@ -0,0 +1,151 @@
+use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomStatusRow {
+    pub user_id: i64,
+    pub text: Option<String>,
+    pub emoji: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for CustomStatusRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let expires_at_raw: Option<String> = row.try_get("expires_at")?;
+        Ok(Self {
+            user_id: row.try_get("user_id")?,
+            text: row.try_get("text")?,
+            emoji: row.try_get("emoji")?,
+            expires_at: expires_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+        })
+    }
+}
+
+/// Replace the user's custom status.
+pub async fn set_custom_status(
+    pool: &DbPool,
+    user_id: i64,
+    text: Option<&str>,
+    emoji: Option<&str>,
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<CustomStatusRow, DbError> {
+    let row = sqlx::query_as::<_, CustomStatusRow>(
+        "INSERT INTO user_custom_statuses (user_id, text, emoji, expires_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (user_id) DO UPDATE SET
+             text = $2,
+             emoji = $3,
+             expires_at = $4,
+             updated_at = $5
+         RETURNING user_id, text, emoji, expires_at",
+    )
+    .bind(user_id)
+    .bind(text)
+    .bind(emoji)
+    .bind(expires_at.map(datetime_to_db_text))
+    .bind(datetime_to_db_text(Utc::now()))
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Returns whether the user had a custom status.
+pub async fn clear_custom_status(pool: &DbPool, user_id: i64) -> Result<bool, DbError> {
+    let result = sqlx::query("DELETE FROM user_custom_statuses WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// The user's custom status, unless it has expired by `now`.
+pub async fn get_custom_status(
+    pool: &DbPool,
+    user_id: i64,
+    now: DateTime<Utc>,
+) -> Result<Option<CustomStatusRow>, DbError> {
+    let row = sqlx::query_as::<_, CustomStatusRow>(
+        "SELECT user_id, text, emoji, expires_at
+         FROM user_custom_statuses
+         WHERE user_id = $1 AND (expires_at IS NULL OR expires_at > $2)",
+    )
+    .bind(user_id)
+    .bind(datetime_to_db_text(now))
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Delete custom statuses that expired at or before `now` and return the
+/// affected user ids.
+pub async fn delete_expired_custom_statuses(
+    pool: &DbPool,
+    now: DateTime<Utc>,
+) -> Result<Vec<i64>, DbError> {
+    let rows: Vec<(i64,)> = sqlx::query_as(
+        "DELETE FROM user_custom_statuses
+         WHERE expires_at IS NOT NULL AND expires_at <= $1
+         RETURNING user_id",
+    )
+    .bind(datetime_to_db_text(now))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(user_id,)| user_id).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn expired_statuses_are_hidden_and_swept() {
+        let pool = test_pool().await;
+        for id in [1, 2] {
+            crate::users::create_user(&pool, id, &format!("u{id}"), 1, &format!("{id}@x.y"), "h")
+                .await
+                .unwrap();
+        }
+        let now = Utc::now();
+        set_custom_status(
+            &pool,
+            1,
+            Some("Lunch"),
+            Some("🍕"),
+            Some(now + Duration::minutes(30)),
+        )
+        .await
+        .unwrap();
+        set_custom_status(&pool, 2, Some("Around"), None, None)
+            .await
+            .unwrap();
+
+        let status = get_custom_status(&pool, 1, now).await.unwrap().unwrap();
+        assert_eq!(status.text.as_deref(), Some("Lunch"));
+        assert_eq!(status.emoji.as_deref(), Some("🍕"));
+        let later = now + Duration::hours(1);
+        assert!(get_custom_status(&pool, 1, later).await.unwrap().is_none());
+
+        assert_eq!(
+            delete_expired_custom_statuses(&pool, later).await.unwrap(),
+            vec![1]
+        );
+        assert!(delete_expired_custom_statuses(&pool, later)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(get_custom_status(&pool, 2, later).await.unwrap().is_some());
+        assert!(clear_custom_status(&pool, 2).await.unwrap());
+        assert!(!clear_custom_status(&pool, 2).await.unwrap());
+    }
+}
@@ -19,9 +19,10 @@ pub async fn find_dm_channel_between(
 ) -> Result<Option<ChannelRow>, DbError> {
     let row = sqlx::query_as::<_, ChannelRow>(
         "SELECT c.id, c.space_id, c.name, c.topic, c.channel_type, c.position, c.parent_id,
-                c.nsfw, c.rate_limit_per_user, c.bitrate, c.user_limit, c.last_message_id,
-                c.required_role_ids, c.thread_metadata, c.owner_id, c.message_count,
-                c.applied_tags, c.default_sort_order, c.created_at
+                CASE WHEN c.nsfw THEN 1 ELSE 0 END AS nsfw, c.rate_limit_per_user, c.bitrate,
+                c.user_limit, c.last_message_id, c.required_role_ids, c.thread_metadata,
+                c.owner_id, c.message_count, c.applied_tags, c.default_sort_order,
+                c.message_retention_days, c.created_at
          FROM channels c
          INNER JOIN dm_recipients a ON a.channel_id = c.id AND a.user_id = $1
          INNER JOIN dm_recipients b ON b.channel_id = c.id AND b.user_id = $2
@@ -64,10 +65,11 @@ pub async fn create_dm_channel(
     tx.commit().await?;
 
     let row = sqlx::query_as::<_, ChannelRow>(
-        "SELECT id, space_id, name, topic, channel_type, position, parent_id, nsfw,
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id,
+                CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw,
                 rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids,
                 thread_metadata, owner_id, message_count, applied_tags, default_sort_order,
-                created_at
+                message_retention_days, created_at
          FROM channels
          WHERE id = $1",
     )
@@ -103,6 +105,40 @@ pub async fn list_user_dm_channels(
     Ok(rows)
 }
 
+/// One page of the user's DM channels, most recently active first. Activity
+/// is the last message id, or the channel id for channels with no messages,
+/// and `before` is the activity of the last channel on the previous page.
+pub async fn list_user_dm_channels_page(
+    pool: &DbPool,
+    user_id: i64,
+    before: Option<i64>,
+    limit: i64,
+) -> Result<Vec<DmChannelWithRecipientRow>, DbError> {
+    let rows = sqlx::query_as::<_, DmChannelWithRecipientRow>(
+        "SELECT c.id, c.channel_type, c.last_message_id,
+                u.id AS recipient_id,
+                u.username AS recipient_username,
+                u.discriminator AS recipient_discriminator,
+                u.avatar_hash AS recipient_avatar_hash,
+                u.public_key AS recipient_public_key
+         FROM channels c
+         INNER JOIN dm_recipients me ON me.channel_id = c.id
+         INNER JOIN dm_recipients other ON other.channel_id = c.id AND other.user_id != me.user_id
+         INNER JOIN users u ON u.id = other.user_id
+         WHERE c.channel_type = 1 AND me.user_id = $1
+           AND COALESCE(c.last_message_id, c.id) < $2
+         ORDER BY COALESCE(c.last_message_id, c.id) DESC
+         LIMIT $3",
+    )
+    .bind(user_id)
+    .bind(before.unwrap_or(i64::MAX))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
 pub async fn get_dm_recipient_ids(pool: &DbPool, channel_id: i64) -> Result<Vec<i64>, DbError> {
     let rows: Vec<(i64,)> =
         sqlx::query_as("SELECT user_id FROM dm_recipients WHERE channel_id = $1")
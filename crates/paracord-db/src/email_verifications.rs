@@ -0,0 +1,180 @@
+use crate::{datetime_to_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+
+/// Store the hash of a verification token for `user_id`, valid until
+/// `expires_at` (unix seconds).
+pub async fn create_email_verification(
+    pool: &DbPool,
+    token_hash: &str,
+    user_id: i64,
+    expires_at: i64,
+) -> Result<(), DbError> {
+    sqlx::query(
+        "INSERT INTO email_verification_tokens (token_hash, user_id, expires_at)
+         VALUES ($1, $2, $3)",
+    )
+    .bind(token_hash)
+    .bind(user_id)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Use a verification token: clears `unverified_flag` on its account and
+/// drops all of the account's tokens. Returns the verified user id, or `None`
+/// when the token is unknown or expired.
+pub async fn consume_email_verification(
+    pool: &DbPool,
+    token_hash: &str,
+    now: i64,
+    unverified_flag: i32,
+) -> Result<Option<i64>, DbError> {
+    let mut tx = pool.begin().await?;
+    let row: Option<(i64,)> = sqlx::query_as(
+        "SELECT user_id FROM email_verification_tokens
+         WHERE token_hash = $1 AND expires_at >= $2",
+    )
+    .bind(token_hash)
+    .bind(now)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some((user_id,)) = row else {
+        return Ok(None);
+    };
+    sqlx::query("DELETE FROM email_verification_tokens WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE users SET flags = flags & ~$2 WHERE id = $1")
+        .bind(user_id)
+        .bind(unverified_flag)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(Some(user_id))
+}
+
+/// Delete accounts still carrying `unverified_flag` that were created before
+/// `created_before`, along with tokens that expired before `now`. Returns the
+/// number of accounts removed.
+pub async fn prune_unverified_users(
+    pool: &DbPool,
+    unverified_flag: i32,
+    created_before: DateTime<Utc>,
+    now: i64,
+) -> Result<u64, DbError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "DELETE FROM email_verification_tokens
+         WHERE user_id IN (
+             SELECT id FROM users WHERE (flags & $1) != 0 AND created_at < $2
+         )",
+    )
+    .bind(unverified_flag)
+    .bind(datetime_to_db_text(created_before))
+    .execute(&mut *tx)
+    .await?;
+    let result = sqlx::query("DELETE FROM users WHERE (flags & $1) != 0 AND created_at < $2")
+        .bind(unverified_flag)
+        .bind(datetime_to_db_text(created_before))
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM email_verification_tokens WHERE expires_at < $1")
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const UNVERIFIED: i32 = 1 << 3;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    async fn unverified_user(pool: &DbPool, id: i64) {
+        crate::users::create_user(
+            pool,
+            id,
+            &format!("user{id}"),
+            1,
+            &format!("{id}@x.test"),
+            "h",
+        )
+        .await
+        .unwrap();
+        crate::users::update_user_flags(pool, id, UNVERIFIED)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_token_verifies_once_and_unverified_accounts_are_pruned() {
+        let pool = test_pool().await;
+        unverified_user(&pool, 1).await;
+        unverified_user(&pool, 2).await;
+        create_email_verification(&pool, "good", 1, 1_100)
+            .await
+            .unwrap();
+        create_email_verification(&pool, "expired", 2, 900)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            consume_email_verification(&pool, "expired", 1_000, UNVERIFIED)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            consume_email_verification(&pool, "good", 1_000, UNVERIFIED)
+                .await
+                .unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            consume_email_verification(&pool, "good", 1_000, UNVERIFIED)
+                .await
+                .unwrap(),
+            None
+        );
+        let user = crate::users::get_user_by_id(&pool, 1)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.flags & UNVERIFIED, 0);
+
+        // Nothing is old enough yet.
+        let past = Utc::now() - Duration::hours(1);
+        assert_eq!(
+            prune_unverified_users(&pool, UNVERIFIED, past, 1_000)
+                .await
+                .unwrap(),
+            0
+        );
+        let future = Utc::now() + Duration::hours(1);
+        assert_eq!(
+            prune_unverified_users(&pool, UNVERIFIED, future, 1_000)
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(crate::users::get_user_by_id(&pool, 1)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(crate::users::get_user_by_id(&pool, 2)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
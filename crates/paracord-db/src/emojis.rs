@@ -37,7 +37,7 @@ pub async fn create_emoji(
     let row = sqlx::query_as::<_, EmojiRow>(
         "INSERT INTO emojis (id, space_id, name, creator_id, animated)
          VALUES ($1, $2, $3, $4, $5)
-         RETURNING id, space_id AS guild_id, name, creator_id, animated, created_at",
+         RETURNING id, space_id AS guild_id, name, creator_id, CASE WHEN animated THEN 1 ELSE 0 END AS animated, created_at",
     )
     .bind(id)
     .bind(guild_id)
@@ -49,9 +49,79 @@ pub async fn create_emoji(
     Ok(row)
 }
 
+#[derive(Debug, Clone)]
+pub enum EmojiCreateOutcome {
+    Created(EmojiRow),
+    /// Another emoji in the space already has this name (ignoring case).
+    NameTaken,
+    /// The space already holds `max_of_kind` emojis of the same kind
+    /// (static or animated).
+    LimitReached,
+}
+
+/// Create an emoji unless its name is taken in the space or the space is at
+/// its cap for that kind. The checks and the insert are one statement so
+/// concurrent uploads cannot overshoot the cap or duplicate a name.
+pub async fn create_emoji_checked(
+    pool: &DbPool,
+    id: i64,
+    guild_id: i64,
+    name: &str,
+    creator_id: i64,
+    animated: bool,
+    max_of_kind: i64,
+) -> Result<EmojiCreateOutcome, DbError> {
+    let row = sqlx::query_as::<_, EmojiRow>(
+        "INSERT INTO emojis (id, space_id, name, creator_id, animated)
+         SELECT $1, $2, $3, $4, $5
+         WHERE NOT EXISTS (
+                 SELECT 1 FROM emojis WHERE space_id = $2 AND LOWER(name) = LOWER($3)
+             )
+           AND (SELECT COUNT(*) FROM emojis WHERE space_id = $2 AND animated = $5) < $6
+         RETURNING id, space_id AS guild_id, name, creator_id, CASE WHEN animated THEN 1 ELSE 0 END AS animated, created_at",
+    )
+    .bind(id)
+    .bind(guild_id)
+    .bind(name)
+    .bind(creator_id)
+    .bind(animated)
+    .bind(max_of_kind)
+    .fetch_optional(pool)
+    .await?;
+    if let Some(row) = row {
+        return Ok(EmojiCreateOutcome::Created(row));
+    }
+    if emoji_name_taken(pool, guild_id, name, None).await? {
+        Ok(EmojiCreateOutcome::NameTaken)
+    } else {
+        Ok(EmojiCreateOutcome::LimitReached)
+    }
+}
+
+/// Whether an emoji other than `except_id` in the space uses `name`,
+/// ignoring case.
+pub async fn emoji_name_taken(
+    pool: &DbPool,
+    guild_id: i64,
+    name: &str,
+    except_id: Option<i64>,
+) -> Result<bool, DbError> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        "SELECT id FROM emojis
+         WHERE space_id = $1 AND LOWER(name) = LOWER($2) AND id <> $3
+         LIMIT 1",
+    )
+    .bind(guild_id)
+    .bind(name)
+    .bind(except_id.unwrap_or(0))
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.is_some())
+}
+
 pub async fn get_emoji(pool: &DbPool, id: i64) -> Result<Option<EmojiRow>, DbError> {
     let row = sqlx::query_as::<_, EmojiRow>(
-        "SELECT id, space_id AS guild_id, name, creator_id, animated, created_at
+        "SELECT id, space_id AS guild_id, name, creator_id, CASE WHEN animated THEN 1 ELSE 0 END AS animated, created_at
          FROM emojis WHERE id = $1",
     )
     .bind(id)
@@ -62,7 +132,7 @@ pub async fn get_emoji(pool: &DbPool, id: i64) -> Result<Option<EmojiRow>, DbErr
 
 pub async fn get_guild_emojis(pool: &DbPool, guild_id: i64) -> Result<Vec<EmojiRow>, DbError> {
     let rows = sqlx::query_as::<_, EmojiRow>(
-        "SELECT id, space_id AS guild_id, name, creator_id, animated, created_at
+        "SELECT id, space_id AS guild_id, name, creator_id, CASE WHEN animated THEN 1 ELSE 0 END AS animated, created_at
          FROM emojis WHERE space_id = $1 ORDER BY name",
     )
     .bind(guild_id)
@@ -75,7 +145,7 @@ pub async fn update_emoji(pool: &DbPool, id: i64, name: &str) -> Result<EmojiRow
     let row = sqlx::query_as::<_, EmojiRow>(
         "UPDATE emojis SET name = $2
          WHERE id = $1
-         RETURNING id, space_id AS guild_id, name, creator_id, animated, created_at",
+         RETURNING id, space_id AS guild_id, name, creator_id, CASE WHEN animated THEN 1 ELSE 0 END AS animated, created_at",
     )
     .bind(id)
     .bind(name)
@@ -91,3 +161,53 @@ pub async fn delete_emoji(pool: &DbPool, id: i64) -> Result<(), DbError> {
         .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        crate::users::create_user(&pool, 1, "owner", 1, "owner@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 100, "Emoji Guild", 1, None)
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_create_emoji_checked_enforces_names_and_caps() {
+        let pool = test_pool().await;
+        let created = create_emoji_checked(&pool, 10, 100, "wave", 1, false, 2)
+            .await
+            .unwrap();
+        assert!(matches!(created, EmojiCreateOutcome::Created(ref e) if e.name == "wave"));
+
+        let duplicate = create_emoji_checked(&pool, 11, 100, "WAVE", 1, true, 2)
+            .await
+            .unwrap();
+        assert!(matches!(duplicate, EmojiCreateOutcome::NameTaken));
+
+        create_emoji_checked(&pool, 12, 100, "smile", 1, false, 2)
+            .await
+            .unwrap();
+        let over_static = create_emoji_checked(&pool, 13, 100, "frown", 1, false, 2)
+            .await
+            .unwrap();
+        assert!(matches!(over_static, EmojiCreateOutcome::LimitReached));
+        // Animated emojis have their own allowance.
+        let animated = create_emoji_checked(&pool, 14, 100, "frown", 1, true, 2)
+            .await
+            .unwrap();
+        assert!(matches!(animated, EmojiCreateOutcome::Created(ref e) if e.animated));
+
+        assert!(emoji_name_taken(&pool, 100, "Smile", None).await.unwrap());
+        assert!(!emoji_name_taken(&pool, 100, "smile", Some(12))
+            .await
+            .unwrap());
+        assert_eq!(get_guild_emojis(&pool, 100).await.unwrap().len(), 3);
+    }
+}
@@ -1,4 +1,5 @@
-use crate::{bool_from_any_row, json_from_db_text, DbPool};
+use crate::{bool_from_any_row, datetime_to_db_text, json_from_db_text, DbPool};
+use chrono::Utc;
 use serde_json::Value;
 use sqlx::Row;
 
@@ -145,7 +146,8 @@ pub async fn get_federated_server(
     server_name: &str,
 ) -> Result<Option<FederatedServerRow>, sqlx::Error> {
     sqlx::query_as::<_, FederatedServerRow>(
-        "SELECT id, server_name, domain, federation_endpoint, public_key_hex, key_id, trusted, last_seen_at, created_at
+        "SELECT id, server_name, domain, federation_endpoint, public_key_hex, key_id,
+                CASE WHEN trusted THEN 1 ELSE 0 END AS trusted, last_seen_at, created_at
          FROM federated_servers WHERE server_name = $1",
     )
     .bind(server_name)
@@ -159,7 +161,8 @@ pub async fn get_federated_server_by_id(
     id: i64,
 ) -> Result<Option<FederatedServerRow>, sqlx::Error> {
     sqlx::query_as::<_, FederatedServerRow>(
-        "SELECT id, server_name, domain, federation_endpoint, public_key_hex, key_id, trusted, last_seen_at, created_at
+        "SELECT id, server_name, domain, federation_endpoint, public_key_hex, key_id,
+                CASE WHEN trusted THEN 1 ELSE 0 END AS trusted, last_seen_at, created_at
          FROM federated_servers WHERE id = $1",
     )
     .bind(id)
@@ -170,7 +173,8 @@ pub async fn get_federated_server_by_id(
 /// List all known federated servers.
 pub async fn list_federated_servers(pool: &DbPool) -> Result<Vec<FederatedServerRow>, sqlx::Error> {
     sqlx::query_as::<_, FederatedServerRow>(
-        "SELECT id, server_name, domain, federation_endpoint, public_key_hex, key_id, trusted, last_seen_at, created_at
+        "SELECT id, server_name, domain, federation_endpoint, public_key_hex, key_id,
+                CASE WHEN trusted THEN 1 ELSE 0 END AS trusted, last_seen_at, created_at
          FROM federated_servers ORDER BY created_at ASC",
     )
     .fetch_all(pool)
@@ -182,7 +186,8 @@ pub async fn list_trusted_federated_servers(
     pool: &DbPool,
 ) -> Result<Vec<FederatedServerRow>, sqlx::Error> {
     sqlx::query_as::<_, FederatedServerRow>(
-        "SELECT id, server_name, domain, federation_endpoint, public_key_hex, key_id, trusted, last_seen_at, created_at
+        "SELECT id, server_name, domain, federation_endpoint, public_key_hex, key_id,
+                CASE WHEN trusted THEN 1 ELSE 0 END AS trusted, last_seen_at, created_at
          FROM federated_servers WHERE trusted = TRUE ORDER BY created_at ASC",
     )
     .fetch_all(pool)
@@ -203,12 +208,11 @@ pub async fn delete_federated_server(
 
 /// Update the last_seen_at timestamp for a federated server.
 pub async fn touch_federated_server(pool: &DbPool, server_name: &str) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        "UPDATE federated_servers SET last_seen_at = datetime('now') WHERE server_name = $1",
-    )
-    .bind(server_name)
-    .execute(pool)
-    .await?;
+    sqlx::query("UPDATE federated_servers SET last_seen_at = $2 WHERE server_name = $1")
+        .bind(server_name)
+        .bind(datetime_to_db_text(Utc::now()))
+        .execute(pool)
+        .await?;
     Ok(())
 }
 
@@ -807,26 +811,110 @@ pub async fn upsert_room_sync_cursor(
     Ok(())
 }
 
-/// Purge expired outbound events that have exceeded max retry attempts or age.
-pub async fn purge_expired_outbound_events(
+/// Move outbound events that have exceeded max retry attempts or age into the
+/// dead-letter table. Returns the number of events moved.
+pub async fn dead_letter_expired_outbound_events(
     pool: &DbPool,
     now_ms: i64,
     max_attempts: i64,
     max_age_ms: i64,
 ) -> Result<u64, sqlx::Error> {
     let cutoff_ms = now_ms.saturating_sub(max_age_ms);
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO federation_outbound_dead_letters (
+             destination_server, event_id, room_id, event_type, sender, origin_server, origin_ts,
+             content, depth, state_key, signatures, attempt_count, last_error, created_at_ms,
+             dead_lettered_at_ms
+         )
+         SELECT
+             destination_server, event_id, room_id, event_type, sender, origin_server, origin_ts,
+             content, depth, state_key, signatures, attempt_count, last_error, created_at_ms, $3
+         FROM federation_outbound_queue
+         WHERE attempt_count >= $1 OR created_at_ms < $2",
+    )
+    .bind(max_attempts)
+    .bind(cutoff_ms)
+    .bind(now_ms)
+    .execute(&mut *tx)
+    .await?;
     let rows = sqlx::query(
         "DELETE FROM federation_outbound_queue
          WHERE attempt_count >= $1 OR created_at_ms < $2",
     )
     .bind(max_attempts)
     .bind(cutoff_ms)
-    .execute(pool)
+    .execute(&mut *tx)
     .await?
     .rows_affected();
+    tx.commit().await?;
     Ok(rows)
 }
 
+/// Push every queued event for a destination back to at least
+/// `next_attempt_at_ms` without using up their attempts, so one unreachable
+/// server is not retried once per queued event.
+pub async fn defer_outbound_destination(
+    pool: &DbPool,
+    destination_server: &str,
+    next_attempt_at_ms: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE federation_outbound_queue
+         SET next_attempt_at_ms = $2
+         WHERE destination_server = $1
+           AND next_attempt_at_ms < $2",
+    )
+    .bind(destination_server)
+    .bind(next_attempt_at_ms)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct OutboundQueueStats {
+    /// Events waiting to be delivered.
+    pub queued: i64,
+    /// Queued events that have failed at least once.
+    pub retrying: i64,
+    /// Events that were given up on.
+    pub dead_lettered: i64,
+    /// Failed delivery attempts since the requested instant.
+    pub failed_attempts: i64,
+}
+
+pub async fn outbound_queue_stats(
+    pool: &DbPool,
+    failures_since_ms: i64,
+) -> Result<OutboundQueueStats, sqlx::Error> {
+    let (queued, retrying): (i64, i64) = sqlx::query_as(
+        "SELECT
+             COUNT(*),
+             COALESCE(SUM(CASE WHEN attempt_count > 0 THEN 1 ELSE 0 END), 0)
+         FROM federation_outbound_queue",
+    )
+    .fetch_one(pool)
+    .await?;
+    let (dead_lettered,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM federation_outbound_dead_letters")
+            .fetch_one(pool)
+            .await?;
+    let (failed_attempts,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM federation_delivery_attempts
+         WHERE success = FALSE AND attempted_at_ms >= $1",
+    )
+    .bind(failures_since_ms)
+    .fetch_one(pool)
+    .await?;
+    Ok(OutboundQueueStats {
+        queued,
+        retrying,
+        dead_lettered,
+        failed_attempts,
+    })
+}
+
 /// Store or replace the local server's ed25519 keypair (singleton row, id=1).
 pub async fn upsert_server_keypair(
     pool: &DbPool,
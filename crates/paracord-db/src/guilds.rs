@@ -1,4 +1,5 @@
-use crate::{datetime_from_db_text, DbError, DbPool};
+use crate::channels::RemovedFiles;
+use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
 use chrono::{DateTime, Utc};
 use sqlx::Row;
 use std::collections::HashSet;
@@ -13,6 +14,7 @@ pub struct SpaceRow {
     pub owner_id: i64,
     pub features: i32,
     pub system_channel_id: Option<i64>,
+    pub system_channel_flags: i32,
     pub vanity_url_code: Option<String>,
     pub visibility: String,
     pub allowed_roles: String,
@@ -33,6 +35,7 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for SpaceRow {
             owner_id: row.try_get("owner_id")?,
             features: row.try_get("features")?,
             system_channel_id: row.try_get("system_channel_id")?,
+            system_channel_flags: row.try_get("system_channel_flags")?,
             vanity_url_code: row.try_get("vanity_url_code")?,
             visibility: row.try_get("visibility")?,
             allowed_roles: row.try_get("allowed_roles")?,
@@ -43,6 +46,86 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for SpaceRow {
     }
 }
 
+/// `spaces.features` bit allowing an animated (GIF) icon. Granted by server
+/// admins.
+pub const SPACE_FEATURE_ANIMATED_ICON: i32 = 1 << 0;
+
+/// API names of the space feature bits.
+pub const SPACE_FEATURE_NAMES: &[(i32, &str)] = &[(SPACE_FEATURE_ANIMATED_ICON, "ANIMATED_ICON")];
+
+/// Feature bits for a list of feature names, or `None` if a name is unknown.
+pub fn space_features_from_names<S: AsRef<str>>(names: &[S]) -> Option<i32> {
+    names.iter().try_fold(0, |bits, name| {
+        SPACE_FEATURE_NAMES
+            .iter()
+            .find(|(_, known)| *known == name.as_ref())
+            .map(|(bit, _)| bits | bit)
+    })
+}
+
+impl SpaceRow {
+    pub fn has_feature(&self, feature: i32) -> bool {
+        self.features & feature != 0
+    }
+
+    /// Names of the features set on the space.
+    pub fn feature_names(&self) -> Vec<&'static str> {
+        SPACE_FEATURE_NAMES
+            .iter()
+            .filter(|(bit, _)| self.has_feature(*bit))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+/// Which uploaded image of a space a hash belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpaceImage {
+    Icon,
+    Banner,
+}
+
+/// Who may see and join a space, parsed from `spaces.visibility`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpaceVisibility {
+    /// Listed in discovery and joined automatically on registration.
+    Public,
+    /// Joinable by invite only.
+    Private,
+    /// Joinable and visible only to users holding one of `allowed_roles`.
+    Roles,
+}
+
+impl SpaceVisibility {
+    /// Unknown values are treated as private so a typo never opens a space up.
+    pub fn parse(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "public" => Self::Public,
+            "roles" => Self::Roles,
+            _ => Self::Private,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Public => "public",
+            Self::Private => "private",
+            Self::Roles => "roles",
+        }
+    }
+}
+
+impl SpaceRow {
+    pub fn visibility(&self) -> SpaceVisibility {
+        SpaceVisibility::parse(&self.visibility)
+    }
+
+    /// Roles that grant access to a role-gated space.
+    pub fn allowed_role_ids(&self) -> Vec<i64> {
+        parse_allowed_role_ids(&self.allowed_roles)
+    }
+}
+
 // Backward compat alias
 pub type GuildRow = SpaceRow;
 
@@ -56,7 +139,7 @@ pub async fn create_space(
     let row = sqlx::query_as::<_, SpaceRow>(
         "INSERT INTO spaces (id, name, owner_id, icon_hash)
          VALUES ($1, $2, $3, $4)
-         RETURNING id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings"
+         RETURNING id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, system_channel_flags, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings"
     )
     .bind(id)
     .bind(name)
@@ -79,7 +162,7 @@ pub async fn create_guild(
 
 pub async fn get_space(pool: &DbPool, id: i64) -> Result<Option<SpaceRow>, DbError> {
     let row = sqlx::query_as::<_, SpaceRow>(
-        "SELECT id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings
+        "SELECT id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, system_channel_flags, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings
          FROM spaces WHERE id = $1"
     )
     .bind(id)
@@ -108,9 +191,9 @@ pub async fn update_space(
              icon_hash = COALESCE($4, icon_hash),
              hub_settings = COALESCE($5, hub_settings),
              bot_settings = COALESCE($6, bot_settings),
-             updated_at = datetime('now')
+             updated_at = $7
          WHERE id = $1
-         RETURNING id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings"
+         RETURNING id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, system_channel_flags, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings"
     )
     .bind(id)
     .bind(name)
@@ -118,11 +201,53 @@ pub async fn update_space(
     .bind(icon_hash)
     .bind(hub_settings)
     .bind(bot_settings)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_one(pool)
     .await?;
     Ok(row)
 }
 
+/// Point the space's icon or banner at `hash`, or clear it with `None`.
+pub async fn set_space_image(
+    pool: &DbPool,
+    id: i64,
+    image: SpaceImage,
+    hash: Option<&str>,
+) -> Result<SpaceRow, DbError> {
+    let sql = match image {
+        SpaceImage::Icon => "UPDATE spaces SET icon_hash = $2, updated_at = $3 WHERE id = $1
+         RETURNING id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, system_channel_flags, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings",
+        SpaceImage::Banner => "UPDATE spaces SET banner_hash = $2, updated_at = $3 WHERE id = $1
+         RETURNING id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, system_channel_flags, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings",
+    };
+    let row = sqlx::query_as::<_, SpaceRow>(sql)
+        .bind(id)
+        .bind(hash)
+        .bind(datetime_to_db_text(Utc::now()))
+        .fetch_optional(pool)
+        .await?
+        .ok_or(DbError::NotFound)?;
+    Ok(row)
+}
+
+pub async fn set_space_features(
+    pool: &DbPool,
+    id: i64,
+    features: i32,
+) -> Result<SpaceRow, DbError> {
+    let row = sqlx::query_as::<_, SpaceRow>(
+        "UPDATE spaces SET features = $2, updated_at = $3 WHERE id = $1
+         RETURNING id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, system_channel_flags, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings",
+    )
+    .bind(id)
+    .bind(features)
+    .bind(datetime_to_db_text(Utc::now()))
+    .fetch_optional(pool)
+    .await?
+    .ok_or(DbError::NotFound)?;
+    Ok(row)
+}
+
 pub async fn update_guild(
     pool: &DbPool,
     id: i64,
@@ -145,33 +270,170 @@ pub async fn update_space_visibility(
         "UPDATE spaces
          SET visibility = $2,
              allowed_roles = $3,
-             updated_at = datetime('now')
+             updated_at = $4
          WHERE id = $1
-         RETURNING id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings"
+         RETURNING id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, system_channel_flags, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings"
     )
     .bind(id)
     .bind(visibility)
     .bind(allowed_roles)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_one(pool)
     .await?;
     Ok(row)
 }
 
-pub async fn delete_space(pool: &DbPool, id: i64) -> Result<(), DbError> {
-    sqlx::query("DELETE FROM spaces WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await?;
-    Ok(())
+/// Set where join/leave announcements are posted (`None` turns them off)
+/// and which of them are suppressed.
+pub async fn update_system_channel(
+    pool: &DbPool,
+    id: i64,
+    system_channel_id: Option<i64>,
+    system_channel_flags: i32,
+) -> Result<SpaceRow, DbError> {
+    let row = sqlx::query_as::<_, SpaceRow>(
+        "UPDATE spaces
+         SET system_channel_id = $2,
+             system_channel_flags = $3,
+             updated_at = $4
+         WHERE id = $1
+         RETURNING id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, system_channel_flags, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings"
+    )
+    .bind(id)
+    .bind(system_channel_id)
+    .bind(system_channel_flags)
+    .bind(datetime_to_db_text(Utc::now()))
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Set or clear a space's vanity invite code. Returns `None` without changing
+/// anything when another space already holds `code`.
+pub async fn set_vanity_url_code(
+    pool: &DbPool,
+    id: i64,
+    code: Option<&str>,
+) -> Result<Option<SpaceRow>, DbError> {
+    let row = sqlx::query_as::<_, SpaceRow>(
+        "UPDATE spaces
+         SET vanity_url_code = $2,
+             updated_at = $3
+         WHERE id = $1
+           AND NOT EXISTS (
+                SELECT 1 FROM spaces other
+                WHERE other.vanity_url_code = $2
+                  AND other.id <> $1
+           )
+         RETURNING id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, system_channel_flags, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings"
+    )
+    .bind(id)
+    .bind(code)
+    .bind(datetime_to_db_text(Utc::now()))
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_space_by_vanity_code(
+    pool: &DbPool,
+    code: &str,
+) -> Result<Option<SpaceRow>, DbError> {
+    let row = sqlx::query_as::<_, SpaceRow>(
+        "SELECT id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, system_channel_flags, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings
+         FROM spaces WHERE vanity_url_code = $1",
+    )
+    .bind(code)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Rows that belong to a space once its channels are gone, in dependency
+/// order. Each statement takes the space id as `$1`.
+const SPACE_CLEANUP_STATEMENTS: &[&str] = &[
+    "DELETE FROM reactions WHERE emoji_id IN (SELECT id FROM emojis WHERE space_id = $1)",
+    "DELETE FROM emojis WHERE space_id = $1",
+    "DELETE FROM member_roles WHERE role_id IN (SELECT id FROM roles WHERE space_id = $1)",
+    "DELETE FROM roles WHERE space_id = $1",
+    "DELETE FROM members WHERE guild_id = $1",
+    "DELETE FROM bans WHERE guild_id = $1",
+    "DELETE FROM invite_uses WHERE guild_id = $1",
+    "DELETE FROM webhooks WHERE space_id = $1",
+    "DELETE FROM event_rsvps WHERE event_id IN (SELECT id FROM scheduled_events WHERE guild_id = $1)",
+    "DELETE FROM scheduled_events WHERE guild_id = $1",
+    "DELETE FROM voice_states WHERE space_id = $1",
+    "DELETE FROM interactions WHERE guild_id = $1",
+    "DELETE FROM bot_guild_installs WHERE guild_id = $1",
+    "DELETE FROM automod_rules WHERE space_id = $1",
+    "DELETE FROM audit_log_entries WHERE space_id = $1",
+    "DELETE FROM guild_storage_policies WHERE guild_id = $1",
+    "DELETE FROM federation_channel_map WHERE local_guild_id = $1",
+    "DELETE FROM federation_space_map WHERE local_guild_id = $1",
+    "DELETE FROM federation_room_memberships WHERE guild_id = $1",
+    "DELETE FROM notification_settings WHERE target_id = $1",
+    "DELETE FROM spaces WHERE id = $1",
+];
+
+/// Delete a space with its channels, messages, roles, emojis and everything
+/// else stored under it in one transaction. Returns the files to remove
+/// from storage.
+pub async fn delete_space(pool: &DbPool, id: i64) -> Result<RemovedFiles, DbError> {
+    let mut tx = pool.begin().await?;
+    let mut removed = RemovedFiles::default();
+
+    let channel_ids: Vec<i64> =
+        sqlx::query_scalar("SELECT id FROM channels WHERE space_id = $1 ORDER BY id")
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?;
+    for channel_id in channel_ids {
+        crate::channels::purge_channel(&mut tx, channel_id, &mut removed).await?;
+    }
+
+    let emojis: Vec<(i64, i32)> = sqlx::query_as(
+        "SELECT id, CASE WHEN animated THEN 1 ELSE 0 END FROM emojis WHERE space_id = $1",
+    )
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+    removed.emojis = emojis
+        .into_iter()
+        .map(|(emoji_id, animated)| (emoji_id, animated != 0))
+        .collect();
+    removed.role_icons = sqlx::query_as(
+        "SELECT id, icon_hash FROM roles WHERE space_id = $1 AND icon_hash IS NOT NULL",
+    )
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let images: Option<(Option<String>, Option<String>)> =
+        sqlx::query_as("SELECT icon_hash, banner_hash FROM spaces WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let (icon_hash, banner_hash) = images.unwrap_or_default();
+    if let Some(hash) = icon_hash {
+        removed.space_icons.push((id, hash));
+    }
+    if let Some(hash) = banner_hash {
+        removed.space_banners.push((id, hash));
+    }
+
+    for statement in SPACE_CLEANUP_STATEMENTS {
+        sqlx::query(statement).bind(id).execute(&mut *tx).await?;
+    }
+    tx.commit().await?;
+    Ok(removed)
 }
 
-pub async fn delete_guild(pool: &DbPool, id: i64) -> Result<(), DbError> {
+pub async fn delete_guild(pool: &DbPool, id: i64) -> Result<RemovedFiles, DbError> {
     delete_space(pool, id).await
 }
 
 pub async fn list_all_spaces(pool: &DbPool) -> Result<Vec<SpaceRow>, DbError> {
     let rows = sqlx::query_as::<_, SpaceRow>(
-        "SELECT id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings
+        "SELECT id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, system_channel_flags, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings
          FROM spaces
          ORDER BY created_at ASC"
     )
@@ -183,7 +445,7 @@ pub async fn list_all_spaces(pool: &DbPool) -> Result<Vec<SpaceRow>, DbError> {
 pub async fn get_user_guilds(pool: &DbPool, user_id: i64) -> Result<Vec<SpaceRow>, DbError> {
     let rows = sqlx::query_as::<_, SpaceRow>(
         "SELECT s.id, s.name, s.description, s.icon_hash, s.banner_hash, s.owner_id, s.features,
-                s.system_channel_id, s.vanity_url_code, s.visibility, s.allowed_roles, s.created_at, s.hub_settings, s.bot_settings
+                s.system_channel_id, s.system_channel_flags, s.vanity_url_code, s.visibility, s.allowed_roles, s.created_at, s.hub_settings, s.bot_settings
          FROM spaces s
          INNER JOIN members m ON m.guild_id = s.id
          WHERE m.user_id = $1
@@ -193,41 +455,89 @@ pub async fn get_user_guilds(pool: &DbPool, user_id: i64) -> Result<Vec<SpaceRow
     .fetch_all(pool)
     .await?;
 
-    let mut visible = Vec::with_capacity(rows.len());
-    for row in rows {
-        if row.visibility != "roles" {
-            visible.push(row);
-            continue;
-        }
+    filter_accessible_spaces(pool, rows, user_id).await
+}
 
-        let allowed_roles = parse_allowed_role_ids(&row.allowed_roles);
-        if allowed_roles.is_empty() {
-            visible.push(row);
-            continue;
-        }
+/// Spaces `user_id` is a member of with ids above `after`, ordered by id for
+/// keyset paging. Visibility is not applied; pass the page through
+/// [`filter_accessible_spaces`] once its cursor has been taken.
+pub async fn list_user_guilds_page(
+    pool: &DbPool,
+    user_id: i64,
+    after: Option<i64>,
+    limit: i64,
+) -> Result<Vec<SpaceRow>, DbError> {
+    let rows = sqlx::query_as::<_, SpaceRow>(
+        "SELECT s.id, s.name, s.description, s.icon_hash, s.banner_hash, s.owner_id, s.features,
+                s.system_channel_id, s.system_channel_flags, s.vanity_url_code, s.visibility, s.allowed_roles, s.created_at, s.hub_settings, s.bot_settings
+         FROM spaces s
+         INNER JOIN members m ON m.guild_id = s.id
+         WHERE m.user_id = $1 AND s.id > $2
+         ORDER BY s.id ASC
+         LIMIT $3",
+    )
+    .bind(user_id)
+    .bind(after.unwrap_or(0))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
 
-        let member_role_rows: Vec<(i64,)> = sqlx::query_as(
-            "SELECT mr.role_id
-             FROM member_roles mr
-             INNER JOIN roles r ON r.id = mr.role_id
-             WHERE mr.user_id = $1
-               AND r.space_id = $2",
-        )
-        .bind(user_id)
-        .bind(row.id)
-        .fetch_all(pool)
-        .await?;
+/// Keep the spaces whose visibility lets `user_id` in, loading the user's
+/// roles at most once for the whole list.
+pub async fn filter_accessible_spaces(
+    pool: &DbPool,
+    spaces: Vec<SpaceRow>,
+    user_id: i64,
+) -> Result<Vec<SpaceRow>, DbError> {
+    let held_role_ids = if spaces.iter().any(|s| is_role_gated_for(s, user_id)) {
+        get_held_role_ids(pool, user_id).await?
+    } else {
+        HashSet::new()
+    };
+    Ok(spaces
+        .into_iter()
+        .filter(|s| !is_role_gated_for(s, user_id) || holds_allowed_role(s, &held_role_ids))
+        .collect())
+}
 
-        let user_roles: HashSet<i64> = member_role_rows.into_iter().map(|(id,)| id).collect();
-        if allowed_roles
-            .into_iter()
-            .any(|role_id| user_roles.contains(&role_id))
-        {
-            visible.push(row);
-        }
+/// Whether the space's visibility lets `user_id` see and join it. Role-gated
+/// spaces require one of the allowed roles; the owner always has access, as
+/// does everyone when no roles are configured.
+pub async fn can_access_space(
+    pool: &DbPool,
+    space: &SpaceRow,
+    user_id: i64,
+) -> Result<bool, DbError> {
+    if !is_role_gated_for(space, user_id) {
+        return Ok(true);
     }
+    let held_role_ids = get_held_role_ids(pool, user_id).await?;
+    Ok(holds_allowed_role(space, &held_role_ids))
+}
 
-    Ok(visible)
+/// Whether access to `space` depends on the roles `user_id` holds.
+fn is_role_gated_for(space: &SpaceRow, user_id: i64) -> bool {
+    space.visibility() == SpaceVisibility::Roles
+        && space.owner_id != user_id
+        && !space.allowed_role_ids().is_empty()
+}
+
+fn holds_allowed_role(space: &SpaceRow, held_role_ids: &HashSet<i64>) -> bool {
+    space
+        .allowed_role_ids()
+        .iter()
+        .any(|role_id| held_role_ids.contains(role_id))
+}
+
+async fn get_held_role_ids(pool: &DbPool, user_id: i64) -> Result<HashSet<i64>, DbError> {
+    let held_role_ids: Vec<i64> =
+        sqlx::query_scalar("SELECT role_id FROM member_roles WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_all(pool)
+            .await?;
+    Ok(held_role_ids.into_iter().collect())
 }
 
 pub fn parse_allowed_role_ids(raw: &str) -> Vec<i64> {
@@ -255,12 +565,13 @@ pub async fn transfer_ownership(
     new_owner_id: i64,
 ) -> Result<SpaceRow, DbError> {
     let row = sqlx::query_as::<_, SpaceRow>(
-        "UPDATE spaces SET owner_id = $2, updated_at = datetime('now')
+        "UPDATE spaces SET owner_id = $2, updated_at = $3
          WHERE id = $1
-         RETURNING id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings"
+         RETURNING id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, system_channel_flags, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings"
     )
     .bind(space_id)
     .bind(new_owner_id)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -353,6 +664,25 @@ mod tests {
         assert_eq!(updated.description.as_deref(), Some("desc only"));
     }
 
+    #[tokio::test]
+    async fn test_update_system_channel() {
+        let pool = test_pool().await;
+        create_test_user(&pool, 1).await;
+        let guild = create_guild(&pool, 304, "Announced", 1, None)
+            .await
+            .unwrap();
+        assert_eq!(guild.system_channel_id, None);
+        assert_eq!(guild.system_channel_flags, 0);
+
+        let updated = update_system_channel(&pool, 304, Some(900), 2)
+            .await
+            .unwrap();
+        assert_eq!(updated.system_channel_id, Some(900));
+        assert_eq!(updated.system_channel_flags, 2);
+        let cleared = update_system_channel(&pool, 304, None, 0).await.unwrap();
+        assert_eq!(cleared.system_channel_id, None);
+    }
+
     #[tokio::test]
     async fn test_delete_guild() {
         let pool = test_pool().await;
@@ -365,6 +695,82 @@ mod tests {
         assert!(guild.is_none());
     }
 
+    #[tokio::test]
+    async fn delete_guild_leaves_no_orphan_rows() {
+        let pool = test_pool().await;
+        create_test_user(&pool, 1).await;
+        create_test_user(&pool, 2).await;
+        create_guild(&pool, 410, "Doomed", 1, None).await.unwrap();
+        crate::members::add_member(&pool, 2, 410).await.unwrap();
+        crate::channels::create_channel(&pool, 411, 410, "general", 0, 0, None, None)
+            .await
+            .unwrap();
+        crate::messages::create_message(&pool, 412, 411, 2, "hi", 0, None)
+            .await
+            .unwrap();
+        crate::attachments::create_attachment(
+            &pool,
+            413,
+            Some(412),
+            "cat.png",
+            Some("image/png"),
+            10,
+            "/x",
+            None,
+            None,
+            Some(2),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        crate::channel_overwrites::upsert_channel_overwrite(&pool, 411, 2, 1, 0, 1024)
+            .await
+            .unwrap();
+        crate::invites::create_invite(&pool, "doomed", 410, 411, 1, None, None, false)
+            .await
+            .unwrap();
+        crate::emojis::create_emoji(&pool, 414, 410, "wave", 1, true)
+            .await
+            .unwrap();
+        crate::roles::create_role(&pool, 415, 410, "Mods", 0)
+            .await
+            .unwrap();
+        crate::roles::set_role_icon(&pool, 415, Some("iconhash"))
+            .await
+            .unwrap();
+        crate::roles::add_member_role(&pool, 2, 410, 415)
+            .await
+            .unwrap();
+        set_space_image(&pool, 410, SpaceImage::Icon, Some("spaceicon"))
+            .await
+            .unwrap();
+
+        let removed = delete_guild(&pool, 410).await.unwrap();
+        assert_eq!(removed.attachments, vec![(413, "cat.png".to_string())]);
+        assert_eq!(removed.emojis, vec![(414, true)]);
+        assert_eq!(removed.role_icons, vec![(415, "iconhash".to_string())]);
+        assert_eq!(removed.space_icons, vec![(410, "spaceicon".to_string())]);
+        assert!(removed.space_banners.is_empty());
+
+        for sql in [
+            "SELECT COUNT(*) FROM spaces WHERE id = 410",
+            "SELECT COUNT(*) FROM channels WHERE space_id = 410",
+            "SELECT COUNT(*) FROM messages",
+            "SELECT COUNT(*) FROM attachments",
+            "SELECT COUNT(*) FROM channel_overwrites",
+            "SELECT COUNT(*) FROM invites",
+            "SELECT COUNT(*) FROM emojis",
+            "SELECT COUNT(*) FROM roles WHERE space_id = 410",
+            "SELECT COUNT(*) FROM member_roles",
+            "SELECT COUNT(*) FROM members WHERE guild_id = 410",
+        ] {
+            let (count,): (i64,) = sqlx::query_as(sql).fetch_one(&pool).await.unwrap();
+            assert_eq!(count, 0, "{sql}");
+        }
+    }
+
     #[tokio::test]
     async fn test_list_user_guilds() {
         let pool = test_pool().await;
@@ -377,6 +783,30 @@ mod tests {
         assert_eq!(guilds.len(), 2);
     }
 
+    #[tokio::test]
+    async fn list_user_guilds_page_walks_memberships_by_id() {
+        let pool = test_pool().await;
+        create_test_user(&pool, 1).await;
+        for id in [502, 500, 501] {
+            create_guild(&pool, id, "Guild", 1, None).await.unwrap();
+            crate::members::add_member(&pool, 1, id).await.unwrap();
+        }
+        let first: Vec<i64> = list_user_guilds_page(&pool, 1, None, 2)
+            .await
+            .unwrap()
+            .iter()
+            .map(|g| g.id)
+            .collect();
+        assert_eq!(first, vec![500, 501]);
+        let rest: Vec<i64> = list_user_guilds_page(&pool, 1, Some(501), 2)
+            .await
+            .unwrap()
+            .iter()
+            .map(|g| g.id)
+            .collect();
+        assert_eq!(rest, vec![502]);
+    }
+
     #[tokio::test]
     async fn test_count_guilds() {
         let pool = test_pool().await;
@@ -429,4 +859,122 @@ mod tests {
         assert_eq!(parse_allowed_role_ids("[]"), Vec::<i64>::new());
         assert_eq!(parse_allowed_role_ids("invalid"), Vec::<i64>::new());
     }
+
+    #[tokio::test]
+    async fn test_vanity_url_code_is_unique_per_space() {
+        let pool = test_pool().await;
+        create_test_user(&pool, 1).await;
+        create_guild(&pool, 100, "First", 1, None).await.unwrap();
+        create_guild(&pool, 101, "Second", 1, None).await.unwrap();
+
+        let updated = set_vanity_url_code(&pool, 100, Some("cool-space"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.vanity_url_code.as_deref(), Some("cool-space"));
+        // Re-setting the same code on the owning space is fine.
+        assert!(set_vanity_url_code(&pool, 100, Some("cool-space"))
+            .await
+            .unwrap()
+            .is_some());
+        assert!(set_vanity_url_code(&pool, 101, Some("cool-space"))
+            .await
+            .unwrap()
+            .is_none());
+
+        let found = get_space_by_vanity_code(&pool, "cool-space")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, 100);
+
+        let cleared = set_vanity_url_code(&pool, 100, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(cleared.vanity_url_code.is_none());
+        assert!(get_space_by_vanity_code(&pool, "cool-space")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn space_images_and_features_can_be_set_and_cleared() {
+        let pool = test_pool().await;
+        create_test_user(&pool, 1).await;
+        create_guild(&pool, 420, "Pictures", 1, None).await.unwrap();
+
+        let space = set_space_image(&pool, 420, SpaceImage::Banner, Some("wide"))
+            .await
+            .unwrap();
+        assert_eq!(space.banner_hash.as_deref(), Some("wide"));
+        assert!(space.icon_hash.is_none());
+        let space = set_space_image(&pool, 420, SpaceImage::Banner, None)
+            .await
+            .unwrap();
+        assert!(space.banner_hash.is_none());
+
+        assert!(!space.has_feature(SPACE_FEATURE_ANIMATED_ICON));
+        let space = set_space_features(&pool, 420, SPACE_FEATURE_ANIMATED_ICON)
+            .await
+            .unwrap();
+        assert!(space.has_feature(SPACE_FEATURE_ANIMATED_ICON));
+        assert_eq!(space.feature_names(), vec!["ANIMATED_ICON"]);
+        assert_eq!(
+            space_features_from_names(&["ANIMATED_ICON"]),
+            Some(SPACE_FEATURE_ANIMATED_ICON)
+        );
+        assert_eq!(space_features_from_names(&["VANITY"]), None);
+        assert!(matches!(
+            set_space_features(&pool, 999, 0).await,
+            Err(DbError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_role_gated_spaces_require_an_allowed_role() {
+        let pool = test_pool().await;
+        create_test_user(&pool, 1).await;
+        create_test_user(&pool, 2).await;
+        create_guild(&pool, 100, "Lobby", 1, None).await.unwrap();
+        create_guild(&pool, 101, "Inner Circle", 1, None)
+            .await
+            .unwrap();
+        crate::roles::create_role(&pool, 500, 100, "Verified", 0)
+            .await
+            .unwrap();
+        crate::members::add_member(&pool, 2, 100).await.unwrap();
+        crate::members::add_member(&pool, 2, 101).await.unwrap();
+        let gated = update_space_visibility(&pool, 101, "roles", "[500]")
+            .await
+            .unwrap();
+        assert_eq!(gated.visibility(), SpaceVisibility::Roles);
+        assert_eq!(gated.allowed_role_ids(), vec![500]);
+
+        assert!(can_access_space(&pool, &gated, 1).await.unwrap());
+        assert!(!can_access_space(&pool, &gated, 2).await.unwrap());
+        let listed: Vec<i64> = get_user_guilds(&pool, 2)
+            .await
+            .unwrap()
+            .iter()
+            .map(|space| space.id)
+            .collect();
+        assert_eq!(listed, vec![100]);
+
+        // A role held in another space unlocks it.
+        crate::roles::add_member_role(&pool, 2, 100, 500)
+            .await
+            .unwrap();
+        assert!(can_access_space(&pool, &gated, 2).await.unwrap());
+        assert_eq!(get_user_guilds(&pool, 2).await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_space_visibility_parse_defaults_to_private() {
+        assert_eq!(SpaceVisibility::parse("Public"), SpaceVisibility::Public);
+        assert_eq!(SpaceVisibility::parse("roles"), SpaceVisibility::Roles);
+        assert_eq!(SpaceVisibility::parse("secret"), SpaceVisibility::Private);
+        assert_eq!(SpaceVisibility::Private.as_str(), "private");
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{bool_from_any_row, datetime_from_db_text, DbError, DbPool};
+use crate::{bool_from_any_row, datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
 use chrono::{DateTime, Utc};
 use sqlx::Row;
 
@@ -14,6 +14,76 @@ pub struct InviteRow {
     pub created_at: DateTime<Utc>,
 }
 
+impl InviteRow {
+    /// When the invite stops working; `None` for invites that never expire.
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.max_age
+            .filter(|max_age| *max_age > 0)
+            .map(|max_age| self.created_at + chrono::Duration::seconds(i64::from(max_age)))
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at()
+            .is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Uses left before the invite is exhausted; `None` for unlimited invites.
+    pub fn remaining_uses(&self) -> Option<i32> {
+        self.max_uses
+            .filter(|max_uses| *max_uses > 0)
+            .map(|max_uses| (max_uses - self.uses).max(0))
+    }
+}
+
+/// A member joining through an invite.
+#[derive(Debug, Clone)]
+pub struct InviteUseRow {
+    pub id: i64,
+    pub code: String,
+    pub guild_id: i64,
+    pub inviter_id: Option<i64>,
+    pub user_id: i64,
+    pub used_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for InviteUseRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let used_at_raw: String = row.try_get("used_at")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            code: row.try_get("code")?,
+            guild_id: row.try_get("guild_id")?,
+            inviter_id: row.try_get("inviter_id")?,
+            user_id: row.try_get("user_id")?,
+            used_at: datetime_from_db_text(&used_at_raw)?,
+        })
+    }
+}
+
+/// Joins attributed to one invite code over a window.
+#[derive(Debug, Clone)]
+pub struct InviteStatsRow {
+    pub code: String,
+    pub inviter_id: Option<i64>,
+    pub joins: i64,
+    pub first_used_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for InviteStatsRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let first_used_at_raw: String = row.try_get("first_used_at")?;
+        let last_used_at_raw: String = row.try_get("last_used_at")?;
+        Ok(Self {
+            code: row.try_get("code")?,
+            inviter_id: row.try_get("inviter_id")?,
+            joins: row.try_get("joins")?,
+            first_used_at: datetime_from_db_text(&first_used_at_raw)?,
+            last_used_at: datetime_from_db_text(&last_used_at_raw)?,
+        })
+    }
+}
+
 impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for InviteRow {
     fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
         let created_at_raw: String = row.try_get("created_at")?;
@@ -30,6 +100,7 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for InviteRow {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn create_invite(
     pool: &DbPool,
     code: &str,
@@ -38,10 +109,11 @@ pub async fn create_invite(
     inviter_id: i64,
     max_uses: Option<i32>,
     max_age: Option<i32>,
+    temporary: bool,
 ) -> Result<InviteRow, DbError> {
     let row = sqlx::query_as::<_, InviteRow>(
-        "INSERT INTO invites (code, channel_id, inviter_id, max_uses, max_age)
-         SELECT $1, $2, $3, $4, $5
+        "INSERT INTO invites (code, channel_id, inviter_id, max_uses, max_age, temporary)
+         SELECT $1, $2, $3, $4, $5, $7
          WHERE EXISTS (
              SELECT 1
              FROM channels c
@@ -56,6 +128,7 @@ pub async fn create_invite(
     .bind(max_uses)
     .bind(max_age)
     .bind(guild_id)
+    .bind(temporary)
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -65,25 +138,24 @@ pub async fn get_invite(pool: &DbPool, code: &str) -> Result<Option<InviteRow>,
     let row = sqlx::query_as::<_, InviteRow>(
         "SELECT code, channel_id, inviter_id, max_uses, uses, max_age, CASE WHEN temporary THEN 1 ELSE 0 END AS temporary, created_at
          FROM invites WHERE code = $1
-           AND (max_age IS NULL OR max_age = 0 OR datetime(created_at, '+' || max_age || ' seconds') > datetime('now'))",
+           AND (max_age IS NULL OR max_age = 0 OR datetime(created_at, '+' || max_age || ' seconds') > $2)",
     )
     .bind(code)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_optional(pool)
     .await?;
     Ok(row)
 }
 
-pub async fn use_invite(pool: &DbPool, code: &str) -> Result<Option<InviteRow>, DbError> {
+/// Fetch an invite even if it has expired or run out of uses, so callers can
+/// tell those cases apart from an unknown code.
+pub async fn get_invite_unfiltered(
+    pool: &DbPool,
+    code: &str,
+) -> Result<Option<InviteRow>, DbError> {
     let row = sqlx::query_as::<_, InviteRow>(
-        "UPDATE invites
-         SET uses = uses + 1
-         WHERE code = $1
-           AND (max_uses IS NULL OR max_uses = 0 OR uses < max_uses)
-           AND (
-                max_age IS NULL OR max_age = 0
-                OR datetime(created_at, '+' || max_age || ' seconds') > datetime('now')
-           )
-         RETURNING code, channel_id, inviter_id, max_uses, uses, max_age, CASE WHEN temporary THEN 1 ELSE 0 END AS temporary, created_at",
+        "SELECT code, channel_id, inviter_id, max_uses, uses, max_age, CASE WHEN temporary THEN 1 ELSE 0 END AS temporary, created_at
+         FROM invites WHERE code = $1",
     )
     .bind(code)
     .fetch_optional(pool)
@@ -91,6 +163,121 @@ pub async fn use_invite(pool: &DbPool, code: &str) -> Result<Option<InviteRow>,
     Ok(row)
 }
 
+const USE_INVITE_SQL: &str = "UPDATE invites
+     SET uses = uses + 1
+     WHERE code = $1
+       AND (max_uses IS NULL OR max_uses = 0 OR uses < max_uses)
+       AND (
+            max_age IS NULL OR max_age = 0
+            OR datetime(created_at, '+' || max_age || ' seconds') > $2
+       )
+     RETURNING code, channel_id, inviter_id, max_uses, uses, max_age, CASE WHEN temporary THEN 1 ELSE 0 END AS temporary, created_at";
+
+/// Consume one use of an invite. The check and increment happen in a single
+/// statement so concurrent accepts cannot push `uses` past `max_uses`.
+pub async fn use_invite(pool: &DbPool, code: &str) -> Result<Option<InviteRow>, DbError> {
+    let row = sqlx::query_as::<_, InviteRow>(USE_INVITE_SQL)
+        .bind(code)
+        .bind(datetime_to_db_text(Utc::now()))
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+/// Consume one use of `code`, add `user_id` to `guild_id` and record the
+/// join, all in one transaction: the use count only moves if the membership
+/// and its `invite_uses` row land too. Returns `None` without changing
+/// anything when the invite is unknown, expired or used up.
+pub async fn redeem_invite(
+    pool: &DbPool,
+    use_id: i64,
+    code: &str,
+    guild_id: i64,
+    user_id: i64,
+) -> Result<Option<InviteRow>, DbError> {
+    let mut tx = pool.begin().await?;
+    let Some(invite) = sqlx::query_as::<_, InviteRow>(USE_INVITE_SQL)
+        .bind(code)
+        .bind(datetime_to_db_text(Utc::now()))
+        .fetch_optional(&mut *tx)
+        .await?
+    else {
+        tx.rollback().await?;
+        return Ok(None);
+    };
+    // Existing memberships are left untouched so a permanent member never
+    // becomes temporary.
+    sqlx::query(
+        "INSERT INTO members (user_id, guild_id, temporary) VALUES ($1, $2, $3)
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(guild_id)
+    .bind(invite.temporary)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "INSERT INTO invite_uses (id, code, guild_id, inviter_id, user_id)
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(use_id)
+    .bind(code)
+    .bind(guild_id)
+    .bind(invite.inviter_id)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(Some(invite))
+}
+
+/// Invites with the most joins in `guild_id` since `since`, busiest first.
+/// Codes that have since expired or been deleted are still counted.
+pub async fn get_top_invites(
+    pool: &DbPool,
+    guild_id: i64,
+    since: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<InviteStatsRow>, DbError> {
+    let rows = sqlx::query_as::<_, InviteStatsRow>(
+        "SELECT code, MAX(inviter_id) AS inviter_id, COUNT(*) AS joins,
+                MIN(used_at) AS first_used_at, MAX(used_at) AS last_used_at
+         FROM invite_uses
+         WHERE guild_id = $1 AND used_at >= $2
+         GROUP BY code
+         ORDER BY joins DESC, last_used_at DESC
+         LIMIT $3",
+    )
+    .bind(guild_id)
+    .bind(datetime_to_db_text(since))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Most recent joins through `code` in `guild_id`, newest first.
+pub async fn get_invite_uses(
+    pool: &DbPool,
+    guild_id: i64,
+    code: &str,
+    limit: i64,
+) -> Result<Vec<InviteUseRow>, DbError> {
+    let rows = sqlx::query_as::<_, InviteUseRow>(
+        "SELECT id, code, guild_id, inviter_id, user_id, used_at
+         FROM invite_uses
+         WHERE guild_id = $1 AND code = $2
+         ORDER BY id DESC
+         LIMIT $3",
+    )
+    .bind(guild_id)
+    .bind(code)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
 pub async fn delete_invite(pool: &DbPool, code: &str) -> Result<(), DbError> {
     sqlx::query("DELETE FROM invites WHERE code = $1")
         .bind(code)
@@ -99,16 +286,34 @@ pub async fn delete_invite(pool: &DbPool, code: &str) -> Result<(), DbError> {
     Ok(())
 }
 
+/// Invites whose `max_age` has elapsed, oldest first.
+pub async fn get_expired_invites(pool: &DbPool, limit: i64) -> Result<Vec<InviteRow>, DbError> {
+    let rows = sqlx::query_as::<_, InviteRow>(
+        "SELECT code, channel_id, inviter_id, max_uses, uses, max_age, CASE WHEN temporary THEN 1 ELSE 0 END AS temporary, created_at
+         FROM invites
+         WHERE max_age IS NOT NULL AND max_age > 0
+           AND datetime(created_at, '+' || max_age || ' seconds') <= $2
+         ORDER BY created_at ASC
+         LIMIT $1",
+    )
+    .bind(limit)
+    .bind(datetime_to_db_text(Utc::now()))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
 pub async fn get_guild_invites(pool: &DbPool, guild_id: i64) -> Result<Vec<InviteRow>, DbError> {
     let rows = sqlx::query_as::<_, InviteRow>(
         "SELECT i.code, i.channel_id, i.inviter_id, i.max_uses, i.uses, i.max_age, CASE WHEN i.temporary THEN 1 ELSE 0 END AS temporary, i.created_at
          FROM invites i
          INNER JOIN channels c ON c.id = i.channel_id
          WHERE c.space_id = $1
-           AND (i.max_age IS NULL OR i.max_age = 0 OR datetime(i.created_at, '+' || i.max_age || ' seconds') > datetime('now'))
+           AND (i.max_age IS NULL OR i.max_age = 0 OR datetime(i.created_at, '+' || i.max_age || ' seconds') > $2)
          ORDER BY i.created_at DESC",
     )
     .bind(guild_id)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_all(pool)
     .await?;
     Ok(rows)
@@ -118,9 +323,10 @@ pub async fn get_all_invites(pool: &DbPool) -> Result<Vec<InviteRow>, DbError> {
     let rows = sqlx::query_as::<_, InviteRow>(
         "SELECT code, channel_id, inviter_id, max_uses, uses, max_age, CASE WHEN temporary THEN 1 ELSE 0 END AS temporary, created_at
          FROM invites
-         WHERE (max_age IS NULL OR max_age = 0 OR datetime(created_at, '+' || max_age || ' seconds') > datetime('now'))
+         WHERE (max_age IS NULL OR max_age = 0 OR datetime(created_at, '+' || max_age || ' seconds') > $1)
          ORDER BY created_at DESC",
     )
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_all(pool)
     .await?;
     Ok(rows)
@@ -134,10 +340,11 @@ pub async fn get_channel_invites(
         "SELECT code, channel_id, inviter_id, max_uses, uses, max_age, CASE WHEN temporary THEN 1 ELSE 0 END AS temporary, created_at
          FROM invites
          WHERE channel_id = $1
-           AND (max_age IS NULL OR max_age = 0 OR datetime(created_at, '+' || max_age || ' seconds') > datetime('now'))
+           AND (max_age IS NULL OR max_age = 0 OR datetime(created_at, '+' || max_age || ' seconds') > $2)
          ORDER BY created_at DESC",
     )
     .bind(channel_id)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_all(pool)
     .await?;
     Ok(rows)
@@ -173,9 +380,11 @@ mod tests {
     async fn test_create_invite() {
         let pool = test_pool().await;
         let (user_id, guild_id, channel_id) = setup_channel(&pool).await;
-        let invite = create_invite(&pool, "abc123", guild_id, channel_id, user_id, None, None)
-            .await
-            .unwrap();
+        let invite = create_invite(
+            &pool, "abc123", guild_id, channel_id, user_id, None, None, false,
+        )
+        .await
+        .unwrap();
         assert_eq!(invite.code, "abc123");
         assert_eq!(invite.channel_id, channel_id);
         assert_eq!(invite.inviter_id, Some(user_id));
@@ -196,6 +405,7 @@ mod tests {
             user_id,
             Some(5),
             Some(3600),
+            false,
         )
         .await
         .unwrap();
@@ -207,9 +417,11 @@ mod tests {
     async fn test_get_invite() {
         let pool = test_pool().await;
         let (user_id, guild_id, channel_id) = setup_channel(&pool).await;
-        create_invite(&pool, "find_me", guild_id, channel_id, user_id, None, None)
-            .await
-            .unwrap();
+        create_invite(
+            &pool, "find_me", guild_id, channel_id, user_id, None, None, false,
+        )
+        .await
+        .unwrap();
         let invite = get_invite(&pool, "find_me").await.unwrap().unwrap();
         assert_eq!(invite.code, "find_me");
     }
@@ -233,16 +445,18 @@ mod tests {
             user_id,
             None,
             Some(1),
+            false,
         )
         .await
         .unwrap();
-        sqlx::query(
-            "UPDATE invites SET created_at = datetime('now', '-5 seconds') WHERE code = $1",
-        )
-        .bind("expired_read")
-        .execute(&pool)
-        .await
-        .unwrap();
+        sqlx::query("UPDATE invites SET created_at = $2 WHERE code = $1")
+            .bind("expired_read")
+            .bind(datetime_to_db_text(
+                Utc::now() - chrono::Duration::seconds(5),
+            ))
+            .execute(&pool)
+            .await
+            .unwrap();
 
         let invite = get_invite(&pool, "expired_read").await.unwrap();
         assert!(invite.is_none());
@@ -252,9 +466,11 @@ mod tests {
     async fn test_use_invite_increments_uses() {
         let pool = test_pool().await;
         let (user_id, guild_id, channel_id) = setup_channel(&pool).await;
-        create_invite(&pool, "useme", guild_id, channel_id, user_id, None, None)
-            .await
-            .unwrap();
+        create_invite(
+            &pool, "useme", guild_id, channel_id, user_id, None, None, false,
+        )
+        .await
+        .unwrap();
         let used = use_invite(&pool, "useme").await.unwrap().unwrap();
         assert_eq!(used.uses, 1);
         let used_again = use_invite(&pool, "useme").await.unwrap().unwrap();
@@ -265,9 +481,18 @@ mod tests {
     async fn test_use_invite_respects_max_uses() {
         let pool = test_pool().await;
         let (user_id, guild_id, channel_id) = setup_channel(&pool).await;
-        create_invite(&pool, "once", guild_id, channel_id, user_id, Some(1), None)
-            .await
-            .unwrap();
+        create_invite(
+            &pool,
+            "once",
+            guild_id,
+            channel_id,
+            user_id,
+            Some(1),
+            None,
+            false,
+        )
+        .await
+        .unwrap();
         let first = use_invite(&pool, "once").await.unwrap();
         assert!(first.is_some());
         let second = use_invite(&pool, "once").await.unwrap();
@@ -278,9 +503,11 @@ mod tests {
     async fn test_delete_invite() {
         let pool = test_pool().await;
         let (user_id, guild_id, channel_id) = setup_channel(&pool).await;
-        create_invite(&pool, "delme", guild_id, channel_id, user_id, None, None)
-            .await
-            .unwrap();
+        create_invite(
+            &pool, "delme", guild_id, channel_id, user_id, None, None, false,
+        )
+        .await
+        .unwrap();
         delete_invite(&pool, "delme").await.unwrap();
         let invite = get_invite(&pool, "delme").await.unwrap();
         assert!(invite.is_none());
@@ -290,12 +517,16 @@ mod tests {
     async fn test_get_guild_invites() {
         let pool = test_pool().await;
         let (user_id, guild_id, channel_id) = setup_channel(&pool).await;
-        create_invite(&pool, "inv1", guild_id, channel_id, user_id, None, None)
-            .await
-            .unwrap();
-        create_invite(&pool, "inv2", guild_id, channel_id, user_id, None, None)
-            .await
-            .unwrap();
+        create_invite(
+            &pool, "inv1", guild_id, channel_id, user_id, None, None, false,
+        )
+        .await
+        .unwrap();
+        create_invite(
+            &pool, "inv2", guild_id, channel_id, user_id, None, None, false,
+        )
+        .await
+        .unwrap();
         let invites = get_guild_invites(&pool, guild_id).await.unwrap();
         assert_eq!(invites.len(), 2);
     }
@@ -308,10 +539,12 @@ mod tests {
         crate::channels::create_channel(&pool, 201, guild_id, "other", 0, 1, None, None)
             .await
             .unwrap();
-        create_invite(&pool, "ch1", guild_id, channel_id, user_id, None, None)
-            .await
-            .unwrap();
-        create_invite(&pool, "ch2", guild_id, 201, user_id, None, None)
+        create_invite(
+            &pool, "ch1", guild_id, channel_id, user_id, None, None, false,
+        )
+        .await
+        .unwrap();
+        create_invite(&pool, "ch2", guild_id, 201, user_id, None, None, false)
             .await
             .unwrap();
         let invites = get_channel_invites(&pool, channel_id).await.unwrap();
@@ -331,6 +564,7 @@ mod tests {
             user_id,
             None,
             Some(1),
+            false,
         )
         .await
         .unwrap();
@@ -342,19 +576,147 @@ mod tests {
             user_id,
             None,
             Some(3600),
+            false,
         )
         .await
         .unwrap();
-        sqlx::query(
-            "UPDATE invites SET created_at = datetime('now', '-5 seconds') WHERE code = $1",
-        )
-        .bind("expired_list")
-        .execute(&pool)
-        .await
-        .unwrap();
+        sqlx::query("UPDATE invites SET created_at = $2 WHERE code = $1")
+            .bind("expired_list")
+            .bind(datetime_to_db_text(
+                Utc::now() - chrono::Duration::seconds(5),
+            ))
+            .execute(&pool)
+            .await
+            .unwrap();
 
         let invites = get_guild_invites(&pool, guild_id).await.unwrap();
         assert_eq!(invites.len(), 1);
         assert_eq!(invites[0].code, "active_list");
     }
+
+    #[tokio::test]
+    async fn test_expired_invites_are_listed_for_sweeping() {
+        let pool = test_pool().await;
+        let (user_id, guild_id, channel_id) = setup_channel(&pool).await;
+        create_invite(
+            &pool,
+            "stale",
+            guild_id,
+            channel_id,
+            user_id,
+            Some(3),
+            Some(1),
+            true,
+        )
+        .await
+        .unwrap();
+        create_invite(
+            &pool,
+            "forever",
+            guild_id,
+            channel_id,
+            user_id,
+            None,
+            Some(0),
+            false,
+        )
+        .await
+        .unwrap();
+        sqlx::query("UPDATE invites SET created_at = $1")
+            .bind(datetime_to_db_text(
+                Utc::now() - chrono::Duration::seconds(5),
+            ))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let expired = get_expired_invites(&pool, 10).await.unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].code, "stale");
+        assert!(expired[0].temporary);
+        assert!(expired[0].is_expired(Utc::now()));
+        assert_eq!(expired[0].remaining_uses(), Some(3));
+
+        // Expired invites are hidden from lookups but still resolvable unfiltered.
+        assert!(get_invite(&pool, "stale").await.unwrap().is_none());
+        assert!(get_invite_unfiltered(&pool, "stale")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(use_invite(&pool, "stale").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_redeem_invite_records_the_join() {
+        let pool = test_pool().await;
+        let (user_id, guild_id, channel_id) = setup_channel(&pool).await;
+        for id in [2, 3, 4] {
+            let email = format!("joiner{id}@example.com");
+            crate::users::create_user(&pool, id, &format!("joiner{id}"), 1, &email, "hash")
+                .await
+                .unwrap();
+        }
+        create_invite(
+            &pool,
+            "raid",
+            guild_id,
+            channel_id,
+            user_id,
+            Some(2),
+            None,
+            true,
+        )
+        .await
+        .unwrap();
+        create_invite(
+            &pool, "quiet", guild_id, channel_id, user_id, None, None, false,
+        )
+        .await
+        .unwrap();
+
+        let redeemed = redeem_invite(&pool, 10, "raid", guild_id, 2)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(redeemed.uses, 1);
+        redeem_invite(&pool, 11, "raid", guild_id, 3)
+            .await
+            .unwrap()
+            .unwrap();
+        redeem_invite(&pool, 12, "quiet", guild_id, 4)
+            .await
+            .unwrap()
+            .unwrap();
+        // Used up: nothing changes for the would-be third joiner.
+        assert!(redeem_invite(&pool, 13, "raid", guild_id, 4)
+            .await
+            .unwrap()
+            .is_none());
+
+        let (temporary,): (i64,) = sqlx::query_as(
+            "SELECT CASE WHEN temporary THEN 1 ELSE 0 END FROM members
+             WHERE user_id = $1 AND guild_id = $2",
+        )
+        .bind(2_i64)
+        .bind(guild_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(temporary, 1);
+        assert_eq!(get_invite(&pool, "raid").await.unwrap().unwrap().uses, 2);
+
+        let since = Utc::now() - chrono::Duration::hours(1);
+        let top = get_top_invites(&pool, guild_id, since, 10).await.unwrap();
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].code, "raid");
+        assert_eq!(top[0].joins, 2);
+        assert_eq!(top[0].inviter_id, Some(user_id));
+        assert_eq!(top[1].code, "quiet");
+
+        // Joins stay attributed after the invite itself is gone.
+        delete_invite(&pool, "raid").await.unwrap();
+        let uses = get_invite_uses(&pool, guild_id, "raid", 10).await.unwrap();
+        let joiners: Vec<i64> = uses.iter().map(|u| u.user_id).collect();
+        assert_eq!(joiners, vec![3, 2]);
+    }
 }
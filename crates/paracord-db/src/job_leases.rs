@@ -0,0 +1,93 @@
+use crate::{datetime_to_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+
+/// Take (or renew) the lease on job `name` for `holder` until `expires_at`.
+/// Returns `false` while another holder's lease is still live.
+pub async fn try_acquire_lease(
+    pool: &DbPool,
+    name: &str,
+    holder: &str,
+    now: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "INSERT INTO job_leases (name, holder, expires_at)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (name) DO UPDATE
+         SET holder = excluded.holder, expires_at = excluded.expires_at
+         WHERE job_leases.holder = excluded.holder OR job_leases.expires_at <= $4",
+    )
+    .bind(name)
+    .bind(holder)
+    .bind(datetime_to_db_text(expires_at))
+    .bind(datetime_to_db_text(now))
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Give up a lease early so another instance can pick the job up.
+pub async fn release_lease(pool: &DbPool, name: &str, holder: &str) -> Result<(), DbError> {
+    sqlx::query("DELETE FROM job_leases WHERE name = $1 AND holder = $2")
+        .bind(name)
+        .bind(holder)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_lease_excludes_other_holders_until_expiry() {
+        let pool = test_pool().await;
+        let now = Utc::now();
+        let until = now + Duration::minutes(10);
+
+        assert!(try_acquire_lease(&pool, "cleanup", "a", now, until)
+            .await
+            .unwrap());
+        assert!(!try_acquire_lease(&pool, "cleanup", "b", now, until)
+            .await
+            .unwrap());
+        // The holder itself can renew.
+        assert!(try_acquire_lease(&pool, "cleanup", "a", now, until)
+            .await
+            .unwrap());
+        // Once expired, anyone can take over.
+        let later = until + Duration::seconds(1);
+        assert!(
+            try_acquire_lease(&pool, "cleanup", "b", later, later + Duration::minutes(10))
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_released_lease_is_free() {
+        let pool = test_pool().await;
+        let now = Utc::now();
+        let until = now + Duration::minutes(10);
+        assert!(try_acquire_lease(&pool, "cleanup", "a", now, until)
+            .await
+            .unwrap());
+        // Releasing someone else's lease does nothing.
+        release_lease(&pool, "cleanup", "b").await.unwrap();
+        assert!(!try_acquire_lease(&pool, "cleanup", "b", now, until)
+            .await
+            .unwrap());
+        release_lease(&pool, "cleanup", "a").await.unwrap();
+        assert!(try_acquire_lease(&pool, "cleanup", "b", now, until)
+            .await
+            .unwrap());
+    }
+}
@@ -1,20 +1,30 @@
+pub mod application_commands;
 pub mod attachments;
 pub mod audit_log;
+pub mod auth_challenges;
 pub mod bans;
 pub mod bot_applications;
 pub mod channel_overwrites;
 pub mod channels;
+pub mod custom_statuses;
 pub mod dms;
+pub mod email_verifications;
 pub mod emojis;
 pub mod federation;
 pub mod federation_file_cache;
 pub mod guild_storage_policies;
 pub mod guilds;
 pub mod invites;
+pub mod job_leases;
+pub mod link_previews;
 pub mod members;
+pub mod message_edits;
+pub mod message_mentions;
 pub mod messages;
+pub mod notification_settings;
 pub mod polls;
 pub mod prekeys;
+pub mod push_subscriptions;
 pub mod rate_limits;
 pub mod reactions;
 pub mod read_states;
@@ -24,6 +34,8 @@ pub mod scheduled_events;
 pub mod security_events;
 pub mod server_settings;
 pub mod sessions;
+pub mod upload_sessions;
+pub mod user_mfa;
 pub mod users;
 pub mod voice_states;
 pub mod webhooks;
@@ -31,6 +43,7 @@ pub mod webhooks;
 use sha2::{Digest, Sha256};
 use sqlx::any::AnyPoolOptions;
 use std::sync::OnceLock;
+use std::time::Duration;
 use thiserror::Error;
 
 pub type DbPool = sqlx::AnyPool;
@@ -69,6 +82,132 @@ pub struct PgConnectOptions {
     pub idle_in_transaction_timeout_secs: u64,
 }
 
+/// Pragmas applied after each SQLite connection is established. The
+/// defaults let readers and a writer work concurrently: WAL journaling, a
+/// 5 second wait on locks instead of failing with `database is locked`, and
+/// `NORMAL` syncing, which is durable in WAL mode except on power loss.
+#[derive(Debug, Clone)]
+pub struct SqliteConnectOptions {
+    /// `journal_mode`: DELETE, TRUNCATE, PERSIST, MEMORY, WAL or OFF.
+    pub journal_mode: String,
+    /// `busy_timeout` in milliseconds (0 = fail immediately on a lock).
+    pub busy_timeout_ms: u64,
+    /// `synchronous`: OFF, NORMAL, FULL or EXTRA.
+    pub synchronous: String,
+    pub foreign_keys: bool,
+}
+
+impl Default for SqliteConnectOptions {
+    fn default() -> Self {
+        Self {
+            journal_mode: "WAL".into(),
+            busy_timeout_ms: 5000,
+            synchronous: "NORMAL".into(),
+            foreign_keys: true,
+        }
+    }
+}
+
+const SQLITE_JOURNAL_MODES: &[&str] = &["DELETE", "TRUNCATE", "PERSIST", "MEMORY", "WAL", "OFF"];
+const SQLITE_SYNCHRONOUS_MODES: &[&str] = &["OFF", "NORMAL", "FULL", "EXTRA"];
+
+impl SqliteConnectOptions {
+    /// Upper-case the modes and reject values SQLite does not know, since
+    /// they are spliced into the pragma statements.
+    fn validated(mut self) -> Result<Self, sqlx::Error> {
+        self.journal_mode = self.journal_mode.trim().to_ascii_uppercase();
+        self.synchronous = self.synchronous.trim().to_ascii_uppercase();
+        if !SQLITE_JOURNAL_MODES.contains(&self.journal_mode.as_str()) {
+            return Err(sqlx::Error::Configuration(
+                format!("invalid sqlite journal_mode '{}'", self.journal_mode).into(),
+            ));
+        }
+        if !SQLITE_SYNCHRONOUS_MODES.contains(&self.synchronous.as_str()) {
+            return Err(sqlx::Error::Configuration(
+                format!("invalid sqlite synchronous mode '{}'", self.synchronous).into(),
+            ));
+        }
+        Ok(self)
+    }
+}
+
+/// Connection pool sizing and connection recycling.
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    pub max_connections: u32,
+    /// Connections kept open even when idle. Clamped to `max_connections`.
+    pub min_connections: u32,
+    /// How long a caller waits for a free connection before failing.
+    pub acquire_timeout_secs: u64,
+    /// Close connections idle for longer than this (0 = never).
+    pub idle_timeout_secs: u64,
+    /// Recycle connections older than this (0 = never).
+    pub max_lifetime_secs: u64,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self {
+            max_connections: 10,
+            min_connections: 0,
+            acquire_timeout_secs: 30,
+            idle_timeout_secs: 600,
+            max_lifetime_secs: 1800,
+        }
+    }
+}
+
+/// Point-in-time pool utilization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStats {
+    pub open: u32,
+    pub idle: usize,
+    pub min: u32,
+    pub max: u32,
+}
+
+impl PoolStats {
+    pub fn in_use(&self) -> u32 {
+        self.open
+            .saturating_sub(u32::try_from(self.idle).unwrap_or(u32::MAX))
+    }
+
+    /// Every connection is open and busy, so new callers have to wait.
+    pub fn saturated(&self) -> bool {
+        self.open >= self.max && self.idle == 0
+    }
+}
+
+pub fn pool_stats(pool: &DbPool) -> PoolStats {
+    let options = pool.options();
+    PoolStats {
+        open: pool.size(),
+        idle: pool.num_idle(),
+        min: options.get_min_connections(),
+        max: options.get_max_connections(),
+    }
+}
+
+/// Round-trip a trivial query to check the database is reachable.
+pub async fn ping(pool: &DbPool) -> Result<(), DbError> {
+    sqlx::query("SELECT 1").execute(pool).await?;
+    Ok(())
+}
+
+/// Flush and close the pool on shutdown. SQLite folds the WAL back into the
+/// main database file first so a stopped server leaves a single clean file.
+pub async fn close(pool: &DbPool) {
+    if active_database_engine() == DatabaseEngine::Sqlite {
+        if let Err(e) = sqlx::query("PRAGMA wal_checkpoint(TRUNCATE);")
+            .execute(pool)
+            .await
+        {
+            tracing::warn!("WAL checkpoint on shutdown failed: {}", e);
+        }
+    }
+    pool.close().await;
+}
+
 pub async fn create_pool(database_url: &str, max_connections: u32) -> Result<DbPool, sqlx::Error> {
     create_pool_full(database_url, max_connections, None, None, None).await
 }
@@ -96,6 +235,29 @@ pub async fn create_pool_full(
     engine: Option<DatabaseEngine>,
     sqlite_key_hex: Option<String>,
     pg_options: Option<PgConnectOptions>,
+) -> Result<DbPool, sqlx::Error> {
+    let pool_options = PoolOptions {
+        max_connections,
+        ..PoolOptions::default()
+    };
+    create_pool_with_options(
+        database_url,
+        pool_options,
+        engine,
+        sqlite_key_hex,
+        None,
+        pg_options,
+    )
+    .await
+}
+
+pub async fn create_pool_with_options(
+    database_url: &str,
+    pool_options: PoolOptions,
+    engine: Option<DatabaseEngine>,
+    sqlite_key_hex: Option<String>,
+    sqlite_options: Option<SqliteConnectOptions>,
+    pg_options: Option<PgConnectOptions>,
 ) -> Result<DbPool, sqlx::Error> {
     let detected_engine = detect_database_engine(database_url)?;
     let engine = engine.unwrap_or(detected_engine);
@@ -135,12 +297,22 @@ pub async fn create_pool_full(
     };
 
     let after_connect_key = sqlite_key_hex.clone();
+    let sqlite_opts = sqlite_options.unwrap_or_default().validated()?;
     let pg_opts = pg_options.unwrap_or_default();
+    let max_connections = pool_options.max_connections.max(1);
+    let secs = |secs: u64| (secs > 0).then(|| Duration::from_secs(secs));
     AnyPoolOptions::new()
         .max_connections(max_connections)
+        .min_connections(pool_options.min_connections.min(max_connections))
+        .acquire_timeout(Duration::from_secs(
+            pool_options.acquire_timeout_secs.max(1),
+        ))
+        .idle_timeout(secs(pool_options.idle_timeout_secs))
+        .max_lifetime(secs(pool_options.max_lifetime_secs))
         .after_connect(move |conn, _meta| {
             let sqlite_key_hex = after_connect_key.clone();
             let sqlite_db = matches!(engine, DatabaseEngine::Sqlite);
+            let sqlite_opts = sqlite_opts.clone();
             let pg_opts = pg_opts.clone();
             Box::pin(async move {
                 if sqlite_db {
@@ -166,18 +338,41 @@ pub async fn create_pool_full(
                     }
 
                     // Tune SQLite for concurrent access.
-                    sqlx::query("PRAGMA journal_mode = WAL;")
-                        .execute(&mut *conn)
-                        .await?;
-                    sqlx::query("PRAGMA foreign_keys = ON;")
-                        .execute(&mut *conn)
-                        .await?;
-                    sqlx::query("PRAGMA busy_timeout = 5000;")
-                        .execute(&mut *conn)
-                        .await?;
-                    sqlx::query("PRAGMA synchronous = NORMAL;")
+                    let journal_mode: String = sqlx::query_scalar(&format!(
+                        "PRAGMA journal_mode = {};",
+                        sqlite_opts.journal_mode
+                    ))
+                    .fetch_one(&mut *conn)
+                    .await?;
+                    // In-memory databases and some filesystems cannot use
+                    // WAL; SQLite then keeps its previous mode.
+                    if !journal_mode.eq_ignore_ascii_case(&sqlite_opts.journal_mode) {
+                        tracing::warn!(
+                            "SQLite journal_mode {} requested but {} is in effect",
+                            sqlite_opts.journal_mode,
+                            journal_mode
+                        );
+                    }
+                    let foreign_keys = if sqlite_opts.foreign_keys {
+                        "ON"
+                    } else {
+                        "OFF"
+                    };
+                    sqlx::query(&format!("PRAGMA foreign_keys = {foreign_keys};"))
                         .execute(&mut *conn)
                         .await?;
+                    sqlx::query(&format!(
+                        "PRAGMA busy_timeout = {};",
+                        sqlite_opts.busy_timeout_ms
+                    ))
+                    .execute(&mut *conn)
+                    .await?;
+                    sqlx::query(&format!(
+                        "PRAGMA synchronous = {};",
+                        sqlite_opts.synchronous
+                    ))
+                    .execute(&mut *conn)
+                    .await?;
                     sqlx::query("PRAGMA cache_size = -8000;")
                         .execute(&mut *conn)
                         .await?;
@@ -267,6 +462,37 @@ fn normalize_sqlite_url_for_any(url: &str) -> String {
     }
 }
 
+/// Lower-cased `[low, high)` bounds holding exactly the strings that start
+/// with `query` in code point order. Bind both for a condition built by
+/// [`prefix_range_condition`].
+pub(crate) fn prefix_range_bounds(query: &str) -> (String, String) {
+    let low = query.to_lowercase();
+    let mut high: Vec<char> = low.chars().collect();
+    while let Some(last) = high.pop() {
+        // The smallest string past every extension of the prefix.
+        if let Some(next) = (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32) {
+            high.push(next);
+            return (low, high.into_iter().collect());
+        }
+    }
+    let high = format!("{low}{}", char::MAX);
+    (low, high)
+}
+
+/// `LOWER(column)` within the bounds bound at `$low_param` and the parameter
+/// after it, compared by code point on both engines so an index on
+/// `LOWER(column)` (with the "C" collation on Postgres) serves the range.
+pub(crate) fn prefix_range_condition(column: &str, low_param: usize) -> String {
+    let collate = match active_database_engine() {
+        DatabaseEngine::Sqlite => "",
+        DatabaseEngine::Postgres => " COLLATE \"C\"",
+    };
+    format!(
+        "(LOWER({column}){collate} >= ${low_param} AND LOWER({column}){collate} < ${})",
+        low_param + 1
+    )
+}
+
 pub(crate) fn datetime_to_db_text(value: chrono::DateTime<chrono::Utc>) -> String {
     value.format("%Y-%m-%d %H:%M:%S").to_string()
 }
@@ -335,11 +561,7 @@ fn sha256_hex(value: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(value.as_bytes());
     let digest = hasher.finalize();
-    let mut out = String::with_capacity(digest.len() * 2);
-    for b in digest {
-        out.push_str(&format!("{:02x}", b));
-    }
-    out
+    paracord_util::hex::hex_encode(&digest)
 }
 
 fn is_hex_sha256(value: &str) -> bool {
@@ -371,9 +593,27 @@ async fn backfill_webhook_token_hashes(pool: &DbPool) -> Result<(), sqlx::Error>
 mod tests {
     use super::{
         backfill_webhook_token_hashes, create_pool, create_pool_with_engine_and_sqlite_key,
-        create_pool_with_sqlite_key, run_migrations, run_migrations_for_engine, DatabaseEngine,
+        create_pool_with_options, create_pool_with_sqlite_key, pool_stats, prefix_range_bounds,
+        run_migrations, run_migrations_for_engine, DatabaseEngine, PoolOptions,
+        SqliteConnectOptions,
     };
 
+    #[test]
+    fn prefix_range_bounds_cover_exactly_the_prefixed_strings() {
+        let (low, high) = prefix_range_bounds("AbZ");
+        assert_eq!((low.as_str(), high.as_str()), ("abz", "ab{"));
+        for name in ["abz", "abz0", "abzzzz", "abz\u{10FFFF}"] {
+            assert!(low.as_str() <= name && name < high.as_str(), "{name}");
+        }
+        for name in ["aby", "ab{", "ac"] {
+            assert!(!(low.as_str() <= name && name < high.as_str()), "{name}");
+        }
+        // A trailing char::MAX carries over to the char before it.
+        assert_eq!(prefix_range_bounds("a\u{10FFFF}").1, "b");
+        // Surrogates are not chars; the bound skips past them.
+        assert_eq!(prefix_range_bounds("\u{D7FF}").1, "\u{E000}");
+    }
+
     #[tokio::test]
     async fn create_pool_supports_default_sqlite_mode() {
         let pool = create_pool("sqlite::memory:", 1).await.expect("pool");
@@ -384,6 +624,98 @@ mod tests {
         assert_eq!(value, 1);
     }
 
+    #[tokio::test]
+    async fn pool_options_are_applied_and_reported() {
+        let options = PoolOptions {
+            max_connections: 3,
+            min_connections: 5,
+            acquire_timeout_secs: 5,
+            idle_timeout_secs: 0,
+            max_lifetime_secs: 60,
+        };
+        let pool = create_pool_with_options("sqlite::memory:", options, None, None, None, None)
+            .await
+            .expect("pool");
+        let stats = pool_stats(&pool);
+        assert_eq!(stats.max, 3);
+        // The minimum never exceeds the pool size.
+        assert_eq!(stats.min, 3);
+        assert!(stats.open >= 1);
+        assert_eq!(pool.options().get_idle_timeout(), None);
+    }
+
+    #[tokio::test]
+    async fn sqlite_pragmas_are_applied_to_every_connection() {
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        let db_path = std::env::temp_dir().join(format!("paracord-db-pragmas-{unique}.db"));
+        let db_url = format!(
+            "sqlite://{}?mode=rwc",
+            db_path.to_string_lossy().replace('\\', "/")
+        );
+        let sqlite_options = SqliteConnectOptions {
+            busy_timeout_ms: 1234,
+            synchronous: "full".into(),
+            ..SqliteConnectOptions::default()
+        };
+        let pool = create_pool_with_options(
+            &db_url,
+            PoolOptions::default(),
+            None,
+            None,
+            Some(sqlite_options),
+            None,
+        )
+        .await
+        .expect("pool");
+
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode;")
+            .fetch_one(&pool)
+            .await
+            .expect("journal_mode");
+        assert_eq!(journal_mode.to_ascii_lowercase(), "wal");
+        let busy_timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout;")
+            .fetch_one(&pool)
+            .await
+            .expect("busy_timeout");
+        assert_eq!(busy_timeout, 1234);
+        // FULL = 2
+        let synchronous: i64 = sqlx::query_scalar("PRAGMA synchronous;")
+            .fetch_one(&pool)
+            .await
+            .expect("synchronous");
+        assert_eq!(synchronous, 2);
+        let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys;")
+            .fetch_one(&pool)
+            .await
+            .expect("foreign_keys");
+        assert_eq!(foreign_keys, 1);
+
+        pool.close().await;
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_sqlite_pragma_values() {
+        let sqlite_options = SqliteConnectOptions {
+            journal_mode: "wal; DROP TABLE users".into(),
+            ..SqliteConnectOptions::default()
+        };
+        let err = create_pool_with_options(
+            "sqlite::memory:",
+            PoolOptions::default(),
+            None,
+            None,
+            Some(sqlite_options),
+            None,
+        )
+        .await
+        .expect_err("invalid journal mode must fail");
+        assert!(matches!(err, sqlx::Error::Configuration(_)));
+    }
+
     #[tokio::test]
     async fn rejects_invalid_sqlite_key_format() {
         let err = create_pool_with_sqlite_key("sqlite::memory:", 1, Some("abc".to_string()))
@@ -524,4 +856,87 @@ mod tests {
             .expect("message exists");
         assert_eq!(fetched.content.as_deref(), Some("postgres smoke"));
     }
+
+    fn migration_names(dir: &str) -> std::collections::BTreeSet<String> {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+        std::fs::read_dir(path)
+            .expect("migration dir")
+            .map(|entry| {
+                entry
+                    .expect("dir entry")
+                    .file_name()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn migration_tracks_stay_in_sync() {
+        let sqlite = migration_names("migrations");
+        let mut postgres = migration_names("migrations_pg");
+        // Postgres-only shim providing datetime()/strftime() to shared queries.
+        assert!(postgres.remove("20260208000000_sqlite_compat.sql"));
+        let missing_pg: Vec<_> = sqlite.difference(&postgres).collect();
+        let missing_sqlite: Vec<_> = postgres.difference(&sqlite).collect();
+        assert!(
+            missing_pg.is_empty(),
+            "missing postgres migrations: {missing_pg:?}"
+        );
+        assert!(
+            missing_sqlite.is_empty(),
+            "missing sqlite migrations: {missing_sqlite:?}"
+        );
+    }
+
+    #[test]
+    fn queries_use_numbered_dollar_placeholders() {
+        // `?N` placeholders only work on SQLite; sqlx::Any passes SQL through
+        // verbatim, so they break silently on Postgres.
+        let src = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        for entry in std::fs::read_dir(src).expect("src dir") {
+            let path = entry.expect("dir entry").path();
+            let source = std::fs::read_to_string(&path).expect("read source");
+            for (line_no, line) in source.lines().enumerate() {
+                let code = line.trim_start();
+                if code.starts_with("//") {
+                    continue;
+                }
+                let has_sqlite_placeholder = line
+                    .as_bytes()
+                    .windows(2)
+                    .any(|pair| pair[0] == b'?' && pair[1].is_ascii_digit());
+                assert!(
+                    !has_sqlite_placeholder,
+                    "{}:{}: use $N placeholders instead of ?N",
+                    path.display(),
+                    line_no + 1
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn queries_bind_the_current_time() {
+        // `datetime('now')` is SQLite syntax that Postgres only accepts
+        // through the compat shim, and it reads the database's clock rather
+        // than ours. Bind `datetime_to_db_text(Utc::now())` instead.
+        let needle = concat!("datetime('", "now'");
+        let src = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        for entry in std::fs::read_dir(src).expect("src dir") {
+            let path = entry.expect("dir entry").path();
+            let source = std::fs::read_to_string(&path).expect("read source");
+            for (line_no, line) in source.lines().enumerate() {
+                if line.trim_start().starts_with("//") {
+                    continue;
+                }
+                assert!(
+                    !line.contains(needle),
+                    "{}:{}: bind datetime_to_db_text(Utc::now()) instead of {needle})",
+                    path.display(),
+                    line_no + 1
+                );
+            }
+        }
+    }
 }
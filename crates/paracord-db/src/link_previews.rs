@@ -0,0 +1,174 @@
+use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+/// Cached preview metadata for one URL. All metadata fields are `None` when
+/// the page had nothing usable or couldn't be fetched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkPreviewRow {
+    pub url_hash: String,
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub site_name: Option<String>,
+    pub image_url: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl LinkPreviewRow {
+    /// Whether there is anything to render.
+    pub fn has_metadata(&self) -> bool {
+        self.title.is_some() || self.description.is_some() || self.image_url.is_some()
+    }
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for LinkPreviewRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let fetched_at_raw: String = row.try_get("fetched_at")?;
+        Ok(Self {
+            url_hash: row.try_get("url_hash")?,
+            url: row.try_get("url")?,
+            title: row.try_get("title")?,
+            description: row.try_get("description")?,
+            site_name: row.try_get("site_name")?,
+            image_url: row.try_get("image_url")?,
+            fetched_at: datetime_from_db_text(&fetched_at_raw)?,
+        })
+    }
+}
+
+/// Store the result of fetching `preview.url`, replacing any earlier entry.
+pub async fn upsert_link_preview(pool: &DbPool, preview: &LinkPreviewRow) -> Result<(), DbError> {
+    sqlx::query(
+        "INSERT INTO link_previews (url_hash, url, title, description, site_name, image_url, fetched_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         ON CONFLICT (url_hash) DO UPDATE SET
+             url = $2,
+             title = $3,
+             description = $4,
+             site_name = $5,
+             image_url = $6,
+             fetched_at = $7",
+    )
+    .bind(&preview.url_hash)
+    .bind(&preview.url)
+    .bind(preview.title.as_deref())
+    .bind(preview.description.as_deref())
+    .bind(preview.site_name.as_deref())
+    .bind(preview.image_url.as_deref())
+    .bind(datetime_to_db_text(preview.fetched_at))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Cached previews for any of `url_hashes`, stale or not, in no particular
+/// order.
+pub async fn get_link_previews(
+    pool: &DbPool,
+    url_hashes: &[String],
+) -> Result<Vec<LinkPreviewRow>, DbError> {
+    if url_hashes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders: Vec<String> = (1..=url_hashes.len()).map(|i| format!("${}", i)).collect();
+    let sql = format!(
+        "SELECT url_hash, url, title, description, site_name, image_url, fetched_at
+         FROM link_previews
+         WHERE url_hash IN ({})",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query_as::<_, LinkPreviewRow>(&sql);
+    for url_hash in url_hashes {
+        query = query.bind(url_hash);
+    }
+    let rows = query.fetch_all(pool).await?;
+    Ok(rows)
+}
+
+/// Delete previews fetched before `cutoff`. Returns how many were removed.
+pub async fn purge_link_previews_fetched_before(
+    pool: &DbPool,
+    cutoff: DateTime<Utc>,
+) -> Result<u64, DbError> {
+    let result = sqlx::query("DELETE FROM link_previews WHERE fetched_at < $1")
+        .bind(datetime_to_db_text(cutoff))
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    fn preview(url_hash: &str, title: Option<&str>, fetched_at: DateTime<Utc>) -> LinkPreviewRow {
+        LinkPreviewRow {
+            url_hash: url_hash.to_string(),
+            url: format!("https://example.com/{url_hash}"),
+            title: title.map(str::to_string),
+            description: None,
+            site_name: None,
+            image_url: None,
+            fetched_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn upsert_replaces_earlier_fetches() {
+        let pool = test_pool().await;
+        let old = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let new = DateTime::from_timestamp(1_700_100_000, 0).unwrap();
+
+        upsert_link_preview(&pool, &preview("a", None, old))
+            .await
+            .unwrap();
+        upsert_link_preview(&pool, &preview("b", Some("B"), old))
+            .await
+            .unwrap();
+        upsert_link_preview(&pool, &preview("a", Some("A"), new))
+            .await
+            .unwrap();
+
+        let mut rows = get_link_previews(&pool, &["a".into(), "b".into(), "c".into()])
+            .await
+            .unwrap();
+        rows.sort_by(|x, y| x.url_hash.cmp(&y.url_hash));
+        assert_eq!(
+            rows,
+            vec![preview("a", Some("A"), new), preview("b", Some("B"), old)]
+        );
+        assert!(rows[1].has_metadata());
+        assert!(!preview("c", None, new).has_metadata());
+    }
+
+    #[tokio::test]
+    async fn purge_removes_only_previews_fetched_before_the_cutoff() {
+        let pool = test_pool().await;
+        let old = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let new = DateTime::from_timestamp(1_700_100_000, 0).unwrap();
+        upsert_link_preview(&pool, &preview("a", Some("A"), old))
+            .await
+            .unwrap();
+        upsert_link_preview(&pool, &preview("b", Some("B"), new))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            purge_link_previews_fetched_before(&pool, new)
+                .await
+                .unwrap(),
+            1
+        );
+        let rows = get_link_previews(&pool, &["a".into(), "b".into()])
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![preview("b", Some("B"), new)]);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{bool_from_any_row, datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use crate::{
+    bool_from_any_row, datetime_from_db_text, datetime_to_db_text, prefix_range_bounds,
+    prefix_range_condition, DbError, DbPool,
+};
 use chrono::{DateTime, Utc};
 use sqlx::Row;
 
@@ -26,6 +29,7 @@ pub struct MemberWithUserRow {
     pub discriminator: i16,
     pub user_avatar_hash: Option<String>,
     pub user_flags: i32,
+    pub user_last_seen_at: Option<DateTime<Utc>>,
 }
 
 impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for MemberRow {
@@ -51,6 +55,7 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for MemberWithUserRow {
     fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
         let joined_at_raw: String = row.try_get("joined_at")?;
         let timeout_raw: Option<String> = row.try_get("communication_disabled_until")?;
+        let last_seen_raw: Option<String> = row.try_get("user_last_seen_at")?;
         Ok(Self {
             user_id: row.try_get("user_id")?,
             nick: row.try_get("nick")?,
@@ -66,6 +71,10 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for MemberWithUserRow {
             discriminator: row.try_get("discriminator")?,
             user_avatar_hash: row.try_get("user_avatar_hash")?,
             user_flags: row.try_get("user_flags")?,
+            user_last_seen_at: last_seen_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
         })
     }
 }
@@ -80,6 +89,62 @@ pub async fn add_member(pool: &DbPool, user_id: i64, guild_id: i64) -> Result<()
     Ok(())
 }
 
+/// Add a member who joined through a temporary invite. Existing memberships
+/// are left untouched so a permanent member never becomes temporary.
+pub async fn add_temporary_member(
+    pool: &DbPool,
+    user_id: i64,
+    guild_id: i64,
+) -> Result<(), DbError> {
+    sqlx::query(
+        "INSERT INTO members (user_id, guild_id, temporary) VALUES ($1, $2, TRUE)
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(guild_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Remove the user's temporary memberships that were never given a role
+/// beyond the space's default one. Returns the spaces they were removed from.
+pub async fn remove_temporary_memberships(
+    pool: &DbPool,
+    user_id: i64,
+) -> Result<Vec<i64>, DbError> {
+    const UNROLED_TEMPORARY: &str = "temporary
+           AND NOT EXISTS (
+                SELECT 1
+                FROM member_roles mr
+                INNER JOIN roles r ON r.id = mr.role_id
+                WHERE mr.user_id = members.user_id
+                  AND r.space_id = members.guild_id
+                  AND r.id <> members.guild_id
+           )";
+    let guild_ids: Vec<i64> = sqlx::query_scalar(&format!(
+        "SELECT guild_id FROM members WHERE user_id = $1 AND {UNROLED_TEMPORARY}"
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut removed = Vec::with_capacity(guild_ids.len());
+    for guild_id in guild_ids {
+        let result = sqlx::query(&format!(
+            "DELETE FROM members WHERE user_id = $1 AND guild_id = $2 AND {UNROLED_TEMPORARY}"
+        ))
+        .bind(user_id)
+        .bind(guild_id)
+        .execute(pool)
+        .await?;
+        if result.rows_affected() > 0 {
+            removed.push(guild_id);
+        }
+    }
+    Ok(removed)
+}
+
 pub async fn add_server_member(pool: &DbPool, user_id: i64) -> Result<(), DbError> {
     sqlx::query(
         "INSERT INTO members (user_id, guild_id)
@@ -129,7 +194,7 @@ pub async fn get_guild_members(
     let rows = if let Some(after_id) = after {
         sqlx::query_as::<_, MemberWithUserRow>(
             "SELECT m.user_id, m.nick, m.avatar_hash, m.joined_at, CASE WHEN m.deaf THEN 1 ELSE 0 END AS deaf, CASE WHEN m.mute THEN 1 ELSE 0 END AS mute, m.communication_disabled_until,
-                    u.username, u.discriminator, u.avatar_hash AS user_avatar_hash, u.flags AS user_flags
+                    u.username, u.discriminator, u.avatar_hash AS user_avatar_hash, u.flags AS user_flags, u.last_seen_at AS user_last_seen_at
              FROM members m
              INNER JOIN users u ON u.id = m.user_id
              WHERE m.guild_id = $3
@@ -145,7 +210,7 @@ pub async fn get_guild_members(
     } else {
         sqlx::query_as::<_, MemberWithUserRow>(
             "SELECT m.user_id, m.nick, m.avatar_hash, m.joined_at, CASE WHEN m.deaf THEN 1 ELSE 0 END AS deaf, CASE WHEN m.mute THEN 1 ELSE 0 END AS mute, m.communication_disabled_until,
-                    u.username, u.discriminator, u.avatar_hash AS user_avatar_hash, u.flags AS user_flags
+                    u.username, u.discriminator, u.avatar_hash AS user_avatar_hash, u.flags AS user_flags, u.last_seen_at AS user_last_seen_at
              FROM members m
              INNER JOIN users u ON u.id = m.user_id
              WHERE m.guild_id = $2
@@ -160,6 +225,165 @@ pub async fn get_guild_members(
     Ok(rows)
 }
 
+/// Optional narrowing applied to a member list. An empty filter matches
+/// every member of the space.
+#[derive(Debug, Clone, Default)]
+pub struct MemberSearchFilter {
+    /// Case-insensitive prefix of the username or space nickname.
+    pub query: Option<String>,
+    pub role_id: Option<i64>,
+    /// `Some(true)` keeps only members whose timeout is still running,
+    /// `Some(false)` only members who can currently talk.
+    pub timed_out: Option<bool>,
+    /// Only members who joined at or after this instant.
+    pub joined_after: Option<DateTime<Utc>>,
+    /// Only members who joined before this instant.
+    pub joined_before: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MemberPage {
+    pub members: Vec<MemberWithUserRow>,
+    /// Members matching the filter across all pages.
+    pub total: i64,
+    /// Whether another page follows this one.
+    pub has_more: bool,
+}
+
+struct MemberPageRow {
+    member: MemberWithUserRow,
+    total: i64,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for MemberPageRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            member: MemberWithUserRow::from_row(row)?,
+            total: row.try_get("total_count")?,
+        })
+    }
+}
+
+/// One page of members matching `filter`, keyset-paginated by user id.
+///
+/// The total is computed by the same statement as the page so the two cannot
+/// disagree when members join or leave in between, and `has_more` comes from
+/// over-fetching one row rather than from comparing against the total.
+pub async fn search_guild_members(
+    pool: &DbPool,
+    guild_id: i64,
+    filter: &MemberSearchFilter,
+    limit: i64,
+    after: Option<i64>,
+) -> Result<MemberPage, DbError> {
+    // $1 is the space id; filter parameters follow in a fixed order.
+    let mut conditions = String::new();
+    let mut next_param = 2;
+    if filter.query.is_some() {
+        conditions.push_str(&format!(
+            " AND ({} OR {})",
+            prefix_range_condition("u.username", next_param),
+            prefix_range_condition("m.nick", next_param)
+        ));
+        next_param += 2;
+    }
+    if filter.role_id.is_some() {
+        conditions.push_str(&format!(
+            " AND EXISTS (SELECT 1 FROM member_roles mr WHERE mr.user_id = m.user_id AND mr.role_id = ${})",
+            next_param
+        ));
+        next_param += 1;
+    }
+    match filter.timed_out {
+        Some(true) => {
+            conditions.push_str(&format!(
+                " AND m.communication_disabled_until IS NOT NULL AND m.communication_disabled_until > ${}",
+                next_param
+            ));
+            next_param += 1;
+        }
+        Some(false) => {
+            conditions.push_str(&format!(
+                " AND (m.communication_disabled_until IS NULL OR m.communication_disabled_until <= ${})",
+                next_param
+            ));
+            next_param += 1;
+        }
+        None => {}
+    }
+    for (present, clause) in [
+        (filter.joined_after.is_some(), "m.joined_at >="),
+        (filter.joined_before.is_some(), "m.joined_at <"),
+    ] {
+        if present {
+            conditions.push_str(&format!(" AND {} ${}", clause, next_param));
+            next_param += 1;
+        }
+    }
+
+    let matching = format!(
+        "FROM members m
+         INNER JOIN users u ON u.id = m.user_id
+         WHERE m.guild_id = $1{conditions}"
+    );
+    let page_sql = format!(
+        "SELECT m.user_id, m.nick, m.avatar_hash, m.joined_at, CASE WHEN m.deaf THEN 1 ELSE 0 END AS deaf, CASE WHEN m.mute THEN 1 ELSE 0 END AS mute, m.communication_disabled_until,
+                u.username, u.discriminator, u.avatar_hash AS user_avatar_hash, u.flags AS user_flags, u.last_seen_at AS user_last_seen_at,
+                (SELECT COUNT(*) {matching}) AS total_count
+         {matching}
+           AND m.user_id > ${after_param}
+         ORDER BY m.user_id
+         LIMIT ${limit_param}",
+        after_param = next_param,
+        limit_param = next_param + 1,
+    );
+    let count_sql = format!("SELECT COUNT(*) {matching}");
+
+    let bounds = filter.query.as_deref().map(prefix_range_bounds);
+    let now = datetime_to_db_text(Utc::now());
+    let mut count_query = sqlx::query_as::<_, (i64,)>(&count_sql).bind(guild_id);
+    let mut page_query = sqlx::query_as::<_, MemberPageRow>(&page_sql).bind(guild_id);
+    if let Some((low, high)) = &bounds {
+        count_query = count_query.bind(low.clone()).bind(high.clone());
+        page_query = page_query.bind(low.clone()).bind(high.clone());
+    }
+    if let Some(role_id) = filter.role_id {
+        count_query = count_query.bind(role_id);
+        page_query = page_query.bind(role_id);
+    }
+    if filter.timed_out.is_some() {
+        count_query = count_query.bind(now.clone());
+        page_query = page_query.bind(now.clone());
+    }
+    if let Some(joined_after) = filter.joined_after {
+        count_query = count_query.bind(datetime_to_db_text(joined_after));
+        page_query = page_query.bind(datetime_to_db_text(joined_after));
+    }
+    if let Some(joined_before) = filter.joined_before {
+        count_query = count_query.bind(datetime_to_db_text(joined_before));
+        page_query = page_query.bind(datetime_to_db_text(joined_before));
+    }
+
+    let limit = limit.max(1);
+    let mut rows = page_query
+        .bind(after.unwrap_or(0))
+        .bind(limit + 1)
+        .fetch_all(pool)
+        .await?;
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    // Past the last page there is no row to carry the total.
+    let total = match rows.first() {
+        Some(row) => row.total,
+        None => count_query.fetch_one(pool).await?.0,
+    };
+    Ok(MemberPage {
+        members: rows.into_iter().map(|row| row.member).collect(),
+        total,
+        has_more,
+    })
+}
+
 pub async fn get_server_members(
     pool: &DbPool,
     limit: i64,
@@ -168,11 +392,11 @@ pub async fn get_server_members(
     let rows = if let Some(after_id) = after {
         sqlx::query_as::<_, MemberWithUserRow>(
             "SELECT m.user_id, m.nick, m.avatar_hash, MIN(m.joined_at) AS joined_at, CASE WHEN m.deaf THEN 1 ELSE 0 END AS deaf, CASE WHEN m.mute THEN 1 ELSE 0 END AS mute, m.communication_disabled_until,
-                    u.username, u.discriminator, u.avatar_hash AS user_avatar_hash, u.flags AS user_flags
+                    u.username, u.discriminator, u.avatar_hash AS user_avatar_hash, u.flags AS user_flags, u.last_seen_at AS user_last_seen_at
              FROM members m
              INNER JOIN users u ON u.id = m.user_id
              WHERE m.user_id > $2
-             GROUP BY m.user_id, m.nick, m.avatar_hash, m.deaf, m.mute, m.communication_disabled_until, u.username, u.discriminator, u.avatar_hash, u.flags
+             GROUP BY m.user_id, m.nick, m.avatar_hash, m.deaf, m.mute, m.communication_disabled_until, u.username, u.discriminator, u.avatar_hash, u.flags, u.last_seen_at
              ORDER BY m.user_id
              LIMIT $1"
         )
@@ -183,10 +407,10 @@ pub async fn get_server_members(
     } else {
         sqlx::query_as::<_, MemberWithUserRow>(
             "SELECT m.user_id, m.nick, m.avatar_hash, MIN(m.joined_at) AS joined_at, CASE WHEN m.deaf THEN 1 ELSE 0 END AS deaf, CASE WHEN m.mute THEN 1 ELSE 0 END AS mute, m.communication_disabled_until,
-                    u.username, u.discriminator, u.avatar_hash AS user_avatar_hash, u.flags AS user_flags
+                    u.username, u.discriminator, u.avatar_hash AS user_avatar_hash, u.flags AS user_flags, u.last_seen_at AS user_last_seen_at
              FROM members m
              INNER JOIN users u ON u.id = m.user_id
-             GROUP BY m.user_id, m.nick, m.avatar_hash, m.deaf, m.mute, m.communication_disabled_until, u.username, u.discriminator, u.avatar_hash, u.flags
+             GROUP BY m.user_id, m.nick, m.avatar_hash, m.deaf, m.mute, m.communication_disabled_until, u.username, u.discriminator, u.avatar_hash, u.flags, u.last_seen_at
              ORDER BY m.joined_at
              LIMIT $1"
         )
@@ -516,4 +740,172 @@ mod tests {
         // user 2 not added to any guild
         assert!(!share_any_guild(&pool, user_id, 2).await.unwrap());
     }
+
+    #[tokio::test]
+    async fn test_search_guild_members_filters_and_counts() {
+        let pool = test_pool().await;
+        let (owner_id, guild_id) = setup_guild(&pool).await;
+        add_member(&pool, owner_id, guild_id).await.unwrap();
+        for (id, name) in [(2, "alice"), (3, "alicia"), (4, "bob"), (5, "al_ice")] {
+            crate::users::create_user(&pool, id, name, 1, &format!("{}@example.com", name), "hash")
+                .await
+                .unwrap();
+            add_member(&pool, id, guild_id).await.unwrap();
+        }
+        update_member(&pool, 4, guild_id, Some("Alfred"), None, None)
+            .await
+            .unwrap();
+        crate::roles::create_role(&pool, 500, guild_id, "mods", 0)
+            .await
+            .unwrap();
+        crate::roles::add_member_role(&pool, 3, guild_id, 500)
+            .await
+            .unwrap();
+        set_member_timeout(
+            &pool,
+            2,
+            guild_id,
+            Some(Utc::now() + chrono::Duration::hours(1)),
+        )
+        .await
+        .unwrap();
+        set_member_timeout(
+            &pool,
+            3,
+            guild_id,
+            Some(Utc::now() - chrono::Duration::hours(1)),
+        )
+        .await
+        .unwrap();
+
+        let by_name = MemberSearchFilter {
+            query: Some("AL".into()),
+            ..Default::default()
+        };
+        let page = search_guild_members(&pool, guild_id, &by_name, 2, None)
+            .await
+            .unwrap();
+        assert_eq!(page.total, 4);
+        assert!(page.has_more);
+        assert_eq!(
+            page.members.iter().map(|m| m.user_id).collect::<Vec<_>>(),
+            [2, 3]
+        );
+        let page = search_guild_members(&pool, guild_id, &by_name, 2, Some(3))
+            .await
+            .unwrap();
+        assert_eq!(page.total, 4);
+        assert!(!page.has_more);
+        // Matched through the nickname.
+        assert_eq!(
+            page.members.iter().map(|m| m.user_id).collect::<Vec<_>>(),
+            [4, 5]
+        );
+
+        // Names match from their start, and `_` is an ordinary character.
+        let literal = MemberSearchFilter {
+            query: Some("al_".into()),
+            ..Default::default()
+        };
+        let page = search_guild_members(&pool, guild_id, &literal, 50, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            page.members.iter().map(|m| m.user_id).collect::<Vec<_>>(),
+            [5]
+        );
+
+        let by_role = MemberSearchFilter {
+            role_id: Some(500),
+            ..Default::default()
+        };
+        let page = search_guild_members(&pool, guild_id, &by_role, 50, None)
+            .await
+            .unwrap();
+        assert_eq!((page.members.len(), page.total), (1, 1));
+        assert_eq!(page.members[0].user_id, 3);
+
+        // An expired timeout no longer counts.
+        let timed_out = MemberSearchFilter {
+            timed_out: Some(true),
+            ..Default::default()
+        };
+        let page = search_guild_members(&pool, guild_id, &timed_out, 50, None)
+            .await
+            .unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.members[0].user_id, 2);
+        let not_timed_out = MemberSearchFilter {
+            timed_out: Some(false),
+            ..Default::default()
+        };
+        let page = search_guild_members(&pool, guild_id, &not_timed_out, 50, None)
+            .await
+            .unwrap();
+        assert_eq!(page.total, 4);
+
+        let future = MemberSearchFilter {
+            joined_after: Some(Utc::now() + chrono::Duration::days(1)),
+            ..Default::default()
+        };
+        let page = search_guild_members(&pool, guild_id, &future, 50, None)
+            .await
+            .unwrap();
+        assert!(page.members.is_empty());
+        assert_eq!(page.total, 0);
+        assert!(!page.has_more);
+        let page = search_guild_members(&pool, guild_id, &MemberSearchFilter::default(), 50, None)
+            .await
+            .unwrap();
+        assert_eq!(page.total, 5);
+
+        // Paging past the end still reports the filtered total.
+        let page = search_guild_members(&pool, guild_id, &by_name, 2, Some(5))
+            .await
+            .unwrap();
+        assert!(page.members.is_empty());
+        assert_eq!(page.total, 4);
+        assert!(!page.has_more);
+    }
+
+    #[tokio::test]
+    async fn test_temporary_members_without_roles_are_removed() {
+        let pool = test_pool().await;
+        let (_owner_id, guild_id) = setup_guild(&pool).await;
+        crate::users::create_user(&pool, 2, "guest", 1, "g@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 101, "Other Guild", 1, None)
+            .await
+            .unwrap();
+        crate::roles::create_role(&pool, guild_id, guild_id, "@everyone", 0)
+            .await
+            .unwrap();
+        crate::roles::create_role(&pool, 500, 101, "Regular", 0)
+            .await
+            .unwrap();
+
+        add_temporary_member(&pool, 2, guild_id).await.unwrap();
+        crate::roles::add_member_role(&pool, 2, guild_id, guild_id)
+            .await
+            .unwrap();
+        add_temporary_member(&pool, 2, 101).await.unwrap();
+        crate::roles::add_member_role(&pool, 2, 101, 500)
+            .await
+            .unwrap();
+
+        // The default role does not make a membership permanent; any other role does.
+        let removed = remove_temporary_memberships(&pool, 2).await.unwrap();
+        assert_eq!(removed, vec![guild_id]);
+        assert!(get_member(&pool, 2, guild_id).await.unwrap().is_none());
+        assert!(get_member(&pool, 2, 101).await.unwrap().is_some());
+
+        // Permanent members are never made temporary by a later temporary join.
+        add_member(&pool, 2, guild_id).await.unwrap();
+        add_temporary_member(&pool, 2, guild_id).await.unwrap();
+        assert!(remove_temporary_memberships(&pool, 2)
+            .await
+            .unwrap()
+            .is_empty());
+    }
 }
@@ -0,0 +1,134 @@
+use crate::{datetime_from_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+/// A prior revision of a message, captured when it was edited.
+#[derive(Debug, Clone)]
+pub struct MessageEditRow {
+    pub id: i64,
+    pub message_id: i64,
+    pub editor_id: i64,
+    /// Content the message had before this edit.
+    pub previous_content: Option<String>,
+    pub edited_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for MessageEditRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let edited_at_raw: String = row.try_get("edited_at")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            message_id: row.try_get("message_id")?,
+            editor_id: row.try_get("editor_id")?,
+            previous_content: row.try_get("previous_content")?,
+            edited_at: datetime_from_db_text(&edited_at_raw)?,
+        })
+    }
+}
+
+/// Store the content a message had before an edit, then drop the oldest
+/// revisions so at most `max_revisions` remain for the message.
+pub async fn record_message_edit(
+    pool: &DbPool,
+    id: i64,
+    message_id: i64,
+    editor_id: i64,
+    previous_content: Option<&str>,
+    max_revisions: i64,
+) -> Result<(), DbError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO message_edits (id, message_id, editor_id, previous_content)
+         VALUES ($1, $2, $3, $4)",
+    )
+    .bind(id)
+    .bind(message_id)
+    .bind(editor_id)
+    .bind(previous_content)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "DELETE FROM message_edits
+         WHERE message_id = $1
+           AND id NOT IN (
+               SELECT id FROM message_edits
+               WHERE message_id = $1
+               ORDER BY id DESC
+               LIMIT $2
+           )",
+    )
+    .bind(message_id)
+    .bind(max_revisions.max(0))
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Stored revisions of a message, oldest first.
+pub async fn get_message_edits(
+    pool: &DbPool,
+    message_id: i64,
+) -> Result<Vec<MessageEditRow>, DbError> {
+    let rows = sqlx::query_as::<_, MessageEditRow>(
+        "SELECT id, message_id, editor_id, previous_content, edited_at
+         FROM message_edits
+         WHERE message_id = $1
+         ORDER BY id ASC",
+    )
+    .bind(message_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        crate::users::create_user(&pool, 1, "author", 1, "author@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 10, "space", 1, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 20, 10, "general", 0, 0, None, None)
+            .await
+            .unwrap();
+        crate::messages::create_message(&pool, 30, 20, 1, "v1", 0, None)
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_revisions_are_capped_oldest_first() {
+        let pool = test_pool().await;
+        for (id, content) in [(101, "v1"), (102, "v2"), (103, "v3")] {
+            record_message_edit(&pool, id, 30, 1, Some(content), 2)
+                .await
+                .unwrap();
+        }
+
+        let edits = get_message_edits(&pool, 30).await.unwrap();
+        let contents: Vec<_> = edits
+            .iter()
+            .map(|e| e.previous_content.as_deref())
+            .collect();
+        assert_eq!(contents, vec![Some("v2"), Some("v3")]);
+        assert!(edits.iter().all(|e| e.editor_id == 1));
+    }
+
+    #[tokio::test]
+    async fn test_revisions_are_removed_with_message() {
+        let pool = test_pool().await;
+        record_message_edit(&pool, 101, 30, 1, Some("v1"), 10)
+            .await
+            .unwrap();
+        crate::messages::delete_message(&pool, 30).await.unwrap();
+        assert!(get_message_edits(&pool, 30).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{DbError, DbPool};
+
+pub const MENTION_TYPE_USER: i16 = 0;
+pub const MENTION_TYPE_ROLE: i16 = 1;
+pub const MENTION_TYPE_CHANNEL: i16 = 2;
+pub const MENTION_TYPE_EVERYONE: i16 = 3;
+pub const MENTION_TYPE_HERE: i16 = 4;
+
+/// Users, roles and channels a message mentions, plus `@everyone`/`@here`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageMentions {
+    pub user_ids: Vec<i64>,
+    pub role_ids: Vec<i64>,
+    pub channel_ids: Vec<i64>,
+    pub everyone: bool,
+    pub here: bool,
+}
+
+impl MessageMentions {
+    pub fn is_empty(&self) -> bool {
+        self.user_ids.is_empty()
+            && self.role_ids.is_empty()
+            && self.channel_ids.is_empty()
+            && !self.everyone
+            && !self.here
+    }
+}
+
+/// Replace the stored mentions of a message in `space_id`. Only members of
+/// the space, its roles and its channels are kept; roles that aren't
+/// mentionable are dropped unless `include_unmentionable_roles` is set.
+/// Returns what was actually stored.
+pub async fn replace_message_mentions(
+    pool: &DbPool,
+    message_id: i64,
+    space_id: i64,
+    mentions: &MessageMentions,
+    include_unmentionable_roles: bool,
+) -> Result<MessageMentions, DbError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM message_mentions WHERE message_id = $1")
+        .bind(message_id)
+        .execute(&mut *tx)
+        .await?;
+    for user_id in &mentions.user_ids {
+        sqlx::query(
+            "INSERT INTO message_mentions (message_id, mention_type, target_id)
+             SELECT $1, $2, user_id FROM members WHERE guild_id = $3 AND user_id = $4
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(message_id)
+        .bind(MENTION_TYPE_USER)
+        .bind(space_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    for role_id in &mentions.role_ids {
+        sqlx::query(
+            "INSERT INTO message_mentions (message_id, mention_type, target_id)
+             SELECT $1, $2, id FROM roles
+             WHERE space_id = $3 AND id = $4 AND (mentionable OR $5)
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(message_id)
+        .bind(MENTION_TYPE_ROLE)
+        .bind(space_id)
+        .bind(role_id)
+        .bind(include_unmentionable_roles)
+        .execute(&mut *tx)
+        .await?;
+    }
+    for channel_id in &mentions.channel_ids {
+        sqlx::query(
+            "INSERT INTO message_mentions (message_id, mention_type, target_id)
+             SELECT $1, $2, id FROM channels WHERE space_id = $3 AND id = $4
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(message_id)
+        .bind(MENTION_TYPE_CHANNEL)
+        .bind(space_id)
+        .bind(channel_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    for (mention_type, mentioned) in [
+        (MENTION_TYPE_EVERYONE, mentions.everyone),
+        (MENTION_TYPE_HERE, mentions.here),
+    ] {
+        if mentioned {
+            sqlx::query(
+                "INSERT INTO message_mentions (message_id, mention_type, target_id)
+                 VALUES ($1, $2, 0)
+                 ON CONFLICT DO NOTHING",
+            )
+            .bind(message_id)
+            .bind(mention_type)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    tx.commit().await?;
+
+    get_message_mentions(pool, message_id).await
+}
+
+pub async fn get_message_mentions(
+    pool: &DbPool,
+    message_id: i64,
+) -> Result<MessageMentions, DbError> {
+    let rows: Vec<(i16, i64)> = sqlx::query_as(
+        "SELECT mention_type, target_id FROM message_mentions
+         WHERE message_id = $1
+         ORDER BY mention_type, target_id",
+    )
+    .bind(message_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut mentions = MessageMentions::default();
+    for (mention_type, target_id) in rows {
+        match mention_type {
+            MENTION_TYPE_USER => mentions.user_ids.push(target_id),
+            MENTION_TYPE_ROLE => mentions.role_ids.push(target_id),
+            MENTION_TYPE_CHANNEL => mentions.channel_ids.push(target_id),
+            MENTION_TYPE_EVERYONE => mentions.everyone = true,
+            MENTION_TYPE_HERE => mentions.here = true,
+            _ => {}
+        }
+    }
+    Ok(mentions)
+}
+
+/// A guild message whose mentions the backfill still has to parse.
+#[derive(Debug, Clone)]
+pub struct MentionBackfillRow {
+    pub id: i64,
+    pub channel_id: i64,
+    pub space_id: i64,
+    pub author_id: i64,
+    pub content: Option<String>,
+}
+
+/// Up to `limit` guild messages with an id below `before_id`, newest first.
+/// DM messages are skipped: mentions are only tracked inside a space.
+pub async fn list_messages_for_mention_backfill(
+    pool: &DbPool,
+    before_id: i64,
+    limit: i64,
+) -> Result<Vec<MentionBackfillRow>, DbError> {
+    let rows: Vec<(i64, i64, i64, i64, Option<String>)> = sqlx::query_as(
+        "SELECT m.id, m.channel_id, c.space_id, m.author_id, m.content
+         FROM messages m
+         INNER JOIN channels c ON c.id = m.channel_id
+         WHERE m.id < $1 AND c.space_id IS NOT NULL
+         ORDER BY m.id DESC
+         LIMIT $2",
+    )
+    .bind(before_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(
+            |(id, channel_id, space_id, author_id, content)| MentionBackfillRow {
+                id,
+                channel_id,
+                space_id,
+                author_id,
+                content,
+            },
+        )
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        for (id, name) in [(1, "author"), (2, "member"), (3, "outsider")] {
+            crate::users::create_user(&pool, id, name, 1, &format!("{name}@example.com"), "hash")
+                .await
+                .unwrap();
+        }
+        crate::guilds::create_guild(&pool, 10, "space", 1, None)
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 11, "elsewhere", 3, None)
+            .await
+            .unwrap();
+        crate::members::add_member(&pool, 2, 10).await.unwrap();
+        crate::channels::create_channel(&pool, 20, 10, "general", 0, 0, None, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 21, 11, "other", 0, 0, None, None)
+            .await
+            .unwrap();
+        crate::roles::create_role(&pool, 30, 10, "quiet", 0)
+            .await
+            .unwrap();
+        crate::messages::create_message(&pool, 100, 20, 1, "hi", 0, None)
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_replace_message_mentions_keeps_only_targets_in_the_space() {
+        let pool = test_pool().await;
+        let parsed = MessageMentions {
+            user_ids: vec![2, 3],
+            role_ids: vec![30],
+            channel_ids: vec![20, 21],
+            everyone: true,
+            here: false,
+        };
+
+        let stored = replace_message_mentions(&pool, 100, 10, &parsed, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            stored,
+            MessageMentions {
+                user_ids: vec![2],
+                role_ids: vec![],
+                channel_ids: vec![20],
+                everyone: true,
+                here: false,
+            }
+        );
+
+        // Unmentionable roles count when the author may ping everyone, and a
+        // second call replaces rather than appends.
+        let stored = replace_message_mentions(
+            &pool,
+            100,
+            10,
+            &MessageMentions {
+                role_ids: vec![30],
+                ..Default::default()
+            },
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(stored.role_ids, vec![30]);
+        assert!(stored.user_ids.is_empty());
+        assert!(!stored.everyone);
+    }
+
+    #[tokio::test]
+    async fn test_list_messages_for_mention_backfill_pages_guild_messages_newest_first() {
+        let pool = test_pool().await;
+        crate::messages::create_message(&pool, 101, 20, 1, "<@2>", 0, None)
+            .await
+            .unwrap();
+        crate::messages::create_message(&pool, 102, 21, 3, "elsewhere", 0, None)
+            .await
+            .unwrap();
+
+        let rows = list_messages_for_mention_backfill(&pool, 102, 10)
+            .await
+            .unwrap();
+        let ids: Vec<i64> = rows.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![101, 100]);
+        assert_eq!(rows[0].space_id, 10);
+        assert_eq!(rows[0].content.as_deref(), Some("<@2>"));
+
+        let rows = list_messages_for_mention_backfill(&pool, 200, 1)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].id, 102);
+        assert_eq!(rows[0].space_id, 11);
+    }
+}
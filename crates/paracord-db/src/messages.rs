@@ -13,7 +13,12 @@ pub struct MessageRow {
     pub message_type: i16,
     pub flags: i32,
     pub edited_at: Option<DateTime<Utc>>,
+    /// Number of times the message has been edited.
+    pub edit_count: i32,
     pub pinned: bool,
+    /// When the message was last pinned; `None` while unpinned.
+    pub pinned_at: Option<DateTime<Utc>>,
+    pub pinned_by: Option<i64>,
     pub reference_id: Option<i64>,
     pub e2ee_header: Option<String>,
     pub created_at: DateTime<Utc>,
@@ -22,6 +27,7 @@ pub struct MessageRow {
 impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for MessageRow {
     fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
         let edited_at_raw: Option<String> = row.try_get("edited_at")?;
+        let pinned_at_raw: Option<String> = row.try_get("pinned_at")?;
         let created_at_raw: String = row.try_get("created_at")?;
         Ok(Self {
             id: row.try_get("id")?,
@@ -35,7 +41,13 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for MessageRow {
                 .as_deref()
                 .map(datetime_from_db_text)
                 .transpose()?,
+            edit_count: row.try_get("edit_count")?,
             pinned: bool_from_any_row(row, "pinned")?,
+            pinned_at: pinned_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+            pinned_by: row.try_get("pinned_by")?,
             reference_id: row.try_get("reference_id")?,
             e2ee_header: row.try_get("e2ee_header")?,
             created_at: datetime_from_db_text(&created_at_raw)?,
@@ -83,7 +95,7 @@ pub async fn create_message_with_meta(
     let row = match sqlx::query_as::<_, MessageRow>(
         "INSERT INTO messages (id, channel_id, author_id, content, nonce, message_type, flags, reference_id, e2ee_header)
          VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-         RETURNING id, channel_id, author_id, content, nonce, message_type, flags, edited_at, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, reference_id, e2ee_header, created_at",
+         RETURNING id, channel_id, author_id, content, nonce, message_type, flags, edited_at, edit_count, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, pinned_at, pinned_by, reference_id, e2ee_header, created_at",
     )
     .bind(id)
     .bind(channel_id)
@@ -111,11 +123,13 @@ pub async fn create_message_with_meta(
     };
 
     // Update last_message_id on the channel
-    let _ = sqlx::query("UPDATE channels SET last_message_id = $1 WHERE id = $2")
-        .bind(row.id)
-        .bind(channel_id)
-        .execute(pool)
-        .await;
+    let _ =
+        sqlx::query("UPDATE channels SET last_message_id = $1, last_message_at = $3 WHERE id = $2")
+            .bind(row.id)
+            .bind(channel_id)
+            .bind(datetime_to_db_text(Utc::now()))
+            .execute(pool)
+            .await;
 
     Ok(row)
 }
@@ -135,14 +149,15 @@ fn is_nonce_dedup_unique_violation(err: &sqlx::Error) -> bool {
     message.contains("idx_messages_nonce_dedup_unique")
 }
 
-async fn get_message_by_channel_author_nonce(
+/// The author's message in `channel_id` sent with `nonce`, if any.
+pub async fn get_message_by_channel_author_nonce(
     pool: &DbPool,
     channel_id: i64,
     author_id: i64,
     nonce: &str,
 ) -> Result<Option<MessageRow>, DbError> {
     let row = sqlx::query_as::<_, MessageRow>(
-        "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, reference_id, e2ee_header, created_at
+        "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, edit_count, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, pinned_at, pinned_by, reference_id, e2ee_header, created_at
          FROM messages
          WHERE channel_id = $1
            AND author_id = $2
@@ -160,7 +175,7 @@ async fn get_message_by_channel_author_nonce(
 
 pub async fn get_message(pool: &DbPool, id: i64) -> Result<Option<MessageRow>, DbError> {
     let row = sqlx::query_as::<_, MessageRow>(
-        "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, reference_id, e2ee_header, created_at
+        "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, edit_count, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, pinned_at, pinned_by, reference_id, e2ee_header, created_at
          FROM messages WHERE id = $1",
     )
     .bind(id)
@@ -169,6 +184,36 @@ pub async fn get_message(pool: &DbPool, id: i64) -> Result<Option<MessageRow>, D
     Ok(row)
 }
 
+/// Messages with any of `ids`, in no particular order. Ids with no message
+/// are skipped.
+pub async fn get_messages_by_ids(pool: &DbPool, ids: &[i64]) -> Result<Vec<MessageRow>, DbError> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    const MAX_LOOKUP_IDS: usize = 500;
+    if ids.len() > MAX_LOOKUP_IDS {
+        return Err(DbError::Sqlx(sqlx::Error::Protocol(
+            "too many message ids in lookup".to_string(),
+        )));
+    }
+    let placeholders: Vec<String> = (1..=ids.len()).map(|i| format!("${}", i)).collect();
+    let sql = format!(
+        "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, edit_count, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, pinned_at, pinned_by, reference_id, e2ee_header, created_at
+         FROM messages WHERE id IN ({})",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query_as::<_, MessageRow>(&sql);
+    for id in ids {
+        query = query.bind(id);
+    }
+    let rows = query.fetch_all(pool).await?;
+    Ok(rows)
+}
+
+/// A page of channel messages, newest first. `before` and `after` are
+/// exclusive snowflake cursors; `after` returns the messages immediately
+/// following the cursor, still in descending order so pages merge the same
+/// way in either direction.
 pub async fn get_channel_messages(
     pool: &DbPool,
     channel_id: i64,
@@ -179,7 +224,7 @@ pub async fn get_channel_messages(
     let rows = match (before, after) {
         (Some(before_id), _) => {
             sqlx::query_as::<_, MessageRow>(
-                "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, reference_id, e2ee_header, created_at
+                "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, edit_count, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, pinned_at, pinned_by, reference_id, e2ee_header, created_at
                  FROM messages WHERE channel_id = $1 AND id < $2 ORDER BY id DESC LIMIT $3",
             )
             .bind(channel_id)
@@ -189,19 +234,21 @@ pub async fn get_channel_messages(
             .await?
         }
         (None, Some(after_id)) => {
-            sqlx::query_as::<_, MessageRow>(
-                "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, reference_id, e2ee_header, created_at
+            let mut rows = sqlx::query_as::<_, MessageRow>(
+                "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, edit_count, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, pinned_at, pinned_by, reference_id, e2ee_header, created_at
                  FROM messages WHERE channel_id = $1 AND id > $2 ORDER BY id ASC LIMIT $3",
             )
             .bind(channel_id)
             .bind(after_id)
             .bind(limit)
             .fetch_all(pool)
-            .await?
+            .await?;
+            rows.reverse();
+            rows
         }
         (None, None) => {
             sqlx::query_as::<_, MessageRow>(
-                "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, reference_id, e2ee_header, created_at
+                "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, edit_count, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, pinned_at, pinned_by, reference_id, e2ee_header, created_at
                  FROM messages WHERE channel_id = $1 ORDER BY id DESC LIMIT $2",
             )
             .bind(channel_id)
@@ -213,14 +260,69 @@ pub async fn get_channel_messages(
     Ok(rows)
 }
 
+/// Up to `limit` messages centred on `around`, newest first. The pivot counts
+/// towards the older half; when one side runs out (near the start or end of
+/// the channel) the other side fills the page.
+pub async fn get_channel_messages_around(
+    pool: &DbPool,
+    channel_id: i64,
+    around: i64,
+    limit: i64,
+) -> Result<Vec<MessageRow>, DbError> {
+    if limit <= 0 {
+        return Ok(Vec::new());
+    }
+    let newer = get_channel_messages(pool, channel_id, None, Some(around), limit).await?;
+    let older = sqlx::query_as::<_, MessageRow>(
+        "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, edit_count, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, pinned_at, pinned_by, reference_id, e2ee_header, created_at
+         FROM messages WHERE channel_id = $1 AND id <= $2 ORDER BY id DESC LIMIT $3",
+    )
+    .bind(channel_id)
+    .bind(around)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    let limit = limit as usize;
+    let newer_take = newer
+        .len()
+        .min((limit / 2).max(limit.saturating_sub(older.len())));
+    let older_take = older.len().min(limit - newer_take);
+    // `newer` is newest first, so keep the messages closest to the pivot.
+    let mut rows: Vec<MessageRow> = newer[newer.len() - newer_take..].to_vec();
+    rows.extend(older.into_iter().take(older_take));
+    Ok(rows)
+}
+
+/// Up to `limit` messages newer than `after`, oldest first. Walks a channel's
+/// whole history in keyset pages starting from `after = None`.
+pub async fn list_channel_messages_after(
+    pool: &DbPool,
+    channel_id: i64,
+    after: Option<i64>,
+    limit: i64,
+) -> Result<Vec<MessageRow>, DbError> {
+    let rows = sqlx::query_as::<_, MessageRow>(
+        "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, edit_count, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, pinned_at, pinned_by, reference_id, e2ee_header, created_at
+         FROM messages WHERE channel_id = $1 AND id > $2 ORDER BY id ASC LIMIT $3",
+    )
+    .bind(channel_id)
+    .bind(after.unwrap_or(0))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
 pub async fn update_message(pool: &DbPool, id: i64, content: &str) -> Result<MessageRow, DbError> {
     let row = sqlx::query_as::<_, MessageRow>(
-        "UPDATE messages SET content = $2, edited_at = datetime('now')
+        "UPDATE messages SET content = $2, edited_at = $3, edit_count = edit_count + 1
          WHERE id = $1
-         RETURNING id, channel_id, author_id, content, nonce, message_type, flags, edited_at, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, reference_id, e2ee_header, created_at",
+         RETURNING id, channel_id, author_id, content, nonce, message_type, flags, edited_at, edit_count, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, pinned_at, pinned_by, reference_id, e2ee_header, created_at",
     )
     .bind(id)
     .bind(content)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -277,13 +379,14 @@ pub async fn update_message_authorized_with_meta(
          )
          UPDATE messages
          SET content = $4,
-             edited_at = datetime('now'),
+             edited_at = $9,
+             edit_count = edit_count + 1,
              nonce = $7,
              flags = COALESCE($8, flags)
          WHERE id = $1
            AND channel_id = $2
            AND (author_id = $3 OR EXISTS (SELECT 1 FROM actor_can_manage))
-         RETURNING id, channel_id, author_id, content, nonce, message_type, flags, edited_at, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, reference_id, e2ee_header, created_at",
+         RETURNING id, channel_id, author_id, content, nonce, message_type, flags, edited_at, edit_count, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, pinned_at, pinned_by, reference_id, e2ee_header, created_at",
     )
     .bind(id)
     .bind(channel_id)
@@ -293,6 +396,7 @@ pub async fn update_message_authorized_with_meta(
     .bind(administrator)
     .bind(nonce)
     .bind(flags)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_optional(pool)
     .await?;
     Ok(row)
@@ -362,8 +466,9 @@ pub async fn get_pinned_messages(
     channel_id: i64,
 ) -> Result<Vec<MessageRow>, DbError> {
     let rows = sqlx::query_as::<_, MessageRow>(
-        "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, reference_id, e2ee_header, created_at
-         FROM messages WHERE channel_id = $1 AND pinned = TRUE ORDER BY id ASC",
+        "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, edit_count, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, pinned_at, pinned_by, reference_id, e2ee_header, created_at
+         FROM messages WHERE channel_id = $1 AND pinned = TRUE
+         ORDER BY pinned_at DESC, id DESC",
     )
     .bind(channel_id)
     .fetch_all(pool)
@@ -371,22 +476,66 @@ pub async fn get_pinned_messages(
     Ok(rows)
 }
 
-pub async fn pin_message(pool: &DbPool, id: i64, channel_id: i64) -> Result<bool, DbError> {
-    let result = sqlx::query("UPDATE messages SET pinned = TRUE WHERE id = $1 AND channel_id = $2")
-        .bind(id)
-        .bind(channel_id)
-        .execute(pool)
-        .await?;
-    Ok(result.rows_affected() > 0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinOutcome {
+    Pinned,
+    /// The message was already pinned; nothing changed.
+    AlreadyPinned,
+    /// The channel already holds `max_pins` pinned messages.
+    LimitReached,
+    NotFound,
+}
+
+/// Pin a message unless the channel is already at `max_pins`. The count check
+/// and the update are a single statement so concurrent pins cannot overshoot.
+pub async fn pin_message(
+    pool: &DbPool,
+    id: i64,
+    channel_id: i64,
+    pinned_by: i64,
+    max_pins: i64,
+) -> Result<PinOutcome, DbError> {
+    let result = sqlx::query(
+        "UPDATE messages
+         SET pinned = TRUE, pinned_at = $5, pinned_by = $3
+         WHERE id = $1 AND channel_id = $2 AND pinned = FALSE
+           AND (SELECT COUNT(*) FROM messages WHERE channel_id = $2 AND pinned = TRUE) < $4",
+    )
+    .bind(id)
+    .bind(channel_id)
+    .bind(pinned_by)
+    .bind(max_pins)
+    .bind(datetime_to_db_text(Utc::now()))
+    .execute(pool)
+    .await?;
+    if result.rows_affected() > 0 {
+        return Ok(PinOutcome::Pinned);
+    }
+
+    let row = sqlx::query(
+        "SELECT CASE WHEN pinned THEN 1 ELSE 0 END AS pinned
+         FROM messages WHERE id = $1 AND channel_id = $2",
+    )
+    .bind(id)
+    .bind(channel_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(match row {
+        None => PinOutcome::NotFound,
+        Some(row) if bool_from_any_row(&row, "pinned")? => PinOutcome::AlreadyPinned,
+        Some(_) => PinOutcome::LimitReached,
+    })
 }
 
 pub async fn unpin_message(pool: &DbPool, id: i64, channel_id: i64) -> Result<bool, DbError> {
-    let result =
-        sqlx::query("UPDATE messages SET pinned = FALSE WHERE id = $1 AND channel_id = $2")
-            .bind(id)
-            .bind(channel_id)
-            .execute(pool)
-            .await?;
+    let result = sqlx::query(
+        "UPDATE messages SET pinned = FALSE, pinned_at = NULL, pinned_by = NULL
+         WHERE id = $1 AND channel_id = $2",
+    )
+    .bind(id)
+    .bind(channel_id)
+    .execute(pool)
+    .await?;
     Ok(result.rows_affected() > 0)
 }
 
@@ -420,6 +569,34 @@ pub async fn bulk_delete_messages(
     Ok(result.rows_affected())
 }
 
+/// Creation times of whichever of `ids` exist in the channel, in one query.
+/// Ids that are missing or belong to another channel are left out.
+pub async fn get_message_timestamps(
+    pool: &DbPool,
+    channel_id: i64,
+    ids: &[i64],
+) -> Result<Vec<(i64, DateTime<Utc>)>, DbError> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders: Vec<String> = (2..=ids.len() + 1).map(|i| format!("${}", i)).collect();
+    let sql = format!(
+        "SELECT id, created_at FROM messages WHERE channel_id = $1 AND id IN ({})",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query(&sql).bind(channel_id);
+    for id in ids {
+        query = query.bind(id);
+    }
+    let rows = query.fetch_all(pool).await?;
+    rows.iter()
+        .map(|row| {
+            let created_at: String = row.try_get("created_at")?;
+            Ok((row.try_get("id")?, datetime_from_db_text(&created_at)?))
+        })
+        .collect()
+}
+
 pub async fn count_messages(pool: &DbPool) -> Result<i64, DbError> {
     let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM messages")
         .fetch_one(pool)
@@ -427,48 +604,199 @@ pub async fn count_messages(pool: &DbPool) -> Result<i64, DbError> {
     Ok(row.0)
 }
 
+/// Optional narrowing applied to a full-text message search.
+#[derive(Debug, Clone, Default)]
+pub struct MessageSearchFilter {
+    pub author_id: Option<i64>,
+    /// Only messages created at or after this instant.
+    pub after: Option<DateTime<Utc>>,
+    /// Only messages created before this instant.
+    pub before: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MessageSearchHit {
+    pub message: MessageRow,
+    /// HTML-escaped fragment of the content around the match, with matched
+    /// terms wrapped in `<mark>`/`</mark>`.
+    pub excerpt: String,
+}
+
+/// Highlight delimiters handed to the search engine. Control characters, so
+/// they survive until the excerpt is escaped and are then swapped for tags.
+const EXCERPT_MARK_START: char = '\u{2}';
+const EXCERPT_MARK_END: char = '\u{3}';
+
+/// Escape a raw search excerpt for HTML and turn the highlight delimiters
+/// into `<mark>` tags. Tags are kept balanced even if the message itself
+/// contains the delimiter characters.
+fn render_excerpt(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len() + 16);
+    let mut open = false;
+    for ch in raw.chars() {
+        match ch {
+            EXCERPT_MARK_START if !open => {
+                out.push_str("<mark>");
+                open = true;
+            }
+            EXCERPT_MARK_END if open => {
+                out.push_str("</mark>");
+                open = false;
+            }
+            EXCERPT_MARK_START | EXCERPT_MARK_END => {}
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    if open {
+        out.push_str("</mark>");
+    }
+    out
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for MessageSearchHit {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let excerpt: Option<String> = row.try_get("excerpt")?;
+        Ok(Self {
+            message: MessageRow::from_row(row)?,
+            excerpt: render_excerpt(&excerpt.unwrap_or_default()),
+        })
+    }
+}
+
+/// Quote each whitespace-separated term so user input can never be parsed as
+/// FTS5 query syntax, and prefix-match it so partial words still hit.
+fn fts5_match_expression(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Same contract as [`fts5_match_expression`] for Postgres `to_tsquery`.
+fn tsquery_expression(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|term| format!("'{}':*", term.replace('\\', "\\\\").replace('\'', "''")))
+        .collect::<Vec<_>>()
+        .join(" & ")
+}
+
+/// Ranked full-text search within one channel. Every term must match.
 pub async fn search_messages(
     pool: &DbPool,
     channel_id: i64,
     query: &str,
+    filter: &MessageSearchFilter,
     limit: i64,
-) -> Result<Vec<MessageRow>, DbError> {
+) -> Result<Vec<MessageSearchHit>, DbError> {
     const MESSAGE_FLAG_DM_E2EE: i32 = 1 << 0;
-    let escaped = query
-        .replace('\\', "\\\\")
-        .replace('%', "\\%")
-        .replace('_', "\\_");
-    let pattern = format!("%{}%", escaped);
-    let rows = sqlx::query_as::<_, MessageRow>(
-        "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, reference_id, e2ee_header, created_at
+    const COLUMNS: &str = "m.id, m.channel_id, m.author_id, m.content, m.nonce, m.message_type, m.flags, m.edited_at, m.edit_count, CASE WHEN m.pinned THEN 1 ELSE 0 END AS pinned, m.pinned_at, m.pinned_by, m.reference_id, m.e2ee_header, m.created_at";
+    if query.split_whitespace().next().is_none() {
+        return Ok(Vec::new());
+    }
+
+    let mut extra = String::new();
+    let mut next_param = 5;
+    for (present, clause) in [
+        (filter.author_id.is_some(), "m.author_id ="),
+        (filter.after.is_some(), "m.created_at >="),
+        (filter.before.is_some(), "m.created_at <"),
+    ] {
+        if present {
+            extra.push_str(&format!(" AND {} ${}", clause, next_param));
+            next_param += 1;
+        }
+    }
+
+    let (sql, match_expr) = match crate::active_database_engine() {
+        crate::DatabaseEngine::Sqlite => (
+            format!(
+                "SELECT {COLUMNS}, snippet(messages_fts, 0, char(2), char(3), '...', 24) AS excerpt
+                 FROM messages_fts
+                 INNER JOIN messages m ON m.id = messages_fts.rowid
+                 WHERE messages_fts MATCH $1
+                   AND m.channel_id = $2
+                   AND (m.flags & $3) = 0{extra}
+                 ORDER BY messages_fts.rank, m.id DESC
+                 LIMIT $4"
+            ),
+            fts5_match_expression(query),
+        ),
+        crate::DatabaseEngine::Postgres => (
+            format!(
+                "WITH q AS (SELECT to_tsquery('simple', $1) AS query)
+                 SELECT {COLUMNS},
+                        ts_headline('simple', m.content, q.query, 'StartSel=' || chr(2) || ', StopSel=' || chr(3) || ', MaxWords=24, MinWords=8') AS excerpt
+                 FROM messages m
+                 CROSS JOIN q
+                 WHERE m.search_vector @@ q.query
+                   AND m.channel_id = $2
+                   AND (m.flags & $3) = 0{extra}
+                 ORDER BY ts_rank(m.search_vector, q.query) DESC, m.id DESC
+                 LIMIT $4"
+            ),
+            tsquery_expression(query),
+        ),
+    };
+
+    let mut rows = sqlx::query_as::<_, MessageSearchHit>(&sql)
+        .bind(match_expr)
+        .bind(channel_id)
+        .bind(MESSAGE_FLAG_DM_E2EE)
+        .bind(limit);
+    if let Some(author_id) = filter.author_id {
+        rows = rows.bind(author_id);
+    }
+    if let Some(after) = filter.after {
+        rows = rows.bind(datetime_to_db_text(after));
+    }
+    if let Some(before) = filter.before {
+        rows = rows.bind(datetime_to_db_text(before));
+    }
+    Ok(rows.fetch_all(pool).await?)
+}
+
+pub async fn get_message_ids_older_than(
+    pool: &DbPool,
+    older_than: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<i64>, DbError> {
+    let rows: Vec<(i64,)> = sqlx::query_as(
+        "SELECT id
          FROM messages
-         WHERE channel_id = $1
-           AND content LIKE $2 ESCAPE '\\'
-           AND (flags & $4) = 0
-         ORDER BY id DESC
-         LIMIT $3",
+         WHERE created_at <= $1
+         ORDER BY created_at ASC
+         LIMIT $2",
     )
-    .bind(channel_id)
-    .bind(pattern)
+    .bind(datetime_to_db_text(older_than))
     .bind(limit)
-    .bind(MESSAGE_FLAG_DM_E2EE)
     .fetch_all(pool)
     .await?;
-    Ok(rows)
+    Ok(rows.into_iter().map(|(id,)| id).collect())
 }
 
-pub async fn get_message_ids_older_than(
+/// Oldest unpinned messages in a channel created at or before `older_than`.
+/// Pinned messages never expire under channel retention.
+pub async fn get_expired_channel_message_ids(
     pool: &DbPool,
+    channel_id: i64,
     older_than: DateTime<Utc>,
     limit: i64,
 ) -> Result<Vec<i64>, DbError> {
     let rows: Vec<(i64,)> = sqlx::query_as(
         "SELECT id
          FROM messages
-         WHERE created_at <= $1
-         ORDER BY created_at ASC
-         LIMIT $2",
+         WHERE channel_id = $1 AND created_at <= $2 AND pinned = FALSE
+         ORDER BY id ASC
+         LIMIT $3",
     )
+    .bind(channel_id)
     .bind(datetime_to_db_text(older_than))
     .bind(limit)
     .fetch_all(pool)
@@ -482,7 +810,7 @@ pub async fn list_messages_by_author(
     limit: i64,
 ) -> Result<Vec<MessageRow>, DbError> {
     let rows = sqlx::query_as::<_, MessageRow>(
-        "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, reference_id, e2ee_header, created_at
+        "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, edit_count, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, pinned_at, pinned_by, reference_id, e2ee_header, created_at
          FROM messages
          WHERE author_id = $1
          ORDER BY id DESC
@@ -558,9 +886,30 @@ mod tests {
         assert_eq!(msg.message_type, 0);
         assert!(!msg.pinned);
         assert!(msg.edited_at.is_none());
+        assert_eq!(msg.edit_count, 0);
         assert!(msg.reference_id.is_none());
     }
 
+    #[tokio::test]
+    async fn get_messages_by_ids_skips_missing_ids() {
+        let pool = test_pool().await;
+        let (user_id, _, channel_id) = setup_channel(&pool).await;
+        for id in [1000, 1001] {
+            create_message(&pool, id, channel_id, user_id, "Hello!", 0, None)
+                .await
+                .unwrap();
+        }
+        let mut ids: Vec<i64> = get_messages_by_ids(&pool, &[1001, 1000, 9999])
+            .await
+            .unwrap()
+            .iter()
+            .map(|msg| msg.id)
+            .collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1000, 1001]);
+        assert!(get_messages_by_ids(&pool, &[]).await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_create_message_with_reference() {
         let pool = test_pool().await;
@@ -617,6 +966,59 @@ mod tests {
         assert!(messages[0].id > messages[1].id);
     }
 
+    #[tokio::test]
+    async fn list_channel_messages_after_walks_history_oldest_first() {
+        let pool = test_pool().await;
+        let (user_id, _, channel_id) = setup_channel(&pool).await;
+        for i in 0..5 {
+            create_message(&pool, 3100 + i, channel_id, user_id, "msg", 0, None)
+                .await
+                .unwrap();
+        }
+        let mut seen = Vec::new();
+        let mut after = None;
+        loop {
+            let page = list_channel_messages_after(&pool, channel_id, after, 2)
+                .await
+                .unwrap();
+            let Some(last) = page.last() else { break };
+            after = Some(last.id);
+            seen.extend(page.iter().map(|m| m.id));
+        }
+        assert_eq!(seen, vec![3100, 3101, 3102, 3103, 3104]);
+    }
+
+    #[tokio::test]
+    async fn expired_channel_messages_skip_pinned_and_recent() {
+        let pool = test_pool().await;
+        let (user_id, _, channel_id) = setup_channel(&pool).await;
+        for id in [3200, 3201, 3202] {
+            create_message(&pool, id, channel_id, user_id, "old", 0, None)
+                .await
+                .unwrap();
+        }
+        create_message(&pool, 3203, channel_id, user_id, "new", 0, None)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE messages SET created_at = '2020-01-01 00:00:00' WHERE id < 3203")
+            .execute(&pool)
+            .await
+            .unwrap();
+        pin_message(&pool, 3201, channel_id, user_id, 50)
+            .await
+            .unwrap();
+
+        let cutoff = Utc::now() - chrono::Duration::days(30);
+        let expired = get_expired_channel_message_ids(&pool, channel_id, cutoff, 10)
+            .await
+            .unwrap();
+        assert_eq!(expired, vec![3200, 3202]);
+        let first = get_expired_channel_message_ids(&pool, channel_id, cutoff, 1)
+            .await
+            .unwrap();
+        assert_eq!(first, vec![3200]);
+    }
+
     #[tokio::test]
     async fn test_get_channel_messages_with_before() {
         let pool = test_pool().await;
@@ -663,6 +1065,51 @@ mod tests {
             .unwrap();
         assert_eq!(messages.len(), 2); // 5003, 5004
         assert!(messages.iter().all(|m| m.id > 5002));
+        assert!(messages[0].id > messages[1].id);
+
+        // Only the messages right after the cursor, still newest first.
+        let messages = get_channel_messages(&pool, channel_id, None, Some(5000), 2)
+            .await
+            .unwrap();
+        let ids: Vec<i64> = messages.iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![5002, 5001]);
+    }
+
+    #[tokio::test]
+    async fn test_get_channel_messages_around() {
+        let pool = test_pool().await;
+        let (user_id, _, channel_id) = setup_channel(&pool).await;
+        for i in 0..10 {
+            create_message(
+                &pool,
+                5500 + i,
+                channel_id,
+                user_id,
+                &format!("msg {}", i),
+                0,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        let ids = |messages: Vec<MessageRow>| messages.iter().map(|m| m.id).collect::<Vec<_>>();
+
+        let messages = get_channel_messages_around(&pool, channel_id, 5505, 4)
+            .await
+            .unwrap();
+        assert_eq!(ids(messages), vec![5507, 5506, 5505, 5504]);
+
+        // Near the newest message the older side fills the page.
+        let messages = get_channel_messages_around(&pool, channel_id, 5508, 4)
+            .await
+            .unwrap();
+        assert_eq!(ids(messages), vec![5509, 5508, 5507, 5506]);
+
+        // Near the start of the channel the newer side fills it.
+        let messages = get_channel_messages_around(&pool, channel_id, 5500, 4)
+            .await
+            .unwrap();
+        assert_eq!(ids(messages), vec![5503, 5502, 5501, 5500]);
     }
 
     #[tokio::test]
@@ -698,6 +1145,7 @@ mod tests {
         let updated = update_message(&pool, 7000, "After").await.unwrap();
         assert_eq!(updated.content.as_deref(), Some("After"));
         assert!(updated.edited_at.is_some());
+        assert_eq!(updated.edit_count, 1);
     }
 
     #[tokio::test]
@@ -725,10 +1173,148 @@ mod tests {
         create_message(&pool, 9002, channel_id, user_id, "hello again", 0, None)
             .await
             .unwrap();
-        let results = search_messages(&pool, channel_id, "hello", 50)
+        let results = search_messages(
+            &pool,
+            channel_id,
+            "hello",
+            &MessageSearchFilter::default(),
+            50,
+        )
+        .await
+        .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results
+            .iter()
+            .all(|hit| hit.excerpt.contains("<mark>hello</mark>")));
+
+        // Prefix matching keeps partial words searchable.
+        let results = search_messages(
+            &pool,
+            channel_id,
+            "good",
+            &MessageSearchFilter::default(),
+            50,
+        )
+        .await
+        .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message.id, 9001);
+
+        // Markup in the content is escaped; only the highlight is a tag.
+        create_message(
+            &pool,
+            9003,
+            channel_id,
+            user_id,
+            "<img src=x onerror=alert(1)> payload",
+            0,
+            None,
+        )
+        .await
+        .unwrap();
+        let results = search_messages(
+            &pool,
+            channel_id,
+            "payload",
+            &MessageSearchFilter::default(),
+            50,
+        )
+        .await
+        .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].excerpt.contains("<img"));
+        assert!(results[0].excerpt.contains("&lt;img"));
+        assert!(results[0].excerpt.contains("<mark>payload</mark>"));
+    }
+
+    #[test]
+    fn test_render_excerpt_keeps_marks_balanced() {
+        assert_eq!(
+            render_excerpt("a \u{2}b\u{3} & \"c\""),
+            "a <mark>b</mark> &amp; &quot;c&quot;"
+        );
+        assert_eq!(render_excerpt("\u{3}x\u{2}\u{2}y"), "x<mark>y</mark>");
+    }
+
+    #[tokio::test]
+    async fn test_search_index_follows_edits_and_deletes() {
+        let pool = test_pool().await;
+        let (user_id, _, channel_id) = setup_channel(&pool).await;
+        for (id, content) in [
+            (9200, "alpha release"),
+            (9201, "alpha build"),
+            (9202, "alpha notes"),
+        ] {
+            create_message(&pool, id, channel_id, user_id, content, 0, None)
+                .await
+                .unwrap();
+        }
+        let filter = MessageSearchFilter::default();
+
+        update_message(&pool, 9200, "beta release").await.unwrap();
+        let alpha = search_messages(&pool, channel_id, "alpha", &filter, 50)
+            .await
+            .unwrap();
+        assert_eq!(alpha.len(), 2);
+        let beta = search_messages(&pool, channel_id, "beta", &filter, 50)
             .await
             .unwrap();
-        assert_eq!(results.len(), 2);
+        assert_eq!(beta.len(), 1);
+        assert_eq!(beta[0].message.id, 9200);
+
+        bulk_delete_messages(&pool, channel_id, &[9201, 9202])
+            .await
+            .unwrap();
+        let alpha = search_messages(&pool, channel_id, "alpha", &filter, 50)
+            .await
+            .unwrap();
+        assert!(alpha.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_filters_and_query_syntax() {
+        let pool = test_pool().await;
+        let (user_id, _, channel_id) = setup_channel(&pool).await;
+        crate::users::create_user(&pool, 9399, "other", 2, "other@example.com", "hash")
+            .await
+            .unwrap();
+        create_message(&pool, 9300, channel_id, user_id, "deploy friday", 0, None)
+            .await
+            .unwrap();
+        create_message(&pool, 9301, channel_id, 9399, "deploy monday", 0, None)
+            .await
+            .unwrap();
+
+        let by_author = MessageSearchFilter {
+            author_id: Some(9399),
+            ..Default::default()
+        };
+        let results = search_messages(&pool, channel_id, "deploy", &by_author, 50)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message.id, 9301);
+
+        let future = MessageSearchFilter {
+            after: Some(Utc::now() + chrono::Duration::days(1)),
+            ..Default::default()
+        };
+        let results = search_messages(&pool, channel_id, "deploy", &future, 50)
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+
+        // FTS operators in user input are treated as literal terms.
+        let results = search_messages(
+            &pool,
+            channel_id,
+            "deploy OR \"NEAR(",
+            &MessageSearchFilter::default(),
+            50,
+        )
+        .await
+        .unwrap();
+        assert!(results.is_empty());
     }
 
     #[tokio::test]
@@ -738,7 +1324,15 @@ mod tests {
         create_message(&pool, 9100, channel_id, user_id, "nothing here", 0, None)
             .await
             .unwrap();
-        let results = search_messages(&pool, channel_id, "xyz", 50).await.unwrap();
+        let results = search_messages(
+            &pool,
+            channel_id,
+            "xyz",
+            &MessageSearchFilter::default(),
+            50,
+        )
+        .await
+        .unwrap();
         assert!(results.is_empty());
     }
 
@@ -750,18 +1344,61 @@ mod tests {
             .await
             .unwrap();
 
-        let pinned = pin_message(&pool, 10000, channel_id).await.unwrap();
-        assert!(pinned);
+        let outcome = pin_message(&pool, 10000, channel_id, user_id, 50)
+            .await
+            .unwrap();
+        assert_eq!(outcome, PinOutcome::Pinned);
 
         let pinned_msgs = get_pinned_messages(&pool, channel_id).await.unwrap();
         assert_eq!(pinned_msgs.len(), 1);
         assert_eq!(pinned_msgs[0].id, 10000);
+        assert_eq!(pinned_msgs[0].pinned_by, Some(user_id));
+        assert!(pinned_msgs[0].pinned_at.is_some());
 
         let unpinned = unpin_message(&pool, 10000, channel_id).await.unwrap();
         assert!(unpinned);
 
         let pinned_msgs = get_pinned_messages(&pool, channel_id).await.unwrap();
         assert!(pinned_msgs.is_empty());
+        let msg = get_message(&pool, 10000).await.unwrap().unwrap();
+        assert!(msg.pinned_at.is_none());
+        assert!(msg.pinned_by.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pin_limit_and_repin() {
+        let pool = test_pool().await;
+        let (user_id, _, channel_id) = setup_channel(&pool).await;
+        for id in 10100..10103 {
+            create_message(&pool, id, channel_id, user_id, "pin", 0, None)
+                .await
+                .unwrap();
+        }
+
+        for id in [10100, 10101] {
+            let outcome = pin_message(&pool, id, channel_id, user_id, 2)
+                .await
+                .unwrap();
+            assert_eq!(outcome, PinOutcome::Pinned);
+        }
+        // Re-pinning is a no-op, even at the limit.
+        let outcome = pin_message(&pool, 10100, channel_id, user_id, 2)
+            .await
+            .unwrap();
+        assert_eq!(outcome, PinOutcome::AlreadyPinned);
+        let outcome = pin_message(&pool, 10102, channel_id, user_id, 2)
+            .await
+            .unwrap();
+        assert_eq!(outcome, PinOutcome::LimitReached);
+        let outcome = pin_message(&pool, 99999, channel_id, user_id, 2)
+            .await
+            .unwrap();
+        assert_eq!(outcome, PinOutcome::NotFound);
+
+        assert_eq!(
+            get_pinned_messages(&pool, channel_id).await.unwrap().len(),
+            2
+        );
     }
 
     #[tokio::test]
@@ -781,6 +1418,13 @@ mod tests {
             .await
             .unwrap();
         }
+        let timestamps = get_message_timestamps(&pool, channel_id, &[11000, 11004, 99999])
+            .await
+            .unwrap();
+        let mut found: Vec<i64> = timestamps.iter().map(|(id, _)| *id).collect();
+        found.sort_unstable();
+        assert_eq!(found, vec![11000, 11004]);
+
         let deleted = bulk_delete_messages(&pool, channel_id, &[11000, 11001, 11002])
             .await
             .unwrap();
@@ -0,0 +1,380 @@
+use crate::read_states::UnreadCounts;
+use crate::{bool_from_any_row, datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, Row};
+use std::collections::HashMap;
+
+pub const TARGET_GUILD: &str = "guild";
+pub const TARGET_CHANNEL: &str = "channel";
+
+pub const LEVEL_ALL: &str = "all";
+pub const LEVEL_MENTIONS: &str = "mentions";
+pub const LEVEL_NOTHING: &str = "nothing";
+
+#[derive(Debug, Clone)]
+pub struct NotificationSettingRow {
+    pub user_id: i64,
+    pub target_id: i64,
+    pub target_type: String,
+    pub level: Option<String>,
+    pub push_level: Option<String>,
+    pub suppress_everyone: bool,
+    pub muted: bool,
+    pub muted_until: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for NotificationSettingRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let muted_until_raw: Option<String> = row.try_get("muted_until")?;
+        let updated_at_raw: String = row.try_get("updated_at")?;
+        Ok(Self {
+            user_id: row.try_get("user_id")?,
+            target_id: row.try_get("target_id")?,
+            target_type: row.try_get("target_type")?,
+            level: row.try_get("level")?,
+            push_level: row.try_get("push_level")?,
+            suppress_everyone: bool_from_any_row(row, "suppress_everyone")?,
+            muted: bool_from_any_row(row, "muted")?,
+            muted_until: muted_until_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+            updated_at: datetime_from_db_text(&updated_at_raw)?,
+        })
+    }
+}
+
+impl NotificationSettingRow {
+    /// Muted right now: flagged muted and either open-ended or not yet expired.
+    pub fn is_muted_at(&self, now: DateTime<Utc>) -> bool {
+        self.muted && self.muted_until.is_none_or(|until| until > now)
+    }
+}
+
+/// Values written for one space or channel.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NotificationSettingUpdate {
+    pub level: Option<String>,
+    pub push_level: Option<String>,
+    pub suppress_everyone: bool,
+    pub muted: bool,
+    pub muted_until: Option<DateTime<Utc>>,
+}
+
+/// What a user should be told about activity in one channel, after merging
+/// the channel's settings over its space's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveNotificationSettings {
+    pub level: String,
+    pub push_level: String,
+    pub suppress_everyone: bool,
+    pub muted: bool,
+}
+
+impl Default for EffectiveNotificationSettings {
+    fn default() -> Self {
+        Self {
+            level: LEVEL_ALL.to_string(),
+            push_level: LEVEL_ALL.to_string(),
+            suppress_everyone: false,
+            muted: false,
+        }
+    }
+}
+
+impl EffectiveNotificationSettings {
+    pub fn resolve(
+        guild: Option<&NotificationSettingRow>,
+        channel: Option<&NotificationSettingRow>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let pick = |field: fn(&NotificationSettingRow) -> Option<&String>| {
+            channel
+                .and_then(field)
+                .or_else(|| guild.and_then(field))
+                .cloned()
+        };
+        let level = pick(|row| row.level.as_ref()).unwrap_or_else(|| LEVEL_ALL.to_string());
+        let push_level = pick(|row| row.push_level.as_ref()).unwrap_or_else(|| level.clone());
+        Self {
+            level,
+            push_level,
+            suppress_everyone: guild.is_some_and(|row| row.suppress_everyone)
+                || channel.is_some_and(|row| row.suppress_everyone),
+            muted: guild.is_some_and(|row| row.is_muted_at(now))
+                || channel.is_some_and(|row| row.is_muted_at(now)),
+        }
+    }
+
+    /// Unread badges as the user should see them: muted channels report no
+    /// unread messages and `nothing` hides mentions too. Messages are still
+    /// stored and counted; only the reported numbers change.
+    pub fn visible_counts(&self, counts: UnreadCounts) -> UnreadCounts {
+        UnreadCounts {
+            unread_count: if self.muted { 0 } else { counts.unread_count },
+            mention_count: if self.level == LEVEL_NOTHING {
+                0
+            } else {
+                counts.mention_count
+            },
+        }
+    }
+
+    /// Whether a message in the channel should raise a notification (desktop
+    /// or push, per `level`) given whether it mentions the user directly and
+    /// whether it is an `@everyone`/`@here` ping.
+    pub fn should_notify(&self, level: &str, mentions_user: bool, mentions_everyone: bool) -> bool {
+        if self.muted {
+            return false;
+        }
+        match level {
+            LEVEL_NOTHING => false,
+            LEVEL_MENTIONS => mentions_user || (mentions_everyone && !self.suppress_everyone),
+            _ => true,
+        }
+    }
+}
+
+pub async fn list_notification_settings(
+    pool: &DbPool,
+    user_id: i64,
+) -> Result<Vec<NotificationSettingRow>, DbError> {
+    let rows = sqlx::query_as::<_, NotificationSettingRow>(
+        "SELECT user_id, target_id, target_type, level, push_level,
+                CASE WHEN suppress_everyone THEN 1 ELSE 0 END AS suppress_everyone,
+                CASE WHEN muted THEN 1 ELSE 0 END AS muted,
+                muted_until, updated_at
+         FROM notification_settings
+         WHERE user_id = $1
+         ORDER BY target_id",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Replace the user's settings for one space or channel. Writing nothing but
+/// defaults removes the row so the target goes back to inheriting.
+pub async fn set_notification_setting(
+    pool: &DbPool,
+    user_id: i64,
+    target_id: i64,
+    target_type: &str,
+    update: &NotificationSettingUpdate,
+) -> Result<(), DbError> {
+    if *update == NotificationSettingUpdate::default() {
+        sqlx::query("DELETE FROM notification_settings WHERE user_id = $1 AND target_id = $2")
+            .bind(user_id)
+            .bind(target_id)
+            .execute(pool)
+            .await?;
+        return Ok(());
+    }
+    sqlx::query(
+        "INSERT INTO notification_settings
+             (user_id, target_id, target_type, level, push_level, suppress_everyone, muted, muted_until, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+         ON CONFLICT (user_id, target_id) DO UPDATE SET
+             target_type = $3,
+             level = $4,
+             push_level = $5,
+             suppress_everyone = $6,
+             muted = $7,
+             muted_until = $8,
+             updated_at = $9",
+    )
+    .bind(user_id)
+    .bind(target_id)
+    .bind(target_type)
+    .bind(update.level.as_deref())
+    .bind(update.push_level.as_deref())
+    .bind(update.suppress_everyone)
+    .bind(update.muted)
+    .bind(update.muted_until.map(datetime_to_db_text))
+    .bind(datetime_to_db_text(Utc::now()))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Settings that apply to `channel_id` for `user_id`, merging in the row for
+/// the channel's space when it has one.
+pub async fn get_effective_settings(
+    pool: &DbPool,
+    user_id: i64,
+    channel_id: i64,
+    now: DateTime<Utc>,
+) -> Result<EffectiveNotificationSettings, DbError> {
+    let rows = sqlx::query_as::<_, NotificationSettingRow>(
+        "SELECT user_id, target_id, target_type, level, push_level,
+                CASE WHEN suppress_everyone THEN 1 ELSE 0 END AS suppress_everyone,
+                CASE WHEN muted THEN 1 ELSE 0 END AS muted,
+                muted_until, updated_at
+         FROM notification_settings
+         WHERE user_id = $1
+           AND (target_id = $2
+                OR target_id = (SELECT space_id FROM channels WHERE id = $2))",
+    )
+    .bind(user_id)
+    .bind(channel_id)
+    .fetch_all(pool)
+    .await?;
+    let guild = rows.iter().find(|row| row.target_type == TARGET_GUILD);
+    let channel = rows.iter().find(|row| row.target_id == channel_id);
+    Ok(EffectiveNotificationSettings::resolve(guild, channel, now))
+}
+
+/// [`get_effective_settings`] for many channels in one query, keyed by
+/// channel id. Unknown channels are left out.
+pub async fn get_effective_settings_for_channels(
+    pool: &DbPool,
+    user_id: i64,
+    channel_ids: &[i64],
+    now: DateTime<Utc>,
+) -> Result<HashMap<i64, EffectiveNotificationSettings>, DbError> {
+    if channel_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let placeholders: Vec<String> = (2..=channel_ids.len() + 1)
+        .map(|i| format!("${}", i))
+        .collect();
+    let sql = format!(
+        "SELECT c.id AS channel_id, ns.user_id, ns.target_id, ns.target_type, ns.level,
+                ns.push_level,
+                CASE WHEN ns.suppress_everyone THEN 1 ELSE 0 END AS suppress_everyone,
+                CASE WHEN ns.muted THEN 1 ELSE 0 END AS muted,
+                ns.muted_until, ns.updated_at
+         FROM channels c
+         LEFT JOIN notification_settings ns
+           ON ns.user_id = $1 AND (ns.target_id = c.id OR ns.target_id = c.space_id)
+         WHERE c.id IN ({})",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query(&sql).bind(user_id);
+    for channel_id in channel_ids {
+        query = query.bind(channel_id);
+    }
+    let rows = query.fetch_all(pool).await?;
+
+    let mut by_channel: HashMap<i64, Vec<NotificationSettingRow>> = HashMap::new();
+    for row in &rows {
+        let channel_id: i64 = row.try_get("channel_id")?;
+        let settings = by_channel.entry(channel_id).or_default();
+        let target_id: Option<i64> = row.try_get("target_id")?;
+        if target_id.is_some() {
+            settings.push(NotificationSettingRow::from_row(row)?);
+        }
+    }
+    Ok(by_channel
+        .into_iter()
+        .map(|(channel_id, rows)| {
+            let guild = rows.iter().find(|row| row.target_type == TARGET_GUILD);
+            let channel = rows.iter().find(|row| row.target_id == channel_id);
+            (
+                channel_id,
+                EffectiveNotificationSettings::resolve(guild, channel, now),
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        crate::users::create_user(&pool, 1, "reader", 1, "reader@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 10, "space", 1, None)
+            .await
+            .unwrap();
+        for id in [20, 21] {
+            crate::channels::create_channel(&pool, id, 10, "text", 0, 0, None, None)
+                .await
+                .unwrap();
+        }
+        pool
+    }
+
+    #[tokio::test]
+    async fn channel_settings_override_space_and_mutes_expire() {
+        let pool = test_pool().await;
+        let now = Utc::now();
+        set_notification_setting(
+            &pool,
+            1,
+            10,
+            TARGET_GUILD,
+            &NotificationSettingUpdate {
+                level: Some(LEVEL_MENTIONS.into()),
+                suppress_everyone: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        set_notification_setting(
+            &pool,
+            1,
+            20,
+            TARGET_CHANNEL,
+            &NotificationSettingUpdate {
+                push_level: Some(LEVEL_NOTHING.into()),
+                muted: true,
+                muted_until: Some(now + Duration::hours(1)),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let effective = get_effective_settings(&pool, 1, 20, now).await.unwrap();
+        assert_eq!(
+            effective,
+            EffectiveNotificationSettings {
+                level: LEVEL_MENTIONS.into(),
+                push_level: LEVEL_NOTHING.into(),
+                suppress_everyone: true,
+                muted: true,
+            }
+        );
+        assert!(!effective.should_notify(&effective.level, true, false));
+
+        let later = get_effective_settings(&pool, 1, 20, now + Duration::hours(2))
+            .await
+            .unwrap();
+        assert!(!later.muted);
+        assert!(later.should_notify(&later.level, true, false));
+        assert!(!later.should_notify(&later.level, false, true));
+
+        // Other channels in the space only see the space row.
+        let sibling = get_effective_settings(&pool, 1, 21, now).await.unwrap();
+        assert_eq!(sibling.push_level, LEVEL_MENTIONS);
+        assert!(!sibling.muted);
+
+        let batched = get_effective_settings_for_channels(&pool, 1, &[20, 21, 99], now)
+            .await
+            .unwrap();
+        assert_eq!(batched.len(), 2);
+        assert_eq!(batched[&20], effective);
+        assert_eq!(batched[&21], sibling);
+
+        // Writing defaults clears the row.
+        set_notification_setting(
+            &pool,
+            1,
+            20,
+            TARGET_CHANNEL,
+            &NotificationSettingUpdate::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(list_notification_settings(&pool, 1).await.unwrap().len(), 1);
+    }
+}
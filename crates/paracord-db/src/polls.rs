@@ -88,7 +88,7 @@ pub async fn create_poll(
     .await?;
 
     for (i, opt) in options.iter().enumerate() {
-        let option_id = paracord_util::snowflake::generate(1);
+        let option_id = paracord_util::snowflake::generate();
         sqlx::query(
             "INSERT INTO poll_options (id, poll_id, text, emoji, position)
              VALUES ($1, $2, $3, $4, $5)",
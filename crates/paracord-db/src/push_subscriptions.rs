@@ -0,0 +1,141 @@
+use crate::{datetime_to_db_text, DbError, DbPool};
+use chrono::Utc;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PushSubscriptionRow {
+    pub endpoint: String,
+    pub user_id: i64,
+    pub p256dh: String,
+    pub auth_secret: String,
+    pub user_agent: Option<String>,
+}
+
+/// Save a subscription for `user_id`, refreshing it if the user registered
+/// the endpoint before (the browser re-subscribed). Returns `false`, without
+/// changing anything, if the endpoint belongs to another user. Only the
+/// user's `keep_latest` most recent subscriptions are kept.
+pub async fn upsert_push_subscription(
+    pool: &DbPool,
+    user_id: i64,
+    endpoint: &str,
+    p256dh: &str,
+    auth_secret: &str,
+    user_agent: Option<&str>,
+    keep_latest: i64,
+) -> Result<bool, DbError> {
+    let mut tx = pool.begin().await?;
+    let saved = sqlx::query(
+        "INSERT INTO push_subscriptions (endpoint, user_id, p256dh, auth_secret, user_agent, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (endpoint) DO UPDATE SET
+             p256dh = $3,
+             auth_secret = $4,
+             user_agent = $5,
+             created_at = $6
+         WHERE push_subscriptions.user_id = $2",
+    )
+    .bind(endpoint)
+    .bind(user_id)
+    .bind(p256dh)
+    .bind(auth_secret)
+    .bind(user_agent)
+    .bind(datetime_to_db_text(Utc::now()))
+    .execute(&mut *tx)
+    .await?
+    .rows_affected()
+        > 0;
+    if !saved {
+        return Ok(false);
+    }
+    sqlx::query(
+        "DELETE FROM push_subscriptions
+         WHERE user_id = $1
+           AND endpoint NOT IN (
+               SELECT endpoint FROM push_subscriptions
+               WHERE user_id = $1
+               ORDER BY created_at DESC, endpoint
+               LIMIT $2
+           )",
+    )
+    .bind(user_id)
+    .bind(keep_latest)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+pub async fn delete_push_subscription(
+    pool: &DbPool,
+    user_id: i64,
+    endpoint: &str,
+) -> Result<bool, DbError> {
+    let result = sqlx::query("DELETE FROM push_subscriptions WHERE user_id = $1 AND endpoint = $2")
+        .bind(user_id)
+        .bind(endpoint)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Drop a subscription the push service reported as gone.
+pub async fn delete_push_subscription_by_endpoint(
+    pool: &DbPool,
+    endpoint: &str,
+) -> Result<(), DbError> {
+    sqlx::query("DELETE FROM push_subscriptions WHERE endpoint = $1")
+        .bind(endpoint)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn list_user_push_subscriptions(
+    pool: &DbPool,
+    user_id: i64,
+) -> Result<Vec<PushSubscriptionRow>, DbError> {
+    let rows = sqlx::query_as::<_, PushSubscriptionRow>(
+        "SELECT endpoint, user_id, p256dh, auth_secret, user_agent
+         FROM push_subscriptions
+         WHERE user_id = $1
+         ORDER BY created_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Members of `space_id` with at least one push subscription. With
+/// `role_ids`, only members holding one of those roles.
+pub async fn list_subscribed_member_ids(
+    pool: &DbPool,
+    space_id: i64,
+    role_ids: Option<&[i64]>,
+) -> Result<Vec<i64>, DbError> {
+    let mut sql = "SELECT DISTINCT ps.user_id
+         FROM push_subscriptions ps
+         INNER JOIN members m ON m.user_id = ps.user_id AND m.guild_id = $1"
+        .to_string();
+    if let Some(role_ids) = role_ids {
+        if role_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let placeholders: Vec<String> = (2..=role_ids.len() + 1)
+            .map(|i| format!("${}", i))
+            .collect();
+        sql.push_str(&format!(
+            " WHERE EXISTS (
+                 SELECT 1 FROM member_roles mr
+                 WHERE mr.user_id = ps.user_id AND mr.role_id IN ({})
+             )",
+            placeholders.join(", ")
+        ));
+    }
+    let mut query = sqlx::query_as::<_, (i64,)>(&sql).bind(space_id);
+    for role_id in role_ids.unwrap_or_default() {
+        query = query.bind(role_id);
+    }
+    let rows = query.fetch_all(pool).await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
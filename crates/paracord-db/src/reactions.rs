@@ -31,6 +31,36 @@ pub struct ReactionCountRow {
     pub count: i64,
 }
 
+/// Per-emoji reaction count on a message, with whether the viewer is among
+/// the reactors.
+#[derive(Debug, Clone)]
+pub struct ReactionSummaryRow {
+    pub emoji_name: String,
+    pub emoji_id: Option<i64>,
+    pub count: i64,
+    pub me: bool,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ReactionSummaryRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let me: i64 = row.try_get("me")?;
+        Ok(Self {
+            emoji_name: row.try_get("emoji_name")?,
+            emoji_id: row.try_get("emoji_id")?,
+            count: row.try_get("count")?,
+            me: me > 0,
+        })
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ReactionUserRow {
+    pub id: i64,
+    pub username: String,
+    pub discriminator: i16,
+    pub avatar_hash: Option<String>,
+}
+
 pub async fn add_reaction(
     pool: &DbPool,
     message_id: i64,
@@ -102,3 +132,127 @@ pub async fn get_reaction_users(
     .await?;
     Ok(rows.into_iter().map(|r| r.0).collect())
 }
+
+pub async fn get_message_reaction_summary(
+    pool: &DbPool,
+    message_id: i64,
+    viewer_id: i64,
+) -> Result<Vec<ReactionSummaryRow>, DbError> {
+    let rows = sqlx::query_as::<_, ReactionSummaryRow>(
+        "SELECT emoji_name, emoji_id, COUNT(*) as count,
+                SUM(CASE WHEN user_id = $2 THEN 1 ELSE 0 END) as me
+         FROM reactions WHERE message_id = $1
+         GROUP BY emoji_name, emoji_id
+         ORDER BY MIN(created_at)",
+    )
+    .bind(message_id)
+    .bind(viewer_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Users who reacted to a message with `emoji_name`, ordered by user id.
+/// Pass the last id of the previous page as `after` to continue.
+pub async fn get_reaction_users_page(
+    pool: &DbPool,
+    message_id: i64,
+    emoji_name: &str,
+    after: Option<i64>,
+    limit: i64,
+) -> Result<Vec<ReactionUserRow>, DbError> {
+    let rows = sqlx::query_as::<_, ReactionUserRow>(
+        "SELECT u.id, u.username, u.discriminator, u.avatar_hash
+         FROM reactions r
+         INNER JOIN users u ON u.id = r.user_id
+         WHERE r.message_id = $1 AND r.emoji_name = $2 AND r.user_id > $3
+         ORDER BY r.user_id
+         LIMIT $4",
+    )
+    .bind(message_id)
+    .bind(emoji_name)
+    .bind(after.unwrap_or(0))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        for id in 1..=3 {
+            crate::users::create_user(
+                &pool,
+                id,
+                &format!("user{id}"),
+                1,
+                &format!("u{id}@example.com"),
+                "hash",
+            )
+            .await
+            .unwrap();
+        }
+        crate::guilds::create_guild(&pool, 10, "space", 1, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 20, 10, "general", 0, 0, None, None)
+            .await
+            .unwrap();
+        crate::messages::create_message(&pool, 30, 20, 1, "hello", 0, None)
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_reaction_users_page_by_user_id() {
+        let pool = test_pool().await;
+        for user_id in [3, 1, 2] {
+            add_reaction(&pool, 30, user_id, "👍", None).await.unwrap();
+        }
+        add_reaction(&pool, 30, 2, "party:99", Some(99))
+            .await
+            .unwrap();
+
+        let first = get_reaction_users_page(&pool, 30, "👍", None, 2)
+            .await
+            .unwrap();
+        assert_eq!(first.iter().map(|u| u.id).collect::<Vec<_>>(), vec![1, 2]);
+        let rest = get_reaction_users_page(&pool, 30, "👍", Some(2), 2)
+            .await
+            .unwrap();
+        assert_eq!(rest.iter().map(|u| u.id).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(rest[0].username, "user3");
+
+        let custom = get_reaction_users_page(&pool, 30, "party:99", None, 10)
+            .await
+            .unwrap();
+        assert_eq!(custom.len(), 1);
+        assert_eq!(custom[0].id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_reaction_summary_flags_viewer() {
+        let pool = test_pool().await;
+        add_reaction(&pool, 30, 1, "👍", None).await.unwrap();
+        add_reaction(&pool, 30, 2, "👍", None).await.unwrap();
+        add_reaction(&pool, 30, 2, "party:99", Some(99))
+            .await
+            .unwrap();
+
+        let summary = get_message_reaction_summary(&pool, 30, 1).await.unwrap();
+        assert_eq!(summary.len(), 2);
+        let thumbs = summary.iter().find(|r| r.emoji_name == "👍").unwrap();
+        assert_eq!(thumbs.count, 2);
+        assert!(thumbs.me);
+        let party = summary.iter().find(|r| r.emoji_name == "party:99").unwrap();
+        assert_eq!(party.emoji_id, Some(99));
+        assert_eq!(party.count, 1);
+        assert!(!party.me);
+    }
+}
@@ -1,4 +1,9 @@
+use crate::message_mentions::{
+    MENTION_TYPE_EVERYONE, MENTION_TYPE_HERE, MENTION_TYPE_ROLE, MENTION_TYPE_USER,
+};
 use crate::{DbError, DbPool};
+use sqlx::Row;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, sqlx::FromRow)]
 pub struct ReadStateRow {
@@ -8,6 +13,17 @@ pub struct ReadStateRow {
     pub mention_count: i32,
 }
 
+/// Highest unread or mention count reported; clients show it as "99+".
+pub const UNREAD_COUNT_CAP: i64 = 100;
+
+/// Messages after a read marker, each capped at the `cap` passed to
+/// [`count_unread`] so the queries stay cheap on busy channels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnreadCounts {
+    pub unread_count: i64,
+    pub mention_count: i64,
+}
+
 pub async fn get_user_read_states(
     pool: &DbPool,
     user_id: i64,
@@ -23,6 +39,31 @@ pub async fn get_user_read_states(
     Ok(rows)
 }
 
+/// The user's read states for just `channel_ids`.
+pub async fn get_read_states_for_channels(
+    pool: &DbPool,
+    user_id: i64,
+    channel_ids: &[i64],
+) -> Result<Vec<ReadStateRow>, DbError> {
+    if channel_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders: Vec<String> = (2..=channel_ids.len() + 1)
+        .map(|i| format!("${}", i))
+        .collect();
+    let sql = format!(
+        "SELECT user_id, channel_id, last_message_id, mention_count
+         FROM read_states
+         WHERE user_id = $1 AND channel_id IN ({})",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query_as::<_, ReadStateRow>(&sql).bind(user_id);
+    for channel_id in channel_ids {
+        query = query.bind(*channel_id);
+    }
+    Ok(query.fetch_all(pool).await?)
+}
+
 pub async fn get_read_state(
     pool: &DbPool,
     user_id: i64,
@@ -58,3 +99,383 @@ pub async fn update_read_state(
     .await?;
     Ok(row)
 }
+
+/// Count messages by other users after `after_message_id`, and how many of
+/// those mention the user, one of their roles, or (unless `include_everyone`
+/// is false) `@everyone`/`@here` according to `message_mentions`. Each count
+/// stops at `cap`.
+pub async fn count_unread(
+    pool: &DbPool,
+    user_id: i64,
+    channel_id: i64,
+    after_message_id: i64,
+    cap: i64,
+    include_everyone: bool,
+) -> Result<UnreadCounts, DbError> {
+    let row = sqlx::query(
+        "SELECT
+             (SELECT COUNT(*) FROM (
+                 SELECT 1 FROM messages m
+                 WHERE m.channel_id = $1 AND m.id > $2 AND m.author_id <> $3
+                 LIMIT $4
+             ) AS unread) AS unread_count,
+             (SELECT COUNT(*) FROM (
+                 SELECT 1 FROM messages m
+                 WHERE m.channel_id = $1 AND m.id > $2 AND m.author_id <> $3
+                   AND EXISTS (
+                       SELECT 1 FROM message_mentions mm
+                       WHERE mm.message_id = m.id
+                         AND ((mm.mention_type = $5 AND mm.target_id = $3)
+                              OR (mm.mention_type = $6
+                                  AND mm.target_id IN (
+                                      SELECT role_id FROM member_roles WHERE user_id = $3
+                                  ))
+                              OR ($9 = 1 AND mm.mention_type IN ($7, $8)))
+                   )
+                 LIMIT $4
+             ) AS mentions) AS mention_count",
+    )
+    .bind(channel_id)
+    .bind(after_message_id)
+    .bind(user_id)
+    .bind(cap)
+    .bind(MENTION_TYPE_USER)
+    .bind(MENTION_TYPE_ROLE)
+    .bind(MENTION_TYPE_EVERYONE)
+    .bind(MENTION_TYPE_HERE)
+    .bind(i32::from(include_everyone))
+    .fetch_one(pool)
+    .await?;
+    Ok(UnreadCounts {
+        unread_count: row.try_get("unread_count")?,
+        mention_count: row.try_get("mention_count")?,
+    })
+}
+
+/// Where [`count_unread_for_channels`] starts counting in one channel.
+#[derive(Debug, Clone, Copy)]
+pub struct UnreadMarker {
+    pub channel_id: i64,
+    pub after_message_id: i64,
+    pub include_everyone: bool,
+}
+
+/// [`count_unread`] for many channels in one grouped query. Channels with
+/// nothing unread are left out of the map. The counts are capped at `cap`
+/// once counted rather than while scanning.
+pub async fn count_unread_for_channels(
+    pool: &DbPool,
+    user_id: i64,
+    markers: &[UnreadMarker],
+    cap: i64,
+) -> Result<HashMap<i64, UnreadCounts>, DbError> {
+    if markers.is_empty() {
+        return Ok(HashMap::new());
+    }
+    // $1 is the user and $2-$3 the mention types; markers follow, then the
+    // @everyone/@here types and channels when any channel wants them.
+    let mut next_param = 4;
+    let mut ranges = Vec::with_capacity(markers.len());
+    for _ in markers {
+        ranges.push(format!(
+            "(m.channel_id = ${} AND m.id > ${})",
+            next_param,
+            next_param + 1
+        ));
+        next_param += 2;
+    }
+    let everyone_channels: Vec<i64> = markers
+        .iter()
+        .filter(|marker| marker.include_everyone)
+        .map(|marker| marker.channel_id)
+        .collect();
+    let everyone_clause = if everyone_channels.is_empty() {
+        String::new()
+    } else {
+        let placeholders: Vec<String> = (next_param + 2..next_param + 2 + everyone_channels.len())
+            .map(|i| format!("${}", i))
+            .collect();
+        format!(
+            "OR (mm.mention_type IN (${}, ${}) AND m.channel_id IN ({}))",
+            next_param,
+            next_param + 1,
+            placeholders.join(", ")
+        )
+    };
+    let sql = format!(
+        "SELECT m.channel_id,
+                COUNT(*) AS unread_count,
+                SUM(CASE WHEN EXISTS (
+                    SELECT 1 FROM message_mentions mm
+                    WHERE mm.message_id = m.id
+                      AND ((mm.mention_type = $2 AND mm.target_id = $1)
+                           OR (mm.mention_type = $3
+                               AND mm.target_id IN (
+                                   SELECT role_id FROM member_roles WHERE user_id = $1
+                               ))
+                           {everyone_clause})
+                ) THEN 1 ELSE 0 END) AS mention_count
+         FROM messages m
+         WHERE m.author_id <> $1 AND ({})
+         GROUP BY m.channel_id",
+        ranges.join(" OR ")
+    );
+    let mut query = sqlx::query(&sql)
+        .bind(user_id)
+        .bind(MENTION_TYPE_USER)
+        .bind(MENTION_TYPE_ROLE);
+    for marker in markers {
+        query = query.bind(marker.channel_id).bind(marker.after_message_id);
+    }
+    if !everyone_channels.is_empty() {
+        query = query.bind(MENTION_TYPE_EVERYONE).bind(MENTION_TYPE_HERE);
+        for channel_id in &everyone_channels {
+            query = query.bind(*channel_id);
+        }
+    }
+    let rows = query.fetch_all(pool).await?;
+    let mut counts = HashMap::with_capacity(rows.len());
+    for row in rows {
+        let unread_count: i64 = row.try_get("unread_count")?;
+        let mention_count: i64 = row.try_get("mention_count")?;
+        counts.insert(
+            row.try_get("channel_id")?,
+            UnreadCounts {
+                unread_count: unread_count.min(cap),
+                mention_count: mention_count.min(cap),
+            },
+        );
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message_mentions::{replace_message_mentions, MessageMentions};
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        crate::users::create_user(&pool, 1, "reader", 1, "reader@example.com", "hash")
+            .await
+            .unwrap();
+        crate::users::create_user(&pool, 2, "writer", 1, "writer@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 10, "space", 2, None)
+            .await
+            .unwrap();
+        crate::members::add_member(&pool, 1, 10).await.unwrap();
+        crate::channels::create_channel(&pool, 20, 10, "general", 0, 0, None, None)
+            .await
+            .unwrap();
+        crate::roles::create_role(&pool, 30, 10, "mods", 0)
+            .await
+            .unwrap();
+        crate::roles::add_member_role(&pool, 1, 10, 30)
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_count_unread_counts_messages_and_mentions() {
+        let pool = test_pool().await;
+        let contents = [
+            "hello",
+            "hey <@1>",
+            "ping <@!1>",
+            "calling <@&30>",
+            "not you <@11>",
+            "nor <@&31>",
+        ];
+        let mentions = [
+            MessageMentions::default(),
+            MessageMentions {
+                user_ids: vec![1],
+                ..Default::default()
+            },
+            MessageMentions {
+                user_ids: vec![1],
+                ..Default::default()
+            },
+            MessageMentions {
+                role_ids: vec![30],
+                ..Default::default()
+            },
+            MessageMentions {
+                user_ids: vec![11],
+                ..Default::default()
+            },
+            MessageMentions {
+                role_ids: vec![31],
+                ..Default::default()
+            },
+        ];
+        for (offset, (content, mentions)) in contents.iter().zip(&mentions).enumerate() {
+            let id = 100 + offset as i64;
+            crate::messages::create_message(&pool, id, 20, 2, content, 0, None)
+                .await
+                .unwrap();
+            replace_message_mentions(&pool, id, 10, mentions, true)
+                .await
+                .unwrap();
+        }
+        // The reader's own messages never count as unread.
+        crate::messages::create_message(&pool, 200, 20, 1, "mine <@1>", 0, None)
+            .await
+            .unwrap();
+        replace_message_mentions(
+            &pool,
+            200,
+            10,
+            &MessageMentions {
+                user_ids: vec![1],
+                ..Default::default()
+            },
+            true,
+        )
+        .await
+        .unwrap();
+
+        let counts = count_unread(&pool, 1, 20, 0, 100, true).await.unwrap();
+        assert_eq!(
+            counts,
+            UnreadCounts {
+                unread_count: 6,
+                mention_count: 3,
+            }
+        );
+
+        let counts = count_unread(&pool, 1, 20, 102, 100, true).await.unwrap();
+        assert_eq!(counts.unread_count, 3);
+        assert_eq!(counts.mention_count, 1);
+
+        let counts = count_unread(&pool, 1, 20, 0, 2, true).await.unwrap();
+        assert_eq!(counts.unread_count, 2);
+        assert_eq!(counts.mention_count, 2);
+    }
+
+    #[tokio::test]
+    async fn count_unread_for_channels_matches_per_channel_counts() {
+        let pool = test_pool().await;
+        crate::channels::create_channel(&pool, 21, 10, "random", 0, 1, None, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 22, 10, "quiet", 0, 2, None, None)
+            .await
+            .unwrap();
+        let sent = [
+            (100, 20, MessageMentions::default()),
+            (
+                101,
+                20,
+                MessageMentions {
+                    user_ids: vec![1],
+                    ..Default::default()
+                },
+            ),
+            (
+                102,
+                20,
+                MessageMentions {
+                    everyone: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                103,
+                21,
+                MessageMentions {
+                    everyone: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                104,
+                21,
+                MessageMentions {
+                    role_ids: vec![30],
+                    ..Default::default()
+                },
+            ),
+        ];
+        for (id, channel_id, mentions) in &sent {
+            crate::messages::create_message(&pool, *id, *channel_id, 2, "hi", 0, None)
+                .await
+                .unwrap();
+            replace_message_mentions(&pool, *id, 10, mentions, true)
+                .await
+                .unwrap();
+        }
+
+        let markers = [
+            UnreadMarker {
+                channel_id: 20,
+                after_message_id: 0,
+                include_everyone: true,
+            },
+            UnreadMarker {
+                channel_id: 21,
+                after_message_id: 0,
+                include_everyone: false,
+            },
+            UnreadMarker {
+                channel_id: 22,
+                after_message_id: 0,
+                include_everyone: true,
+            },
+        ];
+        let counts = count_unread_for_channels(&pool, 1, &markers, 100)
+            .await
+            .unwrap();
+        for marker in &markers {
+            let expected = count_unread(
+                &pool,
+                1,
+                marker.channel_id,
+                marker.after_message_id,
+                100,
+                marker.include_everyone,
+            )
+            .await
+            .unwrap();
+            let batched = counts.get(&marker.channel_id).copied().unwrap_or_default();
+            assert_eq!(batched, expected, "channel {}", marker.channel_id);
+        }
+        assert_eq!(counts[&20].mention_count, 2);
+        assert_eq!(counts[&21].mention_count, 1);
+        assert!(!counts.contains_key(&22));
+
+        let capped = count_unread_for_channels(&pool, 1, &markers[..1], 1)
+            .await
+            .unwrap();
+        assert_eq!(
+            capped[&20],
+            UnreadCounts {
+                unread_count: 1,
+                mention_count: 1,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn read_states_for_channels_only_returns_requested_channels() {
+        let pool = test_pool().await;
+        crate::channels::create_channel(&pool, 21, 10, "random", 0, 1, None, None)
+            .await
+            .unwrap();
+        update_read_state(&pool, 1, 20, 100).await.unwrap();
+        update_read_state(&pool, 1, 21, 101).await.unwrap();
+        update_read_state(&pool, 2, 20, 102).await.unwrap();
+
+        let rows = get_read_states_for_channels(&pool, 1, &[20]).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].channel_id, rows[0].last_message_id), (20, 100));
+        assert!(get_read_states_for_channels(&pool, 1, &[])
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -66,6 +66,34 @@ pub async fn create_relationship(
     Ok(())
 }
 
+/// Block `target_id` on behalf of `user_id`.
+///
+/// Runs in one transaction: the target's friendship or pending request towards
+/// the blocker is removed and the blocker's own row becomes a block. A block
+/// the target placed on the blocker is left in place. Blocking is idempotent.
+/// Returns `true` if a reverse-direction row was removed.
+pub async fn block_user(pool: &DbPool, user_id: i64, target_id: i64) -> Result<bool, DbError> {
+    let mut tx = pool.begin().await?;
+    let removed = sqlx::query(
+        "DELETE FROM relationships
+         WHERE user_id = $1 AND target_id = $2 AND rel_type <> 2",
+    )
+    .bind(target_id)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "INSERT INTO relationships (user_id, target_id, rel_type) VALUES ($1, $2, 2)
+         ON CONFLICT (user_id, target_id) DO UPDATE SET rel_type = 2",
+    )
+    .bind(user_id)
+    .bind(target_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(removed.rows_affected() > 0)
+}
+
 /// Get a single relationship row (directional).
 pub async fn get_relationship(
     pool: &DbPool,
@@ -92,6 +120,15 @@ pub async fn get_relationship(
 pub async fn get_relationships(
     pool: &DbPool,
     user_id: i64,
+) -> Result<Vec<RelationshipWithUserRow>, DbError> {
+    get_relationships_limited(pool, user_id, i64::MAX).await
+}
+
+/// The user's relationships, oldest first, stopping after `limit` rows.
+pub async fn get_relationships_limited(
+    pool: &DbPool,
+    user_id: i64,
+    limit: i64,
 ) -> Result<Vec<RelationshipWithUserRow>, DbError> {
     let rows = sqlx::query_as::<_, RelationshipWithUserRow>(
         "SELECT r.user_id, r.target_id, r.rel_type, r.created_at,
@@ -105,9 +142,11 @@ pub async fn get_relationships(
          FROM relationships r
          INNER JOIN users u ON u.id = r.user_id
          WHERE r.target_id = $1 AND r.rel_type = 4
-         ORDER BY 4"
+         ORDER BY 4
+         LIMIT $2"
     )
     .bind(user_id)
+    .bind(limit)
     .fetch_all(pool)
     .await?;
     Ok(rows)
@@ -188,3 +227,70 @@ pub async fn is_blocked_either_direction(
     .await?;
     Ok(row.is_some())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        for (id, name) in [(1, "alice"), (2, "bob"), (3, "carol")] {
+            crate::users::create_user(&pool, id, name, 1, &format!("{name}@example.com"), "hash")
+                .await
+                .unwrap();
+        }
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_block_removes_friendship_and_pending_requests() {
+        let pool = test_pool().await;
+        create_relationship(&pool, 1, 2, 1).await.unwrap();
+        create_relationship(&pool, 2, 1, 1).await.unwrap();
+        create_relationship(&pool, 3, 1, 4).await.unwrap();
+
+        assert!(block_user(&pool, 1, 2).await.unwrap());
+        assert!(block_user(&pool, 1, 3).await.unwrap());
+        // Blocking again changes nothing.
+        assert!(!block_user(&pool, 1, 2).await.unwrap());
+
+        assert!(get_relationship(&pool, 2, 1).await.unwrap().is_none());
+        assert!(get_relationship(&pool, 3, 1).await.unwrap().is_none());
+        assert_eq!(
+            get_relationship(&pool, 1, 2)
+                .await
+                .unwrap()
+                .unwrap()
+                .rel_type,
+            2
+        );
+        assert!(get_friend_user_ids(&pool, 1).await.unwrap().is_empty());
+        let rels = get_relationships(&pool, 1).await.unwrap();
+        assert!(rels.iter().all(|r| r.rel_type == 2));
+        assert!(is_blocked_either_direction(&pool, 2, 1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_block_keeps_the_other_users_block() {
+        let pool = test_pool().await;
+        block_user(&pool, 2, 1).await.unwrap();
+        assert!(!block_user(&pool, 1, 2).await.unwrap());
+        assert_eq!(
+            get_relationship(&pool, 2, 1)
+                .await
+                .unwrap()
+                .unwrap()
+                .rel_type,
+            2
+        );
+        assert_eq!(
+            get_relationship(&pool, 1, 2)
+                .await
+                .unwrap()
+                .unwrap()
+                .rel_type,
+            2
+        );
+    }
+}
@@ -14,6 +14,7 @@ pub struct RoleRow {
     pub managed: bool,
     pub mentionable: bool,
     pub server_wide: bool,
+    pub icon_hash: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -31,6 +32,7 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for RoleRow {
             managed: bool_from_any_row(row, "managed")?,
             mentionable: bool_from_any_row(row, "mentionable")?,
             server_wide: bool_from_any_row(row, "server_wide")?,
+            icon_hash: row.try_get("icon_hash")?,
             created_at: datetime_from_db_text(&created_at_raw)?,
         })
     }
@@ -53,7 +55,7 @@ pub async fn create_role(
     let row = sqlx::query_as::<_, RoleRow>(
         "INSERT INTO roles (id, space_id, name, permissions)
          VALUES ($1, $2, $3, $4)
-         RETURNING id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, created_at"
+         RETURNING id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, icon_hash, created_at"
     )
     .bind(id)
     .bind(space_id)
@@ -66,7 +68,7 @@ pub async fn create_role(
 
 pub async fn get_role(pool: &DbPool, id: i64) -> Result<Option<RoleRow>, DbError> {
     let row = sqlx::query_as::<_, RoleRow>(
-        "SELECT id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, created_at
+        "SELECT id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, icon_hash, created_at
          FROM roles WHERE id = $1"
     )
     .bind(id)
@@ -92,7 +94,7 @@ pub async fn update_role(
             permissions = COALESCE($5, permissions),
             mentionable = COALESCE($6, mentionable)
          WHERE id = $1
-         RETURNING id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, created_at"
+         RETURNING id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, icon_hash, created_at"
     )
     .bind(id)
     .bind(name)
@@ -105,6 +107,34 @@ pub async fn update_role(
     Ok(row)
 }
 
+/// Set or clear (`None`) the role's icon.
+pub async fn set_role_icon(
+    pool: &DbPool,
+    id: i64,
+    icon_hash: Option<&str>,
+) -> Result<RoleRow, DbError> {
+    let row = sqlx::query_as::<_, RoleRow>(
+        "UPDATE roles SET icon_hash = $2
+         WHERE id = $1
+         RETURNING id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, icon_hash, created_at"
+    )
+    .bind(id)
+    .bind(icon_hash)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Roles in the space that currently have an icon.
+pub async fn count_roles_with_icons(pool: &DbPool, space_id: i64) -> Result<i64, DbError> {
+    let (count,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM roles WHERE space_id = $1 AND icon_hash IS NOT NULL")
+            .bind(space_id)
+            .fetch_one(pool)
+            .await?;
+    Ok(count)
+}
+
 pub async fn delete_role(pool: &DbPool, id: i64) -> Result<(), DbError> {
     sqlx::query("DELETE FROM roles WHERE id = $1")
         .bind(id)
@@ -113,13 +143,55 @@ pub async fn delete_role(pool: &DbPool, id: i64) -> Result<(), DbError> {
     Ok(())
 }
 
+/// Bulk update role positions within a space. Each entry is
+/// (role_id, position).
+///
+/// Runs in a single transaction: if any role is missing or belongs to
+/// another space the whole batch is rolled back with `DbError::NotFound`.
+/// Returns the roles whose position actually changed.
+pub async fn update_role_positions(
+    pool: &DbPool,
+    space_id: i64,
+    positions: &[(i64, i32)],
+) -> Result<Vec<RoleRow>, DbError> {
+    let mut tx = pool.begin().await?;
+    let mut changed = Vec::new();
+    for &(role_id, position) in positions {
+        let existing: Option<(i32,)> =
+            sqlx::query_as("SELECT position FROM roles WHERE id = $1 AND space_id = $2")
+                .bind(role_id)
+                .bind(space_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let Some((current,)) = existing else {
+            return Err(DbError::NotFound);
+        };
+        if current == position {
+            continue;
+        }
+
+        let row = sqlx::query_as::<_, RoleRow>(
+            "UPDATE roles SET position = $2
+             WHERE id = $1
+             RETURNING id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, icon_hash, created_at"
+        )
+        .bind(role_id)
+        .bind(position)
+        .fetch_one(&mut *tx)
+        .await?;
+        changed.push(row);
+    }
+    tx.commit().await?;
+    Ok(changed)
+}
+
 pub async fn get_guild_roles(pool: &DbPool, space_id: i64) -> Result<Vec<RoleRow>, DbError> {
     get_space_roles(pool, space_id).await
 }
 
 pub async fn get_space_roles(pool: &DbPool, space_id: i64) -> Result<Vec<RoleRow>, DbError> {
     let rows = sqlx::query_as::<_, RoleRow>(
-        "SELECT id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, created_at
+        "SELECT id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, icon_hash, created_at
          FROM roles WHERE space_id = $1 ORDER BY position"
     )
     .bind(space_id)
@@ -189,7 +261,7 @@ pub async fn get_member_roles(
 ) -> Result<Vec<RoleRow>, DbError> {
     let rows = sqlx::query_as::<_, RoleRow>(
         "SELECT DISTINCT
-            r.id, r.space_id, r.name, r.color, CASE WHEN r.hoist THEN 1 ELSE 0 END AS hoist, r.position, r.permissions, CASE WHEN r.managed THEN 1 ELSE 0 END AS managed, CASE WHEN r.mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN r.server_wide THEN 1 ELSE 0 END AS server_wide, r.created_at
+            r.id, r.space_id, r.name, r.color, CASE WHEN r.hoist THEN 1 ELSE 0 END AS hoist, r.position, r.permissions, CASE WHEN r.managed THEN 1 ELSE 0 END AS managed, CASE WHEN r.mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN r.server_wide THEN 1 ELSE 0 END AS server_wide, r.icon_hash, r.created_at
          FROM roles r
          LEFT JOIN member_roles mr
             ON mr.role_id = r.id
@@ -215,9 +287,51 @@ pub async fn get_member_roles(
     Ok(rows)
 }
 
+/// The member's roles in each of `space_ids`, @everyone included where they
+/// are a member, in one query. Rows are ordered by space and then position.
+pub async fn get_member_roles_for_spaces(
+    pool: &DbPool,
+    user_id: i64,
+    space_ids: &[i64],
+) -> Result<Vec<RoleRow>, DbError> {
+    if space_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders: Vec<String> = (2..=space_ids.len() + 1)
+        .map(|i| format!("${}", i))
+        .collect();
+    let sql = format!(
+        "SELECT DISTINCT
+            r.id, r.space_id, r.name, r.color, CASE WHEN r.hoist THEN 1 ELSE 0 END AS hoist, r.position, r.permissions, CASE WHEN r.managed THEN 1 ELSE 0 END AS managed, CASE WHEN r.mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN r.server_wide THEN 1 ELSE 0 END AS server_wide, r.icon_hash, r.created_at
+         FROM roles r
+         LEFT JOIN member_roles mr
+            ON mr.role_id = r.id
+            AND mr.user_id = $1
+         WHERE r.space_id IN ({})
+           AND (
+                mr.user_id IS NOT NULL
+                OR (
+                    r.id = r.space_id
+                    AND EXISTS (
+                        SELECT 1 FROM members m
+                        WHERE m.user_id = $1
+                          AND m.guild_id = r.space_id
+                    )
+                )
+           )
+         ORDER BY r.space_id, r.position",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query_as::<_, RoleRow>(&sql).bind(user_id);
+    for space_id in space_ids {
+        query = query.bind(*space_id);
+    }
+    Ok(query.fetch_all(pool).await?)
+}
+
 pub async fn get_user_all_roles(pool: &DbPool, user_id: i64) -> Result<Vec<RoleRow>, DbError> {
     let rows = sqlx::query_as::<_, RoleRow>(
-        "SELECT r.id, r.space_id, r.name, r.color, CASE WHEN r.hoist THEN 1 ELSE 0 END AS hoist, r.position, r.permissions, CASE WHEN r.managed THEN 1 ELSE 0 END AS managed, CASE WHEN r.mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN r.server_wide THEN 1 ELSE 0 END AS server_wide, r.created_at
+        "SELECT r.id, r.space_id, r.name, r.color, CASE WHEN r.hoist THEN 1 ELSE 0 END AS hoist, r.position, r.permissions, CASE WHEN r.managed THEN 1 ELSE 0 END AS managed, CASE WHEN r.mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN r.server_wide THEN 1 ELSE 0 END AS server_wide, r.icon_hash, r.created_at
          FROM roles r
          INNER JOIN member_roles mr ON mr.role_id = r.id
          WHERE mr.user_id = $1
@@ -319,6 +433,27 @@ mod tests {
         assert_eq!(updated.permissions, 42);
     }
 
+    #[tokio::test]
+    async fn test_role_icons_are_set_counted_and_cleared() {
+        let pool = test_pool().await;
+        let (_user_id, guild_id) = setup_guild(&pool).await;
+        create_role(&pool, 540, guild_id, "Iconic", 0)
+            .await
+            .unwrap();
+        create_role(&pool, 541, guild_id, "Plain", 0).await.unwrap();
+        assert_eq!(count_roles_with_icons(&pool, guild_id).await.unwrap(), 0);
+
+        let role = set_role_icon(&pool, 540, Some("abc123")).await.unwrap();
+        assert_eq!(role.icon_hash.as_deref(), Some("abc123"));
+        assert_eq!(count_roles_with_icons(&pool, guild_id).await.unwrap(), 1);
+        let fetched = get_role(&pool, 540).await.unwrap().unwrap();
+        assert_eq!(fetched.icon_hash.as_deref(), Some("abc123"));
+
+        let cleared = set_role_icon(&pool, 540, None).await.unwrap();
+        assert!(cleared.icon_hash.is_none());
+        assert_eq!(count_roles_with_icons(&pool, guild_id).await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn test_delete_role() {
         let pool = test_pool().await;
@@ -362,6 +497,46 @@ mod tests {
         assert!(role_ids.contains(&510));
     }
 
+    #[tokio::test]
+    async fn member_roles_for_spaces_match_per_space_lookups() {
+        let pool = test_pool().await;
+        let (user_id, guild_id) = setup_guild(&pool).await;
+        crate::guilds::create_guild(&pool, 200, "Other Guild", user_id, None)
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 300, "Not Joined", user_id, None)
+            .await
+            .unwrap();
+        for id in [guild_id, 200] {
+            crate::members::add_member(&pool, user_id, id)
+                .await
+                .unwrap();
+        }
+        create_role(&pool, 510, guild_id, "Tester", 0)
+            .await
+            .unwrap();
+        add_member_role(&pool, user_id, guild_id, 510)
+            .await
+            .unwrap();
+
+        let mut batched: Vec<i64> =
+            get_member_roles_for_spaces(&pool, user_id, &[guild_id, 200, 300])
+                .await
+                .unwrap()
+                .iter()
+                .map(|r| r.id)
+                .collect();
+        batched.sort_unstable();
+        let mut expected = Vec::new();
+        for id in [guild_id, 200, 300] {
+            let roles = get_member_roles(&pool, user_id, id).await.unwrap();
+            expected.extend(roles.iter().map(|r| r.id));
+        }
+        expected.sort_unstable();
+        assert_eq!(batched, expected);
+        assert!(batched.contains(&510));
+    }
+
     #[tokio::test]
     async fn test_remove_member_role() {
         let pool = test_pool().await;
@@ -390,4 +565,24 @@ mod tests {
             .unwrap();
         assert_eq!(role.guild_id(), guild_id);
     }
+
+    #[tokio::test]
+    async fn test_update_role_positions_is_atomic() {
+        let pool = test_pool().await;
+        let (_user_id, guild_id) = setup_guild(&pool).await;
+        create_role(&pool, 600, guild_id, "A", 0).await.unwrap();
+        create_role(&pool, 601, guild_id, "B", 0).await.unwrap();
+
+        let changed = update_role_positions(&pool, guild_id, &[(600, 2), (601, 1)])
+            .await
+            .unwrap();
+        assert_eq!(changed.len(), 2);
+        assert_eq!(get_role(&pool, 600).await.unwrap().unwrap().position, 2);
+
+        let err = update_role_positions(&pool, guild_id, &[(601, 5), (99_999, 3)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DbError::NotFound));
+        assert_eq!(get_role(&pool, 601).await.unwrap().unwrap().position, 1);
+    }
 }
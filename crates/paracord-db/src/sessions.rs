@@ -178,6 +178,24 @@ pub async fn list_user_sessions(
     Ok(rows)
 }
 
+/// Device identifiers recorded on the user's retained sessions, as
+/// `(device_id, user_agent)` pairs. Includes revoked and expired sessions
+/// that have not been purged yet.
+pub async fn list_session_devices(
+    pool: &DbPool,
+    user_id: i64,
+) -> Result<Vec<(Option<String>, Option<String>)>, DbError> {
+    let rows: Vec<(Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT DISTINCT device_id, user_agent
+         FROM auth_sessions
+         WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
 pub async fn rotate_session_refresh_token(
     pool: &DbPool,
     session_id: &str,
@@ -0,0 +1,215 @@
+use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+#[derive(Debug, Clone)]
+pub struct UploadSessionRow {
+    pub id: i64,
+    pub user_id: i64,
+    pub channel_id: i64,
+    pub filename: String,
+    pub content_type: Option<String>,
+    pub total_size: i64,
+    pub received_bytes: i64,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for UploadSessionRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let created_raw: String = row.try_get("created_at")?;
+        let expires_raw: String = row.try_get("expires_at")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            user_id: row.try_get("user_id")?,
+            channel_id: row.try_get("channel_id")?,
+            filename: row.try_get("filename")?,
+            content_type: row.try_get("content_type")?,
+            total_size: row.try_get("total_size")?,
+            received_bytes: row.try_get("received_bytes")?,
+            created_at: datetime_from_db_text(&created_raw)?,
+            expires_at: datetime_from_db_text(&expires_raw)?,
+        })
+    }
+}
+
+const UPLOAD_SESSION_COLUMNS: &str = "id, user_id, channel_id, filename, content_type, total_size, received_bytes, created_at, expires_at";
+
+#[allow(clippy::too_many_arguments)]
+pub async fn create_upload_session(
+    pool: &DbPool,
+    id: i64,
+    user_id: i64,
+    channel_id: i64,
+    filename: &str,
+    content_type: Option<&str>,
+    total_size: i64,
+    expires_at: DateTime<Utc>,
+) -> Result<UploadSessionRow, DbError> {
+    let sql = format!(
+        "INSERT INTO upload_sessions (id, user_id, channel_id, filename, content_type, total_size, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING {UPLOAD_SESSION_COLUMNS}"
+    );
+    let row = sqlx::query_as::<_, UploadSessionRow>(&sql)
+        .bind(id)
+        .bind(user_id)
+        .bind(channel_id)
+        .bind(filename)
+        .bind(content_type)
+        .bind(total_size)
+        .bind(datetime_to_db_text(expires_at))
+        .fetch_one(pool)
+        .await?;
+    Ok(row)
+}
+
+pub async fn get_upload_session(
+    pool: &DbPool,
+    id: i64,
+) -> Result<Option<UploadSessionRow>, DbError> {
+    let sql = format!("SELECT {UPLOAD_SESSION_COLUMNS} FROM upload_sessions WHERE id = $1");
+    let row = sqlx::query_as::<_, UploadSessionRow>(&sql)
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+/// Move the committed offset from `expected_offset` to `new_offset` and push
+/// the expiry forward. Returns `false` if another writer advanced the session
+/// first, so two racing chunks can never both be accepted at the same offset.
+pub async fn advance_upload_offset(
+    pool: &DbPool,
+    id: i64,
+    expected_offset: i64,
+    new_offset: i64,
+    expires_at: DateTime<Utc>,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE upload_sessions
+         SET received_bytes = $3, expires_at = $4
+         WHERE id = $1 AND received_bytes = $2 AND $3 <= total_size",
+    )
+    .bind(id)
+    .bind(expected_offset)
+    .bind(new_offset)
+    .bind(datetime_to_db_text(expires_at))
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn delete_upload_session(pool: &DbPool, id: i64) -> Result<bool, DbError> {
+    let result = sqlx::query("DELETE FROM upload_sessions WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_expired_upload_sessions(
+    pool: &DbPool,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<UploadSessionRow>, DbError> {
+    let sql = format!(
+        "SELECT {UPLOAD_SESSION_COLUMNS}
+         FROM upload_sessions
+         WHERE expires_at <= $1
+         ORDER BY expires_at ASC
+         LIMIT $2"
+    );
+    let rows = sqlx::query_as::<_, UploadSessionRow>(&sql)
+        .bind(datetime_to_db_text(now))
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        crate::users::create_user(&pool, 1, "uploader", 1, "up@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 10, "space", 1, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 20, 10, "files", 0, 0, None, None)
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_offset_only_advances_from_expected_position() {
+        let pool = test_pool().await;
+        let expires = Utc::now() + Duration::hours(1);
+        let session = create_upload_session(&pool, 30, 1, 20, "big.bin", None, 100, expires)
+            .await
+            .unwrap();
+        assert_eq!(session.received_bytes, 0);
+
+        assert!(advance_upload_offset(&pool, 30, 0, 40, expires)
+            .await
+            .unwrap());
+        // A retried chunk for the old offset is rejected.
+        assert!(!advance_upload_offset(&pool, 30, 0, 40, expires)
+            .await
+            .unwrap());
+        // Writing past the declared size is rejected.
+        assert!(!advance_upload_offset(&pool, 30, 40, 101, expires)
+            .await
+            .unwrap());
+        assert!(advance_upload_offset(&pool, 30, 40, 100, expires)
+            .await
+            .unwrap());
+
+        let session = get_upload_session(&pool, 30).await.unwrap().unwrap();
+        assert_eq!(session.received_bytes, 100);
+        assert_eq!(session.total_size, 100);
+    }
+
+    #[tokio::test]
+    async fn test_expired_sessions_are_listed() {
+        let pool = test_pool().await;
+        let now = Utc::now();
+        create_upload_session(
+            &pool,
+            31,
+            1,
+            20,
+            "old.bin",
+            None,
+            10,
+            now - Duration::minutes(1),
+        )
+        .await
+        .unwrap();
+        create_upload_session(
+            &pool,
+            32,
+            1,
+            20,
+            "new.bin",
+            None,
+            10,
+            now + Duration::hours(1),
+        )
+        .await
+        .unwrap();
+
+        let expired = get_expired_upload_sessions(&pool, now, 10).await.unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, 31);
+        assert!(delete_upload_session(&pool, 31).await.unwrap());
+        assert!(get_upload_session(&pool, 31).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{bool_from_any_row, datetime_to_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+#[derive(Debug, Clone)]
+pub struct UserMfaRow {
+    pub user_id: i64,
+    pub mfa_enabled: bool,
+    pub totp_secret_enc: Option<String>,
+    pub totp_last_step: Option<i64>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for UserMfaRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            user_id: row.try_get("id")?,
+            mfa_enabled: bool_from_any_row(row, "mfa_enabled")?,
+            totp_secret_enc: row.try_get("totp_secret_enc")?,
+            totp_last_step: row.try_get("totp_last_step")?,
+        })
+    }
+}
+
+pub async fn get_user_mfa(pool: &DbPool, user_id: i64) -> Result<Option<UserMfaRow>, DbError> {
+    let row = sqlx::query_as::<_, UserMfaRow>(
+        "SELECT id, CASE WHEN mfa_enabled THEN 1 ELSE 0 END AS mfa_enabled, totp_secret_enc, totp_last_step
+         FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Store a freshly generated (not yet confirmed) TOTP secret. Refuses to
+/// overwrite the secret of a user who already has 2FA enabled.
+pub async fn set_pending_totp_secret(
+    pool: &DbPool,
+    user_id: i64,
+    secret_enc: &str,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE users
+         SET totp_secret_enc = $2, totp_last_step = NULL
+         WHERE id = $1 AND mfa_enabled = FALSE",
+    )
+    .bind(user_id)
+    .bind(secret_enc)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Enable TOTP for a user and replace any existing recovery codes.
+/// `recovery_codes` holds `(id, code_hash)` pairs.
+pub async fn enable_totp(
+    pool: &DbPool,
+    user_id: i64,
+    accepted_step: i64,
+    recovery_codes: &[(i64, String)],
+) -> Result<bool, DbError> {
+    let mut tx = pool.begin().await?;
+    let result = sqlx::query(
+        "UPDATE users
+         SET mfa_enabled = TRUE, totp_last_step = $2
+         WHERE id = $1 AND mfa_enabled = FALSE AND totp_secret_enc IS NOT NULL",
+    )
+    .bind(user_id)
+    .bind(accepted_step)
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        tx.rollback().await?;
+        return Ok(false);
+    }
+
+    sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    for (id, code_hash) in recovery_codes {
+        sqlx::query(
+            "INSERT INTO user_recovery_codes (id, user_id, code_hash)
+             VALUES ($1, $2, $3)",
+        )
+        .bind(*id)
+        .bind(user_id)
+        .bind(code_hash)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+pub async fn disable_totp(pool: &DbPool, user_id: i64) -> Result<(), DbError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "UPDATE users
+         SET mfa_enabled = FALSE, totp_secret_enc = NULL, totp_last_step = NULL
+         WHERE id = $1",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Record `step` as the most recently accepted TOTP time step. Returns false
+/// when the step (or a later one) was already used, which rejects code replay.
+pub async fn advance_totp_step(pool: &DbPool, user_id: i64, step: i64) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE users
+         SET totp_last_step = $2
+         WHERE id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2)",
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Mark a recovery code as used. Returns false if the code does not exist for
+/// this user or was already consumed.
+pub async fn consume_recovery_code(
+    pool: &DbPool,
+    user_id: i64,
+    code_hash: &str,
+    now: DateTime<Utc>,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE user_recovery_codes
+         SET used_at = $3
+         WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(code_hash)
+    .bind(datetime_to_db_text(now))
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn count_unused_recovery_codes(pool: &DbPool, user_id: i64) -> Result<i64, DbError> {
+    let (count,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM user_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        crate::users::create_user(&pool, 1, "mfa", 1, "mfa@example.com", "hash")
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_enable_totp_requires_pending_secret() {
+        let pool = test_pool().await;
+        assert!(!enable_totp(&pool, 1, 10, &[]).await.unwrap());
+
+        assert!(set_pending_totp_secret(&pool, 1, "enc").await.unwrap());
+        let codes = vec![(11, "h1".to_string()), (12, "h2".to_string())];
+        assert!(enable_totp(&pool, 1, 10, &codes).await.unwrap());
+
+        let row = get_user_mfa(&pool, 1).await.unwrap().unwrap();
+        assert!(row.mfa_enabled);
+        assert_eq!(row.totp_secret_enc.as_deref(), Some("enc"));
+        assert_eq!(row.totp_last_step, Some(10));
+        assert_eq!(count_unused_recovery_codes(&pool, 1).await.unwrap(), 2);
+
+        // Enabled users cannot have their secret silently replaced.
+        assert!(!set_pending_totp_secret(&pool, 1, "other").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_totp_step_and_recovery_codes_are_single_use() {
+        let pool = test_pool().await;
+        set_pending_totp_secret(&pool, 1, "enc").await.unwrap();
+        enable_totp(&pool, 1, 10, &[(11, "h1".to_string())])
+            .await
+            .unwrap();
+
+        assert!(!advance_totp_step(&pool, 1, 10).await.unwrap());
+        assert!(advance_totp_step(&pool, 1, 11).await.unwrap());
+        assert!(!advance_totp_step(&pool, 1, 11).await.unwrap());
+
+        let now = Utc::now();
+        assert!(consume_recovery_code(&pool, 1, "h1", now).await.unwrap());
+        assert!(!consume_recovery_code(&pool, 1, "h1", now).await.unwrap());
+        assert_eq!(count_unused_recovery_codes(&pool, 1).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_disable_totp_clears_state() {
+        let pool = test_pool().await;
+        set_pending_totp_secret(&pool, 1, "enc").await.unwrap();
+        enable_totp(&pool, 1, 10, &[(11, "h1".to_string())])
+            .await
+            .unwrap();
+        disable_totp(&pool, 1).await.unwrap();
+
+        let row = get_user_mfa(&pool, 1).await.unwrap().unwrap();
+        assert!(!row.mfa_enabled);
+        assert!(row.totp_secret_enc.is_none());
+        assert_eq!(count_unused_recovery_codes(&pool, 1).await.unwrap(), 0);
+    }
+}
@@ -1,5 +1,9 @@
-use crate::{bool_from_any_row, datetime_from_db_text, json_from_db_text, DbError, DbPool};
+use crate::{
+    bool_from_any_row, datetime_from_db_text, datetime_to_db_text, json_from_db_text, DbError,
+    DbPool,
+};
 use chrono::{DateTime, Utc};
+use paracord_util::validation::username_skeleton;
 use sqlx::Row;
 
 fn normalize_email(email: &str) -> String {
@@ -16,6 +20,7 @@ pub struct UserRow {
     pub avatar_hash: Option<String>,
     pub banner_hash: Option<String>,
     pub bio: Option<String>,
+    pub pronouns: Option<String>,
     pub accent_color: Option<i32>,
     pub flags: i32,
     pub created_at: DateTime<Utc>,
@@ -33,6 +38,7 @@ pub struct UserAuthRow {
     pub avatar_hash: Option<String>,
     pub banner_hash: Option<String>,
     pub bio: Option<String>,
+    pub pronouns: Option<String>,
     pub accent_color: Option<i32>,
     pub flags: i32,
     pub created_at: DateTime<Utc>,
@@ -47,6 +53,8 @@ pub struct UserSettingsRow {
     pub locale: String,
     pub message_display: String,
     pub crypto_auth_enabled: bool,
+    pub show_mutuals: bool,
+    pub friends_only_profile: bool,
     pub notifications: serde_json::Value,
     pub keybinds: serde_json::Value,
     pub updated_at: DateTime<Utc>,
@@ -64,6 +72,7 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for UserRow {
             avatar_hash: row.try_get("avatar_hash")?,
             banner_hash: row.try_get("banner_hash")?,
             bio: row.try_get("bio")?,
+            pronouns: row.try_get("pronouns")?,
             accent_color: row.try_get("accent_color")?,
             flags: row.try_get("flags")?,
             created_at: datetime_from_db_text(&created_at_raw)?,
@@ -85,6 +94,7 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for UserAuthRow {
             avatar_hash: row.try_get("avatar_hash")?,
             banner_hash: row.try_get("banner_hash")?,
             bio: row.try_get("bio")?,
+            pronouns: row.try_get("pronouns")?,
             accent_color: row.try_get("accent_color")?,
             flags: row.try_get("flags")?,
             created_at: datetime_from_db_text(&created_at_raw)?,
@@ -105,6 +115,8 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for UserSettingsRow {
             locale: row.try_get("locale")?,
             message_display: row.try_get("message_display")?,
             crypto_auth_enabled: bool_from_any_row(row, "crypto_auth_enabled")?,
+            show_mutuals: bool_from_any_row(row, "show_mutuals")?,
+            friends_only_profile: bool_from_any_row(row, "friends_only_profile")?,
             notifications: json_from_db_text(&notifications_raw)?,
             keybinds: json_from_db_text(&keybinds_raw)?,
             updated_at: datetime_from_db_text(&updated_at_raw)?,
@@ -122,15 +134,16 @@ pub async fn create_user(
 ) -> Result<UserRow, DbError> {
     let normalized_email = normalize_email(email);
     let row = sqlx::query_as::<_, UserRow>(
-        "INSERT INTO users (id, username, discriminator, email, password_hash)
-         VALUES ($1, $2, $3, $4, $5)
-         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key",
+        "INSERT INTO users (id, username, discriminator, email, password_hash, username_skeleton)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key",
     )
     .bind(id)
     .bind(username)
     .bind(discriminator)
     .bind(normalized_email)
     .bind(password_hash)
+    .bind(username_skeleton(username))
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -155,9 +168,9 @@ pub async fn create_user_as_first_admin(
     let flags = if count == 0 { admin_flag } else { 0 };
 
     let row = sqlx::query_as::<_, UserRow>(
-        "INSERT INTO users (id, username, discriminator, email, password_hash, flags)
-         VALUES ($1, $2, $3, $4, $5, $6)
-         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key",
+        "INSERT INTO users (id, username, discriminator, email, password_hash, flags, username_skeleton)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key",
     )
     .bind(id)
     .bind(username)
@@ -165,6 +178,7 @@ pub async fn create_user_as_first_admin(
     .bind(normalized_email)
     .bind(password_hash)
     .bind(flags)
+    .bind(username_skeleton(username))
     .fetch_one(&mut *tx)
     .await?;
 
@@ -174,7 +188,7 @@ pub async fn create_user_as_first_admin(
 
 pub async fn get_user_by_id(pool: &DbPool, id: i64) -> Result<Option<UserRow>, DbError> {
     let row = sqlx::query_as::<_, UserRow>(
-        "SELECT id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key
+        "SELECT id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key
          FROM users WHERE id = $1",
     )
     .bind(id)
@@ -186,7 +200,7 @@ pub async fn get_user_by_id(pool: &DbPool, id: i64) -> Result<Option<UserRow>, D
 pub async fn get_user_by_email(pool: &DbPool, email: &str) -> Result<Option<UserAuthRow>, DbError> {
     let normalized_email = normalize_email(email);
     let row = sqlx::query_as::<_, UserAuthRow>(
-        "SELECT id, username, discriminator, email, password_hash, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key
+        "SELECT id, username, discriminator, email, password_hash, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key
          FROM users WHERE lower(email) = $1",
     )
     .bind(normalized_email)
@@ -197,7 +211,7 @@ pub async fn get_user_by_email(pool: &DbPool, email: &str) -> Result<Option<User
 
 pub async fn get_user_auth_by_id(pool: &DbPool, id: i64) -> Result<Option<UserAuthRow>, DbError> {
     let row = sqlx::query_as::<_, UserAuthRow>(
-        "SELECT id, username, discriminator, email, password_hash, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key
+        "SELECT id, username, discriminator, email, password_hash, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key
          FROM users WHERE id = $1",
     )
     .bind(id)
@@ -212,7 +226,7 @@ pub async fn get_user_by_username(
     discriminator: i16,
 ) -> Result<Option<UserRow>, DbError> {
     let row = sqlx::query_as::<_, UserRow>(
-        "SELECT id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key
+        "SELECT id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key
          FROM users WHERE username = $1 AND discriminator = $2",
     )
     .bind(username)
@@ -229,7 +243,7 @@ pub async fn get_user_auth_by_username(
 ) -> Result<Option<UserAuthRow>, DbError> {
     let normalized_username = username.trim().to_ascii_lowercase();
     let row = sqlx::query_as::<_, UserAuthRow>(
-        "SELECT id, username, discriminator, email, password_hash, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key
+        "SELECT id, username, discriminator, email, password_hash, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key
          FROM users WHERE lower(username) = $1 AND discriminator = $2",
     )
     .bind(normalized_username)
@@ -244,7 +258,7 @@ pub async fn get_user_by_username_only(
     username: &str,
 ) -> Result<Option<UserRow>, DbError> {
     let row = sqlx::query_as::<_, UserRow>(
-        "SELECT id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key
+        "SELECT id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key
          FROM users
          WHERE username = $1
          ORDER BY created_at ASC
@@ -256,13 +270,54 @@ pub async fn get_user_by_username_only(
     Ok(row)
 }
 
+/// Id of a user whose username shares `skeleton` (see
+/// [`paracord_util::validation::username_skeleton`]), if any.
+pub async fn find_user_id_by_username_skeleton(
+    pool: &DbPool,
+    skeleton: &str,
+) -> Result<Option<i64>, DbError> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        "SELECT id FROM users
+         WHERE username_skeleton = $1
+         ORDER BY created_at ASC
+         LIMIT 1",
+    )
+    .bind(skeleton)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|(id,)| id))
+}
+
+/// Fill `username_skeleton` for up to `limit` users created before the column
+/// existed. Returns how many rows were updated.
+pub async fn backfill_username_skeletons(pool: &DbPool, limit: i64) -> Result<u64, DbError> {
+    let rows: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT id, username FROM users
+         WHERE username_skeleton IS NULL
+         LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    let mut updated = 0;
+    for (id, username) in rows {
+        updated += sqlx::query("UPDATE users SET username_skeleton = $2 WHERE id = $1")
+            .bind(id)
+            .bind(username_skeleton(&username))
+            .execute(pool)
+            .await?
+            .rows_affected();
+    }
+    Ok(updated)
+}
+
 pub async fn get_user_auth_by_username_only(
     pool: &DbPool,
     username: &str,
 ) -> Result<Option<UserAuthRow>, DbError> {
     let normalized_username = username.trim().to_ascii_lowercase();
     let row = sqlx::query_as::<_, UserAuthRow>(
-        "SELECT id, username, discriminator, email, password_hash, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key
+        "SELECT id, username, discriminator, email, password_hash, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key
          FROM users
          WHERE lower(username) = $1
          ORDER BY created_at ASC
@@ -274,22 +329,46 @@ pub async fn get_user_auth_by_username_only(
     Ok(row)
 }
 
+/// Profile fields to change. `None` leaves a field as it is; an empty
+/// `banner_hash` removes the banner.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UserProfileUpdate<'a> {
+    pub display_name: Option<&'a str>,
+    pub bio: Option<&'a str>,
+    pub pronouns: Option<&'a str>,
+    /// `Some("")` clears the avatar, as it does the banner.
+    pub avatar_hash: Option<&'a str>,
+    pub banner_hash: Option<&'a str>,
+    /// `Some(None)` clears the accent color.
+    pub accent_color: Option<Option<i32>>,
+}
+
 pub async fn update_user(
     pool: &DbPool,
     id: i64,
-    display_name: Option<&str>,
-    bio: Option<&str>,
-    avatar_hash: Option<&str>,
+    update: &UserProfileUpdate<'_>,
 ) -> Result<UserRow, DbError> {
     let row = sqlx::query_as::<_, UserRow>(
-        "UPDATE users SET display_name = COALESCE($2, display_name), bio = COALESCE($3, bio), avatar_hash = COALESCE($4, avatar_hash), updated_at = datetime('now')
+        "UPDATE users SET
+            display_name = COALESCE($2, display_name),
+            bio = COALESCE($3, bio),
+            pronouns = COALESCE($4, pronouns),
+            avatar_hash = CASE WHEN $5 IS NULL THEN avatar_hash ELSE NULLIF($5, '') END,
+            banner_hash = CASE WHEN $6 IS NULL THEN banner_hash ELSE NULLIF($6, '') END,
+            accent_color = CASE WHEN $7 THEN $8 ELSE accent_color END,
+            updated_at = $9
          WHERE id = $1
-         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key",
+         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key",
     )
     .bind(id)
-    .bind(display_name)
-    .bind(bio)
-    .bind(avatar_hash)
+    .bind(update.display_name)
+    .bind(update.bio)
+    .bind(update.pronouns)
+    .bind(update.avatar_hash)
+    .bind(update.banner_hash)
+    .bind(update.accent_color.is_some())
+    .bind(update.accent_color.flatten())
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -300,7 +379,7 @@ pub async fn get_user_settings(
     user_id: i64,
 ) -> Result<Option<UserSettingsRow>, DbError> {
     let row = sqlx::query_as::<_, UserSettingsRow>(
-        "SELECT user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, notifications, keybinds, updated_at
+        "SELECT user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, CASE WHEN show_mutuals THEN 1 ELSE 0 END AS show_mutuals, CASE WHEN friends_only_profile THEN 1 ELSE 0 END AS friends_only_profile, notifications, keybinds, updated_at
          FROM user_settings WHERE user_id = $1",
     )
     .bind(user_id)
@@ -318,12 +397,13 @@ pub async fn count_users(pool: &DbPool) -> Result<i64, DbError> {
 
 pub async fn update_user_flags(pool: &DbPool, id: i64, flags: i32) -> Result<UserRow, DbError> {
     let row = sqlx::query_as::<_, UserRow>(
-        "UPDATE users SET flags = $2, updated_at = datetime('now')
+        "UPDATE users SET flags = $2, updated_at = $3
          WHERE id = $1
-         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key",
+         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key",
     )
     .bind(id)
     .bind(flags)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -335,7 +415,7 @@ pub async fn list_users_paginated(
     limit: i64,
 ) -> Result<Vec<UserRow>, DbError> {
     let rows = sqlx::query_as::<_, UserRow>(
-        "SELECT id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key
+        "SELECT id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key
          FROM users
          ORDER BY created_at ASC
          LIMIT $1 OFFSET $2",
@@ -355,6 +435,94 @@ pub async fn delete_user(pool: &DbPool, id: i64) -> Result<(), DbError> {
     Ok(())
 }
 
+/// An action an admin can apply to many accounts at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkUserAction {
+    /// Set `flag` and revoke every session.
+    Disable {
+        flag: i32,
+    },
+    /// Revoke every session.
+    ForceLogout,
+    Delete,
+    /// Clear `flag`.
+    Unban {
+        flag: i32,
+    },
+}
+
+/// Apply `action` to each user in one transaction. Returns, per id, whether
+/// the user existed and was updated; missing users don't abort the batch.
+pub async fn apply_bulk_user_action(
+    pool: &DbPool,
+    user_ids: &[i64],
+    action: BulkUserAction,
+    revoke_reason: &str,
+    now: DateTime<Utc>,
+) -> Result<Vec<(i64, bool)>, DbError> {
+    let now_text = datetime_to_db_text(now);
+    let mut tx = pool.begin().await?;
+    let mut results = Vec::with_capacity(user_ids.len());
+    for &user_id in user_ids {
+        let applied = match action {
+            BulkUserAction::Disable { flag } => {
+                sqlx::query("UPDATE users SET flags = flags | $2 WHERE id = $1")
+                    .bind(user_id)
+                    .bind(flag)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected()
+                    > 0
+            }
+            BulkUserAction::ForceLogout => {
+                let exists: Option<(i64,)> = sqlx::query_as("SELECT id FROM users WHERE id = $1")
+                    .bind(user_id)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+                exists.is_some()
+            }
+            BulkUserAction::Delete => {
+                sqlx::query("DELETE FROM users WHERE id = $1")
+                    .bind(user_id)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected()
+                    > 0
+            }
+            BulkUserAction::Unban { flag } => {
+                sqlx::query("UPDATE users SET flags = flags & ~$2 WHERE id = $1")
+                    .bind(user_id)
+                    .bind(flag)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected()
+                    > 0
+            }
+        };
+        if applied
+            && matches!(
+                action,
+                BulkUserAction::Disable { .. } | BulkUserAction::ForceLogout
+            )
+        {
+            sqlx::query(
+                "UPDATE auth_sessions
+                 SET revoked_at = $2, revoked_reason = $3
+                 WHERE user_id = $1
+                   AND revoked_at IS NULL",
+            )
+            .bind(user_id)
+            .bind(&now_text)
+            .bind(revoke_reason)
+            .execute(&mut *tx)
+            .await?;
+        }
+        results.push((user_id, applied));
+    }
+    tx.commit().await?;
+    Ok(results)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn upsert_user_settings(
     pool: &DbPool,
@@ -364,6 +532,8 @@ pub async fn upsert_user_settings(
     message_display: &str,
     custom_css: Option<&str>,
     crypto_auth_enabled: Option<bool>,
+    show_mutuals: Option<bool>,
+    friends_only_profile: Option<bool>,
     notifications: Option<&serde_json::Value>,
     keybinds: Option<&serde_json::Value>,
 ) -> Result<UserSettingsRow, DbError> {
@@ -380,18 +550,20 @@ pub async fn upsert_user_settings(
         .transpose()
         .map_err(|e| DbError::Sqlx(sqlx::Error::Protocol(format!("invalid keybinds json: {e}"))))?;
     let row = sqlx::query_as::<_, UserSettingsRow>(
-        "INSERT INTO user_settings (user_id, theme, locale, message_display, custom_css, crypto_auth_enabled, notifications, keybinds)
-         VALUES ($1, $2, $3, $4, $5, COALESCE($6, FALSE), COALESCE($7, '{}'), COALESCE($8, '{}'))
+        "INSERT INTO user_settings (user_id, theme, locale, message_display, custom_css, crypto_auth_enabled, show_mutuals, friends_only_profile, notifications, keybinds)
+         VALUES ($1, $2, $3, $4, $5, COALESCE($6, FALSE), COALESCE($7, TRUE), COALESCE($8, FALSE), COALESCE($9, '{}'), COALESCE($10, '{}'))
          ON CONFLICT (user_id) DO UPDATE SET
             theme = $2,
             locale = $3,
             message_display = $4,
             custom_css = $5,
             crypto_auth_enabled = COALESCE($6, user_settings.crypto_auth_enabled),
-            notifications = COALESCE($7, user_settings.notifications),
-            keybinds = COALESCE($8, user_settings.keybinds),
-            updated_at = datetime('now')
-         RETURNING user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, notifications, keybinds, updated_at",
+            show_mutuals = COALESCE($7, user_settings.show_mutuals),
+            friends_only_profile = COALESCE($8, user_settings.friends_only_profile),
+            notifications = COALESCE($9, user_settings.notifications),
+            keybinds = COALESCE($10, user_settings.keybinds),
+            updated_at = $11
+         RETURNING user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, CASE WHEN show_mutuals THEN 1 ELSE 0 END AS show_mutuals, CASE WHEN friends_only_profile THEN 1 ELSE 0 END AS friends_only_profile, notifications, keybinds, updated_at",
     )
     .bind(user_id)
     .bind(theme)
@@ -399,8 +571,11 @@ pub async fn upsert_user_settings(
     .bind(message_display)
     .bind(custom_css)
     .bind(crypto_auth_enabled)
+    .bind(show_mutuals)
+    .bind(friends_only_profile)
     .bind(notifications)
     .bind(keybinds)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -412,12 +587,13 @@ pub async fn update_user_public_key(
     public_key: &str,
 ) -> Result<UserRow, DbError> {
     let row = sqlx::query_as::<_, UserRow>(
-        "UPDATE users SET public_key = $2, updated_at = datetime('now')
+        "UPDATE users SET public_key = $2, updated_at = $3
          WHERE id = $1
-         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key",
+         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key",
     )
     .bind(id)
     .bind(public_key)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -430,26 +606,51 @@ pub async fn update_user_password_hash(
 ) -> Result<(), DbError> {
     sqlx::query(
         "UPDATE users
-         SET password_hash = $2, updated_at = datetime('now')
+         SET password_hash = $2, updated_at = $3
          WHERE id = $1",
     )
     .bind(id)
     .bind(password_hash)
+    .bind(datetime_to_db_text(Utc::now()))
     .execute(pool)
     .await?;
     Ok(())
 }
 
+/// Record when the user's last gateway session closed.
+pub async fn set_last_seen(pool: &DbPool, id: i64, at: DateTime<Utc>) -> Result<(), DbError> {
+    sqlx::query("UPDATE users SET last_seen_at = $2 WHERE id = $1")
+        .bind(id)
+        .bind(datetime_to_db_text(at))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_last_seen(pool: &DbPool, id: i64) -> Result<Option<DateTime<Utc>>, DbError> {
+    let raw: Option<Option<String>> =
+        sqlx::query_scalar("SELECT last_seen_at FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(raw
+        .flatten()
+        .as_deref()
+        .map(datetime_from_db_text)
+        .transpose()?)
+}
+
 pub async fn update_user_email(pool: &DbPool, id: i64, email: &str) -> Result<UserRow, DbError> {
     let normalized_email = normalize_email(email);
     let row = sqlx::query_as::<_, UserRow>(
         "UPDATE users
-         SET email = $2, updated_at = datetime('now')
+         SET email = $2, updated_at = $3
          WHERE id = $1
-         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key",
+         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key",
     )
     .bind(id)
     .bind(normalized_email)
+    .bind(datetime_to_db_text(Utc::now()))
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -460,7 +661,7 @@ pub async fn get_user_by_public_key(
     public_key: &str,
 ) -> Result<Option<UserRow>, DbError> {
     let row = sqlx::query_as::<_, UserRow>(
-        "SELECT id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key
+        "SELECT id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key
          FROM users WHERE public_key = $1",
     )
     .bind(public_key)
@@ -533,15 +734,16 @@ pub async fn create_user_from_pubkey(
 ) -> Result<UserRow, DbError> {
     let placeholder_email = format!("{}@pubkey", public_key);
     let row = sqlx::query_as::<_, UserRow>(
-        "INSERT INTO users (id, username, discriminator, email, password_hash, display_name, public_key)
-         VALUES ($1, $2, 0, $3, '', $4, $5)
-         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key",
+        "INSERT INTO users (id, username, discriminator, email, password_hash, display_name, public_key, username_skeleton)
+         VALUES ($1, $2, 0, $3, '', $4, $5, $6)
+         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key",
     )
     .bind(id)
     .bind(username)
     .bind(&placeholder_email)
     .bind(display_name)
     .bind(public_key)
+    .bind(username_skeleton(username))
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -564,9 +766,9 @@ pub async fn create_user_from_pubkey_as_first_admin(
     let placeholder_email = format!("{}@pubkey", public_key);
 
     let row = sqlx::query_as::<_, UserRow>(
-        "INSERT INTO users (id, username, discriminator, email, password_hash, display_name, public_key, flags)
-         VALUES ($1, $2, 0, $3, '', $4, $5, $6)
-         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key",
+        "INSERT INTO users (id, username, discriminator, email, password_hash, display_name, public_key, flags, username_skeleton)
+         VALUES ($1, $2, 0, $3, '', $4, $5, $6, $7)
+         RETURNING id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, pronouns, accent_color, flags, created_at, public_key",
     )
     .bind(id)
     .bind(username)
@@ -574,6 +776,7 @@ pub async fn create_user_from_pubkey_as_first_admin(
     .bind(display_name)
     .bind(public_key)
     .bind(flags)
+    .bind(username_skeleton(username))
     .fetch_one(&mut *tx)
     .await?;
 
@@ -606,6 +809,22 @@ mod tests {
         assert_eq!(user.flags, 0);
     }
 
+    #[tokio::test]
+    async fn test_set_last_seen_round_trips() {
+        let pool = test_pool().await;
+        create_user(&pool, 1, "seen", 1, "seen@example.com", "hash")
+            .await
+            .unwrap();
+        assert!(get_last_seen(&pool, 1).await.unwrap().is_none());
+
+        let at = DateTime::parse_from_rfc3339("2026-03-01T12:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        set_last_seen(&pool, 1, at).await.unwrap();
+        assert_eq!(get_last_seen(&pool, 1).await.unwrap(), Some(at));
+        assert!(get_last_seen(&pool, 2).await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_create_user_as_first_admin_sets_only_first_user_admin() {
         let pool = test_pool().await;
@@ -731,9 +950,17 @@ mod tests {
         create_user(&pool, 40, "eve", 1, "eve@example.com", "hash")
             .await
             .unwrap();
-        let updated = update_user(&pool, 40, Some("Eve Display"), Some("Hello!"), None)
-            .await
-            .unwrap();
+        let updated = update_user(
+            &pool,
+            40,
+            &UserProfileUpdate {
+                display_name: Some("Eve Display"),
+                bio: Some("Hello!"),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
         assert_eq!(updated.display_name.as_deref(), Some("Eve Display"));
         assert_eq!(updated.bio.as_deref(), Some("Hello!"));
     }
@@ -744,14 +971,73 @@ mod tests {
         create_user(&pool, 41, "frank", 1, "frank@example.com", "hash")
             .await
             .unwrap();
-        update_user(&pool, 41, Some("Frank"), None, None)
-            .await
-            .unwrap();
+        update_user(
+            &pool,
+            41,
+            &UserProfileUpdate {
+                display_name: Some("Frank"),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
         let user = get_user_by_id(&pool, 41).await.unwrap().unwrap();
         assert_eq!(user.display_name.as_deref(), Some("Frank"));
         assert!(user.bio.is_none());
     }
 
+    #[tokio::test]
+    async fn test_update_user_sets_and_clears_banner_and_accent_color() {
+        let pool = test_pool().await;
+        create_user(&pool, 42, "grace", 1, "grace@example.com", "hash")
+            .await
+            .unwrap();
+        let updated = update_user(
+            &pool,
+            42,
+            &UserProfileUpdate {
+                pronouns: Some("she/her"),
+                banner_hash: Some("abc123"),
+                accent_color: Some(Some(0x5865F2)),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.pronouns.as_deref(), Some("she/her"));
+        assert_eq!(updated.banner_hash.as_deref(), Some("abc123"));
+        assert_eq!(updated.accent_color, Some(0x5865F2));
+
+        // Fields left out are untouched.
+        let updated = update_user(
+            &pool,
+            42,
+            &UserProfileUpdate {
+                bio: Some("hi"),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.banner_hash.as_deref(), Some("abc123"));
+        assert_eq!(updated.accent_color, Some(0x5865F2));
+
+        let cleared = update_user(
+            &pool,
+            42,
+            &UserProfileUpdate {
+                banner_hash: Some(""),
+                accent_color: Some(None),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert!(cleared.banner_hash.is_none());
+        assert!(cleared.accent_color.is_none());
+        assert_eq!(cleared.pronouns.as_deref(), Some("she/her"));
+    }
+
     #[tokio::test]
     async fn test_delete_user() {
         let pool = test_pool().await;
@@ -875,16 +1161,17 @@ mod tests {
         create_user(&pool, 95, "settings_u", 1, "s@example.com", "h")
             .await
             .unwrap();
-        let settings =
-            upsert_user_settings(&pool, 95, "dark", "en-US", "cozy", None, None, None, None)
-                .await
-                .unwrap();
+        let settings = upsert_user_settings(
+            &pool, 95, "dark", "en-US", "cozy", None, None, None, None, None, None,
+        )
+        .await
+        .unwrap();
         assert_eq!(settings.theme, "dark");
         assert_eq!(settings.locale, "en-US");
 
         // Upsert again to update
         let updated = upsert_user_settings(
-            &pool, 95, "light", "en-GB", "compact", None, None, None, None,
+            &pool, 95, "light", "en-GB", "compact", None, None, None, None, None, None,
         )
         .await
         .unwrap();
@@ -900,4 +1187,91 @@ mod tests {
         let settings = get_user_settings(&pool, 96).await.unwrap();
         assert!(settings.is_none());
     }
+
+    #[tokio::test]
+    async fn test_show_mutuals_defaults_on_and_survives_partial_updates() {
+        let pool = test_pool().await;
+        create_user(&pool, 30, "private", 1, "private@example.com", "hash")
+            .await
+            .unwrap();
+
+        let settings = upsert_user_settings(
+            &pool, 30, "dark", "en-US", "cozy", None, None, None, None, None, None,
+        )
+        .await
+        .unwrap();
+        assert!(settings.show_mutuals);
+
+        upsert_user_settings(
+            &pool,
+            30,
+            "dark",
+            "en-US",
+            "cozy",
+            None,
+            None,
+            Some(false),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let settings = upsert_user_settings(
+            &pool, 30, "light", "en-US", "cozy", None, None, None, None, None, None,
+        )
+        .await
+        .unwrap();
+        assert!(!settings.show_mutuals);
+        assert!(
+            !get_user_settings(&pool, 30)
+                .await
+                .unwrap()
+                .unwrap()
+                .show_mutuals
+        );
+    }
+
+    #[tokio::test]
+    async fn test_confusable_usernames_share_a_skeleton() {
+        let pool = test_pool().await;
+        create_user(&pool, 40, "admin", 1, "admin@example.com", "hash")
+            .await
+            .unwrap();
+
+        let skeleton = username_skeleton("\u{0430}dmin");
+        assert_eq!(
+            find_user_id_by_username_skeleton(&pool, &skeleton)
+                .await
+                .unwrap(),
+            Some(40)
+        );
+        assert!(
+            find_user_id_by_username_skeleton(&pool, &username_skeleton("alice"))
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backfill_username_skeletons() {
+        let pool = test_pool().await;
+        create_user(&pool, 41, "Legacy", 1, "legacy@example.com", "hash")
+            .await
+            .unwrap();
+        sqlx::query("UPDATE users SET username_skeleton = NULL")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(backfill_username_skeletons(&pool, 100).await.unwrap(), 1);
+        assert_eq!(backfill_username_skeletons(&pool, 100).await.unwrap(), 0);
+        assert_eq!(
+            find_user_id_by_username_skeleton(&pool, &username_skeleton("legacy"))
+                .await
+                .unwrap(),
+            Some(41)
+        );
+    }
 }
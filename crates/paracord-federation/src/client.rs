@@ -4,11 +4,35 @@ use crate::transport;
 use crate::{FederationError, FederationEventEnvelope, FederationServerKey};
 use ed25519_dalek::SigningKey;
 use reqwest::Client;
+use serde::de::DeserializeOwned;
+use std::sync::OnceLock;
 use std::time::Duration;
 
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
 const MAX_RETRIES: u32 = 3;
 const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// Largest JSON response accepted from a peer.
+const MAX_RESPONSE_BYTES: usize = 4 * 1024 * 1024;
+/// Largest file downloaded from a peer in one request.
+const MAX_FILE_BYTES: usize = 512 * 1024 * 1024;
+
+/// Checks the embedding server applies to every outbound federation request,
+/// so peers (or URLs they hand us) can't point us at private networks.
+#[derive(Clone, Copy)]
+pub struct OutboundGuard {
+    /// Builder for the HTTP client, with address and redirect checks installed.
+    pub client_builder: fn() -> reqwest::ClientBuilder,
+    /// Whether a URL may be requested at all.
+    pub allows_url: fn(&reqwest::Url) -> bool,
+}
+
+static OUTBOUND_GUARD: OnceLock<OutboundGuard> = OnceLock::new();
+
+/// Install the guard used by every [`FederationClient`] created afterwards.
+/// Only the first call has any effect.
+pub fn install_outbound_guard(guard: OutboundGuard) {
+    let _ = OUTBOUND_GUARD.set(guard);
+}
 
 #[derive(Debug, Clone)]
 struct TransportSigner {
@@ -42,7 +66,11 @@ impl FederationClient {
         key_id: Option<String>,
         signing_key: Option<SigningKey>,
     ) -> Result<Self, FederationError> {
-        let http = Client::builder()
+        let builder = match OUTBOUND_GUARD.get() {
+            Some(guard) => (guard.client_builder)(),
+            None => Client::builder(),
+        };
+        let http = builder
             .timeout(DEFAULT_TIMEOUT)
             .user_agent("Paracord-Federation/0.4")
             .build()
@@ -70,10 +98,7 @@ impl FederationClient {
             base_url.trim_end_matches('/')
         );
         let resp = self.get_with_retry(&url).await?;
-        let info: ServerInfo = resp
-            .json()
-            .await
-            .map_err(|e| FederationError::RemoteError(format!("invalid server info: {e}")))?;
+        let info: ServerInfo = read_json(resp, "invalid server info").await?;
         Ok(info)
     }
 
@@ -84,10 +109,7 @@ impl FederationClient {
     ) -> Result<FederationKeysResponse, FederationError> {
         let url = format!("{}/keys", federation_endpoint.trim_end_matches('/'));
         let resp = self.get_with_retry(&url).await?;
-        let keys: FederationKeysResponse = resp
-            .json()
-            .await
-            .map_err(|e| FederationError::RemoteError(format!("invalid keys response: {e}")))?;
+        let keys: FederationKeysResponse = read_json(resp, "invalid keys response").await?;
         Ok(keys)
     }
 
@@ -101,10 +123,7 @@ impl FederationClient {
         let body_bytes =
             serde_json::to_vec(envelope).map_err(|e| FederationError::Http(e.to_string()))?;
         let resp = self.post_with_retry(&url, body_bytes).await?;
-        let body: PostEventResponse = resp
-            .json()
-            .await
-            .map_err(|e| FederationError::RemoteError(format!("invalid event response: {e}")))?;
+        let body: PostEventResponse = read_json(resp, "invalid event response").await?;
         Ok(body)
     }
 
@@ -149,9 +168,7 @@ impl FederationClient {
         let resp = self
             .get_with_retry_with_headers(&url, &extra_headers)
             .await?;
-        resp.json()
-            .await
-            .map_err(|e| FederationError::RemoteError(format!("invalid event response: {e}")))
+        read_json(resp, "invalid event response").await
     }
 
     /// Fetch messages/events from a remote server for a given room, paginated.
@@ -170,10 +187,7 @@ impl FederationClient {
             limit
         );
         let resp = self.get_with_retry_with_headers(&url, &[]).await?;
-        let events: FederationEventsResponse = resp
-            .json()
-            .await
-            .map_err(|e| FederationError::RemoteError(format!("invalid events response: {e}")))?;
+        let events: FederationEventsResponse = read_json(resp, "invalid events response").await?;
         Ok(events.events)
     }
 
@@ -185,9 +199,7 @@ impl FederationClient {
         let url = format!("{}/invite", federation_endpoint.trim_end_matches('/'));
         let body = serde_json::to_vec(payload).map_err(|e| FederationError::Http(e.to_string()))?;
         let resp = self.post_with_retry(&url, body).await?;
-        resp.json()
-            .await
-            .map_err(|e| FederationError::RemoteError(format!("invalid invite response: {e}")))
+        read_json(resp, "invalid invite response").await
     }
 
     pub async fn send_join(
@@ -198,9 +210,7 @@ impl FederationClient {
         let url = format!("{}/join", federation_endpoint.trim_end_matches('/'));
         let body = serde_json::to_vec(payload).map_err(|e| FederationError::Http(e.to_string()))?;
         let resp = self.post_with_retry(&url, body).await?;
-        resp.json()
-            .await
-            .map_err(|e| FederationError::RemoteError(format!("invalid join response: {e}")))
+        read_json(resp, "invalid join response").await
     }
 
     pub async fn send_leave(
@@ -211,9 +221,7 @@ impl FederationClient {
         let url = format!("{}/leave", federation_endpoint.trim_end_matches('/'));
         let body = serde_json::to_vec(payload).map_err(|e| FederationError::Http(e.to_string()))?;
         let resp = self.post_with_retry(&url, body).await?;
-        resp.json()
-            .await
-            .map_err(|e| FederationError::RemoteError(format!("invalid leave response: {e}")))
+        read_json(resp, "invalid leave response").await
     }
 
     pub async fn request_media_token(
@@ -224,9 +232,7 @@ impl FederationClient {
         let url = format!("{}/media/token", federation_endpoint.trim_end_matches('/'));
         let body = serde_json::to_vec(payload).map_err(|e| FederationError::Http(e.to_string()))?;
         let resp = self.post_with_retry(&url, body).await?;
-        resp.json()
-            .await
-            .map_err(|e| FederationError::RemoteError(format!("invalid media token response: {e}")))
+        read_json(resp, "invalid media token response").await
     }
 
     pub async fn relay_media_action(
@@ -237,9 +243,7 @@ impl FederationClient {
         let url = format!("{}/media/relay", federation_endpoint.trim_end_matches('/'));
         let body = serde_json::to_vec(payload).map_err(|e| FederationError::Http(e.to_string()))?;
         let resp = self.post_with_retry(&url, body).await?;
-        resp.json()
-            .await
-            .map_err(|e| FederationError::RemoteError(format!("invalid media relay response: {e}")))
+        read_json(resp, "invalid media relay response").await
     }
 
     pub async fn request_file_token(
@@ -250,9 +254,7 @@ impl FederationClient {
         let url = format!("{}/file/token", federation_endpoint.trim_end_matches('/'));
         let body = serde_json::to_vec(payload).map_err(|e| FederationError::Http(e.to_string()))?;
         let resp = self.post_with_retry(&url, body).await?;
-        resp.json()
-            .await
-            .map_err(|e| FederationError::RemoteError(format!("invalid file token response: {e}")))
+        read_json(resp, "invalid file token response").await
     }
 
     pub async fn download_federated_file(
@@ -275,11 +277,8 @@ impl FederationClient {
                     .and_then(|s| s.strip_suffix('"'))
                     .map(str::to_string)
             });
-        let bytes = resp
-            .bytes()
-            .await
-            .map_err(|e| FederationError::Http(e.to_string()))?;
-        Ok((bytes.to_vec(), content_type, filename))
+        let bytes = read_limited(resp, MAX_FILE_BYTES).await?;
+        Ok((bytes, content_type, filename))
     }
 
     /// GET request with exponential backoff retry.
@@ -292,6 +291,7 @@ impl FederationClient {
         url: &str,
         extra_headers: &[(&str, String)],
     ) -> Result<reqwest::Response, FederationError> {
+        check_outbound_url(url)?;
         let mut last_err = FederationError::Http("no attempts made".to_string());
         for attempt in 0..MAX_RETRIES {
             let path = transport::request_path_from_url(url);
@@ -335,6 +335,7 @@ impl FederationClient {
         url: &str,
         body_bytes: Vec<u8>,
     ) -> Result<reqwest::Response, FederationError> {
+        check_outbound_url(url)?;
         let mut last_err = FederationError::Http("no attempts made".to_string());
         for attempt in 0..MAX_RETRIES {
             let mut request = self
@@ -397,6 +398,52 @@ impl FederationClient {
     }
 }
 
+fn check_outbound_url(url: &str) -> Result<(), FederationError> {
+    let Some(guard) = OUTBOUND_GUARD.get() else {
+        return Ok(());
+    };
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|_| FederationError::Http(format!("invalid URL {url}")))?;
+    if (guard.allows_url)(&parsed) {
+        Ok(())
+    } else {
+        Err(FederationError::Http(format!(
+            "refusing to contact non-public address {url}"
+        )))
+    }
+}
+
+/// Read a response body, failing once it grows past `max` bytes.
+async fn read_limited(mut resp: reqwest::Response, max: usize) -> Result<Vec<u8>, FederationError> {
+    if resp.content_length().is_some_and(|len| len > max as u64) {
+        return Err(FederationError::RemoteError(format!(
+            "response larger than {max} bytes"
+        )));
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| FederationError::Http(e.to_string()))?
+    {
+        if body.len() + chunk.len() > max {
+            return Err(FederationError::RemoteError(format!(
+                "response larger than {max} bytes"
+            )));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+async fn read_json<T: DeserializeOwned>(
+    resp: reqwest::Response,
+    what: &str,
+) -> Result<T, FederationError> {
+    let body = read_limited(resp, MAX_RESPONSE_BYTES).await?;
+    serde_json::from_slice(&body).map_err(|e| FederationError::RemoteError(format!("{what}: {e}")))
+}
+
 impl Default for FederationClient {
     fn default() -> Self {
         Self::new().expect("failed to create federation HTTP client")
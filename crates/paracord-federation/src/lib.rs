@@ -366,11 +366,12 @@ impl FederationService {
         Ok(envelope)
     }
 
-    /// Forward a signed event envelope to all trusted federated peer servers.
+    /// Queue a signed event envelope for delivery to all trusted federated
+    /// peer servers.
     ///
-    /// This is intended to be called from within a `tokio::spawn` so that it
-    /// does not block the original HTTP response.  Errors for individual peers
-    /// are logged and do not propagate.
+    /// Delivery happens on the outbound queue worker, so an unreachable peer
+    /// never holds up the caller. Errors for individual peers are logged and
+    /// do not propagate.
     pub async fn forward_envelope_to_peers(
         &self,
         pool: &DbPool,
@@ -400,25 +401,6 @@ impl FederationService {
             return;
         }
 
-        // Relay cycle guard: if we already persisted this event, skip relaying.
-        match self.fetch_event(pool, &envelope.event_id).await {
-            Ok(Some(_)) => {
-                tracing::debug!(
-                    "federation: skipping relay of already-persisted event {}",
-                    envelope.event_id
-                );
-                return;
-            }
-            Err(e) => {
-                tracing::warn!(
-                    "federation: relay dedup check failed for {}: {}",
-                    envelope.event_id,
-                    e
-                );
-            }
-            Ok(None) => {}
-        }
-
         let now_ms = chrono::Utc::now().timestamp_millis();
         let peers = match paracord_db::federation::list_trusted_federated_servers(pool).await {
             Ok(servers) => servers,
@@ -453,14 +435,7 @@ impl FederationService {
         };
         let has_scoped_targets = !scoped_targets.is_empty();
 
-        let client = match self.build_signed_client() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("federation: failed to create HTTP client: {e}");
-                return;
-            }
-        };
-
+        let mut queued = false;
         for peer in &peers {
             // Don't forward back to ourselves
             if peer.server_name == self.config.server_name {
@@ -505,69 +480,13 @@ impl FederationService {
                     peer.server_name,
                     e
                 );
+                continue;
             }
+            queued = true;
+        }
 
-            let attempt_started = std::time::Instant::now();
-            match client.post_event(&peer.federation_endpoint, envelope).await {
-                Ok(resp) => {
-                    let latency_ms = attempt_started.elapsed().as_millis() as i64;
-                    let attempt_ts = chrono::Utc::now().timestamp_millis();
-                    let _ = paracord_db::federation::record_delivery_attempt(
-                        pool,
-                        &peer.server_name,
-                        &envelope.event_id,
-                        true,
-                        Some(202),
-                        None,
-                        Some(latency_ms),
-                        attempt_ts,
-                    )
-                    .await;
-                    let _ = paracord_db::federation::mark_outbound_event_delivered(
-                        pool,
-                        &peer.server_name,
-                        &envelope.event_id,
-                    )
-                    .await;
-                    tracing::info!(
-                        "federation: forwarded event {} to {} (inserted={})",
-                        envelope.event_id,
-                        peer.server_name,
-                        resp.inserted,
-                    );
-                }
-                Err(e) => {
-                    let latency_ms = attempt_started.elapsed().as_millis() as i64;
-                    let attempt_ts = chrono::Utc::now().timestamp_millis();
-                    let retry_at = next_retry_ts(attempt_ts, 0);
-                    let err_msg = e.to_string();
-                    let _ = paracord_db::federation::record_delivery_attempt(
-                        pool,
-                        &peer.server_name,
-                        &envelope.event_id,
-                        false,
-                        None,
-                        Some(&err_msg),
-                        Some(latency_ms),
-                        attempt_ts,
-                    )
-                    .await;
-                    let _ = paracord_db::federation::mark_outbound_event_retry(
-                        pool,
-                        &peer.server_name,
-                        &envelope.event_id,
-                        retry_at,
-                        Some(&err_msg),
-                        attempt_ts,
-                    )
-                    .await;
-                    tracing::warn!(
-                        "federation: failed to forward event {} to {}: {e}",
-                        envelope.event_id,
-                        peer.server_name,
-                    );
-                }
-            }
+        if queued {
+            OUTBOUND_WAKE.notify_one();
         }
     }
 
@@ -576,26 +495,24 @@ impl FederationService {
             return;
         }
 
-        // Purge events that have exceeded max retries or max age before processing.
-        const MAX_RETRY_ATTEMPTS: i64 = 12;
-        const MAX_EVENT_AGE_MS: i64 = 86_400_000; // 24 hours
+        // Dead-letter events that have exceeded max retries or max age before processing.
         let now_ms = chrono::Utc::now().timestamp_millis();
-        match paracord_db::federation::purge_expired_outbound_events(
+        match paracord_db::federation::dead_letter_expired_outbound_events(
             pool,
             now_ms,
-            MAX_RETRY_ATTEMPTS,
-            MAX_EVENT_AGE_MS,
+            MAX_OUTBOUND_ATTEMPTS,
+            MAX_OUTBOUND_EVENT_AGE_MS,
         )
         .await
         {
-            Ok(purged) if purged > 0 => {
-                tracing::info!(
-                    "federation: purged {} expired outbound queue entries",
-                    purged
+            Ok(moved) if moved > 0 => {
+                tracing::warn!(
+                    "federation: dead-lettered {} outbound events after exhausting retries",
+                    moved
                 );
             }
             Err(e) => {
-                tracing::warn!("federation: failed to purge expired outbound events: {}", e);
+                tracing::warn!("federation: failed to dead-letter outbound events: {}", e);
             }
             _ => {}
         }
@@ -619,7 +536,13 @@ impl FederationService {
             }
         };
 
+        // Once a destination fails, the rest of its events wait for the same
+        // backoff instead of each timing out against a server that is down.
+        let mut unreachable = std::collections::HashSet::new();
         for row in due {
+            if unreachable.contains(&row.destination_server) {
+                continue;
+            }
             let envelope = FederationEventEnvelope {
                 event_id: row.event_id.clone(),
                 room_id: row.room_id.clone(),
@@ -681,6 +604,19 @@ impl FederationService {
                         attempt_ts,
                     )
                     .await;
+                    let _ = paracord_db::federation::defer_outbound_destination(
+                        pool,
+                        &row.destination_server,
+                        retry_at,
+                    )
+                    .await;
+                    tracing::warn!(
+                        "federation: failed to deliver event {} to {} (attempt {}): {err_msg}",
+                        row.event_id,
+                        row.destination_server,
+                        row.attempt_count + 1,
+                    );
+                    unreachable.insert(row.destination_server);
                 }
             }
         }
@@ -726,8 +662,21 @@ impl FederationService {
     }
 }
 
+/// Delivery attempts before an outbound event is dead-lettered. With the
+/// backoff below this rides out a peer being down for most of a day.
+const MAX_OUTBOUND_ATTEMPTS: i64 = 24;
+const MAX_OUTBOUND_EVENT_AGE_MS: i64 = 2 * 86_400_000; // 48 hours
+
+/// Wakes the outbound worker as soon as something is queued.
+static OUTBOUND_WAKE: tokio::sync::Notify = tokio::sync::Notify::const_new();
+
+/// Resolves once an event has been queued for delivery since the last call.
+pub async fn outbound_event_queued() {
+    OUTBOUND_WAKE.notified().await;
+}
+
 fn next_retry_ts(now_ms: i64, attempt_count: i64) -> i64 {
-    let exp = (attempt_count.clamp(0, 8)) as u32;
+    let exp = (attempt_count.clamp(0, 10)) as u32;
     let delay_ms = 5_000_i64.saturating_mul(1_i64 << exp);
     now_ms.saturating_add(delay_ms.min(3_600_000))
 }
@@ -887,4 +836,75 @@ mod tests {
         assert_eq!(env.depth, ts);
         assert_eq!(env.room_id, "!42:chat.example");
     }
+
+    #[tokio::test]
+    async fn outbound_queue_backs_off_unreachable_peers_then_dead_letters() {
+        let pool = paracord_db::create_pool("sqlite::memory:", 1)
+            .await
+            .unwrap();
+        paracord_db::run_migrations(&pool).await.unwrap();
+        // Nothing listens on the discard port, so every delivery fails.
+        paracord_db::federation::upsert_federated_server(
+            &pool,
+            1,
+            "node-b.example",
+            "node-b.example",
+            "http://127.0.0.1:9/_paracord/federation/v1",
+            None,
+            None,
+            true,
+        )
+        .await
+        .unwrap();
+
+        let service = test_service();
+        let now = chrono::Utc::now().timestamp_millis();
+        for (ts, stable_id) in [(now, "1"), (now + 1, "2")] {
+            let env = service
+                .build_custom_envelope(
+                    "m.typing",
+                    "!42:chat.example".to_string(),
+                    "bob",
+                    &serde_json::json!({"guild_id":"42","channel_id":"43"}),
+                    ts,
+                    None,
+                    Some(stable_id),
+                )
+                .unwrap();
+            // Local events are persisted before they are forwarded.
+            service.persist_event(&pool, &env).await.unwrap();
+            service.forward_envelope_to_peers(&pool, &env).await;
+        }
+        let stats = paracord_db::federation::outbound_queue_stats(&pool, 0)
+            .await
+            .unwrap();
+        assert_eq!(stats.queued, 2);
+
+        // The first failure defers the peer's whole queue.
+        service.process_outbound_queue_once(&pool, 64).await;
+        let stats = paracord_db::federation::outbound_queue_stats(&pool, 0)
+            .await
+            .unwrap();
+        assert_eq!((stats.queued, stats.retrying), (2, 1));
+        assert_eq!(stats.failed_attempts, 1);
+        let due = paracord_db::federation::fetch_due_outbound_events(
+            &pool,
+            chrono::Utc::now().timestamp_millis(),
+            64,
+        )
+        .await
+        .unwrap();
+        assert!(due.is_empty());
+
+        sqlx::query("UPDATE federation_outbound_queue SET attempt_count = $1")
+            .bind(MAX_OUTBOUND_ATTEMPTS)
+            .execute(&pool)
+            .await
+            .unwrap();
+        service.process_outbound_queue_once(&pool, 64).await;
+        let stats = paracord_db::federation::outbound_queue_stats(&pool, 0)
+            .await
+            .unwrap();
+        assert_eq!((stats.queued, stats.dead_lettered), (0, 2));
+    }
 }
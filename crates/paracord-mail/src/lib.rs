@@ -0,0 +1,248 @@
+//! Transactional email for Paracord.
+//!
+//! Messages are rendered from a small set of [`Template`]s and handed to a
+//! bounded queue drained by a background task, so a slow or unreachable SMTP
+//! server never stalls the request that triggered the email. When no SMTP host
+//! is configured the [`Mailer`] logs what it would have sent instead.
+
+pub mod template;
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use tokio::sync::mpsc;
+
+pub use template::Template;
+
+/// Maximum number of emails waiting to be handed to the SMTP server.
+const QUEUE_CAPACITY: usize = 256;
+/// Attempts per email before it is dropped (only transient failures retry).
+const MAX_SEND_ATTEMPTS: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, thiserror::Error)]
+pub enum MailError {
+    #[error("invalid email address '{0}'")]
+    InvalidAddress(String),
+    #[error("unknown SMTP TLS mode '{0}' (expected none, starttls or implicit)")]
+    InvalidTlsMode(String),
+    #[error("SMTP transport error: {0}")]
+    Transport(#[from] lettre::transport::smtp::Error),
+}
+
+/// How the connection to the SMTP server is secured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    /// Plaintext connection. Only suitable for a relay on localhost.
+    None,
+    /// Upgrade a plaintext connection with STARTTLS (usually port 587).
+    StartTls,
+    /// TLS from the first byte (usually port 465).
+    Implicit,
+}
+
+impl FromStr for SmtpTls {
+    type Err = MailError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" | "off" | "plain" => Ok(Self::None),
+            "starttls" => Ok(Self::StartTls),
+            "implicit" | "tls" | "ssl" => Ok(Self::Implicit),
+            other => Err(MailError::InvalidTlsMode(other.to_string())),
+        }
+    }
+}
+
+/// SMTP connection settings.
+#[derive(Debug, Clone)]
+pub struct MailConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: SmtpTls,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Sender address, either `addr@example.com` or `Name <addr@example.com>`.
+    pub from: String,
+}
+
+struct QueuedMail {
+    to: String,
+    subject: String,
+    body: String,
+}
+
+/// Handle used to queue outgoing email. Cheap to clone.
+#[derive(Clone)]
+pub struct Mailer {
+    queue: Option<mpsc::Sender<QueuedMail>>,
+}
+
+impl Mailer {
+    /// A mailer that logs messages instead of sending them.
+    pub fn disabled() -> Self {
+        Self { queue: None }
+    }
+
+    /// Build the SMTP transport and spawn the background sender task.
+    /// Must be called from within a Tokio runtime.
+    pub fn start(config: &MailConfig) -> Result<Self, MailError> {
+        let from: Mailbox = config
+            .from
+            .parse()
+            .map_err(|_| MailError::InvalidAddress(config.from.clone()))?;
+        let mut builder = match config.tls {
+            SmtpTls::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(config.host.as_str())
+            }
+            SmtpTls::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+            }
+            SmtpTls::Implicit => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+        }
+        .port(config.port);
+        if let Some(username) = config.username.as_deref().filter(|u| !u.is_empty()) {
+            builder = builder.credentials(Credentials::new(
+                username.to_string(),
+                config.password.clone().unwrap_or_default(),
+            ));
+        }
+        let transport = builder.build();
+
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(run_sender(transport, from, rx));
+        tracing::info!(
+            "SMTP mailer enabled ({}:{}, tls={:?})",
+            config.host,
+            config.port,
+            config.tls
+        );
+        Ok(Self { queue: Some(tx) })
+    }
+
+    /// Whether messages are actually delivered over SMTP.
+    pub fn is_enabled(&self) -> bool {
+        self.queue.is_some()
+    }
+
+    /// Queue `template` for delivery to `to`. Never blocks: if the queue is
+    /// full the message is dropped with a warning.
+    pub fn send(&self, to: &str, template: &Template) {
+        let subject = template.subject();
+        let body = template.body();
+        let Some(queue) = &self.queue else {
+            tracing::info!(
+                to,
+                subject,
+                "SMTP not configured; email not sent:\n{}",
+                body
+            );
+            return;
+        };
+        let mail = QueuedMail {
+            to: to.to_string(),
+            subject,
+            body,
+        };
+        match queue.try_send(mail) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(mail)) => {
+                tracing::warn!(to = %mail.to, subject = %mail.subject, "Mail queue full; dropping email");
+            }
+            Err(mpsc::error::TrySendError::Closed(mail)) => {
+                tracing::warn!(to = %mail.to, subject = %mail.subject, "Mail sender stopped; dropping email");
+            }
+        }
+    }
+}
+
+async fn run_sender(
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    mut rx: mpsc::Receiver<QueuedMail>,
+) {
+    while let Some(mail) = rx.recv().await {
+        let to: Mailbox = match mail.to.parse() {
+            Ok(to) => to,
+            Err(_) => {
+                tracing::warn!(to = %mail.to, "Skipping email to invalid address");
+                continue;
+            }
+        };
+        let message = match Message::builder()
+            .from(from.clone())
+            .to(to)
+            .subject(mail.subject.as_str())
+            .header(ContentType::TEXT_PLAIN)
+            .body(mail.body)
+        {
+            Ok(message) => message,
+            Err(err) => {
+                tracing::warn!(to = %mail.to, "Failed to build email: {}", err);
+                continue;
+            }
+        };
+
+        let mut attempt = 1;
+        loop {
+            match transport.send(message.clone()).await {
+                Ok(_) => break,
+                Err(err) if err.is_transient() && attempt < MAX_SEND_ATTEMPTS => {
+                    tracing::debug!(to = %mail.to, attempt, "Transient SMTP failure: {}", err);
+                    tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt - 1)).await;
+                    attempt += 1;
+                }
+                Err(err) => {
+                    tracing::warn!(to = %mail.to, subject = %mail.subject, "Failed to send email: {}", err);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tls_modes() {
+        assert_eq!("STARTTLS".parse::<SmtpTls>().unwrap(), SmtpTls::StartTls);
+        assert_eq!("implicit".parse::<SmtpTls>().unwrap(), SmtpTls::Implicit);
+        assert_eq!("none".parse::<SmtpTls>().unwrap(), SmtpTls::None);
+        assert!("maybe".parse::<SmtpTls>().is_err());
+    }
+
+    #[tokio::test]
+    async fn start_rejects_invalid_from_address() {
+        let config = MailConfig {
+            host: "localhost".into(),
+            port: 25,
+            tls: SmtpTls::None,
+            username: None,
+            password: None,
+            from: "not an address".into(),
+        };
+        assert!(matches!(
+            Mailer::start(&config),
+            Err(MailError::InvalidAddress(_))
+        ));
+    }
+
+    #[test]
+    fn disabled_mailer_accepts_sends() {
+        let mailer = Mailer::disabled();
+        assert!(!mailer.is_enabled());
+        mailer.send(
+            "user@example.com",
+            &Template::EmailVerification {
+                username: "user".into(),
+                link: "https://chat.example.com/verify".into(),
+            },
+        );
+    }
+}
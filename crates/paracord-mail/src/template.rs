@@ -0,0 +1,69 @@
+//! Plain-text templates for transactional email.
+
+/// A transactional email and the values it is rendered with.
+#[derive(Debug, Clone)]
+pub enum Template {
+    /// Sent after registration when the server requires verified addresses.
+    EmailVerification { username: String, link: String },
+    /// Sent when an account signs in from a device it has not used before.
+    NewDeviceLogin {
+        username: String,
+        device: String,
+        ip_address: Option<String>,
+        time: String,
+    },
+}
+
+impl Template {
+    pub fn subject(&self) -> String {
+        match self {
+            Self::EmailVerification { .. } => "Verify your Paracord email address".to_string(),
+            Self::NewDeviceLogin { .. } => "New sign-in to your Paracord account".to_string(),
+        }
+    }
+
+    pub fn body(&self) -> String {
+        match self {
+            Self::EmailVerification { username, link } => format!(
+                "Hi {username},\n\n\
+                 Confirm your email address to finish setting up your Paracord account:\n\n\
+                 {link}\n\n\
+                 If you did not create this account you can ignore this email."
+            ),
+            Self::NewDeviceLogin {
+                username,
+                device,
+                ip_address,
+                time,
+            } => format!(
+                "Hi {username},\n\n\
+                 Your Paracord account was just signed in to from a new device.\n\n\
+                 Device: {device}\n\
+                 IP address: {}\n\
+                 Time: {time}\n\n\
+                 If this was you, no action is needed. If not, change your password \
+                 and sign out of other sessions from your account settings.",
+                ip_address.as_deref().unwrap_or("unknown")
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_device_login_includes_details() {
+        let template = Template::NewDeviceLogin {
+            username: "alice".into(),
+            device: "Firefox on Linux".into(),
+            ip_address: None,
+            time: "2026-01-01 12:00 UTC".into(),
+        };
+        let body = template.body();
+        assert!(body.contains("Hi alice"));
+        assert!(body.contains("Device: Firefox on Linux"));
+        assert!(body.contains("IP address: unknown"));
+    }
+}
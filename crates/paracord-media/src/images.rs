@@ -0,0 +1,733 @@
+//! Dimension probing and thumbnail generation for image attachments, and
+//! normalization of custom emoji, role icon, avatar, guild icon and banner
+//! images.
+//!
+//! Every stored profile or guild image is decoded and re-encoded from its
+//! pixels, so EXIF and any other metadata in the upload (camera, location,
+//! timestamps) never reaches storage.
+
+use image::codecs::gif::{GifDecoder, GifEncoder, Repeat};
+use image::imageops::{self, FilterType};
+use image::metadata::Orientation;
+use image::{
+    AnimationDecoder, DynamicImage, Frame, ImageDecoder, ImageFormat, ImageReader, Limits,
+    RgbaImage,
+};
+use std::io::Cursor;
+
+/// Longest edge of a generated thumbnail, in pixels.
+pub const THUMBNAIL_MAX_EDGE: u32 = 400;
+/// Images wider or taller than this are never decoded.
+pub const MAX_DECODE_DIMENSION: u32 = 12_000;
+/// Ceiling on decoder allocations so a tiny file cannot expand into gigabytes
+/// of pixels (decompression bomb).
+pub const MAX_DECODE_ALLOC_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Edge length of the square canvas every custom emoji is normalized to.
+pub const EMOJI_SIZE: u32 = 128;
+/// Animated emojis with more frames than this are rejected rather than
+/// decoded, bounding the memory a single upload can claim.
+pub const MAX_EMOJI_FRAMES: usize = 200;
+
+/// Edge length of the square canvas every role icon is normalized to.
+pub const ROLE_ICON_SIZE: u32 = 64;
+
+/// Profile banners larger than this are scaled down to fit, keeping their
+/// aspect ratio.
+pub const BANNER_MAX_WIDTH: u32 = 1500;
+pub const BANNER_MAX_HEIGHT: u32 = 600;
+
+/// Edge lengths of the square variants stored for every avatar and guild
+/// icon, smallest first.
+pub const AVATAR_VARIANT_SIZES: [u32; 5] = [16, 32, 64, 128, 256];
+/// Hashes of animated avatars and icons start with this; their variants are
+/// GIFs rather than PNGs.
+pub const ANIMATED_HASH_PREFIX: &str = "a_";
+
+#[derive(Debug, Clone)]
+pub struct Thumbnail {
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Storage key of the thumbnail stored next to `attachments/{id}.{ext}`.
+pub fn thumbnail_storage_key(attachment_id: i64, ext: &str) -> String {
+    format!("attachments/{}_thumb.{}", attachment_id, ext)
+}
+
+/// A custom emoji re-encoded at [`EMOJI_SIZE`]. Animated emojis are GIFs,
+/// everything else is a PNG.
+#[derive(Debug, Clone)]
+pub struct ProcessedEmoji {
+    pub data: Vec<u8>,
+    pub animated: bool,
+}
+
+impl ProcessedEmoji {
+    pub fn extension(&self) -> &'static str {
+        emoji_extension(self.animated)
+    }
+}
+
+pub fn emoji_extension(animated: bool) -> &'static str {
+    if animated {
+        "gif"
+    } else {
+        "png"
+    }
+}
+
+/// Storage key of a custom emoji image.
+pub fn emoji_storage_key(emoji_id: i64, animated: bool) -> String {
+    format!("emojis/{}.{}", emoji_id, emoji_extension(animated))
+}
+
+/// Storage key of a user's profile banner. `hash` is the banner hash stored
+/// on the user row.
+pub fn banner_storage_key(user_id: i64, hash: &str) -> String {
+    format!("banners/{}/{}.png", user_id, hash)
+}
+
+/// Storage key of a role icon. `hash` is the icon hash stored on the role.
+pub fn role_icon_storage_key(role_id: i64, hash: &str) -> String {
+    format!("role-icons/{}/{}.png", role_id, hash)
+}
+
+/// A user avatar or guild icon re-encoded at every size in
+/// [`AVATAR_VARIANT_SIZES`]. Animated images are GIFs, everything else PNGs.
+#[derive(Debug, Clone)]
+pub struct ProcessedAvatar {
+    /// `(edge length, encoded image)`, smallest first.
+    pub variants: Vec<(u32, Vec<u8>)>,
+    pub animated: bool,
+}
+
+impl ProcessedAvatar {
+    /// The largest variant, which the image's hash is computed from.
+    pub fn largest(&self) -> &[u8] {
+        self.variants
+            .last()
+            .map(|(_, data)| data.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// File extension of the variants of an avatar or icon with `hash`.
+pub fn avatar_extension(hash: &str) -> &'static str {
+    emoji_extension(hash.starts_with(ANIMATED_HASH_PREFIX))
+}
+
+/// The stored variant to serve for a requested edge length: the smallest
+/// one at least that large, or the largest when none is or no size is asked
+/// for.
+pub fn avatar_variant_size(requested: Option<u32>) -> u32 {
+    let largest = AVATAR_VARIANT_SIZES[AVATAR_VARIANT_SIZES.len() - 1];
+    requested
+        .and_then(|size| {
+            AVATAR_VARIANT_SIZES
+                .iter()
+                .copied()
+                .find(|variant| *variant >= size)
+        })
+        .unwrap_or(largest)
+}
+
+/// Storage key of one size variant of a user's avatar.
+pub fn avatar_storage_key(user_id: i64, hash: &str, size: u32) -> String {
+    format!(
+        "avatars/{}/{}_{}.{}",
+        user_id,
+        hash,
+        size,
+        avatar_extension(hash)
+    )
+}
+
+/// Storage key of one size variant of a guild icon.
+pub fn guild_icon_storage_key(guild_id: i64, hash: &str, size: u32) -> String {
+    format!(
+        "guild-icons/{}/{}_{}.{}",
+        guild_id,
+        hash,
+        size,
+        avatar_extension(hash)
+    )
+}
+
+/// Storage key of a guild banner. `hash` is the banner hash on the guild.
+pub fn guild_banner_storage_key(guild_id: i64, hash: &str) -> String {
+    format!("guild-banners/{}/{}.png", guild_id, hash)
+}
+
+fn decode_limits() -> Limits {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DECODE_DIMENSION);
+    limits.max_image_height = Some(MAX_DECODE_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC_BYTES);
+    limits
+}
+
+/// Read the pixel dimensions from the image header without decoding pixels.
+/// Returns `None` for anything that is not a recognizable image.
+pub fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// Downscale an image so its longest edge is at most `max_edge`, re-encoded
+/// in the source format.
+///
+/// Returns `None` when no thumbnail is needed (the image already fits) or
+/// cannot be produced safely: unknown or malformed data, an unsupported
+/// encoder, or an image exceeding the decode limits.
+pub fn generate_thumbnail(data: &[u8], max_edge: u32) -> Option<Thumbnail> {
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?;
+    let format = reader.format()?;
+    let (width, height) = image_dimensions(data)?;
+    if width <= max_edge && height <= max_edge {
+        return None;
+    }
+    if width > MAX_DECODE_DIMENSION || height > MAX_DECODE_DIMENSION {
+        return None;
+    }
+
+    reader.limits(decode_limits());
+    let thumb = reader.decode().ok()?.thumbnail(max_edge, max_edge);
+    // JPEG has no alpha channel; the encoder rejects RGBA input.
+    let thumb = if format == ImageFormat::Jpeg {
+        DynamicImage::ImageRgb8(thumb.to_rgb8())
+    } else {
+        thumb
+    };
+
+    let mut out = Cursor::new(Vec::new());
+    thumb.write_to(&mut out, format).ok()?;
+    Some(Thumbnail {
+        data: out.into_inner(),
+        width: thumb.width(),
+        height: thumb.height(),
+    })
+}
+
+/// Crop the center square out of `image` and scale it to `size` x `size`.
+fn fill_square(image: &RgbaImage, size: u32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let edge = width.min(height).max(1);
+    let square =
+        imageops::crop_imm(image, (width - edge) / 2, (height - edge) / 2, edge, edge).to_image();
+    imageops::resize(&square, size, size, FilterType::Triangle)
+}
+
+/// Decode a still image, turned upright according to its EXIF orientation
+/// so the orientation is not lost along with the rest of the metadata.
+fn decode_upright(mut reader: ImageReader<Cursor<&[u8]>>) -> Option<DynamicImage> {
+    reader.limits(decode_limits());
+    let mut decoder = reader.into_decoder().ok()?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut image = DynamicImage::from_decoder(decoder).ok()?;
+    image.apply_orientation(orientation);
+    Some(image)
+}
+
+/// Scale `image` to fit inside an [`EMOJI_SIZE`] square and center it on a
+/// transparent canvas of exactly that size.
+fn fit_emoji_canvas(image: &RgbaImage) -> RgbaImage {
+    fit_square_canvas(image, EMOJI_SIZE)
+}
+
+/// Scale `image` to fit a transparent `size` x `size` canvas, centered.
+fn fit_square_canvas(image: &RgbaImage, size: u32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let longest = width.max(height).max(1);
+    let scaled_width = (width * size / longest).max(1);
+    let scaled_height = (height * size / longest).max(1);
+    let scaled = imageops::resize(image, scaled_width, scaled_height, FilterType::Triangle);
+    let mut canvas = RgbaImage::new(size, size);
+    imageops::overlay(
+        &mut canvas,
+        &scaled,
+        i64::from((size - scaled_width) / 2),
+        i64::from((size - scaled_height) / 2),
+    );
+    canvas
+}
+
+fn decode_gif_frames(data: &[u8]) -> Option<Vec<Frame>> {
+    let mut decoder = GifDecoder::new(Cursor::new(data)).ok()?;
+    decoder.set_limits(decode_limits()).ok()?;
+    let mut frames = Vec::new();
+    for frame in decoder.into_frames() {
+        if frames.len() == MAX_EMOJI_FRAMES {
+            return None;
+        }
+        frames.push(frame.ok()?);
+    }
+    Some(frames)
+}
+
+/// Normalize an uploaded emoji to an [`EMOJI_SIZE`] square.
+///
+/// Whether the emoji is animated is decided by the file itself: a GIF with
+/// more than one frame stays an animated GIF, while single-frame GIFs and
+/// every other supported format become a static PNG. Returns `None` for
+/// anything that is not a decodable PNG, GIF, JPEG or WebP image within the
+/// decode limits.
+pub fn process_emoji(data: &[u8]) -> Option<ProcessedEmoji> {
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?;
+    let format = reader.format()?;
+    if !matches!(
+        format,
+        ImageFormat::Png | ImageFormat::Gif | ImageFormat::Jpeg | ImageFormat::WebP
+    ) {
+        return None;
+    }
+    let (width, height) = image_dimensions(data)?;
+    if width > MAX_DECODE_DIMENSION || height > MAX_DECODE_DIMENSION {
+        return None;
+    }
+
+    if format == ImageFormat::Gif {
+        let frames = decode_gif_frames(data)?;
+        if frames.len() > 1 {
+            let frames = frames.into_iter().map(|frame| {
+                let delay = frame.delay();
+                Frame::from_parts(fit_emoji_canvas(frame.buffer()), 0, 0, delay)
+            });
+            let mut out = Vec::new();
+            {
+                let mut encoder = GifEncoder::new(&mut out);
+                encoder.set_repeat(Repeat::Infinite).ok()?;
+                encoder.encode_frames(frames).ok()?;
+            }
+            return Some(ProcessedEmoji {
+                data: out,
+                animated: true,
+            });
+        }
+    }
+
+    reader.limits(decode_limits());
+    let image = fit_emoji_canvas(&reader.decode().ok()?.to_rgba8());
+    let mut out = Cursor::new(Vec::new());
+    DynamicImage::ImageRgba8(image)
+        .write_to(&mut out, ImageFormat::Png)
+        .ok()?;
+    Some(ProcessedEmoji {
+        data: out.into_inner(),
+        animated: false,
+    })
+}
+
+/// Normalize an uploaded role icon to a static [`ROLE_ICON_SIZE`] square PNG.
+/// Animated GIFs keep only their first frame. Returns `None` for anything
+/// that is not a decodable PNG, GIF, JPEG or WebP image within the decode
+/// limits.
+pub fn process_role_icon(data: &[u8]) -> Option<Vec<u8>> {
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?;
+    let format = reader.format()?;
+    if !matches!(
+        format,
+        ImageFormat::Png | ImageFormat::Gif | ImageFormat::Jpeg | ImageFormat::WebP
+    ) {
+        return None;
+    }
+    let (width, height) = image_dimensions(data)?;
+    if width > MAX_DECODE_DIMENSION || height > MAX_DECODE_DIMENSION {
+        return None;
+    }
+
+    reader.limits(decode_limits());
+    let image = fit_square_canvas(&reader.decode().ok()?.to_rgba8(), ROLE_ICON_SIZE);
+    let mut out = Cursor::new(Vec::new());
+    DynamicImage::ImageRgba8(image)
+        .write_to(&mut out, ImageFormat::Png)
+        .ok()?;
+    Some(out.into_inner())
+}
+
+/// Whether `data` is an animated image: a GIF, WebP or APNG with more than
+/// one frame. Anything else is treated as a still image.
+pub fn is_animated(data: &[u8]) -> bool {
+    match image::guess_format(data).ok() {
+        Some(ImageFormat::Gif) => {
+            let Ok(mut decoder) = GifDecoder::new(Cursor::new(data)) else {
+                return false;
+            };
+            if decoder.set_limits(decode_limits()).is_err() {
+                return false;
+            }
+            decoder.into_frames().take(2).count() > 1
+        }
+        Some(ImageFormat::WebP) => webp_frame_count(data) > 1,
+        Some(ImageFormat::Png) => apng_frame_count(data) > 1,
+        _ => false,
+    }
+}
+
+/// Number of `ANMF` frames in a RIFF WebP container; 0 for a still image.
+fn webp_frame_count(data: &[u8]) -> usize {
+    let mut frames = 0;
+    // "RIFF", size, "WEBP", then chunks of fourcc + LE size + even-padded payload.
+    let mut offset = 12;
+    while let Some(header) = data.get(offset..offset + 8) {
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        if &header[..4] == b"ANMF" {
+            frames += 1;
+        }
+        offset = match offset
+            .checked_add(8 + size + (size & 1))
+            .filter(|next| *next <= data.len())
+        {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    frames
+}
+
+/// Frame count from a PNG's `acTL` chunk; 0 for a plain PNG. The chunk must
+/// come before the image data, as the APNG spec requires.
+fn apng_frame_count(data: &[u8]) -> usize {
+    // Signature, then chunks of BE length + type + data + CRC.
+    let mut offset = 8;
+    while let Some(header) = data.get(offset..offset + 8) {
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        match &header[4..8] {
+            b"acTL" => {
+                return data
+                    .get(offset + 8..offset + 12)
+                    .map(|n| u32::from_be_bytes([n[0], n[1], n[2], n[3]]) as usize)
+                    .unwrap_or(0);
+            }
+            b"IDAT" | b"IEND" => return 0,
+            _ => {}
+        }
+        offset = match offset.checked_add(12 + length) {
+            Some(next) => next,
+            None => return 0,
+        };
+    }
+    0
+}
+
+/// Re-encode an uploaded avatar or guild icon as center-cropped squares at
+/// every size in [`AVATAR_VARIANT_SIZES`].
+///
+/// An animated GIF stays animated only when `keep_animation` is set;
+/// otherwise, as for every other format, its first frame becomes a static
+/// PNG. Returns `None` for anything that is not a decodable PNG, GIF, JPEG
+/// or WebP image within the decode limits.
+pub fn process_avatar(data: &[u8], keep_animation: bool) -> Option<ProcessedAvatar> {
+    let reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?;
+    let format = reader.format()?;
+    if !matches!(
+        format,
+        ImageFormat::Png | ImageFormat::Gif | ImageFormat::Jpeg | ImageFormat::WebP
+    ) {
+        return None;
+    }
+    let (width, height) = image_dimensions(data)?;
+    if width > MAX_DECODE_DIMENSION || height > MAX_DECODE_DIMENSION {
+        return None;
+    }
+
+    if keep_animation && format == ImageFormat::Gif {
+        let frames = decode_gif_frames(data)?;
+        if frames.len() > 1 {
+            let mut variants = Vec::with_capacity(AVATAR_VARIANT_SIZES.len());
+            for size in AVATAR_VARIANT_SIZES {
+                let resized = frames.iter().map(|frame| {
+                    Frame::from_parts(fill_square(frame.buffer(), size), 0, 0, frame.delay())
+                });
+                let mut out = Vec::new();
+                {
+                    let mut encoder = GifEncoder::new(&mut out);
+                    encoder.set_repeat(Repeat::Infinite).ok()?;
+                    encoder.encode_frames(resized).ok()?;
+                }
+                variants.push((size, out));
+            }
+            return Some(ProcessedAvatar {
+                variants,
+                animated: true,
+            });
+        }
+    }
+
+    let image = decode_upright(reader)?.to_rgba8();
+    let mut variants = Vec::with_capacity(AVATAR_VARIANT_SIZES.len());
+    for size in AVATAR_VARIANT_SIZES {
+        let mut out = Cursor::new(Vec::new());
+        DynamicImage::ImageRgba8(fill_square(&image, size))
+            .write_to(&mut out, ImageFormat::Png)
+            .ok()?;
+        variants.push((size, out.into_inner()));
+    }
+    Some(ProcessedAvatar {
+        variants,
+        animated: false,
+    })
+}
+
+/// Re-encode an uploaded profile banner as a PNG no larger than
+/// [`BANNER_MAX_WIDTH`] x [`BANNER_MAX_HEIGHT`]. Animated GIFs keep only their
+/// first frame. Returns `None` for anything that is not a decodable PNG, GIF,
+/// JPEG or WebP image within the decode limits.
+pub fn process_banner(data: &[u8]) -> Option<Vec<u8>> {
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?;
+    let format = reader.format()?;
+    if !matches!(
+        format,
+        ImageFormat::Png | ImageFormat::Gif | ImageFormat::Jpeg | ImageFormat::WebP
+    ) {
+        return None;
+    }
+    let (width, height) = image_dimensions(data)?;
+    if width > MAX_DECODE_DIMENSION || height > MAX_DECODE_DIMENSION {
+        return None;
+    }
+
+    reader.limits(decode_limits());
+    let mut image = reader.decode().ok()?;
+    if width > BANNER_MAX_WIDTH || height > BANNER_MAX_HEIGHT {
+        image = image.resize(BANNER_MAX_WIDTH, BANNER_MAX_HEIGHT, FilterType::Triangle);
+    }
+    let mut out = Cursor::new(Vec::new());
+    DynamicImage::ImageRgba8(image.to_rgba8())
+        .write_to(&mut out, ImageFormat::Png)
+        .ok()?;
+    Some(out.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    fn encode(width: u32, height: u32, format: ImageFormat) -> Vec<u8> {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(width, height));
+        let image = if format == ImageFormat::Jpeg {
+            DynamicImage::ImageRgb8(image.to_rgb8())
+        } else {
+            image
+        };
+        let mut out = Cursor::new(Vec::new());
+        image.write_to(&mut out, format).unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn thumbnails_keep_aspect_ratio_and_format() {
+        let png = encode(1200, 600, ImageFormat::Png);
+        assert_eq!(image_dimensions(&png), Some((1200, 600)));
+        let thumb = generate_thumbnail(&png, 400).expect("thumbnail");
+        assert_eq!((thumb.width, thumb.height), (400, 200));
+        assert_eq!(image::guess_format(&thumb.data).unwrap(), ImageFormat::Png);
+
+        let jpeg = encode(800, 1600, ImageFormat::Jpeg);
+        let thumb = generate_thumbnail(&jpeg, 400).expect("thumbnail");
+        assert_eq!((thumb.width, thumb.height), (200, 400));
+        assert_eq!(image::guess_format(&thumb.data).unwrap(), ImageFormat::Jpeg);
+    }
+
+    #[test]
+    fn small_or_malformed_images_are_skipped() {
+        let small = encode(64, 64, ImageFormat::Png);
+        assert!(generate_thumbnail(&small, 400).is_none());
+        assert!(generate_thumbnail(b"definitely not an image", 400).is_none());
+        assert!(image_dimensions(b"definitely not an image").is_none());
+
+        let mut truncated = encode(1200, 600, ImageFormat::Png);
+        truncated.truncate(truncated.len() / 2);
+        assert!(generate_thumbnail(&truncated, 400).is_none());
+    }
+
+    fn encode_gif(width: u32, height: u32, frame_count: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut out);
+            let frames = (0..frame_count).map(|i| {
+                let shade = (i * 60) as u8;
+                let buffer = RgbaImage::from_pixel(width, height, image::Rgba([shade, 0, 0, 255]));
+                Frame::new(buffer)
+            });
+            encoder.encode_frames(frames).unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn emojis_are_normalized_and_animation_is_detected() {
+        let png = encode(300, 150, ImageFormat::Png);
+        let emoji = process_emoji(&png).expect("static emoji");
+        assert!(!emoji.animated);
+        assert_eq!(emoji.extension(), "png");
+        assert_eq!(
+            image_dimensions(&emoji.data),
+            Some((EMOJI_SIZE, EMOJI_SIZE))
+        );
+
+        // A GIF with a single frame is not animated and is stored as a PNG.
+        let still_gif = encode_gif(64, 64, 1);
+        let emoji = process_emoji(&still_gif).expect("single-frame gif");
+        assert!(!emoji.animated);
+        assert_eq!(image::guess_format(&emoji.data).unwrap(), ImageFormat::Png);
+
+        let animated_gif = encode_gif(256, 256, 3);
+        let emoji = process_emoji(&animated_gif).expect("animated gif");
+        assert!(emoji.animated);
+        assert_eq!(emoji.extension(), "gif");
+        assert_eq!(
+            image_dimensions(&emoji.data),
+            Some((EMOJI_SIZE, EMOJI_SIZE))
+        );
+        assert_eq!(decode_gif_frames(&emoji.data).map(|f| f.len()), Some(3));
+
+        assert!(process_emoji(b"definitely not an image").is_none());
+        assert_eq!(emoji_storage_key(42, true), "emojis/42.gif");
+    }
+
+    #[test]
+    fn banners_are_scaled_to_fit_and_stored_as_png() {
+        let wide = encode(3000, 600, ImageFormat::Jpeg);
+        let banner = process_banner(&wide).expect("banner");
+        assert_eq!(image::guess_format(&banner).unwrap(), ImageFormat::Png);
+        assert_eq!(image_dimensions(&banner), Some((1500, 300)));
+
+        let small = encode(600, 240, ImageFormat::Png);
+        let banner = process_banner(&small).expect("banner");
+        assert_eq!(image_dimensions(&banner), Some((600, 240)));
+
+        assert!(process_banner(b"definitely not an image").is_none());
+        assert_eq!(banner_storage_key(7, "abc"), "banners/7/abc.png");
+    }
+
+    #[test]
+    fn role_icons_are_static_squares() {
+        let tall = encode(100, 400, ImageFormat::Jpeg);
+        let icon = process_role_icon(&tall).expect("role icon");
+        assert_eq!(image::guess_format(&icon).unwrap(), ImageFormat::Png);
+        assert_eq!(
+            image_dimensions(&icon),
+            Some((ROLE_ICON_SIZE, ROLE_ICON_SIZE))
+        );
+
+        let animated = encode_gif(32, 32, 3);
+        let icon = process_role_icon(&animated).expect("gif role icon");
+        assert_eq!(image::guess_format(&icon).unwrap(), ImageFormat::Png);
+
+        assert!(process_role_icon(b"definitely not an image").is_none());
+        assert_eq!(role_icon_storage_key(9, "abc"), "role-icons/9/abc.png");
+    }
+
+    /// A JPEG carrying an EXIF segment with a recognizable payload.
+    fn jpeg_with_exif(width: u32, height: u32, payload: &[u8]) -> Vec<u8> {
+        let jpeg = encode(width, height, ImageFormat::Jpeg);
+        let mut segment = b"Exif\0\0".to_vec();
+        segment.extend_from_slice(payload);
+        let len = u16::try_from(segment.len() + 2).unwrap();
+        let mut out = jpeg[..2].to_vec();
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&len.to_be_bytes());
+        out.extend_from_slice(&segment);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    #[test]
+    fn avatars_become_square_variants_without_metadata() {
+        let marker = b"GPS 52.3676N 4.9041E";
+        let photo = jpeg_with_exif(300, 200, marker);
+        assert!(photo.windows(marker.len()).any(|w| w == marker));
+
+        let avatar = process_avatar(&photo, false).expect("avatar");
+        assert!(!avatar.animated);
+        let sizes: Vec<u32> = avatar.variants.iter().map(|(size, _)| *size).collect();
+        assert_eq!(sizes, AVATAR_VARIANT_SIZES);
+        for (size, data) in &avatar.variants {
+            assert_eq!(image::guess_format(data).unwrap(), ImageFormat::Png);
+            assert_eq!(image_dimensions(data), Some((*size, *size)));
+            assert!(!data.windows(marker.len()).any(|w| w == marker));
+        }
+        assert_eq!(avatar.largest(), avatar.variants[4].1.as_slice());
+
+        assert!(process_avatar(b"definitely not an image", false).is_none());
+        assert_eq!(avatar_variant_size(None), 256);
+        assert_eq!(avatar_variant_size(Some(40)), 64);
+        assert_eq!(avatar_variant_size(Some(4096)), 256);
+        assert_eq!(avatar_storage_key(7, "abc", 32), "avatars/7/abc_32.png");
+        assert_eq!(
+            guild_icon_storage_key(8, "a_abc", 16),
+            "guild-icons/8/a_abc_16.gif"
+        );
+    }
+
+    #[test]
+    fn animated_avatars_are_kept_only_when_allowed() {
+        let animated_gif = encode_gif(100, 60, 3);
+        assert!(is_animated(&animated_gif));
+        assert!(!is_animated(&encode_gif(100, 60, 1)));
+        assert!(!is_animated(&encode(100, 60, ImageFormat::Png)));
+
+        // An APNG announces its frames in an acTL chunk ahead of the image data.
+        let png = encode(10, 10, ImageFormat::Png);
+        let ihdr_end = 8 + 12 + 13;
+        let mut apng = png[..ihdr_end].to_vec();
+        apng.extend_from_slice(&8u32.to_be_bytes());
+        apng.extend_from_slice(b"acTL");
+        apng.extend_from_slice(&2u32.to_be_bytes());
+        apng.extend_from_slice(&0u32.to_be_bytes());
+        apng.extend_from_slice(&[0; 4]);
+        apng.extend_from_slice(&png[ihdr_end..]);
+        assert!(is_animated(&apng));
+
+        // An animated WebP holds one ANMF chunk per frame.
+        let webp = |frames: usize| {
+            let mut chunks = Vec::new();
+            chunks.extend_from_slice(b"VP8X");
+            chunks.extend_from_slice(&10u32.to_le_bytes());
+            chunks.extend_from_slice(&[0x02, 0, 0, 0, 9, 0, 0, 9, 0, 0]);
+            for _ in 0..frames {
+                chunks.extend_from_slice(b"ANMF");
+                chunks.extend_from_slice(&3u32.to_le_bytes());
+                chunks.extend_from_slice(&[0, 0, 0, 0]);
+            }
+            let mut data = b"RIFF".to_vec();
+            data.extend_from_slice(&(chunks.len() as u32 + 4).to_le_bytes());
+            data.extend_from_slice(b"WEBP");
+            data.extend_from_slice(&chunks);
+            data
+        };
+        assert!(is_animated(&webp(2)));
+        assert!(!is_animated(&webp(1)));
+        assert!(!is_animated(&encode(10, 10, ImageFormat::WebP)));
+
+        let flattened = process_avatar(&animated_gif, false).expect("still avatar");
+        assert!(!flattened.animated);
+        assert_eq!(
+            image::guess_format(flattened.largest()).unwrap(),
+            ImageFormat::Png
+        );
+
+        let animated = process_avatar(&animated_gif, true).expect("animated avatar");
+        assert!(animated.animated);
+        for (size, data) in &animated.variants {
+            assert_eq!(image_dimensions(data), Some((*size, *size)));
+            assert_eq!(decode_gif_frames(data).map(|f| f.len()), Some(3));
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod images;
 pub mod livekit;
 pub mod s3;
 pub mod storage;
@@ -8,7 +9,7 @@ pub use livekit::{AudioBitrate, LiveKitConfig, WebhookEvent};
 pub use s3::S3Config;
 pub use storage::{
     LocalStorage, P2PTransferRequest, Storage, StorageBackend, StorageConfig, StorageError,
-    StorageManager, StoredFile,
+    StorageManager, StorageReader, StoredFile,
 };
 pub use streaming::{
     ScreenCaptureConfig, SimulcastLayer, StreamConfig, StreamMetadata, StreamQualityPreset,
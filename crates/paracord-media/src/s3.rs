@@ -25,6 +25,11 @@ pub struct S3Config {
     /// Force path-style addressing (required for MinIO and some providers).
     #[serde(default)]
     pub force_path_style: bool,
+    /// Redirect attachment downloads to presigned URLs instead of proxying the
+    /// bytes through the server. Has no effect while at-rest file encryption
+    /// is enabled, since the bucket only holds ciphertext.
+    #[serde(default)]
+    pub redirect_downloads: bool,
 }
 
 fn default_region() -> String {
@@ -47,6 +52,7 @@ impl Default for S3Config {
             cdn_url: None,
             presign_expiry_seconds: default_presign_expiry(),
             force_path_style: false,
+            redirect_downloads: false,
         }
     }
 }
@@ -54,7 +60,7 @@ impl Default for S3Config {
 #[cfg(feature = "s3")]
 mod inner {
     use super::*;
-    use crate::storage::{StorageBackend, StorageError};
+    use crate::storage::{StorageBackend, StorageError, StorageReader};
     use aws_config::BehaviorVersion;
     use aws_sdk_s3::config::{Credentials, Region};
     use aws_sdk_s3::presigning::PresigningConfig;
@@ -69,6 +75,7 @@ mod inner {
         prefix: String,
         cdn_url: Option<String>,
         presign_expiry: Duration,
+        redirect_downloads: bool,
     }
 
     impl S3Storage {
@@ -116,6 +123,7 @@ mod inner {
                 prefix: config.prefix.clone(),
                 cdn_url: config.cdn_url.clone(),
                 presign_expiry: Duration::from_secs(config.presign_expiry_seconds),
+                redirect_downloads: config.redirect_downloads,
             })
         }
 
@@ -149,6 +157,33 @@ mod inner {
             Ok(key.to_string())
         }
 
+        async fn store_file(
+            &self,
+            key: &str,
+            path: &std::path::Path,
+        ) -> Result<String, StorageError> {
+            let full_key = self.full_key(key);
+            let content_type = mime_guess::from_path(key)
+                .first_raw()
+                .unwrap_or("application/octet-stream");
+            let body = aws_sdk_s3::primitives::ByteStream::from_path(path)
+                .await
+                .map_err(|e| StorageError::Backend(format!("S3 read file failed: {}", e)))?;
+
+            self.client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(&full_key)
+                .body(body)
+                .content_type(content_type)
+                .send()
+                .await
+                .map_err(|e| StorageError::Backend(format!("S3 PutObject failed: {}", e)))?;
+
+            tracing::debug!("S3: stored object {}", full_key);
+            Ok(key.to_string())
+        }
+
         async fn retrieve(&self, key: &str) -> Result<Vec<u8>, StorageError> {
             let full_key = self.full_key(key);
 
@@ -178,6 +213,60 @@ mod inner {
             Ok(bytes.to_vec())
         }
 
+        async fn size(&self, key: &str) -> Result<u64, StorageError> {
+            let full_key = self.full_key(key);
+
+            let resp = self
+                .client
+                .head_object()
+                .bucket(&self.bucket)
+                .key(&full_key)
+                .send()
+                .await
+                .map_err(|e| {
+                    let msg = format!("{}", e);
+                    if msg.contains("NotFound") || msg.contains("404") || msg.contains("NoSuchKey")
+                    {
+                        StorageError::NotFound(key.to_string())
+                    } else {
+                        StorageError::Backend(format!("S3 HeadObject failed: {}", e))
+                    }
+                })?;
+
+            Ok(resp.content_length().unwrap_or(0).max(0) as u64)
+        }
+
+        async fn open_range(
+            &self,
+            key: &str,
+            offset: u64,
+            len: u64,
+        ) -> Result<StorageReader, StorageError> {
+            let full_key = self.full_key(key);
+            if len == 0 {
+                return Ok(Box::pin(tokio::io::empty()));
+            }
+
+            let resp = self
+                .client
+                .get_object()
+                .bucket(&self.bucket)
+                .key(&full_key)
+                .range(format!("bytes={}-{}", offset, offset + len - 1))
+                .send()
+                .await
+                .map_err(|e| {
+                    let msg = format!("{}", e);
+                    if msg.contains("NoSuchKey") || msg.contains("404") {
+                        StorageError::NotFound(key.to_string())
+                    } else {
+                        StorageError::Backend(format!("S3 GetObject failed: {}", e))
+                    }
+                })?;
+
+            Ok(Box::pin(resp.body.into_async_read()))
+        }
+
         async fn delete(&self, key: &str) -> Result<(), StorageError> {
             let full_key = self.full_key(key);
 
@@ -220,6 +309,39 @@ mod inner {
             }
         }
 
+        async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+            let mut keys = Vec::new();
+            let mut continuation: Option<String> = None;
+            loop {
+                let resp = self
+                    .client
+                    .list_objects_v2()
+                    .bucket(&self.bucket)
+                    .prefix(self.full_key(prefix))
+                    .set_continuation_token(continuation.take())
+                    .send()
+                    .await
+                    .map_err(|e| {
+                        StorageError::Backend(format!("S3 ListObjectsV2 failed: {}", e))
+                    })?;
+
+                for object in resp.contents() {
+                    if let Some(full_key) = object.key() {
+                        let key = full_key.strip_prefix(&self.prefix).unwrap_or(full_key);
+                        keys.push(key.to_string());
+                    }
+                }
+
+                match resp.next_continuation_token() {
+                    Some(token) if resp.is_truncated().unwrap_or(false) => {
+                        continuation = Some(token.to_string());
+                    }
+                    _ => break,
+                }
+            }
+            Ok(keys)
+        }
+
         async fn get_url(&self, key: &str) -> Result<String, StorageError> {
             let full_key = self.full_key(key);
 
@@ -244,6 +366,35 @@ mod inner {
 
             Ok(presigned.uri().to_string())
         }
+
+        async fn download_url(
+            &self,
+            key: &str,
+            content_type: &str,
+            content_disposition: &str,
+        ) -> Result<Option<String>, StorageError> {
+            if !self.redirect_downloads {
+                return Ok(None);
+            }
+            // Always presign, even with a CDN configured: the response header
+            // overrides keep the download safe regardless of what the object
+            // was stored with.
+            let presign_config = PresigningConfig::expires_in(self.presign_expiry)
+                .map_err(|e| StorageError::Backend(format!("presign config error: {}", e)))?;
+
+            let presigned = self
+                .client
+                .get_object()
+                .bucket(&self.bucket)
+                .key(self.full_key(key))
+                .response_content_type(content_type)
+                .response_content_disposition(content_disposition)
+                .presigned(presign_config)
+                .await
+                .map_err(|e| StorageError::Backend(format!("S3 presigned URL failed: {}", e)))?;
+
+            Ok(Some(presigned.uri().to_string()))
+        }
     }
 }
 
@@ -1,7 +1,8 @@
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use thiserror::Error;
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use uuid::Uuid;
 
 #[derive(Debug, Error)]
@@ -16,6 +17,9 @@ pub enum StorageError {
     Backend(String),
 }
 
+/// Streaming reader over a stored object, or a byte range of one.
+pub type StorageReader = Pin<Box<dyn AsyncRead + Send>>;
+
 /// Unified storage backend trait for file persistence.
 ///
 /// Implementations exist for local filesystem and S3-compatible object stores.
@@ -25,20 +29,49 @@ pub trait StorageBackend: Send + Sync {
     /// Store `data` under `key`, returning the canonical key.
     async fn store(&self, key: &str, data: &[u8]) -> Result<String, StorageError>;
 
+    /// Store the contents of the local file at `path` under `key` without
+    /// reading it into memory, returning the canonical key.
+    async fn store_file(&self, key: &str, path: &Path) -> Result<String, StorageError>;
+
     /// Retrieve the raw bytes for `key`.
     async fn retrieve(&self, key: &str) -> Result<Vec<u8>, StorageError>;
 
+    /// Size in bytes of the object at `key`.
+    async fn size(&self, key: &str) -> Result<u64, StorageError>;
+
+    /// Stream `len` bytes of the object at `key`, starting at `offset`.
+    async fn open_range(
+        &self,
+        key: &str,
+        offset: u64,
+        len: u64,
+    ) -> Result<StorageReader, StorageError>;
+
     /// Delete the object at `key`. No-op if it does not exist.
     async fn delete(&self, key: &str) -> Result<(), StorageError>;
 
     /// Check whether `key` exists in the store.
     async fn exists(&self, key: &str) -> Result<bool, StorageError>;
 
+    /// List every key that starts with `prefix` (e.g. `attachments/`).
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError>;
+
     /// Return a URL suitable for the client to fetch this object.
     ///
     /// For local storage this returns the API download path (e.g. `/api/v1/attachments/123`).
     /// For S3 storage this can return a presigned URL.
     async fn get_url(&self, key: &str) -> Result<String, StorageError>;
+
+    /// A short-lived URL the client can be redirected to for downloading
+    /// `key` directly, served with the given response headers. `None` means
+    /// the backend does not support (or is not configured for) redirects and
+    /// the server should proxy the bytes itself.
+    async fn download_url(
+        &self,
+        key: &str,
+        content_type: &str,
+        content_disposition: &str,
+    ) -> Result<Option<String>, StorageError>;
 }
 
 /// Enum-dispatch wrapper that implements `StorageBackend` and is `Clone + Send + Sync`.
@@ -60,6 +93,14 @@ impl Storage {
         }
     }
 
+    pub async fn store_file(&self, key: &str, path: &Path) -> Result<String, StorageError> {
+        match self {
+            Storage::Local(s) => s.store_file(key, path).await,
+            #[cfg(feature = "s3")]
+            Storage::S3(s) => s.store_file(key, path).await,
+        }
+    }
+
     pub async fn retrieve(&self, key: &str) -> Result<Vec<u8>, StorageError> {
         match self {
             Storage::Local(s) => s.retrieve(key).await,
@@ -68,6 +109,27 @@ impl Storage {
         }
     }
 
+    pub async fn size(&self, key: &str) -> Result<u64, StorageError> {
+        match self {
+            Storage::Local(s) => s.size(key).await,
+            #[cfg(feature = "s3")]
+            Storage::S3(s) => s.size(key).await,
+        }
+    }
+
+    pub async fn open_range(
+        &self,
+        key: &str,
+        offset: u64,
+        len: u64,
+    ) -> Result<StorageReader, StorageError> {
+        match self {
+            Storage::Local(s) => s.open_range(key, offset, len).await,
+            #[cfg(feature = "s3")]
+            Storage::S3(s) => s.open_range(key, offset, len).await,
+        }
+    }
+
     pub async fn delete(&self, key: &str) -> Result<(), StorageError> {
         match self {
             Storage::Local(s) => s.delete(key).await,
@@ -84,6 +146,14 @@ impl Storage {
         }
     }
 
+    pub async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        match self {
+            Storage::Local(s) => s.list(prefix).await,
+            #[cfg(feature = "s3")]
+            Storage::S3(s) => s.list(prefix).await,
+        }
+    }
+
     pub async fn get_url(&self, key: &str) -> Result<String, StorageError> {
         match self {
             Storage::Local(s) => s.get_url(key).await,
@@ -91,6 +161,19 @@ impl Storage {
             Storage::S3(s) => s.get_url(key).await,
         }
     }
+
+    pub async fn download_url(
+        &self,
+        key: &str,
+        content_type: &str,
+        content_disposition: &str,
+    ) -> Result<Option<String>, StorageError> {
+        match self {
+            Storage::Local(s) => s.download_url(key, content_type, content_disposition).await,
+            #[cfg(feature = "s3")]
+            Storage::S3(s) => s.download_url(key, content_type, content_disposition).await,
+        }
+    }
 }
 
 // ── Local filesystem backend ─────────────────────────────────────────────────
@@ -118,6 +201,15 @@ impl StorageBackend for LocalStorage {
         Ok(key.to_string())
     }
 
+    async fn store_file(&self, key: &str, path: &Path) -> Result<String, StorageError> {
+        let dest = self.base_path.join(key);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::copy(path, &dest).await?;
+        Ok(key.to_string())
+    }
+
     async fn retrieve(&self, key: &str) -> Result<Vec<u8>, StorageError> {
         let path = self.base_path.join(key);
         if !Path::new(&path).exists() {
@@ -126,6 +218,35 @@ impl StorageBackend for LocalStorage {
         Ok(fs::read(&path).await?)
     }
 
+    async fn size(&self, key: &str) -> Result<u64, StorageError> {
+        let path = self.base_path.join(key);
+        match fs::metadata(&path).await {
+            Ok(meta) => Ok(meta.len()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(StorageError::NotFound(key.to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn open_range(
+        &self,
+        key: &str,
+        offset: u64,
+        len: u64,
+    ) -> Result<StorageReader, StorageError> {
+        let path = self.base_path.join(key);
+        let mut file = match fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(StorageError::NotFound(key.to_string()));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        file.seek(std::io::SeekFrom::Start(offset)).await?;
+        Ok(Box::pin(file.take(len)))
+    }
+
     async fn delete(&self, key: &str) -> Result<(), StorageError> {
         let path = self.base_path.join(key);
         if path.exists() {
@@ -139,6 +260,33 @@ impl StorageBackend for LocalStorage {
         Ok(path.exists())
     }
 
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        // Keys map onto a directory plus a file-name prefix; only that one
+        // directory level is listed, matching how keys are laid out.
+        let (dir, name_prefix) = match prefix.rfind('/') {
+            Some(idx) => (&prefix[..=idx], &prefix[idx + 1..]),
+            None => ("", prefix),
+        };
+        let mut entries = match fs::read_dir(self.base_path.join(dir)).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut keys = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_type().await?.is_file() {
+                continue;
+            }
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            if name.starts_with(name_prefix) {
+                keys.push(format!("{}{}", dir, name));
+            }
+        }
+        Ok(keys)
+    }
+
     async fn get_url(&self, key: &str) -> Result<String, StorageError> {
         // Extract the attachment ID from the key for the API path.
         // Keys are formatted as `attachments/{id}.{ext}`.
@@ -148,6 +296,15 @@ impl StorageBackend for LocalStorage {
             .unwrap_or(key);
         Ok(format!("/api/v1/attachments/{}", stem))
     }
+
+    async fn download_url(
+        &self,
+        _key: &str,
+        _content_type: &str,
+        _content_disposition: &str,
+    ) -> Result<Option<String>, StorageError> {
+        Ok(None)
+    }
 }
 
 // --- File sharing storage ---
@@ -1,6 +1,107 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// Action recorded in an audit log entry. Stored as an `i16` code; codes
+/// this build does not know about decode to `Unknown` so older or newer rows
+/// still read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AuditAction {
+    GuildUpdate,
+    ChannelCreate,
+    ChannelUpdate,
+    ChannelDelete,
+    ChannelOverwriteCreate,
+    ChannelOverwriteUpdate,
+    ChannelOverwriteDelete,
+    MemberUpdate,
+    MemberKick,
+    MemberBanAdd,
+    MemberBanRemove,
+    RoleCreate,
+    RoleUpdate,
+    RoleDelete,
+    InviteCreate,
+    InviteDelete,
+    MessageBulkDelete,
+    Unknown(i16),
+}
+
+impl AuditAction {
+    /// Stable snake_case name used in API responses.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::GuildUpdate => "guild_update",
+            Self::ChannelCreate => "channel_create",
+            Self::ChannelUpdate => "channel_update",
+            Self::ChannelDelete => "channel_delete",
+            Self::ChannelOverwriteCreate => "channel_overwrite_create",
+            Self::ChannelOverwriteUpdate => "channel_overwrite_update",
+            Self::ChannelOverwriteDelete => "channel_overwrite_delete",
+            Self::MemberUpdate => "member_update",
+            Self::MemberKick => "member_kick",
+            Self::MemberBanAdd => "member_ban_add",
+            Self::MemberBanRemove => "member_ban_remove",
+            Self::RoleCreate => "role_create",
+            Self::RoleUpdate => "role_update",
+            Self::RoleDelete => "role_delete",
+            Self::InviteCreate => "invite_create",
+            Self::InviteDelete => "invite_delete",
+            Self::MessageBulkDelete => "message_bulk_delete",
+            Self::Unknown(_) => "unknown",
+        }
+    }
+}
+
+impl From<i16> for AuditAction {
+    fn from(code: i16) -> Self {
+        match code {
+            1 => Self::GuildUpdate,
+            10 => Self::ChannelCreate,
+            11 => Self::ChannelUpdate,
+            12 => Self::ChannelDelete,
+            13 => Self::ChannelOverwriteCreate,
+            14 => Self::ChannelOverwriteUpdate,
+            15 => Self::ChannelOverwriteDelete,
+            20 => Self::MemberUpdate,
+            21 => Self::MemberKick,
+            22 => Self::MemberBanAdd,
+            23 => Self::MemberBanRemove,
+            30 => Self::RoleCreate,
+            31 => Self::RoleUpdate,
+            32 => Self::RoleDelete,
+            40 => Self::InviteCreate,
+            41 => Self::InviteDelete,
+            73 => Self::MessageBulkDelete,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+impl From<AuditAction> for i16 {
+    fn from(action: AuditAction) -> Self {
+        match action {
+            AuditAction::GuildUpdate => 1,
+            AuditAction::ChannelCreate => 10,
+            AuditAction::ChannelUpdate => 11,
+            AuditAction::ChannelDelete => 12,
+            AuditAction::ChannelOverwriteCreate => 13,
+            AuditAction::ChannelOverwriteUpdate => 14,
+            AuditAction::ChannelOverwriteDelete => 15,
+            AuditAction::MemberUpdate => 20,
+            AuditAction::MemberKick => 21,
+            AuditAction::MemberBanAdd => 22,
+            AuditAction::MemberBanRemove => 23,
+            AuditAction::RoleCreate => 30,
+            AuditAction::RoleUpdate => 31,
+            AuditAction::RoleDelete => 32,
+            AuditAction::InviteCreate => 40,
+            AuditAction::InviteDelete => 41,
+            AuditAction::MessageBulkDelete => 73,
+            AuditAction::Unknown(code) => code,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditLogEntry {
     pub id: i64,
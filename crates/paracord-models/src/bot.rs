@@ -22,3 +22,99 @@ pub struct BotGuildInstall {
     pub permissions: i64,
     pub created_at: DateTime<Utc>,
 }
+
+/// Value type of a slash-command option. Serialized as its numeric code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "u8", into = "u8")]
+pub enum CommandOptionType {
+    String = 3,
+    Integer = 4,
+    Boolean = 5,
+    User = 6,
+    Channel = 7,
+    Role = 8,
+    Number = 10,
+}
+
+impl TryFrom<u8> for CommandOptionType {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            3 => Ok(Self::String),
+            4 => Ok(Self::Integer),
+            5 => Ok(Self::Boolean),
+            6 => Ok(Self::User),
+            7 => Ok(Self::Channel),
+            8 => Ok(Self::Role),
+            10 => Ok(Self::Number),
+            other => Err(format!("unknown command option type {other}")),
+        }
+    }
+}
+
+impl From<CommandOptionType> for u8 {
+    fn from(value: CommandOptionType) -> Self {
+        value as u8
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandOptionChoice {
+    pub name: String,
+    pub value: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandOption {
+    #[serde(rename = "type")]
+    pub option_type: CommandOptionType,
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub required: bool,
+    /// When non-empty, the only values the option accepts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<CommandOptionChoice>,
+}
+
+/// A slash command as registered by a bot application.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplicationCommandDefinition {
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub options: Vec<CommandOption>,
+}
+
+/// A value supplied for one option when a member invokes a command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandOptionValue {
+    pub name: String,
+    pub value: serde_json::Value,
+}
+
+/// An option value after it has been checked against the command definition,
+/// as delivered to the bot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InteractionOption {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub option_type: CommandOptionType,
+    pub value: serde_json::Value,
+}
+
+/// A bot's reply to an interaction, either returned from its interactions
+/// endpoint or posted to the interaction callback.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionResponse {
+    #[serde(rename = "type")]
+    pub response_type: u8,
+    #[serde(default)]
+    pub data: Option<InteractionResponseData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionResponseData {
+    pub content: String,
+}
@@ -14,6 +14,54 @@ pub enum ChannelType {
     Forum = 7,
 }
 
+/// Bitrate given to voice channels created without one.
+pub const DEFAULT_VOICE_BITRATE: i32 = 64_000;
+pub const MIN_VOICE_BITRATE: i32 = 8_000;
+pub const MAX_VOICE_BITRATE: i32 = 384_000;
+/// Highest voice `user_limit`; 0 means unlimited.
+pub const MAX_VOICE_USER_LIMIT: i32 = 99;
+/// Longest per-channel message retention, in days.
+pub const MAX_MESSAGE_RETENTION_DAYS: i32 = 3650;
+
+impl ChannelType {
+    /// Types that can be created directly in a space. DMs and threads have
+    /// their own creation paths.
+    pub fn is_space_channel(self) -> bool {
+        matches!(
+            self,
+            Self::Text | Self::Voice | Self::Category | Self::Announcement | Self::Forum
+        )
+    }
+
+    pub fn is_voice(self) -> bool {
+        self == Self::Voice
+    }
+}
+
+impl TryFrom<i16> for ChannelType {
+    type Error = i16;
+
+    fn try_from(code: i16) -> Result<Self, Self::Error> {
+        match code {
+            0 => Ok(Self::Text),
+            1 => Ok(Self::DM),
+            2 => Ok(Self::Voice),
+            3 => Ok(Self::GroupDM),
+            4 => Ok(Self::Category),
+            5 => Ok(Self::Announcement),
+            6 => Ok(Self::Thread),
+            7 => Ok(Self::Forum),
+            other => Err(other),
+        }
+    }
+}
+
+impl From<ChannelType> for i16 {
+    fn from(channel_type: ChannelType) -> Self {
+        channel_type as i16
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThreadMetadata {
     pub archived: bool,
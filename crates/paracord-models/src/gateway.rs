@@ -65,6 +65,7 @@ pub const EVENT_MESSAGE_CREATE: &str = "MESSAGE_CREATE";
 pub const EVENT_MESSAGE_UPDATE: &str = "MESSAGE_UPDATE";
 pub const EVENT_MESSAGE_DELETE: &str = "MESSAGE_DELETE";
 pub const EVENT_MESSAGE_DELETE_BULK: &str = "MESSAGE_DELETE_BULK";
+pub const EVENT_MESSAGE_EMBEDS_UPDATE: &str = "MESSAGE_EMBEDS_UPDATE";
 pub const EVENT_MESSAGE_REACTION_ADD: &str = "MESSAGE_REACTION_ADD";
 pub const EVENT_MESSAGE_REACTION_REMOVE: &str = "MESSAGE_REACTION_REMOVE";
 pub const EVENT_MESSAGE_REACTION_REMOVE_ALL: &str = "MESSAGE_REACTION_REMOVE_ALL";
@@ -85,6 +86,9 @@ pub const EVENT_INVITE_DELETE: &str = "INVITE_DELETE";
 pub const EVENT_RELATIONSHIP_ADD: &str = "RELATIONSHIP_ADD";
 pub const EVENT_RELATIONSHIP_REMOVE: &str = "RELATIONSHIP_REMOVE";
 
+// Interaction events (delivered only to the bot that owns the command)
+pub const EVENT_INTERACTION_CREATE: &str = "INTERACTION_CREATE";
+
 // Media events
 pub const EVENT_MEDIA_SESSION_DESC: &str = "MEDIA_SESSION_DESC";
 pub const EVENT_MEDIA_KEY_DELIVER: &str = "MEDIA_KEY_DELIVER";
@@ -16,8 +16,13 @@ pub enum MessageType {
     PinnedMessage = 6,
     GuildMemberJoin = 7,
     SystemMessage = 8,
+    GuildMemberLeave = 9,
+    GuildMemberKick = 10,
+    GuildMemberBan = 11,
     Reply = 19,
     Poll = 20,
+    /// A bot's reply to a slash command.
+    ChatInputCommand = 21,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -120,7 +120,7 @@ async fn handle_welcome_bot(
         .unwrap_or("User");
     let content = template.replace("{user}", username);
 
-    let msg_id = paracord_util::snowflake::generate(1);
+    let msg_id = paracord_util::snowflake::generate();
 
     if let Ok(msg) = paracord_db::messages::create_message(
         &state.db,
@@ -225,7 +225,7 @@ async fn handle_auto_mod(
                     Some(guild_id),
                 );
 
-                let warning_id = paracord_util::snowflake::generate(1);
+                let warning_id = paracord_util::snowflake::generate();
                 let warning_content =
                     format!("A message was removed for containing restricted words.");
                 if let Ok(warning_msg) = paracord_db::messages::create_message(
@@ -55,11 +55,27 @@ pub struct Config {
     #[serde(default)]
     pub tls: TlsConfig,
     #[serde(default)]
+    pub messages: MessagesConfig,
+    #[serde(default)]
+    pub emojis: EmojiConfig,
+    #[serde(default)]
     pub retention: RetentionConfig,
     #[serde(default)]
+    pub attachment_cleanup: AttachmentCleanupConfig,
+    #[serde(default)]
     pub at_rest: AtRestConfig,
     #[serde(default)]
     pub backup: BackupConfig,
+    #[serde(default)]
+    pub rate_limits: RateLimitConfig,
+    #[serde(default)]
+    pub presence: PresenceConfig,
+    #[serde(default)]
+    pub email: EmailConfig,
+    #[serde(default)]
+    pub push: PushConfig,
+    #[serde(default)]
+    pub link_previews: LinkPreviewConfig,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -72,6 +88,15 @@ pub struct ServerConfig {
     /// Public URL of this server (e.g., https://chat.example.com).
     /// Used for CORS auto-configuration and invite links.
     pub public_url: Option<String>,
+    /// Snowflake worker id (0-1023). Every node writing to the same database
+    /// needs a distinct value.
+    #[serde(default = "default_worker_id")]
+    pub worker_id: u16,
+    /// Reverse proxies (addresses or CIDR ranges) whose `X-Forwarded-For`
+    /// is believed. Empty means clients are identified by their socket
+    /// address and forwarded headers are ignored.
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
 }
 
 impl Default for ServerConfig {
@@ -81,6 +106,8 @@ impl Default for ServerConfig {
             server_name: default_server_name(),
             web_dir: None,
             public_url: None,
+            worker_id: default_worker_id(),
+            trusted_proxies: Vec::new(),
         }
     }
 }
@@ -92,6 +119,33 @@ pub struct DatabaseConfig {
     pub url: String,
     #[serde(default = "default_max_connections")]
     pub max_connections: u32,
+    /// Connections kept open even when idle.
+    #[serde(default)]
+    pub min_connections: u32,
+    /// Seconds a request waits for a free pool connection before failing.
+    #[serde(default = "default_db_acquire_timeout_secs")]
+    pub acquire_timeout_secs: u64,
+    /// Close pool connections idle for this many seconds (0 = never).
+    #[serde(default = "default_db_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+    /// Recycle pool connections after this many seconds (0 = never).
+    #[serde(default = "default_db_max_lifetime_secs")]
+    pub max_lifetime_secs: u64,
+    /// How often pool utilization is logged, in seconds (0 = disabled).
+    #[serde(default = "default_db_pool_stats_interval_secs")]
+    pub pool_stats_interval_secs: u64,
+    /// SQLite `journal_mode`. WAL lets reads proceed during writes.
+    #[serde(default = "default_sqlite_journal_mode")]
+    pub sqlite_journal_mode: String,
+    /// Milliseconds SQLite waits on a locked database before failing.
+    #[serde(default = "default_sqlite_busy_timeout_ms")]
+    pub sqlite_busy_timeout_ms: u64,
+    /// SQLite `synchronous` level.
+    #[serde(default = "default_sqlite_synchronous")]
+    pub sqlite_synchronous: String,
+    /// Enforce foreign key constraints on SQLite.
+    #[serde(default = "default_true")]
+    pub sqlite_foreign_keys: bool,
     /// Statement timeout in seconds for PostgreSQL connections (0 = disabled).
     #[serde(default)]
     pub statement_timeout_secs: u64,
@@ -119,6 +173,15 @@ impl Default for DatabaseConfig {
             engine: default_database_engine(),
             url: "sqlite://./data/paracord.db?mode=rwc".into(),
             max_connections: default_max_connections(),
+            min_connections: 0,
+            acquire_timeout_secs: default_db_acquire_timeout_secs(),
+            idle_timeout_secs: default_db_idle_timeout_secs(),
+            max_lifetime_secs: default_db_max_lifetime_secs(),
+            pool_stats_interval_secs: default_db_pool_stats_interval_secs(),
+            sqlite_journal_mode: default_sqlite_journal_mode(),
+            sqlite_busy_timeout_ms: default_sqlite_busy_timeout_ms(),
+            sqlite_synchronous: default_sqlite_synchronous(),
+            sqlite_foreign_keys: true,
             statement_timeout_secs: 0,
             idle_in_transaction_timeout_secs: 0,
         }
@@ -136,6 +199,47 @@ pub struct AuthConfig {
     pub allow_username_login: bool,
     #[serde(default = "default_false")]
     pub require_email: bool,
+    /// Key id of the JWT key that signs new access tokens. Unset means
+    /// `jwt_secret` (key id `default`).
+    #[serde(default)]
+    pub jwt_signing_kid: Option<String>,
+    /// Extra JWT keys accepted for verification, selected by a token's `kid`.
+    #[serde(default)]
+    pub jwt_keys: Vec<JwtKeyConfig>,
+    /// Keep accepting tokens signed with `jwt_secret`. Turn off once a
+    /// rotation away from it has outlived the token TTL; `jwt_secret` still
+    /// seeds the server's other derived keys.
+    #[serde(default = "default_true")]
+    pub accept_default_jwt_key: bool,
+    /// Argon2id memory cost for new password hashes, in KiB. Stored hashes
+    /// with weaker settings are upgraded on the user's next login.
+    #[serde(default = "default_argon2_memory_kib")]
+    pub argon2_memory_kib: u32,
+    /// Argon2id passes over memory.
+    #[serde(default = "default_argon2_iterations")]
+    pub argon2_iterations: u32,
+    /// Argon2id lanes.
+    #[serde(default = "default_argon2_parallelism")]
+    pub argon2_parallelism: u32,
+    /// Minimum length of new passwords, in characters. Values below
+    /// `PASSWORD_MIN_LENGTH` (10) are raised to it.
+    #[serde(default = "default_password_min_length")]
+    pub password_min_length: usize,
+    #[serde(default = "default_false")]
+    pub password_require_lowercase: bool,
+    #[serde(default = "default_false")]
+    pub password_require_uppercase: bool,
+    #[serde(default = "default_false")]
+    pub password_require_digit: bool,
+    #[serde(default = "default_false")]
+    pub password_require_symbol: bool,
+    /// Reject new passwords found in the Pwned Passwords breach corpus. Off
+    /// by default, since it sends a 5-character SHA-1 prefix of every new
+    /// password to a third party; lookups fail open.
+    #[serde(default = "default_false")]
+    pub check_breached_passwords: bool,
+    #[serde(default = "default_breached_password_api_url")]
+    pub breached_password_api_url: String,
 }
 
 impl Default for AuthConfig {
@@ -146,10 +250,80 @@ impl Default for AuthConfig {
             registration_enabled: true,
             allow_username_login: true,
             require_email: false,
+            jwt_signing_kid: None,
+            jwt_keys: Vec::new(),
+            accept_default_jwt_key: true,
+            argon2_memory_kib: default_argon2_memory_kib(),
+            argon2_iterations: default_argon2_iterations(),
+            argon2_parallelism: default_argon2_parallelism(),
+            password_min_length: default_password_min_length(),
+            password_require_lowercase: false,
+            password_require_uppercase: false,
+            password_require_digit: false,
+            password_require_symbol: false,
+            check_breached_passwords: false,
+            breached_password_api_url: default_breached_password_api_url(),
         }
     }
 }
 
+#[derive(Clone, Deserialize, Serialize)]
+pub struct JwtKeyConfig {
+    pub kid: String,
+    pub secret: String,
+}
+
+impl std::fmt::Debug for JwtKeyConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JwtKeyConfig")
+            .field("kid", &self.kid)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AuthConfig {
+    pub fn password_policy(&self) -> paracord_util::validation::PasswordPolicy {
+        paracord_util::validation::PasswordPolicy {
+            min_length: self.password_min_length.clamp(
+                paracord_util::validation::PASSWORD_MIN_LENGTH,
+                paracord_util::validation::PASSWORD_MAX_LENGTH,
+            ),
+            require_lowercase: self.password_require_lowercase,
+            require_uppercase: self.password_require_uppercase,
+            require_digit: self.password_require_digit,
+            require_symbol: self.password_require_symbol,
+        }
+    }
+
+    /// The breach-check API URL, or `None` when the check is disabled.
+    pub fn breached_password_api_url(&self) -> Option<String> {
+        let url = self.breached_password_api_url.trim();
+        (self.check_breached_passwords && !url.is_empty()).then(|| url.to_string())
+    }
+
+    /// Build the JWT key set from `jwt_secret` (as `default`) and `jwt_keys`.
+    pub fn jwt_key_set(&self) -> Result<paracord_core::auth::JwtKeySet> {
+        use paracord_core::auth::{JwtKey, JwtKeySet};
+
+        let mut keys = Vec::with_capacity(self.jwt_keys.len() + 1);
+        if self.accept_default_jwt_key {
+            keys.push(JwtKey::new(JwtKeySet::DEFAULT_KID, self.jwt_secret.trim()));
+        }
+        keys.extend(
+            self.jwt_keys
+                .iter()
+                .map(|key| JwtKey::new(key.kid.trim(), key.secret.trim())),
+        );
+        let signing_kid = self
+            .jwt_signing_kid
+            .as_deref()
+            .map(str::trim)
+            .unwrap_or(JwtKeySet::DEFAULT_KID);
+        JwtKeySet::new(signing_kid, keys)
+            .map_err(|e| anyhow::anyhow!("Invalid [auth] JWT keys: {}", e))
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct StorageConfig {
     #[serde(default = "default_storage_type")]
@@ -160,6 +334,11 @@ pub struct StorageConfig {
     pub max_upload_size: u64,
     #[serde(default = "default_max_guild_storage_quota")]
     pub max_guild_storage_quota: u64,
+    /// Content types browsers may render inline. Uploads are labelled from
+    /// their sniffed bytes, and anything outside this list is downloaded as
+    /// an attachment.
+    #[serde(default = "default_inline_content_types")]
+    pub inline_content_types: Vec<String>,
 }
 
 impl Default for StorageConfig {
@@ -169,6 +348,7 @@ impl Default for StorageConfig {
             path: default_storage_path(),
             max_upload_size: default_max_upload_size(),
             max_guild_storage_quota: default_max_guild_storage_quota(),
+            inline_content_types: default_inline_content_types(),
         }
     }
 }
@@ -241,6 +421,49 @@ pub struct LiveKitConfig {
     /// Public LiveKit URL sent to clients (e.g., wss://chat.example.com/livekit).
     /// Falls back to `url` if not set.
     pub public_url: Option<String>,
+    /// Verbosity of the managed LiveKit process. Its output is forwarded to
+    /// the server log under the `livekit` target.
+    #[serde(default)]
+    pub log_level: LiveKitLogLevel,
+}
+
+/// How much of the managed LiveKit server's output to forward.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveKitLogLevel {
+    /// Discard LiveKit output entirely.
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    /// Includes per-participant ICE/TURN negotiation details.
+    Debug,
+}
+
+impl LiveKitLogLevel {
+    /// Parse a level name as accepted in the config file.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "off" => Some(Self::Off),
+            "error" => Some(Self::Error),
+            "warn" => Some(Self::Warn),
+            "info" => Some(Self::Info),
+            "debug" => Some(Self::Debug),
+            _ => None,
+        }
+    }
+
+    /// Most verbose level forwarded, or `None` when output is discarded.
+    pub fn max_level(self) -> Option<tracing::Level> {
+        match self {
+            Self::Off => None,
+            Self::Error => Some(tracing::Level::ERROR),
+            Self::Warn => Some(tracing::Level::WARN),
+            Self::Info => Some(tracing::Level::INFO),
+            Self::Debug => Some(tracing::Level::DEBUG),
+        }
+    }
 }
 
 impl Default for LiveKitConfig {
@@ -251,6 +474,7 @@ impl Default for LiveKitConfig {
             url: default_livekit_url(),
             http_url: default_livekit_http_url(),
             public_url: None,
+            log_level: LiveKitLogLevel::default(),
         }
     }
 }
@@ -270,10 +494,28 @@ impl Default for NetworkConfig {
     }
 }
 
+/// Where the HTTPS certificate comes from.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TlsCertMode {
+    /// Generate a self-signed certificate when none exists.
+    SelfSigned,
+    /// Obtain and renew a certificate with the external ACME client at
+    /// `tls.acme.client_path` (certbot), falling back to self-signed when
+    /// issuance fails so the server still boots.
+    Acme,
+    /// Use the operator-provided `cert_path`/`key_path` as-is.
+    Manual,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TlsConfig {
     #[serde(default = "default_true")]
     pub enabled: bool,
+    /// Certificate source. When unset, derived from the legacy
+    /// `acme.enabled` / `auto_generate` flags (see [`TlsConfig::mode`]).
+    #[serde(default, rename = "mode")]
+    pub cert_mode: Option<TlsCertMode>,
     #[serde(default = "default_tls_port")]
     pub port: u16,
     #[serde(default = "default_cert_path")]
@@ -290,6 +532,7 @@ impl Default for TlsConfig {
     fn default() -> Self {
         Self {
             enabled: true,
+            cert_mode: None,
             port: default_tls_port(),
             cert_path: default_cert_path(),
             key_path: default_key_path(),
@@ -299,6 +542,17 @@ impl Default for TlsConfig {
     }
 }
 
+impl TlsConfig {
+    pub fn mode(&self) -> TlsCertMode {
+        match self.cert_mode {
+            Some(mode) => mode,
+            None if self.acme.enabled => TlsCertMode::Acme,
+            None if self.auto_generate => TlsCertMode::SelfSigned,
+            None => TlsCertMode::Manual,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TlsAcmeConfig {
     #[serde(default = "default_false")]
@@ -314,6 +568,9 @@ pub struct TlsAcmeConfig {
     pub webroot_path: String,
     #[serde(default = "default_acme_cert_name")]
     pub cert_name: String,
+    /// Directory for the ACME client's account, certificate cache and logs.
+    /// Defaults to `acme/` under `[storage] path`.
+    pub state_path: Option<String>,
     pub cert_source_path: Option<String>,
     pub key_source_path: Option<String>,
     #[serde(default)]
@@ -336,6 +593,7 @@ impl Default for TlsAcmeConfig {
             domains: Vec::new(),
             webroot_path: default_acme_webroot_path(),
             cert_name: default_acme_cert_name(),
+            state_path: None,
             cert_source_path: None,
             key_source_path: None,
             additional_args: Vec::new(),
@@ -346,6 +604,58 @@ impl Default for TlsAcmeConfig {
     }
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MessagesConfig {
+    /// Prior revisions kept per message; the oldest are dropped beyond this.
+    /// 0 disables edit history.
+    #[serde(default = "default_message_edit_history_limit")]
+    pub edit_history_limit: u32,
+    /// Let bot accounts post without waiting out channel slowmode.
+    #[serde(default = "default_true")]
+    pub slowmode_exempt_bots: bool,
+    /// Pinned messages allowed per channel.
+    #[serde(default = "default_max_pins_per_channel")]
+    pub max_pins_per_channel: u32,
+    /// Longest message content accepted, counted in grapheme clusters so an
+    /// emoji sequence counts as one character.
+    #[serde(default = "default_max_message_length")]
+    pub max_length: u32,
+    /// Attachments allowed on a single message.
+    #[serde(default = "default_max_message_attachments")]
+    pub max_attachments: u32,
+}
+
+impl Default for MessagesConfig {
+    fn default() -> Self {
+        Self {
+            edit_history_limit: default_message_edit_history_limit(),
+            slowmode_exempt_bots: true,
+            max_pins_per_channel: default_max_pins_per_channel(),
+            max_length: default_max_message_length(),
+            max_attachments: default_max_message_attachments(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EmojiConfig {
+    /// Static (PNG) custom emojis allowed per space.
+    #[serde(default = "default_max_emojis_per_space")]
+    pub max_static_per_space: u32,
+    /// Animated (GIF) custom emojis allowed per space.
+    #[serde(default = "default_max_emojis_per_space")]
+    pub max_animated_per_space: u32,
+}
+
+impl Default for EmojiConfig {
+    fn default() -> Self {
+        Self {
+            max_static_per_space: default_max_emojis_per_space(),
+            max_animated_per_space: default_max_emojis_per_space(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RetentionConfig {
     #[serde(default = "default_false")]
@@ -376,6 +686,35 @@ impl Default for RetentionConfig {
     }
 }
 
+/// Background sweep for attachments that never got (or lost) their message,
+/// plus reconciliation between attachment rows and the storage backend.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AttachmentCleanupConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_attachment_cleanup_interval_seconds")]
+    pub interval_seconds: u64,
+    /// Unlinked attachments, and stored files without a row, are removed
+    /// once they are older than this.
+    #[serde(default = "default_orphan_attachment_age_hours")]
+    pub orphan_age_hours: u64,
+    /// Also delete stored files with no attachment row and flag rows whose
+    /// file is missing. Lists the whole `attachments/` prefix each run.
+    #[serde(default = "default_true")]
+    pub reconcile_storage: bool,
+}
+
+impl Default for AttachmentCleanupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_seconds: default_attachment_cleanup_interval_seconds(),
+            orphan_age_hours: default_orphan_attachment_age_hours(),
+            reconcile_storage: true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AtRestConfig {
     #[serde(default = "default_false")]
@@ -421,6 +760,10 @@ pub struct FederationConfig {
     pub file_cache_max_size: u64,
     #[serde(default = "default_federation_file_cache_ttl_hours")]
     pub file_cache_ttl_hours: u64,
+    /// Let federation requests reach private and loopback addresses, for
+    /// peers on the same LAN.
+    #[serde(default = "default_false")]
+    pub allow_private_peers: bool,
 }
 
 impl Default for FederationConfig {
@@ -435,6 +778,7 @@ impl Default for FederationConfig {
             file_cache_enabled: false,
             file_cache_max_size: default_federation_file_cache_max_size(),
             file_cache_ttl_hours: default_federation_file_cache_ttl_hours(),
+            allow_private_peers: false,
         }
     }
 }
@@ -465,6 +809,147 @@ impl Default for BackupConfig {
     }
 }
 
+/// Per-client HTTP rate limits, grouped by route category.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RateLimitConfig {
+    /// Login, registration, MFA and key-verification attempts.
+    #[serde(default = "default_rate_limit_auth_per_minute")]
+    pub auth_per_minute: u32,
+    /// Message sends (channel messages and webhook executes).
+    #[serde(default = "default_rate_limit_message_send_per_second")]
+    pub message_send_per_second: u32,
+    /// Other mutating requests.
+    #[serde(default = "default_rate_limit_write_per_second")]
+    pub write_per_second: u32,
+    /// GET/HEAD requests.
+    #[serde(default = "default_rate_limit_read_per_second")]
+    pub read_per_second: u32,
+    /// Additional per-token limit for bot-authenticated requests.
+    #[serde(default = "default_rate_limit_bot_per_minute")]
+    pub bot_per_minute: u32,
+    /// Most client buckets held in memory; least recently used are evicted.
+    #[serde(default = "default_rate_limit_max_tracked_keys")]
+    pub max_tracked_keys: usize,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            auth_per_minute: default_rate_limit_auth_per_minute(),
+            message_send_per_second: default_rate_limit_message_send_per_second(),
+            write_per_second: default_rate_limit_write_per_second(),
+            read_per_second: default_rate_limit_read_per_second(),
+            bot_per_minute: default_rate_limit_bot_per_minute(),
+            max_tracked_keys: default_rate_limit_max_tracked_keys(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PresenceConfig {
+    /// Seconds without gateway activity before an online user shows as idle.
+    /// 0 disables automatic idling.
+    #[serde(default = "default_presence_idle_timeout_seconds")]
+    pub idle_timeout_seconds: u64,
+}
+
+impl Default for PresenceConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout_seconds: default_presence_idle_timeout_seconds(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EmailConfig {
+    /// SMTP server hostname. Email is logged instead of sent when unset.
+    #[serde(default)]
+    pub smtp_host: Option<String>,
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    /// "starttls", "implicit" or "none".
+    #[serde(default = "default_smtp_tls")]
+    pub smtp_tls: String,
+    #[serde(default)]
+    pub smtp_username: Option<String>,
+    #[serde(default)]
+    pub smtp_password: Option<String>,
+    /// Sender address, e.g. `Paracord <noreply@example.com>`.
+    #[serde(default = "default_email_from")]
+    pub from_address: String,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            smtp_host: None,
+            smtp_port: default_smtp_port(),
+            smtp_tls: default_smtp_tls(),
+            smtp_username: None,
+            smtp_password: None,
+            from_address: default_email_from(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PushConfig {
+    /// Send Web Push notifications for mentions and DMs.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Contact URL (`mailto:` or `https:`) push services can reach the operator at.
+    #[serde(default = "default_vapid_subject")]
+    pub vapid_subject: String,
+    /// VAPID private key file; generated on first start if missing.
+    #[serde(default = "default_vapid_private_key_path")]
+    pub vapid_private_key_path: String,
+}
+
+impl Default for PushConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            vapid_subject: default_vapid_subject(),
+            vapid_private_key_path: default_vapid_private_key_path(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LinkPreviewConfig {
+    /// Fetch OpenGraph previews for links posted in messages.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// When non-empty, only these domains (and their subdomains) are fetched.
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+    /// Domains (and their subdomains) that are never fetched.
+    #[serde(default)]
+    pub denied_domains: Vec<String>,
+    /// Bytes of each page read when looking for metadata.
+    #[serde(default = "default_link_preview_max_page_bytes")]
+    pub max_page_bytes: usize,
+    #[serde(default = "default_link_preview_timeout_seconds")]
+    pub timeout_seconds: u64,
+    /// How long a fetched preview is reused before the page is fetched again.
+    #[serde(default = "default_link_preview_cache_ttl_hours")]
+    pub cache_ttl_hours: u64,
+}
+
+impl Default for LinkPreviewConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            allowed_domains: Vec::new(),
+            denied_domains: Vec::new(),
+            max_page_bytes: default_link_preview_max_page_bytes(),
+            timeout_seconds: default_link_preview_timeout_seconds(),
+            cache_ttl_hours: default_link_preview_cache_ttl_hours(),
+        }
+    }
+}
+
 // ── Helpers ──────────────────────────────────────────────────────────────────
 
 /// Generate a cryptographically random hex string of the given length.
@@ -485,15 +970,54 @@ fn generate_random_hex(len: usize) -> String {
 fn default_server_name() -> String {
     "localhost".into()
 }
+fn default_worker_id() -> u16 {
+    1
+}
 fn default_database_engine() -> DatabaseEngine {
     DatabaseEngine::Sqlite
 }
 fn default_max_connections() -> u32 {
     20
 }
+fn default_db_acquire_timeout_secs() -> u64 {
+    30
+}
+fn default_db_idle_timeout_secs() -> u64 {
+    600
+}
+fn default_db_max_lifetime_secs() -> u64 {
+    1800
+}
+fn default_db_pool_stats_interval_secs() -> u64 {
+    300
+}
+fn default_sqlite_journal_mode() -> String {
+    "WAL".to_string()
+}
+fn default_sqlite_busy_timeout_ms() -> u64 {
+    5000
+}
+fn default_sqlite_synchronous() -> String {
+    "NORMAL".to_string()
+}
 fn default_jwt_expiry() -> u64 {
     900
 }
+fn default_argon2_memory_kib() -> u32 {
+    paracord_core::auth::PasswordHashParams::default().memory_kib
+}
+fn default_argon2_iterations() -> u32 {
+    paracord_core::auth::PasswordHashParams::default().iterations
+}
+fn default_argon2_parallelism() -> u32 {
+    paracord_core::auth::PasswordHashParams::default().parallelism
+}
+fn default_password_min_length() -> usize {
+    paracord_util::validation::PasswordPolicy::default().min_length
+}
+fn default_breached_password_api_url() -> String {
+    "https://api.pwnedpasswords.com/range/".to_string()
+}
 fn default_true() -> bool {
     true
 }
@@ -509,6 +1033,26 @@ fn default_storage_path() -> String {
 fn default_max_upload_size() -> u64 {
     52_428_800 // 50MB
 }
+fn default_inline_content_types() -> Vec<String> {
+    [
+        "image/jpeg",
+        "image/png",
+        "image/gif",
+        "image/webp",
+        "image/avif",
+        "audio/mpeg",
+        "audio/ogg",
+        "audio/wav",
+        "audio/x-wav",
+        "video/mp4",
+        "video/webm",
+        "text/plain",
+        "application/pdf",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect()
+}
 fn default_media_storage_path() -> String {
     "./data/files".into()
 }
@@ -569,6 +1113,27 @@ fn default_retention_interval_seconds() -> u64 {
 fn default_retention_batch_size() -> i64 {
     256
 }
+fn default_message_edit_history_limit() -> u32 {
+    25
+}
+fn default_max_pins_per_channel() -> u32 {
+    50
+}
+fn default_max_message_length() -> u32 {
+    paracord_util::validation::DEFAULT_MAX_MESSAGE_LENGTH as u32
+}
+fn default_max_message_attachments() -> u32 {
+    10
+}
+fn default_max_emojis_per_space() -> u32 {
+    50
+}
+fn default_attachment_cleanup_interval_seconds() -> u64 {
+    3600
+}
+fn default_orphan_attachment_age_hours() -> u64 {
+    24
+}
 fn default_at_rest_key_env() -> String {
     "PARACORD_AT_REST_KEY".into()
 }
@@ -596,6 +1161,51 @@ fn default_backup_dir() -> String {
 fn default_auto_backup_interval() -> u64 {
     86_400 // 24 hours
 }
+fn default_rate_limit_auth_per_minute() -> u32 {
+    5
+}
+fn default_rate_limit_message_send_per_second() -> u32 {
+    10
+}
+fn default_rate_limit_write_per_second() -> u32 {
+    60
+}
+fn default_rate_limit_read_per_second() -> u32 {
+    120
+}
+fn default_rate_limit_bot_per_minute() -> u32 {
+    300
+}
+fn default_rate_limit_max_tracked_keys() -> usize {
+    paracord_core::rate_limit::DEFAULT_MAX_TRACKED_KEYS
+}
+fn default_presence_idle_timeout_seconds() -> u64 {
+    600
+}
+fn default_smtp_port() -> u16 {
+    587
+}
+fn default_smtp_tls() -> String {
+    "starttls".to_string()
+}
+fn default_email_from() -> String {
+    "Paracord <noreply@localhost>".to_string()
+}
+fn default_vapid_subject() -> String {
+    "mailto:admin@localhost".to_string()
+}
+fn default_vapid_private_key_path() -> String {
+    "./data/vapid_private_key".to_string()
+}
+fn default_link_preview_max_page_bytes() -> usize {
+    512 * 1024
+}
+fn default_link_preview_timeout_seconds() -> u64 {
+    5
+}
+fn default_link_preview_cache_ttl_hours() -> u64 {
+    24
+}
 fn default_max_backups() -> u32 {
     10
 }
@@ -622,6 +1232,8 @@ fn validate_secret_configuration(config: &Config) -> Result<()> {
         );
     }
 
+    config.auth.jwt_key_set()?;
+
     let lk_key = config.livekit.api_key.trim();
     let lk_secret = config.livekit.api_secret.trim();
     if looks_like_placeholder_secret(lk_key) || looks_like_placeholder_secret(lk_secret) {
@@ -644,6 +1256,10 @@ bind_address = "{bind_address}"
 server_name = "{server_name}"
 # Set explicitly for internet-facing deployments:
 # public_url = "https://your-domain-or-ip:8443"
+# Unique per node when several servers share one database (0-1023).
+worker_id = {worker_id}
+# Reverse proxies whose X-Forwarded-For is trusted, e.g. ["127.0.0.1", "10.0.0.0/8"].
+trusted_proxies = []
 
 [database]
 engine = "{db_engine}"
@@ -658,12 +1274,38 @@ registration_enabled = {registration_enabled}
 allow_username_login = {allow_username_login}
 # Require email during password registration.
 require_email = {require_email}
+# JWT key rotation: add a key below, point jwt_signing_kid at it, wait
+# jwt_expiry_seconds, then remove the old key. jwt_secret is key "default";
+# set accept_default_jwt_key = false to retire it.
+# jwt_signing_kid = "2026-10"
+# accept_default_jwt_key = true
+# [[auth.jwt_keys]]
+# kid = "2026-10"
+# secret = "at-least-32-random-characters"
+# Argon2id cost for new password hashes (memory in KiB, max 262144).
+# Existing hashes are upgraded when users next log in.
+argon2_memory_kib = {argon2_memory_kib}
+argon2_iterations = {argon2_iterations}
+argon2_parallelism = {argon2_parallelism}
+# Password policy for registration and password changes.
+password_min_length = {password_min_length}
+password_require_lowercase = {password_require_lowercase}
+password_require_uppercase = {password_require_uppercase}
+password_require_digit = {password_require_digit}
+password_require_symbol = {password_require_symbol}
+# Reject passwords found in known breaches (opt-in; a k-anonymity lookup
+# sends a hash prefix of each new password to the API, failing open).
+check_breached_passwords = {check_breached_passwords}
+breached_password_api_url = "{breached_password_api_url}"
 
 [storage]
 # Storage backend: "local" (default) or "s3".
 # When set to "s3", configure the [s3] section below and build with `--features s3`.
 storage_type = "{storage_type}"
 path = "{storage_path}"
+# Content types browsers may display inline; other uploads download as attachments.
+# Types are taken from the file's sniffed signature, not the client's claim.
+inline_content_types = {inline_content_types}
 
 # [s3]
 # # S3-compatible object storage (MinIO, AWS S3, R2, DigitalOcean Spaces, etc.).
@@ -681,6 +1323,9 @@ path = "{storage_path}"
 # # cdn_url = "https://cdn.example.com"
 # # Presigned URL expiry (default 3600s):
 # # presign_expiry_seconds = 3600
+# # Redirect attachment downloads to presigned URLs instead of proxying them
+# # (ignored when at-rest file encryption is enabled):
+# # redirect_downloads = false
 
 [media]
 storage_path = "{media_path}"
@@ -694,6 +1339,8 @@ url = "{lk_url}"
 http_url = "{lk_http_url}"
 # Optional public URL sent to clients:
 # public_url = "wss://your-domain-or-ip:8443/livekit"
+# Managed LiveKit output forwarded to the server log: off, error, warn, info, debug
+log_level = "info"
 
 [federation]
 enabled = {federation_enabled}
@@ -705,6 +1352,8 @@ allow_discovery = {federation_allow_discovery}
 # max_events_per_peer_per_minute = 120
 # Per-peer rate limit for remote user creation (per hour). Set to 0 to disable.
 # max_user_creates_per_peer_per_hour = 100
+# Allow federating with peers on private/loopback addresses (LAN setups).
+# allow_private_peers = false
 
 [network]
 # On Windows, optionally auto-create local firewall allow rules.
@@ -714,6 +1363,8 @@ windows_firewall_auto_allow = {windows_firewall_auto_allow}
 # HTTPS support — required for getUserMedia() on non-localhost origins.
 # A self-signed certificate is auto-generated on first run.
 enabled = {tls_enabled}
+# Certificate source: "self_signed", "acme" (see [tls.acme]) or "manual".
+# mode = "self_signed"
 port = {tls_port}
 cert_path = "{tls_cert}"
 key_path = "{tls_key}"
@@ -728,6 +1379,8 @@ directory_url = "{acme_directory_url}"
 # domains = ["chat.example.com"]
 webroot_path = "{acme_webroot_path}"
 cert_name = "{acme_cert_name}"
+# ACME account and certificate cache. Defaults to <storage path>/acme.
+# state_path = "./data/uploads/acme"
 # Optional source overrides if your ACME client writes certs elsewhere.
 # cert_source_path = "/etc/letsencrypt/live/paracord/fullchain.pem"
 # key_source_path = "/etc/letsencrypt/live/paracord/privkey.pem"
@@ -736,6 +1389,23 @@ auto_renew = {acme_auto_renew}
 renew_interval_seconds = {acme_renew_interval_seconds}
 # additional_args = ["--preferred-challenges", "http"]
 
+[messages]
+# Prior revisions kept per edited message (0 disables edit history).
+edit_history_limit = {message_edit_history_limit}
+# Bots skip channel slowmode; members with Manage Messages/Channels always do.
+slowmode_exempt_bots = {message_slowmode_exempt_bots}
+# Pinned messages allowed per channel.
+max_pins_per_channel = {message_max_pins_per_channel}
+# Longest message, in characters (an emoji sequence counts as one).
+max_length = {message_max_length}
+# Attachments allowed on a single message.
+max_attachments = {message_max_attachments}
+
+[emojis]
+# Custom emojis allowed per space, counted separately for static and animated.
+max_static_per_space = {emoji_max_static}
+max_animated_per_space = {emoji_max_animated}
+
 [retention]
 # Data retention purge worker. Disabled by default.
 enabled = {retention_enabled}
@@ -750,6 +1420,17 @@ batch_size = {retention_batch}
 # security_event_days = 180
 # session_days = 90
 
+[attachment_cleanup]
+# Periodically delete attachments never linked to a message. When several
+# servers share a database only one of them runs the sweep at a time.
+enabled = {attachment_cleanup_enabled}
+interval_seconds = {attachment_cleanup_interval}
+# Minimum age before an unlinked attachment or stray file is removed.
+orphan_age_hours = {attachment_cleanup_age}
+# Delete stored files that have no attachment row and flag rows whose file
+# is missing from storage.
+reconcile_storage = {attachment_cleanup_reconcile}
+
 [at_rest]
 # Optional encryption-at-rest profile. Disabled by default.
 enabled = {at_rest_enabled}
@@ -774,8 +1455,59 @@ auto_backup_interval_seconds = {backup_interval}
 include_media = {backup_include_media}
 # Maximum number of backups to keep (oldest are pruned).
 max_backups = {backup_max_backups}
+
+[rate_limits]
+# Per-client HTTP request limits, applied separately to each route category.
+# Login, register, MFA and key verification (per minute).
+auth_per_minute = {rl_auth_per_minute}
+# Sending messages and executing webhooks (per second).
+message_send_per_second = {rl_message_send_per_second}
+# Other POST/PUT/PATCH/DELETE requests (per second).
+write_per_second = {rl_write_per_second}
+# GET/HEAD requests (per second).
+read_per_second = {rl_read_per_second}
+# Extra per-token limit for bot-authenticated requests (per minute).
+bot_per_minute = {rl_bot_per_minute}
+# Most client buckets kept in memory; the least recently used are evicted.
+max_tracked_keys = {rl_max_tracked_keys}
+
+[presence]
+# Seconds without gateway activity before an online user shows as idle (0 disables).
+idle_timeout_seconds = {presence_idle_timeout}
+
+[email]
+# Transactional email (verification links, security alerts). Messages are
+# written to the log instead of sent until smtp_host is set.
+# smtp_host = "smtp.example.com"
+smtp_port = {email_smtp_port}
+# "starttls" (usually port 587), "implicit" (usually 465) or "none".
+smtp_tls = "{email_smtp_tls}"
+# smtp_username = "paracord"
+# smtp_password = "your-smtp-password"
+from_address = "{email_from}"
+
+[push]
+# Web Push notifications for mentions and DMs.
+enabled = {push_enabled}
+# Contact push services can reach the operator at.
+vapid_subject = "{push_vapid_subject}"
+# Generated automatically if missing.
+vapid_private_key_path = "{push_vapid_private_key_path}"
+
+[link_previews]
+# Fetch OpenGraph previews for links posted in messages. Private and
+# loopback addresses are never fetched.
+enabled = {link_previews_enabled}
+# Only fetch from these domains (empty = any domain).
+allowed_domains = []
+# Never fetch from these domains.
+denied_domains = []
+max_page_bytes = {link_previews_max_page_bytes}
+timeout_seconds = {link_previews_timeout_seconds}
+cache_ttl_hours = {link_previews_cache_ttl_hours}
 "#,
         bind_address = config.server.bind_address,
+        worker_id = config.server.worker_id,
         server_name = config.server.server_name,
         db_engine = match config.database.engine {
             DatabaseEngine::Sqlite => "sqlite",
@@ -788,8 +1520,19 @@ max_backups = {backup_max_backups}
         registration_enabled = config.auth.registration_enabled,
         allow_username_login = config.auth.allow_username_login,
         require_email = config.auth.require_email,
+        argon2_memory_kib = config.auth.argon2_memory_kib,
+        argon2_iterations = config.auth.argon2_iterations,
+        argon2_parallelism = config.auth.argon2_parallelism,
+        password_min_length = config.auth.password_min_length,
+        password_require_lowercase = config.auth.password_require_lowercase,
+        password_require_uppercase = config.auth.password_require_uppercase,
+        password_require_digit = config.auth.password_require_digit,
+        password_require_symbol = config.auth.password_require_symbol,
+        check_breached_passwords = config.auth.check_breached_passwords,
+        breached_password_api_url = config.auth.breached_password_api_url,
         storage_type = config.storage.storage_type,
         storage_path = config.storage.path,
+        inline_content_types = toml::Value::from(config.storage.inline_content_types.clone()),
         media_path = config.media.storage_path,
         max_file_size = config.media.max_file_size,
         p2p_threshold = config.media.p2p_threshold,
@@ -818,9 +1561,20 @@ max_backups = {backup_max_backups}
         acme_serve_http_challenge = config.tls.acme.serve_http_challenge,
         acme_auto_renew = config.tls.acme.auto_renew,
         acme_renew_interval_seconds = config.tls.acme.renew_interval_seconds,
+        message_edit_history_limit = config.messages.edit_history_limit,
+        message_slowmode_exempt_bots = config.messages.slowmode_exempt_bots,
+        message_max_pins_per_channel = config.messages.max_pins_per_channel,
+        message_max_length = config.messages.max_length,
+        message_max_attachments = config.messages.max_attachments,
+        emoji_max_static = config.emojis.max_static_per_space,
+        emoji_max_animated = config.emojis.max_animated_per_space,
         retention_enabled = config.retention.enabled,
         retention_interval = config.retention.interval_seconds,
         retention_batch = config.retention.batch_size,
+        attachment_cleanup_enabled = config.attachment_cleanup.enabled,
+        attachment_cleanup_interval = config.attachment_cleanup.interval_seconds,
+        attachment_cleanup_age = config.attachment_cleanup.orphan_age_hours,
+        attachment_cleanup_reconcile = config.attachment_cleanup.reconcile_storage,
         at_rest_enabled = config.at_rest.enabled,
         at_rest_key_env = config.at_rest.key_env,
         at_rest_encrypt_sqlite = config.at_rest.encrypt_sqlite,
@@ -831,6 +1585,23 @@ max_backups = {backup_max_backups}
         backup_interval = config.backup.auto_backup_interval_seconds,
         backup_include_media = config.backup.include_media,
         backup_max_backups = config.backup.max_backups,
+        rl_auth_per_minute = config.rate_limits.auth_per_minute,
+        rl_message_send_per_second = config.rate_limits.message_send_per_second,
+        rl_write_per_second = config.rate_limits.write_per_second,
+        rl_read_per_second = config.rate_limits.read_per_second,
+        rl_bot_per_minute = config.rate_limits.bot_per_minute,
+        rl_max_tracked_keys = config.rate_limits.max_tracked_keys,
+        presence_idle_timeout = config.presence.idle_timeout_seconds,
+        email_smtp_port = config.email.smtp_port,
+        email_smtp_tls = config.email.smtp_tls,
+        email_from = config.email.from_address,
+        push_enabled = config.push.enabled,
+        push_vapid_subject = config.push.vapid_subject,
+        push_vapid_private_key_path = config.push.vapid_private_key_path,
+        link_previews_enabled = config.link_previews.enabled,
+        link_previews_max_page_bytes = config.link_previews.max_page_bytes,
+        link_previews_timeout_seconds = config.link_previews.timeout_seconds,
+        link_previews_cache_ttl_hours = config.link_previews.cache_ttl_hours,
     )
 }
 
@@ -874,6 +1645,28 @@ impl Config {
         if let Ok(value) = std::env::var("PARACORD_PUBLIC_URL") {
             config.server.public_url = Some(value);
         }
+        if let Ok(value) = std::env::var("PARACORD_WORKER_ID") {
+            if let Ok(parsed) = value.parse::<u16>() {
+                config.server.worker_id = parsed;
+            }
+        }
+        let legacy_trust_proxy = std::env::var("PARACORD_TRUST_PROXY")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        // Older deployments enabled proxy trust with PARACORD_TRUST_PROXY plus
+        // PARACORD_TRUSTED_PROXY_IPS.
+        let trusted_proxies = std::env::var("PARACORD_TRUSTED_PROXIES").ok().or_else(|| {
+            legacy_trust_proxy
+                .then(|| std::env::var("PARACORD_TRUSTED_PROXY_IPS").ok())
+                .flatten()
+        });
+        if let Some(value) = trusted_proxies {
+            config.server.trusted_proxies = value
+                .split(',')
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect();
+        }
         if let Ok(value) = std::env::var("PARACORD_DATABASE_URL") {
             config.database.url = value;
         }
@@ -895,6 +1688,42 @@ impl Config {
                 config.database.max_connections = parsed;
             }
         }
+        if let Ok(value) = std::env::var("PARACORD_DATABASE_MIN_CONNECTIONS") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.database.min_connections = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_DATABASE_ACQUIRE_TIMEOUT_SECS") {
+            if let Ok(parsed) = value.parse::<u64>() {
+                config.database.acquire_timeout_secs = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_DATABASE_IDLE_TIMEOUT_SECS") {
+            if let Ok(parsed) = value.parse::<u64>() {
+                config.database.idle_timeout_secs = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_DATABASE_MAX_LIFETIME_SECS") {
+            if let Ok(parsed) = value.parse::<u64>() {
+                config.database.max_lifetime_secs = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_DATABASE_SQLITE_JOURNAL_MODE") {
+            config.database.sqlite_journal_mode = value;
+        }
+        if let Ok(value) = std::env::var("PARACORD_DATABASE_SQLITE_BUSY_TIMEOUT_MS") {
+            if let Ok(parsed) = value.parse::<u64>() {
+                config.database.sqlite_busy_timeout_ms = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_DATABASE_SQLITE_SYNCHRONOUS") {
+            config.database.sqlite_synchronous = value;
+        }
+        if let Ok(value) = std::env::var("PARACORD_DATABASE_SQLITE_FOREIGN_KEYS") {
+            if let Ok(parsed) = value.parse::<bool>() {
+                config.database.sqlite_foreign_keys = parsed;
+            }
+        }
         if let Ok(value) = std::env::var("PARACORD_DATABASE_STATEMENT_TIMEOUT_SECS") {
             if let Ok(parsed) = value.parse::<u64>() {
                 config.database.statement_timeout_secs = parsed;
@@ -928,9 +1757,83 @@ impl Config {
                 config.auth.require_email = parsed;
             }
         }
+        if let Ok(value) = std::env::var("PARACORD_JWT_SIGNING_KID") {
+            config.auth.jwt_signing_kid = Some(value);
+        }
+        if let Ok(value) = std::env::var("PARACORD_JWT_KEYS") {
+            // Comma-separated `kid=secret` pairs.
+            config.auth.jwt_keys = value
+                .split(',')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(kid, secret)| JwtKeyConfig {
+                    kid: kid.trim().to_string(),
+                    secret: secret.trim().to_string(),
+                })
+                .collect();
+        }
+        if let Ok(value) = std::env::var("PARACORD_ACCEPT_DEFAULT_JWT_KEY") {
+            if let Ok(parsed) = value.parse::<bool>() {
+                config.auth.accept_default_jwt_key = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_ARGON2_MEMORY_KIB") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.auth.argon2_memory_kib = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_ARGON2_ITERATIONS") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.auth.argon2_iterations = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_ARGON2_PARALLELISM") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.auth.argon2_parallelism = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_PASSWORD_MIN_LENGTH") {
+            if let Ok(parsed) = value.parse::<usize>() {
+                config.auth.password_min_length = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_PASSWORD_REQUIRE_LOWERCASE") {
+            if let Ok(parsed) = value.parse::<bool>() {
+                config.auth.password_require_lowercase = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_PASSWORD_REQUIRE_UPPERCASE") {
+            if let Ok(parsed) = value.parse::<bool>() {
+                config.auth.password_require_uppercase = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_PASSWORD_REQUIRE_DIGIT") {
+            if let Ok(parsed) = value.parse::<bool>() {
+                config.auth.password_require_digit = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_PASSWORD_REQUIRE_SYMBOL") {
+            if let Ok(parsed) = value.parse::<bool>() {
+                config.auth.password_require_symbol = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_CHECK_BREACHED_PASSWORDS") {
+            if let Ok(parsed) = value.parse::<bool>() {
+                config.auth.check_breached_passwords = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_BREACHED_PASSWORD_API_URL") {
+            config.auth.breached_password_api_url = value;
+        }
         if let Ok(value) = std::env::var("PARACORD_STORAGE_TYPE") {
             config.storage.storage_type = value;
         }
+        if let Ok(value) = std::env::var("PARACORD_INLINE_CONTENT_TYPES") {
+            config.storage.inline_content_types = value
+                .split(',')
+                .map(|v| v.trim().to_ascii_lowercase())
+                .filter(|v| !v.is_empty())
+                .collect();
+        }
         if let Ok(value) = std::env::var("PARACORD_STORAGE_PATH") {
             config.storage.path = value;
         }
@@ -961,6 +1864,11 @@ impl Config {
                 config.s3.force_path_style = parsed;
             }
         }
+        if let Ok(value) = std::env::var("PARACORD_S3_REDIRECT_DOWNLOADS") {
+            if let Ok(parsed) = value.parse::<bool>() {
+                config.s3.redirect_downloads = parsed;
+            }
+        }
         if let Ok(value) = std::env::var("PARACORD_MEDIA_STORAGE_PATH") {
             config.media.storage_path = value;
         }
@@ -979,6 +1887,17 @@ impl Config {
         if let Ok(value) = std::env::var("PARACORD_LIVEKIT_PUBLIC_URL") {
             config.livekit.public_url = Some(value);
         }
+        if let Ok(value) = std::env::var("PARACORD_LIVEKIT_LOG_LEVEL") {
+            match LiveKitLogLevel::parse(&value) {
+                Some(level) => config.livekit.log_level = level,
+                None => {
+                    tracing::warn!(
+                        "Ignoring invalid PARACORD_LIVEKIT_LOG_LEVEL value '{}'; expected off, error, warn, info or debug",
+                        value
+                    );
+                }
+            }
+        }
         if let Ok(value) = std::env::var("PARACORD_WINDOWS_FIREWALL_AUTO_ALLOW") {
             if let Ok(parsed) = value.parse::<bool>() {
                 config.network.windows_firewall_auto_allow = parsed;
@@ -989,6 +1908,19 @@ impl Config {
                 config.tls.enabled = parsed;
             }
         }
+        if let Ok(value) = std::env::var("PARACORD_TLS_MODE") {
+            match value.trim().to_ascii_lowercase().as_str() {
+                "self_signed" => config.tls.cert_mode = Some(TlsCertMode::SelfSigned),
+                "acme" => config.tls.cert_mode = Some(TlsCertMode::Acme),
+                "manual" => config.tls.cert_mode = Some(TlsCertMode::Manual),
+                _ => {
+                    tracing::warn!(
+                        "Ignoring invalid PARACORD_TLS_MODE value '{}'; expected self_signed, acme or manual",
+                        value
+                    );
+                }
+            }
+        }
         if let Ok(value) = std::env::var("PARACORD_TLS_ACME_ENABLED") {
             if let Ok(parsed) = value.parse::<bool>() {
                 config.tls.acme.enabled = parsed;
@@ -1043,6 +1975,11 @@ impl Config {
                 Some(value)
             };
         }
+        if let Ok(value) = std::env::var("PARACORD_TLS_ACME_STATE_PATH") {
+            if !value.trim().is_empty() {
+                config.tls.acme.state_path = Some(value);
+            }
+        }
         if let Ok(value) = std::env::var("PARACORD_TLS_ACME_SERVE_HTTP_CHALLENGE") {
             if let Ok(parsed) = value.parse::<bool>() {
                 config.tls.acme.serve_http_challenge = parsed;
@@ -1119,6 +2056,11 @@ impl Config {
                 config.federation.file_cache_ttl_hours = parsed;
             }
         }
+        if let Ok(value) = std::env::var("PARACORD_FEDERATION_ALLOW_PRIVATE_PEERS") {
+            if let Ok(parsed) = value.parse::<bool>() {
+                config.federation.allow_private_peers = parsed;
+            }
+        }
         if let Ok(value) = std::env::var("PARACORD_RETENTION_ENABLED") {
             if let Ok(parsed) = value.parse::<bool>() {
                 config.retention.enabled = parsed;
@@ -1129,6 +2071,41 @@ impl Config {
                 config.retention.interval_seconds = parsed.max(60);
             }
         }
+        if let Ok(value) = std::env::var("PARACORD_MESSAGE_EDIT_HISTORY_LIMIT") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.messages.edit_history_limit = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_SLOWMODE_EXEMPT_BOTS") {
+            if let Ok(parsed) = value.parse::<bool>() {
+                config.messages.slowmode_exempt_bots = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_MAX_PINS_PER_CHANNEL") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.messages.max_pins_per_channel = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_MAX_MESSAGE_LENGTH") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.messages.max_length = parsed.max(1);
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_MAX_MESSAGE_ATTACHMENTS") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.messages.max_attachments = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_MAX_STATIC_EMOJIS") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.emojis.max_static_per_space = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_MAX_ANIMATED_EMOJIS") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.emojis.max_animated_per_space = parsed;
+            }
+        }
         if let Ok(value) = std::env::var("PARACORD_RETENTION_BATCH_SIZE") {
             if let Ok(parsed) = value.parse::<i64>() {
                 config.retention.batch_size = parsed.clamp(1, 10_000);
@@ -1149,6 +2126,26 @@ impl Config {
         if let Ok(value) = std::env::var("PARACORD_RETENTION_SESSION_DAYS") {
             config.retention.session_days = parse_optional_days(&value);
         }
+        if let Ok(value) = std::env::var("PARACORD_ATTACHMENT_CLEANUP_ENABLED") {
+            if let Ok(parsed) = value.parse::<bool>() {
+                config.attachment_cleanup.enabled = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_ATTACHMENT_CLEANUP_INTERVAL_SECONDS") {
+            if let Ok(parsed) = value.parse::<u64>() {
+                config.attachment_cleanup.interval_seconds = parsed.max(60);
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_ATTACHMENT_CLEANUP_ORPHAN_AGE_HOURS") {
+            if let Ok(parsed) = value.parse::<u64>() {
+                config.attachment_cleanup.orphan_age_hours = parsed.max(1);
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_ATTACHMENT_CLEANUP_RECONCILE_STORAGE") {
+            if let Ok(parsed) = value.parse::<bool>() {
+                config.attachment_cleanup.reconcile_storage = parsed;
+            }
+        }
         if let Ok(value) = std::env::var("PARACORD_AT_REST_ENABLED") {
             if let Ok(parsed) = value.parse::<bool>() {
                 config.at_rest.enabled = parsed;
@@ -1197,12 +2194,106 @@ impl Config {
                 config.backup.max_backups = parsed.clamp(1, 100);
             }
         }
+        if let Ok(value) = std::env::var("PARACORD_RATE_LIMIT_AUTH_PER_MINUTE") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.rate_limits.auth_per_minute = parsed.max(1);
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_RATE_LIMIT_MESSAGE_SEND_PER_SECOND") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.rate_limits.message_send_per_second = parsed.max(1);
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_RATE_LIMIT_WRITE_PER_SECOND") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.rate_limits.write_per_second = parsed.max(1);
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_RATE_LIMIT_READ_PER_SECOND") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.rate_limits.read_per_second = parsed.max(1);
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_RATE_LIMIT_BOT_PER_MINUTE") {
+            if let Ok(parsed) = value.parse::<u32>() {
+                config.rate_limits.bot_per_minute = parsed.max(1);
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_RATE_LIMIT_MAX_TRACKED_KEYS") {
+            if let Ok(parsed) = value.parse::<usize>() {
+                config.rate_limits.max_tracked_keys = parsed.max(1_000);
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_PRESENCE_IDLE_TIMEOUT_SECONDS") {
+            if let Ok(parsed) = value.parse::<u64>() {
+                config.presence.idle_timeout_seconds = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_SMTP_HOST") {
+            config.email.smtp_host = Some(value).filter(|v| !v.trim().is_empty());
+        }
+        if let Ok(value) = std::env::var("PARACORD_SMTP_PORT") {
+            if let Ok(parsed) = value.parse::<u16>() {
+                config.email.smtp_port = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_SMTP_TLS") {
+            config.email.smtp_tls = value;
+        }
+        if let Ok(value) = std::env::var("PARACORD_SMTP_USERNAME") {
+            config.email.smtp_username = Some(value);
+        }
+        if let Ok(value) = std::env::var("PARACORD_SMTP_PASSWORD") {
+            config.email.smtp_password = Some(value);
+        }
+        if let Ok(value) = std::env::var("PARACORD_EMAIL_FROM") {
+            config.email.from_address = value;
+        }
+        if let Ok(value) = std::env::var("PARACORD_PUSH_ENABLED") {
+            if let Ok(parsed) = value.parse::<bool>() {
+                config.push.enabled = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_VAPID_SUBJECT") {
+            config.push.vapid_subject = value;
+        }
+        if let Ok(value) = std::env::var("PARACORD_VAPID_PRIVATE_KEY_PATH") {
+            config.push.vapid_private_key_path = value;
+        }
+        if let Ok(value) = std::env::var("PARACORD_LINK_PREVIEWS_ENABLED") {
+            if let Ok(parsed) = value.parse::<bool>() {
+                config.link_previews.enabled = parsed;
+            }
+        }
+        if let Ok(value) = std::env::var("PARACORD_LINK_PREVIEW_ALLOWED_DOMAINS") {
+            config.link_previews.allowed_domains = parse_domain_list(&value);
+        }
+        if let Ok(value) = std::env::var("PARACORD_LINK_PREVIEW_DENIED_DOMAINS") {
+            config.link_previews.denied_domains = parse_domain_list(&value);
+        }
+
+        if config.tls.acme.state_path.is_none() {
+            config.tls.acme.state_path = Some(
+                std::path::Path::new(&config.storage.path)
+                    .join("acme")
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+        }
 
         validate_secret_configuration(&config)?;
         Ok(config)
     }
 }
 
+/// Comma-separated domain names, lowercased.
+fn parse_domain_list(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
 fn parse_optional_days(raw: &str) -> Option<i64> {
     raw.parse::<i64>()
         .ok()
@@ -1211,13 +2302,28 @@ fn parse_optional_days(raw: &str) -> Option<i64> {
 
 #[cfg(test)]
 mod tests {
-    use super::{Config, DatabaseConfig, DatabaseEngine, TlsConfig};
+    use super::{AuthConfig, Config, DatabaseConfig, DatabaseEngine, TlsCertMode, TlsConfig};
 
     #[test]
     fn tls_defaults_enable_self_signed_bootstrap() {
         let tls = TlsConfig::default();
         assert!(tls.enabled);
         assert!(tls.auto_generate);
+        assert_eq!(tls.mode(), TlsCertMode::SelfSigned);
+    }
+
+    #[test]
+    fn tls_mode_falls_back_to_legacy_flags() {
+        let mut tls = TlsConfig::default();
+        tls.acme.enabled = true;
+        assert_eq!(tls.mode(), TlsCertMode::Acme);
+        tls.acme.enabled = false;
+        tls.auto_generate = false;
+        assert_eq!(tls.mode(), TlsCertMode::Manual);
+
+        let tls: TlsConfig =
+            toml::from_str("mode = \"manual\"\n[acme]\nenabled = true").expect("parse tls config");
+        assert_eq!(tls.mode(), TlsCertMode::Manual);
     }
 
     #[test]
@@ -1226,6 +2332,59 @@ mod tests {
         assert_eq!(db.engine, DatabaseEngine::Sqlite);
     }
 
+    #[test]
+    fn jwt_key_set_includes_rotated_keys() {
+        let auth: AuthConfig = toml::from_str(
+            r#"
+jwt_secret = "0123456789abcdef0123456789abcdef"
+jwt_signing_kid = "next"
+[[jwt_keys]]
+kid = "next"
+secret = "fedcba9876543210fedcba9876543210"
+"#,
+        )
+        .expect("parse auth config");
+        let keys = auth.jwt_key_set().expect("key set");
+        assert_eq!(keys.signing_kid(), "next");
+
+        let token = paracord_core::auth::create_token(1, &keys, 60).expect("token");
+        let retired = AuthConfig {
+            accept_default_jwt_key: false,
+            ..auth
+        };
+        let retired_keys = retired.jwt_key_set().expect("key set");
+        assert!(paracord_core::auth::validate_token(&token, &retired_keys).is_ok());
+
+        let missing = AuthConfig {
+            jwt_signing_kid: Some("unknown".into()),
+            ..retired
+        };
+        assert!(missing.jwt_key_set().is_err());
+    }
+
+    #[test]
+    fn password_checks_default_to_local_rules_with_a_length_floor() {
+        let auth = AuthConfig::default();
+        assert!(auth.breached_password_api_url().is_none());
+
+        let auth: AuthConfig = toml::from_str(
+            r#"
+jwt_secret = "0123456789abcdef0123456789abcdef"
+password_min_length = 4
+check_breached_passwords = true
+"#,
+        )
+        .expect("parse auth config");
+        assert_eq!(
+            auth.password_policy().min_length,
+            paracord_util::validation::PASSWORD_MIN_LENGTH
+        );
+        assert_eq!(
+            auth.breached_password_api_url().as_deref(),
+            Some("https://api.pwnedpasswords.com/range/")
+        );
+    }
+
     #[test]
     fn env_override_accepts_postgres_engine() {
         let temp = tempfile::tempdir().expect("tempdir");
@@ -1,26 +1,247 @@
+use crate::config::LiveKitLogLevel;
+use paracord_core::observability::{set_livekit_status, LiveKitStatus};
 use std::io::Write;
 use std::path::PathBuf;
 use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::{Child, Command};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::Level;
+
+/// How often the startup health check polls a freshly spawned LiveKit.
+const STARTUP_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Give up waiting for LiveKit to report healthy after this long.
+const STARTUP_READY_TIMEOUT: Duration = Duration::from_secs(20);
+/// Consecutive crashes tolerated before supervision gives up.
+const MAX_RESTART_ATTEMPTS: u32 = 5;
+const RESTART_BASE_DELAY: Duration = Duration::from_secs(1);
+const RESTART_MAX_DELAY: Duration = Duration::from_secs(30);
+/// A process that stays up this long is considered healthy again, so an
+/// occasional crash days apart never exhausts the restart budget.
+const STABLE_RUN_DURATION: Duration = Duration::from_secs(300);
 
 /// Handle to a managed LiveKit server process.
+///
+/// The process itself is owned by a supervisor task that respawns it if it
+/// exits unexpectedly; dropping or killing the handle stops supervision.
 pub struct LiveKitProcess {
-    child: Child,
+    shutdown: Option<oneshot::Sender<()>>,
+    supervisor: Option<JoinHandle<()>>,
     config_path: PathBuf,
 }
 
 impl LiveKitProcess {
     pub async fn kill(&mut self) {
-        if let Err(e) = self.child.kill().await {
-            tracing::warn!("Failed to kill LiveKit process: {}", e);
-        } else {
-            tracing::info!("LiveKit server stopped.");
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(supervisor) = self.supervisor.take() {
+            let _ = supervisor.await;
         }
         // Clean up temp config
         let _ = std::fs::remove_file(&self.config_path);
     }
 }
 
+/// Everything needed to (re)spawn the managed LiveKit process.
+struct LiveKitLaunch {
+    binary: PathBuf,
+    config_path: PathBuf,
+    log_level: LiveKitLogLevel,
+    port: u16,
+}
+
+impl LiveKitLaunch {
+    /// Spawn the process and forward its stdout/stderr into `tracing`.
+    fn spawn(&self) -> std::io::Result<Child> {
+        let max_level = self.log_level.max_level();
+        let output = || {
+            if max_level.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            }
+        };
+
+        let mut child = Command::new(&self.binary)
+            .arg("--config")
+            .arg(&self.config_path)
+            .stdout(output())
+            .stderr(output())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        if let Some(max_level) = max_level {
+            if let Some(stdout) = child.stdout.take() {
+                tokio::spawn(forward_livekit_output(stdout, Level::INFO, max_level));
+            }
+            if let Some(stderr) = child.stderr.take() {
+                tokio::spawn(forward_livekit_output(stderr, Level::WARN, max_level));
+            }
+        }
+        Ok(child)
+    }
+
+    /// Wait for LiveKit to bind its ports and answer health checks before
+    /// handing out voice tokens that point at it. Returns `false` if the
+    /// process exited while starting.
+    async fn wait_until_ready(&self, child: &mut Child) -> bool {
+        let health_url = format!("http://127.0.0.1:{}", self.port);
+        let started = Instant::now();
+        loop {
+            if let Ok(Some(status)) = child.try_wait() {
+                tracing::error!("LiveKit exited during startup ({})", status);
+                return false;
+            }
+            match paracord_api::routes::livekit_proxy::probe_livekit_backend(&health_url).await {
+                Ok(_) => {
+                    tracing::info!(
+                        "Managed LiveKit server ready after {}ms (PID: {})",
+                        started.elapsed().as_millis(),
+                        child
+                            .id()
+                            .map(|id| id.to_string())
+                            .unwrap_or_else(|| "unknown".into())
+                    );
+                    return true;
+                }
+                Err(e) if started.elapsed() >= STARTUP_READY_TIMEOUT => {
+                    tracing::warn!(
+                        "LiveKit did not become healthy within {}s ({}); continuing anyway",
+                        STARTUP_READY_TIMEOUT.as_secs(),
+                        e
+                    );
+                    return true;
+                }
+                Err(_) => tokio::time::sleep(STARTUP_POLL_INTERVAL).await,
+            }
+        }
+    }
+}
+
+/// Split a LiveKit console log line into its level and the text after it.
+///
+/// LiveKit logs tab-separated `timestamp LEVEL logger caller message {fields}`
+/// lines. Lines that don't follow that shape (Go panics, startup banners)
+/// return `None` and are logged at the stream's default level.
+fn parse_livekit_log_line(line: &str) -> Option<(Level, String)> {
+    let mut fields = line.split('\t');
+    let _timestamp = fields.next()?;
+    let level = match fields.next()?.trim() {
+        "DEBUG" => Level::DEBUG,
+        "INFO" => Level::INFO,
+        "WARN" => Level::WARN,
+        "ERROR" | "DPANIC" | "PANIC" | "FATAL" => Level::ERROR,
+        _ => return None,
+    };
+    let rest: Vec<&str> = fields.map(str::trim).filter(|f| !f.is_empty()).collect();
+    Some((level, rest.join(" ")))
+}
+
+/// Forward each line LiveKit writes to `stream` into the server log under
+/// the `livekit` target, dropping anything more verbose than `max_level`.
+async fn forward_livekit_output<R>(stream: R, default_level: Level, max_level: Level)
+where
+    R: AsyncRead + Unpin,
+{
+    let mut lines = BufReader::new(stream).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (level, message) =
+            parse_livekit_log_line(&line).unwrap_or_else(|| (default_level, line.clone()));
+        if level > max_level {
+            continue;
+        }
+        // The console formatter hides targets, so label the line as well.
+        match level {
+            Level::ERROR => tracing::error!(target: "livekit", "LiveKit: {}", message),
+            Level::WARN => tracing::warn!(target: "livekit", "LiveKit: {}", message),
+            Level::INFO => tracing::info!(target: "livekit", "LiveKit: {}", message),
+            _ => tracing::debug!(target: "livekit", "LiveKit: {}", message),
+        }
+    }
+}
+
+/// Delay before respawn number `attempt` (0-based): 1s, 2s, 4s, ... capped.
+fn restart_backoff(attempt: u32) -> Duration {
+    RESTART_BASE_DELAY
+        .saturating_mul(1u32 << attempt.min(16))
+        .min(RESTART_MAX_DELAY)
+}
+
+/// Watch the managed LiveKit process and respawn it whenever it exits,
+/// until `shutdown` fires or it crashes `MAX_RESTART_ATTEMPTS` times in a row.
+async fn supervise(launch: LiveKitLaunch, mut child: Child, mut shutdown: oneshot::Receiver<()>) {
+    let mut consecutive_failures = 0u32;
+    loop {
+        let started = Instant::now();
+        tokio::select! {
+            status = child.wait() => match status {
+                Ok(status) => tracing::warn!("Managed LiveKit server exited ({})", status),
+                Err(e) => tracing::warn!("Failed to wait on LiveKit process: {}", e),
+            },
+            _ = &mut shutdown => {
+                if let Err(e) = child.kill().await {
+                    tracing::warn!("Failed to kill LiveKit process: {}", e);
+                } else {
+                    tracing::info!("LiveKit server stopped.");
+                }
+                return;
+            }
+        }
+        if started.elapsed() >= STABLE_RUN_DURATION {
+            consecutive_failures = 0;
+        }
+
+        loop {
+            if consecutive_failures >= MAX_RESTART_ATTEMPTS {
+                set_livekit_status(LiveKitStatus::Failed);
+                tracing::error!(
+                    "LiveKit crashed {} times in a row; giving up. Voice is unavailable \
+                     until the server is restarted.",
+                    consecutive_failures
+                );
+                return;
+            }
+            set_livekit_status(LiveKitStatus::Restarting);
+            let delay = restart_backoff(consecutive_failures);
+            consecutive_failures += 1;
+            tracing::info!(
+                "Restarting LiveKit in {}s (attempt {}/{})",
+                delay.as_secs(),
+                consecutive_failures,
+                MAX_RESTART_ATTEMPTS
+            );
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = &mut shutdown => return,
+            }
+
+            paracord_core::observability::livekit_restarted();
+            let mut respawned = match launch.spawn() {
+                Ok(child) => child,
+                Err(e) => {
+                    tracing::error!("Failed to restart LiveKit: {}", e);
+                    continue;
+                }
+            };
+            let ready = tokio::select! {
+                ready = launch.wait_until_ready(&mut respawned) => ready,
+                _ = &mut shutdown => return,
+            };
+            if ready {
+                set_livekit_status(LiveKitStatus::Running);
+                child = respawned;
+                break;
+            }
+        }
+    }
+}
+
 /// Find the livekit-server binary.
 fn find_livekit_binary() -> Option<PathBuf> {
     let exe_name = if cfg!(windows) {
@@ -95,6 +316,7 @@ fn write_livekit_config(
     server_port: u16,
     external_ip: Option<&str>,
     local_ip: Option<&str>,
+    log_level: LiveKitLogLevel,
 ) -> std::io::Result<PathBuf> {
     let is_local_only = external_ip.is_none();
 
@@ -164,7 +386,14 @@ fn write_livekit_config(
         lines.push(format!("    relay_range_end: {relay_end}"));
     }
     lines.push("logging:".to_string());
-    lines.push("    level: info".to_string());
+    // LiveKit has no "off"; its output is discarded instead.
+    let level = match log_level {
+        LiveKitLogLevel::Off | LiveKitLogLevel::Error => "error",
+        LiveKitLogLevel::Warn => "warn",
+        LiveKitLogLevel::Info => "info",
+        LiveKitLogLevel::Debug => "debug",
+    };
+    lines.push(format!("    level: {level}"));
     let config = lines.join("\n") + "\n";
 
     tracing::info!(
@@ -202,6 +431,7 @@ pub async fn start_livekit(
     server_port: u16,
     external_ip: Option<&str>,
     local_ip: Option<&str>,
+    log_level: LiveKitLogLevel,
 ) -> Option<LiveKitProcess> {
     let binary = match find_livekit_binary() {
         Some(path) => {
@@ -257,6 +487,7 @@ pub async fn start_livekit(
         server_port,
         external_ip,
         local_ip,
+        log_level,
     ) {
         Ok(path) => path,
         Err(e) => {
@@ -285,28 +516,14 @@ pub async fn start_livekit(
 
     tracing::info!("Starting managed LiveKit server on port {}...", port);
 
-    // Write LiveKit output to a log file so we can diagnose connection issues.
-    let log_path = std::env::temp_dir().join("paracord-livekit.log");
-    let (lk_stdout, lk_stderr) = match std::fs::File::create(&log_path) {
-        Ok(f) => {
-            let f2 = f.try_clone().unwrap_or_else(|_| {
-                std::fs::File::create(std::env::temp_dir().join("paracord-livekit-err.log"))
-                    .expect("fallback log")
-            });
-            (Stdio::from(f), Stdio::from(f2))
-        }
-        Err(_) => (Stdio::null(), Stdio::null()),
+    let launch = LiveKitLaunch {
+        binary,
+        config_path: config_path.clone(),
+        log_level,
+        port,
     };
-    tracing::info!("LiveKit log file: {}", log_path.display());
-
-    let child = match Command::new(&binary)
-        .arg("--config")
-        .arg(&config_path)
-        .stdout(lk_stdout)
-        .stderr(lk_stderr)
-        .kill_on_drop(true)
-        .spawn()
-    {
+
+    let mut child = match launch.spawn() {
         Ok(child) => child,
         Err(e) => {
             tracing::error!("Failed to start LiveKit: {}", e);
@@ -315,16 +532,51 @@ pub async fn start_livekit(
         }
     };
 
-    // Give LiveKit a moment to start — needs time to bind ports and init
-    tokio::time::sleep(std::time::Duration::from_secs(4)).await;
+    if !launch.wait_until_ready(&mut child).await {
+        let _ = std::fs::remove_file(&config_path);
+        return None;
+    }
+    set_livekit_status(LiveKitStatus::Running);
 
-    tracing::info!(
-        "Managed LiveKit server started (PID: {})",
-        child
-            .id()
-            .map(|id| id.to_string())
-            .unwrap_or_else(|| "unknown".into())
-    );
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    let supervisor = tokio::spawn(supervise(launch, child, shutdown_rx));
 
-    Some(LiveKitProcess { child, config_path })
+    Some(LiveKitProcess {
+        shutdown: Some(shutdown_tx),
+        supervisor: Some(supervisor),
+        config_path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_livekit_console_lines() {
+        let line = "2026-03-01T10:00:00.000Z\tWARN\tlivekit\trtc/transport.go:512\tICE connection failed\t{\"participant\": \"alice\"}";
+        let (level, message) = parse_livekit_log_line(line).expect("parsed");
+        assert_eq!(level, Level::WARN);
+        assert_eq!(
+            message,
+            "livekit rtc/transport.go:512 ICE connection failed {\"participant\": \"alice\"}"
+        );
+
+        let (level, _) =
+            parse_livekit_log_line("2026-03-01T10:00:00.000Z\tFATAL\tlivekit\tbind failed")
+                .expect("parsed");
+        assert_eq!(level, Level::ERROR);
+
+        assert!(parse_livekit_log_line("panic: runtime error: index out of range").is_none());
+        assert!(parse_livekit_log_line("goroutine 1 [running]:").is_none());
+    }
+
+    #[test]
+    fn restart_backoff_doubles_up_to_cap() {
+        assert_eq!(restart_backoff(0), Duration::from_secs(1));
+        assert_eq!(restart_backoff(1), Duration::from_secs(2));
+        assert_eq!(restart_backoff(4), Duration::from_secs(16));
+        assert_eq!(restart_backoff(5), RESTART_MAX_DELAY);
+        assert_eq!(restart_backoff(40), RESTART_MAX_DELAY);
+    }
 }
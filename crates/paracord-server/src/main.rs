@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
 use axum::response::IntoResponse;
 use clap::Parser;
+use paracord_core::observability::{set_db_pool_stats, set_livekit_status, LiveKitStatus};
 use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -28,6 +29,32 @@ fn map_db_engine(engine: config::DatabaseEngine) -> paracord_db::DatabaseEngine
     }
 }
 
+fn http_rate_limits_from_config(
+    config: &config::RateLimitConfig,
+) -> paracord_core::rate_limit::HttpRateLimits {
+    use paracord_core::rate_limit::{HttpRateLimits, RouteCategory, RouteLimit};
+    let mut limits = HttpRateLimits::default();
+    limits.set(
+        RouteCategory::Auth,
+        RouteLimit::per_minute(config.auth_per_minute),
+    );
+    limits.set(
+        RouteCategory::MessageSend,
+        RouteLimit::per_second(config.message_send_per_second),
+    );
+    limits.set(
+        RouteCategory::Write,
+        RouteLimit::per_second(config.write_per_second),
+    );
+    limits.set(
+        RouteCategory::Read,
+        RouteLimit::per_second(config.read_per_second),
+    );
+    limits.bot = RouteLimit::per_minute(config.bot_per_minute);
+    limits.max_tracked_keys = config.max_tracked_keys.max(1_000);
+    limits
+}
+
 fn parse_env_bool(name: &str, default: bool) -> bool {
     std::env::var(name)
         .ok()
@@ -53,7 +80,7 @@ async fn main() -> Result<()> {
     };
     let use_ansi = parse_env_bool("PARACORD_LOG_ANSI", ansi_default);
     let default_log_filter =
-        "paracord=info,paracord_api=info,paracord_server=info,paracord_core=info,tower_http=info,axum=warn,hyper=warn";
+        "paracord=info,paracord_api=info,paracord_server=info,paracord_core=info,livekit=debug,tower_http=info,axum=warn,hyper=warn";
 
     tracing_subscriber::fmt()
         .compact()
@@ -67,11 +94,23 @@ async fn main() -> Result<()> {
 
     let args = cli::Args::parse();
     let config = config::Config::load(&args.config)?;
-    if config.tls.acme.enabled && !config.tls.enabled {
+    if config.tls.mode() == config::TlsCertMode::Acme && !config.tls.enabled {
         tracing::warn!(
-            "tls.acme.enabled is true while tls.enabled is false; ACME automation will be inactive"
+            "tls.mode is acme while tls.enabled is false; ACME automation will be inactive"
         );
     }
+    paracord_core::auth::configure_password_hashing(paracord_core::auth::PasswordHashParams {
+        memory_kib: config.auth.argon2_memory_kib,
+        iterations: config.auth.argon2_iterations,
+        parallelism: config.auth.argon2_parallelism,
+    })
+    .map_err(|e| anyhow::anyhow!("Invalid [auth] password hashing settings: {}", e))?;
+    paracord_util::snowflake::set_worker_id(config.server.worker_id)
+        .map_err(|e| anyhow::anyhow!("Invalid [server] worker_id: {}", e))?;
+    paracord_api::client_ip::install_trusted_proxies(
+        paracord_api::client_ip::TrustedProxies::parse(&config.server.trusted_proxies)
+            .map_err(|e| anyhow::anyhow!("Invalid [server] trusted_proxies: {}", e))?,
+    );
     let at_rest_profile = build_at_rest_profile(&config)?;
     if livekit_credentials_look_insecure(&config.livekit.api_key, &config.livekit.api_secret) {
         if config.server.public_url.is_some() {
@@ -232,6 +271,7 @@ async fn main() -> Result<()> {
             server_public_port,
             detected_external_ip.as_deref(),
             detected_local_ip.as_deref(),
+            config.livekit.log_level,
         )
         .await
         {
@@ -243,6 +283,7 @@ async fn main() -> Result<()> {
             None if already_running => {
                 livekit_status = format!("External (port {})", livekit_port);
                 livekit_reachable = true;
+                set_livekit_status(LiveKitStatus::External);
             }
             None => {
                 livekit_status = "Not available (binary not found)".to_string();
@@ -251,6 +292,7 @@ async fn main() -> Result<()> {
     } else {
         // External LiveKit URL configured — assume reachable
         livekit_reachable = true;
+        set_livekit_status(LiveKitStatus::External);
     }
 
     let db_engine = map_db_engine(config.database.engine);
@@ -258,11 +300,25 @@ async fn main() -> Result<()> {
         statement_timeout_secs: config.database.statement_timeout_secs,
         idle_in_transaction_timeout_secs: config.database.idle_in_transaction_timeout_secs,
     };
-    let db = paracord_db::create_pool_full(
+    let sqlite_options = paracord_db::SqliteConnectOptions {
+        journal_mode: config.database.sqlite_journal_mode.clone(),
+        busy_timeout_ms: config.database.sqlite_busy_timeout_ms,
+        synchronous: config.database.sqlite_synchronous.clone(),
+        foreign_keys: config.database.sqlite_foreign_keys,
+    };
+    let pool_options = paracord_db::PoolOptions {
+        max_connections: config.database.max_connections,
+        min_connections: config.database.min_connections,
+        acquire_timeout_secs: config.database.acquire_timeout_secs,
+        idle_timeout_secs: config.database.idle_timeout_secs,
+        max_lifetime_secs: config.database.max_lifetime_secs,
+    };
+    let db = paracord_db::create_pool_with_options(
         &config.database.url,
-        config.database.max_connections,
+        pool_options,
         Some(db_engine),
         at_rest_profile.sqlite_key_hex.clone(),
+        Some(sqlite_options),
         Some(pg_options),
     )
     .await
@@ -294,6 +350,46 @@ async fn main() -> Result<()> {
         Err(e) => tracing::warn!("Failed to clear stale voice states: {}", e),
     }
 
+    // Accounts created before username skeletons existed need one before the
+    // registration look-alike check can see them.
+    let mut backfilled = 0u64;
+    loop {
+        match paracord_db::users::backfill_username_skeletons(&db, 500).await {
+            Ok(0) => break,
+            Ok(n) => backfilled += n,
+            Err(e) => {
+                tracing::warn!("Failed to backfill username skeletons: {}", e);
+                break;
+            }
+        }
+    }
+    if backfilled > 0 {
+        tracing::info!("Backfilled {} username skeleton(s)", backfilled);
+    }
+
+    // Messages sent before mention tracking have no stored mentions. There
+    // can be a lot of them, so parse them in the background rather than
+    // holding up startup; the cursor is persisted after every batch.
+    {
+        let db = db.clone();
+        tokio::spawn(async move {
+            let mut backfilled = 0u64;
+            loop {
+                match paracord_core::mentions::backfill_message_mentions(&db, 500).await {
+                    Ok(0) => break,
+                    Ok(n) => backfilled += n,
+                    Err(e) => {
+                        tracing::warn!("Failed to backfill message mentions: {}", e);
+                        break;
+                    }
+                }
+            }
+            if backfilled > 0 {
+                tracing::info!("Backfilled mentions for {} message(s)", backfilled);
+            }
+        });
+    }
+
     // ── Load runtime settings from database ─────────────────────────────────
     let runtime = load_runtime_settings(&db).await;
     let runtime = Arc::new(RwLock::new(runtime));
@@ -390,6 +486,17 @@ async fn main() -> Result<()> {
 
     let shutdown_notify = Arc::new(tokio::sync::Notify::new());
 
+    // Federation requests go to peer-supplied URLs; keep them off private
+    // networks unless the operator federates over a LAN.
+    if !config.federation.allow_private_peers {
+        paracord_federation::client::install_outbound_guard(
+            paracord_federation::client::OutboundGuard {
+                client_builder: paracord_core::outbound::guarded_client_builder,
+                allows_url: paracord_core::outbound::is_fetchable_url,
+            },
+        );
+    }
+
     // Build a pre-initialized FederationService so routes don't re-parse
     // environment variables on every request.
     let federation_service = if config.federation.enabled {
@@ -415,6 +522,11 @@ async fn main() -> Result<()> {
         None
     };
 
+    let mailer = build_mailer(&config.email)?;
+    let push = build_push_dispatcher(&config.push, &db)?;
+    let event_bus = paracord_core::events::EventBus::default();
+    let link_previews = build_link_previewer(&config.link_previews, &db, &event_bus)?;
+
     let memberships = paracord_db::members::get_all_memberships(&db)
         .await
         .context("failed to load memberships for member index")?;
@@ -422,15 +534,18 @@ async fn main() -> Result<()> {
 
     let mut state = paracord_core::AppState {
         db,
-        event_bus: paracord_core::events::EventBus::default(),
+        event_bus,
         runtime,
         shutdown: shutdown_notify.clone(),
         config: paracord_core::AppConfig {
             jwt_secret: config.auth.jwt_secret.clone(),
+            jwt_keys: config.auth.jwt_key_set()?,
             jwt_expiry_seconds: config.auth.jwt_expiry_seconds,
             registration_enabled: config.auth.registration_enabled,
             allow_username_login: config.auth.allow_username_login,
             require_email: config.auth.require_email,
+            password_policy: config.auth.password_policy(),
+            breached_password_api_url: config.auth.breached_password_api_url(),
             storage_path: config.storage.path.clone(),
             max_upload_size: config.storage.max_upload_size,
             livekit_api_key: config.livekit.api_key.clone(),
@@ -463,6 +578,17 @@ async fn main() -> Result<()> {
             federation_file_cache_enabled: config.federation.file_cache_enabled,
             federation_file_cache_max_size: config.federation.file_cache_max_size,
             federation_file_cache_ttl_hours: config.federation.file_cache_ttl_hours,
+            audit_log_retention_days: config.retention.audit_log_days,
+            security_event_retention_days: config.retention.security_event_days,
+            http_rate_limits: http_rate_limits_from_config(&config.rate_limits),
+            inline_content_types: config.storage.inline_content_types.clone(),
+            message_edit_history_limit: config.messages.edit_history_limit,
+            slowmode_exempt_bots: config.messages.slowmode_exempt_bots,
+            max_pins_per_channel: config.messages.max_pins_per_channel,
+            max_message_length: config.messages.max_length.max(1),
+            max_attachments_per_message: config.messages.max_attachments,
+            max_static_emojis: config.emojis.max_static_per_space,
+            max_animated_emojis: config.emojis.max_animated_per_space,
         },
         voice,
         storage,
@@ -472,8 +598,15 @@ async fn main() -> Result<()> {
         permission_cache: paracord_core::build_permission_cache(),
         federation_service,
         member_index: Arc::new(member_index),
-        presence_manager: Arc::new(paracord_core::presence_manager::PresenceManager::new()),
+        presence_manager: Arc::new(
+            paracord_core::presence_manager::PresenceManager::new().with_idle_timeout(Some(
+                std::time::Duration::from_secs(config.presence.idle_timeout_seconds),
+            )),
+        ),
         native_media: None,
+        mailer,
+        push,
+        link_previews,
     };
 
     // ── Native QUIC media server ─────────────────────────────────────────────
@@ -562,7 +695,8 @@ async fn main() -> Result<()> {
         tracing::info!("QUIC file transfer enabled (sharing native media QUIC endpoint)");
     }
 
-    paracord_api::install_http_rate_limiter();
+    paracord_api::install_http_rate_limiter(state.config.http_rate_limits.clone());
+    paracord_api::install_cors_runtime_settings(state.runtime.clone());
     paracord_api::spawn_http_rate_limiter_cleanup(shutdown_notify.clone());
 
     spawn_pending_attachment_cleanup(
@@ -576,6 +710,12 @@ async fn main() -> Result<()> {
         config.retention.clone(),
         shutdown_notify.clone(),
     );
+    spawn_attachment_cleanup(
+        state.db.clone(),
+        state.storage_backend.clone(),
+        config.attachment_cleanup.clone(),
+        shutdown_notify.clone(),
+    );
     spawn_auto_backup(
         config.backup.clone(),
         config.database.url.clone(),
@@ -584,8 +724,26 @@ async fn main() -> Result<()> {
         shutdown_notify.clone(),
     );
     spawn_federation_delivery_worker(state.clone(), shutdown_notify.clone());
+    spawn_ban_expiry_sweeper(state.clone(), shutdown_notify.clone());
+    spawn_invite_expiry_sweeper(state.clone(), shutdown_notify.clone());
+    spawn_auth_challenge_sweeper(state.clone(), shutdown_notify.clone());
+    spawn_link_preview_sweeper(
+        state.clone(),
+        std::time::Duration::from_secs(config.link_previews.cache_ttl_hours * 60 * 60),
+        shutdown_notify.clone(),
+    );
+    spawn_unverified_account_sweeper(state.clone(), shutdown_notify.clone());
+    spawn_idle_presence_sweeper(state.clone(), shutdown_notify.clone());
+    spawn_custom_status_expiry_sweeper(state.clone(), shutdown_notify.clone());
+    spawn_thread_auto_archiver(state.clone(), shutdown_notify.clone());
+    spawn_db_pool_stats_logger(
+        state.db.clone(),
+        config.database.pool_stats_interval_secs,
+        shutdown_notify.clone(),
+    );
     bots::spawn_bot_manager(state.clone(), shutdown_notify.clone());
 
+    let shutdown_db = state.db.clone();
     let router = paracord_api::build_router()
         .merge(paracord_ws::gateway_router())
         .with_state(state);
@@ -655,6 +813,11 @@ async fn main() -> Result<()> {
             rustls_config.clone(),
             shutdown_notify.clone(),
         );
+        tls::spawn_cert_watch_task(
+            config.tls.clone(),
+            rustls_config.clone(),
+            shutdown_notify.clone(),
+        );
     }
 
     // ── Startup banner ───────────────────────────────────────────────────────
@@ -683,22 +846,21 @@ async fn main() -> Result<()> {
         &voice_status,
     );
 
-    // Graceful shutdown on ctrl-c or API-triggered restart
-    let shutdown_notify_http = shutdown_notify.clone();
-    let shutdown_signal_http = async move {
-        tokio::select! {
-            _ = tokio::signal::ctrl_c() => {
-                println!();
-                tracing::info!("Shutting down (ctrl-c)...");
-            }
-            _ = shutdown_notify_http.notified() => {
-                tracing::info!("Shutting down (restart requested via API)...");
-            }
-        }
-        if let Some(mut lk) = managed_livekit {
-            lk.kill().await;
-        }
-    };
+    // Graceful shutdown on ctrl-c, SIGTERM or API-triggered restart: stop
+    // accepting connections, wake every worker and gateway session so they
+    // can close cleanly, then give in-flight requests a bounded drain window.
+    let (drain_tx, drain_rx) = tokio::sync::watch::channel(false);
+    let https_handle = axum_server::Handle::new();
+    {
+        let shutdown_notify = shutdown_notify.clone();
+        let https_handle = https_handle.clone();
+        tokio::spawn(async move {
+            wait_for_shutdown_signal(&shutdown_notify).await;
+            shutdown_notify.notify_waiters();
+            https_handle.graceful_shutdown(Some(SHUTDOWN_DRAIN_TIMEOUT));
+            let _ = drain_tx.send(true);
+        });
+    }
 
     if let Some(rustls_config) = tls_rustls_config {
         // Run HTTP redirect + HTTPS concurrently.
@@ -733,28 +895,93 @@ async fn main() -> Result<()> {
             listener,
             http_redirect_app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
         )
-        .with_graceful_shutdown(shutdown_signal_http);
+        .with_graceful_shutdown(shutdown_started(drain_rx.clone()));
 
         let https_server = axum_server::bind_rustls(tls_addr, rustls_config)
+            .handle(https_handle)
             .serve(app_https.into_make_service_with_connect_info::<std::net::SocketAddr>());
 
+        // Both listeners drain together; either failing stops the server.
+        let servers = async { tokio::try_join!(async { http_server.await }, https_server) };
         tokio::select! {
-            result = http_server => { result?; }
-            result = https_server => { result?; }
+            result = servers => { result?; }
+            () = drain_deadline(drain_rx) => {}
         }
     } else {
         // HTTP only
-        axum::serve(
+        let http_server = axum::serve(
             listener,
             app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
         )
-        .with_graceful_shutdown(shutdown_signal_http)
-        .await?;
+        .with_graceful_shutdown(shutdown_started(drain_rx.clone()));
+
+        tokio::select! {
+            result = http_server => { result?; }
+            () = drain_deadline(drain_rx) => {}
+        }
     }
 
+    // Nothing is serving anymore: stop the managed LiveKit process and flush
+    // the database before exiting.
+    if let Some(mut lk) = managed_livekit {
+        lk.kill().await;
+    }
+    paracord_db::close(&shutdown_db).await;
+    tracing::info!("Shutdown complete");
+
     Ok(())
 }
 
+/// How long in-flight requests get to finish once shutdown starts.
+const SHUTDOWN_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Resolves on ctrl-c, SIGTERM (unix) or an API-triggered restart.
+async fn wait_for_shutdown_signal(restart: &tokio::sync::Notify) {
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("Could not install SIGTERM handler: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {
+            println!();
+            tracing::info!("Shutting down (ctrl-c)...");
+        }
+        () = terminate => {
+            tracing::info!("Shutting down (SIGTERM)...");
+        }
+        () = restart.notified() => {
+            tracing::info!("Shutting down (restart requested via API)...");
+        }
+    }
+}
+
+/// Resolves once the shutdown signal has fired.
+async fn shutdown_started(mut drain: tokio::sync::watch::Receiver<bool>) {
+    let _ = drain.wait_for(|started| *started).await;
+}
+
+/// Resolves `SHUTDOWN_DRAIN_TIMEOUT` after shutdown starts, cutting off any
+/// connections that are still open.
+async fn drain_deadline(drain: tokio::sync::watch::Receiver<bool>) {
+    shutdown_started(drain).await;
+    tokio::time::sleep(SHUTDOWN_DRAIN_TIMEOUT).await;
+    tracing::warn!(
+        "Connections still open after {}s; closing them",
+        SHUTDOWN_DRAIN_TIMEOUT.as_secs()
+    );
+}
+
 /// Ensure all data directories exist before the server starts.
 fn ensure_data_dirs(config: &config::Config) {
     // Storage directories
@@ -880,6 +1107,9 @@ async fn load_runtime_settings(db: &paracord_db::DbPool) -> paracord_core::Runti
         for (key, value) in all {
             match key.as_str() {
                 "registration_enabled" => settings.registration_enabled = value == "true",
+                "require_email_verification" => {
+                    settings.require_email_verification = value == "true"
+                }
                 "server_name" => settings.server_name = value,
                 "server_description" => settings.server_description = value,
                 "max_guilds_per_user" => {
@@ -892,6 +1122,9 @@ async fn load_runtime_settings(db: &paracord_db::DbPool) -> paracord_core::Runti
                         settings.max_members_per_guild = v;
                     }
                 }
+                "cors_allowed_origins" => {
+                    settings.cors_allowed_origins = paracord_core::parse_origin_list(&value)
+                }
                 _ => {}
             }
         }
@@ -1119,6 +1352,99 @@ fn build_at_rest_profile(config: &config::Config) -> Result<AtRestRuntimeProfile
     })
 }
 
+fn build_mailer(config: &config::EmailConfig) -> Result<paracord_mail::Mailer> {
+    let Some(host) = config
+        .smtp_host
+        .as_deref()
+        .map(str::trim)
+        .filter(|host| !host.is_empty())
+    else {
+        tracing::info!("SMTP not configured; transactional email will be logged instead of sent");
+        return Ok(paracord_mail::Mailer::disabled());
+    };
+    let mail_config = paracord_mail::MailConfig {
+        host: host.to_string(),
+        port: config.smtp_port,
+        tls: config
+            .smtp_tls
+            .parse()
+            .map_err(|err| anyhow::anyhow!("invalid email.smtp_tls: {}", err))?,
+        username: config.smtp_username.clone(),
+        password: config.smtp_password.clone(),
+        from: config.from_address.clone(),
+    };
+    paracord_mail::Mailer::start(&mail_config)
+        .map_err(|err| anyhow::anyhow!("failed to configure SMTP mailer: {}", err))
+}
+
+fn build_push_dispatcher(
+    config: &config::PushConfig,
+    db: &paracord_db::DbPool,
+) -> Result<paracord_core::push::PushDispatcher> {
+    if !config.enabled {
+        return Ok(paracord_core::push::PushDispatcher::disabled());
+    }
+    let vapid = ensure_vapid_key_file(&config.vapid_private_key_path)?;
+    paracord_core::push::PushDispatcher::start(db.clone(), vapid, config.vapid_subject.clone())
+        .map_err(|err| anyhow::anyhow!("failed to start push delivery: {}", err))
+}
+
+fn build_link_previewer(
+    config: &config::LinkPreviewConfig,
+    db: &paracord_db::DbPool,
+    event_bus: &paracord_core::events::EventBus,
+) -> Result<paracord_core::link_preview::LinkPreviewer> {
+    if !config.enabled {
+        return Ok(paracord_core::link_preview::LinkPreviewer::disabled());
+    }
+    paracord_core::link_preview::LinkPreviewer::start(
+        db.clone(),
+        event_bus.clone(),
+        paracord_core::link_preview::LinkPreviewConfig {
+            allowed_domains: config.allowed_domains.clone(),
+            denied_domains: config.denied_domains.clone(),
+            max_body_bytes: config.max_page_bytes.max(1024),
+            timeout: std::time::Duration::from_secs(config.timeout_seconds.max(1)),
+            cache_ttl: std::time::Duration::from_secs(config.cache_ttl_hours * 60 * 60),
+        },
+    )
+    .context("failed to start link preview worker")
+}
+
+fn ensure_vapid_key_file(path: &str) -> Result<paracord_util::web_push::VapidKey> {
+    let path = Path::new(path);
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent).with_context(|| {
+                format!(
+                    "failed to create VAPID key directory '{}'",
+                    parent.display()
+                )
+            })?;
+        }
+    }
+
+    if !path.exists() {
+        let key = paracord_util::web_push::VapidKey::generate();
+        std::fs::write(path, format!("{}\n", key.to_base64()))
+            .with_context(|| format!("failed to write VAPID key file '{}'", path.display()))?;
+        harden_secret_file_permissions(path);
+        tracing::info!("Generated VAPID key at '{}'", path.display());
+        return Ok(key);
+    }
+
+    let raw_key = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read VAPID key from '{}'", path.display()))?;
+    let key = paracord_util::web_push::VapidKey::from_base64(raw_key.trim()).map_err(|_| {
+        anyhow::anyhow!(
+            "invalid VAPID key at '{}': expected a base64url P-256 private key",
+            path.display()
+        )
+    })?;
+    harden_secret_file_permissions(path);
+    Ok(key)
+}
+
 fn spawn_pending_attachment_cleanup(
     db: paracord_db::DbPool,
     backend: Arc<paracord_media::Storage>,
@@ -1160,11 +1486,203 @@ async fn cleanup_pending_attachments_once(
             .unwrap_or("bin");
         let storage_key = format!("attachments/{}.{}", attachment.id, ext);
         let _ = backend.delete(&storage_key).await;
+        let _ = backend
+            .delete(&paracord_media::images::thumbnail_storage_key(
+                attachment.id,
+                ext,
+            ))
+            .await;
         let _ = paracord_db::attachments::delete_attachment(db, attachment.id).await;
     }
     Ok(())
 }
 
+const ATTACHMENT_CLEANUP_LEASE: &str = "attachment_cleanup";
+const ATTACHMENT_CLEANUP_BATCH: i64 = 500;
+
+fn spawn_attachment_cleanup(
+    db: paracord_db::DbPool,
+    backend: Arc<paracord_media::Storage>,
+    cleanup: config::AttachmentCleanupConfig,
+    shutdown: Arc<tokio::sync::Notify>,
+) {
+    if !cleanup.enabled {
+        tracing::info!("Attachment cleanup disabled");
+        return;
+    }
+
+    let interval_seconds = cleanup.interval_seconds.max(60);
+    // Identifies this process when several servers share one database.
+    let holder = format!("{}-{:016x}", std::process::id(), rand::random::<u64>());
+    tracing::info!(
+        "Attachment cleanup enabled (interval={}s, orphan_age={}h, reconcile_storage={})",
+        interval_seconds,
+        cleanup.orphan_age_hours,
+        cleanup.reconcile_storage
+    );
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_seconds));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => {
+                    break;
+                }
+                _ = interval.tick() => {
+                    if let Err(err) =
+                        run_attachment_cleanup_once(&db, &backend, &cleanup, &holder, interval_seconds).await
+                    {
+                        tracing::warn!("Attachment cleanup failed: {}", err);
+                    }
+                }
+            }
+        }
+        let _ =
+            paracord_db::job_leases::release_lease(&db, ATTACHMENT_CLEANUP_LEASE, &holder).await;
+    });
+}
+
+async fn run_attachment_cleanup_once(
+    db: &paracord_db::DbPool,
+    backend: &paracord_media::Storage,
+    cleanup: &config::AttachmentCleanupConfig,
+    holder: &str,
+    interval_seconds: u64,
+) -> Result<()> {
+    let now = chrono::Utc::now();
+    // The holder renews every tick; two intervals of slack lets another
+    // instance take over soon after this one stops.
+    let lease_until = now + chrono::Duration::seconds(interval_seconds.saturating_mul(2) as i64);
+    let acquired = paracord_db::job_leases::try_acquire_lease(
+        db,
+        ATTACHMENT_CLEANUP_LEASE,
+        holder,
+        now,
+        lease_until,
+    )
+    .await?;
+    if !acquired {
+        tracing::debug!("Attachment cleanup skipped: another instance holds the lease");
+        return Ok(());
+    }
+
+    let age_hours = cleanup.orphan_age_hours.clamp(1, 24 * 3650) as i64;
+    let cutoff = now - chrono::Duration::hours(age_hours);
+    let orphans =
+        purge_unlinked_attachments_older_than(db, backend, cutoff, ATTACHMENT_CLEANUP_BATCH)
+            .await?;
+    let (stray_files, missing_files) = if cleanup.reconcile_storage {
+        reconcile_attachment_storage(db, backend, cutoff).await?
+    } else {
+        (0, 0)
+    };
+
+    if orphans > 0 || stray_files > 0 || missing_files > 0 {
+        tracing::info!(
+            "Attachment cleanup reclaimed {} orphaned attachment(s) and {} stray file(s); {} attachment(s) newly missing their file",
+            orphans,
+            stray_files,
+            missing_files
+        );
+    }
+    Ok(())
+}
+
+/// Cross-check stored attachment files against attachment rows. Files whose
+/// row is gone are deleted once their id is older than `cutoff` (the file is
+/// written before the row, so younger ones may be mid-upload). Rows whose
+/// file is missing are flagged, and unflagged if it shows up again.
+///
+/// Returns `(files deleted, rows newly flagged)`.
+async fn reconcile_attachment_storage(
+    db: &paracord_db::DbPool,
+    backend: &paracord_media::Storage,
+    cutoff: chrono::DateTime<chrono::Utc>,
+) -> Result<(u64, u64)> {
+    let listed_at = chrono::Utc::now();
+    let listed_before_id = paracord_util::snowflake::generate();
+    let keys = backend.list("attachments/").await?;
+
+    let mut keys_by_id: std::collections::HashMap<i64, Vec<&str>> =
+        std::collections::HashMap::new();
+    for key in &keys {
+        if let Some(id) = attachment_id_from_storage_key(key) {
+            keys_by_id.entry(id).or_default().push(key);
+        }
+    }
+
+    let cutoff_id = paracord_util::snowflake::Snowflake::first_at(cutoff).get();
+    let candidates: Vec<i64> = keys_by_id
+        .keys()
+        .copied()
+        .filter(|id| *id < cutoff_id)
+        .collect();
+    let mut deleted_files = 0_u64;
+    for chunk in candidates.chunks(ATTACHMENT_CLEANUP_BATCH as usize) {
+        let existing: std::collections::HashSet<i64> =
+            paracord_db::attachments::get_existing_attachment_ids(db, chunk)
+                .await?
+                .into_iter()
+                .collect();
+        for id in chunk.iter().filter(|id| !existing.contains(id)) {
+            for key in &keys_by_id[id] {
+                match backend.delete(key).await {
+                    Ok(()) => deleted_files = deleted_files.saturating_add(1),
+                    Err(err) => tracing::warn!("Failed deleting stray file {}: {}", key, err),
+                }
+            }
+        }
+    }
+
+    let stored: std::collections::HashSet<&str> = keys.iter().map(String::as_str).collect();
+    let mut newly_missing = 0_u64;
+    let mut after_id = 0_i64;
+    loop {
+        let rows = paracord_db::attachments::list_attachment_files_after(
+            db,
+            after_id,
+            ATTACHMENT_CLEANUP_BATCH,
+        )
+        .await?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        after_id = last.id;
+
+        for row in &rows {
+            // Rows created after the listing may have files it did not see.
+            if row.id >= listed_before_id {
+                continue;
+            }
+            let key = attachment_storage_key_for(row.id, &row.filename);
+            let present = stored.contains(key.as_str());
+            if !present && !row.file_missing {
+                tracing::warn!("Attachment {} is missing its stored file {}", row.id, key);
+                paracord_db::attachments::set_attachment_file_missing(db, row.id, Some(listed_at))
+                    .await?;
+                newly_missing = newly_missing.saturating_add(1);
+            } else if present && row.file_missing {
+                paracord_db::attachments::set_attachment_file_missing(db, row.id, None).await?;
+            }
+        }
+
+        if (rows.len() as i64) < ATTACHMENT_CLEANUP_BATCH {
+            break;
+        }
+    }
+
+    Ok((deleted_files, newly_missing))
+}
+
+/// Attachment id encoded in `attachments/{id}.{ext}` or its thumbnail key.
+fn attachment_id_from_storage_key(key: &str) -> Option<i64> {
+    let name = key.strip_prefix("attachments/")?;
+    let stem = name.split('.').next()?;
+    let id = stem.strip_suffix("_thumb").unwrap_or(stem);
+    id.parse().ok()
+}
+
 fn spawn_federation_delivery_worker(
     state: paracord_core::AppState,
     shutdown: Arc<tokio::sync::Notify>,
@@ -1183,6 +1701,9 @@ fn spawn_federation_delivery_worker(
         loop {
             tokio::select! {
                 _ = shutdown.notified() => break,
+                _ = paracord_federation::outbound_event_queued() => {
+                    service.process_outbound_queue_once(&state.db, 64).await;
+                }
                 _ = interval.tick() => {
                     service.process_outbound_queue_once(&state.db, 64).await;
                     paracord_api::routes::federation::run_federation_catchup_once(&state, 128, 64)
@@ -1195,6 +1716,214 @@ fn spawn_federation_delivery_worker(
     });
 }
 
+fn spawn_db_pool_stats_logger(
+    db: paracord_db::DbPool,
+    interval_seconds: u64,
+    shutdown: Arc<tokio::sync::Notify>,
+) {
+    if interval_seconds == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_seconds));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    let stats = paracord_db::pool_stats(&db);
+                    set_db_pool_stats(stats.open, stats.idle, stats.max);
+                    if stats.saturated() {
+                        tracing::warn!(
+                            "Database pool saturated: {}/{} connections in use; consider raising [database] max_connections",
+                            stats.in_use(),
+                            stats.max
+                        );
+                    } else {
+                        tracing::info!(
+                            "Database pool: {} open, {} idle, {} in use (min {}, max {})",
+                            stats.open,
+                            stats.idle,
+                            stats.in_use(),
+                            stats.min,
+                            stats.max
+                        );
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn spawn_ban_expiry_sweeper(state: paracord_core::AppState, shutdown: Arc<tokio::sync::Notify>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    match paracord_api::routes::bans::lift_expired_bans_once(&state).await {
+                        Ok(0) => {}
+                        Ok(lifted) => tracing::info!("Lifted {} expired ban(s)", lifted),
+                        Err(err) => tracing::warn!("Ban expiry sweep failed: {}", err),
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn spawn_invite_expiry_sweeper(state: paracord_core::AppState, shutdown: Arc<tokio::sync::Notify>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(300));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    match paracord_api::routes::invites::delete_expired_invites_once(&state).await {
+                        Ok(0) => {}
+                        Ok(deleted) => tracing::info!("Deleted {} expired invite(s)", deleted),
+                        Err(err) => tracing::warn!("Invite expiry sweep failed: {}", err),
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn spawn_auth_challenge_sweeper(
+    state: paracord_core::AppState,
+    shutdown: Arc<tokio::sync::Notify>,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(300));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    if let Err(err) = paracord_api::routes::auth::purge_expired_challenges_once(&state).await {
+                        tracing::warn!("Auth challenge sweep failed: {}", err);
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn spawn_link_preview_sweeper(
+    state: paracord_core::AppState,
+    cache_ttl: std::time::Duration,
+    shutdown: Arc<tokio::sync::Notify>,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    match paracord_core::link_preview::purge_expired_previews(&state.db, cache_ttl).await {
+                        Ok(0) => {}
+                        Ok(purged) => tracing::debug!("Purged {} expired link previews", purged),
+                        Err(err) => tracing::warn!("Link preview sweep failed: {}", err),
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn spawn_unverified_account_sweeper(
+    state: paracord_core::AppState,
+    shutdown: Arc<tokio::sync::Notify>,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    match paracord_api::routes::auth::prune_unverified_accounts_once(&state).await {
+                        Ok(0) => {}
+                        Ok(pruned) => tracing::info!("Pruned {} unverified accounts", pruned),
+                        Err(err) => tracing::warn!("Unverified account sweep failed: {}", err),
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn spawn_thread_auto_archiver(state: paracord_core::AppState, shutdown: Arc<tokio::sync::Notify>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    match paracord_api::routes::channels::archive_inactive_threads_once(&state).await {
+                        Ok(0) => {}
+                        Ok(archived) => tracing::info!("Auto-archived {} inactive thread(s)", archived),
+                        Err(err) => tracing::warn!("Thread auto-archive sweep failed: {}", err),
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn spawn_idle_presence_sweeper(state: paracord_core::AppState, shutdown: Arc<tokio::sync::Notify>) {
+    let Some(idle_timeout) = state.presence_manager.idle_timeout() else {
+        return;
+    };
+    // Check a few times per timeout so users idle close to on schedule.
+    let period = (idle_timeout / 4).clamp(
+        std::time::Duration::from_secs(5),
+        std::time::Duration::from_secs(60),
+    );
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    let idled = paracord_ws::sweep_idle_presences(&state).await;
+                    if idled > 0 {
+                        tracing::debug!("Marked {} user(s) idle", idled);
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn spawn_custom_status_expiry_sweeper(
+    state: paracord_core::AppState,
+    shutdown: Arc<tokio::sync::Notify>,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    let cleared = paracord_core::custom_status::sweep_expired(&state).await;
+                    if cleared > 0 {
+                        tracing::debug!("Cleared {} expired custom status(es)", cleared);
+                    }
+                }
+            }
+        }
+    });
+}
+
 fn spawn_retention_jobs(
     db: paracord_db::DbPool,
     backend: Arc<paracord_media::Storage>,
@@ -1238,31 +1967,76 @@ async fn run_retention_once(
 ) -> Result<()> {
     let now = chrono::Utc::now();
     let batch_size = retention.batch_size.clamp(1, 10_000);
+    let mut purged = paracord_core::observability::RetentionPurgeCounts::default();
 
     if let Some(cutoff) = retention_cutoff(now, retention.message_days) {
         let deleted = purge_messages_older_than(db, backend, cutoff, batch_size).await?;
+        purged.messages = deleted;
         if deleted > 0 {
             tracing::info!("Retention removed {} message(s)", deleted);
         }
     }
 
+    // Per-channel message retention. Pinned messages are always kept.
+    if let Ok(channels) = paracord_db::channels::list_channels_with_message_retention(db).await {
+        for (channel_id, days) in channels {
+            let Some(cutoff) = retention_cutoff(now, Some(i64::from(days))) else {
+                continue;
+            };
+            match purge_channel_messages_older_than(db, backend, channel_id, cutoff, batch_size)
+                .await
+            {
+                Ok((messages, attachments)) => {
+                    paracord_core::observability::channel_retention_purged(
+                        channel_id,
+                        messages,
+                        attachments,
+                    );
+                    purged.messages = purged.messages.saturating_add(messages);
+                    purged.attachments = purged.attachments.saturating_add(attachments);
+                    if messages > 0 {
+                        tracing::info!(
+                            "Channel {} retention removed {} message(s)",
+                            channel_id,
+                            messages
+                        );
+                    }
+                }
+                Err(err) => {
+                    tracing::warn!("Channel {} retention failed: {}", channel_id, err);
+                }
+            }
+        }
+    }
+
     if let Some(cutoff) = retention_cutoff(now, retention.attachment_days) {
         let deleted =
             purge_unlinked_attachments_older_than(db, backend, cutoff, batch_size).await?;
+        purged.attachments = deleted;
         if deleted > 0 {
             tracing::info!("Retention removed {} unlinked attachment(s)", deleted);
         }
     }
 
-    if let Some(cutoff) = retention_cutoff(now, retention.audit_log_days) {
+    let audit_log_days =
+        retention_days_override(db, "audit_log_retention_days", retention.audit_log_days).await;
+    if let Some(cutoff) = retention_cutoff(now, audit_log_days) {
         let deleted = purge_audit_entries_older_than(db, cutoff, batch_size).await?;
+        purged.audit_log = deleted;
         if deleted > 0 {
             tracing::info!("Retention removed {} audit log entrie(s)", deleted);
         }
     }
 
-    if let Some(cutoff) = retention_cutoff(now, retention.security_event_days) {
+    let security_event_days = retention_days_override(
+        db,
+        "security_event_retention_days",
+        retention.security_event_days,
+    )
+    .await;
+    if let Some(cutoff) = retention_cutoff(now, security_event_days) {
         let deleted = purge_security_events_older_than(db, cutoff, batch_size).await?;
+        purged.security_events = deleted;
         if deleted > 0 {
             tracing::info!("Retention removed {} security event(s)", deleted);
         }
@@ -1272,6 +2046,7 @@ async fn run_retention_once(
         // Keep session records for a bounded post-expiry period.
         let session_cutoff = now - chrono::Duration::days(days.min(3650));
         let deleted = purge_expired_sessions_older_than(db, session_cutoff, batch_size).await?;
+        purged.sessions = deleted;
         if deleted > 0 {
             tracing::info!("Retention removed {} expired/revoked session(s)", deleted);
         }
@@ -1314,6 +2089,7 @@ async fn run_retention_once(
                     remove_attachment_file(backend, attachment).await;
                     guild_deleted += 1;
                 }
+                purged.attachments = purged.attachments.saturating_add(batch_len as u64);
                 if (batch_len as i64) < batch_size {
                     break;
                 }
@@ -1390,9 +2166,26 @@ async fn run_retention_once(
         }
     }
 
+    paracord_core::observability::retention_run_completed(purged);
     Ok(())
 }
 
+/// Retention window from the admin-editable server setting `key`, falling
+/// back to the config file. A stored "0" disables the policy.
+async fn retention_days_override(
+    db: &paracord_db::DbPool,
+    key: &str,
+    configured: Option<i64>,
+) -> Option<i64> {
+    match paracord_db::server_settings::get_setting(db, key).await {
+        Ok(Some(value)) => match value.trim().parse::<i64>() {
+            Ok(days) => Some(days),
+            Err(_) => configured,
+        },
+        _ => configured,
+    }
+}
+
 fn retention_cutoff(
     now: chrono::DateTime<chrono::Utc>,
     days: Option<i64>,
@@ -1439,6 +2232,51 @@ async fn purge_messages_older_than(
     Ok(total_deleted)
 }
 
+/// Delete a channel's expired, unpinned messages and their attachment files
+/// in batches. Returns `(messages, attachments)` removed.
+async fn purge_channel_messages_older_than(
+    db: &paracord_db::DbPool,
+    backend: &paracord_media::Storage,
+    channel_id: i64,
+    older_than: chrono::DateTime<chrono::Utc>,
+    batch_size: i64,
+) -> Result<(u64, u64)> {
+    let mut messages_deleted = 0_u64;
+    let mut attachments_deleted = 0_u64;
+
+    loop {
+        let message_ids = paracord_db::messages::get_expired_channel_message_ids(
+            db, channel_id, older_than, batch_size,
+        )
+        .await?;
+        if message_ids.is_empty() {
+            break;
+        }
+
+        let attachment_limit = batch_size.saturating_mul(32).clamp(32, 100_000);
+        let attachments = paracord_db::attachments::get_attachments_for_message_ids(
+            db,
+            &message_ids,
+            attachment_limit,
+        )
+        .await?;
+
+        let deleted = paracord_db::messages::delete_messages_by_ids(db, &message_ids).await?;
+        messages_deleted = messages_deleted.saturating_add(deleted);
+
+        for attachment in &attachments {
+            remove_attachment_file(backend, attachment).await;
+        }
+        attachments_deleted = attachments_deleted.saturating_add(attachments.len() as u64);
+
+        if (message_ids.len() as i64) < batch_size {
+            break;
+        }
+    }
+
+    Ok((messages_deleted, attachments_deleted))
+}
+
 async fn purge_unlinked_attachments_older_than(
     db: &paracord_db::DbPool,
     backend: &paracord_media::Storage,
@@ -1522,11 +2360,15 @@ async fn purge_security_events_older_than(
 }
 
 fn attachment_storage_key(attachment: &paracord_db::attachments::AttachmentRow) -> String {
-    let ext = std::path::Path::new(&attachment.filename)
+    attachment_storage_key_for(attachment.id, &attachment.filename)
+}
+
+fn attachment_storage_key_for(id: i64, filename: &str) -> String {
+    let ext = std::path::Path::new(filename)
         .extension()
         .and_then(|e| e.to_str())
         .unwrap_or("bin");
-    format!("attachments/{}.{}", attachment.id, ext)
+    format!("attachments/{}.{}", id, ext)
 }
 
 async fn remove_attachment_file(
@@ -1537,6 +2379,16 @@ async fn remove_attachment_file(
     if let Err(err) = backend.delete(&key).await {
         tracing::warn!("Failed deleting attachment file {}: {}", attachment.id, err);
     }
+    let ext = std::path::Path::new(&attachment.filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("bin");
+    let _ = backend
+        .delete(&paracord_media::images::thumbnail_storage_key(
+            attachment.id,
+            ext,
+        ))
+        .await;
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -1983,9 +2835,28 @@ async fn handle_webtransport_connection(
 #[cfg(test)]
 mod tests {
     use super::{
-        ensure_federation_signing_key_file, livekit_credentials_look_insecure, normalize_https_host,
+        attachment_id_from_storage_key, ensure_federation_signing_key_file, ensure_vapid_key_file,
+        livekit_credentials_look_insecure, normalize_https_host,
     };
 
+    #[test]
+    fn parses_attachment_ids_from_storage_keys() {
+        assert_eq!(
+            attachment_id_from_storage_key("attachments/42.png"),
+            Some(42)
+        );
+        assert_eq!(
+            attachment_id_from_storage_key("attachments/42_thumb.png"),
+            Some(42)
+        );
+        assert_eq!(attachment_id_from_storage_key("attachments/7"), Some(7));
+        assert_eq!(
+            attachment_id_from_storage_key("attachments/notes.txt"),
+            None
+        );
+        assert_eq!(attachment_id_from_storage_key("avatars/42.png"), None);
+    }
+
     #[test]
     fn normalizes_https_host_with_custom_port() {
         assert_eq!(
@@ -2028,5 +2899,16 @@ mod tests {
             .to_string()
             .contains("invalid federation signing key at"));
     }
+
+    #[test]
+    fn vapid_key_file_is_generated_once_and_reused() {
+        let temp_dir = tempfile::tempdir().expect("temp dir");
+        let key_path = temp_dir.path().join("vapid_private_key");
+        let key_path = key_path.to_str().expect("utf8 path");
+
+        let generated = ensure_vapid_key_file(key_path).unwrap();
+        let reloaded = ensure_vapid_key_file(key_path).unwrap();
+        assert_eq!(generated.public_key(), reloaded.public_key());
+    }
 }
 
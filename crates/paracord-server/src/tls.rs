@@ -4,8 +4,9 @@ use axum::response::IntoResponse;
 use axum_server::tls_rustls::RustlsConfig;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
-use crate::config::TlsConfig;
+use crate::config::{TlsCertMode, TlsConfig};
 
 fn harden_private_key_permissions(_path: &Path) -> Result<()> {
     #[cfg(unix)]
@@ -57,7 +58,8 @@ pub fn reload_rustls_from_disk(rustls_config: &RustlsConfig, tls_config: &TlsCon
     Ok(())
 }
 
-/// Ensure TLS certificate and key files exist, generating them if needed.
+/// Ensure TLS certificate and key files exist according to the configured
+/// [`TlsCertMode`], generating a self-signed pair if needed.
 /// Returns a `RustlsConfig` ready for use with `axum-server`.
 pub async fn ensure_certs(
     tls_config: &TlsConfig,
@@ -67,8 +69,9 @@ pub async fn ensure_certs(
     let cert_path = Path::new(&tls_config.cert_path);
     let key_path = Path::new(&tls_config.key_path);
     let missing_initial = !cert_path.exists() || !key_path.exists();
+    let mode = tls_config.mode();
 
-    if tls_config.acme.enabled {
+    if mode == TlsCertMode::Acme {
         match run_acme_automation_cycle(tls_config).await {
             Ok(changed) => {
                 if changed {
@@ -77,7 +80,10 @@ pub async fn ensure_certs(
             }
             Err(err) => {
                 if missing_initial {
-                    tracing::warn!("ACME bootstrap failed with missing certs: {}", err);
+                    tracing::warn!(
+                        "ACME bootstrap failed with missing certs; falling back to self-signed: {}",
+                        err
+                    );
                 } else {
                     tracing::warn!(
                         "ACME renewal attempt failed; continuing with existing certs: {}",
@@ -89,9 +95,15 @@ pub async fn ensure_certs(
     }
 
     if !cert_path.exists() || !key_path.exists() {
-        if !tls_config.auto_generate {
+        // ACME mode always falls back so a failed issuance cannot keep the
+        // server from booting; the renewal task replaces the cert later.
+        let may_generate = match mode {
+            TlsCertMode::SelfSigned | TlsCertMode::Acme => true,
+            TlsCertMode::Manual => false,
+        };
+        if !may_generate {
             anyhow::bail!(
-                "TLS cert/key not found at {:?} / {:?} and auto_generate is disabled",
+                "TLS cert/key not found at {:?} / {:?} and tls.mode is manual",
                 cert_path,
                 key_path
             );
@@ -113,7 +125,7 @@ pub async fn maybe_serve_acme_http_challenge(
     tls_config: &TlsConfig,
     request_path: &str,
 ) -> Option<axum::response::Response> {
-    if !tls_config.acme.enabled || !tls_config.acme.serve_http_challenge {
+    if tls_config.mode() != TlsCertMode::Acme || !tls_config.acme.serve_http_challenge {
         return None;
     }
 
@@ -162,7 +174,7 @@ pub fn spawn_acme_renewal_task(
     rustls_config: RustlsConfig,
     shutdown: Arc<tokio::sync::Notify>,
 ) {
-    if !tls_config.acme.enabled || !tls_config.acme.auto_renew {
+    if tls_config.mode() != TlsCertMode::Acme || !tls_config.acme.auto_renew {
         return;
     }
     let interval_seconds = tls_config.acme.renew_interval_seconds.max(300);
@@ -202,6 +214,73 @@ pub fn spawn_acme_renewal_task(
     });
 }
 
+/// How often the cert/key files are checked for external changes.
+const CERT_WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Modification time and size of the cert and key, following symlinks so a
+/// certbot-style `live/` link swap is seen as a change.
+type CertFingerprint = [Option<(SystemTime, u64)>; 2];
+
+fn cert_fingerprint(tls_config: &TlsConfig) -> CertFingerprint {
+    let stat = |path: &str| {
+        let meta = std::fs::metadata(path).ok()?;
+        Some((meta.modified().ok()?, meta.len()))
+    };
+    [stat(&tls_config.cert_path), stat(&tls_config.key_path)]
+}
+
+/// Watch `cert_path`/`key_path` and reload the live TLS config in place when
+/// they change, so certificates rotated by an external tool (e.g. certbot
+/// writing to `cert_path`) apply without a restart.
+pub fn spawn_cert_watch_task(
+    tls_config: TlsConfig,
+    rustls_config: RustlsConfig,
+    shutdown: Arc<tokio::sync::Notify>,
+) {
+    tokio::spawn(async move {
+        let mut applied = cert_fingerprint(&tls_config);
+        let mut pending: Option<CertFingerprint> = None;
+
+        let mut interval = tokio::time::interval(CERT_WATCH_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        interval.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => {
+                    break;
+                }
+                _ = interval.tick() => {
+                    let current = cert_fingerprint(&tls_config);
+                    if current == applied {
+                        pending = None;
+                        continue;
+                    }
+                    // Debounce: wait until the files stop changing for one
+                    // interval so a cert and key written back to back (or a
+                    // file still being written) are loaded together.
+                    if pending != Some(current) {
+                        pending = Some(current);
+                        continue;
+                    }
+                    pending = None;
+                    applied = current;
+                    match reload_rustls_from_disk(&rustls_config, &tls_config) {
+                        Ok(()) => tracing::info!(
+                            "TLS certificate reloaded from {:?}",
+                            tls_config.cert_path
+                        ),
+                        Err(err) => tracing::warn!(
+                            "TLS certificate changed on disk but reload failed; keeping previous certificate: {:#}",
+                            err
+                        ),
+                    }
+                }
+            }
+        }
+    });
+}
+
 async fn run_acme_automation_cycle(tls_config: &TlsConfig) -> Result<bool> {
     validate_acme_config(tls_config)?;
 
@@ -217,7 +296,7 @@ async fn run_acme_automation_cycle(tls_config: &TlsConfig) -> Result<bool> {
 
 fn validate_acme_config(tls_config: &TlsConfig) -> Result<()> {
     if tls_config.acme.domains.is_empty() {
-        anyhow::bail!("tls.acme.enabled=true requires at least one entry in tls.acme.domains");
+        anyhow::bail!("tls.mode=acme requires at least one entry in tls.acme.domains");
     }
     if tls_config.acme.client_path.trim().is_empty() {
         anyhow::bail!("tls.acme.client_path must not be empty");
@@ -228,6 +307,7 @@ fn validate_acme_config(tls_config: &TlsConfig) -> Result<()> {
     if tls_config.acme.cert_name.trim().is_empty() {
         anyhow::bail!("tls.acme.cert_name must not be empty");
     }
+    acme_state_dir(tls_config)?;
     Ok(())
 }
 
@@ -247,6 +327,17 @@ async fn run_certbot_certonly(tls_config: &TlsConfig) -> Result<()> {
         .arg("-w")
         .arg(acme.webroot_path.trim());
 
+    // Keep the ACME account, issued certificates and client logs inside the
+    // server's own storage instead of the system-wide certbot directories.
+    let state_dir = acme_state_dir(tls_config)?;
+    command
+        .arg("--config-dir")
+        .arg(&state_dir)
+        .arg("--work-dir")
+        .arg(state_dir.join("work"))
+        .arg("--logs-dir")
+        .arg(state_dir.join("logs"));
+
     if let Some(email) = acme
         .email
         .as_deref()
@@ -295,7 +386,7 @@ async fn run_certbot_certonly(tls_config: &TlsConfig) -> Result<()> {
 fn sync_cert_from_acme_source(tls_config: &TlsConfig) -> Result<bool> {
     let cert_path = Path::new(&tls_config.cert_path);
     let key_path = Path::new(&tls_config.key_path);
-    let (source_cert, source_key) = acme_source_paths(tls_config);
+    let (source_cert, source_key) = acme_source_paths(tls_config)?;
     let mut changed = false;
 
     if let (Some(source_cert), Some(source_key)) = (source_cert, source_key) {
@@ -324,7 +415,7 @@ fn sync_cert_from_acme_source(tls_config: &TlsConfig) -> Result<bool> {
     Ok(changed)
 }
 
-fn acme_source_paths(tls_config: &TlsConfig) -> (Option<PathBuf>, Option<PathBuf>) {
+fn acme_source_paths(tls_config: &TlsConfig) -> Result<(Option<PathBuf>, Option<PathBuf>)> {
     let acme = &tls_config.acme;
 
     let explicit_cert = acme
@@ -340,25 +431,31 @@ fn acme_source_paths(tls_config: &TlsConfig) -> (Option<PathBuf>, Option<PathBuf
         .filter(|v| !v.is_empty())
         .map(PathBuf::from);
     if explicit_cert.is_some() || explicit_key.is_some() {
-        return (explicit_cert, explicit_key);
+        return Ok((explicit_cert, explicit_key));
     }
 
     let cert_name = acme.cert_name.trim();
     if cert_name.is_empty() {
-        return (None, None);
+        return Ok((None, None));
     }
-    (
-        Some(
-            Path::new("/etc/letsencrypt/live")
-                .join(cert_name)
-                .join("fullchain.pem"),
-        ),
-        Some(
-            Path::new("/etc/letsencrypt/live")
-                .join(cert_name)
-                .join("privkey.pem"),
-        ),
-    )
+    let live_dir = acme_state_dir(tls_config)?.join("live").join(cert_name);
+    Ok((
+        Some(live_dir.join("fullchain.pem")),
+        Some(live_dir.join("privkey.pem")),
+    ))
+}
+
+/// `tls.acme.state_path`, which `Config::load` defaults to `acme/` under the
+/// storage path.
+fn acme_state_dir(tls_config: &TlsConfig) -> Result<PathBuf> {
+    tls_config
+        .acme
+        .state_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .context("tls.acme.state_path must not be empty")
 }
 
 fn copy_if_different(src: &Path, dst: &Path) -> Result<bool> {
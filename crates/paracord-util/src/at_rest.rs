@@ -171,6 +171,16 @@ pub fn derive_sqlite_key_hex(master_key: &[u8; 32]) -> String {
     encode_hex(&key)
 }
 
+/// Derive a 32-byte key from an arbitrary-length server secret (e.g. the JWT
+/// secret) for encrypting small values that live in the database.
+pub fn derive_key_from_secret(secret: &str, context: &[u8]) -> [u8; 32] {
+    let mut out = [0_u8; 32];
+    let hkdf = Hkdf::<Sha256>::new(Some(b"paracord-secret-v1"), secret.as_bytes());
+    hkdf.expand(context, &mut out)
+        .expect("HKDF output length is always valid for 32-byte subkeys");
+    out
+}
+
 fn derive_subkey(master_key: &[u8; 32], context: &[u8]) -> [u8; 32] {
     let mut out = [0_u8; 32];
     let hkdf = Hkdf::<Sha256>::new(Some(b"paracord-at-rest-v1"), master_key);
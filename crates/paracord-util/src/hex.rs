@@ -0,0 +1,123 @@
+//! Lowercase hex encoding for keys, signatures and token hashes.
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Encode bytes as lowercase hex.
+pub fn hex_encode(bytes: &[u8]) -> String {
+    let mut out = vec![0u8; bytes.len() * 2];
+    for (chunk, byte) in out.chunks_exact_mut(2).zip(bytes) {
+        chunk[0] = HEX_DIGITS[(byte >> 4) as usize];
+        chunk[1] = HEX_DIGITS[(byte & 0x0f) as usize];
+    }
+    // Every byte written above is an ASCII hex digit.
+    String::from_utf8(out).expect("hex output is ASCII")
+}
+
+fn nibble(ch: u8) -> Option<u8> {
+    match ch {
+        b'0'..=b'9' => Some(ch - b'0'),
+        b'a'..=b'f' => Some(ch - b'a' + 10),
+        b'A'..=b'F' => Some(ch - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Decode a hex string of either case. Returns `None` for odd lengths or
+/// non-hex characters.
+pub fn hex_decode(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    let mut out = vec![0u8; value.len() / 2];
+    hex_decode_into(value, &mut out)?;
+    Some(out)
+}
+
+/// Decode hex into a caller-provided buffer, for fixed-size keys and
+/// signatures. The input must encode exactly `out.len()` bytes.
+pub fn hex_decode_into(value: &str, out: &mut [u8]) -> Option<()> {
+    let input = value.as_bytes();
+    if input.len() != out.len() * 2 {
+        return None;
+    }
+    for (byte, pair) in out.iter_mut().zip(input.chunks_exact(2)) {
+        *byte = (nibble(pair[0])? << 4) | nibble(pair[1])?;
+    }
+    Some(())
+}
+
+/// Compare two hex strings without short-circuiting on the first mismatch,
+/// ignoring letter case. Only the lengths are allowed to leak through timing.
+/// Strings containing non-hex characters never compare equal.
+pub fn hex_eq_ct(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0u8;
+    let mut invalid = 0u8;
+    for (&x, &y) in a.iter().zip(b) {
+        invalid |= u8::from(!x.is_ascii_hexdigit()) | u8::from(!y.is_ascii_hexdigit());
+        // Setting 0x20 folds A-F onto a-f and leaves the digits unchanged.
+        diff |= (x | 0x20) ^ (y | 0x20);
+    }
+    (diff | invalid) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `format!`-based encoder this module replaced.
+    fn legacy_hex_encode(bytes: &[u8]) -> String {
+        let mut out = String::with_capacity(bytes.len() * 2);
+        for b in bytes {
+            out.push_str(&format!("{:02x}", b));
+        }
+        out
+    }
+
+    #[test]
+    fn encode_matches_legacy_output_for_every_byte() {
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(hex_encode(&all), legacy_hex_encode(&all));
+        assert_eq!(hex_encode(&[]), "");
+        assert_eq!(hex_encode(b"hello world"), "68656c6c6f20776f726c64");
+    }
+
+    #[test]
+    fn decode_round_trips_and_rejects_bad_input() {
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(hex_decode(&hex_encode(&all)).unwrap(), all);
+        assert_eq!(
+            hex_decode("DEADbeef").unwrap(),
+            vec![0xde, 0xad, 0xbe, 0xef]
+        );
+        assert!(hex_decode("abc").is_none());
+        assert!(hex_decode("zzzz").is_none());
+        assert!(hex_decode("+f").is_none());
+        assert!(hex_decode("ab€x").is_none());
+    }
+
+    #[test]
+    fn decode_into_requires_exact_length() {
+        let mut key = [0u8; 4];
+        assert!(hex_decode_into("00112233", &mut key).is_some());
+        assert_eq!(key, [0x00, 0x11, 0x22, 0x33]);
+        assert!(hex_decode_into("001122", &mut key).is_none());
+        assert!(hex_decode_into("0011223344", &mut key).is_none());
+        assert!(hex_decode_into("0011223g", &mut key).is_none());
+    }
+
+    #[test]
+    fn constant_time_eq_ignores_case_only() {
+        assert!(hex_eq_ct("deadbeef", "deadbeef"));
+        assert!(hex_eq_ct("DEADBEEF", "deadbeef"));
+        assert!(!hex_eq_ct("deadbeef", "deadbeee"));
+        assert!(!hex_eq_ct("deadbeef", "deadbee"));
+        assert!(hex_eq_ct("", ""));
+        // '@' | 0x20 == '`'; non-hex input must not alias.
+        assert!(!hex_eq_ct("@0", "`0"));
+        assert!(!hex_eq_ct("zz", "zz"));
+    }
+}
@@ -1,4 +1,7 @@
 pub mod at_rest;
+pub mod hex;
 pub mod pagination;
 pub mod snowflake;
+pub mod totp;
 pub mod validation;
+pub mod web_push;
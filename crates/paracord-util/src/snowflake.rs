@@ -1,55 +1,339 @@
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU16, Ordering};
 use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use chrono::{DateTime, Utc};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
 /// Custom epoch: 2024-01-01T00:00:00Z
 const PARACORD_EPOCH: u64 = 1_704_067_200_000;
 
+const WORKER_ID_BITS: u32 = 10;
+const SEQUENCE_BITS: u32 = 12;
+/// Largest worker id that fits in the 10 worker bits.
+pub const MAX_WORKER_ID: u16 = (1 << WORKER_ID_BITS) - 1;
+const MAX_SEQUENCE: u64 = (1 << SEQUENCE_BITS) - 1;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SnowflakeError {
+    #[error("snowflake worker id {0} is out of range (0-{MAX_WORKER_ID})")]
+    WorkerIdOutOfRange(u16),
+}
+
 struct SnowflakeState {
     last_timestamp: u64,
     sequence: u64,
 }
 
+impl SnowflakeState {
+    /// Next `(timestamp, sequence)` pair for a clock reading of `now`, or
+    /// `None` when the current millisecond's sequence is used up and the
+    /// caller should retry once the clock has advanced.
+    fn advance(&mut self, now: u64) -> Option<(u64, u64)> {
+        if now > self.last_timestamp {
+            self.last_timestamp = now;
+            self.sequence = 0;
+            return Some((now, 0));
+        }
+        // Same millisecond, or the clock moved backwards: keep counting from
+        // the last timestamp handed out so ids never repeat or decrease.
+        if self.sequence < MAX_SEQUENCE {
+            self.sequence += 1;
+            return Some((self.last_timestamp, self.sequence));
+        }
+        if now < self.last_timestamp {
+            // The clock is behind and may stay there for a while; borrow the
+            // next millisecond instead of stalling until it catches up.
+            self.last_timestamp += 1;
+            self.sequence = 0;
+            return Some((self.last_timestamp, 0));
+        }
+        None
+    }
+}
+
 static STATE: Mutex<SnowflakeState> = Mutex::new(SnowflakeState {
     last_timestamp: 0,
     sequence: 0,
 });
 
+static WORKER_ID: AtomicU16 = AtomicU16::new(1);
+
 fn current_timestamp() -> u64 {
-    SystemTime::now()
+    let now_ms = SystemTime::now()
         .duration_since(UNIX_EPOCH)
-        .expect("time went backwards")
-        .as_millis() as u64
-        - PARACORD_EPOCH
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0);
+    now_ms.saturating_sub(PARACORD_EPOCH)
 }
 
-/// Generate a Snowflake ID.
-/// Format: 42 bits timestamp | 10 bits worker | 12 bits sequence
-pub fn generate(worker_id: u16) -> i64 {
-    let mut state = STATE.lock().unwrap();
-    let mut timestamp = current_timestamp();
-
-    if timestamp == state.last_timestamp {
-        state.sequence = (state.sequence + 1) & 0xFFF;
-        if state.sequence == 0 {
-            // Sequence overflow: wait until next millisecond to avoid collisions.
-            while timestamp <= state.last_timestamp {
-                drop(state);
-                std::hint::spin_loop();
-                state = STATE.lock().unwrap();
-                timestamp = current_timestamp();
-            }
-        }
-    } else {
-        state.sequence = 0;
+/// Set the worker id embedded in every id this process generates. Each node
+/// sharing a database needs its own id.
+pub fn set_worker_id(worker_id: u16) -> Result<(), SnowflakeError> {
+    if worker_id > MAX_WORKER_ID {
+        return Err(SnowflakeError::WorkerIdOutOfRange(worker_id));
     }
+    WORKER_ID.store(worker_id, Ordering::Relaxed);
+    Ok(())
+}
 
-    state.last_timestamp = timestamp;
-    let seq = state.sequence;
-    let id = (timestamp << 22) | ((worker_id as u64 & 0x3FF) << 12) | seq;
+pub fn worker_id() -> u16 {
+    WORKER_ID.load(Ordering::Relaxed)
+}
+
+/// Generate a Snowflake ID for this process's worker id.
+/// Format: 42 bits timestamp | 10 bits worker | 12 bits sequence
+///
+/// Ids are strictly increasing within a process, even if the system clock
+/// steps backwards.
+pub fn generate() -> i64 {
+    let worker_id = worker_id() as u64;
+    let mut state = STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let (timestamp, sequence) = loop {
+        if let Some(next) = state.advance(current_timestamp()) {
+            break next;
+        }
+        // Sequence overflow: wait until the next millisecond.
+        drop(state);
+        std::hint::spin_loop();
+        state = STATE
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+    };
+    let id =
+        (timestamp << (WORKER_ID_BITS + SEQUENCE_BITS)) | (worker_id << SEQUENCE_BITS) | sequence;
     id as i64
 }
 
 /// Extract the Unix timestamp (ms) from a snowflake.
 pub fn timestamp_millis(id: i64) -> u64 {
-    ((id as u64) >> 22) + PARACORD_EPOCH
+    ((id as u64) >> (WORKER_ID_BITS + SEQUENCE_BITS)) + PARACORD_EPOCH
+}
+
+/// A snowflake id. Serializes as a decimal string so JavaScript clients don't
+/// round ids above 2^53; deserializes from either a string or a number.
+/// Converts to and from `i64` for code that still passes raw ids.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Snowflake(i64);
+
+impl Snowflake {
+    pub const fn new(id: i64) -> Self {
+        Self(id)
+    }
+
+    /// A fresh id from [`generate`].
+    pub fn generate() -> Self {
+        Self(generate())
+    }
+
+    pub const fn get(self) -> i64 {
+        self.0
+    }
+
+    /// Unix time (ms) the id was generated at.
+    pub fn timestamp_millis(self) -> u64 {
+        timestamp_millis(self.0)
+    }
+
+    pub fn timestamp(self) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(self.timestamp_millis() as i64).unwrap_or_default()
+    }
+
+    /// The smallest id that can be generated at `time`: every id generated
+    /// before `time` compares below it. Times before the epoch give 0.
+    pub fn first_at(time: DateTime<Utc>) -> Self {
+        let timestamp = (time.timestamp_millis().max(0) as u64).saturating_sub(PARACORD_EPOCH);
+        Self((timestamp << (WORKER_ID_BITS + SEQUENCE_BITS)) as i64)
+    }
+
+    pub fn worker_id(self) -> u16 {
+        ((self.0 as u64 >> SEQUENCE_BITS) & MAX_WORKER_ID as u64) as u16
+    }
+
+    pub fn sequence(self) -> u16 {
+        (self.0 as u64 & MAX_SEQUENCE) as u16
+    }
+}
+
+impl From<i64> for Snowflake {
+    fn from(id: i64) -> Self {
+        Self(id)
+    }
+}
+
+impl From<Snowflake> for i64 {
+    fn from(id: Snowflake) -> Self {
+        id.0
+    }
+}
+
+impl Deref for Snowflake {
+    type Target = i64;
+
+    fn deref(&self) -> &i64 {
+        &self.0
+    }
+}
+
+impl fmt::Display for Snowflake {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for Snowflake {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+impl Serialize for Snowflake {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Snowflake {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SnowflakeVisitor;
+
+        impl de::Visitor<'_> for SnowflakeVisitor {
+            type Value = Snowflake;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a snowflake id as a string or integer")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Snowflake, E> {
+                value
+                    .parse()
+                    .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Snowflake, E> {
+                Ok(Snowflake(value))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Snowflake, E> {
+                i64::try_from(value)
+                    .map(Snowflake)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+            }
+        }
+
+        deserializer.deserialize_any(SnowflakeVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn rejects_worker_ids_outside_ten_bits() {
+        assert_eq!(
+            set_worker_id(MAX_WORKER_ID + 1),
+            Err(SnowflakeError::WorkerIdOutOfRange(1024))
+        );
+    }
+
+    #[test]
+    fn concurrent_generation_is_unique_and_monotonic() {
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    let ids: Vec<i64> = (0..20_000).map(|_| generate()).collect();
+                    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+                    ids
+                })
+            })
+            .collect();
+        let mut seen = HashSet::new();
+        for thread in threads {
+            for id in thread.join().unwrap() {
+                assert!(seen.insert(id), "duplicate id {id}");
+            }
+        }
+        assert_eq!(seen.len(), 8 * 20_000);
+    }
+
+    #[test]
+    fn clock_regression_never_repeats_or_decreases() {
+        let mut state = SnowflakeState {
+            last_timestamp: 0,
+            sequence: 0,
+        };
+        let mut issued = vec![state.advance(1_000).unwrap()];
+        // The clock steps back 500ms and stays there well past a full
+        // millisecond's worth of sequence numbers.
+        for _ in 0..(MAX_SEQUENCE as usize * 3) {
+            issued.push(state.advance(500).unwrap());
+        }
+        issued.push(state.advance(1_001).unwrap());
+        issued.push(state.advance(5_000).unwrap());
+        assert!(issued.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(issued.last(), Some(&(5_000, 0)));
+    }
+
+    #[test]
+    fn snowflake_exposes_its_parts() {
+        let timestamp = 1_234_567;
+        let id = Snowflake::new(((timestamp as i64) << 22) | (513 << 12) | 77);
+        assert_eq!(id.timestamp_millis(), timestamp + PARACORD_EPOCH);
+        assert_eq!(
+            id.timestamp().timestamp_millis(),
+            (timestamp + PARACORD_EPOCH) as i64
+        );
+        assert_eq!(id.worker_id(), 513);
+        assert_eq!(id.sequence(), 77);
+        assert_eq!(*id, id.get());
+        assert_eq!(id.to_string().parse::<Snowflake>(), Ok(id));
+    }
+
+    #[test]
+    fn first_at_bounds_ids_generated_earlier() {
+        let before = Snowflake::generate();
+        let boundary = Snowflake::first_at(Utc::now() + chrono::Duration::milliseconds(1));
+        assert!(before < boundary);
+        assert_eq!(boundary.worker_id(), 0);
+        assert_eq!(boundary.sequence(), 0);
+        assert_eq!(
+            boundary.timestamp(),
+            Snowflake::first_at(boundary.timestamp()).timestamp()
+        );
+        assert_eq!(Snowflake::first_at(DateTime::<Utc>::UNIX_EPOCH).get(), 0);
+    }
+
+    #[test]
+    fn snowflake_serializes_as_a_string() {
+        // Above 2^53, where a JavaScript number would lose precision.
+        let id = Snowflake::new(9_007_199_254_740_993);
+        let encoded = serde_json::to_value(id).unwrap();
+        assert_eq!(encoded, serde_json::json!("9007199254740993"));
+        assert_eq!(serde_json::from_value::<Snowflake>(encoded).unwrap(), id);
+        assert_eq!(
+            serde_json::from_str::<Snowflake>("9007199254740993").unwrap(),
+            id
+        );
+        assert!(serde_json::from_str::<Snowflake>("\"12ab\"").is_err());
+    }
+
+    #[test]
+    fn exhausted_millisecond_waits_for_the_clock() {
+        let mut state = SnowflakeState {
+            last_timestamp: 42,
+            sequence: MAX_SEQUENCE,
+        };
+        assert_eq!(state.advance(42), None);
+        assert_eq!(state.advance(43), Some((43, 0)));
+    }
 }
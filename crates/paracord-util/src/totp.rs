@@ -0,0 +1,179 @@
+//! RFC 6238 time-based one-time passwords (HMAC-SHA1, 6 digits, 30s steps),
+//! compatible with the common authenticator apps.
+
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+
+pub const SECRET_LEN: usize = 20;
+pub const STEP_SECONDS: i64 = 30;
+pub const DIGITS: u32 = 6;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Generate a random 160-bit shared secret.
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0_u8; SECRET_LEN];
+    rand::thread_rng().fill_bytes(&mut secret);
+    secret
+}
+
+/// Unpadded RFC 4648 base32, the encoding authenticator apps expect.
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+/// Decode base32, ignoring case, padding, spaces and dashes.
+pub fn base32_decode(value: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(value.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for ch in value.bytes() {
+        if matches!(ch, b'=' | b' ' | b'-') {
+            continue;
+        }
+        let upper = ch.to_ascii_uppercase();
+        let index = BASE32_ALPHABET.iter().position(|&c| c == upper)? as u32;
+        buffer = (buffer << 5) | index;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push(((buffer >> bits) & 0xff) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// The time step containing `unix_seconds`.
+pub fn step_for(unix_seconds: i64) -> i64 {
+    unix_seconds.div_euclid(STEP_SECONDS)
+}
+
+/// Compute the code for a given time step.
+pub fn code_at_step(secret: &[u8], step: i64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(&(step as u64).to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    binary % 10_u32.pow(DIGITS)
+}
+
+/// Verify a user-supplied code against the steps within `window` of the
+/// current one. Returns the matching step so callers can reject replays.
+pub fn verify_code(secret: &[u8], code: &str, unix_seconds: i64, window: i64) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let expected: u32 = code.parse().ok()?;
+    let current = step_for(unix_seconds);
+    let mut matched = None;
+    // Check every candidate so timing does not reveal which step matched.
+    for step in (current - window)..=(current + window) {
+        let candidate = code_at_step(secret, step);
+        if constant_time_eq_u32(candidate, expected) && matched.is_none() {
+            matched = Some(step);
+        }
+    }
+    matched
+}
+
+/// Build the `otpauth://` provisioning URI rendered as a QR code by clients.
+pub fn provisioning_uri(secret: &[u8], issuer: &str, account: &str) -> String {
+    let issuer = percent_encode(issuer);
+    let account = percent_encode(account);
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={}&issuer={issuer}&algorithm=SHA1&digits={DIGITS}&period={STEP_SECONDS}",
+        base32_encode(secret)
+    )
+}
+
+fn constant_time_eq_u32(a: u32, b: u32) -> bool {
+    (a ^ b) == 0
+}
+
+fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 appendix B test secret ("12345678901234567890").
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn matches_rfc6238_vectors() {
+        // The RFC lists 8-digit codes; the 6-digit code is the low six digits.
+        assert_eq!(code_at_step(RFC_SECRET, step_for(59)), 287_082);
+        assert_eq!(code_at_step(RFC_SECRET, step_for(1_111_111_109)), 81_804);
+        assert_eq!(code_at_step(RFC_SECRET, step_for(1_234_567_890)), 5_924);
+        assert_eq!(code_at_step(RFC_SECRET, step_for(2_000_000_000)), 279_037);
+    }
+
+    #[test]
+    fn verify_accepts_adjacent_steps_only() {
+        let now = 1_700_000_000;
+        let step = step_for(now);
+        let previous = format!("{:06}", code_at_step(RFC_SECRET, step - 1));
+        let next = format!("{:06}", code_at_step(RFC_SECRET, step + 1));
+        let stale = format!("{:06}", code_at_step(RFC_SECRET, step - 3));
+        assert_eq!(verify_code(RFC_SECRET, &previous, now, 1), Some(step - 1));
+        assert_eq!(verify_code(RFC_SECRET, &next, now, 1), Some(step + 1));
+        assert_eq!(verify_code(RFC_SECRET, &stale, now, 1), None);
+    }
+
+    #[test]
+    fn verify_rejects_malformed_codes() {
+        assert_eq!(verify_code(RFC_SECRET, "12345", 59, 1), None);
+        assert_eq!(verify_code(RFC_SECRET, "abcdef", 59, 1), None);
+        assert_eq!(verify_code(RFC_SECRET, "1234567", 59, 1), None);
+    }
+
+    #[test]
+    fn base32_roundtrip() {
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        let secret = generate_secret();
+        let encoded = base32_encode(&secret);
+        assert_eq!(base32_decode(&encoded).unwrap(), secret);
+        assert_eq!(base32_decode("mzxw 6ytb-oi======").unwrap(), b"foobar");
+        assert!(base32_decode("not base32!").is_none());
+    }
+
+    #[test]
+    fn provisioning_uri_escapes_labels() {
+        let uri = provisioning_uri(b"foobar", "My Server", "alice@example.com");
+        assert!(uri.starts_with("otpauth://totp/My%20Server:alice%40example.com?"));
+        assert!(uri.contains("secret=MZXW6YTBOI"));
+        assert!(uri.contains("issuer=My%20Server"));
+    }
+}
@@ -1,4 +1,6 @@
 use thiserror::Error;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Debug, Error)]
 pub enum ValidationError {
@@ -26,6 +28,56 @@ pub fn validate_username(name: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
+/// Canonical stored form of a username: NFKC-normalized and trimmed, so
+/// compatibility variants such as fullwidth letters collapse to one spelling.
+pub fn normalize_username(name: &str) -> String {
+    name.trim().nfkc().collect()
+}
+
+/// Invisible code points that render as nothing but make strings distinct.
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{034F}'
+            | '\u{061C}'
+            | '\u{115F}'
+            | '\u{1160}'
+            | '\u{17B4}'
+            | '\u{17B5}'
+            | '\u{180B}'..='\u{180F}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{206F}'
+            | '\u{3164}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FEFF}'
+            | '\u{FFA0}'
+            | '\u{E0000}'..='\u{E0FFF}'
+    )
+}
+
+/// UTS #39 confusable skeleton of a username, lowercased. Two usernames with
+/// the same skeleton look alike (`аdmin` with a Cyrillic `а`, `admin`,
+/// `ADMIN`, `ad\u{200B}min`), whatever mix of scripts they use.
+pub fn username_skeleton(name: &str) -> String {
+    // Lowercase before mapping as well as after: prototypes differ by case
+    // (`M` stays `M` while `m` becomes `rn`).
+    let visible: String = normalize_username(name)
+        .to_lowercase()
+        .chars()
+        .filter(|c| !is_invisible(*c))
+        .collect();
+    unicode_security::skeleton(&visible)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Whether two usernames would be mistaken for one another.
+pub fn usernames_confusable(a: &str, b: &str) -> bool {
+    username_skeleton(a) == username_skeleton(b)
+}
+
 pub fn validate_guild_name(name: &str) -> Result<(), ValidationError> {
     let len = name.len();
     if len < 2 {
@@ -54,14 +106,54 @@ pub fn validate_channel_name(name: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
-pub fn validate_message_content(content: &str) -> Result<(), ValidationError> {
-    let len = content.len();
+/// Custom emoji names: 2-32 ASCII letters, digits or underscores, so they
+/// can be typed as `:name:`.
+pub fn validate_emoji_name(name: &str) -> Result<(), ValidationError> {
+    let len = name.len();
+    if len < 2 {
+        return Err(ValidationError::TooShort { min: 2, got: len });
+    }
+    if len > 32 {
+        return Err(ValidationError::TooLong { max: 32, got: len });
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(ValidationError::InvalidCharacters);
+    }
+    Ok(())
+}
+
+/// Message length limit when the server config doesn't set one.
+pub const DEFAULT_MAX_MESSAGE_LENGTH: usize = 4000;
+
+/// Length of message content as a reader sees it: extended grapheme
+/// clusters, so a flag, a skin-toned emoji or a ZWJ family sequence counts
+/// once however many code points and bytes it is made of.
+pub fn message_length(content: &str) -> usize {
+    content.graphemes(true).count()
+}
+
+/// Bytes allowed per character of the length limit. Generous enough for the
+/// longest emoji ZWJ sequences, but a single grapheme can otherwise carry any
+/// number of combining marks, so the grapheme count alone doesn't bound size.
+pub const MAX_MESSAGE_BYTES_PER_CHARACTER: usize = 64;
+
+/// Check message content against `max_length` graphemes, and against
+/// `max_length * MAX_MESSAGE_BYTES_PER_CHARACTER` bytes before counting.
+pub fn validate_message_content(content: &str, max_length: usize) -> Result<(), ValidationError> {
+    let max_bytes = max_length.saturating_mul(MAX_MESSAGE_BYTES_PER_CHARACTER);
+    if content.len() > max_bytes {
+        return Err(ValidationError::TooLong {
+            max: max_bytes,
+            got: content.len(),
+        });
+    }
+    let len = message_length(content);
     if len < 1 {
         return Err(ValidationError::TooShort { min: 1, got: len });
     }
-    if len > 2000 {
+    if len > max_length {
         return Err(ValidationError::TooLong {
-            max: 2000,
+            max: max_length,
             got: len,
         });
     }
@@ -96,6 +188,117 @@ pub fn validate_password(password: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
+/// Upper bound on password length regardless of policy, so hashing cost
+/// stays bounded.
+pub const PASSWORD_MAX_LENGTH: usize = 128;
+
+/// Lower bound on password length regardless of policy; a configured
+/// `min_length` below this is raised to it.
+pub const PASSWORD_MIN_LENGTH: usize = 10;
+
+/// Requirements a new password must meet on registration or change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    /// Minimum length in characters; never less than [`PASSWORD_MIN_LENGTH`].
+    pub min_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    /// Require a character that is neither a letter nor a digit.
+    pub require_symbol: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: PASSWORD_MIN_LENGTH,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+        }
+    }
+}
+
+/// A password rule that a candidate password failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordRule {
+    MinLength,
+    MaxLength,
+    Lowercase,
+    Uppercase,
+    Digit,
+    Symbol,
+    /// The password appears in a known breach corpus.
+    Breached,
+}
+
+impl PasswordRule {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PasswordRule::MinLength => "min_length",
+            PasswordRule::MaxLength => "max_length",
+            PasswordRule::Lowercase => "lowercase",
+            PasswordRule::Uppercase => "uppercase",
+            PasswordRule::Digit => "digit",
+            PasswordRule::Symbol => "symbol",
+            PasswordRule::Breached => "breached",
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Every rule `password` fails, in a stable order. Empty means the
+    /// password is acceptable (breach lists aside).
+    pub fn violations(&self, password: &str) -> Vec<PasswordRule> {
+        let len = password.chars().count();
+        let mut failed = Vec::new();
+        if len < self.min_length.max(PASSWORD_MIN_LENGTH) {
+            failed.push(PasswordRule::MinLength);
+        }
+        if len > PASSWORD_MAX_LENGTH {
+            failed.push(PasswordRule::MaxLength);
+        }
+        if self.require_lowercase && !password.chars().any(char::is_lowercase) {
+            failed.push(PasswordRule::Lowercase);
+        }
+        if self.require_uppercase && !password.chars().any(char::is_uppercase) {
+            failed.push(PasswordRule::Uppercase);
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            failed.push(PasswordRule::Digit);
+        }
+        if self.require_symbol && !password.chars().any(|c| !c.is_alphanumeric()) {
+            failed.push(PasswordRule::Symbol);
+        }
+        failed
+    }
+}
+
+/// Split a password's uppercase SHA-1 hex digest into the 5-character prefix
+/// sent to a Pwned Passwords range API and the 35-character suffix looked up
+/// locally in its response (k-anonymity: the full hash never leaves).
+pub fn breached_password_range(password: &str) -> (String, String) {
+    use sha1::{Digest, Sha1};
+
+    let digest = crate::hex::hex_encode(&Sha1::digest(password.as_bytes())).to_ascii_uppercase();
+    let (prefix, suffix) = digest.split_at(5);
+    (prefix.to_string(), suffix.to_string())
+}
+
+/// Whether a range API response (`SUFFIX:COUNT` per line) lists `suffix`
+/// with a non-zero count. Padding entries carry a count of 0.
+pub fn breached_range_contains(body: &str, suffix: &str) -> bool {
+    body.lines().any(|line| {
+        line.trim()
+            .split_once(':')
+            .is_some_and(|(candidate, count)| {
+                candidate.eq_ignore_ascii_case(suffix)
+                    && count.trim().parse::<u64>().is_ok_and(|n| n > 0)
+            })
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -138,6 +341,30 @@ mod tests {
         assert!(validate_username(&"a".repeat(32)).is_ok());
     }
 
+    // ---- username normalization ----
+
+    #[test]
+    fn username_normalization_folds_compatibility_forms() {
+        assert_eq!(normalize_username("  ａｌｉｃｅ "), "alice");
+        assert_eq!(normalize_username("ﬁsh"), "fish");
+        assert_eq!(normalize_username("alice"), "alice");
+    }
+
+    #[test]
+    fn username_skeleton_catches_homoglyphs_and_invisible_chars() {
+        // Cyrillic а (U+0430) in place of Latin a.
+        assert!(usernames_confusable("\u{0430}dmin", "admin"));
+        assert!(usernames_confusable("ad\u{200B}min", "admin"));
+        assert!(usernames_confusable("ADMIN", "admin"));
+        assert!(usernames_confusable("ａdmin", "admin"));
+        assert!(usernames_confusable("paypa1", "paypal"));
+        assert!(!usernames_confusable("alice", "alicia"));
+        assert_eq!(
+            username_skeleton("\u{0430}dmin"),
+            username_skeleton("admin")
+        );
+    }
+
     // ---- validate_guild_name ----
 
     #[test]
@@ -197,30 +424,85 @@ mod tests {
         assert!(matches!(err2, ValidationError::InvalidCharacters));
     }
 
+    // ---- validate_emoji_name ----
+
+    #[test]
+    fn emoji_name_rules() {
+        assert!(validate_emoji_name("party_parrot").is_ok());
+        assert!(validate_emoji_name("OK").is_ok());
+        assert!(validate_emoji_name(&"a".repeat(32)).is_ok());
+        assert!(matches!(
+            validate_emoji_name("x").unwrap_err(),
+            ValidationError::TooShort { min: 2, got: 1 }
+        ));
+        assert!(matches!(
+            validate_emoji_name(&"a".repeat(33)).unwrap_err(),
+            ValidationError::TooLong { max: 32, .. }
+        ));
+        for bad in [
+            "party-parrot",
+            "with space",
+            "caf\u{e9}",
+            "\u{1F600}\u{1F600}",
+        ] {
+            assert!(matches!(
+                validate_emoji_name(bad).unwrap_err(),
+                ValidationError::InvalidCharacters
+            ));
+        }
+    }
+
     // ---- validate_message_content ----
 
     #[test]
     fn message_content_valid() {
-        assert!(validate_message_content("Hello!").is_ok());
-        assert!(validate_message_content("a").is_ok());
+        assert!(validate_message_content("Hello!", DEFAULT_MAX_MESSAGE_LENGTH).is_ok());
+        assert!(validate_message_content("a", DEFAULT_MAX_MESSAGE_LENGTH).is_ok());
     }
 
     #[test]
     fn message_content_empty() {
-        let err = validate_message_content("").unwrap_err();
+        let err = validate_message_content("", DEFAULT_MAX_MESSAGE_LENGTH).unwrap_err();
         assert!(matches!(err, ValidationError::TooShort { min: 1, got: 0 }));
     }
 
     #[test]
     fn message_content_too_long() {
         let long = "a".repeat(2001);
-        let err = validate_message_content(&long).unwrap_err();
+        let err = validate_message_content(&long, 2000).unwrap_err();
         assert!(matches!(err, ValidationError::TooLong { max: 2000, .. }));
     }
 
     #[test]
     fn message_content_at_boundary() {
-        assert!(validate_message_content(&"a".repeat(2000)).is_ok());
+        assert!(validate_message_content(&"a".repeat(2000), 2000).is_ok());
+    }
+
+    #[test]
+    fn message_length_counts_graphemes() {
+        assert_eq!(message_length("héllo"), 5);
+        // Decomposed e + combining acute accent.
+        assert_eq!(message_length("e\u{301}"), 1);
+        // Family: four people joined by zero-width joiners.
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}";
+        assert_eq!(family.len(), 25);
+        assert_eq!(message_length(family), 1);
+        assert_eq!(message_length("\u{1F1F3}\u{1F1FF}\u{1F44D}\u{1F3FD}"), 2);
+        assert!(validate_message_content(&family.repeat(4000), 4000).is_ok());
+        assert!(validate_message_content(&family.repeat(4001), 4000).is_err());
+    }
+
+    #[test]
+    fn message_content_byte_limit() {
+        // One grapheme: a base letter buried under combining marks.
+        let zalgo = format!("a{}", "\u{301}".repeat(100));
+        assert_eq!(message_length(&zalgo), 1);
+        let err = validate_message_content(&zalgo, 2).unwrap_err();
+        assert!(matches!(
+            err,
+            ValidationError::TooLong { max: 128, got: 201 }
+        ));
+        assert!(validate_message_content(&zalgo[..127], 2).is_ok());
     }
 
     // ---- validate_email ----
@@ -287,4 +569,51 @@ mod tests {
         assert!(validate_password(&"a".repeat(10)).is_ok());
         assert!(validate_password(&"a".repeat(128)).is_ok());
     }
+
+    // ---- PasswordPolicy ----
+
+    #[test]
+    fn password_policy_reports_every_failed_rule() {
+        let policy = PasswordPolicy {
+            min_length: 12,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+        };
+        assert_eq!(
+            policy.violations("abc"),
+            vec![
+                PasswordRule::MinLength,
+                PasswordRule::Uppercase,
+                PasswordRule::Digit,
+                PasswordRule::Symbol,
+            ]
+        );
+        assert!(policy.violations("Correct-Horse-9").is_empty());
+        let lax = PasswordPolicy {
+            min_length: 0,
+            ..Default::default()
+        };
+        assert_eq!(lax.violations(""), vec![PasswordRule::MinLength]);
+        assert!(lax.violations(&"a".repeat(PASSWORD_MIN_LENGTH)).is_empty());
+        assert_eq!(
+            PasswordPolicy::default().violations(&"a".repeat(129)),
+            vec![PasswordRule::MaxLength]
+        );
+    }
+
+    #[test]
+    fn breached_range_lookup_uses_sha1_prefix_and_suffix() {
+        // SHA-1("password") = 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+        let (prefix, suffix) = breached_password_range("password");
+        assert_eq!(prefix, "5BAA6");
+        assert_eq!(suffix, "1E4C9B93F3F0682250B6CF8331B7EE68FD8");
+
+        let body = "0018A45C4D1DEF81644B54AB7F969B88D65:1\r\n\
+                    1e4c9b93f3f0682250b6cf8331b7ee68fd8:9545824\r\n";
+        assert!(breached_range_contains(body, &suffix));
+        let padded = "1E4C9B93F3F0682250B6CF8331B7EE68FD8:0\r\n";
+        assert!(!breached_range_contains(padded, &suffix));
+    }
 }
@@ -0,0 +1,303 @@
+//! Web Push message encryption (RFC 8291, `aes128gcm`) and VAPID request
+//! signing (RFC 8292).
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes128Gcm, Nonce};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL_SAFE_NO_PAD;
+use base64::Engine;
+use hkdf::Hkdf;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use rand::RngCore;
+use sha2::Sha256;
+use thiserror::Error;
+
+/// Record size advertised in the `aes128gcm` header. Push payloads are a
+/// single record, so this only needs to exceed the payload size.
+const RECORD_SIZE: u32 = 4096;
+/// Largest plaintext that keeps the whole body within the 4096 bytes push
+/// services accept: the 86-byte header, padding delimiter and GCM tag.
+pub const MAX_PAYLOAD_LEN: usize = RECORD_SIZE as usize - 1 - 16 - 86;
+/// VAPID tokens must expire within 24 hours; 12 leaves room for clock skew.
+const VAPID_TOKEN_TTL_SECONDS: i64 = 12 * 60 * 60;
+
+#[derive(Debug, Error)]
+pub enum WebPushError {
+    #[error("subscription p256dh key is not a valid P-256 public key")]
+    InvalidClientKey,
+    #[error("subscription auth secret must decode to 16 bytes")]
+    InvalidAuthSecret,
+    #[error("VAPID private key must be a base64url-encoded P-256 scalar")]
+    InvalidVapidKey,
+    #[error("push endpoint is not a valid https URL")]
+    InvalidEndpoint,
+    #[error("push payload is too large ({0} bytes)")]
+    PayloadTooLarge(usize),
+    #[error("push payload encryption failed")]
+    Encryption,
+}
+
+/// Server identity used to sign push requests.
+#[derive(Clone)]
+pub struct VapidKey {
+    signing_key: SigningKey,
+    public_key: String,
+}
+
+impl VapidKey {
+    pub fn generate() -> Self {
+        Self::from_secret(SecretKey::random(&mut rand::rngs::OsRng))
+    }
+
+    /// Parse a base64url-encoded 32-byte private key.
+    pub fn from_base64(encoded: &str) -> Result<Self, WebPushError> {
+        let bytes = BASE64_URL_SAFE_NO_PAD
+            .decode(encoded.trim().trim_end_matches('='))
+            .map_err(|_| WebPushError::InvalidVapidKey)?;
+        let secret = SecretKey::from_slice(&bytes).map_err(|_| WebPushError::InvalidVapidKey)?;
+        Ok(Self::from_secret(secret))
+    }
+
+    fn from_secret(secret: SecretKey) -> Self {
+        let public_key =
+            BASE64_URL_SAFE_NO_PAD.encode(secret.public_key().to_encoded_point(false).as_bytes());
+        Self {
+            signing_key: SigningKey::from(secret),
+            public_key,
+        }
+    }
+
+    /// Private key in the form accepted by [`VapidKey::from_base64`].
+    pub fn to_base64(&self) -> String {
+        BASE64_URL_SAFE_NO_PAD.encode(self.signing_key.to_bytes())
+    }
+
+    /// Uncompressed public key, base64url-encoded. Browsers need this as the
+    /// `applicationServerKey` when subscribing.
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    /// `Authorization` header value for a request to `endpoint`.
+    pub fn authorization(
+        &self,
+        endpoint: &str,
+        subject: &str,
+        now_unix: i64,
+    ) -> Result<String, WebPushError> {
+        let audience = endpoint_origin(endpoint).ok_or(WebPushError::InvalidEndpoint)?;
+        let header = BASE64_URL_SAFE_NO_PAD.encode(br#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = serde_json::json!({
+            "aud": audience,
+            "exp": now_unix + VAPID_TOKEN_TTL_SECONDS,
+            "sub": subject,
+        });
+        let claims = BASE64_URL_SAFE_NO_PAD.encode(claims.to_string());
+        let signing_input = format!("{header}.{claims}");
+        let signature: Signature = self.signing_key.sign(signing_input.as_bytes());
+        let signature = BASE64_URL_SAFE_NO_PAD.encode(signature.to_bytes());
+        Ok(format!(
+            "vapid t={signing_input}.{signature}, k={}",
+            self.public_key
+        ))
+    }
+}
+
+/// `https://host[:port]` of a push endpoint.
+fn endpoint_origin(endpoint: &str) -> Option<String> {
+    let rest = endpoint.strip_prefix("https://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    if authority.is_empty() || authority.contains('@') {
+        return None;
+    }
+    Some(format!("https://{authority}"))
+}
+
+/// Encrypt `payload` for a subscription's `p256dh` public key and `auth`
+/// secret (both base64url, as browsers report them). The result is the
+/// request body to send with `Content-Encoding: aes128gcm`.
+pub fn encrypt(payload: &[u8], p256dh: &str, auth: &str) -> Result<Vec<u8>, WebPushError> {
+    if payload.len() > MAX_PAYLOAD_LEN {
+        return Err(WebPushError::PayloadTooLarge(payload.len()));
+    }
+    let client_public_bytes = decode_base64url(p256dh).ok_or(WebPushError::InvalidClientKey)?;
+    let client_public = PublicKey::from_sec1_bytes(&client_public_bytes)
+        .map_err(|_| WebPushError::InvalidClientKey)?;
+    let client_public_bytes = client_public.to_encoded_point(false);
+    let auth_secret = decode_base64url(auth)
+        .filter(|secret| secret.len() == 16)
+        .ok_or(WebPushError::InvalidAuthSecret)?;
+
+    let server_secret = p256::ecdh::EphemeralSecret::random(&mut rand::rngs::OsRng);
+    let server_public = server_secret.public_key().to_encoded_point(false);
+    let shared = server_secret.diffie_hellman(&client_public);
+
+    let mut salt = [0u8; 16];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+
+    let (cek, nonce) = derive_content_keys(
+        shared.raw_secret_bytes(),
+        &auth_secret,
+        &salt,
+        client_public_bytes.as_bytes(),
+        server_public.as_bytes(),
+    )?;
+
+    let mut plaintext = Vec::with_capacity(payload.len() + 1);
+    plaintext.extend_from_slice(payload);
+    // Padding delimiter for the last (only) record.
+    plaintext.push(0x02);
+    let cipher = Aes128Gcm::new_from_slice(&cek).map_err(|_| WebPushError::Encryption)?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|_| WebPushError::Encryption)?;
+
+    let key_id = server_public.as_bytes();
+    let mut body = Vec::with_capacity(16 + 4 + 1 + key_id.len() + ciphertext.len());
+    body.extend_from_slice(&salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(key_id.len() as u8);
+    body.extend_from_slice(key_id);
+    body.extend_from_slice(&ciphertext);
+    Ok(body)
+}
+
+fn decode_base64url(value: &str) -> Option<Vec<u8>> {
+    BASE64_URL_SAFE_NO_PAD
+        .decode(value.trim().trim_end_matches('='))
+        .ok()
+}
+
+/// Content encryption key and nonce per RFC 8291 section 3.4.
+fn derive_content_keys(
+    ecdh_secret: &[u8],
+    auth_secret: &[u8],
+    salt: &[u8],
+    client_public: &[u8],
+    server_public: &[u8],
+) -> Result<([u8; 16], [u8; 12]), WebPushError> {
+    let mut key_info = Vec::with_capacity(14 + client_public.len() + server_public.len());
+    key_info.extend_from_slice(b"WebPush: info\0");
+    key_info.extend_from_slice(client_public);
+    key_info.extend_from_slice(server_public);
+    let mut ikm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(auth_secret), ecdh_secret)
+        .expand(&key_info, &mut ikm)
+        .map_err(|_| WebPushError::Encryption)?;
+
+    let prk = Hkdf::<Sha256>::new(Some(salt), &ikm);
+    let mut cek = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    prk.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .map_err(|_| WebPushError::Encryption)?;
+    prk.expand(b"Content-Encoding: nonce\0", &mut nonce)
+        .map_err(|_| WebPushError::Encryption)?;
+    Ok((cek, nonce))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::signature::Verifier;
+    use p256::ecdsa::VerifyingKey;
+
+    /// What a browser does with a push body: decrypt with its private key.
+    fn decrypt(body: &[u8], client_secret: &SecretKey, auth_secret: &[u8]) -> Vec<u8> {
+        let salt = &body[..16];
+        let key_len = body[20] as usize;
+        let server_public = PublicKey::from_sec1_bytes(&body[21..21 + key_len]).unwrap();
+        let ciphertext = &body[21 + key_len..];
+        let shared = p256::ecdh::diffie_hellman(
+            client_secret.to_nonzero_scalar(),
+            server_public.as_affine(),
+        );
+        let (cek, nonce) = derive_content_keys(
+            shared.raw_secret_bytes(),
+            auth_secret,
+            salt,
+            client_secret
+                .public_key()
+                .to_encoded_point(false)
+                .as_bytes(),
+            server_public.to_encoded_point(false).as_bytes(),
+        )
+        .unwrap();
+        let mut plaintext = Aes128Gcm::new_from_slice(&cek)
+            .unwrap()
+            .decrypt(Nonce::from_slice(&nonce), ciphertext)
+            .unwrap();
+        assert_eq!(plaintext.pop(), Some(0x02));
+        plaintext
+    }
+
+    #[test]
+    fn encrypted_payload_decrypts_with_subscription_keys() {
+        let client_secret = SecretKey::random(&mut rand::rngs::OsRng);
+        let p256dh = BASE64_URL_SAFE_NO_PAD.encode(
+            client_secret
+                .public_key()
+                .to_encoded_point(false)
+                .as_bytes(),
+        );
+        let auth_secret = [7u8; 16];
+        let auth = BASE64_URL_SAFE_NO_PAD.encode(auth_secret);
+
+        let body = encrypt(b"{\"type\":\"message\"}", &p256dh, &auth).unwrap();
+        assert_eq!(&body[16..20], &RECORD_SIZE.to_be_bytes());
+        assert_eq!(
+            decrypt(&body, &client_secret, &auth_secret),
+            b"{\"type\":\"message\"}"
+        );
+
+        assert!(matches!(
+            encrypt(b"x", &p256dh, "c2hvcnQ"),
+            Err(WebPushError::InvalidAuthSecret)
+        ));
+        assert!(matches!(
+            encrypt(&vec![0u8; MAX_PAYLOAD_LEN + 1], &p256dh, &auth),
+            Err(WebPushError::PayloadTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn vapid_authorization_is_signed_for_endpoint_origin() {
+        let key = VapidKey::generate();
+        let restored = VapidKey::from_base64(&key.to_base64()).unwrap();
+        assert_eq!(restored.public_key(), key.public_key());
+
+        let header = key
+            .authorization(
+                "https://push.example.com/send/abc?x=1",
+                "mailto:admin@example.com",
+                1_700_000_000,
+            )
+            .unwrap();
+        let token = header
+            .strip_prefix("vapid t=")
+            .and_then(|rest| rest.split(", k=").next())
+            .unwrap();
+        let (signing_input, signature) = token.rsplit_once('.').unwrap();
+        let claims: serde_json::Value = serde_json::from_slice(
+            &BASE64_URL_SAFE_NO_PAD
+                .decode(signing_input.split('.').nth(1).unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(claims["aud"], "https://push.example.com");
+        assert_eq!(claims["sub"], "mailto:admin@example.com");
+
+        let public = BASE64_URL_SAFE_NO_PAD.decode(key.public_key()).unwrap();
+        let signature =
+            Signature::from_slice(&BASE64_URL_SAFE_NO_PAD.decode(signature).unwrap()).unwrap();
+        VerifyingKey::from_sec1_bytes(&public)
+            .unwrap()
+            .verify(signing_input.as_bytes(), &signature)
+            .unwrap();
+
+        assert!(key
+            .authorization("http://push.example.com/x", "mailto:a@b.c", 0)
+            .is_err());
+    }
+}
@@ -477,16 +477,19 @@ fn extract_activities(raw: Option<&Value>) -> Vec<Value> {
     activities
 }
 
+/// `custom_status` is the stored status (see `paracord_core::custom_status`);
+/// it is dropped when the user appears offline.
 fn build_presence_payload(
     user_id: i64,
     status: Option<&str>,
     activities: Option<&Value>,
-    custom_status: Option<&str>,
+    custom_status: Value,
 ) -> Value {
+    let status = normalize_status(status);
     json!({
         "user_id": user_id.to_string(),
-        "status": normalize_status(status),
-        "custom_status": custom_status.map(|v| truncate_for_presence(v, MAX_ACTIVITY_TEXT_LEN)),
+        "status": status,
+        "custom_status": if status == "offline" { Value::Null } else { custom_status },
         "activities": extract_activities(activities),
     })
 }
@@ -519,6 +522,63 @@ async fn collect_presence_recipient_ids(
     recipients.into_iter().collect()
 }
 
+/// Switch `user_id` from `from` to `to`, keeping activities and custom status,
+/// and broadcast the change. Returns `false` when the user was not in `from`.
+async fn set_presence_status(
+    state: &AppState,
+    user_id: i64,
+    guild_ids: &[i64],
+    from: &str,
+    to: &str,
+) -> bool {
+    let presence = {
+        let mut presences = state.user_presences.write().await;
+        let Some(value) = presences.get_mut(&user_id) else {
+            return false;
+        };
+        if value.get("status").and_then(|v| v.as_str()) != Some(from) {
+            return false;
+        }
+        value["status"] = json!(to);
+        value.clone()
+    };
+    let recipient_ids = collect_presence_recipient_ids(state, user_id, guild_ids).await;
+    state
+        .event_bus
+        .dispatch_to_users(EVENT_PRESENCE_UPDATE, presence, recipient_ids);
+    true
+}
+
+/// Move users who have been quiet past the idle timeout from online to idle.
+/// Users who picked idle, dnd or invisible themselves are left alone.
+pub async fn sweep_idle_presences(state: &AppState) -> usize {
+    let mut idled = 0;
+    for user_id in state.presence_manager.idle_candidates() {
+        let online = state
+            .user_presences
+            .read()
+            .await
+            .get(&user_id)
+            .and_then(|v| v.get("status"))
+            .and_then(|v| v.as_str())
+            == Some("online");
+        if !online {
+            continue;
+        }
+        let guild_ids: Vec<i64> = paracord_db::guilds::get_user_guilds(&state.db, user_id)
+            .await
+            .unwrap_or_default()
+            .iter()
+            .map(|g| g.id)
+            .collect();
+        if set_presence_status(state, user_id, &guild_ids, "online", "idle").await {
+            state.presence_manager.mark_auto_idle(user_id);
+            idled += 1;
+        }
+    }
+    idled
+}
+
 fn extract_channel_id_from_event(event_type: &str, payload: &Value) -> Option<i64> {
     if let Some(raw) = payload.get("channel_id").and_then(|v| v.as_str()) {
         if let Ok(channel_id) = raw.parse::<i64>() {
@@ -534,6 +594,7 @@ fn extract_channel_id_from_event(event_type: &str, payload: &Value) -> Option<i6
             | "THREAD_CREATE"
             | "THREAD_UPDATE"
             | "THREAD_DELETE"
+            | "THREAD_MEMBERS_UPDATE"
     ) {
         return payload
             .get("id")
@@ -852,6 +913,7 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
 
     // Track this user as online
     state.presence_manager.cancel_offline(session_user_id);
+    state.presence_manager.record_activity(session_user_id);
     state.online_users.write().await.insert(session_user_id);
     let online_presence = {
         let existing = state
@@ -860,18 +922,24 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
             .await
             .get(&session_user_id)
             .cloned();
-        if let Some(mut value) = existing {
-            if let Some(obj) = value.as_object_mut() {
-                obj.insert("user_id".to_string(), json!(session_user_id.to_string()));
-                obj.insert("status".to_string(), json!("online"));
-                if !obj.contains_key("activities") {
-                    obj.insert("activities".to_string(), json!([]));
-                }
+        let mut value =
+            existing.unwrap_or_else(|| default_presence_payload(session_user_id, "online"));
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("user_id".to_string(), json!(session_user_id.to_string()));
+            obj.insert("status".to_string(), json!("online"));
+            if !obj.contains_key("activities") {
+                obj.insert("activities".to_string(), json!([]));
             }
-            value
-        } else {
-            default_presence_payload(session_user_id, "online")
+            obj.insert(
+                "custom_status".to_string(),
+                paracord_core::custom_status::current_custom_status_json(
+                    &state.db,
+                    session_user_id,
+                )
+                .await,
+            );
         }
+        value
     };
     state
         .user_presences
@@ -1007,7 +1075,57 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
             }
 
             state_clone.online_users.write().await.remove(&session_user_id);
-            let offline_presence = default_presence_payload(session_user_id, "offline");
+            state_clone.presence_manager.clear_activity(session_user_id);
+            let last_seen_at = chrono::Utc::now();
+            if let Err(err) =
+                paracord_db::users::set_last_seen(&state_clone.db, session_user_id, last_seen_at)
+                    .await
+            {
+                tracing::warn!(
+                    "Failed to record last_seen for user {}: {}",
+                    session_user_id,
+                    err
+                );
+            }
+            // Temporary members leave with their last session unless they
+            // were given a role in the meantime.
+            match paracord_db::members::remove_temporary_memberships(
+                &state_clone.db,
+                session_user_id,
+            )
+            .await
+            {
+                Ok(removed_guild_ids) => {
+                    for guild_id in removed_guild_ids {
+                        paracord_core::permissions::invalidate_member(
+                            &state_clone.permission_cache,
+                            guild_id,
+                            session_user_id,
+                        )
+                        .await;
+                        state_clone
+                            .member_index
+                            .remove_member(guild_id, session_user_id);
+                        state_clone.event_bus.dispatch(
+                            "GUILD_MEMBER_REMOVE",
+                            json!({
+                                "guild_id": guild_id.to_string(),
+                                "user_id": session_user_id.to_string(),
+                            }),
+                            Some(guild_id),
+                        );
+                    }
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to remove temporary memberships for user {}: {}",
+                        session_user_id,
+                        err
+                    );
+                }
+            }
+            let mut offline_presence = default_presence_payload(session_user_id, "offline");
+            offline_presence["last_seen_at"] = json!(last_seen_at.to_rfc3339());
             state_clone
                 .user_presences
                 .write()
@@ -1025,6 +1143,37 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
     }
 }
 
+/// Resolve the token sent with IDENTIFY or RESUME to a user ID. Accepts a
+/// user's access token or `Bot <token>` for a bot application.
+async fn authenticate_gateway_token(state: &AppState, token: &str) -> Option<i64> {
+    if let Some(bot_token) = token.strip_prefix("Bot ") {
+        let token_hash = paracord_db::bot_applications::hash_token(bot_token.trim());
+        let app = paracord_db::bot_applications::get_bot_application_by_token_hash(
+            &state.db,
+            &token_hash,
+        )
+        .await
+        .ok()??;
+        return Some(app.bot_user_id);
+    }
+
+    let claims = paracord_core::auth::validate_token(token, &state.config.jwt_keys).ok()?;
+    let (session_id, jti) = match (claims.sid.as_deref(), claims.jti.as_deref()) {
+        (Some(session_id), Some(jti)) => (session_id, jti),
+        _ => return None,
+    };
+    let active = paracord_db::sessions::is_access_token_active(
+        &state.db,
+        claims.sub,
+        session_id,
+        jti,
+        chrono::Utc::now(),
+    )
+    .await
+    .ok()?;
+    active.then_some(claims.sub)
+}
+
 async fn wait_for_identify_or_resume(
     receiver: &mut (impl StreamExt<Item = Result<Message, axum::Error>> + Unpin),
     state: &AppState,
@@ -1040,42 +1189,23 @@ async fn wait_for_identify_or_resume(
             if let Ok(payload) = serde_json::from_str::<Value>(&text) {
                 if let Some(d) = payload.get("d") {
                     if let Some(token) = d.get("token").and_then(|v| v.as_str()) {
-                        let claims =
-                            paracord_core::auth::validate_token(token, &state.config.jwt_secret)
-                                .ok()?;
-                        let (session_id, jti) = match (claims.sid.as_deref(), claims.jti.as_deref())
-                        {
-                            (Some(session_id), Some(jti)) => (session_id, jti),
-                            _ => return None,
-                        };
-                        let active = paracord_db::sessions::is_access_token_active(
-                            &state.db,
-                            claims.sub,
-                            session_id,
-                            jti,
-                            chrono::Utc::now(),
-                        )
-                        .await
-                        .ok()?;
-                        if !active {
-                            return None;
-                        }
+                        let user_id = authenticate_gateway_token(state, token).await?;
                         let op = payload.get("op").and_then(|v| v.as_u64())?;
                         if op == OP_IDENTIFY as u64 {
                             let guilds =
-                                paracord_db::guilds::get_user_guilds(&state.db, claims.sub)
+                                paracord_db::guilds::get_user_guilds(&state.db, user_id)
                                     .await
                                     .unwrap_or_default();
                             let guild_ids = guilds.iter().map(|g| g.id).collect();
                             let guild_owner_ids = guilds.iter().map(|g| (g.id, g.owner_id)).collect();
-                            return Some((Session::new(claims.sub, guild_ids, guild_owner_ids), false));
+                            return Some((Session::new(user_id, guild_ids, guild_owner_ids), false));
                         }
                         if op == OP_RESUME as u64 {
                             let requested_session_id =
                                 d.get("session_id").and_then(|v| v.as_str())?.to_string();
                             let requested_seq = d.get("seq").and_then(|v| v.as_u64()).unwrap_or(0);
                             if let Some(cached) = session_cache().get(&requested_session_id).await {
-                                if cached.user_id == claims.sub {
+                                if cached.user_id == user_id {
                                     let mut resumed =
                                         Session::new(cached.user_id, cached.guild_ids.clone(), cached.guild_owner_ids.clone());
                                     resumed.session_id = requested_session_id;
@@ -1087,12 +1217,12 @@ async fn wait_for_identify_or_resume(
                             // fresh session immediately so clients recover without an extra
                             // invalid-session reconnect cycle.
                             let guilds =
-                                paracord_db::guilds::get_user_guilds(&state.db, claims.sub)
+                                paracord_db::guilds::get_user_guilds(&state.db, user_id)
                                     .await
                                     .unwrap_or_default();
                             let guild_ids = guilds.iter().map(|g| g.id).collect();
                             let guild_owner_ids = guilds.iter().map(|g| (g.id, g.owner_id)).collect();
-                            return Some((Session::new(claims.sub, guild_ids, guild_owner_ids), false));
+                            return Some((Session::new(user_id, guild_ids, guild_owner_ids), false));
                         }
                     }
                 }
@@ -1120,6 +1250,10 @@ async fn run_session(
     ws_ping_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
     let heartbeat_sleep = tokio::time::sleep(heartbeat_timeout);
     tokio::pin!(heartbeat_sleep);
+    // Registered up front so a shutdown that fires mid-dispatch isn't missed.
+    let shutdown = state.shutdown.notified();
+    tokio::pin!(shutdown);
+    shutdown.as_mut().enable();
 
     let (disconnect_reason, heartbeat_timed_out) = loop {
         tokio::select! {
@@ -1141,6 +1275,20 @@ async fn run_session(
                         );
                         // Heartbeats are never rate limited
                         if opcode != OP_HEARTBEAT {
+                            // Anything but a heartbeat means the client is in use;
+                            // an explicit presence update picks its own status.
+                            if state.presence_manager.record_activity(session.user_id)
+                                && opcode != OP_PRESENCE_UPDATE
+                            {
+                                set_presence_status(
+                                    &state,
+                                    session.user_id,
+                                    &session.guild_ids,
+                                    "idle",
+                                    "online",
+                                )
+                                .await;
+                            }
                             if let Err(retry_after_ms) = rate_limits.check(session.user_id, opcode) {
                                 match opcode {
                                     OP_PRESENCE_UPDATE | OP_TYPING_START | OP_VOICE_STATE_UPDATE => {
@@ -1346,6 +1494,19 @@ async fn run_session(
                     break ("websocket ping send error".to_string(), false);
                 }
             }
+            () = &mut shutdown => {
+                // 1012 (service restart) tells clients to reconnect and resume.
+                let _ = send_ws_close_logged(
+                    &mut sender,
+                    1012,
+                    "Server restarting",
+                    Some(session.user_id),
+                    Some(session.session_id.as_str()),
+                    "shutdown_close",
+                )
+                .await;
+                break ("server shutting down".to_string(), false);
+            }
         }
     };
     if heartbeat_timed_out {
@@ -1410,12 +1571,13 @@ async fn handle_client_message(
                     .get(&session.user_id)
                     .cloned();
                 let status = d.get("status").and_then(|v| v.as_str());
-                let custom_status = d.get("custom_status").and_then(|v| v.as_str()).or_else(|| {
-                    existing_presence
-                        .as_ref()
-                        .and_then(|v| v.get("custom_status"))
-                        .and_then(|v| v.as_str())
-                });
+                // Custom statuses are set through the REST API and stored;
+                // the gateway only republishes them.
+                let custom_status = paracord_core::custom_status::current_custom_status_json(
+                    &state.db,
+                    session.user_id,
+                )
+                .await;
                 let activities = d
                     .get("activities")
                     .or_else(|| existing_presence.as_ref().and_then(|v| v.get("activities")));
@@ -1501,10 +1663,17 @@ async fn handle_client_message(
                         return;
                     }
 
+                    let now_ms = chrono::Utc::now().timestamp_millis();
+                    let Some(expires_at_ms) =
+                        paracord_core::typing::tracker().start(cid, session.user_id, now_ms)
+                    else {
+                        return;
+                    };
                     let typing_payload = json!({
                         "channel_id": channel_id_str,
                         "user_id": session.user_id.to_string(),
-                        "timestamp": chrono::Utc::now().timestamp(),
+                        "timestamp": now_ms / 1000,
+                        "expires_at": expires_at_ms / 1000,
                     });
 
                     if guild_id.is_none() {
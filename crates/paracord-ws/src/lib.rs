@@ -2,6 +2,8 @@ mod compression;
 mod handler;
 mod session;
 
+pub use handler::sweep_idle_presences;
+
 use axum::{
     extract::{ws::WebSocketUpgrade, Query, State},
     http::{header, HeaderMap, StatusCode},
@@ -35,7 +37,7 @@ fn default_allowed_origins() -> BTreeSet<String> {
     .collect()
 }
 
-fn build_allowed_origins(state: &AppState) -> BTreeSet<String> {
+async fn build_allowed_origins(state: &AppState) -> BTreeSet<String> {
     let mut allowed = default_allowed_origins();
 
     if let Some(public_url) = state.config.public_url.as_deref() {
@@ -52,17 +54,21 @@ fn build_allowed_origins(state: &AppState) -> BTreeSet<String> {
         }
     }
 
+    for origin in &state.runtime.read().await.cors_allowed_origins {
+        allowed.insert(normalize_origin(origin));
+    }
+
     allowed
 }
 
-fn is_origin_allowed(headers: &HeaderMap, state: &AppState) -> bool {
+async fn is_origin_allowed(headers: &HeaderMap, state: &AppState) -> bool {
     let Some(origin) = headers.get(header::ORIGIN).and_then(|v| v.to_str().ok()) else {
         // Native clients and non-browser callers typically omit Origin.
         return true;
     };
 
     let normalized = normalize_origin(origin);
-    let allowed = build_allowed_origins(state);
+    let allowed = build_allowed_origins(state).await;
     if allowed.contains("*") {
         tracing::warn!(
             "PARACORD_WS_ALLOWED_ORIGINS/CORS contains '*'; wildcard is not permitted for websocket origin checks"
@@ -96,7 +102,7 @@ async fn ws_upgrade(
     headers: HeaderMap,
     ws: WebSocketUpgrade,
 ) -> impl IntoResponse {
-    if !is_origin_allowed(&headers, &state) {
+    if !is_origin_allowed(&headers, &state).await {
         return StatusCode::FORBIDDEN.into_response();
     }
 